@@ -0,0 +1,22 @@
+-- Periodic snapshots of marketplace trust scores
+CREATE TABLE IF NOT EXISTS marketplace_trust_score_history (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id TEXT NOT NULL,
+    trust_score DOUBLE PRECISION NOT NULL,
+    total_transactions INTEGER NOT NULL DEFAULT 0,
+    successful_transactions INTEGER NOT NULL DEFAULT 0,
+    average_rating DOUBLE PRECISION NOT NULL DEFAULT 0,
+    total_reviews INTEGER NOT NULL DEFAULT 0,
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_trust_score_history_user
+    ON marketplace_trust_score_history (user_id, recorded_at DESC);
+
+-- Badges derived from trust score history and transaction stats
+CREATE TABLE IF NOT EXISTS marketplace_seller_badges (
+    user_id TEXT NOT NULL,
+    badge TEXT NOT NULL,
+    awarded_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, badge)
+);
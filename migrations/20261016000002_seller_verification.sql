@@ -0,0 +1,30 @@
+-- Marketplace roles (admin, verifier, ...)
+CREATE TABLE IF NOT EXISTS marketplace_user_roles (
+    user_id TEXT NOT NULL,
+    role TEXT NOT NULL,
+    granted_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, role)
+);
+
+-- KYC-lite seller verification submissions
+CREATE TABLE IF NOT EXISTS marketplace_seller_verifications (
+    id UUID PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    full_name TEXT NOT NULL,
+    document_type TEXT NOT NULL,
+    document_url TEXT NOT NULL,
+    selfie_url TEXT,
+    status TEXT NOT NULL DEFAULT 'pending',
+    reviewer_id TEXT,
+    review_notes TEXT,
+    submitted_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    reviewed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_seller_verifications_status
+    ON marketplace_seller_verifications (status, submitted_at);
+
+-- Only one open submission per seller
+CREATE UNIQUE INDEX IF NOT EXISTS idx_seller_verifications_open
+    ON marketplace_seller_verifications (user_id)
+    WHERE status IN ('pending', 'in_progress');
@@ -0,0 +1,48 @@
+-- Managed category tree
+CREATE TABLE IF NOT EXISTS marketplace_categories (
+    slug TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    parent_slug TEXT REFERENCES marketplace_categories (slug),
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE
+);
+
+CREATE INDEX IF NOT EXISTS idx_categories_parent ON marketplace_categories (parent_slug);
+
+INSERT INTO marketplace_categories (slug, name, parent_slug, sort_order) VALUES
+    ('electronics', 'Electronics', NULL, 1),
+    ('fashion', 'Fashion', NULL, 2),
+    ('food-dining', 'Food & Dining', NULL, 3),
+    ('travel', 'Travel', NULL, 4),
+    ('entertainment', 'Entertainment', NULL, 5),
+    ('health-beauty', 'Health & Beauty', NULL, 6),
+    ('home-garden', 'Home & Garden', NULL, 7),
+    ('other', 'Other', NULL, 99)
+ON CONFLICT (slug) DO NOTHING;
+
+INSERT INTO marketplace_categories (slug, name, parent_slug, sort_order) VALUES
+    ('phones', 'Phones', 'electronics', 1),
+    ('laptops', 'Laptops & Computers', 'electronics', 2),
+    ('electronics-accessories', 'Accessories', 'electronics', 3),
+    ('clothing', 'Clothing', 'fashion', 1),
+    ('shoes', 'Shoes', 'fashion', 2),
+    ('restaurants', 'Restaurants', 'food-dining', 1),
+    ('food-delivery', 'Food Delivery', 'food-dining', 2),
+    ('groceries', 'Groceries', 'food-dining', 3),
+    ('flights', 'Flights', 'travel', 1),
+    ('hotels', 'Hotels', 'travel', 2),
+    ('ride-hailing', 'Ride Hailing', 'travel', 3),
+    ('streaming', 'Streaming', 'entertainment', 1),
+    ('gaming', 'Gaming', 'entertainment', 2),
+    ('movies', 'Movies & Events', 'entertainment', 3)
+ON CONFLICT (slug) DO NOTHING;
+
+-- Normalize existing free-form categories onto the tree
+-- Same rule as CategoryService::normalize_slug: trimmed, lowercased, whitespace runs to '-'
+UPDATE marketplace_listings SET category = regexp_replace(LOWER(TRIM(category)), '\s+', '-', 'g');
+UPDATE marketplace_listings SET category = 'other'
+WHERE category NOT IN (SELECT slug FROM marketplace_categories);
+
+ALTER TABLE marketplace_listings
+    ADD CONSTRAINT fk_listings_category
+    FOREIGN KEY (category) REFERENCES marketplace_categories (slug);
@@ -0,0 +1,20 @@
+-- Canonical brands
+CREATE TABLE IF NOT EXISTS marketplace_brands (
+    id UUID PRIMARY KEY,
+    slug TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    logo_url TEXT,
+    website_url TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Normalized aliases ("amazon", "amazoncom", "amzn") pointing at a brand
+CREATE TABLE IF NOT EXISTS marketplace_brand_aliases (
+    alias TEXT PRIMARY KEY,
+    brand_id UUID NOT NULL REFERENCES marketplace_brands (id) ON DELETE CASCADE
+);
+
+ALTER TABLE marketplace_listings
+    ADD COLUMN IF NOT EXISTS brand_id UUID REFERENCES marketplace_brands (id);
+
+CREATE INDEX IF NOT EXISTS idx_listings_brand ON marketplace_listings (brand_id) WHERE brand_id IS NOT NULL;
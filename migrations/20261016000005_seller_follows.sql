@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS marketplace_seller_follows (
+    follower_id TEXT NOT NULL,
+    seller_id TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (follower_id, seller_id),
+    CHECK (follower_id <> seller_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_seller_follows_seller ON marketplace_seller_follows (seller_id);
@@ -0,0 +1,3 @@
+-- Structured per-listing-type metadata (gift card balance, referral URL, ...)
+ALTER TABLE marketplace_listings
+    ADD COLUMN IF NOT EXISTS details JSONB;
@@ -0,0 +1,16 @@
+-- Multi-unit listings
+ALTER TABLE marketplace_listings
+    ADD COLUMN IF NOT EXISTS quantity INTEGER NOT NULL DEFAULT 1 CHECK (quantity > 0),
+    ADD COLUMN IF NOT EXISTS remaining_quantity INTEGER NOT NULL DEFAULT 1 CHECK (remaining_quantity >= 0);
+
+UPDATE marketplace_listings SET remaining_quantity = 0 WHERE status = 'sold';
+
+-- One stored code per unit
+ALTER TABLE marketplace_coupon_codes
+    ADD COLUMN IF NOT EXISTS id UUID NOT NULL DEFAULT gen_random_uuid();
+
+ALTER TABLE marketplace_coupon_codes DROP CONSTRAINT IF EXISTS marketplace_coupon_codes_pkey;
+ALTER TABLE marketplace_coupon_codes DROP CONSTRAINT IF EXISTS marketplace_coupon_codes_listing_id_key;
+ALTER TABLE marketplace_coupon_codes ADD PRIMARY KEY (id);
+
+CREATE INDEX IF NOT EXISTS idx_coupon_codes_listing ON marketplace_coupon_codes (listing_id);
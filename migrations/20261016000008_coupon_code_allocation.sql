@@ -0,0 +1,45 @@
+-- Each stored code is allocated to at most one transaction
+ALTER TABLE marketplace_coupon_codes
+    ADD COLUMN IF NOT EXISTS allocated_transaction_id UUID REFERENCES marketplace_transactions (id),
+    ADD COLUMN IF NOT EXISTS allocated_to TEXT,
+    ADD COLUMN IF NOT EXISTS allocated_at TIMESTAMPTZ;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_coupon_codes_allocated_transaction
+    ON marketplace_coupon_codes (allocated_transaction_id)
+    WHERE allocated_transaction_id IS NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_coupon_codes_unallocated
+    ON marketplace_coupon_codes (listing_id)
+    WHERE allocated_transaction_id IS NULL;
+
+-- Buyers may purchase several units of the same listing
+ALTER TABLE marketplace_coupon_access
+    DROP CONSTRAINT IF EXISTS marketplace_coupon_access_listing_id_user_id_key;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_coupon_access_transaction
+    ON marketplace_coupon_access (transaction_id);
+
+-- Codes bought before allocation existed go to the purchases that were granted access to
+-- them, the oldest purchase of a listing first, so those buyers can still reveal them
+WITH purchases AS (
+    SELECT a.listing_id, a.transaction_id, a.user_id, COALESCE(t.completed_at, t.created_at) AS bought_at,
+           ROW_NUMBER() OVER (PARTITION BY a.listing_id ORDER BY t.created_at, t.id) AS n
+    FROM marketplace_coupon_access a
+    JOIN marketplace_transactions t ON t.id = a.transaction_id
+    WHERE NOT EXISTS (
+        SELECT 1 FROM marketplace_coupon_codes c WHERE c.allocated_transaction_id = a.transaction_id
+    )
+),
+codes AS (
+    SELECT id, listing_id, ROW_NUMBER() OVER (PARTITION BY listing_id ORDER BY id) AS n
+    FROM marketplace_coupon_codes
+    WHERE allocated_transaction_id IS NULL
+)
+UPDATE marketplace_coupon_codes c
+SET allocated_transaction_id = p.transaction_id,
+    allocated_to = p.user_id,
+    allocated_at = p.bought_at
+FROM codes, purchases p
+WHERE c.id = codes.id
+AND p.listing_id = codes.listing_id
+AND p.n = codes.n;
@@ -0,0 +1,6 @@
+ALTER TABLE marketplace_listings
+    ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_listings_deleted_at
+    ON marketplace_listings (deleted_at)
+    WHERE deleted_at IS NOT NULL;
@@ -0,0 +1,21 @@
+CREATE TABLE IF NOT EXISTS marketplace_price_history (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    listing_id UUID NOT NULL REFERENCES marketplace_listings (id) ON DELETE CASCADE,
+    old_price NUMERIC(12, 2) NOT NULL,
+    new_price NUMERIC(12, 2) NOT NULL,
+    changed_by TEXT NOT NULL,
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_price_history_listing
+    ON marketplace_price_history (listing_id, changed_at DESC);
+
+-- Buyers' saved listings, used for price-drop alerts
+CREATE TABLE IF NOT EXISTS marketplace_favorites (
+    user_id TEXT NOT NULL,
+    listing_id UUID NOT NULL REFERENCES marketplace_listings (id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, listing_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_favorites_listing ON marketplace_favorites (listing_id);
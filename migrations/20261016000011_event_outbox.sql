@@ -0,0 +1,21 @@
+-- Domain events written in the same transaction as the change that caused them
+CREATE TABLE IF NOT EXISTS marketplace_outbox (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    aggregate_type TEXT NOT NULL,
+    aggregate_id UUID NOT NULL,
+    event_type TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    published_at TIMESTAMPTZ,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_outbox_unpublished
+    ON marketplace_outbox (created_at)
+    WHERE published_at IS NULL;
+
+-- Events that ran out of publish attempts hold back the rest of their aggregate
+CREATE INDEX IF NOT EXISTS idx_outbox_unpublished_aggregate
+    ON marketplace_outbox (aggregate_type, aggregate_id)
+    WHERE published_at IS NULL;
@@ -0,0 +1,14 @@
+-- Every status change a transaction goes through, written by the state machine
+CREATE TABLE IF NOT EXISTS marketplace_transaction_status_history (
+    id UUID PRIMARY KEY,
+    transaction_id UUID NOT NULL REFERENCES marketplace_transactions(id) ON DELETE CASCADE,
+    from_status TEXT NOT NULL,
+    to_status TEXT NOT NULL,
+    event TEXT NOT NULL,
+    actor_id TEXT NOT NULL,
+    reason TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_transaction_status_history_transaction
+    ON marketplace_transaction_status_history (transaction_id, created_at);
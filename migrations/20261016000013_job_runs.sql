@@ -0,0 +1,8 @@
+-- Last scheduled slot claimed per background job, so each slot runs once across replicas
+CREATE TABLE IF NOT EXISTS marketplace_job_runs (
+    job_name TEXT PRIMARY KEY,
+    last_scheduled_at TIMESTAMPTZ NOT NULL,
+    last_started_at TIMESTAMPTZ NOT NULL,
+    last_finished_at TIMESTAMPTZ,
+    last_error TEXT
+);
@@ -0,0 +1,15 @@
+-- User data export archives, generated in the background and kept for a limited time
+CREATE TABLE IF NOT EXISTS marketplace_data_exports (
+    id UUID PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    format TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    content BYTEA,
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    completed_at TIMESTAMPTZ,
+    expires_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_data_exports_user ON marketplace_data_exports (user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_data_exports_pending ON marketplace_data_exports (created_at) WHERE status = 'pending';
@@ -0,0 +1,14 @@
+-- Account deletion requests. `user_id` is cleared once the account is anonymized,
+-- so the table never links the opaque id back to the person.
+CREATE TABLE IF NOT EXISTS marketplace_account_deletions (
+    id UUID PRIMARY KEY,
+    user_id TEXT,
+    status TEXT NOT NULL DEFAULT 'pending',
+    error TEXT,
+    requested_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    completed_at TIMESTAMPTZ
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_account_deletions_open
+    ON marketplace_account_deletions (user_id)
+    WHERE status = 'pending';
@@ -0,0 +1,28 @@
+-- Recent account actions with the client address, input to anomaly detection
+CREATE TABLE IF NOT EXISTS marketplace_account_activity (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id TEXT NOT NULL,
+    action TEXT NOT NULL,
+    ip_address TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_account_activity_user_action
+    ON marketplace_account_activity (user_id, action, created_at DESC);
+
+-- Temporary restrictions applied automatically when an account behaves abnormally
+CREATE TABLE IF NOT EXISTS marketplace_account_restrictions (
+    id UUID PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    action TEXT NOT NULL,
+    rule TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMPTZ NOT NULL,
+    lifted_at TIMESTAMPTZ,
+    lifted_by TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_account_restrictions_active
+    ON marketplace_account_restrictions (user_id, action)
+    WHERE lifted_at IS NULL;
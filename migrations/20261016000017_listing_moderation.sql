@@ -0,0 +1,20 @@
+-- Listings held back by automated screening until a moderator reviews them
+CREATE TABLE IF NOT EXISTS marketplace_moderation_queue (
+    id UUID PRIMARY KEY,
+    listing_id UUID NOT NULL REFERENCES marketplace_listings(id) ON DELETE CASCADE,
+    seller_id TEXT NOT NULL,
+    flags JSONB NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    reviewer_id TEXT,
+    review_notes TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    reviewed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_moderation_queue_status
+    ON marketplace_moderation_queue (status, created_at);
+
+-- Only one open case per listing; later flags are merged into it
+CREATE UNIQUE INDEX IF NOT EXISTS idx_moderation_queue_open
+    ON marketplace_moderation_queue (listing_id)
+    WHERE status = 'pending';
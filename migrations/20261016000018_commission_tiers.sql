@@ -0,0 +1,23 @@
+-- Current commission tier per seller; sellers without a row pay the standard rate
+CREATE TABLE IF NOT EXISTS marketplace_seller_commission_tiers (
+    user_id TEXT PRIMARY KEY,
+    tier TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS marketplace_commission_tier_history (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id TEXT NOT NULL,
+    previous_tier TEXT NOT NULL,
+    tier TEXT NOT NULL,
+    trust_score DOUBLE PRECISION NOT NULL,
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_commission_tier_history_user
+    ON marketplace_commission_tier_history (user_id, changed_at DESC);
+
+-- The commission charged is fixed when the transaction is created
+ALTER TABLE marketplace_transactions
+    ADD COLUMN IF NOT EXISTS commission_tier TEXT,
+    ADD COLUMN IF NOT EXISTS platform_fee NUMERIC(12, 2);
@@ -0,0 +1,30 @@
+-- Single-use tokens that authorize one coupon reveal; only a hash of the token is stored
+CREATE TABLE IF NOT EXISTS marketplace_coupon_reveal_tokens (
+    token_hash TEXT PRIMARY KEY,
+    listing_id UUID NOT NULL REFERENCES marketplace_listings(id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_coupon_reveal_tokens_expires
+    ON marketplace_coupon_reveal_tokens (expires_at);
+
+-- Every coupon reveal, so a leaked code can be traced back through its watermark reference
+CREATE TABLE IF NOT EXISTS marketplace_coupon_reveals (
+    id UUID PRIMARY KEY,
+    listing_id UUID NOT NULL REFERENCES marketplace_listings(id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL,
+    reference TEXT NOT NULL,
+    code_count INTEGER NOT NULL,
+    ip_address TEXT,
+    user_agent TEXT,
+    revealed_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_coupon_reveals_listing
+    ON marketplace_coupon_reveals (listing_id, revealed_at DESC);
+
+CREATE INDEX IF NOT EXISTS idx_coupon_reveals_reference
+    ON marketplace_coupon_reveals (reference);
@@ -0,0 +1,21 @@
+-- Buyer claims that a revealed code does not work; escrow stays frozen until resolved
+CREATE TABLE IF NOT EXISTS marketplace_protection_claims (
+    id UUID PRIMARY KEY,
+    transaction_id UUID NOT NULL UNIQUE REFERENCES marketplace_transactions(id),
+    buyer_id TEXT NOT NULL,
+    seller_id TEXT NOT NULL,
+    details TEXT,
+    status TEXT NOT NULL DEFAULT 'awaiting_seller',
+    validity_check TEXT NOT NULL,
+    validity_notes TEXT,
+    seller_response TEXT,
+    seller_responded_at TIMESTAMPTZ,
+    response_due_at TIMESTAMPTZ NOT NULL,
+    resolution_notes TEXT,
+    resolved_by TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    resolved_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_protection_claims_status
+    ON marketplace_protection_claims (status, response_due_at);
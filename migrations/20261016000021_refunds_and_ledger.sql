@@ -0,0 +1,34 @@
+ALTER TABLE marketplace_transactions
+    ADD COLUMN IF NOT EXISTS refunded_amount NUMERIC(12, 2) NOT NULL DEFAULT 0;
+
+-- Refunds sent to the payment provider; the refund id is the provider idempotency key
+CREATE TABLE IF NOT EXISTS marketplace_refunds (
+    id UUID PRIMARY KEY,
+    transaction_id UUID NOT NULL REFERENCES marketplace_transactions(id),
+    amount NUMERIC(12, 2) NOT NULL CHECK (amount > 0),
+    reason TEXT NOT NULL,
+    initiated_by TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    provider_refund_id TEXT,
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_refunds_transaction
+    ON marketplace_refunds (transaction_id, created_at);
+
+-- Money movements per party; the entries of one event sum to zero
+CREATE TABLE IF NOT EXISTS marketplace_ledger_entries (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    transaction_id UUID NOT NULL REFERENCES marketplace_transactions(id),
+    refund_id UUID REFERENCES marketplace_refunds(id),
+    entry_type TEXT NOT NULL,
+    account TEXT NOT NULL,
+    user_id TEXT,
+    amount NUMERIC(12, 2) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_ledger_entries_transaction
+    ON marketplace_ledger_entries (transaction_id);
@@ -0,0 +1,28 @@
+CREATE TABLE IF NOT EXISTS marketplace_cart_items (
+    user_id TEXT NOT NULL,
+    listing_id UUID NOT NULL REFERENCES marketplace_listings(id) ON DELETE CASCADE,
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    added_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, listing_id)
+);
+
+-- One payment covering several transactions, one per purchased unit
+CREATE TABLE IF NOT EXISTS marketplace_checkouts (
+    id UUID PRIMARY KEY,
+    buyer_id TEXT NOT NULL,
+    payment_method TEXT NOT NULL,
+    payment_id TEXT,
+    total_amount NUMERIC(12, 2) NOT NULL,
+    status TEXT NOT NULL DEFAULT 'awaiting_payment',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    paid_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_checkouts_buyer
+    ON marketplace_checkouts (buyer_id, created_at DESC);
+
+ALTER TABLE marketplace_transactions
+    ADD COLUMN IF NOT EXISTS checkout_id UUID REFERENCES marketplace_checkouts(id);
+
+CREATE INDEX IF NOT EXISTS idx_transactions_checkout
+    ON marketplace_transactions (checkout_id) WHERE checkout_id IS NOT NULL;
@@ -0,0 +1,27 @@
+CREATE TABLE IF NOT EXISTS marketplace_bundles (
+    id UUID PRIMARY KEY,
+    seller_id TEXT NOT NULL,
+    title TEXT NOT NULL,
+    description TEXT,
+    bundle_price NUMERIC(12, 2) NOT NULL CHECK (bundle_price > 0),
+    status TEXT NOT NULL DEFAULT 'active',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_bundles_seller
+    ON marketplace_bundles (seller_id, status);
+
+CREATE TABLE IF NOT EXISTS marketplace_bundle_items (
+    bundle_id UUID NOT NULL REFERENCES marketplace_bundles(id) ON DELETE CASCADE,
+    listing_id UUID NOT NULL REFERENCES marketplace_listings(id),
+    position INTEGER NOT NULL,
+    PRIMARY KEY (bundle_id, listing_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_bundle_items_listing
+    ON marketplace_bundle_items (listing_id);
+
+-- A bundle purchase is a checkout with one transaction per bundled listing
+ALTER TABLE marketplace_checkouts
+    ADD COLUMN IF NOT EXISTS bundle_id UUID REFERENCES marketplace_bundles(id);
@@ -0,0 +1,19 @@
+-- Existing tags are normalized the same way new ones are: trimmed, lowercased,
+-- whitespace collapsed, empty and duplicate tags dropped in order of first appearance
+UPDATE marketplace_listings l
+SET tags = COALESCE((
+    SELECT ARRAY_AGG(tag ORDER BY first_position)
+    FROM (
+        SELECT tag, MIN(position) as first_position
+        FROM (
+            SELECT LOWER(REGEXP_REPLACE(BTRIM(raw), '\s+', ' ', 'g')) as tag, position
+            FROM unnest(l.tags) WITH ORDINALITY as t(raw, position)
+        ) normalized
+        WHERE tag <> ''
+        GROUP BY tag
+    ) deduplicated
+), '{}')
+WHERE tags IS NOT NULL AND cardinality(tags) > 0;
+
+CREATE INDEX IF NOT EXISTS idx_listings_tags
+    ON marketplace_listings USING GIN (tags);
@@ -0,0 +1,12 @@
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- Trigram indexes back the typo-tolerant `<%` matches of search suggestions
+CREATE INDEX IF NOT EXISTS idx_brands_name_trgm
+    ON marketplace_brands USING GIN (LOWER(name) gin_trgm_ops);
+
+CREATE INDEX IF NOT EXISTS idx_categories_name_trgm
+    ON marketplace_categories USING GIN (LOWER(name) gin_trgm_ops);
+
+CREATE INDEX IF NOT EXISTS idx_listings_title_trgm
+    ON marketplace_listings USING GIN (LOWER(title) gin_trgm_ops)
+    WHERE status = 'active' AND deleted_at IS NULL;
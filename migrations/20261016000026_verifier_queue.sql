@@ -0,0 +1,32 @@
+-- Verifier assignment queue for seller verification submissions
+ALTER TABLE marketplace_seller_verifications
+    ADD COLUMN IF NOT EXISTS claim_expires_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS escalated_at TIMESTAMPTZ;
+
+-- Claimable submissions, escalated first then oldest
+CREATE INDEX IF NOT EXISTS idx_seller_verifications_claimable
+    ON marketplace_seller_verifications (escalated_at NULLS LAST, submitted_at)
+    WHERE status IN ('pending', 'in_progress');
+
+-- Every claim a verifier took, kept for throughput metrics
+CREATE TABLE IF NOT EXISTS marketplace_verification_claims (
+    id UUID PRIMARY KEY,
+    verification_id UUID NOT NULL REFERENCES marketplace_seller_verifications(id) ON DELETE CASCADE,
+    verifier_id TEXT NOT NULL,
+    claimed_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMPTZ NOT NULL,
+    outcome TEXT, -- verified, rejected, expired or released; NULL while open
+    closed_at TIMESTAMPTZ
+);
+
+-- At most one open claim per submission
+CREATE UNIQUE INDEX IF NOT EXISTS idx_verification_claims_open
+    ON marketplace_verification_claims (verification_id)
+    WHERE outcome IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_verification_claims_verifier
+    ON marketplace_verification_claims (verifier_id, claimed_at);
+
+CREATE INDEX IF NOT EXISTS idx_verification_claims_expiry
+    ON marketplace_verification_claims (expires_at)
+    WHERE outcome IS NULL;
@@ -0,0 +1,13 @@
+-- Seller storefront pages under a vanity slug
+CREATE TABLE IF NOT EXISTS marketplace_storefronts (
+    user_id TEXT PRIMARY KEY,
+    slug TEXT NOT NULL, -- stored lowercase
+    bio TEXT,
+    banner_url TEXT,
+    featured_listing_ids UUID[] NOT NULL DEFAULT '{}', -- in display order
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_storefronts_slug
+    ON marketplace_storefronts (slug);
@@ -0,0 +1,56 @@
+-- Paid featured placement of a listing for a time window
+CREATE TABLE IF NOT EXISTS marketplace_promotions (
+    id UUID PRIMARY KEY,
+    listing_id UUID NOT NULL REFERENCES marketplace_listings(id),
+    seller_id TEXT NOT NULL,
+    category TEXT NOT NULL, -- of the listing when booked; featured slots are per category
+    starts_at TIMESTAMPTZ NOT NULL,
+    ends_at TIMESTAMPTZ NOT NULL CHECK (ends_at > starts_at),
+    status TEXT NOT NULL DEFAULT 'pending_payment', -- pending_payment, scheduled, active, expired, cancelled
+    price NUMERIC(12, 2) NOT NULL CHECK (price >= 0),
+    paid_with TEXT NOT NULL, -- card or credits
+    payment_id TEXT, -- captured card payment, set when the payment is confirmed
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    cancelled_at TIMESTAMPTZ
+);
+
+-- Featured lookups and overlap checks only look at promotions holding a slot
+CREATE INDEX IF NOT EXISTS idx_promotions_listing_window
+    ON marketplace_promotions (listing_id, starts_at, ends_at)
+    WHERE status IN ('pending_payment', 'scheduled', 'active');
+
+CREATE INDEX IF NOT EXISTS idx_promotions_category_window
+    ON marketplace_promotions (category, starts_at, ends_at)
+    WHERE status IN ('pending_payment', 'scheduled', 'active');
+
+CREATE INDEX IF NOT EXISTS idx_promotions_seller
+    ON marketplace_promotions (seller_id, created_at);
+
+-- Loyalty credits sellers can spend on promotions; the balance is the sum of the entries
+CREATE TABLE IF NOT EXISTS marketplace_credit_entries (
+    id UUID PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    amount NUMERIC(12, 2) NOT NULL,
+    reason TEXT NOT NULL,
+    promotion_id UUID REFERENCES marketplace_promotions(id),
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_credit_entries_user
+    ON marketplace_credit_entries (user_id, created_at);
+
+-- What sellers were billed for marketplace services; refunds are negative
+CREATE TABLE IF NOT EXISTS marketplace_billing_entries (
+    id UUID PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    promotion_id UUID REFERENCES marketplace_promotions(id),
+    amount NUMERIC(12, 2) NOT NULL,
+    paid_with TEXT NOT NULL,
+    provider_reference TEXT,
+    description TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_billing_entries_user
+    ON marketplace_billing_entries (user_id, created_at);
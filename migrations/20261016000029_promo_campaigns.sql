@@ -0,0 +1,42 @@
+-- Platform-funded promo codes buyers redeem at checkout
+CREATE TABLE IF NOT EXISTS marketplace_promo_campaigns (
+    id UUID PRIMARY KEY,
+    code TEXT NOT NULL UNIQUE, -- stored uppercase
+    description TEXT,
+    discount_type TEXT NOT NULL, -- percentage or fixed
+    discount_value NUMERIC(12, 2) NOT NULL CHECK (discount_value > 0),
+    max_discount NUMERIC(12, 2) CHECK (max_discount > 0), -- cap on percentage discounts
+    first_purchase_only BOOLEAN NOT NULL DEFAULT FALSE,
+    category TEXT, -- only items in this category count towards and receive the discount
+    min_amount NUMERIC(12, 2) CHECK (min_amount >= 0), -- of the eligible items
+    max_redemptions INTEGER CHECK (max_redemptions > 0), -- across all users
+    max_per_user INTEGER NOT NULL DEFAULT 1 CHECK (max_per_user > 0),
+    starts_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    ends_at TIMESTAMPTZ,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CHECK (ends_at IS NULL OR ends_at > starts_at)
+);
+
+-- A redemption stops counting towards the caps once every transaction of its checkout is cancelled
+CREATE TABLE IF NOT EXISTS marketplace_promo_redemptions (
+    id UUID PRIMARY KEY,
+    campaign_id UUID NOT NULL REFERENCES marketplace_promo_campaigns(id),
+    user_id TEXT NOT NULL,
+    checkout_id UUID NOT NULL UNIQUE REFERENCES marketplace_checkouts(id),
+    discount_amount NUMERIC(12, 2) NOT NULL CHECK (discount_amount > 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_promo_redemptions_campaign_user
+    ON marketplace_promo_redemptions (campaign_id, user_id);
+
+-- The buyer is charged total_amount - discount_amount; sellers are paid in full
+ALTER TABLE marketplace_checkouts
+    ADD COLUMN IF NOT EXISTS promo_code TEXT,
+    ADD COLUMN IF NOT EXISTS discount_amount NUMERIC(12, 2) NOT NULL DEFAULT 0;
+
+-- Share of the checkout discount on this item, which is never refunded to the buyer
+ALTER TABLE marketplace_transactions
+    ADD COLUMN IF NOT EXISTS discount_amount NUMERIC(12, 2) NOT NULL DEFAULT 0;
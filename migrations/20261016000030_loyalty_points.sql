@@ -0,0 +1,24 @@
+-- Loyalty points ledger; a buyer's balance is the sum of their live entries
+CREATE TABLE IF NOT EXISTS marketplace_loyalty_entries (
+    id UUID PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    points BIGINT NOT NULL CHECK (points <> 0), -- negative when redeemed or expired
+    kind TEXT NOT NULL, -- earned, redeemed or expired
+    transaction_id UUID REFERENCES marketplace_transactions(id), -- the completed purchase points were earned on
+    checkout_id UUID REFERENCES marketplace_checkouts(id), -- where points were redeemed; void once all its items are cancelled
+    expires_at TIMESTAMPTZ, -- earned points only
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_loyalty_entries_user
+    ON marketplace_loyalty_entries (user_id, created_at);
+
+-- Points are earned once per transaction
+CREATE UNIQUE INDEX IF NOT EXISTS idx_loyalty_entries_earned_transaction
+    ON marketplace_loyalty_entries (transaction_id) WHERE kind = 'earned';
+
+CREATE INDEX IF NOT EXISTS idx_loyalty_entries_expiring
+    ON marketplace_loyalty_entries (expires_at) WHERE kind = 'earned';
+
+ALTER TABLE marketplace_checkouts
+    ADD COLUMN IF NOT EXISTS points_redeemed BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,52 @@
+-- Money added to a wallet from a card or UPI payment
+CREATE TABLE IF NOT EXISTS marketplace_wallet_topups (
+    id UUID PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    amount NUMERIC(12, 2) NOT NULL CHECK (amount > 0),
+    funding_method TEXT NOT NULL, -- card or upi
+    status TEXT NOT NULL DEFAULT 'pending', -- pending, completed, failed
+    payment_id TEXT, -- captured payment, refunded against on withdrawal
+    withdrawn_amount NUMERIC(12, 2) NOT NULL DEFAULT 0 CHECK (withdrawn_amount >= 0 AND withdrawn_amount <= amount),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_wallet_topups_user
+    ON marketplace_wallet_topups (user_id, created_at);
+
+-- Money sent back from a wallet to the payment that funded one top-up
+CREATE TABLE IF NOT EXISTS marketplace_wallet_withdrawals (
+    id UUID PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    topup_id UUID NOT NULL REFERENCES marketplace_wallet_topups(id),
+    amount NUMERIC(12, 2) NOT NULL CHECK (amount > 0),
+    status TEXT NOT NULL DEFAULT 'pending', -- pending, completed, failed
+    provider_refund_id TEXT,
+    failure_reason TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_wallet_withdrawals_user
+    ON marketplace_wallet_withdrawals (user_id, created_at);
+
+-- Double-entry wallet ledger: the entries of one journal sum to zero. Accounts are a user's
+-- wallet, funding (card and UPI money entering or leaving) and escrow (wallet payments held
+-- for purchases). A wallet balance is the sum of its entries.
+CREATE TABLE IF NOT EXISTS marketplace_wallet_entries (
+    id UUID PRIMARY KEY,
+    journal_id UUID NOT NULL,
+    account TEXT NOT NULL, -- wallet, funding or escrow
+    user_id TEXT, -- wallet entries only
+    amount NUMERIC(12, 2) NOT NULL,
+    entry_type TEXT NOT NULL, -- topup, payment, refund, withdrawal, withdrawal_reversal
+    reference_id UUID NOT NULL, -- the top-up, checkout, refund or withdrawal
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CHECK ((account = 'wallet') = (user_id IS NOT NULL))
+);
+
+CREATE INDEX IF NOT EXISTS idx_wallet_entries_user
+    ON marketplace_wallet_entries (user_id, created_at) WHERE account = 'wallet';
+
+CREATE INDEX IF NOT EXISTS idx_wallet_entries_journal
+    ON marketplace_wallet_entries (journal_id);
@@ -0,0 +1,28 @@
+-- UPI payments for checkouts and wallet top-ups, settled by provider webhooks or reconciliation
+CREATE TABLE IF NOT EXISTS marketplace_upi_payments (
+    id UUID PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    purpose TEXT NOT NULL, -- checkout or wallet_topup
+    reference_id UUID NOT NULL, -- the checkout or top-up being paid
+    amount NUMERIC(12, 2) NOT NULL CHECK (amount > 0),
+    flow TEXT NOT NULL, -- intent or collect
+    vpa TEXT, -- collect flow only
+    provider_payment_id TEXT UNIQUE,
+    provider_order_id TEXT,
+    intent_url TEXT,
+    status TEXT NOT NULL DEFAULT 'pending', -- pending, completed, failed, expired or refunded
+    failure_reason TEXT,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    completed_at TIMESTAMPTZ
+);
+
+-- One payment in flight per checkout or top-up
+CREATE UNIQUE INDEX IF NOT EXISTS idx_upi_payments_pending_reference
+    ON marketplace_upi_payments (reference_id) WHERE status = 'pending';
+
+CREATE INDEX IF NOT EXISTS idx_upi_payments_pending
+    ON marketplace_upi_payments (created_at) WHERE status = 'pending';
+
+CREATE INDEX IF NOT EXISTS idx_upi_payments_user
+    ON marketplace_upi_payments (user_id, created_at);
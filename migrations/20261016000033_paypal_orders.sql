@@ -0,0 +1,50 @@
+-- Saved payment methods; PayPal payers are stored by their PayPal payer id
+CREATE TABLE IF NOT EXISTS marketplace_payment_methods (
+    id UUID PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    payment_type TEXT NOT NULL, -- card, paypal, upi or wallet
+    provider_customer_id TEXT,
+    last_four TEXT,
+    card_brand TEXT,
+    is_default BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_payment_methods_provider_customer
+    ON marketplace_payment_methods (user_id, payment_type, provider_customer_id);
+
+-- PayPal orders for checkouts; the buyer approves on PayPal and the order is captured on return
+CREATE TABLE IF NOT EXISTS marketplace_paypal_orders (
+    id UUID PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    checkout_id UUID NOT NULL REFERENCES marketplace_checkouts(id),
+    provider_order_id TEXT NOT NULL UNIQUE,
+    amount NUMERIC(12, 2) NOT NULL CHECK (amount > 0),
+    currency TEXT NOT NULL,
+    approval_url TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'created', -- created, captured, failed or refunded
+    payment_id TEXT, -- PayPal capture id, refunded against
+    payer_id TEXT,
+    failure_reason TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    captured_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_paypal_orders_checkout
+    ON marketplace_paypal_orders (checkout_id);
+
+-- Captured payments the provider took back, by reversal or chargeback
+CREATE TABLE IF NOT EXISTS marketplace_payment_reversals (
+    id UUID PRIMARY KEY,
+    provider TEXT NOT NULL,
+    provider_event_id TEXT NOT NULL,
+    payment_id TEXT NOT NULL,
+    kind TEXT NOT NULL, -- reversal or chargeback
+    amount NUMERIC(12, 2), -- as reported by the provider
+    reason TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (provider, provider_event_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_payment_reversals_payment
+    ON marketplace_payment_reversals (payment_id);
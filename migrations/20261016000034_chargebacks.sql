@@ -0,0 +1,22 @@
+-- Chargebacks are applied to the purchases paid by the charged back payment. One that
+-- arrives before its payment settled stays unapplied and is retried by reconciliation.
+ALTER TABLE marketplace_payment_reversals
+    ADD COLUMN IF NOT EXISTS applied_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_payment_reversals_unapplied
+    ON marketplace_payment_reversals (created_at)
+    WHERE kind = 'chargeback' AND applied_at IS NULL;
+
+-- The part of a chargeback that hit one purchase and what was clawed back from its seller
+CREATE TABLE IF NOT EXISTS marketplace_chargebacks (
+    id UUID PRIMARY KEY,
+    reversal_id UUID NOT NULL REFERENCES marketplace_payment_reversals(id),
+    transaction_id UUID NOT NULL REFERENCES marketplace_transactions(id),
+    amount NUMERIC(12, 2) NOT NULL CHECK (amount > 0),
+    seller_clawback NUMERIC(12, 2) NOT NULL CHECK (seller_clawback >= 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (reversal_id, transaction_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_chargebacks_transaction
+    ON marketplace_chargebacks (transaction_id);
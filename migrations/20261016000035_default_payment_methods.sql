@@ -0,0 +1,11 @@
+-- At most one default payment method per user
+UPDATE marketplace_payment_methods SET is_default = FALSE
+WHERE is_default AND id NOT IN (
+    SELECT DISTINCT ON (user_id) id FROM marketplace_payment_methods
+    WHERE is_default
+    ORDER BY user_id, created_at DESC
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_payment_methods_one_default
+    ON marketplace_payment_methods (user_id)
+    WHERE is_default;
@@ -0,0 +1,17 @@
+-- Stripe Connect accounts sellers are paid out to, one per seller
+CREATE TABLE IF NOT EXISTS marketplace_payout_accounts (
+    user_id TEXT PRIMARY KEY,
+    provider_account_id TEXT NOT NULL UNIQUE,
+    status TEXT NOT NULL DEFAULT 'pending', -- pending, restricted or enabled
+    details_submitted BOOLEAN NOT NULL DEFAULT FALSE,
+    payouts_enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    requirements_due TEXT[] NOT NULL DEFAULT '{}',
+    disabled_reason TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    checked_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP, -- last status poll
+    enabled_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_payout_accounts_unfinished
+    ON marketplace_payout_accounts (checked_at)
+    WHERE status <> 'enabled';
@@ -0,0 +1,37 @@
+-- Per-user notification preferences; users without a row get the defaults
+CREATE TABLE IF NOT EXISTS marketplace_notification_settings (
+    user_id TEXT PRIMARY KEY,
+    email_notifications BOOLEAN NOT NULL DEFAULT TRUE,
+    push_notifications BOOLEAN NOT NULL DEFAULT FALSE,
+    new_listing_alerts BOOLEAN NOT NULL DEFAULT TRUE,
+    price_drop_alerts BOOLEAN NOT NULL DEFAULT TRUE,
+    transaction_updates BOOLEAN NOT NULL DEFAULT TRUE,
+    review_notifications BOOLEAN NOT NULL DEFAULT TRUE,
+    digest_mode TEXT NOT NULL DEFAULT 'immediate', -- immediate, hourly or daily
+    last_digest_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP, -- start of the current digest period
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Summaries sent in place of one email or push per notification
+CREATE TABLE IF NOT EXISTS marketplace_notification_digests (
+    id UUID PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    digest_mode TEXT NOT NULL,
+    notification_count INTEGER NOT NULL CHECK (notification_count > 0),
+    period_start TIMESTAMPTZ NOT NULL,
+    sent_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_notification_digests_user
+    ON marketplace_notification_digests (user_id, sent_at DESC);
+
+-- Notifications are emailed or pushed once; earlier ones are not sent again
+ALTER TABLE marketplace_notifications
+    ADD COLUMN IF NOT EXISTS delivered_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS digest_id UUID REFERENCES marketplace_notification_digests(id);
+
+UPDATE marketplace_notifications SET delivered_at = created_at WHERE delivered_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_notifications_undelivered
+    ON marketplace_notifications (user_id, created_at)
+    WHERE delivered_at IS NULL;
@@ -0,0 +1,10 @@
+-- Channels per notification type and quiet hours, read in the user's time zone
+ALTER TABLE marketplace_notification_settings
+    ADD COLUMN IF NOT EXISTS channel_preferences JSONB NOT NULL DEFAULT '{}', -- type -> {email, push, in_app}
+    ADD COLUMN IF NOT EXISTS quiet_hours_start TIME,
+    ADD COLUMN IF NOT EXISTS quiet_hours_end TIME,
+    ADD COLUMN IF NOT EXISTS timezone TEXT NOT NULL DEFAULT 'UTC';
+
+-- Notifications of types the user only wants emailed or pushed are kept out of the inbox
+ALTER TABLE marketplace_notifications
+    ADD COLUMN IF NOT EXISTS in_app BOOLEAN NOT NULL DEFAULT TRUE;
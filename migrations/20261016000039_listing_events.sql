@@ -0,0 +1,29 @@
+-- Announce status, price and stock changes of listings once their transaction commits;
+-- the service forwards them to Redis for clients streaming the listing's events
+CREATE OR REPLACE FUNCTION marketplace_notify_listing_change() RETURNS trigger AS $$
+BEGIN
+    IF NEW.status IS DISTINCT FROM OLD.status
+        OR NEW.selling_price IS DISTINCT FROM OLD.selling_price
+        OR NEW.remaining_quantity IS DISTINCT FROM OLD.remaining_quantity
+        OR NEW.deleted_at IS DISTINCT FROM OLD.deleted_at
+    THEN
+        PERFORM pg_notify('marketplace_listing_events', json_build_object(
+            'event_id', gen_random_uuid(),
+            'listing_id', NEW.id,
+            'status', NEW.status,
+            'previous_status', OLD.status,
+            'selling_price', NEW.selling_price::text,
+            'previous_price', OLD.selling_price::text,
+            'remaining_quantity', NEW.remaining_quantity,
+            'deleted', NEW.deleted_at IS NOT NULL,
+            'occurred_at', CURRENT_TIMESTAMP
+        )::text);
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trg_listing_events ON marketplace_listings;
+CREATE TRIGGER trg_listing_events
+    AFTER UPDATE ON marketplace_listings
+    FOR EACH ROW EXECUTE FUNCTION marketplace_notify_listing_change();
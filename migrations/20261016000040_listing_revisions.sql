@@ -0,0 +1,11 @@
+-- Edits of listings, one row per edit with the fields it changed
+CREATE TABLE IF NOT EXISTS marketplace_listing_revisions (
+    id UUID PRIMARY KEY,
+    listing_id UUID NOT NULL REFERENCES marketplace_listings(id),
+    revision INTEGER NOT NULL CHECK (revision > 0),
+    editor_id TEXT NOT NULL,
+    changes JSONB NOT NULL, -- field -> {from, to}
+    reverts_revision INTEGER, -- set when the edit restored an earlier revision
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (listing_id, revision)
+);
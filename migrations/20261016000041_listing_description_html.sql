@@ -0,0 +1,3 @@
+-- Sanitized HTML rendering of each listing's markdown description; existing
+-- descriptions are rendered by the description_render job
+ALTER TABLE marketplace_listings ADD COLUMN IF NOT EXISTS description_html TEXT;
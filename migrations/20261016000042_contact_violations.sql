@@ -0,0 +1,12 @@
+-- Text submitted with contact details in it, masked or rejected; the text is not kept
+CREATE TABLE IF NOT EXISTS marketplace_contact_violations (
+    id UUID PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    field TEXT NOT NULL,
+    kinds JSONB NOT NULL, -- contact detail kinds found
+    rejected BOOLEAN NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_contact_violations_user
+    ON marketplace_contact_violations (user_id, created_at DESC);
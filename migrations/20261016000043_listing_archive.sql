@@ -0,0 +1,51 @@
+-- Sold, expired and deleted listings past the archival age move here so the live table
+-- stays small. Columns must match marketplace_listings exactly: a migration adding a
+-- listing column adds it here too and recreates marketplace_listings_all.
+CREATE TABLE IF NOT EXISTS marketplace_listings_archive (
+    LIKE marketplace_listings INCLUDING DEFAULTS INCLUDING CONSTRAINTS
+);
+
+ALTER TABLE marketplace_listings_archive
+    DROP CONSTRAINT IF EXISTS marketplace_listings_archive_pkey,
+    ADD CONSTRAINT marketplace_listings_archive_pkey PRIMARY KEY (id);
+
+CREATE INDEX IF NOT EXISTS idx_listings_archive_seller
+    ON marketplace_listings_archive (seller_id, updated_at DESC);
+
+-- Live and archived listings, for history that must resolve a listing of any age
+CREATE OR REPLACE VIEW marketplace_listings_all AS
+    SELECT * FROM marketplace_listings
+    UNION ALL
+    SELECT * FROM marketplace_listings_archive;
+
+-- Records that outlive a listing's move to the archive can no longer reference the live
+-- table. Favorites, cart items, moderation cases and reveal tokens still cascade.
+DO $$
+DECLARE
+    fk record;
+BEGIN
+    FOR fk IN
+        SELECT conrelid::regclass AS table_name, conname
+        FROM pg_constraint
+        WHERE contype = 'f'
+        AND confrelid = 'marketplace_listings'::regclass
+        AND conrelid::regclass::text NOT IN (
+            'marketplace_favorites',
+            'marketplace_cart_items',
+            'marketplace_moderation_queue',
+            'marketplace_coupon_reveal_tokens'
+        )
+    LOOP
+        EXECUTE format('ALTER TABLE %s DROP CONSTRAINT %I', fk.table_name, fk.conname);
+    END LOOP;
+END $$;
+
+-- Finds archival candidates without scanning live listings
+CREATE INDEX IF NOT EXISTS idx_listings_archivable
+    ON marketplace_listings (updated_at)
+    WHERE status IN ('sold', 'expired') OR deleted_at IS NOT NULL;
+
+-- Browsing reads only listings for sale, which most live rows are once old ones move out
+CREATE INDEX IF NOT EXISTS idx_listings_active_created
+    ON marketplace_listings (created_at DESC)
+    WHERE status = 'active' AND deleted_at IS NULL;
@@ -0,0 +1,35 @@
+-- Listing views per day, so trending can weigh recent interest rather than lifetime views
+CREATE TABLE IF NOT EXISTS marketplace_listing_daily_views (
+    listing_id UUID NOT NULL,
+    day DATE NOT NULL,
+    views INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (listing_id, day)
+);
+
+CREATE INDEX IF NOT EXISTS idx_listing_daily_views_day ON marketplace_listing_daily_views (day);
+
+-- Listings for sale ranked by views, favorites and purchases over the last 7 days, each
+-- day's signals counting half as much as the day after. Refreshed by the trending job.
+CREATE MATERIALIZED VIEW IF NOT EXISTS marketplace_trending_listings AS
+    WITH signals AS (
+        SELECT listing_id, day, views::float8 AS weight
+        FROM marketplace_listing_daily_views
+        WHERE day > CURRENT_DATE - 7
+        UNION ALL
+        SELECT listing_id, created_at::date, 3.0
+        FROM marketplace_favorites
+        WHERE created_at > CURRENT_DATE - 7
+        UNION ALL
+        SELECT listing_id, created_at::date, 10.0
+        FROM marketplace_transactions
+        WHERE created_at > CURRENT_DATE - 7 AND status NOT IN ('cancelled', 'charged_back')
+    )
+    SELECT s.listing_id, SUM(s.weight * POWER(0.5, CURRENT_DATE - s.day))::float8 AS score
+    FROM signals s
+    JOIN marketplace_listings l ON l.id = s.listing_id
+    WHERE l.status = 'active' AND l.deleted_at IS NULL
+    GROUP BY s.listing_id;
+
+-- Required for concurrent refreshes
+CREATE UNIQUE INDEX IF NOT EXISTS idx_trending_listings_listing
+    ON marketplace_trending_listings (listing_id);
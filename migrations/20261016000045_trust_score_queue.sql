@@ -0,0 +1,9 @@
+-- Users whose trust score needs recomputing after a review, sale or refund. One row per
+-- user, so bursts of events collapse into a single recompute once the row is due.
+CREATE TABLE IF NOT EXISTS marketplace_trust_score_queue (
+    user_id TEXT PRIMARY KEY,
+    enqueued_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    due_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_trust_score_queue_due ON marketplace_trust_score_queue (due_at);
@@ -0,0 +1,16 @@
+-- Notifications raised by business operations, turned into stored notifications and
+-- delivered by the fan-out worker so the operation never waits on them
+CREATE TABLE IF NOT EXISTS marketplace_notification_queue (
+    id UUID PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    notification_type TEXT NOT NULL,
+    title TEXT NOT NULL,
+    message TEXT NOT NULL,
+    related_listing_id UUID,
+    related_transaction_id UUID,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_notification_queue_created ON marketplace_notification_queue (created_at);
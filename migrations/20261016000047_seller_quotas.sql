@@ -0,0 +1,9 @@
+-- Active listing limits set by admins for individual sellers, replacing their tier's limit
+CREATE TABLE IF NOT EXISTS marketplace_seller_quota_overrides (
+    user_id TEXT PRIMARY KEY,
+    max_active_listings INTEGER NOT NULL CHECK (max_active_listings >= 0),
+    reason TEXT,
+    set_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
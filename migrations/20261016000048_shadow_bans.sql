@@ -0,0 +1,15 @@
+-- Sellers whose listings are hidden from everyone else's browsing, search, feeds and
+-- alerts without telling them. Lifted bans are kept for the record.
+CREATE TABLE IF NOT EXISTS marketplace_shadow_bans (
+    id UUID PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    banned_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    lifted_by TEXT,
+    lifted_at TIMESTAMPTZ
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_shadow_bans_active
+    ON marketplace_shadow_bans (user_id)
+    WHERE lifted_at IS NULL;
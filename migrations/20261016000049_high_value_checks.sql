@@ -0,0 +1,23 @@
+-- Extra confirmation of transactions at or above the high-value threshold. Their payment
+-- is held here, and the transaction stays pending, until every required check has passed.
+CREATE TABLE IF NOT EXISTS marketplace_high_value_checks (
+    transaction_id UUID PRIMARY KEY REFERENCES marketplace_transactions(id),
+    buyer_confirmation_required BOOLEAN NOT NULL,
+    code_hash TEXT,
+    code_expires_at TIMESTAMPTZ,
+    code_attempts INTEGER NOT NULL DEFAULT 0,
+    buyer_confirmed_at TIMESTAMPTZ,
+    seller_review_required BOOLEAN NOT NULL,
+    seller_approved BOOLEAN,
+    seller_reviewed_by TEXT,
+    seller_reviewed_at TIMESTAMPTZ,
+    seller_review_notes TEXT,
+    payment_id TEXT,
+    payment_received_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- The admin review queue
+CREATE INDEX IF NOT EXISTS idx_high_value_checks_review
+    ON marketplace_high_value_checks (created_at)
+    WHERE seller_review_required AND seller_reviewed_at IS NULL;
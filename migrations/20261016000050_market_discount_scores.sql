@@ -0,0 +1,14 @@
+-- How much cheaper each listing is than is typical for its brand, or its category when
+-- the brand has too few prices to compare; computed nightly by the market_discount job
+ALTER TABLE marketplace_listings
+    ADD COLUMN IF NOT EXISTS market_discount_score NUMERIC(6, 2),
+    ADD COLUMN IF NOT EXISTS market_discount_basis TEXT;
+
+ALTER TABLE marketplace_listings_archive
+    ADD COLUMN IF NOT EXISTS market_discount_score NUMERIC(6, 2),
+    ADD COLUMN IF NOT EXISTS market_discount_basis TEXT;
+
+CREATE OR REPLACE VIEW marketplace_listings_all AS
+    SELECT * FROM marketplace_listings
+    UNION ALL
+    SELECT * FROM marketplace_listings_archive;
@@ -0,0 +1,11 @@
+-- Reports from any signed-in user that a discount code listing's code no longer works,
+-- one per user and listing; enough of them take the listing down as expired
+CREATE TABLE IF NOT EXISTS marketplace_expired_code_reports (
+    listing_id UUID NOT NULL REFERENCES marketplace_listings (id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (listing_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_expired_code_reports_user
+    ON marketplace_expired_code_reports (user_id);
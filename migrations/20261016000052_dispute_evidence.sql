@@ -0,0 +1,26 @@
+-- Uploaded files, kept in Postgres so they are backed up and deleted with the records
+-- that reference them
+CREATE TABLE IF NOT EXISTS marketplace_media (
+    id UUID PRIMARY KEY,
+    owner_id TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    sha256 TEXT NOT NULL,
+    data BYTEA NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_media_owner ON marketplace_media (owner_id);
+
+-- Screenshots and documents either party attaches to a disputed transaction
+CREATE TABLE IF NOT EXISTS marketplace_dispute_evidence (
+    id UUID PRIMARY KEY,
+    transaction_id UUID NOT NULL REFERENCES marketplace_transactions (id) ON DELETE CASCADE,
+    uploaded_by TEXT NOT NULL,
+    media_id UUID NOT NULL REFERENCES marketplace_media (id),
+    file_name TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_dispute_evidence_transaction
+    ON marketplace_dispute_evidence (transaction_id, created_at);
@@ -0,0 +1,29 @@
+-- Short-lived read-only sessions in which a support admin sees the marketplace as a user
+CREATE TABLE IF NOT EXISTS marketplace_impersonation_sessions (
+    id UUID PRIMARY KEY,
+    admin_id TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    reason TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMPTZ NOT NULL,
+    revoked_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_impersonation_sessions_user
+    ON marketplace_impersonation_sessions (user_id, created_at DESC);
+
+-- Every request made while impersonating, including those refused as not read-only
+CREATE TABLE IF NOT EXISTS marketplace_impersonation_audit (
+    id UUID PRIMARY KEY,
+    session_id UUID NOT NULL REFERENCES marketplace_impersonation_sessions (id),
+    admin_id TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    method TEXT NOT NULL,
+    path TEXT NOT NULL,
+    allowed BOOLEAN NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_impersonation_audit_session
+    ON marketplace_impersonation_audit (session_id, created_at);
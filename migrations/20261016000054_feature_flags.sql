@@ -0,0 +1,16 @@
+-- Flags gating new behaviour, on for everyone in the allow-list plus a stable
+-- percentage of other users while enabled
+CREATE TABLE IF NOT EXISTS marketplace_feature_flags (
+    name TEXT PRIMARY KEY,
+    description TEXT,
+    enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    rollout_percent INTEGER NOT NULL DEFAULT 0 CHECK (rollout_percent BETWEEN 0 AND 100),
+    allow_list TEXT[] NOT NULL DEFAULT '{}',
+    updated_by TEXT,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Crowd reporting of expired codes shipped before flags; it stays on for everyone
+INSERT INTO marketplace_feature_flags (name, description, enabled, rollout_percent)
+VALUES ('expired_code_reports', 'Reporting discount codes as expired, with automatic takedown', TRUE, 100)
+ON CONFLICT (name) DO NOTHING;
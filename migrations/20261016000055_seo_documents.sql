@@ -0,0 +1,7 @@
+-- Sitemaps and merchant product feeds, regenerated on a schedule and served as stored
+CREATE TABLE IF NOT EXISTS marketplace_seo_documents (
+    name TEXT PRIMARY KEY, -- e.g. sitemap.xml, listings-1.xml, merchant.csv
+    content_type TEXT NOT NULL,
+    body TEXT NOT NULL,
+    generated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,38 @@
+-- Aggregator partners reading public listings through /api/partner/marketplace
+CREATE TABLE IF NOT EXISTS marketplace_partner_accounts (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL,
+    owner_id TEXT NOT NULL, -- User who manages the partner's keys
+    contact_email TEXT,
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_partner_accounts_owner ON marketplace_partner_accounts (owner_id);
+
+-- Only a hash of each key is stored; the prefix identifies it in listings
+CREATE TABLE IF NOT EXISTS marketplace_partner_keys (
+    id UUID PRIMARY KEY,
+    partner_id UUID NOT NULL REFERENCES marketplace_partner_accounts (id),
+    name TEXT NOT NULL,
+    key_prefix TEXT NOT NULL,
+    key_hash TEXT NOT NULL UNIQUE,
+    requests_per_minute INTEGER NOT NULL CHECK (requests_per_minute > 0),
+    daily_quota INTEGER NOT NULL CHECK (daily_quota > 0),
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    revoked_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_partner_keys_partner ON marketplace_partner_keys (partner_id);
+
+-- Requests per key per UTC day, with the current minute's count for the rate limit
+CREATE TABLE IF NOT EXISTS marketplace_partner_key_usage (
+    key_id UUID NOT NULL REFERENCES marketplace_partner_keys (id),
+    day DATE NOT NULL,
+    requests BIGINT NOT NULL DEFAULT 0, -- Including rejected ones
+    rejected BIGINT NOT NULL DEFAULT 0, -- Over the rate limit or quota
+    minute_start TIMESTAMPTZ NOT NULL,
+    minute_requests INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (key_id, day)
+);
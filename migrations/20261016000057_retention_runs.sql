@@ -0,0 +1,16 @@
+-- Rows each retention policy purged per cleanup run
+CREATE TABLE IF NOT EXISTS marketplace_retention_runs (
+    id UUID PRIMARY KEY,
+    policy TEXT NOT NULL,
+    rows_purged BIGINT NOT NULL,
+    duration_ms BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_retention_runs_policy ON marketplace_retention_runs (policy, created_at DESC);
+
+-- Retention deletes by age; these tables had no index to find old rows with
+CREATE INDEX IF NOT EXISTS idx_impersonation_audit_created ON marketplace_impersonation_audit (created_at);
+CREATE INDEX IF NOT EXISTS idx_contact_violations_created ON marketplace_contact_violations (created_at);
+CREATE INDEX IF NOT EXISTS idx_outbox_published ON marketplace_outbox (published_at) WHERE published_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_partner_key_usage_day ON marketplace_partner_key_usage (day);
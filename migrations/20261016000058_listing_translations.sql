@@ -0,0 +1,32 @@
+-- Language a listing was written in, as a locale code
+ALTER TABLE marketplace_listings
+    ADD COLUMN IF NOT EXISTS language TEXT NOT NULL DEFAULT 'en';
+
+ALTER TABLE marketplace_listings_archive
+    ADD COLUMN IF NOT EXISTS language TEXT NOT NULL DEFAULT 'en';
+
+CREATE OR REPLACE VIEW marketplace_listings_all AS
+    SELECT * FROM marketplace_listings
+    UNION ALL
+    SELECT * FROM marketplace_listings_archive;
+
+CREATE INDEX IF NOT EXISTS idx_listings_language ON marketplace_listings (language)
+    WHERE status = 'active' AND deleted_at IS NULL;
+
+-- Listing text in other locales, written by the seller or by machine translation;
+-- archived listings are no longer browsed and lose theirs
+CREATE TABLE IF NOT EXISTS marketplace_listing_translations (
+    listing_id UUID NOT NULL REFERENCES marketplace_listings (id) ON DELETE CASCADE,
+    locale TEXT NOT NULL,
+    title TEXT NOT NULL,
+    description TEXT,
+    description_html TEXT,
+    source TEXT NOT NULL,
+    -- md5 of the title and description a machine translation was made from, so edits refresh it
+    source_digest TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (listing_id, locale)
+);
+
+CREATE INDEX IF NOT EXISTS idx_listing_translations_locale ON marketplace_listing_translations (locale, listing_id);
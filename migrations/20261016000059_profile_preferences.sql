@@ -0,0 +1,6 @@
+-- Per-user display preferences set on the profile; users without a row get the defaults
+CREATE TABLE IF NOT EXISTS marketplace_profile_preferences (
+    user_id TEXT PRIMARY KEY,
+    locale TEXT NOT NULL DEFAULT 'en', -- listings and notifications are shown in it where translated
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,23 @@
+-- Country a listing is sold in, as an ISO 3166-1 alpha-2 code. Existing listings and users
+-- get US, the default DEFAULT_REGION.
+ALTER TABLE marketplace_listings
+    ADD COLUMN IF NOT EXISTS region TEXT NOT NULL DEFAULT 'US';
+
+ALTER TABLE marketplace_listings_archive
+    ADD COLUMN IF NOT EXISTS region TEXT NOT NULL DEFAULT 'US';
+
+CREATE OR REPLACE VIEW marketplace_listings_all AS
+    SELECT * FROM marketplace_listings
+    UNION ALL
+    SELECT * FROM marketplace_listings_archive;
+
+-- Browsing is scoped to a region, newest first by default
+CREATE INDEX IF NOT EXISTS idx_listings_region ON marketplace_listings (region, created_at DESC)
+    WHERE status = 'active' AND deleted_at IS NULL;
+
+ALTER TABLE marketplace_profile_preferences
+    ADD COLUMN IF NOT EXISTS region TEXT NOT NULL DEFAULT 'US';
+
+-- Tax on the platform fee at the rate of the listing's region, withheld from the seller with the fee
+ALTER TABLE marketplace_transactions
+    ADD COLUMN IF NOT EXISTS tax_amount NUMERIC(12, 2) NOT NULL DEFAULT 0;
@@ -0,0 +1,19 @@
+-- Prohibited items: what can't be listed, checked when listings are created and edited.
+-- A rule matches a listing when every condition it sets matches.
+CREATE TABLE IF NOT EXISTS marketplace_listing_policy_rules (
+    id UUID PRIMARY KEY,
+    category TEXT, -- the category and everything under it
+    brand_id UUID REFERENCES marketplace_brands(id) ON DELETE CASCADE,
+    keyword TEXT, -- lowercase word or phrase in the title, description or tags
+    region TEXT, -- only listings sold in this region; NULL for everywhere
+    reason TEXT NOT NULL, -- shown to sellers whose listing is rejected
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CHECK (category IS NOT NULL OR brand_id IS NOT NULL OR keyword IS NOT NULL)
+);
+
+CREATE INDEX IF NOT EXISTS idx_listing_policy_rules_active
+    ON marketplace_listing_policy_rules (region)
+    WHERE is_active;
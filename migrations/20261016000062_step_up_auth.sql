@@ -0,0 +1,18 @@
+-- One-time codes emailed for step-up authentication; a new code replaces the user's last one
+CREATE TABLE IF NOT EXISTS marketplace_step_up_codes (
+    user_id TEXT PRIMARY KEY,
+    code_hash TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+-- Short-lived tokens issued for a verified code, required by the sensitive routes
+CREATE TABLE IF NOT EXISTS marketplace_step_up_tokens (
+    token_hash TEXT PRIMARY KEY, -- SHA-256 of the token; the token itself is never stored
+    user_id TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_step_up_tokens_user ON marketplace_step_up_tokens (user_id, expires_at);
@@ -0,0 +1,16 @@
+-- Signed-in devices, one row per bearer token seen on authenticated requests
+CREATE TABLE IF NOT EXISTS marketplace_sessions (
+    id UUID PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE, -- SHA-256 of the bearer token; the token itself is never stored
+    device_id TEXT, -- X-Device-Id sent by the app, stable across sign-ins
+    user_agent TEXT,
+    ip_address TEXT, -- of the latest request
+    new_device BOOLEAN NOT NULL DEFAULT FALSE, -- first seen on a device the user had not used before
+    new_device_notified_at TIMESTAMPTZ, -- when the user was told the new device transacted
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_seen_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    revoked_at TIMESTAMPTZ -- requests with the token are refused from then on
+);
+
+CREATE INDEX IF NOT EXISTS idx_sessions_user ON marketplace_sessions (user_id, last_seen_at DESC);
@@ -0,0 +1,14 @@
+-- Sellers on vacation and deactivated accounts. While a row exists the user's listings
+-- are hidden from everyone else and purchases from them are declined.
+CREATE TABLE IF NOT EXISTS marketplace_seller_away (
+    user_id TEXT PRIMARY KEY,
+    mode TEXT NOT NULL CHECK (mode IN ('vacation', 'deactivated')),
+    message TEXT, -- shown to buyers whose purchases are declined
+    reactivate_at TIMESTAMPTZ, -- vacations end by themselves at this time
+    started_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CHECK (mode = 'deactivated' OR reactivate_at IS NOT NULL)
+);
+
+CREATE INDEX IF NOT EXISTS idx_seller_away_reactivate
+    ON marketplace_seller_away (reactivate_at)
+    WHERE reactivate_at IS NOT NULL;
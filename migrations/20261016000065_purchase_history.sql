@@ -0,0 +1,15 @@
+-- Purchase history: the listing as it was when sold, so later edits don't rewrite what
+-- the buyer bought, and when the buyer was reminded of the code's expiry.
+ALTER TABLE marketplace_transactions
+    ADD COLUMN IF NOT EXISTS listing_title TEXT,
+    ADD COLUMN IF NOT EXISTS listing_brand_name TEXT,
+    ADD COLUMN IF NOT EXISTS code_expiry_reminded_at TIMESTAMPTZ;
+
+-- Earlier sales get the listing as it is now, the closest there is
+UPDATE marketplace_transactions t
+SET listing_title = l.title, listing_brand_name = l.brand_name
+FROM marketplace_listings_all l
+WHERE l.id = t.listing_id AND t.listing_title IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_transactions_buyer_created
+    ON marketplace_transactions (buyer_id, created_at DESC);
@@ -0,0 +1,8 @@
+-- How many days before a purchased code expires its buyer is reminded; 0 turns reminders off
+ALTER TABLE marketplace_notification_settings
+    ADD COLUMN IF NOT EXISTS code_expiry_reminder_days INTEGER NOT NULL DEFAULT 3;
+
+-- Reminders are due by expiration date, per the buyer's setting
+CREATE INDEX IF NOT EXISTS idx_transactions_code_expiry_reminder
+    ON marketplace_transactions (listing_id)
+    WHERE code_expiry_reminded_at IS NULL AND status IN ('escrow', 'completed');
@@ -0,0 +1,18 @@
+-- Questions and answers sellers attach to a listing, shown in the seller's order
+CREATE TABLE IF NOT EXISTS marketplace_listing_faq (
+    id UUID PRIMARY KEY,
+    listing_id UUID NOT NULL REFERENCES marketplace_listings (id) ON DELETE CASCADE,
+    question TEXT NOT NULL,
+    answer TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (listing_id, position)
+);
+
+-- A seller's reply to a buyer's first message
+CREATE TABLE IF NOT EXISTS marketplace_seller_auto_replies (
+    seller_id TEXT PRIMARY KEY,
+    message TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,49 @@
+-- Non-critical side effects (notifications, cache invalidation) that failed when first
+-- tried, retried with exponential backoff
+CREATE TABLE IF NOT EXISTS marketplace_side_effect_retries (
+    id UUID PRIMARY KEY,
+    kind TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 1,
+    last_error TEXT NOT NULL,
+    next_attempt_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_side_effect_retries_due
+    ON marketplace_side_effect_retries (next_attempt_at);
+
+-- Side effects that ran out of attempts, kept for admins to inspect and replay
+CREATE TABLE IF NOT EXISTS marketplace_dead_letters (
+    id UUID PRIMARY KEY,
+    kind TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    attempts INTEGER NOT NULL,
+    last_error TEXT NOT NULL,
+    first_failed_at TIMESTAMPTZ NOT NULL,
+    dead_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    replayed_at TIMESTAMPTZ,
+    replayed_by TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_dead_letters_dead ON marketplace_dead_letters (dead_at DESC);
+
+-- Queued notifications the fan-out gave up on used to stay in the queue; they are dead
+-- letters now
+INSERT INTO marketplace_dead_letters (id, kind, payload, attempts, last_error, first_failed_at, dead_at)
+SELECT id, 'notification',
+       jsonb_build_object(
+           'kind', 'notification',
+           'user_id', user_id,
+           'notification_type', notification_type,
+           'title', title,
+           'message', message,
+           'listing_id', related_listing_id,
+           'transaction_id', related_transaction_id
+       ),
+       attempts, COALESCE(last_error, 'unknown'), created_at, CURRENT_TIMESTAMP
+FROM marketplace_notification_queue
+WHERE attempts >= 10
+ON CONFLICT (id) DO NOTHING;
+
+DELETE FROM marketplace_notification_queue WHERE attempts >= 10;
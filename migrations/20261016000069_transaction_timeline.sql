@@ -0,0 +1,7 @@
+-- Lookups by transaction for the support timeline
+CREATE INDEX IF NOT EXISTS idx_notifications_transaction
+    ON marketplace_notifications (related_transaction_id, created_at)
+    WHERE related_transaction_id IS NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_upi_payments_reference
+    ON marketplace_upi_payments (reference_id, created_at);
@@ -0,0 +1,4 @@
+-- Codes the re-encryption job could not decrypt, so one bad value doesn't stop the rotation
+ALTER TABLE marketplace_coupon_codes
+    ADD COLUMN IF NOT EXISTS reencryption_failed_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS reencryption_error TEXT;
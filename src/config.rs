@@ -0,0 +1,665 @@
+use crate::services::encryption::EncryptionService;
+use bigdecimal::BigDecimal;
+use std::env;
+use std::fmt;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+// Service configuration loaded from environment variables
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub environment: Environment,           // APP_ENV, "production" unless set to "development"
+    pub redis_url: Option<String>,
+    pub database: DatabaseSettings,
+    pub read_replica: ReadReplicaSettings,
+    pub feed: FeedWeights,
+    pub grpc_addr: SocketAddr,               // internal gRPC API, separate from the HTTP port
+    pub internal_api_token: Option<Secret>,  // INTERNAL_API_TOKEN, shared secret for service-to-service calls; required
+    pub trusted_proxy_hops: usize,           // TRUSTED_PROXY_HOPS, proxies in front of the service that append to X-Forwarded-For
+    pub event_broker: String,                // outbox publisher: "log" or "nats"
+    pub nats_url: Option<String>,
+    pub log_format: LogFormat,
+    pub encryption: EncryptionKeys,
+    pub platform_fee_rate: f64,              // share of GMV kept by the platform, for analytics
+    pub currency: String,                    // MARKETPLACE_CURRENCY, ISO 4217 code of prices and transaction amounts
+    pub image_moderation: ImageModeration,
+    pub notification_delivery: NotificationDelivery,
+    pub machine_translation: MachineTranslation,
+    pub payments: PaymentProviderConfig,
+    pub paypal: PaypalSettings,
+    pub stripe_connect: StripeConnectSettings,
+    pub cors: CorsSettings,
+    pub loyalty: LoyaltySettings,
+    pub listing_cache: ListingCacheSettings,
+    pub seller_quotas: SellerQuotaSettings,
+    pub high_value: HighValueSettings,
+    pub seo: SeoSettings,
+    pub partner_api: PartnerApiSettings,
+    pub retention: RetentionSettings,
+    pub regions: RegionSettings,
+    pub step_up: StepUpSettings,
+}
+
+// Sensitive actions need a one-time code emailed to the user; verifying it gives a
+// short-lived step-up token the listed routes require
+#[derive(Debug, Clone)]
+pub struct StepUpSettings {
+    pub routes: Vec<String>,                // STEP_UP_ROUTES, "METHOD /path" as routed, e.g. "POST /payouts/onboarding-link"
+    pub code_ttl_mins: i32,                 // STEP_UP_CODE_TTL_MINS
+    pub max_code_attempts: i32,             // STEP_UP_MAX_CODE_ATTEMPTS, per code sent
+    pub token_ttl_mins: i32,                // STEP_UP_TOKEN_TTL_MINS, how long a verified code unlocks the routes
+}
+
+// Countries the marketplace operates in. Listings and users each belong to one, and browsing
+// stays within one region unless the caller opts into all of them.
+#[derive(Debug, Clone)]
+pub struct RegionSettings {
+    pub enabled: Vec<String>,               // REGIONS, ISO 3166-1 alpha-2 codes like "US,IN"; defaults to DEFAULT_REGION
+    pub default_region: String,             // DEFAULT_REGION, of anonymous browsing and users who never chose one
+    pub fee_rates: Vec<(String, f64)>,      // REGION_FEE_RATES, "IN=0.04,US=0.05"; other regions use PLATFORM_FEE_RATE
+    pub tax_rates: Vec<(String, f64)>,      // REGION_TAX_RATES, "IN=0.18"; charged on the platform fee, none where unset
+}
+
+// Days rows are kept before the nightly cleanup deletes them; 0 keeps them forever
+#[derive(Debug, Clone)]
+pub struct RetentionSettings {
+    pub notifications_days: i32,            // RETENTION_NOTIFICATIONS_DAYS
+    pub rate_limits_days: i32,              // RETENTION_RATE_LIMITS_DAYS
+    pub audit_days: i32,                    // RETENTION_AUDIT_DAYS, impersonation audit, contact violations and idle sessions
+    pub outbox_days: i32,                   // RETENTION_OUTBOX_DAYS, after an event is published
+    pub reveal_tokens_days: i32,            // RETENTION_REVEAL_TOKENS_DAYS, coupon reveal and step-up tokens and codes, after they expire
+    pub partner_usage_days: i32,            // RETENTION_PARTNER_USAGE_DAYS
+    pub batch_size: i64,                    // RETENTION_BATCH_SIZE, rows per delete statement
+}
+
+// Limits new partner API keys start with; admins change them per key
+#[derive(Debug, Clone)]
+pub struct PartnerApiSettings {
+    pub default_requests_per_minute: i32,   // PARTNER_DEFAULT_REQUESTS_PER_MINUTE
+    pub default_daily_quota: i32,           // PARTNER_DEFAULT_DAILY_QUOTA, requests per UTC day
+}
+
+// Public sitemaps and the merchant product feed link to listings on the web frontend
+#[derive(Debug, Clone)]
+pub struct SeoSettings {
+    pub site_url: String,                   // SEO_SITE_URL, web origin that also proxies the API, without a trailing slash
+    pub currency: String,                   // SEO_FEED_CURRENCY, ISO 4217 code of listing prices; defaults to MARKETPLACE_CURRENCY
+}
+
+// Extra checks on transactions at or above the threshold before their payment moves to escrow
+#[derive(Debug, Clone)]
+pub struct HighValueSettings {
+    pub threshold: BigDecimal,              // HIGH_VALUE_THRESHOLD, transaction amount that needs them
+    pub buyer_confirmation: bool,           // HIGH_VALUE_BUYER_CONFIRMATION, buyer confirms with an emailed code
+    pub seller_review: bool,                // HIGH_VALUE_SELLER_REVIEW, an admin reviews the seller
+    pub code_ttl_mins: i32,                 // HIGH_VALUE_CODE_TTL_MINS
+    pub max_code_attempts: i32,             // HIGH_VALUE_MAX_CODE_ATTEMPTS, per code sent
+}
+
+// Active listings a seller may have at once, by tier; admins can override it per seller
+#[derive(Debug, Clone)]
+pub struct SellerQuotaSettings {
+    pub standard_max_listings: i64,         // SELLER_QUOTA_STANDARD_LISTINGS
+    pub trusted_max_listings: i64,          // SELLER_QUOTA_TRUSTED_LISTINGS, for sellers at or above the trusted score
+    pub verified_max_listings: i64,         // SELLER_QUOTA_VERIFIED_LISTINGS, for verified sellers at or above the trusted score
+    pub trusted_min_score: f64,             // SELLER_QUOTA_TRUSTED_MIN_SCORE
+}
+
+// Postgres connection pool sizing and query time limits
+#[derive(Debug, Clone)]
+pub struct DatabaseSettings {
+    pub url: Option<Secret>,                // DATABASE_URL, the primary; required
+    pub max_connections: u32,               // DATABASE_MAX_CONNECTIONS
+    pub min_connections: u32,               // DATABASE_MIN_CONNECTIONS, kept open while idle
+    pub acquire_timeout_ms: u64,            // DATABASE_ACQUIRE_TIMEOUT_MS, wait for a free connection before failing
+    pub statement_timeout_ms: u64,          // DATABASE_STATEMENT_TIMEOUT_MS, Postgres cancels longer statements; 0 disables
+    pub slow_query_ms: u64,                 // DATABASE_SLOW_QUERY_MS, timed queries slower than this are logged; 0 disables
+}
+
+// Read-only queries go to a replica when one is configured; writes always go to the primary
+#[derive(Debug, Clone)]
+pub struct ReadReplicaSettings {
+    pub url: Option<Secret>,                // DATABASE_READ_URL
+    pub max_connections: u32,               // DATABASE_READ_MAX_CONNECTIONS
+    pub max_lag_ms: u64,                    // DATABASE_READ_MAX_LAG_MS, reads fall back to the primary while the replica is further behind
+}
+
+// Caching of anonymous listing browse responses, in shared HTTP caches and in process
+#[derive(Debug, Clone)]
+pub struct ListingCacheSettings {
+    pub s_maxage_secs: u64,                 // LISTING_CACHE_S_MAXAGE_SECS, how long CDNs and proxies may serve a response
+    pub stale_while_revalidate_secs: u64,   // LISTING_CACHE_STALE_SECS, how long after that they may serve it while refetching
+    pub local_ttl_secs: u64,                // LISTING_CACHE_LOCAL_TTL_SECS, in-process cache lifetime; 0 turns it off
+    pub local_capacity: u64,                // LISTING_CACHE_LOCAL_CAPACITY, distinct queries kept in process
+}
+
+// Points buyers earn on completed purchases and redeem as a discount at checkout
+#[derive(Debug, Clone)]
+pub struct LoyaltySettings {
+    pub points_per_unit_spent: i64,     // LOYALTY_POINTS_PER_UNIT_SPENT, earned per whole unit of currency paid
+    pub points_per_unit_discount: i64,  // LOYALTY_POINTS_PER_UNIT_DISCOUNT, redeemed for one unit of currency off
+    pub max_redeem_percent: u32,        // LOYALTY_MAX_REDEEM_PERCENT, share of a checkout points may pay for
+    pub expiry_months: u32,             // LOYALTY_EXPIRY_MONTHS, unused points lapse this long after they were earned
+}
+
+// Cross-origin access for the web frontend
+#[derive(Debug, Clone)]
+pub struct CorsSettings {
+    pub allowed_origins: Vec<String>,   // CORS_ALLOWED_ORIGINS, comma-separated; "https://*.example.com" allows subdomains
+    pub allowed_methods: Vec<String>,   // CORS_ALLOWED_METHODS
+    pub allowed_headers: Vec<String>,   // CORS_ALLOWED_HEADERS
+    pub allow_credentials: bool,        // CORS_ALLOW_CREDENTIALS
+    pub max_age_secs: u64,              // CORS_MAX_AGE_SECS, how long browsers may cache preflight results
+}
+
+// Payment provider used for refunds and UPI payments
+#[derive(Debug, Clone)]
+pub struct PaymentProviderConfig {
+    pub provider: String,                   // PAYMENT_PROVIDER: "http" or "razorpay", or "log" in development; required
+    pub url: Option<String>,                // PAYMENT_PROVIDER_URL, base URL of the http provider; overrides the Razorpay API URL
+    pub key_id: Option<String>,             // PAYMENT_PROVIDER_KEY_ID, Razorpay key id
+    pub api_key: Option<Secret>,            // PAYMENT_PROVIDER_API_KEY, the Razorpay key secret
+    pub webhook_secret: Option<Secret>,     // PAYMENT_WEBHOOK_SECRET, signs payment webhooks
+    pub upi_expiry_mins: u32,               // UPI_PAYMENT_EXPIRY_MINS, how long a UPI request can be approved
+    pub checkout_hold_mins: u32,            // CHECKOUT_HOLD_MINS, how long an unpaid purchase holds its units before it is cancelled
+}
+
+// PayPal checkout, offered alongside the main payment provider when a client id is set
+#[derive(Debug, Clone)]
+pub struct PaypalSettings {
+    pub client_id: Option<String>,          // PAYPAL_CLIENT_ID
+    pub client_secret: Option<Secret>,      // PAYPAL_CLIENT_SECRET
+    pub webhook_id: Option<String>,         // PAYPAL_WEBHOOK_ID, the webhook registered for this service
+    pub api_url: String,                    // PAYPAL_API_URL, the sandbox URL outside production
+    pub currency: String,                   // PAYPAL_CURRENCY, must match the marketplace currency
+    pub return_url: Option<String>,         // PAYPAL_RETURN_URL, where buyers land after approving
+    pub cancel_url: Option<String>,         // PAYPAL_CANCEL_URL
+}
+
+// Stripe Connect accounts sellers are paid out to; onboarding is off when no secret key is set
+#[derive(Debug, Clone)]
+pub struct StripeConnectSettings {
+    pub secret_key: Option<Secret>,         // STRIPE_SECRET_KEY
+    pub api_url: String,                    // STRIPE_API_URL
+    pub country: String,                    // STRIPE_CONNECT_COUNTRY, of new connected accounts
+    pub return_url: Option<String>,         // STRIPE_CONNECT_RETURN_URL, where sellers land after onboarding
+    pub refresh_url: Option<String>,        // STRIPE_CONNECT_REFRESH_URL, where expired onboarding links send them
+    pub required_after_sales: i64,          // PAYOUT_ONBOARDING_REQUIRED_AFTER_SALES, completed sales before listing needs onboarding
+}
+
+// External image moderation API; listing images are not screened when no URL is set
+#[derive(Debug, Clone)]
+pub struct ImageModeration {
+    pub url: Option<String>,        // IMAGE_MODERATION_URL
+    pub api_key: Option<Secret>,    // IMAGE_MODERATION_API_KEY, sent as a bearer token
+    pub timeout_ms: u64,            // IMAGE_MODERATION_TIMEOUT_MS, defaults to 5000
+}
+
+// Messaging service that emails and pushes notifications to users; they are logged when no URL is set
+#[derive(Debug, Clone)]
+pub struct NotificationDelivery {
+    pub url: Option<String>,        // NOTIFICATION_DELIVERY_URL
+    pub api_key: Option<Secret>,    // NOTIFICATION_DELIVERY_API_KEY, sent as a bearer token
+    pub timeout_ms: u64,            // NOTIFICATION_DELIVERY_TIMEOUT_MS, defaults to 5000
+}
+
+// Machine translation service for listing text; listings are not machine-translated when no URL is set
+#[derive(Debug, Clone)]
+pub struct MachineTranslation {
+    pub url: Option<String>,        // MACHINE_TRANSLATION_URL
+    pub api_key: Option<Secret>,    // MACHINE_TRANSLATION_API_KEY, sent as a bearer token
+    pub timeout_ms: u64,            // MACHINE_TRANSLATION_TIMEOUT_MS, defaults to 5000
+}
+
+// Coupon code encryption keys; the current key encrypts, retired keys only decrypt
+#[derive(Debug, Clone)]
+pub struct EncryptionKeys {
+    pub current: Option<Secret>,        // ENCRYPTION_KEY
+    pub current_version: u32,           // ENCRYPTION_KEY_VERSION, defaults to 1
+    pub retired: Vec<(u32, Secret)>,    // ENCRYPTION_KEYS_RETIRED, "1=key,2=key"
+}
+
+// A value that must never appear in logs
+#[derive(Clone)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[redacted]")
+    }
+}
+
+// Where the service runs; development allows fakes that must never handle real money
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Environment {
+    Production,
+    Development,
+}
+
+impl FromStr for Environment {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "production" => Ok(Environment::Production),
+            "development" => Ok(Environment::Development),
+            other => Err(format!("Unknown environment: {}", other)),
+        }
+    }
+}
+
+// Log output format, `LOG_FORMAT=json` for log aggregation or `pretty` for local development
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogFormat {
+    Json,
+    Pretty,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "json" => Ok(LogFormat::Json),
+            "pretty" => Ok(LogFormat::Pretty),
+            other => Err(format!("Unknown log format: {}", other)),
+        }
+    }
+}
+
+// Weights used by the recommendation feed scorer
+#[derive(Debug, Clone)]
+pub struct FeedWeights {
+    pub trending: f64,
+    pub category_affinity: f64,
+    pub followed_seller: f64,
+    pub recency: f64,
+    pub seller_trust: f64,
+}
+
+impl Config {
+    pub fn from_env() -> Self {
+        Self {
+            environment: env_or("APP_ENV", Environment::Production),
+            redis_url: env::var("REDIS_URL").ok(),
+            database: DatabaseSettings {
+                url: env::var("DATABASE_URL").ok().filter(|url| !url.is_empty()).map(Secret),
+                max_connections: env_or("DATABASE_MAX_CONNECTIONS", 20),
+                min_connections: env_or("DATABASE_MIN_CONNECTIONS", 2),
+                acquire_timeout_ms: env_or("DATABASE_ACQUIRE_TIMEOUT_MS", 3000),
+                statement_timeout_ms: env_or("DATABASE_STATEMENT_TIMEOUT_MS", 15000),
+                slow_query_ms: env_or("DATABASE_SLOW_QUERY_MS", 500),
+            },
+            read_replica: ReadReplicaSettings {
+                url: env::var("DATABASE_READ_URL").ok().filter(|url| !url.is_empty()).map(Secret),
+                max_connections: env_or("DATABASE_READ_MAX_CONNECTIONS", 10),
+                max_lag_ms: env_or("DATABASE_READ_MAX_LAG_MS", 1000),
+            },
+            feed: FeedWeights {
+                trending: env_or("FEED_WEIGHT_TRENDING", 0.35),
+                category_affinity: env_or("FEED_WEIGHT_CATEGORY_AFFINITY", 0.25),
+                followed_seller: env_or("FEED_WEIGHT_FOLLOWED_SELLER", 0.2),
+                recency: env_or("FEED_WEIGHT_RECENCY", 0.15),
+                seller_trust: env_or("FEED_WEIGHT_SELLER_TRUST", 0.05),
+            },
+            grpc_addr: env_or("GRPC_ADDR", SocketAddr::from(([0, 0, 0, 0], 50051))),
+            internal_api_token: env::var("INTERNAL_API_TOKEN").ok().filter(|token| !token.is_empty()).map(Secret),
+            trusted_proxy_hops: env_or("TRUSTED_PROXY_HOPS", 1),
+            event_broker: env_or("EVENT_BROKER", "log".to_string()),
+            nats_url: env::var("NATS_URL").ok(),
+            log_format: env_or("LOG_FORMAT", LogFormat::Json),
+            encryption: EncryptionKeys {
+                current: env::var("ENCRYPTION_KEY").ok().filter(|key| !key.is_empty()).map(Secret),
+                current_version: env_or("ENCRYPTION_KEY_VERSION", 1),
+                retired: env::var("ENCRYPTION_KEYS_RETIRED")
+                    .map(|value| parse_retired_keys(&value))
+                    .unwrap_or_default(),
+            },
+            platform_fee_rate: env_or("PLATFORM_FEE_RATE", 0.05),
+            currency: env_or("MARKETPLACE_CURRENCY", "USD".to_string()),
+            image_moderation: ImageModeration {
+                url: env::var("IMAGE_MODERATION_URL").ok().filter(|url| !url.is_empty()),
+                api_key: env::var("IMAGE_MODERATION_API_KEY").ok().filter(|key| !key.is_empty()).map(Secret),
+                timeout_ms: env_or("IMAGE_MODERATION_TIMEOUT_MS", 5000),
+            },
+            notification_delivery: NotificationDelivery {
+                url: env::var("NOTIFICATION_DELIVERY_URL").ok().filter(|url| !url.is_empty()),
+                api_key: env::var("NOTIFICATION_DELIVERY_API_KEY").ok().filter(|key| !key.is_empty()).map(Secret),
+                timeout_ms: env_or("NOTIFICATION_DELIVERY_TIMEOUT_MS", 5000),
+            },
+            machine_translation: MachineTranslation {
+                url: env::var("MACHINE_TRANSLATION_URL").ok().filter(|url| !url.is_empty()),
+                api_key: env::var("MACHINE_TRANSLATION_API_KEY").ok().filter(|key| !key.is_empty()).map(Secret),
+                timeout_ms: env_or("MACHINE_TRANSLATION_TIMEOUT_MS", 5000),
+            },
+            payments: PaymentProviderConfig {
+                provider: env::var("PAYMENT_PROVIDER").unwrap_or_default(),
+                url: env::var("PAYMENT_PROVIDER_URL").ok().filter(|url| !url.is_empty()),
+                key_id: env::var("PAYMENT_PROVIDER_KEY_ID").ok().filter(|key| !key.is_empty()),
+                api_key: env::var("PAYMENT_PROVIDER_API_KEY").ok().filter(|key| !key.is_empty()).map(Secret),
+                webhook_secret: env::var("PAYMENT_WEBHOOK_SECRET").ok().filter(|key| !key.is_empty()).map(Secret),
+                upi_expiry_mins: env_or("UPI_PAYMENT_EXPIRY_MINS", 15),
+                checkout_hold_mins: env_or("CHECKOUT_HOLD_MINS", 20),
+            },
+            paypal: PaypalSettings {
+                client_id: env::var("PAYPAL_CLIENT_ID").ok().filter(|id| !id.is_empty()),
+                client_secret: env::var("PAYPAL_CLIENT_SECRET").ok().filter(|key| !key.is_empty()).map(Secret),
+                webhook_id: env::var("PAYPAL_WEBHOOK_ID").ok().filter(|id| !id.is_empty()),
+                api_url: env_or("PAYPAL_API_URL", "https://api-m.paypal.com".to_string()),
+                currency: env_or("PAYPAL_CURRENCY", "USD".to_string()),
+                return_url: env::var("PAYPAL_RETURN_URL").ok().filter(|url| !url.is_empty()),
+                cancel_url: env::var("PAYPAL_CANCEL_URL").ok().filter(|url| !url.is_empty()),
+            },
+            stripe_connect: StripeConnectSettings {
+                secret_key: env::var("STRIPE_SECRET_KEY").ok().filter(|key| !key.is_empty()).map(Secret),
+                api_url: env_or("STRIPE_API_URL", "https://api.stripe.com".to_string()),
+                country: env_or("STRIPE_CONNECT_COUNTRY", "US".to_string()),
+                return_url: env::var("STRIPE_CONNECT_RETURN_URL").ok().filter(|url| !url.is_empty()),
+                refresh_url: env::var("STRIPE_CONNECT_REFRESH_URL").ok().filter(|url| !url.is_empty()),
+                required_after_sales: env_or("PAYOUT_ONBOARDING_REQUIRED_AFTER_SALES", 10),
+            },
+            cors: CorsSettings {
+                allowed_origins: env_list("CORS_ALLOWED_ORIGINS", "http://localhost:3000"),
+                allowed_methods: env_list("CORS_ALLOWED_METHODS", "GET,POST,PUT,DELETE,OPTIONS"),
+                allowed_headers: env_list(
+                    "CORS_ALLOWED_HEADERS",
+                    "authorization,content-type,if-none-match,x-request-id,x-impersonation-token,x-step-up-token,x-device-id",
+                ),
+                allow_credentials: env_or("CORS_ALLOW_CREDENTIALS", true),
+                max_age_secs: env_or("CORS_MAX_AGE_SECS", 3600),
+            },
+            loyalty: LoyaltySettings {
+                points_per_unit_spent: env_or("LOYALTY_POINTS_PER_UNIT_SPENT", 1),
+                points_per_unit_discount: env_or("LOYALTY_POINTS_PER_UNIT_DISCOUNT", 100),
+                max_redeem_percent: env_or("LOYALTY_MAX_REDEEM_PERCENT", 50),
+                expiry_months: env_or("LOYALTY_EXPIRY_MONTHS", 12),
+            },
+            listing_cache: ListingCacheSettings {
+                s_maxage_secs: env_or("LISTING_CACHE_S_MAXAGE_SECS", 10),
+                stale_while_revalidate_secs: env_or("LISTING_CACHE_STALE_SECS", 30),
+                local_ttl_secs: env_or("LISTING_CACHE_LOCAL_TTL_SECS", 5),
+                local_capacity: env_or("LISTING_CACHE_LOCAL_CAPACITY", 1000),
+            },
+            seller_quotas: SellerQuotaSettings {
+                standard_max_listings: env_or("SELLER_QUOTA_STANDARD_LISTINGS", 10),
+                trusted_max_listings: env_or("SELLER_QUOTA_TRUSTED_LISTINGS", 50),
+                verified_max_listings: env_or("SELLER_QUOTA_VERIFIED_LISTINGS", 200),
+                trusted_min_score: env_or("SELLER_QUOTA_TRUSTED_MIN_SCORE", 70.0),
+            },
+            high_value: HighValueSettings {
+                threshold: env_or("HIGH_VALUE_THRESHOLD", BigDecimal::from(500)),
+                buyer_confirmation: env_or("HIGH_VALUE_BUYER_CONFIRMATION", true),
+                seller_review: env_or("HIGH_VALUE_SELLER_REVIEW", true),
+                code_ttl_mins: env_or("HIGH_VALUE_CODE_TTL_MINS", 15),
+                max_code_attempts: env_or("HIGH_VALUE_MAX_CODE_ATTEMPTS", 5),
+            },
+            seo: SeoSettings {
+                site_url: env_or("SEO_SITE_URL", "https://dealmate.app".to_string()).trim_end_matches('/').to_string(),
+                currency: env::var("SEO_FEED_CURRENCY").unwrap_or_else(|_| env_or("MARKETPLACE_CURRENCY", "USD".to_string())),
+            },
+            partner_api: PartnerApiSettings {
+                default_requests_per_minute: env_or("PARTNER_DEFAULT_REQUESTS_PER_MINUTE", 60),
+                default_daily_quota: env_or("PARTNER_DEFAULT_DAILY_QUOTA", 10_000),
+            },
+            retention: RetentionSettings {
+                notifications_days: env_or("RETENTION_NOTIFICATIONS_DAYS", 180),
+                rate_limits_days: env_or("RETENTION_RATE_LIMITS_DAYS", 2),
+                audit_days: env_or("RETENTION_AUDIT_DAYS", 365),
+                outbox_days: env_or("RETENTION_OUTBOX_DAYS", 14),
+                reveal_tokens_days: env_or("RETENTION_REVEAL_TOKENS_DAYS", 7),
+                partner_usage_days: env_or("RETENTION_PARTNER_USAGE_DAYS", 400),
+                batch_size: env_or("RETENTION_BATCH_SIZE", 5000),
+            },
+            regions: RegionSettings {
+                enabled: env_list("REGIONS", &env_or("DEFAULT_REGION", "US".to_string())),
+                default_region: env_or("DEFAULT_REGION", "US".to_string()),
+                fee_rates: env::var("REGION_FEE_RATES").map(|value| parse_region_rates(&value)).unwrap_or_default(),
+                tax_rates: env::var("REGION_TAX_RATES").map(|value| parse_region_rates(&value)).unwrap_or_default(),
+            },
+            step_up: StepUpSettings {
+                routes: env_list("STEP_UP_ROUTES", "POST /payouts/onboarding-link"),
+                code_ttl_mins: env_or("STEP_UP_CODE_TTL_MINS", 10),
+                max_code_attempts: env_or("STEP_UP_MAX_CODE_ATTEMPTS", 5),
+                token_ttl_mins: env_or("STEP_UP_TOKEN_TTL_MINS", 15),
+            },
+        }
+    }
+
+    /// Check settings the service cannot run without; call once at startup
+    pub fn validate(&self) -> Result<(), String> {
+        let Some(current_key) = &self.encryption.current else {
+            return Err("ENCRYPTION_KEY must be set; stored coupon codes cannot be decrypted without it".to_string());
+        };
+        if EncryptionService::new(current_key.expose()).is_err() {
+            return Err("ENCRYPTION_KEY is not a valid encryption key".to_string());
+        }
+        if let Some((version, _)) = self
+            .encryption
+            .retired
+            .iter()
+            .find(|(_, key)| EncryptionService::new(key.expose()).is_err())
+        {
+            return Err(format!("ENCRYPTION_KEYS_RETIRED key version {} is not a valid encryption key", version));
+        }
+        if self.encryption.current_version == 0 {
+            return Err("ENCRYPTION_KEY_VERSION must be at least 1".to_string());
+        }
+        if self.encryption.retired.iter().any(|(version, _)| *version == self.encryption.current_version) {
+            return Err("ENCRYPTION_KEYS_RETIRED must not contain the current key version".to_string());
+        }
+        match self.payments.provider.as_str() {
+            "" => return Err("PAYMENT_PROVIDER must be set to http or razorpay".to_string()),
+            "log" if self.environment != Environment::Development => {
+                return Err("PAYMENT_PROVIDER=log only logs refunds and payments; it is allowed with APP_ENV=development only".to_string());
+            }
+            _ => {}
+        }
+        if self.payments.provider == "http" && self.payments.url.is_none() {
+            return Err("PAYMENT_PROVIDER_URL must be set for the http payment provider".to_string());
+        }
+        if self.payments.provider == "razorpay"
+            && (self.payments.key_id.is_none() || self.payments.api_key.is_none() || self.payments.webhook_secret.is_none())
+        {
+            return Err("PAYMENT_PROVIDER_KEY_ID, PAYMENT_PROVIDER_API_KEY and PAYMENT_WEBHOOK_SECRET must be set for the razorpay payment provider".to_string());
+        }
+        if self.paypal.client_id.is_some() && (self.paypal.client_secret.is_none() || self.paypal.webhook_id.is_none()) {
+            return Err("PAYPAL_CLIENT_SECRET and PAYPAL_WEBHOOK_ID must be set when PAYPAL_CLIENT_ID is".to_string());
+        }
+        if self.stripe_connect.secret_key.is_some()
+            && (self.stripe_connect.return_url.is_none() || self.stripe_connect.refresh_url.is_none())
+        {
+            return Err("STRIPE_CONNECT_RETURN_URL and STRIPE_CONNECT_REFRESH_URL must be set when STRIPE_SECRET_KEY is".to_string());
+        }
+        if self.stripe_connect.required_after_sales < 0 {
+            return Err("PAYOUT_ONBOARDING_REQUIRED_AFTER_SALES must not be negative".to_string());
+        }
+        if self.payments.upi_expiry_mins < 5 {
+            return Err("UPI_PAYMENT_EXPIRY_MINS must be at least 5".to_string());
+        }
+        if self.payments.checkout_hold_mins < self.payments.upi_expiry_mins {
+            return Err("CHECKOUT_HOLD_MINS must be at least UPI_PAYMENT_EXPIRY_MINS".to_string());
+        }
+        if self.cors.allowed_origins.iter().any(|origin| origin == "*") {
+            return Err("CORS_ALLOWED_ORIGINS must list origins; \"*\" would let any site call the API".to_string());
+        }
+        if let Some(origin) = self.cors.allowed_origins.iter().find(|origin| !is_valid_origin_pattern(origin)) {
+            return Err(format!("CORS_ALLOWED_ORIGINS entry {} is not an origin like https://app.example.com or https://*.example.com", origin));
+        }
+        if self.database.url.is_none() {
+            return Err("DATABASE_URL must be set".to_string());
+        }
+        match &self.internal_api_token {
+            None => return Err("INTERNAL_API_TOKEN must be set; the internal gRPC API does not run unauthenticated".to_string()),
+            // Sent as gRPC metadata, which only carries visible ASCII
+            Some(token) if !token.expose().bytes().all(|b| b.is_ascii_graphic()) => {
+                return Err("INTERNAL_API_TOKEN must only contain visible ASCII characters".to_string());
+            }
+            Some(_) => {}
+        }
+        if self.database.max_connections == 0 {
+            return Err("DATABASE_MAX_CONNECTIONS must be at least 1".to_string());
+        }
+        if self.database.min_connections > self.database.max_connections {
+            return Err("DATABASE_MIN_CONNECTIONS must not exceed DATABASE_MAX_CONNECTIONS".to_string());
+        }
+        if self.read_replica.url.is_some() && self.read_replica.max_connections == 0 {
+            return Err("DATABASE_READ_MAX_CONNECTIONS must be at least 1".to_string());
+        }
+        if self.seller_quotas.standard_max_listings < 1 {
+            return Err("SELLER_QUOTA_STANDARD_LISTINGS must be at least 1".to_string());
+        }
+        if self.seller_quotas.trusted_max_listings < self.seller_quotas.standard_max_listings
+            || self.seller_quotas.verified_max_listings < self.seller_quotas.trusted_max_listings
+        {
+            return Err("SELLER_QUOTA_TRUSTED_LISTINGS and SELLER_QUOTA_VERIFIED_LISTINGS must not be below the tier beneath them".to_string());
+        }
+        if self.high_value.threshold <= BigDecimal::from(0) {
+            return Err("HIGH_VALUE_THRESHOLD must be positive".to_string());
+        }
+        if self.high_value.code_ttl_mins < 1 || self.high_value.max_code_attempts < 1 {
+            return Err("HIGH_VALUE_CODE_TTL_MINS and HIGH_VALUE_MAX_CODE_ATTEMPTS must be at least 1".to_string());
+        }
+        if let Some(route) = self.step_up.routes.iter().find(|route| !is_valid_route_pattern(route)) {
+            return Err(format!("STEP_UP_ROUTES entry {} is not a method and path like POST /payouts/onboarding-link", route));
+        }
+        if self.step_up.code_ttl_mins < 1 || self.step_up.max_code_attempts < 1 || self.step_up.token_ttl_mins < 1 {
+            return Err("STEP_UP_CODE_TTL_MINS, STEP_UP_MAX_CODE_ATTEMPTS and STEP_UP_TOKEN_TTL_MINS must be at least 1".to_string());
+        }
+        if !self.seo.site_url.starts_with("https://") && !self.seo.site_url.starts_with("http://") {
+            return Err("SEO_SITE_URL must be an http or https URL".to_string());
+        }
+        if self.currency.len() != 3 || !self.currency.chars().all(|c| c.is_ascii_uppercase()) {
+            return Err("MARKETPLACE_CURRENCY must be a three-letter currency code like USD".to_string());
+        }
+        if self.seo.currency.len() != 3 || !self.seo.currency.chars().all(|c| c.is_ascii_uppercase()) {
+            return Err("SEO_FEED_CURRENCY must be a three-letter currency code like USD".to_string());
+        }
+        if self.partner_api.default_requests_per_minute < 1 || self.partner_api.default_daily_quota < 1 {
+            return Err("PARTNER_DEFAULT_REQUESTS_PER_MINUTE and PARTNER_DEFAULT_DAILY_QUOTA must be at least 1".to_string());
+        }
+        let retention = &self.retention;
+        if [
+            retention.notifications_days,
+            retention.rate_limits_days,
+            retention.audit_days,
+            retention.outbox_days,
+            retention.reveal_tokens_days,
+            retention.partner_usage_days,
+        ]
+        .iter()
+        .any(|days| *days < 0)
+        {
+            return Err("RETENTION_*_DAYS must not be negative".to_string());
+        }
+        // Repeat offenders are counted over 30 days and partners see 90 days of usage
+        if (1..30).contains(&retention.audit_days) || (1..90).contains(&retention.partner_usage_days) {
+            return Err("RETENTION_AUDIT_DAYS must be at least 30 and RETENTION_PARTNER_USAGE_DAYS at least 90".to_string());
+        }
+        if retention.batch_size < 1 {
+            return Err("RETENTION_BATCH_SIZE must be at least 1".to_string());
+        }
+        let regions = &self.regions;
+        if let Some(code) = regions.enabled.iter().find(|code| code.len() != 2 || !code.chars().all(|c| c.is_ascii_uppercase())) {
+            return Err(format!("REGIONS entry {} is not a two-letter country code like US", code));
+        }
+        if !regions.enabled.contains(&regions.default_region) {
+            return Err("DEFAULT_REGION must be one of REGIONS".to_string());
+        }
+        if let Some((code, _)) = regions
+            .fee_rates
+            .iter()
+            .chain(&regions.tax_rates)
+            .find(|(code, _)| !regions.enabled.contains(code))
+        {
+            return Err(format!("REGION_FEE_RATES and REGION_TAX_RATES name {}, which is not one of REGIONS", code));
+        }
+        if regions.fee_rates.iter().chain(&regions.tax_rates).any(|(_, rate)| !(0.0..1.0).contains(rate)) {
+            return Err("REGION_FEE_RATES and REGION_TAX_RATES must be fractions from 0 up to 1".to_string());
+        }
+        if self.loyalty.points_per_unit_spent < 0 {
+            return Err("LOYALTY_POINTS_PER_UNIT_SPENT must not be negative".to_string());
+        }
+        if self.loyalty.points_per_unit_discount < 1 {
+            return Err("LOYALTY_POINTS_PER_UNIT_DISCOUNT must be at least 1".to_string());
+        }
+        if self.loyalty.max_redeem_percent > 100 {
+            return Err("LOYALTY_MAX_REDEEM_PERCENT must be at most 100".to_string());
+        }
+        if self.loyalty.expiry_months == 0 {
+            return Err("LOYALTY_EXPIRY_MONTHS must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    /// Process-wide configuration, loaded on first access
+    pub fn get() -> &'static Config {
+        CONFIG.get_or_init(Config::from_env)
+    }
+}
+
+fn env_or<T: FromStr>(key: &str, default: T) -> T {
+    env::var(key)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+// Comma-separated list with surrounding whitespace and empty entries dropped
+fn env_list(key: &str, default: &str) -> Vec<String> {
+    env::var(key)
+        .unwrap_or_else(|_| default.to_string())
+        .split(',')
+        .map(|entry| entry.trim().to_string())
+        .filter(|entry| !entry.is_empty())
+        .collect()
+}
+
+// An HTTP method and a routed path, e.g. "PUT /listings/:id"
+fn is_valid_route_pattern(route: &str) -> bool {
+    match route.split_once(' ') {
+        Some((method, path)) => {
+            matches!(method, "GET" | "POST" | "PUT" | "PATCH" | "DELETE") && path.starts_with('/') && !path.contains(' ')
+        }
+        None => false,
+    }
+}
+
+// scheme://host[:port], where the host may start with a "*." wildcard label
+fn is_valid_origin_pattern(origin: &str) -> bool {
+    let Some((scheme, host)) = origin.split_once("://") else {
+        return false;
+    };
+    let host = host.strip_prefix("*.").unwrap_or(host);
+    matches!(scheme, "http" | "https")
+        && !host.is_empty()
+        && !host.contains(['/', '*', '?', '#'])
+}
+
+fn parse_region_rates(value: &str) -> Vec<(String, f64)> {
+    value
+        .split(',')
+        .filter_map(|entry| {
+            let (region, rate) = entry.trim().split_once('=')?;
+            Some((region.trim().to_uppercase(), rate.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+fn parse_retired_keys(value: &str) -> Vec<(u32, Secret)> {
+    value
+        .split(',')
+        .filter_map(|entry| {
+            let (version, key) = entry.trim().split_once('=')?;
+            Some((version.trim().parse().ok()?, Secret(key.trim().to_string())))
+        })
+        .collect()
+}
@@ -0,0 +1,70 @@
+use crate::config::CorsSettings;
+use crate::logging::REQUEST_ID_HEADER;
+use axum::http::{header, request::Parts, HeaderName, HeaderValue, Method};
+use std::time::Duration;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+/// CORS for the configured frontend origins only. An entry like `https://*.example.com`
+/// allows any subdomain of `example.com` over https, but not `example.com` itself.
+pub fn layer(settings: &CorsSettings) -> CorsLayer {
+    let patterns: Vec<OriginPattern> = settings.allowed_origins.iter().map(|o| OriginPattern::parse(o)).collect();
+
+    let methods: Vec<Method> = settings
+        .allowed_methods
+        .iter()
+        .filter_map(|method| method.to_uppercase().parse().ok())
+        .collect();
+    let headers: Vec<HeaderName> = settings
+        .allowed_headers
+        .iter()
+        .filter_map(|name| name.to_lowercase().parse().ok())
+        .collect();
+
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::predicate(move |origin: &HeaderValue, _: &Parts| {
+            origin
+                .to_str()
+                .is_ok_and(|origin| patterns.iter().any(|pattern| pattern.matches(origin)))
+        }))
+        .allow_methods(methods)
+        .allow_headers(headers)
+        // Let the frontend read revalidation tags and correlate requests with our logs
+        .expose_headers([header::ETAG, HeaderName::from_static(REQUEST_ID_HEADER)])
+        .allow_credentials(settings.allow_credentials)
+        .max_age(Duration::from_secs(settings.max_age_secs))
+        .vary([header::ORIGIN])
+}
+
+enum OriginPattern {
+    Exact(String),
+    // Scheme and the ".example.com" suffix the host must end with
+    Subdomain { scheme: String, suffix: String },
+}
+
+impl OriginPattern {
+    fn parse(pattern: &str) -> Self {
+        let pattern = pattern.trim_end_matches('/').to_lowercase();
+        match pattern.split_once("://*.") {
+            Some((scheme, domain)) => OriginPattern::Subdomain {
+                scheme: scheme.to_string(),
+                suffix: format!(".{}", domain),
+            },
+            None => OriginPattern::Exact(pattern),
+        }
+    }
+
+    fn matches(&self, origin: &str) -> bool {
+        let origin = origin.to_lowercase();
+        match self {
+            OriginPattern::Exact(allowed) => origin == *allowed,
+            OriginPattern::Subdomain { scheme, suffix } => {
+                let Some(host) = origin.strip_prefix(scheme.as_str()).and_then(|rest| rest.strip_prefix("://")) else {
+                    return false;
+                };
+                host.strip_suffix(suffix.as_str()).is_some_and(|label| {
+                    !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+                })
+            }
+        }
+    }
+}
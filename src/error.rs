@@ -0,0 +1,144 @@
+use crate::validation::FieldError;
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use std::fmt;
+use utoipa::ToSchema;
+
+// Application error returned by services and handlers
+#[derive(Debug)]
+pub enum AppError {
+    BadRequest(String),          // Malformed input
+    NotFound(String),            // Resource does not exist
+    Unauthorized(String),        // Missing or invalid credentials
+    Forbidden(String),           // Caller may not act on the resource
+    StepUpRequired(String),      // Sensitive action; the caller must verify a one-time code first
+    Conflict(String),            // Resource is in the wrong state for the action
+    UnprocessableEntity(String), // Well-formed input that fails domain validation
+    ValidationFailed(Vec<FieldError>),
+    RateLimited { message: String, retry_after: u64 },
+    ServiceUnavailable(String),  // Feature whose provider is not configured on this deployment
+    InternalError(String),
+}
+
+// JSON error body; `error_code` is stable and safe for clients to branch on
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorBody {
+    pub error_code: &'static str,
+    pub message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+}
+
+impl AppError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) | AppError::StepUpRequired(_) => StatusCode::FORBIDDEN,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::UnprocessableEntity(_) | AppError::ValidationFailed(_) => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            AppError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+            AppError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::InternalError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            AppError::BadRequest(_) => "bad_request",
+            AppError::NotFound(_) => "not_found",
+            AppError::Unauthorized(_) => "unauthorized",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::StepUpRequired(_) => "step_up_required",
+            AppError::Conflict(_) => "conflict",
+            AppError::UnprocessableEntity(_) => "unprocessable_entity",
+            AppError::ValidationFailed(_) => "validation_failed",
+            AppError::RateLimited { .. } => "rate_limited",
+            AppError::ServiceUnavailable(_) => "service_unavailable",
+            AppError::InternalError(_) => "internal_error",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::BadRequest(message)
+            | AppError::NotFound(message)
+            | AppError::Unauthorized(message)
+            | AppError::Forbidden(message)
+            | AppError::StepUpRequired(message)
+            | AppError::Conflict(message)
+            | AppError::UnprocessableEntity(message)
+            | AppError::RateLimited { message, .. }
+            | AppError::ServiceUnavailable(message)
+            | AppError::InternalError(message) => message.as_str(),
+            AppError::ValidationFailed(_) => "Request validation failed",
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<sqlx::Error> for AppError {
+    fn from(error: sqlx::Error) -> Self {
+        match &error {
+            sqlx::Error::RowNotFound => AppError::NotFound("Resource not found".to_string()),
+            sqlx::Error::Database(db) if db.is_unique_violation() => {
+                AppError::Conflict("Resource already exists".to_string())
+            }
+            _ => AppError::InternalError(format!("Database error: {}", error)),
+        }
+    }
+}
+
+impl From<Vec<FieldError>> for AppError {
+    fn from(errors: Vec<FieldError>) -> Self {
+        AppError::ValidationFailed(errors)
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(mut self) -> Response {
+        let status = self.status();
+
+        // Internal details are logged, never returned to clients
+        let message = match &self {
+            AppError::InternalError(details) => {
+                tracing::error!(error = %details, "internal error");
+                "Internal server error".to_string()
+            }
+            _ => self.message().to_string(),
+        };
+
+        let error_code = self.error_code();
+        let fields = match &mut self {
+            AppError::ValidationFailed(fields) => std::mem::take(fields),
+            _ => Vec::new(),
+        };
+
+        let body = Json(ErrorBody {
+            error_code,
+            message,
+            fields,
+        });
+
+        match self {
+            AppError::RateLimited { retry_after, .. } => {
+                (status, [(header::RETRY_AFTER, retry_after.to_string())], body).into_response()
+            }
+            _ => (status, body).into_response(),
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use crate::config::{Config, LogFormat};
+use axum::{extract::Request, http::HeaderValue, middleware::Next, response::Response};
+use std::time::Instant;
+use tracing::{field, Instrument};
+use tracing_subscriber::EnvFilter;
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+// Request id attached to request extensions for handlers that need it
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+/// Install the global tracing subscriber; `RUST_LOG` overrides the default `info` level
+pub fn init(config: &Config) {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let subscriber = tracing_subscriber::fmt().with_env_filter(filter);
+
+    match config.log_format {
+        LogFormat::Json => subscriber.json().init(),
+        LogFormat::Pretty => subscriber.pretty().init(),
+    }
+}
+
+/// Per-request span and completion log with method, path, status, latency and request id.
+/// Reuses an incoming `X-Request-Id` or generates one, and echoes it on the response.
+pub async fn log_requests(mut request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty() && value.len() <= 128)
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    request.extensions_mut().insert(RequestId(request_id.clone()));
+
+    let span = tracing::info_span!(
+        "request",
+        method = %request.method(),
+        path = %request.uri().path(),
+        request_id = %request_id,
+        user_id = field::Empty,
+    );
+
+    let started = Instant::now();
+    let mut response = next.run(request).instrument(span.clone()).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    let status = response.status().as_u16();
+
+    span.in_scope(|| {
+        if response.status().is_server_error() {
+            tracing::error!(status, latency_ms, "request failed");
+        } else {
+            tracing::info!(status, latency_ms, "request completed");
+        }
+    });
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+
+    response
+}
+
+/// Attach the authenticated user to the current request span
+pub fn record_user_id(user_id: &str) {
+    tracing::Span::current().record("user_id", user_id);
+}
@@ -1,19 +1,108 @@
-use axum::{routing::{get, post}, Router, Json};
+mod auth;
+mod config;
+mod cors;
+mod error;
+mod logging;
+mod marketplace;
+mod models;
+mod services;
+mod validation;
+
+use axum::{middleware, routing::{get, post}, Router, Json};
+use config::Config;
+use marketplace::jobs::JobRunner;
+use marketplace::{database, grpc, listing_events, notification_queue, outbox, replica, tasks};
+use std::time::Duration;
 use serde_json::{json, Value};
-use tower_http::cors::CorsLayer;
+use tower_http::compression::CompressionLayer;
 
 #[tokio::main]
 async fn main() {
+    let config = Config::get();
+    logging::init(config);
+
+    // Refuse to start rather than write coupon codes that can never be decrypted
+    if let Err(e) = config.validate() {
+        tracing::error!(error = %e, "invalid configuration");
+        std::process::exit(1);
+    }
+
+    // Both are required by validate
+    let database_url = config.database.url.as_ref().expect("DATABASE_URL is validated").expose();
+    let internal_token = config.internal_api_token.as_ref().expect("INTERNAL_API_TOKEN is validated");
+
+    // `tasks <task> [options]` runs one operational task and exits instead of serving
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some((command, task_args)) = args.split_first() {
+        if command != "tasks" {
+            tracing::error!(command = %command, "unknown command; expected no arguments or tasks <task> [options]");
+            std::process::exit(2);
+        }
+        if let Err(e) = tasks::run_command(database_url, task_args).await {
+            tracing::error!(error = %e, "task failed");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let pool = match database::connect_primary(database_url).await {
+        Ok(pool) => pool,
+        Err(e) => {
+            tracing::error!(error = %e, "could not connect to the database");
+            std::process::exit(1);
+        }
+    };
+    if let Err(e) = replica::init(&config.read_replica, &config.database).await {
+        tracing::error!(error = %e, "could not connect to the read replica");
+        std::process::exit(1);
+    }
+
+    match JobRunner::maintenance(pool.clone()) {
+        Ok(runner) => {
+            runner.spawn();
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "invalid job schedule");
+            std::process::exit(1);
+        }
+    }
+
+    match outbox::publisher_from_config(config).await {
+        Ok(publisher) => {
+            outbox::spawn_outbox_relay(pool.clone(), publisher, Duration::from_secs(1));
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "could not connect to the event broker");
+            std::process::exit(1);
+        }
+    }
+
+    notification_queue::spawn_notification_fanout(pool.clone(), Duration::from_secs(5));
+
+    // Live listing updates reach subscribers through Redis pub/sub
+    match &config.redis_url {
+        Some(redis_url) => {
+            listing_events::spawn_listing_event_forwarder(pool.clone(), redis_url.clone());
+        }
+        None => tracing::warn!("REDIS_URL is not set; listing changes are not forwarded to subscribers"),
+    }
+
+    grpc::spawn_grpc_server(pool.clone(), config.grpc_addr, internal_token.expose().to_string());
+    tracing::info!(addr = %config.grpc_addr, "internal gRPC API running");
+
     let app = Router::new()
         .route("/health", get(health))
         .route("/marketplace/products", get(get_marketplace_products))
         .route("/marketplace/vendors", get(get_vendors))
         .route("/marketplace/products", post(add_product))
         .route("/marketplace/vendors", post(add_vendor))
-        .layer(CorsLayer::permissive());
+        .layer(cors::layer(&config.cors))
+        // gzip/brotli per Accept-Encoding; listing search payloads shrink several times over
+        .layer(CompressionLayer::new())
+        .layer(middleware::from_fn(logging::log_requests));
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3004").await.unwrap();
-    println!("🏪 Marketplace Service running on port 3004");
+    tracing::info!(port = 3004, "marketplace service running");
     axum::serve(listener, app).await.unwrap();
 }
 
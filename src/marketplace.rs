@@ -1,43 +1,108 @@
+use crate::marketplace::away;
+use crate::marketplace::faq;
+use crate::marketplace::policy;
+use crate::marketplace::promo_codes;
+use crate::marketplace::promotions;
+use crate::marketplace::storefront::{self, StorefrontService};
+use crate::marketplace::tags::TagService;
+use crate::marketplace::regions;
+use crate::marketplace::translations;
+use crate::marketplace::upi;
+use crate::marketplace::wallet;
+use crate::validation::{FieldError, Validate, Validator};
 use bigdecimal::BigDecimal;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use std::collections::BTreeMap;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
 #[sqlx(type_name = "text")]
 #[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
 pub enum ListingType {
+    #[serde(alias = "DiscountCode")]
     DiscountCode,
+    #[serde(alias = "GiftCard")]
     GiftCard,
+    #[serde(alias = "ReferralLink")]
     ReferralLink,
+    #[serde(alias = "LocationDeal")]
     LocationDeal,
+    #[serde(alias = "CashbackOffer")]
     CashbackOffer,
+    #[serde(alias = "LoyaltyPoints")]
     LoyaltyPoints,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type)]
+impl ListingType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ListingType::DiscountCode => "discount_code",
+            ListingType::GiftCard => "gift_card",
+            ListingType::ReferralLink => "referral_link",
+            ListingType::LocationDeal => "location_deal",
+            ListingType::CashbackOffer => "cashback_offer",
+            ListingType::LoyaltyPoints => "loyalty_points",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
 #[sqlx(type_name = "text")]
 #[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
 pub enum ListingStatus {
     Active,
     Sold,
     Expired,
     Suspended,
+    PendingReview, // Held by content moderation
+    Reserved, // Shown, never stored: sold out to checkouts that are not paid yet
+}
+
+impl ListingStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ListingStatus::Active => "active",
+            ListingStatus::Sold => "sold",
+            ListingStatus::Expired => "expired",
+            ListingStatus::Suspended => "suspended",
+            ListingStatus::PendingReview => "pending_review",
+            ListingStatus::Reserved => "reserved",
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
 #[sqlx(type_name = "text")]
 #[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
 pub enum TransactionStatus {
     Pending,
     Escrow,
     Completed,
     Cancelled,
     Disputed,
+    ChargedBack,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type)]
+impl TransactionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransactionStatus::Pending => "pending",
+            TransactionStatus::Escrow => "escrow",
+            TransactionStatus::Completed => "completed",
+            TransactionStatus::Cancelled => "cancelled",
+            TransactionStatus::Disputed => "disputed",
+            TransactionStatus::ChargedBack => "charged_back",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "text")]
 #[sqlx(rename_all = "snake_case")]
 pub enum PaymentType {
@@ -47,9 +112,10 @@ pub enum PaymentType {
     Wallet,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
 #[sqlx(type_name = "text")]
 #[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
 pub enum VerificationStatus {
     Pending,
     InProgress,
@@ -57,71 +123,356 @@ pub enum VerificationStatus {
     Rejected,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, PartialEq, ToSchema)]
+#[sqlx(type_name = "text")]
+#[sqlx(rename_all = "snake_case")]
+pub enum BadgeType {
+    PowerSeller,
+    FastResponder,
+    HundredPlusSales,
+    TopRated,
+    VerifiedSeller,
+}
+
+impl BadgeType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BadgeType::PowerSeller => "power_seller",
+            BadgeType::FastResponder => "fast_responder",
+            BadgeType::HundredPlusSales => "hundred_plus_sales",
+            BadgeType::TopRated => "top_rated",
+            BadgeType::VerifiedSeller => "verified_seller",
+        }
+    }
+}
+
+// What a listing's market discount is measured against
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "text")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum MarketDiscountBasis {
+    Brand,
+    Category,
+}
+
 // Marketplace Listing Model
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct MarketplaceListing {
     pub id: Uuid,
     pub seller_id: String,
-    pub listing_type: String, // We'll use String for DB compatibility
+    pub listing_type: ListingType,
     pub title: String,
-    pub description: Option<String>,
+    pub description: Option<String>, // Markdown
+    pub description_html: Option<String>, // Sanitized HTML rendering of the description
     pub category: String,
     pub brand_name: Option<String>,
+    #[schema(value_type = Option<String>)]
     pub original_value: Option<BigDecimal>,
+    #[schema(value_type = String)]
     pub selling_price: BigDecimal,
+    #[schema(value_type = Option<String>)]
     pub discount_percentage: Option<BigDecimal>,
     pub expiration_date: Option<DateTime<Utc>>,
     pub proof_image_url: Option<String>,
-    pub status: String,
+    pub status: ListingStatus,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub view_count: i32,
     pub tags: Vec<String>,
     pub is_verified: bool,
     pub verification_date: Option<DateTime<Utc>>,
+    #[schema(value_type = Option<Object>)]
+    pub details: Option<serde_json::Value>,
+    pub quantity: i32,
+    pub remaining_quantity: i32,
+    pub deleted_at: Option<DateTime<Utc>>,
+    #[schema(value_type = Option<String>)]
+    pub market_discount_score: Option<BigDecimal>, // % cheaper than typical; negative when pricier
+    pub market_discount_basis: Option<MarketDiscountBasis>,
+    pub language: String, // Locale the seller wrote the listing in
+    pub region: String, // Country it is sold in, the seller's region when it was created
+}
+
+// Category Model
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct MarketplaceCategory {
+    pub slug: String,
+    pub name: String,
+    pub parent_slug: Option<String>,
+    pub sort_order: i32,
+    pub is_active: bool,
+}
+
+// Category Tree Node with listing counts
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CategoryNode {
+    pub slug: String,
+    pub name: String,
+    pub listing_count: i64,
+    pub children: Vec<CategoryNode>,
+}
+
+// Brand Model
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct MarketplaceBrand {
+    pub id: Uuid,
+    pub slug: String,
+    pub name: String,
+    pub logo_url: Option<String>,
+    pub website_url: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+// Create Brand Request (admin)
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateBrandRequest {
+    pub name: String,
+    pub slug: Option<String>,
+    pub logo_url: Option<String>,
+    pub website_url: Option<String>,
+    pub aliases: Vec<String>,
+}
+
+// Brand Landing Page Response
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BrandLanding {
+    #[serde(flatten)]
+    pub brand: MarketplaceBrand,
+    pub active_listings: i64,
+    pub average_discount_percentage: Option<f64>,
+    pub lowest_price: Option<f64>,
 }
 
 // Create Listing Request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CreateListingRequest {
     pub listing_type: ListingType,
     pub title: String,
-    pub description: Option<String>,
+    pub description: Option<String>, // Markdown; rendered to description_html
     pub category: String,
     pub brand_name: Option<String>,
+    #[schema(value_type = Option<String>)]
     pub original_value: Option<BigDecimal>,
+    #[schema(value_type = String)]
     pub selling_price: BigDecimal,
+    #[schema(value_type = Option<String>)]
     pub discount_percentage: Option<BigDecimal>,
     pub expiration_date: Option<DateTime<Utc>>,
     pub proof_image_url: Option<String>,
     pub tags: Vec<String>,
     pub coupon_code: Option<String>, // For discount code listings
+    #[serde(default)]
+    pub coupon_codes: Vec<String>, // Additional codes for multi-unit discount code listings
+    pub quantity: Option<i32>, // Defaults to 1
+    #[schema(value_type = Option<Object>)]
+    pub details: Option<serde_json::Value>, // Validated against ListingDetails for the listing type
+    pub language: Option<String>, // Locale the listing is written in; defaults to en
+}
+
+// Bulk Listing Row Error
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BulkRowError {
+    pub row: usize,
+    pub error: String,
+}
+
+// Bulk Listing Creation Response
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BulkCreateListingResponse {
+    pub created: Vec<MarketplaceListing>,
+    pub errors: Vec<BulkRowError>,
+}
+
+// Gift Card Details
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct GiftCardDetails {
+    pub merchant: String,
+    #[schema(value_type = String)]
+    pub balance: BigDecimal,
+    pub currency: Option<String>,
+    pub pin_required: Option<bool>,
+}
+
+// Referral Link Details
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ReferralLinkDetails {
+    pub referral_url: String,
+    pub reward_description: Option<String>,
+}
+
+// Loyalty Points Details
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct LoyaltyPointsDetails {
+    pub program: String,
+    pub points: i64,
+    pub transfer_method: Option<String>,
+}
+
+// Discount Code Details
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct DiscountCodeDetails {
+    #[schema(value_type = Option<String>)]
+    pub minimum_order_value: Option<BigDecimal>,
+    pub applies_to: Option<String>,
+    pub single_use: Option<bool>,
+}
+
+// Location Deal Details
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct LocationDealDetails {
+    pub venue_name: String,
+    pub address: String,
+    pub city: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+}
+
+// Cashback Offer Details
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CashbackOfferDetails {
+    pub platform: String,
+    #[schema(value_type = String)]
+    pub cashback_percentage: BigDecimal,
+    #[schema(value_type = Option<String>)]
+    pub max_cashback: Option<BigDecimal>,
+}
+
+// Structured metadata per listing type
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum ListingDetails {
+    GiftCard(GiftCardDetails),
+    ReferralLink(ReferralLinkDetails),
+    LoyaltyPoints(LoyaltyPointsDetails),
+    DiscountCode(DiscountCodeDetails),
+    LocationDeal(LocationDealDetails),
+    CashbackOffer(CashbackOfferDetails),
+}
+
+impl ListingDetails {
+    /// Whether the listing type cannot be listed without details
+    pub fn is_required(listing_type: &ListingType) -> bool {
+        matches!(
+            listing_type,
+            ListingType::GiftCard | ListingType::ReferralLink | ListingType::LoyaltyPoints
+        )
+    }
+
+    /// Parse and validate raw details against the structure of the listing type
+    pub fn parse(listing_type: &ListingType, value: serde_json::Value) -> Result<Self, String> {
+        let details = match listing_type {
+            ListingType::GiftCard => serde_json::from_value(value).map(ListingDetails::GiftCard),
+            ListingType::ReferralLink => serde_json::from_value(value).map(ListingDetails::ReferralLink),
+            ListingType::LoyaltyPoints => serde_json::from_value(value).map(ListingDetails::LoyaltyPoints),
+            ListingType::DiscountCode => serde_json::from_value(value).map(ListingDetails::DiscountCode),
+            ListingType::LocationDeal => serde_json::from_value(value).map(ListingDetails::LocationDeal),
+            ListingType::CashbackOffer => serde_json::from_value(value).map(ListingDetails::CashbackOffer),
+        }
+        .map_err(|e| format!("Invalid details: {}", e))?;
+
+        details.validate()?;
+        Ok(details)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let zero = BigDecimal::from(0);
+
+        match self {
+            ListingDetails::GiftCard(d) => {
+                if d.merchant.trim().is_empty() {
+                    return Err("Gift card merchant is required".to_string());
+                }
+                if d.balance <= zero {
+                    return Err("Gift card balance must be positive".to_string());
+                }
+            }
+            ListingDetails::ReferralLink(d) => {
+                if !(d.referral_url.starts_with("https://") || d.referral_url.starts_with("http://")) {
+                    return Err("Referral URL must be an http(s) URL".to_string());
+                }
+            }
+            ListingDetails::LoyaltyPoints(d) => {
+                if d.program.trim().is_empty() {
+                    return Err("Loyalty program is required".to_string());
+                }
+                if d.points <= 0 {
+                    return Err("Loyalty points must be positive".to_string());
+                }
+            }
+            ListingDetails::DiscountCode(d) => {
+                if d.minimum_order_value.as_ref().is_some_and(|v| v < &zero) {
+                    return Err("Minimum order value cannot be negative".to_string());
+                }
+            }
+            ListingDetails::LocationDeal(d) => {
+                if d.venue_name.trim().is_empty() || d.city.trim().is_empty() {
+                    return Err("Venue name and city are required".to_string());
+                }
+                if d.latitude.is_some_and(|lat| !(-90.0..=90.0).contains(&lat))
+                    || d.longitude.is_some_and(|lng| !(-180.0..=180.0).contains(&lng))
+                {
+                    return Err("Invalid coordinates".to_string());
+                }
+            }
+            ListingDetails::CashbackOffer(d) => {
+                if d.cashback_percentage <= zero || d.cashback_percentage > BigDecimal::from(100) {
+                    return Err("Cashback percentage must be between 0 and 100".to_string());
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
 
 // Update Listing Request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct UpdateListingRequest {
     pub title: Option<String>,
-    pub description: Option<String>,
+    pub description: Option<String>, // Markdown; rendered to description_html
     pub category: Option<String>,
     pub brand_name: Option<String>,
-    pub original_value: Option<f64>,
-    pub selling_price: Option<f64>,
-    pub discount_percentage: Option<f64>,
+    #[schema(value_type = Option<String>)]
+    pub original_value: Option<BigDecimal>,
+    #[schema(value_type = Option<String>)]
+    pub selling_price: Option<BigDecimal>,
+    #[schema(value_type = Option<String>)]
+    pub discount_percentage: Option<BigDecimal>,
     pub expiration_date: Option<DateTime<Utc>>,
     pub proof_image_url: Option<String>,
     pub tags: Option<Vec<String>>,
+    #[schema(value_type = Option<Object>)]
+    pub details: Option<serde_json::Value>,
+}
+
+// Price History Entry
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct PriceHistoryEntry {
+    pub listing_id: Uuid,
+    #[schema(value_type = String)]
+    pub old_price: BigDecimal,
+    #[schema(value_type = String)]
+    pub new_price: BigDecimal,
+    pub changed_at: DateTime<Utc>,
 }
 
 // Marketplace Transaction Model
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct MarketplaceTransaction {
     pub id: Uuid,
     pub listing_id: Uuid,
     pub buyer_id: String,
     pub seller_id: String,
-    pub amount: f64,
-    pub status: String,
+    #[schema(value_type = String)]
+    pub amount: BigDecimal,
+    pub status: TransactionStatus,
     pub payment_method: Option<String>,
     pub payment_id: Option<String>,
     pub escrow_release_date: Option<DateTime<Utc>>,
@@ -129,17 +480,27 @@ pub struct MarketplaceTransaction {
     pub completed_at: Option<DateTime<Utc>>,
     pub cancellation_reason: Option<String>,
     pub dispute_reason: Option<String>,
+    pub commission_tier: Option<CommissionTier>,
+    #[schema(value_type = Option<String>)]
+    pub platform_fee: Option<BigDecimal>,
+    #[schema(value_type = String)]
+    pub refunded_amount: BigDecimal,
+    pub checkout_id: Option<Uuid>,
+    #[schema(value_type = String)]
+    pub discount_amount: BigDecimal, // Promo and points discount on this item, funded by the platform
+    #[schema(value_type = String)]
+    pub tax_amount: BigDecimal, // Tax on the platform fee in the listing's region, withheld with the fee
 }
 
 // Create Transaction Request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CreateTransactionRequest {
     pub listing_id: Uuid,
     pub payment_method: String,
 }
 
 // Update Transaction Request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct UpdateTransactionRequest {
     pub status: Option<String>,
     pub cancellation_reason: Option<String>,
@@ -147,7 +508,7 @@ pub struct UpdateTransactionRequest {
 }
 
 // Marketplace Review Model
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct MarketplaceReview {
     pub id: Uuid,
     pub transaction_id: Uuid,
@@ -161,7 +522,7 @@ pub struct MarketplaceReview {
 }
 
 // Create Review Request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CreateReviewRequest {
     pub transaction_id: Uuid,
     pub rating: i32,
@@ -169,8 +530,35 @@ pub struct CreateReviewRequest {
     pub deal_verified: bool,
 }
 
+// Archived Listing Filter Options
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ArchivedListingFilters {
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+// Purchase History Filter Options
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PurchaseFilters {
+    pub status: Option<TransactionStatus>,
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+// Review Filter Options
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReviewFilters {
+    pub is_buyer_review: Option<bool>,
+    pub min_rating: Option<i32>,
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+}
+
 // Trust Score Model
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct MarketplaceTrustScore {
     pub user_id: String,
     pub total_transactions: i32,
@@ -182,8 +570,28 @@ pub struct MarketplaceTrustScore {
     pub last_calculated: DateTime<Utc>,
 }
 
+// Trust Score History Snapshot
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct TrustScoreSnapshot {
+    pub id: Uuid,
+    pub user_id: String,
+    pub trust_score: f64,
+    pub total_transactions: i32,
+    pub successful_transactions: i32,
+    pub average_rating: f64,
+    pub total_reviews: i32,
+    pub recorded_at: DateTime<Utc>,
+}
+
+// Seller Badge Model
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct SellerBadge {
+    pub badge: String,
+    pub awarded_at: DateTime<Utc>,
+}
+
 // Payment Method Model
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct UserPaymentMethod {
     pub id: Uuid,
     pub user_id: String,
@@ -196,29 +604,105 @@ pub struct UserPaymentMethod {
 }
 
 // Create Payment Method Request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CreatePaymentMethodRequest {
-    pub payment_type: String,
-    pub provider_customer_id: Option<String>,
-    pub last_four: Option<String>,
-    pub card_brand: Option<String>,
+    pub payment_token: String, // From the payment provider's client SDK; card details never reach us
+    #[serde(default)]
     pub is_default: bool,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PayWithPaymentMethodRequest {
+    pub payment_method_id: Uuid,
+}
+
 // Verification Queue Model
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct MarketplaceVerificationQueue {
     pub id: Uuid,
     pub listing_id: Uuid,
     pub verifier_id: Option<String>,
-    pub verification_status: String,
+    pub verification_status: VerificationStatus,
     pub verification_notes: Option<String>,
     pub submitted_at: DateTime<Utc>,
     pub verified_at: Option<DateTime<Utc>>,
 }
 
+// Seller Verification Model
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct SellerVerification {
+    pub id: Uuid,
+    pub user_id: String,
+    pub full_name: String,
+    pub document_type: String,
+    pub document_url: String,
+    pub selfie_url: Option<String>,
+    pub status: VerificationStatus,
+    pub reviewer_id: Option<String>, // Claiming verifier while in progress
+    pub review_notes: Option<String>,
+    pub submitted_at: DateTime<Utc>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub claim_expires_at: Option<DateTime<Utc>>,
+    pub escalated_at: Option<DateTime<Utc>>, // Waited past the review SLA; claimed before others
+}
+
+// Verifier Claims
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "text")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationClaimOutcome {
+    Verified,
+    Rejected,
+    Expired,  // Not reviewed within the claim SLA; returned to the queue
+    Released, // Reviewed by someone else, e.g. an admin
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct VerificationClaim {
+    pub id: Uuid,
+    pub verification_id: Uuid,
+    pub verifier_id: String,
+    pub claimed_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub outcome: Option<VerificationClaimOutcome>,
+    pub closed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ClaimedVerification {
+    pub verification: SellerVerification,
+    pub claim: VerificationClaim,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct VerifierMetrics {
+    pub verifier_id: String,
+    pub claims: i64,
+    pub verified: i64,
+    pub rejected: i64,
+    pub expired: i64,
+    pub avg_review_seconds: Option<f64>, // From claim to decision
+}
+
+// Submit Seller Verification Request
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SubmitSellerVerificationRequest {
+    pub full_name: String,
+    pub document_type: String, // "passport", "national_id", "drivers_license"
+    pub document_url: String,
+    pub selfie_url: Option<String>,
+}
+
+// Review Seller Verification Request
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReviewSellerVerificationRequest {
+    pub approved: bool,
+    pub review_notes: Option<String>,
+}
+
 // Notification Model
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct MarketplaceNotification {
     pub id: Uuid,
     pub user_id: String,
@@ -232,7 +716,7 @@ pub struct MarketplaceNotification {
 }
 
 // Create Notification Request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CreateNotificationRequest {
     pub user_id: String,
     pub notification_type: String,
@@ -243,56 +727,160 @@ pub struct CreateNotificationRequest {
 }
 
 // Listing Filter Options
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct ListingFilters {
     pub category: Option<String>,
-    pub listing_type: Option<String>,
+    pub listing_type: Option<ListingType>,
     pub min_price: Option<f64>,
     pub max_price: Option<f64>,
     pub seller_id: Option<String>,
-    pub status: Option<String>,
+    pub status: Option<ListingStatus>,
     pub is_verified: Option<bool>,
+    pub verified_seller: Option<bool>,
+    pub brand: Option<String>, // brand slug
+    pub tags: Option<String>, // comma-separated; listings must carry all of them
+    pub language: Option<String>, // locale; listings written in it or translated to it
+    pub region: Option<String>, // country code; browse endpoints default to the caller's region
+    pub all_regions: Option<bool>, // true to browse every region when no region is given
+    #[serde(skip)]
+    pub followed_by: Option<String>, // set server-side only
+    #[serde(skip)]
+    pub include_deleted: bool, // set server-side only, for sellers and admins
+    #[serde(skip)]
+    pub include_shadow_banned: bool, // set server-side only, for sellers and admins
+    #[serde(skip)]
+    pub include_away: bool, // set server-side only, for sellers and admins
     pub search_query: Option<String>,
-    pub sort_by: Option<String>, // "price_asc", "price_desc", "created_at", "popularity"
+    pub sort_by: Option<String>, // "price_asc", "price_desc", "created_at", "popularity", "trending", "market_discount"
     pub page: Option<i64>,
     pub limit: Option<i64>,
+    pub facets: Option<bool>, // Default: true on the public listing search
+}
+
+// Paginated Response Envelope
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PaginatedResponse<T> {
+    pub items: Vec<T>,
+    pub total_count: i64,
+    pub page: i64,
+    pub limit: i64,
+    pub has_more: bool,
+}
+
+// Search Suggestions
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionKind {
+    Brand,
+    Category,
+    Title,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SearchSuggestion {
+    pub kind: SuggestionKind,
+    pub text: String,
+    pub slug: Option<String>, // Brand or category slug to filter by
+    pub score: f64,
+}
+
+// Search Facets
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PriceBucketCount {
+    pub min_price: f64,
+    pub max_price: Option<f64>, // Exclusive; open-ended for the top bucket
+    pub count: i64,
+}
+
+/// Counts of the filtered listings by each filterable attribute
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ListingFacets {
+    pub categories: Vec<FacetCount>,
+    pub brands: Vec<FacetCount>,
+    pub listing_types: Vec<FacetCount>,
+    pub price_buckets: Vec<PriceBucketCount>,
+    pub verified_count: i64,
+}
+
+// Listing Search Response: a page of listings with facets for the filter sidebar
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ListingSearchResponse<T> {
+    #[serde(flatten)]
+    pub results: PaginatedResponse<T>, // Full listings, or sparse objects when `fields` is given
+    pub facets: Option<ListingFacets>,
 }
 
 // Marketplace Profile Response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct MarketplaceProfile {
     pub user_id: String,
     pub username: String,
     pub profile_image_url: Option<String>,
     pub trust_score: MarketplaceTrustScore,
+    pub badges: Vec<SellerBadge>,
+    pub follower_count: i64,
     pub total_listings: i64,
     pub active_listings: i64,
     pub completed_sales: i64,
     pub member_since: DateTime<Utc>,
+    pub locale: String, // Preferred locale, see `ProfilePreferences`
+    pub region: String,
+    pub timezone: String, // IANA zone from the user's notification settings
 }
 
 // Transaction Summary for Dashboard
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct TransactionSummary {
-    pub total_sales: f64,
-    pub total_purchases: f64,
+    #[schema(value_type = String)]
+    pub total_sales: BigDecimal,
+    #[schema(value_type = String)]
+    pub total_purchases: BigDecimal,
     pub pending_transactions: i64,
     pub completed_transactions: i64,
-    pub average_transaction_value: f64,
+    #[schema(value_type = String)]
+    pub average_transaction_value: BigDecimal,
 }
 
 // Listing with Seller Info
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ListingWithSeller {
     #[serde(flatten)]
     pub listing: MarketplaceListing,
     pub seller_username: String,
     pub seller_trust_score: f64,
-    pub seller_profile_image: Option<String>,
+    pub seller_badges: Vec<String>,
+    pub price_drop_percentage: Option<f64>, // Drop vs the highest price in the last 30 days
+    pub featured: bool, // Inside a paid promotion window right now
+    pub currency: String, // ISO 4217 code of the prices
+    pub selling_price_minor: i64, // selling_price in minor units of the currency, e.g. cents
+    pub original_value_minor: Option<i64>,
+    pub seller_timezone: String, // IANA zone the seller set, for showing dates in it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub translated_from: Option<String>, // Original locale when the text was shown translated
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub faq: Vec<ListingFaqEntry>, // The seller's first questions and answers, on the listing detail only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reserved_until: Option<DateTime<Utc>>, // While reserved: when the checkouts holding it lapse unless paid
+}
+
+// Recommendation Feed Item
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FeedItem {
+    #[serde(flatten)]
+    pub listing: ListingWithSeller,
+    pub score: f64,
+    pub reasons: Vec<String>,
 }
 
 // Transaction Detail with Listing and User Info
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct TransactionDetail {
     #[serde(flatten)]
     pub transaction: MarketplaceTransaction,
@@ -301,10 +889,48 @@ pub struct TransactionDetail {
     pub seller_username: String,
     pub can_review: bool,
     pub has_reviewed: bool,
+    pub currency: String, // ISO 4217 code of the amounts
+    pub amount_minor: i64, // amount in minor units of the currency, e.g. cents
+    pub refunded_amount_minor: i64,
+    pub seller_timezone: String, // IANA zone the seller set, for showing dates in it
+}
+
+// Purchase History
+
+// Something the caller bought, with the listing as it was sold
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Purchase {
+    pub transaction_id: Uuid,
+    pub listing_id: Uuid,
+    pub title: String, // At the time of sale
+    pub brand_name: Option<String>, // At the time of sale
+    pub seller_id: String,
+    pub seller_username: String,
+    #[schema(value_type = String)]
+    pub amount: BigDecimal,
+    pub currency: String, // ISO 4217 code of the amount
+    pub amount_minor: i64, // amount in minor units of the currency, e.g. cents
+    pub status: TransactionStatus,
+    pub purchased_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub code_expires_at: Option<DateTime<Utc>>,
+    pub code_available: bool, // The code can be revealed again with GET /purchases/{id}/code
+    pub last_revealed_at: Option<DateTime<Utc>>,
 }
 
 // Notification Settings
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "text")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationDigestMode {
+    #[default]
+    Immediate, // One email or push per notification
+    Hourly,
+    Daily,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct NotificationSettings {
     pub email_notifications: bool,
     pub push_notifications: bool,
@@ -312,4 +938,2116 @@ pub struct NotificationSettings {
     pub price_drop_alerts: bool,
     pub transaction_updates: bool,
     pub review_notifications: bool,
+    #[serde(default)]
+    pub digest_mode: NotificationDigestMode, // Hourly and daily modes send one summary per period
+    #[serde(default)]
+    #[schema(value_type = BTreeMap<String, NotificationChannels>)]
+    pub channel_preferences: sqlx::types::Json<BTreeMap<String, NotificationChannels>>, // By notification type; other types follow the switches above
+    #[schema(value_type = Option<String>, example = "22:00:00")]
+    pub quiet_hours_start: Option<NaiveTime>, // Nothing is emailed or pushed from start until end
+    #[schema(value_type = Option<String>, example = "07:00:00")]
+    pub quiet_hours_end: Option<NaiveTime>,
+    #[serde(default = "default_timezone")]
+    pub timezone: String, // IANA zone quiet hours are in
+    #[serde(default = "default_code_expiry_reminder_days")]
+    pub code_expiry_reminder_days: i32, // Days before a purchased, unrevealed code expires; 0 for no reminder
+}
+
+fn default_timezone() -> String {
+    "UTC".to_string()
+}
+
+fn default_code_expiry_reminder_days() -> i32 {
+    DEFAULT_CODE_EXPIRY_REMINDER_DAYS
+}
+
+impl NotificationSettings {
+    /// Channels a notification of this type goes out on
+    pub fn channels(&self, notification_type: &str) -> NotificationChannels {
+        self.channel_preferences
+            .get(notification_type)
+            .copied()
+            .unwrap_or(NotificationChannels {
+                email: self.email_notifications,
+                push: self.push_notifications,
+                in_app: true,
+            })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub struct NotificationChannels {
+    pub email: bool,
+    pub push: bool,
+    pub in_app: bool,
+}
+
+/// Summary of the notifications sent to a user in one email or push
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct NotificationDigest {
+    pub id: Uuid,
+    pub user_id: String,
+    pub digest_mode: NotificationDigestMode,
+    pub notification_count: i32,
+    pub period_start: DateTime<Utc>,
+    pub sent_at: DateTime<Utc>,
+}
+
+// Admin Analytics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AnalyticsInterval {
+    Day,
+    Week,
+    Month,
+}
+
+impl AnalyticsInterval {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnalyticsInterval::Day => "day",
+            AnalyticsInterval::Week => "week",
+            AnalyticsInterval::Month => "month",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MarketplaceKpis {
+    pub days: i32,
+    #[schema(value_type = String)]
+    pub gross_merchandise_value: BigDecimal, // Completed transactions only
+    #[schema(value_type = String)]
+    pub platform_revenue: BigDecimal,
+    pub take_rate: f64,
+    pub completed_transactions: i64,
+    pub listing_views: i64,
+    pub purchases: i64,
+    pub conversion_rate: f64, // Purchases per view on listings created in the window
+    pub disputed_transactions: i64,
+    pub dispute_rate: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct GmvPoint {
+    pub period_start: DateTime<Utc>,
+    #[schema(value_type = String)]
+    pub gmv: BigDecimal,
+    pub transaction_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct CategoryActivity {
+    pub category: String,
+    pub active_listings: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct SellerCohort {
+    pub cohort_month: DateTime<Utc>,   // Month of the seller's first listing
+    pub new_sellers: i64,
+    pub sellers_with_sale: i64,
+    #[schema(value_type = String)]
+    pub gmv: BigDecimal,
+}
+
+// Data Export
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "text")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Json,
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "application/json",
+            ExportFormat::Csv => "text/csv",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "text")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ExportStatus {
+    Pending,
+    Ready,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DataExport {
+    pub id: Uuid,
+    pub user_id: String,
+    pub format: ExportFormat,
+    pub status: ExportStatus,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>, // Archive is deleted after this
+}
+
+// Everything the marketplace stores about a user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserDataArchive {
+    pub user_id: String,
+    pub generated_at: DateTime<Utc>,
+    pub listings: Vec<MarketplaceListing>,
+    pub transactions: Vec<MarketplaceTransaction>,
+    pub reviews_written: Vec<MarketplaceReview>,
+    pub reviews_received: Vec<MarketplaceReview>,
+    pub notifications: Vec<MarketplaceNotification>,
+    pub favorite_listing_ids: Vec<Uuid>,
+    pub followed_seller_ids: Vec<String>,
+}
+
+// Account Deletion
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "text")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum DeletionStatus {
+    Pending,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct AccountDeletionRequest {
+    pub id: Uuid,
+    pub status: DeletionStatus,
+    pub requested_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+// Account Restrictions
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "text")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum RestrictedAction {
+    CreateListing,
+    CreateTransaction,
+    OpenDispute,
+}
+
+impl RestrictedAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RestrictedAction::CreateListing => "create_listing",
+            RestrictedAction::CreateTransaction => "create_transaction",
+            RestrictedAction::OpenDispute => "open_dispute",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct AccountRestriction {
+    pub id: Uuid,
+    pub user_id: String,
+    pub action: RestrictedAction,
+    pub rule: String,   // Detection rule that applied it
+    pub reason: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub lifted_at: Option<DateTime<Utc>>,
+    pub lifted_by: Option<String>,
+}
+
+// Content Moderation
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationReason {
+    ProhibitedItem,
+    Profanity,
+    ContactInfo,
+    Spam,
+    Image,
+    ExpiredReports, // Seller's appeal of a takedown after buyers reported the code expired
+}
+
+// A single screening finding; `detail` says what matched
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct ModerationFlag {
+    pub reason: ModerationReason,
+    pub field: String,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "text")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ModerationCase {
+    pub id: Uuid,
+    pub listing_id: Uuid,
+    pub seller_id: String,
+    #[schema(value_type = Vec<ModerationFlag>)]
+    pub flags: sqlx::types::Json<Vec<ModerationFlag>>,
+    pub status: ModerationStatus,
+    pub reviewer_id: Option<String>,
+    pub review_notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReviewModerationCaseRequest {
+    pub approved: bool,
+    pub review_notes: Option<String>,
+}
+
+// Expired Code Reports
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ExpiredCodeReportOutcome {
+    pub listing_id: Uuid,
+    pub report_count: i64,
+    pub taken_down: bool, // The listing was just marked expired
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AppealExpiredTakedownRequest {
+    pub message: String, // How the seller knows the code still works
+}
+
+// Contact Details
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ContactDetailKind {
+    Email,
+    Phone,
+    MessagingLink,
+    Handle,
+}
+
+impl ContactDetailKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ContactDetailKind::Email => "email address",
+            ContactDetailKind::Phone => "phone number",
+            ContactDetailKind::MessagingLink => "messaging app link",
+            ContactDetailKind::Handle => "messaging handle",
+        }
+    }
+}
+
+// Text a user submitted with contact details in it; the text itself is not kept
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ContactViolation {
+    pub id: Uuid,
+    pub user_id: String,
+    pub field: String,
+    #[schema(value_type = Vec<ContactDetailKind>)]
+    pub kinds: sqlx::types::Json<Vec<ContactDetailKind>>,
+    pub rejected: bool, // Rejected rather than masked
+    pub created_at: DateTime<Utc>,
+}
+
+// Users who keep posting contact details, most violations first
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ContactOffender {
+    pub user_id: String,
+    pub violations: i64,
+    pub rejected: i64,
+    pub last_violation_at: DateTime<Utc>,
+}
+
+// Shadow Bans
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ShadowBan {
+    pub id: Uuid,
+    pub user_id: String,
+    pub reason: String,
+    pub banned_by: String,
+    pub created_at: DateTime<Utc>,
+    pub lifted_by: Option<String>,
+    pub lifted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateShadowBanRequest {
+    pub user_id: String,
+    pub reason: String,
+}
+
+// Listing Policy Rules
+
+// A prohibited item: listings matching every condition the rule sets can't be created or edited
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ListingPolicyRule {
+    pub id: Uuid,
+    pub category: Option<String>, // Also covers its subcategories
+    pub brand_id: Option<Uuid>,
+    pub keyword: Option<String>, // Whole words in the title, description or tags
+    pub region: Option<String>, // Unset for every region
+    pub reason: String, // Shown to sellers whose listing it rejects
+    pub is_active: bool,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// Create or replace a policy rule (admin); at least one of category, brand_name and keyword
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ListingPolicyRuleRequest {
+    pub category: Option<String>,
+    pub brand_name: Option<String>, // Resolved through the brand registry, aliases included
+    pub keyword: Option<String>, // Case-insensitive
+    pub region: Option<String>,
+    pub reason: String,
+    pub is_active: Option<bool>, // Default: true
+}
+
+// High-Value Transaction Checks
+
+// Checks a high-value transaction must pass before its payment moves to escrow
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct HighValueCheck {
+    pub transaction_id: Uuid,
+    pub buyer_confirmation_required: bool,
+    pub buyer_confirmed_at: Option<DateTime<Utc>>,
+    pub seller_review_required: bool,
+    pub seller_approved: Option<bool>, // Unset until an admin reviews the seller
+    pub seller_reviewed_by: Option<String>,
+    pub seller_reviewed_at: Option<DateTime<Utc>>,
+    pub seller_review_notes: Option<String>,
+    pub payment_received_at: Option<DateTime<Utc>>, // Payment held until the checks pass
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ConfirmHighValueTransactionRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReviewHighValueTransactionRequest {
+    pub approved: bool,
+    pub review_notes: Option<String>,
+}
+
+// Feature Flags
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct FeatureFlag {
+    pub name: String,
+    pub description: Option<String>,
+    pub enabled: bool, // Off means off for everyone, allow-list included
+    pub rollout_percent: i32, // Share of users it is on for, stable per user
+    pub allow_list: Vec<String>, // Users it is always on for while enabled
+    pub updated_by: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UpdateFeatureFlagRequest {
+    pub description: Option<String>,
+    pub enabled: bool,
+    pub rollout_percent: i32,
+    #[serde(default)]
+    pub allow_list: Vec<String>,
+}
+
+// Admin Bulk Actions
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkItemStatus {
+    Succeeded,
+    Skipped, // Nothing to do, e.g. not found or in the wrong state
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BulkItemResult {
+    pub id: String, // Listing or seller id, as sent
+    pub status: BulkItemStatus,
+    pub detail: String,
+}
+
+// Results in request order; a batch is applied in one transaction, so it either
+// applies to every succeeded item or fails as a whole
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BulkActionResponse {
+    pub succeeded: usize,
+    pub skipped: usize,
+    pub results: Vec<BulkItemResult>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BulkSuspendSellersRequest {
+    pub seller_ids: Vec<String>,
+    pub reason: String, // Shown to the sellers
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BulkListingsRequest {
+    pub listing_ids: Vec<Uuid>,
+}
+
+// Admin Impersonation
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ImpersonationSession {
+    pub id: Uuid,
+    pub admin_id: String,
+    pub user_id: String, // The user being impersonated
+    pub reason: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+// Returned once on issue; only a hash of the token is stored
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ImpersonationGrant {
+    #[serde(flatten)]
+    pub session: ImpersonationSession,
+    pub token: String, // Sent as X-Impersonation-Token alongside the admin's own credentials
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateImpersonationRequest {
+    pub user_id: String,
+    pub reason: String, // e.g. the support ticket being debugged
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ImpersonationAuditEntry {
+    pub id: Uuid,
+    pub session_id: Uuid,
+    pub admin_id: String,
+    pub user_id: String,
+    pub method: String,
+    pub path: String,
+    pub allowed: bool, // False when refused as not read-only
+    pub created_at: DateTime<Utc>,
+}
+
+// Step-Up Authentication
+
+// A one-time code was emailed to the caller
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StepUpChallenge {
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct VerifyStepUpRequest {
+    pub code: String,
+}
+
+// Sent in the X-Step-Up-Token header to routes that require step-up
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StepUpToken {
+    pub token: String, // Only returned once
+    pub expires_at: DateTime<Utc>,
+}
+
+// Sessions
+
+// A signed-in device, seen through the bearer token its requests carry
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct MarketplaceSession {
+    pub id: Uuid,
+    pub device_id: Option<String>, // X-Device-Id sent by the app
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>, // Of the latest request
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub current: bool, // The session making this request
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SessionsRevoked {
+    pub revoked: u64,
+}
+
+// Vacation Mode and Deactivation
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "text")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum AwayMode {
+    Vacation,
+    Deactivated,
+}
+
+// The caller's listings are hidden and purchases from them declined until they return
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct SellerAway {
+    pub mode: AwayMode,
+    pub message: Option<String>, // Shown to buyers whose purchases are declined
+    pub reactivate_at: Option<DateTime<Utc>>, // Vacations end by themselves at this time
+    pub started_at: DateTime<Utc>,
+}
+
+// Start Vacation Request; sent again to change the message or the return date
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct VacationModeRequest {
+    pub message: Option<String>,
+    pub reactivate_at: DateTime<Utc>,
+}
+
+// Deactivate Account Response
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AccountDeactivated {
+    pub away: SellerAway,
+    pub cancelled_transaction_ids: Vec<Uuid>, // Pending purchases and sales cancelled on the way out
+}
+
+// Partner API
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct PartnerAccount {
+    pub id: Uuid,
+    pub name: String,
+    pub owner_id: String, // User who manages the partner's keys
+    pub contact_email: Option<String>,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreatePartnerAccountRequest {
+    pub name: String,
+    pub owner_id: String,
+    pub contact_email: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct PartnerApiKey {
+    pub id: Uuid,
+    pub partner_id: Uuid,
+    pub name: String,
+    pub key_prefix: String, // First characters of the key, to tell keys apart
+    pub requests_per_minute: i32,
+    pub daily_quota: i32,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+// Returned once on issue; only a hash of the key is stored
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PartnerApiKeyGrant {
+    #[serde(flatten)]
+    pub key: PartnerApiKey,
+    pub api_key: String, // Sent as X-Api-Key
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreatePartnerApiKeyRequest {
+    pub partner_id: Uuid,
+    pub name: String, // e.g. "production"
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UpdatePartnerKeyLimitsRequest {
+    pub requests_per_minute: i32,
+    pub daily_quota: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct PartnerKeyUsage {
+    pub day: NaiveDate, // UTC
+    pub requests: i64,  // Including rejected ones
+    pub rejected: i64,  // Over the rate limit or daily quota
+}
+
+// Data Retention
+
+// A retention policy with what its cleanup purged over the last 30 days
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RetentionPolicyStatus {
+    pub policy: String,
+    pub table_name: String,
+    pub retention_days: i32, // 0 keeps rows forever
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub last_rows_purged: Option<i64>,
+    pub rows_purged_30d: i64,
+}
+
+// Event Contracts
+
+// Payload of `listing.deleted`
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ListingDeletedEvent {
+    pub listing_id: Uuid,
+    pub seller_id: String,
+}
+
+// Payload of `transaction.refunded`
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TransactionRefundedEvent {
+    pub transaction: MarketplaceTransaction,
+    pub refund: TransactionRefund,
+}
+
+// Payload of `transaction.charged_back`
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TransactionChargedBackEvent {
+    pub transaction: MarketplaceTransaction,
+    pub chargeback: Chargeback,
+}
+
+// A published event type, its current payload version and an example payload
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EventSchemaInfo {
+    pub event_type: String,
+    pub version: u32,
+    pub versioned_type: String, // e.g. transaction.completed.v1
+    pub aggregate_type: String,
+    pub description: String,
+    #[schema(value_type = Object)]
+    pub sample_payload: serde_json::Value,
+}
+
+// Send Test Event Request (admin)
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SendTestEventRequest {
+    pub event_type: String, // Unversioned, e.g. transaction.completed
+}
+
+// A sample event as published to subscribers of the test subject
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TestEventResponse {
+    pub subject: String,
+    pub event_id: Uuid,
+    pub schema: String,
+    #[schema(value_type = Object)]
+    pub payload: serde_json::Value,
+}
+
+// Seller Quotas
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SellerQuotaTier {
+    Standard,
+    Trusted,
+    Verified,
+}
+
+// Seller's listing capacity as shown by GET /my-limits
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SellerLimits {
+    pub tier: SellerQuotaTier,
+    pub max_active_listings: i64,
+    pub active_listings: i64, // Active and held for review
+    pub remaining_listings: i64,
+    pub overridden: bool, // An admin set this seller's limit
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct SellerQuotaOverride {
+    pub user_id: String,
+    pub max_active_listings: i32,
+    pub reason: Option<String>,
+    pub set_by: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SetSellerQuotaRequest {
+    pub max_active_listings: i32,
+    pub reason: Option<String>,
+}
+
+// Seller Commission
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, PartialOrd, Ord, ToSchema)]
+#[sqlx(type_name = "text")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum CommissionTier {
+    Standard,
+    Trusted,
+    Verified,
+    Elite,
+}
+
+impl CommissionTier {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CommissionTier::Standard => "standard",
+            CommissionTier::Trusted => "trusted",
+            CommissionTier::Verified => "verified",
+            CommissionTier::Elite => "elite",
+        }
+    }
+
+    /// Share of the base platform fee rate charged in this tier
+    pub fn rate_multiplier(&self) -> f64 {
+        match self {
+            CommissionTier::Standard => 1.0,
+            CommissionTier::Trusted => 0.8,
+            CommissionTier::Verified => 0.7,
+            CommissionTier::Elite => 0.5,
+        }
+    }
+}
+
+// Seller's commission as shown in the dashboard
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SellerCommission {
+    pub tier: CommissionTier,
+    pub commission_rate: f64,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct CommissionTierChange {
+    pub id: Uuid,
+    pub previous_tier: CommissionTier,
+    pub tier: CommissionTier,
+    pub trust_score: f64,
+    pub changed_at: DateTime<Utc>,
+}
+
+// Coupon Reveals
+
+// Single-use authorization for one coupon reveal
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CouponRevealToken {
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+// Shown alongside revealed codes so screenshots and copies can be traced to the reveal
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CouponWatermark {
+    pub reveal_id: Uuid,
+    pub reference: String,
+    pub label: String,
+    pub revealed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct CouponRevealEvent {
+    pub id: Uuid,
+    pub listing_id: Uuid,
+    pub user_id: String,
+    pub reference: String,
+    pub code_count: i32,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub revealed_at: DateTime<Utc>,
+}
+
+// Dispute Evidence
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DisputeEvidence {
+    pub id: Uuid,
+    pub transaction_id: Uuid,
+    pub uploaded_by: String,
+    pub file_name: Option<String>,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub created_at: DateTime<Utc>,
+    pub url: String, // Download link, served to the parties and admins
+}
+
+// A disputed transaction as admins see it when resolving the dispute
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DisputeCase {
+    #[serde(flatten)]
+    pub transaction: MarketplaceTransaction,
+    pub protection_claim_id: Option<Uuid>,
+    pub evidence: Vec<DisputeEvidence>,
+}
+
+// Purchase Protection
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "text")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ProtectionClaimStatus {
+    AwaitingSeller,
+    SellerResponded,
+    Refunded,
+    Rejected,
+}
+
+// Outcome of the automatic code check run when a claim is opened
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "text")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum CodeValidity {
+    Invalid,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ProtectionClaim {
+    pub id: Uuid,
+    pub transaction_id: Uuid,
+    pub buyer_id: String,
+    pub seller_id: String,
+    pub details: Option<String>,
+    pub status: ProtectionClaimStatus,
+    pub validity_check: CodeValidity,
+    pub validity_notes: Option<String>,
+    pub seller_response: Option<String>,
+    pub seller_responded_at: Option<DateTime<Utc>>,
+    pub response_due_at: DateTime<Utc>,   // Refunded automatically if the seller has not responded by then
+    pub resolution_notes: Option<String>,
+    pub resolved_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+// Refunds
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "text")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum RefundStatus {
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct TransactionRefund {
+    pub id: Uuid,
+    pub transaction_id: Uuid,
+    #[schema(value_type = String)]
+    pub amount: BigDecimal,
+    pub reason: String,
+    pub initiated_by: String,
+    pub status: RefundStatus,
+    pub provider_refund_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+// Cart & Checkout
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct CartItem {
+    pub listing_id: Uuid,
+    pub title: String,
+    pub seller_id: String,
+    #[schema(value_type = String)]
+    pub unit_price: BigDecimal,
+    pub quantity: i32,
+    pub remaining_quantity: i32,
+    pub listing_status: ListingStatus,
+    pub added_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Cart {
+    pub items: Vec<CartItem>,
+    pub item_count: i32,
+    #[schema(value_type = String)]
+    pub total_amount: BigDecimal,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "text")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum CheckoutStatus {
+    AwaitingPayment,
+    Paid,
+    Expired, // Not paid within the checkout hold; its items were cancelled
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Checkout {
+    pub id: Uuid,
+    pub buyer_id: String,
+    pub payment_method: String,
+    pub payment_id: Option<String>,
+    #[schema(value_type = String)]
+    pub total_amount: BigDecimal,
+    pub status: CheckoutStatus,
+    pub bundle_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub paid_at: Option<DateTime<Utc>>,
+    pub promo_code: Option<String>,
+    #[schema(value_type = String)]
+    pub discount_amount: BigDecimal, // Promo code and loyalty points; the buyer pays total_amount - discount_amount
+    pub points_redeemed: i64,
+}
+
+/// A checkout with one transaction per purchased unit; each item keeps its own escrow status
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CheckoutDetail {
+    #[serde(flatten)]
+    pub checkout: Checkout,
+    pub transactions: Vec<MarketplaceTransaction>,
+}
+
+// Bundles
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "text")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum BundleStatus {
+    Active,
+    Inactive,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ListingBundle {
+    pub id: Uuid,
+    pub seller_id: String,
+    pub title: String,
+    pub description: Option<String>,
+    #[schema(value_type = String)]
+    pub bundle_price: BigDecimal,
+    pub status: BundleStatus,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct BundleItem {
+    pub listing_id: Uuid,
+    pub title: String,
+    pub brand_name: Option<String>,
+    pub listing_type: ListingType,
+    #[schema(value_type = Option<String>)]
+    pub original_value: Option<BigDecimal>,
+    #[schema(value_type = String)]
+    pub selling_price: BigDecimal,
+    pub remaining_quantity: i32,
+    pub status: ListingStatus,
+}
+
+/// A bundle as shown to buyers, with what it saves against buying the listings one by one
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BundleWithItems {
+    #[serde(flatten)]
+    pub bundle: ListingBundle,
+    pub items: Vec<BundleItem>,
+    #[schema(value_type = String)]
+    pub separate_price: BigDecimal,
+    #[schema(value_type = String)]
+    pub savings: BigDecimal,
+    pub available: bool,
+}
+
+// Tags
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct TagCount {
+    pub tag: String,
+    pub listing_count: i64,
+}
+
+// Pricing Suggestions
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PricingBasis {
+    Brand,     // Sales of the same brand in the category
+    Category,  // Sales in the category
+    None,      // Not enough sales to suggest a price
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PricingSuggestion {
+    pub basis: PricingBasis,
+    pub sample_size: i64,
+    #[schema(value_type = Option<String>)]
+    pub suggested_price: Option<BigDecimal>,
+    #[schema(value_type = Option<String>)]
+    pub price_low: Option<BigDecimal>,  // 25th percentile
+    #[schema(value_type = Option<String>)]
+    pub price_high: Option<BigDecimal>, // 75th percentile
+    pub typical_discount_percentage: Option<f64>, // Median discount off face value
+}
+
+// Seller Storefronts
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Storefront {
+    pub user_id: String,
+    pub slug: String,
+    pub bio: Option<String>,
+    pub banner_url: Option<String>,
+    pub featured_listing_ids: Vec<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StorefrontPage {
+    pub storefront: Storefront,
+    pub profile: MarketplaceProfile,
+    pub featured: Vec<ListingWithSeller>, // Featured listings still for sale, in the seller's order
+    pub listings: PaginatedResponse<ListingWithSeller>,
+}
+
+// Promotions
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "text")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum PromotionStatus {
+    PendingPayment,
+    Scheduled,
+    Active,
+    Expired,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "text")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum PromotionPayment {
+    Card,
+    Credits,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Promotion {
+    pub id: Uuid,
+    pub listing_id: Uuid,
+    pub seller_id: String,
+    pub category: String,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub status: PromotionStatus,
+    #[schema(value_type = String)]
+    pub price: BigDecimal,
+    pub paid_with: PromotionPayment,
+    pub payment_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub cancelled_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct CreditEntry {
+    pub id: Uuid,
+    pub user_id: String,
+    #[schema(value_type = String)]
+    pub amount: BigDecimal, // Negative when spent
+    pub reason: String,
+    pub promotion_id: Option<Uuid>,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreditBalance {
+    #[schema(value_type = String)]
+    pub balance: BigDecimal,
+    pub entries: Vec<CreditEntry>, // Most recent first
+}
+
+// Create Promotion Request
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreatePromotionRequest {
+    pub listing_id: Uuid,
+    pub starts_at: Option<DateTime<Utc>>, // Default: now
+    pub days: i32,
+    pub paid_with: PromotionPayment, // Card promotions start once the payment is confirmed
+}
+
+// Grant Credits Request (admin)
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GrantCreditsRequest {
+    pub user_id: String,
+    #[schema(value_type = String)]
+    pub amount: BigDecimal, // Negative to correct an earlier grant
+    pub reason: String,
+}
+
+// Update Storefront Request; empty bio or banner_url clears it
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UpdateStorefrontRequest {
+    pub slug: Option<String>,
+    pub bio: Option<String>,
+    pub banner_url: Option<String>,
+    pub featured_listing_ids: Option<Vec<Uuid>>, // Own active listings, in display order
+}
+
+// Promo Codes
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "text")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum PromoDiscountType {
+    Percentage,
+    Fixed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct PromoCampaign {
+    pub id: Uuid,
+    pub code: String,
+    pub description: Option<String>,
+    pub discount_type: PromoDiscountType,
+    #[schema(value_type = String)]
+    pub discount_value: BigDecimal,
+    #[schema(value_type = Option<String>)]
+    pub max_discount: Option<BigDecimal>,
+    pub first_purchase_only: bool,
+    pub category: Option<String>,
+    #[schema(value_type = Option<String>)]
+    pub min_amount: Option<BigDecimal>,
+    pub max_redemptions: Option<i32>,
+    pub max_per_user: i32,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub is_active: bool,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A campaign with how much it has been used, for admins
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PromoCampaignUsage {
+    #[serde(flatten)]
+    pub campaign: PromoCampaign,
+    pub redemption_count: i64,
+    #[schema(value_type = String)]
+    pub discount_total: BigDecimal,
+}
+
+/// What a promo code would take off the buyer's current cart
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PromoQuote {
+    pub code: String,
+    #[schema(value_type = String)]
+    pub eligible_amount: BigDecimal,
+    #[schema(value_type = String)]
+    pub discount_amount: BigDecimal,
+    #[schema(value_type = String)]
+    pub total_amount: BigDecimal,
+    #[schema(value_type = String)]
+    pub amount_due: BigDecimal,
+}
+
+// Create Promo Campaign Request (admin)
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreatePromoCampaignRequest {
+    pub code: String,
+    pub description: Option<String>,
+    pub discount_type: PromoDiscountType,
+    #[schema(value_type = String)]
+    pub discount_value: BigDecimal, // Percent for percentage discounts
+    #[schema(value_type = Option<String>)]
+    pub max_discount: Option<BigDecimal>,
+    #[serde(default)]
+    pub first_purchase_only: bool,
+    pub category: Option<String>,
+    #[schema(value_type = Option<String>)]
+    pub min_amount: Option<BigDecimal>,
+    pub max_redemptions: Option<i32>,
+    pub max_per_user: Option<i32>, // Default: 1
+    pub starts_at: Option<DateTime<Utc>>, // Default: now
+    pub ends_at: Option<DateTime<Utc>>,
+}
+
+// Loyalty Points
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "text")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum LoyaltyEntryKind {
+    Earned,
+    Redeemed,
+    Expired,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct LoyaltyEntry {
+    pub id: Uuid,
+    pub user_id: String,
+    pub points: i64, // Negative when redeemed or expired
+    pub kind: LoyaltyEntryKind,
+    pub transaction_id: Option<Uuid>,
+    pub checkout_id: Option<Uuid>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LoyaltyBalance {
+    pub points: i64,
+    #[schema(value_type = String)]
+    pub value: BigDecimal, // What the points take off at checkout
+    pub expiring_soon: i64, // Points that lapse within the next 30 days unless spent
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LoyaltyHistory {
+    #[serde(flatten)]
+    pub balance: LoyaltyBalance,
+    pub entries: Vec<LoyaltyEntry>, // Most recent first
+}
+
+// Wallet
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "text")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum WalletFundingMethod {
+    Card,
+    Upi,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "text")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum WalletOperationStatus {
+    Pending,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct WalletTopup {
+    pub id: Uuid,
+    pub user_id: String,
+    #[schema(value_type = String)]
+    pub amount: BigDecimal,
+    pub funding_method: WalletFundingMethod,
+    pub status: WalletOperationStatus,
+    pub payment_id: Option<String>,
+    #[schema(value_type = String)]
+    pub withdrawn_amount: BigDecimal,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct WalletWithdrawal {
+    pub id: Uuid,
+    pub user_id: String,
+    pub topup_id: Uuid,
+    #[schema(value_type = String)]
+    pub amount: BigDecimal,
+    pub status: WalletOperationStatus,
+    pub provider_refund_id: Option<String>,
+    pub failure_reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// One movement in or out of a user's wallet
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct WalletEntry {
+    pub id: Uuid,
+    pub journal_id: Uuid,
+    #[schema(value_type = String)]
+    pub amount: BigDecimal, // Negative when money leaves the wallet
+    pub entry_type: String, // topup, payment, refund, withdrawal or withdrawal_reversal
+    pub reference_id: Uuid,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Wallet {
+    #[schema(value_type = String)]
+    pub balance: BigDecimal,
+    #[schema(value_type = String)]
+    pub withdrawable: BigDecimal, // What can still go back to the payments that funded the wallet
+    pub entries: Vec<WalletEntry>, // Most recent first
+}
+
+// Wallet Top-up Request
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WalletTopupRequest {
+    #[schema(value_type = String)]
+    pub amount: BigDecimal,
+    pub funding_method: WalletFundingMethod,
+}
+
+// Wallet Withdrawal Request
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WalletWithdrawalRequest {
+    #[schema(value_type = String)]
+    pub amount: BigDecimal,
+}
+
+// UPI Payments
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "text")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum UpiPaymentPurpose {
+    Checkout,
+    WalletTopup,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "text")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum UpiFlow {
+    Intent,  // Buyer opens a upi:// link in their UPI app
+    Collect, // Provider sends a request to the buyer's VPA
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "text")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum UpiPaymentStatus {
+    Pending,
+    Completed,
+    Failed,
+    Expired,
+    Refunded, // Captured when there was nothing left to pay
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct UpiPayment {
+    pub id: Uuid,
+    pub user_id: String,
+    pub purpose: UpiPaymentPurpose,
+    pub reference_id: Uuid,
+    #[schema(value_type = String)]
+    pub amount: BigDecimal,
+    pub flow: UpiFlow,
+    pub vpa: Option<String>,
+    pub provider_payment_id: Option<String>,
+    pub provider_order_id: Option<String>,
+    pub intent_url: Option<String>,
+    pub status: UpiPaymentStatus,
+    pub failure_reason: Option<String>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+// Start UPI Payment Request
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StartUpiPaymentRequest {
+    pub vpa: Option<String>, // e.g. name@bank for a collect request; omit to pay through an intent link
+}
+
+// PayPal Orders
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "text")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum PaypalOrderStatus {
+    Created,  // Waiting for the buyer to approve on PayPal
+    Captured,
+    Failed,
+    Refunded, // Captured when there was nothing left to pay
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct PaypalOrder {
+    pub id: Uuid,
+    pub user_id: String,
+    pub checkout_id: Uuid,
+    pub provider_order_id: String,
+    #[schema(value_type = String)]
+    pub amount: BigDecimal,
+    pub currency: String,
+    pub approval_url: String, // Send the buyer here to approve the payment
+    pub status: PaypalOrderStatus,
+    pub payment_id: Option<String>,
+    pub payer_id: Option<String>,
+    pub failure_reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub captured_at: Option<DateTime<Utc>>,
+}
+
+// Payment Reversals
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "text")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentReversalKind {
+    Reversal,
+    Chargeback,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct PaymentReversal {
+    pub id: Uuid,
+    pub provider: String,
+    pub provider_event_id: String,
+    pub payment_id: String,
+    pub kind: PaymentReversalKind,
+    #[schema(value_type = Option<String>)]
+    pub amount: Option<BigDecimal>,
+    pub reason: String,
+    pub created_at: DateTime<Utc>,
+    pub applied_at: Option<DateTime<Utc>>, // Chargebacks, once applied to the purchases they hit
+}
+
+/// The part of a chargeback that hit one purchase
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Chargeback {
+    pub id: Uuid,
+    pub reversal_id: Uuid,
+    pub transaction_id: Uuid,
+    #[schema(value_type = String)]
+    pub amount: BigDecimal,
+    #[schema(value_type = String)]
+    pub seller_clawback: BigDecimal, // Taken back from the seller's proceeds; the platform covers the rest
+    pub created_at: DateTime<Utc>,
+}
+
+// Listing Events
+
+/// Change to a listing's status, price or stock, streamed to clients watching it
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ListingAvailabilityEvent {
+    pub event_id: Uuid,
+    pub listing_id: Uuid,
+    pub status: ListingStatus,
+    pub previous_status: ListingStatus,
+    #[schema(value_type = String)]
+    pub selling_price: BigDecimal,
+    #[schema(value_type = String)]
+    pub previous_price: BigDecimal,
+    pub remaining_quantity: i32,
+    pub deleted: bool,
+    pub occurred_at: DateTime<Utc>,
+}
+
+// Listing Revisions
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct FieldChange {
+    #[schema(value_type = Object)]
+    pub from: serde_json::Value,
+    #[schema(value_type = Object)]
+    pub to: serde_json::Value,
+}
+
+/// One edit of a listing: the fields it changed, who made it and when
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ListingRevision {
+    pub id: Uuid,
+    pub listing_id: Uuid,
+    pub revision: i32, // 1 for the first edit; revision 0 is the listing as created
+    pub editor_id: String,
+    #[schema(value_type = BTreeMap<String, FieldChange>)]
+    pub changes: sqlx::types::Json<BTreeMap<String, FieldChange>>,
+    pub reverts_revision: Option<i32>, // Set when the edit restored an earlier revision
+    pub created_at: DateTime<Utc>,
+}
+
+// Listing Translations
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "text")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum TranslationSource {
+    Seller,
+    Machine, // Replaced whenever the listing text changes; a seller translation never is
+}
+
+/// A listing's title and description in another locale
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ListingTranslation {
+    pub listing_id: Uuid,
+    pub locale: String,
+    pub title: String,
+    pub description: Option<String>, // Markdown
+    pub description_html: Option<String>,
+    pub source: TranslationSource,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// Upsert Listing Translation Request
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UpsertListingTranslationRequest {
+    pub title: String,
+    pub description: Option<String>, // Markdown; rendered to description_html
+}
+
+// Listing FAQ
+
+/// A question and answer the seller attached to a listing
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ListingFaqEntry {
+    pub id: Uuid,
+    pub question: String,
+    pub answer: String,
+    pub position: i32, // Entries are shown in this order, from 0
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ListingFaqEntryRequest {
+    pub question: String,
+    pub answer: String,
+}
+
+// Replace Listing FAQ Request
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReplaceListingFaqRequest {
+    pub entries: Vec<ListingFaqEntryRequest>, // In display order; an empty list removes the FAQ
+}
+
+/// A listing's questions and answers with its seller's auto-reply
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ListingFaq {
+    pub listing_id: Uuid,
+    pub entries: Vec<ListingFaqEntry>,
+    pub auto_reply: Option<String>, // Shown to a buyer who writes to the seller for the first time
+}
+
+/// The reply a seller's buyers get to their first message
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct SellerAutoReply {
+    pub message: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+// Set Auto-Reply Request
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SetAutoReplyRequest {
+    pub message: String,
+}
+
+// Profile Preferences
+
+/// Display preferences set on the user's profile; users who never set them get the defaults
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ProfilePreferences {
+    pub locale: String, // Listings and emailed or pushed notifications use it where translated
+    pub region: String, // Country the user buys and sells in; new listings are sold there
+}
+
+// Payout Accounts
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "text")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum PayoutAccountStatus {
+    Pending,    // Onboarding not finished
+    Restricted, // Submitted, but the provider needs more before paying out
+    Enabled,
+}
+
+/// A seller's connected account at the payout provider
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct PayoutAccount {
+    pub user_id: String,
+    pub provider_account_id: String,
+    pub status: PayoutAccountStatus,
+    pub details_submitted: bool,
+    pub payouts_enabled: bool,
+    pub requirements_due: Vec<String>, // What the provider still needs from the seller
+    pub disabled_reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub checked_at: DateTime<Utc>,
+    pub enabled_at: Option<DateTime<Utc>>,
+}
+
+/// Single-use link to the provider's hosted onboarding
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PayoutOnboardingLink {
+    pub url: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+// Dead Letters
+
+/// A side effect, such as a notification or a cache invalidation, that kept failing after
+/// every retry
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct DeadLetter {
+    pub id: Uuid,
+    pub kind: String, // notification, invalidate_listing or clear_user_caches
+    #[schema(value_type = Object)]
+    pub payload: serde_json::Value, // What the side effect does, as replayed
+    pub attempts: i32,
+    pub last_error: String,
+    pub first_failed_at: DateTime<Utc>,
+    pub dead_at: DateTime<Utc>,
+    pub replayed_at: Option<DateTime<Utc>>,
+    pub replayed_by: Option<String>,
+}
+
+// Transaction Timeline
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type, PartialEq, Eq, ToSchema)]
+#[sqlx(type_name = "text")]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum TimelineEntryKind {
+    StatusChange,
+    Payment,      // Provider payments, captures, high-value holds and reversals
+    Refund,
+    Chargeback,
+    Notification, // Sent to either party about this transaction
+    Dispute,      // Evidence and purchase protection claims
+    Reveal,       // The buyer revealed the code
+    Message,      // What a party wrote to the other in a claim; there is no direct messaging
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct TimelineEntry {
+    pub at: DateTime<Utc>,
+    pub kind: TimelineEntryKind,
+    pub actor_id: Option<String>, // None for what providers reported and for notifications
+    pub summary: String,
+    #[schema(value_type = Object)]
+    pub details: serde_json::Value, // Depends on the kind
+}
+
+/// Everything that happened to one transaction, oldest first, for support agents
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TransactionTimeline {
+    pub transaction: MarketplaceTransaction,
+    pub entries: Vec<TimelineEntry>,
+}
+
+// Request Validation
+
+pub const MAX_TITLE_LENGTH: usize = 120;
+pub const MAX_DESCRIPTION_LENGTH: usize = 5000;
+pub const MAX_CATEGORY_LENGTH: usize = 64;
+pub const MAX_BRAND_NAME_LENGTH: usize = 100;
+const MAX_COUPON_CODE_LENGTH: usize = 100;
+const MAX_TAGS: usize = 10;
+const MAX_TAG_LENGTH: usize = 30;
+const MAX_REVIEW_LENGTH: usize = 2000;
+const MAX_POLICY_KEYWORD_LENGTH: usize = 100;
+const MAX_CHANNEL_PREFERENCES: usize = 100;
+const MAX_AWAY_MESSAGE_LENGTH: usize = 500;
+pub const DEFAULT_CODE_EXPIRY_REMINDER_DAYS: i32 = 3;
+const MAX_CODE_EXPIRY_REMINDER_DAYS: i32 = 60;
+
+// Limits apply to the tags as stored, after normalization
+fn validate_tags(v: &mut Validator, tags: &[String]) {
+    let normalized = TagService::normalize(tags);
+    v.check(normalized.len() <= MAX_TAGS, "tags", format!("must have at most {} distinct tags", MAX_TAGS));
+    v.check(
+        tags.iter().all(|tag| !tag.trim().is_empty()) && normalized.iter().all(|tag| tag.chars().count() <= MAX_TAG_LENGTH),
+        "tags",
+        format!("each tag must be 1 to {} characters", MAX_TAG_LENGTH),
+    );
+}
+
+impl Validate for CreateListingRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let zero = BigDecimal::from(0);
+        let mut v = Validator::new();
+
+        v.length("title", &self.title, 3, MAX_TITLE_LENGTH)
+            .optional_length("description", self.description.as_deref(), 0, MAX_DESCRIPTION_LENGTH)
+            .length("category", &self.category, 1, MAX_CATEGORY_LENGTH)
+            .optional_length("brand_name", self.brand_name.as_deref(), 1, MAX_BRAND_NAME_LENGTH)
+            .check(self.selling_price > zero, "selling_price", "must be positive")
+            .check(
+                self.original_value.as_ref().is_none_or(|value| value > &zero),
+                "original_value",
+                "must be positive",
+            )
+            .check(
+                self.discount_percentage
+                    .as_ref()
+                    .is_none_or(|pct| pct >= &zero && pct <= &BigDecimal::from(100)),
+                "discount_percentage",
+                "must be between 0 and 100",
+            )
+            .check(
+                self.expiration_date.is_none_or(|date| date > Utc::now()),
+                "expiration_date",
+                "must be in the future",
+            )
+            .url("proof_image_url", self.proof_image_url.as_deref())
+            .optional_length("coupon_code", self.coupon_code.as_deref(), 1, MAX_COUPON_CODE_LENGTH)
+            .check(
+                self.coupon_codes
+                    .iter()
+                    .all(|code| !code.trim().is_empty() && code.len() <= MAX_COUPON_CODE_LENGTH),
+                "coupon_codes",
+                format!("each code must be 1 to {} characters", MAX_COUPON_CODE_LENGTH),
+            )
+            .check(self.quantity.is_none_or(|q| q > 0), "quantity", "must be positive")
+            .check(
+                self.language.as_deref().is_none_or(translations::is_supported),
+                "language",
+                format!("must be one of {}", translations::SUPPORTED_LOCALES.join(", ")),
+            );
+        validate_tags(&mut v, &self.tags);
+
+        v.finish()
+    }
+}
+
+impl Validate for UpdateListingRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let zero = BigDecimal::from(0);
+        let mut v = Validator::new();
+
+        v.optional_length("title", self.title.as_deref(), 3, MAX_TITLE_LENGTH)
+            .optional_length("description", self.description.as_deref(), 0, MAX_DESCRIPTION_LENGTH)
+            .optional_length("category", self.category.as_deref(), 1, MAX_CATEGORY_LENGTH)
+            .optional_length("brand_name", self.brand_name.as_deref(), 1, MAX_BRAND_NAME_LENGTH)
+            .check(
+                self.selling_price.as_ref().is_none_or(|price| price > &zero),
+                "selling_price",
+                "must be positive",
+            )
+            .check(
+                self.original_value.as_ref().is_none_or(|value| value > &zero),
+                "original_value",
+                "must be positive",
+            )
+            .check(
+                self.discount_percentage
+                    .as_ref()
+                    .is_none_or(|pct| pct >= &zero && pct <= &BigDecimal::from(100)),
+                "discount_percentage",
+                "must be between 0 and 100",
+            )
+            .check(
+                self.expiration_date.is_none_or(|date| date > Utc::now()),
+                "expiration_date",
+                "must be in the future",
+            )
+            .url("proof_image_url", self.proof_image_url.as_deref());
+        if let Some(tags) = &self.tags {
+            validate_tags(&mut v, tags);
+        }
+
+        v.finish()
+    }
+}
+
+impl Validate for UpsertListingTranslationRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .length("title", &self.title, 3, MAX_TITLE_LENGTH)
+            .optional_length("description", self.description.as_deref(), 0, MAX_DESCRIPTION_LENGTH)
+            .finish()
+    }
+}
+
+impl Validate for ReplaceListingFaqRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut v = Validator::new();
+        v.check(
+            self.entries.len() <= faq::MAX_FAQ_ENTRIES,
+            "entries",
+            format!("must have at most {} questions", faq::MAX_FAQ_ENTRIES),
+        );
+        for (i, entry) in self.entries.iter().enumerate() {
+            v.length(&format!("entries[{}].question", i), &entry.question, 5, faq::MAX_QUESTION_LENGTH)
+                .length(&format!("entries[{}].answer", i), &entry.answer, 1, faq::MAX_ANSWER_LENGTH);
+        }
+        v.finish()
+    }
+}
+
+impl Validate for SetAutoReplyRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .length("message", &self.message, 1, faq::MAX_AUTO_REPLY_LENGTH)
+            .finish()
+    }
+}
+
+impl Validate for CreateTransactionRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .length("payment_method", &self.payment_method, 1, 50)
+            .finish()
+    }
+}
+
+impl Validate for CreateReviewRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .check((1..=5).contains(&self.rating), "rating", "must be between 1 and 5")
+            .optional_length("review_text", self.review_text.as_deref(), 0, MAX_REVIEW_LENGTH)
+            .finish()
+    }
+}
+
+impl Validate for CreateBrandRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .length("name", &self.name, 1, MAX_BRAND_NAME_LENGTH)
+            .url("logo_url", self.logo_url.as_deref())
+            .url("website_url", self.website_url.as_deref())
+            .check(
+                self.aliases.iter().all(|alias| alias.chars().count() <= MAX_BRAND_NAME_LENGTH),
+                "aliases",
+                format!("each alias must be at most {} characters", MAX_BRAND_NAME_LENGTH),
+            )
+            .finish()
+    }
+}
+
+impl Validate for ListingPolicyRuleRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .check(
+                self.category.is_some() || self.brand_name.is_some() || self.keyword.is_some(),
+                "category",
+                "a rule needs a category, brand_name or keyword",
+            )
+            .optional_length("category", self.category.as_deref(), 1, MAX_CATEGORY_LENGTH)
+            .optional_length("brand_name", self.brand_name.as_deref(), 1, MAX_BRAND_NAME_LENGTH)
+            .check(
+                self.keyword.as_deref().is_none_or(|keyword| {
+                    (1..=MAX_POLICY_KEYWORD_LENGTH).contains(&policy::normalize_keyword(keyword).chars().count())
+                }),
+                "keyword",
+                format!("must have 1 to {} characters of letters or digits", MAX_POLICY_KEYWORD_LENGTH),
+            )
+            .check(
+                self.region.as_deref().is_none_or(regions::is_supported),
+                "region",
+                format!("must be one of {}", regions::enabled().join(", ")),
+            )
+            .length("reason", self.reason.trim(), 1, 500)
+            .finish()
+    }
+}
+
+impl Validate for SubmitSellerVerificationRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .length("full_name", &self.full_name, 1, 200)
+            .url("document_url", Some(self.document_url.as_str()))
+            .url("selfie_url", self.selfie_url.as_deref())
+            .finish()
+    }
+}
+
+impl Validate for UpdateStorefrontRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut v = Validator::new();
+        if let Some(slug) = &self.slug {
+            v.check(
+                StorefrontService::is_valid_slug(&StorefrontService::normalize_slug(slug)),
+                "slug",
+                format!(
+                    "must be {} to {} letters, digits or hyphens, not starting or ending with a hyphen, and not reserved",
+                    storefront::MIN_SLUG_LENGTH,
+                    storefront::MAX_SLUG_LENGTH
+                ),
+            );
+        }
+        v.optional_length("bio", self.bio.as_deref(), 0, storefront::MAX_BIO_LENGTH)
+            .url("banner_url", self.banner_url.as_deref().filter(|url| !url.is_empty()))
+            .check(
+                self.featured_listing_ids.as_ref().is_none_or(|ids| ids.len() <= storefront::MAX_FEATURED_LISTINGS),
+                "featured_listing_ids",
+                format!("must have at most {} listings", storefront::MAX_FEATURED_LISTINGS),
+            )
+            .finish()
+    }
+}
+
+impl Validate for CreatePromotionRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .check(
+                (promotions::MIN_PROMOTION_DAYS..=promotions::MAX_PROMOTION_DAYS).contains(&self.days),
+                "days",
+                format!(
+                    "must be between {} and {}",
+                    promotions::MIN_PROMOTION_DAYS,
+                    promotions::MAX_PROMOTION_DAYS
+                ),
+            )
+            .finish()
+    }
+}
+
+impl Validate for GrantCreditsRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .length("user_id", &self.user_id, 1, 128)
+            .length("reason", &self.reason, 3, 500)
+            .check(self.amount != BigDecimal::from(0), "amount", "must not be zero")
+            .finish()
+    }
+}
+
+impl Validate for CreatePromoCampaignRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let zero = BigDecimal::from(0);
+        let mut v = Validator::new();
+
+        v.check(
+            promo_codes::is_valid_code(&promo_codes::normalize_code(&self.code)),
+            "code",
+            format!(
+                "must be {} to {} letters, digits, dashes or underscores",
+                promo_codes::MIN_CODE_LENGTH,
+                promo_codes::MAX_CODE_LENGTH
+            ),
+        )
+        .optional_length("description", self.description.as_deref(), 0, 500)
+        .check(self.discount_value > zero, "discount_value", "must be positive")
+        .check(
+            self.discount_type != PromoDiscountType::Percentage || self.discount_value <= BigDecimal::from(100),
+            "discount_value",
+            "a percentage must be at most 100",
+        )
+        .check(self.max_discount.as_ref().is_none_or(|max| max > &zero), "max_discount", "must be positive")
+        .optional_length("category", self.category.as_deref(), 1, MAX_CATEGORY_LENGTH)
+        .check(self.min_amount.as_ref().is_none_or(|min| min >= &zero), "min_amount", "must not be negative")
+        .check(self.max_redemptions.is_none_or(|max| max > 0), "max_redemptions", "must be positive")
+        .check(self.max_per_user.is_none_or(|max| max > 0), "max_per_user", "must be positive")
+        .check(
+            match (self.starts_at, self.ends_at) {
+                (Some(starts_at), Some(ends_at)) => ends_at > starts_at,
+                _ => true,
+            },
+            "ends_at",
+            "must be after starts_at",
+        );
+
+        v.finish()
+    }
+}
+
+impl Validate for WalletTopupRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .check(
+                self.amount >= BigDecimal::from(wallet::MIN_TOPUP) && self.amount <= BigDecimal::from(wallet::MAX_TOPUP),
+                "amount",
+                format!("must be between {} and {}", wallet::MIN_TOPUP, wallet::MAX_TOPUP),
+            )
+            .finish()
+    }
+}
+
+impl Validate for WalletWithdrawalRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            // Withdrawals are made in whole cents, so what rounds to nothing is refused
+            .check(self.amount.round(2) > BigDecimal::from(0), "amount", "must be at least 0.01")
+            .finish()
+    }
+}
+
+impl Validate for StartUpiPaymentRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .check(
+                self.vpa.as_deref().is_none_or(upi::is_valid_vpa),
+                "vpa",
+                "must be a UPI address like name@bank",
+            )
+            .finish()
+    }
+}
+
+impl Validate for CreatePaymentMethodRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .length("payment_token", &self.payment_token, 1, 255)
+            .check(
+                self.payment_token.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-'),
+                "payment_token",
+                "must be a payment provider token",
+            )
+            .finish()
+    }
+}
+
+impl Validate for ProfilePreferences {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .check(
+                translations::is_supported(&self.locale),
+                "locale",
+                format!("must be one of {}", translations::SUPPORTED_LOCALES.join(", ")),
+            )
+            .check(
+                regions::is_supported(&self.region),
+                "region",
+                format!("must be one of {}", regions::enabled().join(", ")),
+            )
+            .finish()
+    }
+}
+
+impl Validate for NotificationSettings {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .length("timezone", &self.timezone, 1, 64)
+            .check(
+                self.quiet_hours_start.is_some() == self.quiet_hours_end.is_some(),
+                "quiet_hours_end",
+                "quiet hours need both a start and an end",
+            )
+            .check(
+                self.quiet_hours_start.is_none() || self.quiet_hours_start != self.quiet_hours_end,
+                "quiet_hours_end",
+                "must differ from quiet_hours_start",
+            )
+            .check(
+                self.channel_preferences.len() <= MAX_CHANNEL_PREFERENCES,
+                "channel_preferences",
+                format!("must have at most {} notification types", MAX_CHANNEL_PREFERENCES),
+            )
+            .check(
+                self.channel_preferences.keys().all(|key| !key.trim().is_empty() && key.len() <= 64),
+                "channel_preferences",
+                "notification types must be 1 to 64 characters",
+            )
+            .check(
+                (0..=MAX_CODE_EXPIRY_REMINDER_DAYS).contains(&self.code_expiry_reminder_days),
+                "code_expiry_reminder_days",
+                format!("must be between 0 and {}", MAX_CODE_EXPIRY_REMINDER_DAYS),
+            )
+            .finish()
+    }
+}
+
+impl Validate for VacationModeRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let now = Utc::now();
+        Validator::new()
+            .optional_length("message", self.message.as_deref(), 1, MAX_AWAY_MESSAGE_LENGTH)
+            .check(self.reactivate_at > now, "reactivate_at", "must be in the future")
+            .check(
+                self.reactivate_at <= now + Duration::days(away::MAX_VACATION_DAYS),
+                "reactivate_at",
+                format!("must be at most {} days away", away::MAX_VACATION_DAYS),
+            )
+            .finish()
+    }
 }
@@ -0,0 +1,301 @@
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::marketplace::expired_reports::EXPIRED_REPORT_THRESHOLD;
+use crate::marketplace::moderation::ModerationService;
+use crate::marketplace::outbox::{event_types, OutboxService};
+use crate::marketplace::replica;
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{
+    BulkActionResponse, BulkItemResult, BulkItemStatus, BulkListingsRequest, BulkSuspendSellersRequest,
+    ListingStatus, MarketplaceListing,
+};
+use chrono::Utc;
+use sqlx::PgPool;
+use std::collections::HashSet;
+use std::hash::Hash;
+use uuid::Uuid;
+
+// Sellers or listings accepted per request
+pub const MAX_BULK_ITEMS: usize = 500;
+
+/// Moderator actions over many sellers or listings at once. Each batch is applied in a
+/// single transaction and reports what happened to every item; items with nothing to do
+/// are skipped rather than failing the batch.
+pub struct AdminBulkService {
+    pool: PgPool,
+}
+
+impl AdminBulkService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Suspend every live or held listing of each seller
+    pub async fn suspend_seller_listings(
+        &self,
+        auth_user: &AuthUser,
+        request: BulkSuspendSellersRequest,
+    ) -> Result<BulkActionResponse, AppError> {
+        let service = MarketplaceService::new(self.pool.clone());
+        service.require_admin(auth_user).await?;
+
+        let mut results = Vec::new();
+        let mut suspended = Vec::new();
+
+        let mut tx = self.pool.begin().await?;
+        for seller_id in unique(&request.seller_ids) {
+            let listings = sqlx::query_as::<_, MarketplaceListing>(
+                r#"
+                UPDATE marketplace_listings
+                SET status = $2, updated_at = CURRENT_TIMESTAMP
+                WHERE seller_id = $1 AND status IN ('active', 'pending_review') AND deleted_at IS NULL
+                RETURNING *
+                "#
+            )
+            .bind(&seller_id)
+            .bind(ListingStatus::Suspended)
+            .fetch_all(&mut *tx)
+            .await?;
+
+            if listings.is_empty() {
+                results.push(skipped(&seller_id, "No active or held listings"));
+                continue;
+            }
+            for listing in &listings {
+                OutboxService::record(&mut tx, "listing", listing.id, event_types::LISTING_UPDATED, listing).await?;
+            }
+            results.push(succeeded(&seller_id, format!("{} listings suspended", listings.len())));
+            suspended.push((seller_id, listings));
+        }
+        tx.commit().await?;
+
+        for (seller_id, listings) in &suspended {
+            for listing in listings {
+                replica::listing_written(listing.id);
+            }
+            replica::user_written(seller_id);
+
+            service.create_notification(
+                seller_id,
+                "listings_suspended",
+                "Listings suspended",
+                &format!("{} of your listings were suspended: {}", listings.len(), request.reason.trim()),
+                None,
+                None,
+            ).await;
+        }
+
+        tracing::info!(admin_id = %auth_user.0.auth0_id, sellers = suspended.len(), "bulk suspended seller listings");
+        Ok(summarize(results))
+    }
+
+    /// Dismiss the expired code reports on each listing. Listings the reports took down are
+    /// put back on sale unless their own expiration date has passed.
+    pub async fn dismiss_expired_reports(
+        &self,
+        auth_user: &AuthUser,
+        request: BulkListingsRequest,
+    ) -> Result<BulkActionResponse, AppError> {
+        let service = MarketplaceService::new(self.pool.clone());
+        service.require_admin(auth_user).await?;
+
+        let mut results = Vec::new();
+        let mut relisted = Vec::new();
+
+        let mut tx = self.pool.begin().await?;
+        for listing_id in unique(&request.listing_ids) {
+            let id = listing_id.to_string();
+            let listing = sqlx::query_as::<_, MarketplaceListing>(
+                "SELECT * FROM marketplace_listings WHERE id = $1 AND deleted_at IS NULL FOR UPDATE"
+            )
+            .bind(listing_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+            let Some(listing) = listing else {
+                results.push(skipped(&id, "Listing not found"));
+                continue;
+            };
+
+            let dismissed = sqlx::query("DELETE FROM marketplace_expired_code_reports WHERE listing_id = $1")
+                .bind(listing_id)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+            if dismissed == 0 {
+                results.push(skipped(&id, "No expired code reports"));
+                continue;
+            }
+
+            let taken_down = listing.status == ListingStatus::Expired && dismissed >= EXPIRED_REPORT_THRESHOLD as u64;
+            let still_valid = listing.expiration_date.is_none_or(|expires| expires > Utc::now());
+            if !(taken_down && still_valid) {
+                results.push(succeeded(&id, format!("{} reports dismissed", dismissed)));
+                continue;
+            }
+
+            let listing = sqlx::query_as::<_, MarketplaceListing>(
+                r#"
+                UPDATE marketplace_listings
+                SET status = $2, updated_at = CURRENT_TIMESTAMP
+                WHERE id = $1
+                RETURNING *
+                "#
+            )
+            .bind(listing_id)
+            .bind(ListingStatus::Active)
+            .fetch_one(&mut *tx)
+            .await?;
+            OutboxService::record(&mut tx, "listing", listing_id, event_types::LISTING_UPDATED, &listing).await?;
+
+            results.push(succeeded(&id, format!("{} reports dismissed, listing relisted", dismissed)));
+            relisted.push(listing);
+        }
+        tx.commit().await?;
+
+        for listing in &relisted {
+            replica::listing_written(listing.id);
+
+            service.create_notification(
+                &listing.seller_id,
+                "listing_relisted",
+                "Listing back on sale",
+                &format!("A moderator dismissed the expired code reports on \"{}\" and put it back on sale", listing.title),
+                Some(listing.id),
+                None,
+            ).await;
+        }
+
+        tracing::info!(admin_id = %auth_user.0.auth0_id, relisted = relisted.len(), "bulk dismissed expired code reports");
+        Ok(summarize(results))
+    }
+
+    /// Screen live listings again, e.g. after the content rules changed. Listings that pass
+    /// are marked verified; listings with findings are held for a moderator.
+    pub async fn reverify_listings(
+        &self,
+        auth_user: &AuthUser,
+        request: BulkListingsRequest,
+    ) -> Result<BulkActionResponse, AppError> {
+        let service = MarketplaceService::new(self.pool.clone());
+        service.require_admin(auth_user).await?;
+
+        let listing_ids = unique(&request.listing_ids);
+        let listings = sqlx::query_as::<_, MarketplaceListing>(
+            "SELECT * FROM marketplace_listings WHERE id = ANY($1) AND status = 'active' AND deleted_at IS NULL"
+        )
+        .bind(&listing_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        // Image screening calls out to the moderation API, so it happens before the transaction
+        let moderation = ModerationService::new(self.pool.clone());
+        let mut screened = Vec::with_capacity(listings.len());
+        for listing in listings {
+            let flags = moderation
+                .screen_listing(&listing.title, listing.description.as_deref(), listing.proof_image_url.as_deref())
+                .await?;
+            screened.push((listing, flags));
+        }
+
+        let mut results = Vec::new();
+        let mut written = Vec::new();
+        let mut held = Vec::new();
+
+        let mut tx = self.pool.begin().await?;
+        for listing_id in listing_ids {
+            let id = listing_id.to_string();
+            let Some((listing, flags)) = screened.iter().find(|(listing, _)| listing.id == listing_id) else {
+                results.push(skipped(&id, "Listing not found or not active"));
+                continue;
+            };
+
+            let query = if flags.is_empty() {
+                r#"
+                UPDATE marketplace_listings
+                SET is_verified = TRUE, verification_date = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
+                WHERE id = $1 AND updated_at = $2 AND status = 'active' AND deleted_at IS NULL
+                RETURNING *
+                "#
+            } else {
+                r#"
+                UPDATE marketplace_listings
+                SET status = 'pending_review', is_verified = FALSE, updated_at = CURRENT_TIMESTAMP
+                WHERE id = $1 AND updated_at = $2 AND status = 'active' AND deleted_at IS NULL
+                RETURNING *
+                "#
+            };
+            // Only as screened; the seller may have edited or sold it meanwhile
+            let updated = sqlx::query_as::<_, MarketplaceListing>(query)
+                .bind(listing_id)
+                .bind(listing.updated_at)
+                .fetch_optional(&mut *tx)
+                .await?;
+            let Some(updated) = updated else {
+                results.push(skipped(&id, "Listing changed while it was being screened"));
+                continue;
+            };
+            OutboxService::record(&mut tx, "listing", listing_id, event_types::LISTING_UPDATED, &updated).await?;
+            written.push(listing_id);
+
+            if flags.is_empty() {
+                results.push(succeeded(&id, "Verified".to_string()));
+            } else {
+                ModerationService::enqueue(&mut tx, listing_id, &updated.seller_id, flags).await?;
+                results.push(succeeded(&id, format!("Held for review with {} findings", flags.len())));
+                held.push(updated);
+            }
+        }
+        tx.commit().await?;
+
+        for listing_id in written {
+            replica::listing_written(listing_id);
+        }
+        for listing in &held {
+            service.create_notification(
+                &listing.seller_id,
+                "listing_under_review",
+                "Listing under review",
+                &format!(
+                    "\"{}\" was checked against updated marketplace rules and is hidden until a moderator has reviewed it",
+                    listing.title
+                ),
+                Some(listing.id),
+                None,
+            ).await;
+        }
+
+        tracing::info!(admin_id = %auth_user.0.auth0_id, held = held.len(), "bulk re-verified listings");
+        Ok(summarize(results))
+    }
+}
+
+// Items in request order, each once
+fn unique<T: Clone + Eq + Hash>(items: &[T]) -> Vec<T> {
+    let mut seen = HashSet::new();
+    items.iter().filter(|item| seen.insert((*item).clone())).cloned().collect()
+}
+
+fn succeeded(id: &str, detail: String) -> BulkItemResult {
+    BulkItemResult {
+        id: id.to_string(),
+        status: BulkItemStatus::Succeeded,
+        detail,
+    }
+}
+
+fn skipped(id: &str, detail: &str) -> BulkItemResult {
+    BulkItemResult {
+        id: id.to_string(),
+        status: BulkItemStatus::Skipped,
+        detail: detail.to_string(),
+    }
+}
+
+fn summarize(results: Vec<BulkItemResult>) -> BulkActionResponse {
+    let succeeded = results.iter().filter(|result| result.status == BulkItemStatus::Succeeded).count();
+    BulkActionResponse {
+        succeeded,
+        skipped: results.len() - succeeded,
+        results,
+    }
+}
@@ -0,0 +1,231 @@
+use crate::error::AppError;
+use crate::marketplace::cache::{cache_ttl, MarketplaceCache};
+use crate::models::marketplace::{
+    AnalyticsInterval, CategoryActivity, GmvPoint, MarketplaceKpis, SellerCohort,
+};
+use bigdecimal::BigDecimal;
+use serde::{de::DeserializeOwned, Serialize};
+use sqlx::{PgPool, Row};
+use std::future::Future;
+
+// Widest reporting windows accepted from callers
+const MAX_ANALYTICS_DAYS: i32 = 730;
+const MAX_COHORT_MONTHS: i32 = 24;
+
+/// Marketplace KPIs for the admin dashboard, computed with aggregate SQL and cached
+pub struct AnalyticsService {
+    pool: PgPool,
+}
+
+impl AnalyticsService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Headline KPIs over the last `days` days.
+    /// Revenue sums the stored platform fees; transactions from before commission tiers apply `fee_rate`.
+    pub async fn get_kpis(
+        &self,
+        cache: &MarketplaceCache,
+        days: i32,
+        fee_rate: f64,
+    ) -> Result<MarketplaceKpis, AppError> {
+        let days = days.clamp(1, MAX_ANALYTICS_DAYS);
+        let key = format!("analytics:kpis:{}:{}", days, fee_rate);
+
+        cached(cache, &key, async {
+            let transactions = sqlx::query(
+                r#"
+                SELECT
+                    COALESCE(SUM(amount - refunded_amount) FILTER (WHERE status = 'completed'), 0)::numeric as gmv,
+                    ROUND(COALESCE(SUM(
+                        COALESCE(platform_fee, amount * $2::numeric) * (amount - refunded_amount) / NULLIF(amount, 0)
+                    ) FILTER (WHERE status = 'completed'), 0), 2) as platform_revenue,
+                    COUNT(*) FILTER (WHERE status = 'completed') as completed_transactions,
+                    COUNT(*) as total_transactions,
+                    COUNT(*) FILTER (WHERE dispute_reason IS NOT NULL) as disputed_transactions
+                FROM marketplace_transactions
+                WHERE created_at >= CURRENT_TIMESTAMP - make_interval(days => $1)
+                "#
+            )
+            .bind(days)
+            .bind(fee_rate)
+            .fetch_one(&self.pool)
+            .await?;
+
+            // Views are only counted per listing, so conversion looks at listings created in the window
+            let funnel = sqlx::query(
+                r#"
+                SELECT
+                    COALESCE(SUM(l.view_count), 0)::int8 as listing_views,
+                    (
+                        SELECT COUNT(*) FROM marketplace_transactions t
+                        JOIN marketplace_listings pl ON pl.id = t.listing_id
+                        WHERE pl.created_at >= CURRENT_TIMESTAMP - make_interval(days => $1)
+                        AND t.status <> 'cancelled'
+                    ) as purchases
+                FROM marketplace_listings l
+                WHERE l.created_at >= CURRENT_TIMESTAMP - make_interval(days => $1)
+                "#
+            )
+            .bind(days)
+            .fetch_one(&self.pool)
+            .await?;
+
+            let gmv: BigDecimal = transactions.get("gmv");
+            let platform_revenue: BigDecimal = transactions.get("platform_revenue");
+            let total_transactions: i64 = transactions.get("total_transactions");
+            let disputed_transactions: i64 = transactions.get("disputed_transactions");
+            let listing_views: i64 = funnel.get("listing_views");
+            let purchases: i64 = funnel.get("purchases");
+
+            let take_rate = if gmv > BigDecimal::from(0) {
+                ratio_of(&platform_revenue, &gmv)
+            } else {
+                0.0
+            };
+
+            Ok(MarketplaceKpis {
+                days,
+                gross_merchandise_value: gmv,
+                platform_revenue,
+                take_rate,
+                completed_transactions: transactions.get("completed_transactions"),
+                listing_views,
+                purchases,
+                conversion_rate: rate(purchases, listing_views),
+                disputed_transactions,
+                dispute_rate: rate(disputed_transactions, total_transactions),
+            })
+        })
+        .await
+    }
+
+    /// Completed GMV bucketed by completion date
+    pub async fn get_gmv_series(
+        &self,
+        cache: &MarketplaceCache,
+        interval: AnalyticsInterval,
+        days: i32,
+    ) -> Result<Vec<GmvPoint>, AppError> {
+        let days = days.clamp(1, MAX_ANALYTICS_DAYS);
+        let key = format!("analytics:gmv:{}:{}", interval.as_str(), days);
+
+        cached(cache, &key, async {
+            let points = sqlx::query_as::<_, GmvPoint>(
+                r#"
+                SELECT
+                    date_trunc($1, completed_at) as period_start,
+                    COALESCE(SUM(amount - refunded_amount), 0)::numeric as gmv,
+                    COUNT(*) as transaction_count
+                FROM marketplace_transactions
+                WHERE status = 'completed'
+                AND completed_at >= CURRENT_TIMESTAMP - make_interval(days => $2)
+                GROUP BY 1
+                ORDER BY 1
+                "#
+            )
+            .bind(interval.as_str())
+            .bind(days)
+            .fetch_all(&self.pool)
+            .await?;
+
+            Ok(points)
+        })
+        .await
+    }
+
+    pub async fn get_active_listings_by_category(
+        &self,
+        cache: &MarketplaceCache,
+    ) -> Result<Vec<CategoryActivity>, AppError> {
+        cached(cache, "analytics:categories", async {
+            let categories = sqlx::query_as::<_, CategoryActivity>(
+                r#"
+                SELECT category, COUNT(*) as active_listings
+                FROM marketplace_listings
+                WHERE status = 'active' AND deleted_at IS NULL
+                GROUP BY category
+                ORDER BY active_listings DESC, category
+                "#
+            )
+            .fetch_all(&self.pool)
+            .await?;
+
+            Ok(categories)
+        })
+        .await
+    }
+
+    /// Sellers grouped by the month of their first listing, with how many went on to sell
+    pub async fn get_seller_cohorts(
+        &self,
+        cache: &MarketplaceCache,
+        months: i32,
+    ) -> Result<Vec<SellerCohort>, AppError> {
+        let months = months.clamp(1, MAX_COHORT_MONTHS);
+        let key = format!("analytics:cohorts:{}", months);
+
+        cached(cache, &key, async {
+            let cohorts = sqlx::query_as::<_, SellerCohort>(
+                r#"
+                WITH first_listing AS (
+                    SELECT seller_id, date_trunc('month', MIN(created_at)) as cohort_month
+                    FROM marketplace_listings
+                    GROUP BY seller_id
+                ),
+                seller_sales AS (
+                    SELECT seller_id, SUM(amount - refunded_amount) as gmv
+                    FROM marketplace_transactions
+                    WHERE status = 'completed'
+                    GROUP BY seller_id
+                )
+                SELECT
+                    f.cohort_month,
+                    COUNT(*) as new_sellers,
+                    COUNT(s.seller_id) as sellers_with_sale,
+                    COALESCE(SUM(s.gmv), 0)::numeric as gmv
+                FROM first_listing f
+                LEFT JOIN seller_sales s ON s.seller_id = f.seller_id
+                WHERE f.cohort_month >= date_trunc('month', CURRENT_TIMESTAMP) - make_interval(months => $1 - 1)
+                GROUP BY f.cohort_month
+                ORDER BY f.cohort_month
+                "#
+            )
+            .bind(months)
+            .fetch_all(&self.pool)
+            .await?;
+
+            Ok(cohorts)
+        })
+        .await
+    }
+}
+
+// Serve from cache when possible; cache failures are treated as misses
+async fn cached<T, F>(cache: &MarketplaceCache, key: &str, compute: F) -> Result<T, AppError>
+where
+    T: Serialize + DeserializeOwned,
+    F: Future<Output = Result<T, AppError>>,
+{
+    if let Ok(Some(report)) = cache.get_report(key).await {
+        return Ok(report);
+    }
+
+    let report = compute.await?;
+    let _ = cache.cache_report(key, &report, cache_ttl::ANALYTICS).await;
+
+    Ok(report)
+}
+
+fn rate(count: i64, total: i64) -> f64 {
+    if total > 0 {
+        count as f64 / total as f64
+    } else {
+        0.0
+    }
+}
+
+fn ratio_of(part: &BigDecimal, whole: &BigDecimal) -> f64 {
+    (part / whole).to_string().parse().unwrap_or(0.0)
+}
@@ -0,0 +1,325 @@
+use crate::auth::AuthUser;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::marketplace::jobs::Job;
+use crate::models::marketplace::{AccountRestriction, RestrictedAction};
+use async_trait::async_trait;
+use axum::http::HeaderMap;
+use chrono::Utc;
+use sqlx::{PgPool, Postgres, QueryBuilder, Row};
+use std::net::IpAddr;
+use uuid::Uuid;
+
+// One account creating listings from this many addresses within the window is suspicious
+const LISTING_IP_THRESHOLD: i64 = 5;
+const LISTING_IP_WINDOW_HOURS: i32 = 1;
+const LISTING_RESTRICTION_HOURS: i32 = 24;
+
+// Buyers disputing more than this share of their recent purchases
+const DISPUTE_RATE_THRESHOLD: f64 = 0.3;
+const DISPUTE_MIN_PURCHASES: i64 = 5;
+const DISPUTE_WINDOW_DAYS: i32 = 30;
+const DISPUTE_RESTRICTION_HOURS: i32 = 7 * 24;
+
+// Buyers with a chargeback cannot buy until an admin has reviewed them, or this passes
+const CHARGEBACK_RESTRICTION_HOURS: i32 = 90 * 24;
+const CHARGEBACK_LOOKBACK_HOURS: i32 = 24;
+
+// Activity older than this no longer feeds any rule
+const ACTIVITY_RETENTION_DAYS: i32 = 7;
+
+pub mod rules {
+    pub const LISTING_IP_VELOCITY: &str = "listing_ip_velocity";
+    pub const DISPUTE_RATE: &str = "dispute_rate";
+    pub const CHARGEBACK: &str = "chargeback";
+}
+
+/// Detects abnormal account behaviour that per-action rate limits miss and applies
+/// temporary restrictions. A restriction lifted by an admin is not re-applied by the
+/// same rule until its lookback window has passed.
+pub struct AnomalyDetector {
+    pool: PgPool,
+}
+
+impl AnomalyDetector {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Fail with `RateLimited` while the account has an active restriction for `action`
+    pub async fn ensure_allowed(&self, user_id: &str, action: RestrictedAction) -> Result<(), AppError> {
+        let restriction = sqlx::query(
+            r#"
+            SELECT reason, expires_at FROM marketplace_account_restrictions
+            WHERE user_id = $1 AND action = $2 AND lifted_at IS NULL AND expires_at > CURRENT_TIMESTAMP
+            ORDER BY expires_at DESC
+            LIMIT 1
+            "#
+        )
+        .bind(user_id)
+        .bind(action)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match restriction {
+            Some(row) => {
+                let reason: String = row.get("reason");
+                let expires_at: chrono::DateTime<Utc> = row.get("expires_at");
+                Err(AppError::RateLimited {
+                    message: format!("Account temporarily restricted: {}", reason),
+                    retry_after: (expires_at - Utc::now()).num_seconds().max(0) as u64,
+                })
+            }
+            None => Ok(()),
+        }
+    }
+
+    pub async fn record_activity(
+        &self,
+        user_id: &str,
+        action: RestrictedAction,
+        ip_address: Option<&str>,
+    ) -> Result<(), AppError> {
+        sqlx::query(
+            "INSERT INTO marketplace_account_activity (user_id, action, ip_address) VALUES ($1, $2, $3)"
+        )
+        .bind(user_id)
+        .bind(action)
+        .bind(ip_address)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Restrict listing creation when recent listings came from too many addresses
+    pub async fn evaluate_listing_velocity(&self, user_id: &str) -> Result<Option<AccountRestriction>, AppError> {
+        let distinct_ips: i64 = sqlx::query(
+            r#"
+            SELECT COUNT(DISTINCT ip_address) as distinct_ips
+            FROM marketplace_account_activity
+            WHERE user_id = $1 AND action = $2 AND ip_address IS NOT NULL
+            AND created_at > CURRENT_TIMESTAMP - make_interval(hours => $3)
+            "#
+        )
+        .bind(user_id)
+        .bind(RestrictedAction::CreateListing)
+        .bind(LISTING_IP_WINDOW_HOURS)
+        .fetch_one(&self.pool)
+        .await?
+        .get("distinct_ips");
+
+        if distinct_ips < LISTING_IP_THRESHOLD {
+            return Ok(None);
+        }
+
+        self.restrict(
+            user_id,
+            RestrictedAction::CreateListing,
+            rules::LISTING_IP_VELOCITY,
+            &format!("listings created from {} addresses within an hour", distinct_ips),
+            LISTING_RESTRICTION_HOURS,
+            LISTING_IP_WINDOW_HOURS,
+        )
+        .await
+    }
+
+    /// Restrict disputes and purchases for buyers who dispute a large share of their purchases
+    pub async fn evaluate_dispute_rate(&self, buyer_id: &str) -> Result<Vec<AccountRestriction>, AppError> {
+        let row = sqlx::query(
+            r#"
+            SELECT
+                COUNT(*) as purchases,
+                COUNT(*) FILTER (WHERE dispute_reason IS NOT NULL) as disputes
+            FROM marketplace_transactions
+            WHERE buyer_id = $1
+            AND created_at > CURRENT_TIMESTAMP - make_interval(days => $2)
+            "#
+        )
+        .bind(buyer_id)
+        .bind(DISPUTE_WINDOW_DAYS)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let purchases: i64 = row.get("purchases");
+        let disputes: i64 = row.get("disputes");
+
+        if purchases < DISPUTE_MIN_PURCHASES || (disputes as f64 / purchases as f64) <= DISPUTE_RATE_THRESHOLD {
+            return Ok(Vec::new());
+        }
+
+        let reason = format!("{} of {} recent purchases disputed", disputes, purchases);
+        let mut applied = Vec::new();
+        for action in [RestrictedAction::OpenDispute, RestrictedAction::CreateTransaction] {
+            if let Some(restriction) = self
+                .restrict(
+                    buyer_id,
+                    action,
+                    rules::DISPUTE_RATE,
+                    &reason,
+                    DISPUTE_RESTRICTION_HOURS,
+                    DISPUTE_WINDOW_DAYS * 24,
+                )
+                .await?
+            {
+                applied.push(restriction);
+            }
+        }
+
+        Ok(applied)
+    }
+
+    /// Hold the purchases of a buyer whose payment was charged back until an admin reviews
+    /// them; lifting the restriction clears the buyer
+    pub async fn flag_chargeback(&self, buyer_id: &str, reason: &str) -> Result<Option<AccountRestriction>, AppError> {
+        self.restrict(
+            buyer_id,
+            RestrictedAction::CreateTransaction,
+            rules::CHARGEBACK,
+            &format!("payment charged back: {}", reason),
+            CHARGEBACK_RESTRICTION_HOURS,
+            CHARGEBACK_LOOKBACK_HOURS,
+        )
+        .await
+    }
+
+    // Apply a restriction unless one is already active or an admin lifted one within the lookback window
+    async fn restrict(
+        &self,
+        user_id: &str,
+        action: RestrictedAction,
+        rule: &str,
+        reason: &str,
+        duration_hours: i32,
+        lookback_hours: i32,
+    ) -> Result<Option<AccountRestriction>, AppError> {
+        let restriction = sqlx::query_as::<_, AccountRestriction>(
+            r#"
+            INSERT INTO marketplace_account_restrictions (id, user_id, action, rule, reason, created_at, expires_at)
+            SELECT $1, $2, $3, $4, $5, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP + make_interval(hours => $6)
+            WHERE NOT EXISTS (
+                SELECT 1 FROM marketplace_account_restrictions
+                WHERE user_id = $2 AND action = $3
+                AND (
+                    (lifted_at IS NULL AND expires_at > CURRENT_TIMESTAMP)
+                    OR (rule = $4 AND lifted_at > CURRENT_TIMESTAMP - make_interval(hours => $7))
+                )
+            )
+            RETURNING *
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(action)
+        .bind(rule)
+        .bind(reason)
+        .bind(duration_hours)
+        .bind(lookback_hours)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if let Some(restriction) = &restriction {
+            tracing::warn!(
+                user_id = %restriction.user_id,
+                action = restriction.action.as_str(),
+                rule = %restriction.rule,
+                "account restricted"
+            );
+        }
+
+        Ok(restriction)
+    }
+
+    pub async fn list_restrictions(
+        &self,
+        user_id: Option<&str>,
+        active_only: bool,
+    ) -> Result<Vec<AccountRestriction>, AppError> {
+        let mut query = QueryBuilder::<Postgres>::new("SELECT * FROM marketplace_account_restrictions WHERE 1=1");
+
+        if let Some(user_id) = user_id {
+            query.push(" AND user_id = ").push_bind(user_id.to_string());
+        }
+        if active_only {
+            query.push(" AND lifted_at IS NULL AND expires_at > CURRENT_TIMESTAMP");
+        }
+        query.push(" ORDER BY created_at DESC LIMIT 200");
+
+        let restrictions = query
+            .build_query_as::<AccountRestriction>()
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(restrictions)
+    }
+
+    /// Admin override: lift a restriction before it expires
+    pub async fn lift_restriction(
+        &self,
+        auth_user: &AuthUser,
+        restriction_id: Uuid,
+    ) -> Result<AccountRestriction, AppError> {
+        sqlx::query_as::<_, AccountRestriction>(
+            r#"
+            UPDATE marketplace_account_restrictions
+            SET lifted_at = CURRENT_TIMESTAMP, lifted_by = $2
+            WHERE id = $1 AND lifted_at IS NULL
+            RETURNING *
+            "#
+        )
+        .bind(restriction_id)
+        .bind(&auth_user.0.auth0_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Active restriction not found".to_string()))
+    }
+
+    pub async fn purge_activity(&self) -> Result<u64, AppError> {
+        let result = sqlx::query(
+            "DELETE FROM marketplace_account_activity WHERE created_at < CURRENT_TIMESTAMP - make_interval(days => $1)"
+        )
+        .bind(ACTIVITY_RETENTION_DAYS)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+/// Client address from X-Forwarded-For. Clients can send the header themselves, so only the
+/// entries appended by our own TRUSTED_PROXY_HOPS proxies count: the client is the address the
+/// outermost of them saw, that many entries from the right.
+pub fn client_ip(headers: &HeaderMap) -> Option<String> {
+    forwarded_client_ip(headers, Config::get().trusted_proxy_hops)
+}
+
+fn forwarded_client_ip(headers: &HeaderMap, trusted_hops: usize) -> Option<String> {
+    if trusted_hops == 0 {
+        return None;
+    }
+
+    let entries: Vec<&str> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .collect();
+
+    let index = entries.len().checked_sub(trusted_hops)?;
+    entries[index].trim().parse::<IpAddr>().ok().map(|ip| ip.to_string())
+}
+
+/// Drops account activity that no rule looks at anymore
+pub struct ActivityPurgeJob;
+
+#[async_trait]
+impl Job for ActivityPurgeJob {
+    fn name(&self) -> &'static str {
+        "account_activity_purge"
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<(), AppError> {
+        AnomalyDetector::new(pool.clone()).purge_activity().await?;
+        Ok(())
+    }
+}
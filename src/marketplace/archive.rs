@@ -0,0 +1,170 @@
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::marketplace::jobs::Job;
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{ArchivedListingFilters, MarketplaceListing, PaginatedResponse};
+use async_trait::async_trait;
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+// Sold, expired and deleted listings move to the archive once untouched this long
+pub const LISTING_ARCHIVE_AFTER_MONTHS: i32 = 6;
+
+// Listings moved per statement, so each run holds locks briefly
+const ARCHIVE_BATCH_SIZE: i64 = 1000;
+
+/// Moves listings that are no longer for sale out of `marketplace_listings` into
+/// `marketplace_listings_archive`. Transactions, coupon codes and other history keep
+/// their listing ids and resolve them through the `marketplace_listings_all` view.
+pub struct ListingArchiveService {
+    pool: PgPool,
+}
+
+impl ListingArchiveService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Archive listings past `after_months` that nothing open still depends on
+    pub async fn archive_listings(&self, after_months: i32) -> Result<u64, AppError> {
+        let mut archived = 0;
+
+        loop {
+            let moved = sqlx::query(
+                r#"
+                WITH candidates AS (
+                    SELECT l.id FROM marketplace_listings l
+                    WHERE (l.status IN ('sold', 'expired') OR l.deleted_at IS NOT NULL)
+                    AND l.updated_at < CURRENT_TIMESTAMP - make_interval(months => $1)
+                    AND NOT EXISTS (
+                        SELECT 1 FROM marketplace_transactions t
+                        WHERE t.listing_id = l.id AND t.status IN ('pending', 'escrow', 'disputed')
+                    )
+                    AND NOT EXISTS (
+                        SELECT 1 FROM marketplace_promotions p
+                        WHERE p.listing_id = l.id AND p.status IN ('pending_payment', 'scheduled', 'active')
+                    )
+                    AND NOT EXISTS (
+                        SELECT 1 FROM marketplace_bundle_items bi
+                        JOIN marketplace_bundles b ON b.id = bi.bundle_id
+                        WHERE bi.listing_id = l.id AND b.status = 'active'
+                    )
+                    ORDER BY l.updated_at
+                    LIMIT $2
+                    FOR UPDATE SKIP LOCKED
+                ),
+                moved AS (
+                    DELETE FROM marketplace_listings l
+                    USING candidates c
+                    WHERE l.id = c.id
+                    RETURNING l.*
+                )
+                INSERT INTO marketplace_listings_archive SELECT * FROM moved
+                "#
+            )
+            .bind(after_months)
+            .bind(ARCHIVE_BATCH_SIZE)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+            archived += moved;
+            if moved < ARCHIVE_BATCH_SIZE as u64 {
+                break;
+            }
+        }
+
+        if archived > 0 {
+            tracing::info!(archived, "archived listings");
+        }
+        Ok(archived)
+    }
+
+    /// The seller's archived listings, most recently changed first
+    pub async fn seller_listings(
+        &self,
+        auth_user: &AuthUser,
+        filters: ArchivedListingFilters,
+    ) -> Result<PaginatedResponse<MarketplaceListing>, AppError> {
+        let limit = filters.limit.unwrap_or(20).clamp(1, 100);
+        let page = filters.page.unwrap_or(0).max(0);
+        let offset = page
+            .checked_mul(limit)
+            .ok_or_else(|| AppError::BadRequest("page is out of range".to_string()))?;
+
+        let total_count: i64 = sqlx::query(
+            "SELECT COUNT(*) as total_count FROM marketplace_listings_archive WHERE seller_id = $1"
+        )
+        .bind(&auth_user.0.auth0_id)
+        .fetch_one(&self.pool)
+        .await?
+        .get("total_count");
+
+        let listings = sqlx::query_as::<_, MarketplaceListing>(
+            r#"
+            SELECT * FROM marketplace_listings_archive
+            WHERE seller_id = $1
+            ORDER BY updated_at DESC
+            LIMIT $2 OFFSET $3
+            "#
+        )
+        .bind(&auth_user.0.auth0_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(PaginatedResponse {
+            has_more: offset + (listings.len() as i64) < total_count,
+            items: listings,
+            total_count,
+            page,
+            limit,
+        })
+    }
+
+    /// An archived listing, visible to its seller, its buyers and admins
+    pub async fn get_listing(&self, auth_user: &AuthUser, listing_id: Uuid) -> Result<MarketplaceListing, AppError> {
+        let user_id = &auth_user.0.auth0_id;
+
+        let listing = sqlx::query_as::<_, MarketplaceListing>(
+            "SELECT * FROM marketplace_listings_archive WHERE id = $1"
+        )
+        .bind(listing_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Archived listing not found".to_string()))?;
+
+        if listing.seller_id == *user_id {
+            return Ok(listing);
+        }
+
+        let bought = sqlx::query("SELECT 1 FROM marketplace_transactions WHERE listing_id = $1 AND buyer_id = $2 LIMIT 1")
+            .bind(listing_id)
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .is_some();
+        if !bought && !MarketplaceService::new(self.pool.clone()).is_admin(user_id).await? {
+            return Err(AppError::NotFound("Archived listing not found".to_string()));
+        }
+        Ok(listing)
+    }
+}
+
+/// Moves old sold, expired and deleted listings to the archive
+pub struct ListingArchiveJob;
+
+#[async_trait]
+impl Job for ListingArchiveJob {
+    fn name(&self) -> &'static str {
+        "listing_archive"
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<(), AppError> {
+        ListingArchiveService::new(pool.clone())
+            .archive_listings(LISTING_ARCHIVE_AFTER_MONTHS)
+            .await?;
+        Ok(())
+    }
+}
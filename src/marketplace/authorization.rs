@@ -0,0 +1,103 @@
+use crate::error::AppError;
+use crate::marketplace::repository::{Repositories, RoleRepository};
+use crate::models::marketplace::MarketplaceTransaction;
+use sqlx::PgPool;
+use std::sync::Arc;
+
+/// Who may edit listings, view transactions and resolve disputes, by the caller's user id;
+/// each rule refuses with 403
+pub struct AuthorizationPolicy {
+    roles: Arc<dyn RoleRepository>,
+}
+
+impl AuthorizationPolicy {
+    pub fn new(pool: PgPool) -> Self {
+        Self::with_roles(Repositories::postgres(pool).roles)
+    }
+
+    /// Look roles up in the given repository, e.g. an in-memory store when testing without a database
+    pub fn with_roles(roles: Arc<dyn RoleRepository>) -> Self {
+        Self { roles }
+    }
+
+    /// Only the seller edits, deletes, translates or promotes a listing; admins moderate
+    /// listings through the admin endpoints instead
+    pub fn can_edit_listing(&self, user_id: &str, seller_id: &str) -> Result<(), AppError> {
+        if user_id != seller_id {
+            return Err(AppError::Forbidden("You can only change your own listings".to_string()));
+        }
+        Ok(())
+    }
+
+    /// The buyer, the seller and admins see a transaction and everything attached to it
+    pub async fn can_view_transaction(
+        &self,
+        user_id: &str,
+        transaction: &MarketplaceTransaction,
+    ) -> Result<(), AppError> {
+        // Parties are the common case, so the role lookup only runs for everyone else
+        if is_party(user_id, transaction) || self.roles.is_admin(user_id).await? {
+            return Ok(());
+        }
+        Err(AppError::Forbidden("You are not part of this transaction".to_string()))
+    }
+
+    /// Disputes and purchase protection claims are decided by admins, never by either party
+    pub async fn can_resolve_dispute(&self, user_id: &str) -> Result<(), AppError> {
+        if !self.roles.is_admin(user_id).await? {
+            return Err(AppError::Forbidden("Only an admin can resolve disputes".to_string()));
+        }
+        Ok(())
+    }
+}
+
+fn is_party(user_id: &str, transaction: &MarketplaceTransaction) -> bool {
+    transaction.buyer_id == user_id || transaction.seller_id == user_id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::marketplace::repository::fixtures::transaction;
+    use crate::marketplace::repository::InMemoryRepository;
+    use crate::models::marketplace::TransactionStatus;
+
+    fn policy() -> AuthorizationPolicy {
+        let store = InMemoryRepository::new();
+        store.admins.lock().unwrap().insert("admin".to_string());
+        AuthorizationPolicy::with_roles(Arc::new(store))
+    }
+
+    #[test]
+    fn only_the_seller_edits_a_listing() {
+        let policy = policy();
+
+        assert!(policy.can_edit_listing("seller", "seller").is_ok());
+        assert!(matches!(policy.can_edit_listing("admin", "seller"), Err(AppError::Forbidden(_))));
+        assert!(matches!(policy.can_edit_listing("stranger", "seller"), Err(AppError::Forbidden(_))));
+    }
+
+    #[tokio::test]
+    async fn parties_and_admins_view_a_transaction() {
+        let policy = policy();
+        let transaction = transaction(TransactionStatus::Escrow);
+
+        assert!(policy.can_view_transaction("buyer", &transaction).await.is_ok());
+        assert!(policy.can_view_transaction("seller", &transaction).await.is_ok());
+        assert!(policy.can_view_transaction("admin", &transaction).await.is_ok());
+        assert!(matches!(
+            policy.can_view_transaction("stranger", &transaction).await,
+            Err(AppError::Forbidden(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn only_admins_resolve_disputes() {
+        let policy = policy();
+
+        assert!(policy.can_resolve_dispute("admin").await.is_ok());
+        for user_id in ["buyer", "seller", "stranger"] {
+            assert!(matches!(policy.can_resolve_dispute(user_id).await, Err(AppError::Forbidden(_))), "{}", user_id);
+        }
+    }
+}
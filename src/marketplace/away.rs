@@ -0,0 +1,243 @@
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::marketplace::jobs::Job;
+use crate::marketplace::outbox::{event_types, OutboxService};
+use crate::marketplace::replica;
+use crate::marketplace::transaction_state::{TransactionEvent, TransactionStateMachine};
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{
+    AccountDeactivated, AwayMode, MarketplaceTransaction, SellerAway, VacationModeRequest,
+};
+use async_trait::async_trait;
+use sqlx::{PgExecutor, PgPool};
+
+/// Longest vacation that can be booked at once; it can be extended before it ends
+pub const MAX_VACATION_DAYS: i64 = 180;
+
+/// True when the seller of listing `l` is neither on vacation nor deactivated. Everything
+/// that shows listings to anyone but their seller filters on it, next to `NOT_SHADOW_BANNED`.
+pub const SELLER_NOT_AWAY: &str = r#"NOT EXISTS (
+    SELECT 1 FROM marketplace_seller_away sa WHERE sa.user_id = l.seller_id
+)"#;
+
+const AWAY_COLUMNS: &str = "mode, message, reactivate_at, started_at";
+
+const DEACTIVATION_REASON: &str = "The other party deactivated their account";
+
+/// Vacation mode and account deactivation. Either hides the user's active listings from
+/// everyone else without touching them, so they come back as they were, and declines new
+/// purchases from the user with their away message. Vacations end by themselves on the
+/// date the seller gave; a deactivated account stays away until its owner returns, and
+/// deactivating also cancels the user's pending purchases and sales. Transactions already
+/// in escrow or disputed carry on, so money in flight still settles.
+pub struct AwayService {
+    pool: PgPool,
+}
+
+impl AwayService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn get(&self, auth_user: &AuthUser) -> Result<SellerAway, AppError> {
+        sqlx::query_as::<_, SellerAway>(&format!(
+            "SELECT {} FROM marketplace_seller_away WHERE user_id = $1",
+            AWAY_COLUMNS
+        ))
+        .bind(&auth_user.0.auth0_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("You are not on vacation or deactivated".to_string()))
+    }
+
+    /// Start a vacation, or change the message or return date of the current one
+    pub async fn start_vacation(&self, auth_user: &AuthUser, request: VacationModeRequest) -> Result<SellerAway, AppError> {
+        let user_id = &auth_user.0.auth0_id;
+
+        let away = sqlx::query_as::<_, SellerAway>(&format!(
+            r#"
+            INSERT INTO marketplace_seller_away (user_id, mode, message, reactivate_at, started_at)
+            VALUES ($1, $2, $3, $4, CURRENT_TIMESTAMP)
+            ON CONFLICT (user_id) DO UPDATE SET
+                message = EXCLUDED.message,
+                reactivate_at = EXCLUDED.reactivate_at
+            WHERE marketplace_seller_away.mode = $2
+            RETURNING {}
+            "#,
+            AWAY_COLUMNS
+        ))
+        .bind(user_id)
+        .bind(AwayMode::Vacation)
+        .bind(request.message.as_deref().map(str::trim))
+        .bind(request.reactivate_at)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::Conflict("Your account is deactivated; reactivate it first".to_string()))?;
+        replica::user_written(user_id);
+
+        tracing::info!(user_id, reactivate_at = ?away.reactivate_at, "vacation mode on");
+        Ok(away)
+    }
+
+    /// Deactivate the account, ending any vacation, and cancel its pending transactions
+    pub async fn deactivate(&self, auth_user: &AuthUser) -> Result<AccountDeactivated, AppError> {
+        let user_id = &auth_user.0.auth0_id;
+        let mut tx = self.pool.begin().await?;
+
+        let away = sqlx::query_as::<_, SellerAway>(&format!(
+            r#"
+            INSERT INTO marketplace_seller_away (user_id, mode, message, reactivate_at, started_at)
+            VALUES ($1, $2, NULL, NULL, CURRENT_TIMESTAMP)
+            ON CONFLICT (user_id) DO UPDATE SET
+                mode = EXCLUDED.mode,
+                message = NULL,
+                reactivate_at = NULL,
+                started_at = EXCLUDED.started_at
+            WHERE marketplace_seller_away.mode <> EXCLUDED.mode
+            RETURNING {}
+            "#,
+            AWAY_COLUMNS
+        ))
+        .bind(user_id)
+        .bind(AwayMode::Deactivated)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::Conflict("Your account is already deactivated".to_string()))?;
+
+        // Escrowed and disputed transactions are left to settle
+        let pending = sqlx::query_as::<_, MarketplaceTransaction>(
+            r#"
+            SELECT * FROM marketplace_transactions
+            WHERE (buyer_id = $1 OR seller_id = $1) AND status = 'pending'
+            ORDER BY created_at
+            FOR UPDATE
+            "#
+        )
+        .bind(user_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for transaction in &pending {
+            let updated = TransactionStateMachine::apply(
+                &mut tx,
+                transaction,
+                TransactionEvent::Cancelled,
+                user_id,
+                Some(DEACTIVATION_REASON),
+            ).await?;
+
+            if MarketplaceService::release_coupon_code(&mut tx, transaction).await? {
+                MarketplaceService::restock_listing(&mut tx, transaction.listing_id).await?;
+            }
+            OutboxService::record(&mut tx, "transaction", transaction.id, event_types::TRANSACTION_CANCELLED, &updated).await?;
+        }
+        tx.commit().await?;
+        replica::user_written(user_id);
+
+        let marketplace = MarketplaceService::new(self.pool.clone());
+        for transaction in &pending {
+            let counterparty = if transaction.buyer_id == *user_id {
+                &transaction.seller_id
+            } else {
+                &transaction.buyer_id
+            };
+            marketplace.create_notification(
+                counterparty,
+                "transaction_cancelled",
+                "Transaction Cancelled",
+                DEACTIVATION_REASON,
+                Some(transaction.listing_id),
+                Some(transaction.id),
+            ).await;
+        }
+
+        tracing::info!(user_id, cancelled = pending.len(), "account deactivated");
+        Ok(AccountDeactivated {
+            away,
+            cancelled_transaction_ids: pending.iter().map(|transaction| transaction.id).collect(),
+        })
+    }
+
+    /// End a vacation early or reactivate a deactivated account; listings show again at once
+    pub async fn reactivate(&self, auth_user: &AuthUser) -> Result<(), AppError> {
+        let user_id = &auth_user.0.auth0_id;
+
+        let result = sqlx::query("DELETE FROM marketplace_seller_away WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("You are not on vacation or deactivated".to_string()));
+        }
+        replica::user_written(user_id);
+
+        tracing::info!(user_id, "seller back from away");
+        Ok(())
+    }
+
+    /// End vacations whose return date has passed. Returns how many sellers are back.
+    pub async fn reactivate_due(&self) -> Result<usize, AppError> {
+        let returned: Vec<String> = sqlx::query_scalar(
+            r#"
+            DELETE FROM marketplace_seller_away
+            WHERE mode = 'vacation' AND reactivate_at <= CURRENT_TIMESTAMP
+            RETURNING user_id
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let marketplace = MarketplaceService::new(self.pool.clone());
+        for user_id in &returned {
+            marketplace.create_notification(
+                user_id,
+                "vacation_ended",
+                "Welcome back",
+                "Your vacation has ended and your listings are visible again",
+                None,
+                None,
+            ).await;
+        }
+
+        Ok(returned.len())
+    }
+}
+
+/// Refuse a purchase from a seller who is on vacation or deactivated, with their message
+pub async fn ensure_selling<'e>(executor: impl PgExecutor<'e>, seller_id: &str) -> Result<(), AppError> {
+    let away = sqlx::query_as::<_, SellerAway>(&format!(
+        "SELECT {} FROM marketplace_seller_away WHERE user_id = $1",
+        AWAY_COLUMNS
+    ))
+    .bind(seller_id)
+    .fetch_optional(executor)
+    .await?;
+
+    let Some(away) = away else {
+        return Ok(());
+    };
+    let message = match (away.mode, away.reactivate_at) {
+        (AwayMode::Vacation, Some(reactivate_at)) => format!(
+            "The seller is on vacation until {}{}",
+            reactivate_at.format("%Y-%m-%d"),
+            away.message.map(|message| format!(": {}", message)).unwrap_or_default()
+        ),
+        _ => "The seller's account is deactivated".to_string(),
+    };
+    Err(AppError::Conflict(message))
+}
+
+/// Ends vacations on their return date
+pub struct SellerReactivationJob;
+
+#[async_trait]
+impl Job for SellerReactivationJob {
+    fn name(&self) -> &'static str {
+        "seller_reactivation"
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<(), AppError> {
+        AwayService::new(pool.clone()).reactivate_due().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,185 @@
+use crate::error::AppError;
+use crate::marketplace::jobs::Job;
+use crate::models::marketplace::{BadgeType, SellerBadge, TrustScoreSnapshot};
+use async_trait::async_trait;
+use sqlx::{PgPool, Row};
+
+// Badge thresholds
+const POWER_SELLER_MIN_SALES: i64 = 25;
+const POWER_SELLER_MIN_TRUST: f64 = 80.0;
+const HUNDRED_PLUS_SALES: i64 = 100;
+const TOP_RATED_MIN_RATING: f64 = 4.7;
+const TOP_RATED_MIN_REVIEWS: i32 = 10;
+const FAST_RESPONDER_MAX_HOURS: f64 = 24.0;
+const FAST_RESPONDER_MIN_SALES: i64 = 5;
+
+pub struct BadgeService {
+    pool: PgPool,
+}
+
+impl BadgeService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Record a snapshot of every seller's current trust score
+    pub async fn snapshot_trust_scores(&self) -> Result<u64, AppError> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO marketplace_trust_score_history (
+                user_id, trust_score, total_transactions, successful_transactions,
+                average_rating, total_reviews, recorded_at
+            )
+            SELECT
+                user_id, trust_score, total_transactions, successful_transactions,
+                average_rating, total_reviews, CURRENT_TIMESTAMP
+            FROM marketplace_trust_scores
+            "#
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Recompute badges for all sellers from trust scores and transaction stats
+    pub async fn recompute_badges(&self) -> Result<(), AppError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT
+                ts.user_id,
+                ts.trust_score,
+                ts.average_rating,
+                ts.total_reviews,
+                ts.verified_seller,
+                COUNT(t.id) FILTER (WHERE t.status = 'completed') as completed_sales,
+                AVG(EXTRACT(EPOCH FROM (t.completed_at - t.created_at)) / 3600.0)
+                    FILTER (WHERE t.status = 'completed') as avg_completion_hours
+            FROM marketplace_trust_scores ts
+            LEFT JOIN marketplace_transactions t ON t.seller_id = ts.user_id
+            GROUP BY ts.user_id, ts.trust_score, ts.average_rating, ts.total_reviews, ts.verified_seller
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        for row in rows {
+            let user_id: String = row.get("user_id");
+            let trust_score: f64 = row.get("trust_score");
+            let average_rating: f64 = row.get("average_rating");
+            let total_reviews: i32 = row.get("total_reviews");
+            let verified_seller: bool = row.get("verified_seller");
+            let completed_sales: i64 = row.get("completed_sales");
+            let avg_completion_hours: Option<f64> = row.get("avg_completion_hours");
+
+            let mut badges = Vec::new();
+
+            if completed_sales >= POWER_SELLER_MIN_SALES && trust_score >= POWER_SELLER_MIN_TRUST {
+                badges.push(BadgeType::PowerSeller);
+            }
+
+            if completed_sales >= HUNDRED_PLUS_SALES {
+                badges.push(BadgeType::HundredPlusSales);
+            }
+
+            if total_reviews >= TOP_RATED_MIN_REVIEWS && average_rating >= TOP_RATED_MIN_RATING {
+                badges.push(BadgeType::TopRated);
+            }
+
+            if completed_sales >= FAST_RESPONDER_MIN_SALES
+                && avg_completion_hours.is_some_and(|hours| hours <= FAST_RESPONDER_MAX_HOURS)
+            {
+                badges.push(BadgeType::FastResponder);
+            }
+
+            if verified_seller {
+                badges.push(BadgeType::VerifiedSeller);
+            }
+
+            self.replace_badges(&user_id, &badges).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Replace a user's badges, keeping the original award date for badges they already hold
+    async fn replace_badges(&self, user_id: &str, badges: &[BadgeType]) -> Result<(), AppError> {
+        let badge_names: Vec<&str> = badges.iter().map(|b| b.as_str()).collect();
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            "DELETE FROM marketplace_seller_badges WHERE user_id = $1 AND NOT (badge = ANY($2))"
+        )
+        .bind(user_id)
+        .bind(&badge_names)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_seller_badges (user_id, badge, awarded_at)
+            SELECT $1, UNNEST($2::text[]), CURRENT_TIMESTAMP
+            ON CONFLICT (user_id, badge) DO NOTHING
+            "#
+        )
+        .bind(user_id)
+        .bind(&badge_names)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Get badges currently held by a user
+    pub async fn get_badges(&self, user_id: &str) -> Result<Vec<SellerBadge>, AppError> {
+        let badges = sqlx::query_as::<_, SellerBadge>(
+            "SELECT badge, awarded_at FROM marketplace_seller_badges WHERE user_id = $1 ORDER BY awarded_at"
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(badges)
+    }
+
+    /// Get trust score history for a user, most recent first
+    pub async fn get_history(
+        &self,
+        user_id: &str,
+        limit: i64,
+    ) -> Result<Vec<TrustScoreSnapshot>, AppError> {
+        let history = sqlx::query_as::<_, TrustScoreSnapshot>(
+            r#"
+            SELECT * FROM marketplace_trust_score_history
+            WHERE user_id = $1
+            ORDER BY recorded_at DESC
+            LIMIT $2
+            "#
+        )
+        .bind(user_id)
+        .bind(limit.clamp(1, 365))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(history)
+    }
+}
+
+/// Snapshots trust scores, then recomputes badges from the fresh numbers
+pub struct BadgeJob;
+
+#[async_trait]
+impl Job for BadgeJob {
+    fn name(&self) -> &'static str {
+        "trust_snapshot_and_badges"
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<(), AppError> {
+        let service = BadgeService::new(pool.clone());
+        service.snapshot_trust_scores().await?;
+        service.recompute_badges().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,206 @@
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{BrandLanding, CreateBrandRequest, MarketplaceBrand};
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+// Domain suffixes stripped when matching brand names ("Amazon.com" -> "amazon")
+const DOMAIN_SUFFIXES: &[&str] = &[".com", ".in", ".co.uk", ".co", ".net", ".org", ".io"];
+
+pub struct BrandService {
+    pool: PgPool,
+}
+
+impl BrandService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Normalize a free-text brand name into an alias key
+    pub fn normalize_alias(name: &str) -> String {
+        let mut value = name.trim().to_lowercase();
+
+        for prefix in ["https://", "http://", "www."] {
+            if let Some(stripped) = value.strip_prefix(prefix) {
+                value = stripped.to_string();
+            }
+        }
+
+        for suffix in DOMAIN_SUFFIXES {
+            if let Some(stripped) = value.strip_suffix(suffix) {
+                value = stripped.to_string();
+                break;
+            }
+        }
+
+        value.chars().filter(|c| c.is_alphanumeric()).collect()
+    }
+
+    pub(crate) fn slugify(name: &str) -> String {
+        name.trim()
+            .to_lowercase()
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '-' })
+            .collect::<String>()
+            .split('-')
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("-")
+    }
+
+    /// Resolve a free-text brand name to a registered brand, if any
+    pub async fn resolve(&self, brand_name: &str) -> Result<Option<MarketplaceBrand>, AppError> {
+        let alias = Self::normalize_alias(brand_name);
+        if alias.is_empty() {
+            return Ok(None);
+        }
+
+        let brand = sqlx::query_as::<_, MarketplaceBrand>(
+            r#"
+            SELECT b.* FROM marketplace_brands b
+            JOIN marketplace_brand_aliases a ON a.brand_id = b.id
+            WHERE a.alias = $1
+            "#
+        )
+        .bind(&alias)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(brand)
+    }
+
+    pub async fn list_brands(&self) -> Result<Vec<MarketplaceBrand>, AppError> {
+        let brands = sqlx::query_as::<_, MarketplaceBrand>(
+            "SELECT * FROM marketplace_brands ORDER BY name"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(brands)
+    }
+
+    /// Brand landing page with brand-level stats
+    pub async fn get_landing(&self, slug: &str) -> Result<BrandLanding, AppError> {
+        let brand = sqlx::query_as::<_, MarketplaceBrand>(
+            "SELECT * FROM marketplace_brands WHERE slug = $1"
+        )
+        .bind(slug)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Brand not found".to_string()))?;
+
+        let stats = sqlx::query(
+            r#"
+            SELECT
+                COUNT(*) as active_listings,
+                AVG(discount_percentage)::float8 as average_discount_percentage,
+                MIN(selling_price)::float8 as lowest_price
+            FROM marketplace_listings
+            WHERE brand_id = $1 AND status = 'active' AND deleted_at IS NULL
+            "#
+        )
+        .bind(brand.id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(BrandLanding {
+            brand,
+            active_listings: stats.get("active_listings"),
+            average_discount_percentage: stats.get("average_discount_percentage"),
+            lowest_price: stats.get("lowest_price"),
+        })
+    }
+
+    /// Register a brand with its aliases (admin only)
+    pub async fn create_brand(
+        &self,
+        auth_user: &AuthUser,
+        request: CreateBrandRequest,
+    ) -> Result<MarketplaceBrand, AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+
+        let slug = request
+            .slug
+            .as_deref()
+            .map(Self::slugify)
+            .unwrap_or_else(|| Self::slugify(&request.name));
+
+        if slug.is_empty() {
+            return Err(AppError::BadRequest("Brand name is required".to_string()));
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        let brand = sqlx::query_as::<_, MarketplaceBrand>(
+            r#"
+            INSERT INTO marketplace_brands (id, slug, name, logo_url, website_url, created_at)
+            VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP)
+            RETURNING *
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(&slug)
+        .bind(request.name.trim())
+        .bind(&request.logo_url)
+        .bind(&request.website_url)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let mut aliases: Vec<String> = request
+            .aliases
+            .iter()
+            .chain(std::iter::once(&request.name))
+            .chain(request.website_url.iter())
+            .map(|alias| Self::normalize_alias(alias))
+            .filter(|alias| !alias.is_empty())
+            .collect();
+        aliases.sort();
+        aliases.dedup();
+
+        for alias in aliases {
+            sqlx::query(
+                r#"
+                INSERT INTO marketplace_brand_aliases (alias, brand_id) VALUES ($1, $2)
+                ON CONFLICT (alias) DO NOTHING
+                "#
+            )
+            .bind(&alias)
+            .bind(brand.id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        // Link existing listings whose brand text normalizes to one of the aliases the brand
+        // got, the same way listings are resolved when they are created or edited. The match
+        // is normalize_alias written in SQL, so only the matching listings are touched.
+        sqlx::query(
+            r#"
+            UPDATE marketplace_listings
+            SET brand_id = $1, brand_name = $2
+            WHERE brand_id IS NULL AND brand_name IS NOT NULL
+            AND regexp_replace(
+                regexp_replace(
+                    regexp_replace(LOWER(TRIM(brand_name)), '^(https://)?(http://)?(www\.)?', ''),
+                    $3, ''
+                ),
+                '[^[:alnum:]]', '', 'g'
+            ) IN (SELECT alias FROM marketplace_brand_aliases WHERE brand_id = $1)
+            "#
+        )
+        .bind(brand.id)
+        .bind(&brand.name)
+        .bind(domain_suffix_pattern())
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(brand)
+    }
+}
+
+// The first of DOMAIN_SUFFIXES a name ends with, as a Postgres regular expression
+fn domain_suffix_pattern() -> String {
+    let suffixes: Vec<String> = DOMAIN_SUFFIXES.iter().map(|suffix| suffix.replace('.', "\\.")).collect();
+    format!("({})$", suffixes.join("|"))
+}
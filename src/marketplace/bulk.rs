@@ -0,0 +1,251 @@
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::marketplace::duplicate_detector::DuplicateDetector;
+use crate::marketplace::rate_limiter::{ActionType, RateLimiter};
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{
+    BulkCreateListingResponse, BulkRowError, CreateListingRequest, ListingDetails, ListingType,
+};
+use crate::validation::Validate;
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::collections::HashSet;
+
+// Maximum rows accepted in a single bulk request
+pub const MAX_BULK_ROWS: usize = 100;
+
+// Duplicate matches at or above this confidence reject the row
+const DUPLICATE_CONFIDENCE_THRESHOLD: u8 = 80;
+
+/// A single CSV row; tags are separated by semicolons and `details` holds the JSON object
+/// of the listing type's details, e.g. `{"merchant":"Amazon","balance":"50"}` for a gift card
+#[derive(Debug, Deserialize)]
+struct CsvListingRow {
+    listing_type: ListingType,
+    title: String,
+    description: Option<String>,
+    category: String,
+    brand_name: Option<String>,
+    original_value: Option<BigDecimal>,
+    selling_price: BigDecimal,
+    expiration_date: Option<DateTime<Utc>>,
+    proof_image_url: Option<String>,
+    tags: Option<String>,
+    coupon_code: Option<String>,
+    #[serde(default)]
+    language: Option<String>,
+    #[serde(default)]
+    details: Option<String>,
+}
+
+impl TryFrom<CsvListingRow> for CreateListingRequest {
+    type Error = String;
+
+    fn try_from(row: CsvListingRow) -> Result<Self, Self::Error> {
+        let details = row
+            .details
+            .as_deref()
+            .filter(|details| !details.is_empty())
+            .map(serde_json::from_str::<serde_json::Value>)
+            .transpose()
+            .map_err(|e| format!("Invalid details JSON: {}", e))?;
+
+        Ok(CreateListingRequest {
+            listing_type: row.listing_type,
+            title: row.title,
+            description: row.description,
+            category: row.category,
+            brand_name: row.brand_name,
+            original_value: row.original_value,
+            selling_price: row.selling_price,
+            discount_percentage: None,
+            expiration_date: row.expiration_date,
+            proof_image_url: row.proof_image_url,
+            tags: row
+                .tags
+                .map(|tags| {
+                    tags.split(';')
+                        .map(|tag| tag.trim().to_string())
+                        .filter(|tag| !tag.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            coupon_code: row.coupon_code,
+            coupon_codes: vec![],
+            quantity: None,
+            details,
+            language: row.language,
+        })
+    }
+}
+
+pub struct BulkListingService {
+    pool: PgPool,
+}
+
+impl BulkListingService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Parse a CSV upload into listing requests, collecting per-row parse errors
+    pub fn parse_csv(data: &[u8]) -> (Vec<(usize, CreateListingRequest)>, Vec<BulkRowError>) {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(data);
+
+        let mut rows = Vec::new();
+        let mut errors = Vec::new();
+
+        for (index, record) in reader.deserialize::<CsvListingRow>().enumerate() {
+            match record.map_err(|e| format!("Invalid CSV row: {}", e)).and_then(CreateListingRequest::try_from) {
+                Ok(request) => rows.push((index, request)),
+                Err(error) => errors.push(BulkRowError { row: index, error }),
+            }
+        }
+
+        (rows, errors)
+    }
+
+    /// Create many listings at once with per-row validation and duplicate detection
+    pub async fn create_listings(
+        &self,
+        auth_user: &AuthUser,
+        rows: Vec<(usize, CreateListingRequest)>,
+        mut errors: Vec<BulkRowError>,
+    ) -> Result<BulkCreateListingResponse, AppError> {
+        if rows.len() + errors.len() > MAX_BULK_ROWS {
+            return Err(AppError::BadRequest(format!(
+                "Bulk requests are limited to {} rows",
+                MAX_BULK_ROWS
+            )));
+        }
+
+        let seller_id = &auth_user.0.auth0_id;
+        let detector = DuplicateDetector::new(self.pool.clone());
+
+        // Duplicate detection within the batch and against existing listings
+        let mut seen_fingerprints = HashSet::new();
+        let mut accepted = Vec::new();
+
+        for (index, request) in rows {
+            if let Err(field_errors) = request.validate() {
+                errors.push(BulkRowError {
+                    row: index,
+                    error: field_errors
+                        .iter()
+                        .map(|e| format!("{} {}", e.field, e.message))
+                        .collect::<Vec<_>>()
+                        .join("; "),
+                });
+                continue;
+            }
+
+            // Types that need details are rejected here rather than failing at creation
+            let details = match &request.details {
+                Some(details) => ListingDetails::parse(&request.listing_type, details.clone()).map(|_| ()),
+                None if ListingDetails::is_required(&request.listing_type) => {
+                    Err("Details are required for this listing type".to_string())
+                }
+                None => Ok(()),
+            };
+            if let Err(error) = details {
+                errors.push(BulkRowError { row: index, error });
+                continue;
+            }
+
+            if let Some(code) = &request.coupon_code {
+                let fingerprint = DuplicateDetector::generate_fingerprint(
+                    code,
+                    &request.category,
+                    request.brand_name.as_deref(),
+                );
+
+                if !seen_fingerprints.insert(fingerprint) {
+                    errors.push(BulkRowError {
+                        row: index,
+                        error: "Duplicate coupon code within this batch".to_string(),
+                    });
+                    continue;
+                }
+
+                let duplicate = detector
+                    .check_duplicate(code, &request.category, request.brand_name.as_deref(), seller_id)
+                    .await?;
+
+                if let Some(duplicate) = duplicate {
+                    if duplicate.confidence >= DUPLICATE_CONFIDENCE_THRESHOLD {
+                        errors.push(BulkRowError {
+                            row: index,
+                            error: format!("Possible duplicate of listing {}", duplicate.listing_id),
+                        });
+                        continue;
+                    }
+                }
+            }
+
+            accepted.push((index, request));
+        }
+
+        // Reserve the whole batch against the rate limit at once; rows that then fail to be
+        // created are given back below, so only created listings count
+        let rate_limiter = RateLimiter::new(self.pool.clone());
+        if !accepted.is_empty() {
+            let rate_limit = rate_limiter
+                .check_and_increment_by(seller_id, ActionType::BulkCreateListing, accepted.len() as i32)
+                .await?;
+
+            if !rate_limit.allowed {
+                return Err(AppError::RateLimited {
+                    message: format!(
+                        "Bulk listing limit reached, {} rows remaining this hour",
+                        rate_limit.remaining
+                    ),
+                    retry_after: rate_limit.retry_after,
+                });
+            }
+        }
+
+        let service = MarketplaceService::new(self.pool.clone());
+        let reserved = accepted.len();
+        let mut created = Vec::new();
+
+        for (index, request) in accepted {
+            let coupon_code = request.coupon_code.clone();
+            let category = request.category.clone();
+            let brand_name = request.brand_name.clone();
+
+            match service.create_listing(auth_user, request).await {
+                Ok(listing) => {
+                    // The listing exists either way; a missing fingerprint is filled in by the backfill task
+                    if let Some(code) = coupon_code {
+                        let stored = detector
+                            .store_fingerprint(&listing.id.to_string(), &code, &category, brand_name.as_deref())
+                            .await;
+                        if let Err(e) = stored {
+                            tracing::warn!(error = %e, listing_id = %listing.id, "could not store listing fingerprint");
+                        }
+                    }
+                    created.push(listing);
+                }
+                Err(e) => errors.push(BulkRowError {
+                    row: index,
+                    error: e.to_string(),
+                }),
+            }
+        }
+
+        let failed = reserved - created.len();
+        if failed > 0 {
+            rate_limiter
+                .release(seller_id, ActionType::BulkCreateListing, failed as i32)
+                .await?;
+        }
+
+        errors.sort_by_key(|e| e.row);
+
+        Ok(BulkCreateListingResponse { created, errors })
+    }
+}
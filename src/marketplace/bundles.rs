@@ -0,0 +1,334 @@
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::marketplace::away::SELLER_NOT_AWAY;
+use crate::marketplace::cart::CheckoutDiscounts;
+use crate::marketplace::shadow_bans::NOT_SHADOW_BANNED;
+use crate::marketplace::wallet::{is_wallet_payment, WalletService};
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{
+    BundleItem, BundleStatus, BundleWithItems, Checkout, CheckoutDetail, CheckoutStatus, ListingBundle, ListingStatus,
+};
+use bigdecimal::{BigDecimal, Zero};
+use sqlx::{FromRow, PgPool, Row};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+// Listings per bundle
+pub const MIN_BUNDLE_LISTINGS: usize = 2;
+pub const MAX_BUNDLE_LISTINGS: usize = 10;
+
+// Active bundles a seller can offer at once
+const MAX_ACTIVE_BUNDLES: i64 = 50;
+
+/// Seller-defined bundles of their own listings sold for one combined price.
+/// Buying a bundle opens a checkout with one transaction per listing, the bundle price
+/// split across them in proportion to the listings' own prices, so each code is paid,
+/// allocated, held in escrow and refunded like any other purchase.
+pub struct BundleService {
+    pool: PgPool,
+}
+
+impl BundleService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create_bundle(
+        &self,
+        auth_user: &AuthUser,
+        title: &str,
+        description: Option<&str>,
+        listing_ids: &[Uuid],
+        bundle_price: &BigDecimal,
+    ) -> Result<BundleWithItems, AppError> {
+        let seller_id = &auth_user.0.auth0_id;
+
+        let unique: HashSet<&Uuid> = listing_ids.iter().collect();
+        if unique.len() != listing_ids.len() {
+            return Err(AppError::BadRequest("A listing can only appear once in a bundle".to_string()));
+        }
+
+        let active_bundles = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM marketplace_bundles WHERE seller_id = $1 AND status = $2"
+        )
+        .bind(seller_id)
+        .bind(BundleStatus::Active)
+        .fetch_one(&self.pool)
+        .await?;
+
+        if active_bundles >= MAX_ACTIVE_BUNDLES {
+            return Err(AppError::Conflict(format!("You can offer at most {} bundles at once", MAX_ACTIVE_BUNDLES)));
+        }
+
+        let listings = sqlx::query(
+            r#"
+            SELECT id, seller_id, status, selling_price
+            FROM marketplace_listings
+            WHERE id = ANY($1) AND deleted_at IS NULL
+            "#
+        )
+        .bind(listing_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        if listings.len() != listing_ids.len() {
+            return Err(AppError::NotFound("One or more listings were not found".to_string()));
+        }
+
+        let mut separate_price = BigDecimal::zero();
+        for listing in &listings {
+            if listing.get::<String, _>("seller_id") != *seller_id {
+                return Err(AppError::Forbidden("You can only bundle your own listings".to_string()));
+            }
+            if listing.get::<ListingStatus, _>("status") != ListingStatus::Active {
+                return Err(AppError::Conflict("Only active listings can be bundled".to_string()));
+            }
+            separate_price += listing.get::<BigDecimal, _>("selling_price");
+        }
+
+        if *bundle_price >= separate_price {
+            return Err(AppError::BadRequest(format!(
+                "The bundle price must be below the listings' combined price of {}",
+                separate_price
+            )));
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        let bundle_id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_bundles (id, seller_id, title, description, bundle_price, status, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+            "#
+        )
+        .bind(bundle_id)
+        .bind(seller_id)
+        .bind(title.trim())
+        .bind(description)
+        .bind(bundle_price.round(2))
+        .bind(BundleStatus::Active)
+        .execute(&mut *tx)
+        .await?;
+
+        for (position, listing_id) in listing_ids.iter().enumerate() {
+            sqlx::query("INSERT INTO marketplace_bundle_items (bundle_id, listing_id, position) VALUES ($1, $2, $3)")
+                .bind(bundle_id)
+                .bind(listing_id)
+                .bind(position as i32)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+
+        self.get_bundle(bundle_id).await
+    }
+
+    /// A bundle with its listings; inactive bundles are only found by their seller
+    pub async fn get_bundle(&self, bundle_id: Uuid) -> Result<BundleWithItems, AppError> {
+        let bundle = sqlx::query_as::<_, ListingBundle>("SELECT * FROM marketplace_bundles WHERE id = $1")
+            .bind(bundle_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Bundle not found".to_string()))?;
+
+        let items = self.load_items(&[bundle.id]).await?.remove(&bundle.id).unwrap_or_default();
+        Ok(with_items(bundle, items))
+    }
+
+    /// Active bundles, optionally of one seller, newest first. Like listings, bundles of
+    /// shadow-banned and away sellers are hidden.
+    pub async fn list_bundles(&self, seller_id: Option<&str>, limit: i64) -> Result<Vec<BundleWithItems>, AppError> {
+        // Aliased `l` for the seller filters, which look at `l.seller_id`
+        let query = format!(
+            r#"
+            SELECT l.* FROM marketplace_bundles l
+            WHERE l.status = $1 AND ($2::text IS NULL OR l.seller_id = $2)
+            AND {} AND {}
+            ORDER BY l.created_at DESC
+            LIMIT $3
+            "#,
+            NOT_SHADOW_BANNED, SELLER_NOT_AWAY
+        );
+        let bundles = sqlx::query_as::<_, ListingBundle>(&query)
+            .bind(BundleStatus::Active)
+            .bind(seller_id)
+            .bind(limit.clamp(1, 100))
+            .fetch_all(&self.pool)
+            .await?;
+
+        let bundle_ids: Vec<Uuid> = bundles.iter().map(|bundle| bundle.id).collect();
+        let mut items = self.load_items(&bundle_ids).await?;
+
+        Ok(bundles
+            .into_iter()
+            .map(|bundle| {
+                let bundle_items = items.remove(&bundle.id).unwrap_or_default();
+                with_items(bundle, bundle_items)
+            })
+            .collect())
+    }
+
+    /// Take a bundle off sale; the listings themselves stay on sale
+    pub async fn deactivate_bundle(&self, auth_user: &AuthUser, bundle_id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE marketplace_bundles
+            SET status = $3, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1 AND seller_id = $2
+            "#
+        )
+        .bind(bundle_id)
+        .bind(&auth_user.0.auth0_id)
+        .bind(BundleStatus::Inactive)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Bundle not found".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Reserve one unit of every listing in the bundle under a single checkout.
+    /// The codes are allocated when the checkout's payment is confirmed, or at once when
+    /// paying by wallet.
+    pub async fn purchase(
+        &self,
+        auth_user: &AuthUser,
+        bundle_id: Uuid,
+        payment_method: &str,
+        discounts: &CheckoutDiscounts<'_>,
+    ) -> Result<CheckoutDetail, AppError> {
+        let buyer_id = &auth_user.0.auth0_id;
+        let bundle = self.get_bundle(bundle_id).await?;
+
+        if bundle.bundle.status != BundleStatus::Active {
+            return Err(AppError::Conflict("Bundle is no longer on sale".to_string()));
+        }
+        if bundle.bundle.seller_id == *buyer_id {
+            return Err(AppError::Forbidden("You cannot purchase your own bundle".to_string()));
+        }
+        if !bundle.available {
+            return Err(AppError::Conflict("A listing in this bundle is sold out or unavailable".to_string()));
+        }
+
+        let prices = split_price(&bundle.bundle.bundle_price, &bundle.items);
+
+        let mut tx = self.pool.begin().await?;
+
+        let checkout_id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_checkouts (id, buyer_id, payment_method, total_amount, status, bundle_id, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, CURRENT_TIMESTAMP)
+            "#
+        )
+        .bind(checkout_id)
+        .bind(buyer_id)
+        .bind(payment_method)
+        .bind(&bundle.bundle.bundle_price)
+        .bind(CheckoutStatus::AwaitingPayment)
+        .bind(bundle_id)
+        .execute(&mut *tx)
+        .await?;
+
+        // Listings are reserved in id order so concurrent purchases lock them consistently
+        let mut items: Vec<_> = bundle.items.iter().zip(prices).collect();
+        items.sort_by_key(|(item, _)| item.listing_id);
+
+        let mut transactions = Vec::with_capacity(items.len());
+        for (item, price) in items {
+            let transaction = MarketplaceService::open_transaction(
+                &mut tx,
+                buyer_id,
+                item.listing_id,
+                payment_method,
+                Some(checkout_id),
+                Some(price),
+            ).await?;
+            transactions.push(transaction);
+        }
+
+        let checkout = sqlx::query_as::<_, Checkout>("SELECT * FROM marketplace_checkouts WHERE id = $1")
+            .bind(checkout_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        let mut checkout = discounts.apply(&mut tx, checkout, &mut transactions).await?;
+        if is_wallet_payment(payment_method) {
+            checkout = WalletService::pay_checkout(&mut tx, &checkout, &mut transactions).await?;
+        }
+
+        tx.commit().await?;
+
+        MarketplaceService::new(self.pool.clone())
+            .create_notification(
+                &bundle.bundle.seller_id,
+                "new_sale",
+                "New Sale!",
+                &format!("Your bundle \"{}\" has been purchased", bundle.bundle.title),
+                None,
+                None,
+            )
+            .await;
+
+        Ok(CheckoutDetail { checkout, transactions })
+    }
+
+    /// The listings of each bundle in their bundle order, loaded in one query
+    async fn load_items(&self, bundle_ids: &[Uuid]) -> Result<HashMap<Uuid, Vec<BundleItem>>, AppError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT
+                bi.bundle_id, l.id as listing_id, l.title, l.brand_name, l.listing_type, l.original_value,
+                l.selling_price, l.remaining_quantity, l.status
+            FROM marketplace_bundle_items bi
+            JOIN marketplace_listings l ON l.id = bi.listing_id
+            WHERE bi.bundle_id = ANY($1)
+            ORDER BY bi.bundle_id, bi.position
+            "#
+        )
+        .bind(bundle_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut items: HashMap<Uuid, Vec<BundleItem>> = HashMap::new();
+        for row in &rows {
+            items.entry(row.get("bundle_id")).or_default().push(BundleItem::from_row(row)?);
+        }
+        Ok(items)
+    }
+}
+
+fn with_items(bundle: ListingBundle, items: Vec<BundleItem>) -> BundleWithItems {
+    let separate_price: BigDecimal = items.iter().map(|item| item.selling_price.clone()).sum();
+    let savings = (&separate_price - &bundle.bundle_price).max(BigDecimal::zero());
+    let available = items
+        .iter()
+        .all(|item| item.status == ListingStatus::Active && item.remaining_quantity > 0);
+
+    BundleWithItems { bundle, items, separate_price, savings, available }
+}
+
+// Bundle price spread over the items by their own prices; the last item takes the rounding remainder
+fn split_price(bundle_price: &BigDecimal, items: &[BundleItem]) -> Vec<BigDecimal> {
+    let separate_price: BigDecimal = items.iter().map(|item| item.selling_price.clone()).sum();
+    let mut prices = Vec::with_capacity(items.len());
+    let mut allocated = BigDecimal::zero();
+
+    for (index, item) in items.iter().enumerate() {
+        let price = if index + 1 == items.len() {
+            bundle_price - &allocated
+        } else if separate_price.is_zero() {
+            BigDecimal::zero()
+        } else {
+            (bundle_price * &item.selling_price / &separate_price).round(2)
+        };
+        allocated += &price;
+        prices.push(price);
+    }
+
+    prices
+}
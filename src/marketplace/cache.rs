@@ -1,8 +1,10 @@
 use crate::error::AppError;
 use crate::models::marketplace::{ListingWithSeller, MarketplaceProfile};
 use redis::{AsyncCommands, Client};
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
 use std::time::Duration;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 pub struct MarketplaceCache {
@@ -183,6 +185,31 @@ impl MarketplaceCache {
         Ok(None)
     }
 
+    /// Get view counts for many listings in a single round trip
+    pub async fn get_view_counts(&self, listing_ids: &[Uuid]) -> Result<HashMap<Uuid, i32>, AppError> {
+        let mut counts = HashMap::new();
+
+        if listing_ids.is_empty() {
+            return Ok(counts);
+        }
+
+        if let Some(client) = &self.redis_client {
+            let mut conn = client.get_async_connection().await
+                .map_err(|e| AppError::InternalError(format!("Redis connection error: {}", e)))?;
+
+            let keys: Vec<String> = listing_ids.iter().map(|id| format!("views:{}", id)).collect();
+            let results: Vec<Option<i32>> = redis::cmd("MGET").arg(&keys).query_async(&mut conn).await
+                .map_err(|e| AppError::InternalError(format!("Redis mget error: {}", e)))?;
+
+            for (id, count) in listing_ids.iter().zip(results) {
+                if let Some(count) = count {
+                    counts.insert(*id, count);
+                }
+            }
+        }
+        Ok(counts)
+    }
+
     /// Cache search results
     pub async fn cache_search_results(
         &self,
@@ -223,6 +250,41 @@ impl MarketplaceCache {
         Ok(None)
     }
 
+    /// Cache an arbitrary report under `report:<key>`
+    pub async fn cache_report<T: Serialize>(&self, key: &str, report: &T, ttl_seconds: u64) -> Result<(), AppError> {
+        if let Some(client) = &self.redis_client {
+            let mut conn = client.get_async_connection().await
+                .map_err(|e| AppError::InternalError(format!("Redis connection error: {}", e)))?;
+
+            let key = format!("report:{}", key);
+            let serialized = serde_json::to_string(report)
+                .map_err(|e| AppError::InternalError(format!("Serialization error: {}", e)))?;
+
+            conn.set_ex::<_, _, ()>(&key, serialized, ttl_seconds).await
+                .map_err(|e| AppError::InternalError(format!("Redis set error: {}", e)))?;
+        }
+        Ok(())
+    }
+
+    /// Get a cached report
+    pub async fn get_report<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, AppError> {
+        if let Some(client) = &self.redis_client {
+            let mut conn = client.get_async_connection().await
+                .map_err(|e| AppError::InternalError(format!("Redis connection error: {}", e)))?;
+
+            let key = format!("report:{}", key);
+            let result: Option<String> = conn.get(&key).await
+                .map_err(|e| AppError::InternalError(format!("Redis get error: {}", e)))?;
+
+            if let Some(data) = result {
+                let report = serde_json::from_str(&data)
+                    .map_err(|e| AppError::InternalError(format!("Deserialization error: {}", e)))?;
+                return Ok(Some(report));
+            }
+        }
+        Ok(None)
+    }
+
     /// Clear all caches for a user (useful when profile or listings change)
     pub async fn clear_user_caches(&self, user_id: &str) -> Result<(), AppError> {
         if let Some(client) = &self.redis_client {
@@ -241,14 +303,15 @@ impl MarketplaceCache {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CategoryStats {
     pub total_listings: i64,
     pub avg_price: f64,
     pub min_price: f64,
     pub max_price: f64,
     pub median_price: f64,
-    pub top_brands: Vec<(String, i64)>,
+    #[schema(value_type = Vec<Vec<Value>>)]
+    pub top_brands: Vec<(String, i64)>, // (brand name, listing count)
 }
 
 impl CategoryStats {
@@ -263,4 +326,6 @@ pub mod cache_ttl {
     pub const PROFILE: u64 = 600; // 10 minutes
     pub const SEARCH_RESULTS: u64 = 180; // 3 minutes
     pub const CATEGORY_STATS: u64 = 300; // 5 minutes
+    pub const ANALYTICS: u64 = 900; // 15 minutes
+    pub const SEARCH_SUGGESTIONS: u64 = 60; // 1 minute
 }
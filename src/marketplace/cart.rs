@@ -0,0 +1,510 @@
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::marketplace::away;
+use crate::marketplace::high_value::HighValueService;
+use crate::marketplace::holds::{self, CheckoutHolds};
+use crate::marketplace::loyalty::LoyaltyService;
+use crate::marketplace::outbox::{event_types, OutboxService};
+use crate::marketplace::promo_codes::PromoCodeService;
+use crate::marketplace::transaction_state::{TransactionEvent, TransactionStateMachine};
+use crate::marketplace::wallet::{is_wallet_payment, WalletService};
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{
+    Cart, CartItem, Checkout, CheckoutDetail, CheckoutStatus, ListingStatus, MarketplaceTransaction,
+    TransactionStatus,
+};
+use bigdecimal::{BigDecimal, Zero};
+use sqlx::{PgPool, Postgres, Row, Transaction};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+// Different listings a cart can hold
+const MAX_CART_LISTINGS: i64 = 20;
+
+// Units of one listing per cart line
+pub const MAX_ITEM_QUANTITY: i32 = 10;
+
+/// Shopping cart and bundled checkout. A checkout turns every unit in the cart into its own
+/// pending transaction in one database transaction, so either all items are reserved or none
+/// are. The transactions share one payment but move through escrow independently.
+pub struct CartService {
+    pool: PgPool,
+}
+
+impl CartService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn get_cart(&self, user_id: &str) -> Result<Cart, AppError> {
+        let items = sqlx::query_as::<_, CartItem>(
+            r#"
+            SELECT
+                c.listing_id, l.title, l.seller_id, l.selling_price as unit_price, c.quantity,
+                l.remaining_quantity, l.status as listing_status, c.added_at
+            FROM marketplace_cart_items c
+            JOIN marketplace_listings l ON l.id = c.listing_id
+            WHERE c.user_id = $1 AND l.deleted_at IS NULL
+            ORDER BY c.added_at
+            "#
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let item_count = items.iter().map(|item| item.quantity).sum();
+        let total_amount = items
+            .iter()
+            .map(|item| &item.unit_price * BigDecimal::from(item.quantity))
+            .sum();
+
+        Ok(Cart { items, item_count, total_amount })
+    }
+
+    /// Add units of a listing, merging with a line already in the cart
+    pub async fn add_item(&self, user_id: &str, listing_id: Uuid, quantity: i32) -> Result<Cart, AppError> {
+        let in_cart = sqlx::query_scalar::<_, i32>(
+            "SELECT quantity FROM marketplace_cart_items WHERE user_id = $1 AND listing_id = $2"
+        )
+        .bind(user_id)
+        .bind(listing_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if in_cart.is_none() {
+            let lines = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM marketplace_cart_items WHERE user_id = $1")
+                .bind(user_id)
+                .fetch_one(&self.pool)
+                .await?;
+            if lines >= MAX_CART_LISTINGS {
+                return Err(AppError::Conflict(format!("A cart can hold at most {} listings", MAX_CART_LISTINGS)));
+            }
+        }
+
+        self.set_quantity(user_id, listing_id, in_cart.unwrap_or(0) + quantity).await
+    }
+
+    /// Change how many units of a listing are in the cart
+    pub async fn update_item(&self, user_id: &str, listing_id: Uuid, quantity: i32) -> Result<Cart, AppError> {
+        let exists = sqlx::query("SELECT 1 FROM marketplace_cart_items WHERE user_id = $1 AND listing_id = $2")
+            .bind(user_id)
+            .bind(listing_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .is_some();
+
+        if !exists {
+            return Err(AppError::NotFound("Listing is not in your cart".to_string()));
+        }
+
+        self.set_quantity(user_id, listing_id, quantity).await
+    }
+
+    pub async fn remove_item(&self, user_id: &str, listing_id: Uuid) -> Result<Cart, AppError> {
+        let result = sqlx::query("DELETE FROM marketplace_cart_items WHERE user_id = $1 AND listing_id = $2")
+            .bind(user_id)
+            .bind(listing_id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Listing is not in your cart".to_string()));
+        }
+
+        self.get_cart(user_id).await
+    }
+
+    pub async fn clear(&self, user_id: &str) -> Result<(), AppError> {
+        sqlx::query("DELETE FROM marketplace_cart_items WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Buy everything in the cart under one payment, optionally redeeming a promo code and
+    /// loyalty points. Paying by wallet funds every item's escrow at once. Fails without
+    /// reserving anything if any unit is no longer available, a discount cannot be applied
+    /// or the wallet does not cover the order.
+    pub async fn checkout(
+        &self,
+        auth_user: &AuthUser,
+        payment_method: &str,
+        discounts: &CheckoutDiscounts<'_>,
+    ) -> Result<CheckoutDetail, AppError> {
+        let buyer_id = &auth_user.0.auth0_id;
+        let cart = self.get_cart(buyer_id).await?;
+
+        if cart.items.is_empty() {
+            return Err(AppError::BadRequest("Your cart is empty".to_string()));
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        let checkout_id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_checkouts (id, buyer_id, payment_method, total_amount, status, created_at)
+            VALUES ($1, $2, $3, 0, $4, CURRENT_TIMESTAMP)
+            "#
+        )
+        .bind(checkout_id)
+        .bind(buyer_id)
+        .bind(payment_method)
+        .bind(CheckoutStatus::AwaitingPayment)
+        .execute(&mut *tx)
+        .await?;
+
+        // Listings are reserved in id order so concurrent checkouts lock them consistently
+        let mut items = cart.items;
+        items.sort_by_key(|item| item.listing_id);
+
+        let mut transactions = Vec::with_capacity(cart.item_count as usize);
+        for item in &items {
+            for _ in 0..item.quantity {
+                let transaction = MarketplaceService::open_transaction(
+                    &mut tx,
+                    buyer_id,
+                    item.listing_id,
+                    payment_method,
+                    Some(checkout_id),
+                    None,
+                )
+                .await
+                .map_err(|e| match e {
+                    AppError::Conflict(message) => AppError::Conflict(format!("{}: {}", item.title, message)),
+                    other => other,
+                })?;
+                transactions.push(transaction);
+            }
+        }
+
+        let mut checkout = sqlx::query_as::<_, Checkout>(
+            r#"
+            UPDATE marketplace_checkouts
+            SET total_amount = (SELECT SUM(amount) FROM marketplace_transactions WHERE checkout_id = $1)
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(checkout_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        checkout = discounts.apply(&mut tx, checkout, &mut transactions).await?;
+        if is_wallet_payment(payment_method) {
+            checkout = WalletService::pay_checkout(&mut tx, &checkout, &mut transactions).await?;
+        }
+
+        sqlx::query("DELETE FROM marketplace_cart_items WHERE user_id = $1")
+            .bind(buyer_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        // One notification per seller rather than one per unit
+        let marketplace = MarketplaceService::new(self.pool.clone());
+        for (seller_id, sold) in units_by_seller(&transactions) {
+            let message = if sold == 1 {
+                "Your listing has been purchased".to_string()
+            } else {
+                format!("{} of your codes were purchased in one order", sold)
+            };
+            marketplace.create_notification(seller_id, "new_sale", "New Sale!", &message, None, None).await;
+        }
+
+        Ok(CheckoutDetail { checkout, transactions })
+    }
+
+    /// A checkout with the current status of each item, for its buyer or an admin
+    pub async fn get_checkout(&self, auth_user: &AuthUser, checkout_id: Uuid) -> Result<CheckoutDetail, AppError> {
+        let user_id = &auth_user.0.auth0_id;
+        let checkout = self.fetch_checkout(checkout_id).await?;
+
+        if checkout.buyer_id != *user_id && !MarketplaceService::new(self.pool.clone()).is_admin(user_id).await? {
+            return Err(AppError::Forbidden("This checkout belongs to another user".to_string()));
+        }
+
+        let transactions = self.checkout_transactions(checkout_id).await?;
+        Ok(CheckoutDetail { checkout, transactions })
+    }
+
+    /// Record the single payment of a checkout and move every item still awaiting it into escrow
+    pub async fn confirm_payment(
+        &self,
+        auth_user: &AuthUser,
+        checkout_id: Uuid,
+        payment_id: Option<&str>,
+    ) -> Result<CheckoutDetail, AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+
+        let mut tx = self.pool.begin().await?;
+        let (checkout, pending) = mark_paid(&mut tx, checkout_id, payment_id, &auth_user.0.auth0_id).await?;
+        tx.commit().await?;
+
+        self.notify_paid(&checkout, &pending).await;
+
+        let transactions = self.checkout_transactions(checkout_id).await?;
+        Ok(CheckoutDetail { checkout, transactions })
+    }
+
+    /// Tell the buyer and sellers that a checkout's payment is held in escrow
+    pub(crate) async fn notify_paid(&self, checkout: &Checkout, pending: &[MarketplaceTransaction]) {
+        let marketplace = MarketplaceService::new(self.pool.clone());
+
+        for (seller_id, _) in units_by_seller(pending) {
+            marketplace.create_notification(
+                seller_id,
+                "payment_confirmed",
+                "Payment Received",
+                "The buyer's payment is held in escrow until they confirm receipt",
+                None,
+                None,
+            ).await;
+        }
+
+        if !pending.is_empty() {
+            marketplace.create_notification(
+                &checkout.buyer_id,
+                "payment_confirmed",
+                "Payment Confirmed",
+                "Your payment is held in escrow and your codes can now be revealed",
+                None,
+                None,
+            ).await;
+        }
+    }
+
+    // Validate against the listing and store the new quantity
+    async fn set_quantity(&self, user_id: &str, listing_id: Uuid, quantity: i32) -> Result<Cart, AppError> {
+        let listing = sqlx::query(
+            "SELECT seller_id, status, remaining_quantity FROM marketplace_listings WHERE id = $1 AND deleted_at IS NULL"
+        )
+        .bind(listing_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Listing not found".to_string()))?;
+
+        let seller_id: String = listing.get("seller_id");
+        let status: ListingStatus = listing.get("status");
+        let remaining: i32 = listing.get("remaining_quantity");
+
+        if seller_id == user_id {
+            return Err(AppError::Forbidden("You cannot purchase your own listing".to_string()));
+        }
+        if status != ListingStatus::Active {
+            return Err(AppError::Conflict("Listing is not available for purchase".to_string()));
+        }
+        away::ensure_selling(&self.pool, &seller_id).await?;
+        if quantity > MAX_ITEM_QUANTITY {
+            return Err(AppError::BadRequest(format!("At most {} units of a listing per order", MAX_ITEM_QUANTITY)));
+        }
+        if quantity > remaining {
+            return Err(AppError::Conflict(format!("Only {} left in stock", remaining)));
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_cart_items (user_id, listing_id, quantity, added_at)
+            VALUES ($1, $2, $3, CURRENT_TIMESTAMP)
+            ON CONFLICT (user_id, listing_id) DO UPDATE SET quantity = EXCLUDED.quantity
+            "#
+        )
+        .bind(user_id)
+        .bind(listing_id)
+        .bind(quantity)
+        .execute(&self.pool)
+        .await?;
+
+        self.get_cart(user_id).await
+    }
+
+    pub(crate) async fn fetch_checkout(&self, checkout_id: Uuid) -> Result<Checkout, AppError> {
+        sqlx::query_as::<_, Checkout>("SELECT * FROM marketplace_checkouts WHERE id = $1")
+            .bind(checkout_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Checkout not found".to_string()))
+    }
+
+    async fn checkout_transactions(&self, checkout_id: Uuid) -> Result<Vec<MarketplaceTransaction>, AppError> {
+        let transactions = sqlx::query_as::<_, MarketplaceTransaction>(
+            "SELECT * FROM marketplace_transactions WHERE checkout_id = $1 ORDER BY created_at"
+        )
+        .bind(checkout_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(transactions)
+    }
+}
+
+/// Record a checkout's payment and fund its items, returning the checkout and the items as
+/// they were before
+pub(crate) async fn mark_paid(
+    tx: &mut Transaction<'_, Postgres>,
+    checkout_id: Uuid,
+    payment_id: Option<&str>,
+    actor_id: &str,
+) -> Result<(Checkout, Vec<MarketplaceTransaction>), AppError> {
+    let checkout = sqlx::query_as::<_, Checkout>(
+        r#"
+        UPDATE marketplace_checkouts
+        SET status = $2, payment_id = $3, paid_at = CURRENT_TIMESTAMP
+        WHERE id = $1 AND status = $4
+        RETURNING *
+        "#
+    )
+    .bind(checkout_id)
+    .bind(CheckoutStatus::Paid)
+    .bind(payment_id)
+    .bind(CheckoutStatus::AwaitingPayment)
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    let Some(checkout) = checkout else {
+        let status: Option<CheckoutStatus> = sqlx::query_scalar("SELECT status FROM marketplace_checkouts WHERE id = $1")
+            .bind(checkout_id)
+            .fetch_optional(&mut **tx)
+            .await?;
+        return Err(match status {
+            Some(CheckoutStatus::Expired) => AppError::Conflict("Checkout expired before it was paid".to_string()),
+            Some(_) => AppError::Conflict("Checkout has already been paid".to_string()),
+            None => AppError::NotFound("Checkout not found".to_string()),
+        });
+    };
+
+    let pending = fund_pending(tx, checkout_id, payment_id, actor_id).await?;
+    Ok((checkout, pending))
+}
+
+/// Move every item of a checkout still awaiting payment into escrow under `payment_id`,
+/// returning the items as they were before
+pub(crate) async fn fund_pending(
+    tx: &mut Transaction<'_, Postgres>,
+    checkout_id: Uuid,
+    payment_id: Option<&str>,
+    actor_id: &str,
+) -> Result<Vec<MarketplaceTransaction>, AppError> {
+    let pending = sqlx::query_as::<_, MarketplaceTransaction>(
+        "SELECT * FROM marketplace_transactions WHERE checkout_id = $1 AND status = $2 ORDER BY created_at"
+    )
+    .bind(checkout_id)
+    .bind(TransactionStatus::Pending)
+    .fetch_all(&mut **tx)
+    .await?;
+
+    for transaction in &pending {
+        fund_transaction(tx, transaction, payment_id, actor_id).await?;
+    }
+
+    Ok(pending)
+}
+
+/// Move a pending transaction into escrow under `payment_id`, returning it as updated.
+/// The payment of a high-value transaction still awaiting its checks is held instead,
+/// returning `None`; it moves to escrow once they pass.
+pub(crate) async fn fund_transaction(
+    tx: &mut Transaction<'_, Postgres>,
+    transaction: &MarketplaceTransaction,
+    payment_id: Option<&str>,
+    actor_id: &str,
+) -> Result<Option<MarketplaceTransaction>, AppError> {
+    if HighValueService::hold_payment(tx, transaction, payment_id).await? {
+        return Ok(None);
+    }
+
+    let updated = TransactionStateMachine::apply(
+        tx,
+        transaction,
+        TransactionEvent::PaymentConfirmed,
+        actor_id,
+        None,
+    ).await?;
+
+    let updated = sqlx::query_as::<_, MarketplaceTransaction>(
+        "UPDATE marketplace_transactions SET payment_id = COALESCE($2, payment_id) WHERE id = $1 RETURNING *"
+    )
+    .bind(updated.id)
+    .bind(payment_id)
+    .fetch_one(&mut **tx)
+    .await?;
+
+    // The buyer gets their code while the funds are still in escrow, so purchase protection can apply
+    MarketplaceService::allocate_coupon_code(tx, transaction).await?;
+    OutboxService::record(tx, "transaction", transaction.id, event_types::TRANSACTION_PAID, &updated).await?;
+
+    // A paid listing is sold, not reserved
+    if !holds::has_unpaid(tx, transaction.listing_id).await? {
+        CheckoutHolds::from_config().release(transaction.listing_id).await;
+    }
+
+    Ok(Some(updated))
+}
+
+/// Discounts a buyer asked for at checkout
+#[derive(Debug, Default)]
+pub struct CheckoutDiscounts<'a> {
+    pub promo_code: Option<&'a str>,
+    pub points: Option<i64>,
+}
+
+impl CheckoutDiscounts<'_> {
+    /// Apply the promo code first, then points, to a checkout being created in `tx`
+    pub(crate) async fn apply(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        mut checkout: Checkout,
+        transactions: &mut [MarketplaceTransaction],
+    ) -> Result<Checkout, AppError> {
+        if let Some(code) = self.promo_code {
+            checkout = PromoCodeService::redeem(tx, &checkout, code, transactions).await?;
+        }
+        if let Some(points) = self.points {
+            checkout = LoyaltyService::redeem(tx, &checkout, points, transactions).await?;
+        }
+        Ok(checkout)
+    }
+}
+
+/// Spread a checkout discount over some of its items in proportion to what is still due on
+/// each, the last item taking the rounding remainder
+pub(crate) async fn spread_discount(
+    tx: &mut Transaction<'_, Postgres>,
+    transactions: Vec<&mut MarketplaceTransaction>,
+    discount: &BigDecimal,
+) -> Result<(), AppError> {
+    let due = |transaction: &MarketplaceTransaction| &transaction.amount - &transaction.discount_amount;
+    let total_due: BigDecimal = transactions.iter().map(|transaction| due(transaction)).sum();
+
+    let count = transactions.len();
+    let mut allocated = BigDecimal::zero();
+    for (index, transaction) in transactions.into_iter().enumerate() {
+        let share = if index + 1 == count {
+            discount - &allocated
+        } else if total_due.is_zero() {
+            BigDecimal::zero()
+        } else {
+            (discount * due(transaction) / &total_due).round(2)
+        };
+        allocated += &share;
+
+        transaction.discount_amount = sqlx::query_scalar::<_, BigDecimal>(
+            "UPDATE marketplace_transactions SET discount_amount = discount_amount + $2 WHERE id = $1 RETURNING discount_amount"
+        )
+        .bind(transaction.id)
+        .bind(&share)
+        .fetch_one(&mut **tx)
+        .await?;
+    }
+
+    Ok(())
+}
+
+fn units_by_seller(transactions: &[MarketplaceTransaction]) -> BTreeMap<&str, usize> {
+    let mut sellers = BTreeMap::new();
+    for transaction in transactions {
+        *sellers.entry(transaction.seller_id.as_str()).or_insert(0) += 1;
+    }
+    sellers
+}
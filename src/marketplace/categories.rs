@@ -0,0 +1,207 @@
+use crate::error::AppError;
+use crate::marketplace::cache::{cache_ttl, CategoryStats, MarketplaceCache};
+use crate::models::marketplace::{CategoryNode, MarketplaceCategory};
+use sqlx::{PgPool, Postgres, QueryBuilder, Row};
+use std::collections::HashMap;
+
+pub struct CategoryService {
+    pool: PgPool,
+}
+
+impl CategoryService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Normalize a category slug (trim, lowercase, spaces to hyphens)
+    pub fn normalize_slug(category: &str) -> String {
+        category
+            .trim()
+            .to_lowercase()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join("-")
+    }
+
+    /// Validate that a category exists and is active, returning its normalized slug
+    pub async fn validate_category(&self, category: &str) -> Result<String, AppError> {
+        let slug = Self::normalize_slug(category);
+
+        let exists = sqlx::query(
+            "SELECT 1 FROM marketplace_categories WHERE slug = $1 AND is_active = TRUE"
+        )
+        .bind(&slug)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if exists.is_none() {
+            return Err(AppError::UnprocessableEntity(format!("Unknown category: {}", category)));
+        }
+
+        Ok(slug)
+    }
+
+    /// Get the full category tree with active listing counts rolled up to parents
+    pub async fn get_tree(&self) -> Result<Vec<CategoryNode>, AppError> {
+        let categories = sqlx::query_as::<_, MarketplaceCategory>(
+            "SELECT * FROM marketplace_categories WHERE is_active = TRUE ORDER BY sort_order, name"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let counts: HashMap<String, i64> = sqlx::query(
+            r#"
+            SELECT category, COUNT(*) as listing_count
+            FROM marketplace_listings
+            WHERE status = 'active' AND deleted_at IS NULL
+            GROUP BY category
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| (row.get("category"), row.get("listing_count")))
+        .collect();
+
+        let mut children_by_parent: HashMap<Option<String>, Vec<MarketplaceCategory>> = HashMap::new();
+        for category in categories {
+            children_by_parent
+                .entry(category.parent_slug.clone())
+                .or_default()
+                .push(category);
+        }
+
+        Ok(Self::build_nodes(None, &children_by_parent, &counts))
+    }
+
+    /// Get price distribution and top brands for a category, served from cache when possible
+    pub async fn get_stats(
+        &self,
+        cache: &MarketplaceCache,
+        category: &str,
+    ) -> Result<CategoryStats, AppError> {
+        let slug = self.validate_category(category).await?;
+
+        // Cache failures are treated as misses
+        if let Ok(Some(stats)) = cache.get_category_stats(&slug).await {
+            return Ok(stats);
+        }
+
+        let stats = self.compute_stats(&slug).await?;
+        let _ = cache.cache_category_stats(&slug, &stats, cache_ttl::CATEGORY_STATS).await;
+
+        Ok(stats)
+    }
+
+    async fn compute_stats(&self, slug: &str) -> Result<CategoryStats, AppError> {
+        let subtree = category_subtree_condition("category", 1);
+
+        let price_query = format!(
+            r#"
+            SELECT
+                COUNT(*) as total_listings,
+                COALESCE(AVG(selling_price), 0)::float8 as avg_price,
+                COALESCE(MIN(selling_price), 0)::float8 as min_price,
+                COALESCE(MAX(selling_price), 0)::float8 as max_price,
+                COALESCE(PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY selling_price), 0)::float8 as median_price
+            FROM marketplace_listings
+            WHERE status = 'active' AND deleted_at IS NULL{}
+            "#,
+            subtree
+        );
+
+        let prices = sqlx::query(&price_query)
+            .bind(slug)
+            .fetch_one(&self.pool)
+            .await?;
+
+        let brands_query = format!(
+            r#"
+            SELECT brand_name, COUNT(*) as listing_count
+            FROM marketplace_listings
+            WHERE status = 'active' AND deleted_at IS NULL AND brand_name IS NOT NULL{}
+            GROUP BY brand_name
+            ORDER BY listing_count DESC
+            LIMIT 10
+            "#,
+            subtree
+        );
+
+        let top_brands = sqlx::query(&brands_query)
+            .bind(slug)
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|row| (row.get("brand_name"), row.get("listing_count")))
+            .collect();
+
+        Ok(CategoryStats {
+            total_listings: prices.get("total_listings"),
+            avg_price: prices.get("avg_price"),
+            min_price: prices.get("min_price"),
+            max_price: prices.get("max_price"),
+            median_price: prices.get("median_price"),
+            top_brands,
+        })
+    }
+
+    fn build_nodes(
+        parent: Option<String>,
+        children_by_parent: &HashMap<Option<String>, Vec<MarketplaceCategory>>,
+        counts: &HashMap<String, i64>,
+    ) -> Vec<CategoryNode> {
+        children_by_parent
+            .get(&parent)
+            .map(|children| {
+                children
+                    .iter()
+                    .map(|category| {
+                        let children = Self::build_nodes(
+                            Some(category.slug.clone()),
+                            children_by_parent,
+                            counts,
+                        );
+                        let own_count = counts.get(&category.slug).copied().unwrap_or(0);
+                        let listing_count = own_count + children.iter().map(|c| c.listing_count).sum::<i64>();
+
+                        CategoryNode {
+                            slug: category.slug.clone(),
+                            name: category.name.clone(),
+                            listing_count,
+                            children,
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// SQL fragment matching a category and all of its descendants, bound at the given placeholder
+pub fn category_subtree_condition(column: &str, placeholder: usize) -> String {
+    format!(
+        r#" AND {} IN (
+            WITH RECURSIVE subtree AS (
+                SELECT slug FROM marketplace_categories WHERE slug = ${}
+                UNION ALL
+                SELECT c.slug FROM marketplace_categories c JOIN subtree s ON c.parent_slug = s.slug
+            )
+            SELECT slug FROM subtree
+        )"#,
+        column, placeholder
+    )
+}
+
+/// Same as `category_subtree_condition`, binding the slug on a query builder
+pub fn push_category_subtree(query: &mut QueryBuilder<'_, Postgres>, column: &str, slug: String) {
+    query
+        .push(format!(
+            " AND {} IN (WITH RECURSIVE subtree AS (SELECT slug FROM marketplace_categories WHERE slug = ",
+            column
+        ))
+        .push_bind(slug)
+        .push(
+            " UNION ALL SELECT c.slug FROM marketplace_categories c JOIN subtree s ON c.parent_slug = s.slug) \
+             SELECT slug FROM subtree)",
+        );
+}
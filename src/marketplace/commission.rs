@@ -0,0 +1,223 @@
+use crate::error::AppError;
+use crate::marketplace::jobs::Job;
+use crate::marketplace::preferences::load_preferences;
+use crate::marketplace::regions;
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{BadgeType, CommissionTier, CommissionTierChange, SellerCommission};
+use async_trait::async_trait;
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, Row, Transaction};
+
+// Tier thresholds
+const TRUSTED_MIN_SCORE: f64 = 70.0;
+const ELITE_MIN_SCORE: f64 = 85.0;
+
+/// Commission tier a seller qualifies for. Verification and the sales badges
+/// only count for sellers who also hold a high trust score.
+pub fn commission_tier(trust_score: f64, verified_seller: bool, badges: &[String]) -> CommissionTier {
+    let has_badge = |badge: BadgeType| badges.iter().any(|b| b == badge.as_str());
+
+    if trust_score < TRUSTED_MIN_SCORE {
+        CommissionTier::Standard
+    } else if !verified_seller {
+        CommissionTier::Trusted
+    } else if trust_score >= ELITE_MIN_SCORE
+        && (has_badge(BadgeType::PowerSeller) || has_badge(BadgeType::TopRated))
+    {
+        CommissionTier::Elite
+    } else {
+        CommissionTier::Verified
+    }
+}
+
+/// Platform commission on a sale in `tier`, rounded to cents
+pub fn commission_fee(amount: &BigDecimal, tier: CommissionTier, base_rate: f64) -> BigDecimal {
+    let rate = (base_rate * tier.rate_multiplier()).to_string().parse::<BigDecimal>().unwrap_or_default();
+    (amount * rate).round(2)
+}
+
+/// Seller commission tiers. Tiers are re-evaluated nightly after badges are recomputed
+/// and when a seller is verified; each transaction keeps the tier and fee it was created with.
+pub struct CommissionService {
+    pool: PgPool,
+}
+
+impl CommissionService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// The seller's tier and the rate it gives on sales in their region
+    pub async fn get_commission(&self, user_id: &str) -> Result<SellerCommission, AppError> {
+        let row = sqlx::query(
+            "SELECT tier, updated_at FROM marketplace_seller_commission_tiers WHERE user_id = $1"
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let (tier, updated_at) = match row {
+            Some(row) => (row.get("tier"), Some(row.get::<DateTime<Utc>, _>("updated_at"))),
+            None => (CommissionTier::Standard, None),
+        };
+        let region = load_preferences(&self.pool, user_id).await?.region;
+
+        Ok(SellerCommission {
+            tier,
+            commission_rate: regions::fee_rate(&region) * tier.rate_multiplier(),
+            updated_at,
+        })
+    }
+
+    /// The seller's tier and the fee they pay on `amount` at the base rate of the listing's
+    /// `region`, read inside the purchase transaction
+    pub async fn fee_for(
+        tx: &mut Transaction<'_, Postgres>,
+        seller_id: &str,
+        amount: &BigDecimal,
+        region: &str,
+    ) -> Result<(CommissionTier, BigDecimal), AppError> {
+        let tier = sqlx::query_scalar::<_, CommissionTier>(
+            "SELECT tier FROM marketplace_seller_commission_tiers WHERE user_id = $1"
+        )
+        .bind(seller_id)
+        .fetch_optional(&mut **tx)
+        .await?
+        .unwrap_or(CommissionTier::Standard);
+
+        Ok((tier, commission_fee(amount, tier, regions::fee_rate(region))))
+    }
+
+    /// Tier changes of a seller, most recent first
+    pub async fn get_history(&self, user_id: &str, limit: i64) -> Result<Vec<CommissionTierChange>, AppError> {
+        let history = sqlx::query_as::<_, CommissionTierChange>(
+            r#"
+            SELECT id, previous_tier, tier, trust_score, changed_at
+            FROM marketplace_commission_tier_history
+            WHERE user_id = $1
+            ORDER BY changed_at DESC
+            LIMIT $2
+            "#
+        )
+        .bind(user_id)
+        .bind(limit.clamp(1, 100))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(history)
+    }
+
+    /// Re-evaluate the tier of every seller with a trust score. Returns how many changed.
+    pub async fn refresh_all(&self) -> Result<usize, AppError> {
+        self.refresh(None).await
+    }
+
+    /// Re-evaluate one seller, e.g. right after their verification was approved
+    pub async fn refresh_seller(&self, user_id: &str) -> Result<(), AppError> {
+        self.refresh(Some(user_id)).await?;
+        Ok(())
+    }
+
+    async fn refresh(&self, user_id: Option<&str>) -> Result<usize, AppError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT
+                ts.user_id,
+                ts.trust_score,
+                ts.verified_seller,
+                COALESCE(ct.tier, 'standard') as current_tier,
+                COALESCE(ARRAY_AGG(b.badge) FILTER (WHERE b.badge IS NOT NULL), '{}') as badges
+            FROM marketplace_trust_scores ts
+            LEFT JOIN marketplace_seller_commission_tiers ct ON ct.user_id = ts.user_id
+            LEFT JOIN marketplace_seller_badges b ON b.user_id = ts.user_id
+            WHERE $1::text IS NULL OR ts.user_id = $1
+            GROUP BY ts.user_id, ts.trust_score, ts.verified_seller, ct.tier
+            "#
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let marketplace = MarketplaceService::new(self.pool.clone());
+        let mut changed = 0;
+
+        for row in rows {
+            let user_id: String = row.get("user_id");
+            let trust_score: f64 = row.get("trust_score");
+            let current: CommissionTier = row.get("current_tier");
+            let badges: Vec<String> = row.get("badges");
+
+            let tier = commission_tier(trust_score, row.get("verified_seller"), &badges);
+            if tier == current {
+                continue;
+            }
+
+            let mut tx = self.pool.begin().await?;
+
+            sqlx::query(
+                r#"
+                INSERT INTO marketplace_seller_commission_tiers (user_id, tier, updated_at)
+                VALUES ($1, $2, CURRENT_TIMESTAMP)
+                ON CONFLICT (user_id) DO UPDATE SET tier = EXCLUDED.tier, updated_at = EXCLUDED.updated_at
+                "#
+            )
+            .bind(&user_id)
+            .bind(tier)
+            .execute(&mut *tx)
+            .await?;
+
+            sqlx::query(
+                r#"
+                INSERT INTO marketplace_commission_tier_history (user_id, previous_tier, tier, trust_score, changed_at)
+                VALUES ($1, $2, $3, $4, CURRENT_TIMESTAMP)
+                "#
+            )
+            .bind(&user_id)
+            .bind(current)
+            .bind(tier)
+            .bind(trust_score)
+            .execute(&mut *tx)
+            .await?;
+
+            tx.commit().await?;
+            changed += 1;
+
+            let region = load_preferences(&self.pool, &user_id).await?.region;
+            let rate = regions::fee_rate(&region) * tier.rate_multiplier() * 100.0;
+            let (title, message) = if tier > current {
+                (
+                    "You've unlocked a lower commission",
+                    format!("You're now in the {} tier and pay {:.1}% commission on sales", tier.as_str(), rate),
+                )
+            } else {
+                (
+                    "Your commission tier changed",
+                    format!(
+                        "You're now in the {} tier and pay {:.1}% commission on sales. Keep your trust score up to regain your tier.",
+                        tier.as_str(),
+                        rate
+                    ),
+                )
+            };
+            marketplace.create_notification(&user_id, "commission_tier_changed", title, &message, None, None).await;
+        }
+
+        Ok(changed)
+    }
+}
+
+/// Re-evaluates seller commission tiers from the latest trust scores and badges
+pub struct CommissionTierJob;
+
+#[async_trait]
+impl Job for CommissionTierJob {
+    fn name(&self) -> &'static str {
+        "commission_tiers"
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<(), AppError> {
+        CommissionService::new(pool.clone()).refresh_all().await?;
+        Ok(())
+    }
+}
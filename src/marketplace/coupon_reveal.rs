@@ -0,0 +1,293 @@
+use crate::auth::AuthUser;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::marketplace::keyring::CouponKeyring;
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{CouponRevealEvent, CouponRevealToken, CouponWatermark};
+use chrono::{Duration, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::{PgPool, Postgres, QueryBuilder, Row, Transaction};
+use uuid::Uuid;
+
+// Reveal tokens must be used shortly after they are issued
+const REVEAL_TOKEN_TTL_MINUTES: i64 = 5;
+
+// Hex characters of the watermark reference
+const REFERENCE_LENGTH: usize = 10;
+
+/// Coupon reveals with leak tracing. Each reveal needs a fresh single-use token,
+/// is logged with the caller's address and carries a watermark reference that
+/// identifies the reveal, so a leaked code can be traced back to its buyer.
+pub struct CouponRevealService {
+    pool: PgPool,
+}
+
+/// Client details recorded with each reveal
+pub struct RevealContext<'a> {
+    pub ip_address: Option<&'a str>,
+    pub user_agent: Option<&'a str>,
+}
+
+impl CouponRevealService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Issue a single-use reveal token to the seller or a buyer with access to the codes
+    pub async fn issue_token(&self, auth_user: &AuthUser, listing_id: Uuid) -> Result<CouponRevealToken, AppError> {
+        let user_id = &auth_user.0.auth0_id;
+
+        let has_access = sqlx::query(
+            r#"
+            SELECT 1 FROM marketplace_listings_all WHERE id = $1 AND seller_id = $2
+            UNION ALL
+            SELECT 1 FROM marketplace_coupon_access WHERE listing_id = $1 AND user_id = $2
+            LIMIT 1
+            "#
+        )
+        .bind(listing_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .is_some();
+
+        if !has_access {
+            return Err(AppError::Forbidden("You have no coupon codes to reveal for this listing".to_string()));
+        }
+
+        sqlx::query("DELETE FROM marketplace_coupon_reveal_tokens WHERE user_id = $1 AND expires_at < CURRENT_TIMESTAMP")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let expires_at = Utc::now() + Duration::minutes(REVEAL_TOKEN_TTL_MINUTES);
+
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_coupon_reveal_tokens (token_hash, listing_id, user_id, created_at, expires_at)
+            VALUES ($1, $2, $3, CURRENT_TIMESTAMP, $4)
+            "#
+        )
+        .bind(hash_token(&token))
+        .bind(listing_id)
+        .bind(user_id)
+        .bind(expires_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(CouponRevealToken { token, expires_at })
+    }
+
+    /// Consume `token` and return the caller's codes with the watermark of this reveal
+    pub async fn reveal(
+        &self,
+        auth_user: &AuthUser,
+        listing_id: Uuid,
+        token: &str,
+        context: RevealContext<'_>,
+    ) -> Result<(Vec<String>, CouponWatermark), AppError> {
+        let mut tx = self.pool.begin().await?;
+        consume_token(&mut tx, auth_user, listing_id, token).await?;
+
+        let codes = MarketplaceService::new(self.pool.clone()).get_coupon_codes(auth_user, listing_id).await?;
+        let watermark = self.log_reveal(tx, auth_user, listing_id, codes.len(), context).await?;
+
+        Ok((codes, watermark))
+    }
+
+    /// Reveal the code of one purchase again, with a token issued for its listing. Only the
+    /// buyer can, and only while the purchase still gives access to the code.
+    pub async fn reveal_purchase(
+        &self,
+        auth_user: &AuthUser,
+        transaction_id: Uuid,
+        token: &str,
+        context: RevealContext<'_>,
+    ) -> Result<(Vec<String>, CouponWatermark), AppError> {
+        let transaction = MarketplaceService::new(self.pool.clone()).get_transaction_by_id(transaction_id).await?;
+        if transaction.buyer_id != auth_user.0.auth0_id {
+            return Err(AppError::Forbidden("Only the buyer can reveal the code of a purchase".to_string()));
+        }
+
+        let mut tx = self.pool.begin().await?;
+        consume_token(&mut tx, auth_user, transaction.listing_id, token).await?;
+
+        let encrypted: Vec<String> = sqlx::query_scalar(
+            r#"
+            SELECT c.encrypted_code
+            FROM marketplace_coupon_codes c
+            JOIN marketplace_coupon_access a ON a.transaction_id = c.allocated_transaction_id
+            WHERE c.allocated_transaction_id = $1 AND a.user_id = $2
+            ORDER BY c.allocated_at
+            "#
+        )
+        .bind(transaction_id)
+        .bind(&auth_user.0.auth0_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        if encrypted.is_empty() {
+            return Err(AppError::Forbidden("This purchase has no code to reveal".to_string()));
+        }
+
+        let keyring = CouponKeyring::from_config(Config::get())?;
+        let codes = encrypted
+            .iter()
+            .map(|code| keyring.decrypt(code))
+            .collect::<Result<Vec<_>, _>>()?;
+        let watermark = self.log_reveal(tx, auth_user, transaction.listing_id, codes.len(), context).await?;
+
+        Ok((codes, watermark))
+    }
+
+    // Record a reveal of `code_count` codes, commit `tx` and return the reveal's watermark
+    async fn log_reveal(
+        &self,
+        mut tx: Transaction<'_, Postgres>,
+        auth_user: &AuthUser,
+        listing_id: Uuid,
+        code_count: usize,
+        context: RevealContext<'_>,
+    ) -> Result<CouponWatermark, AppError> {
+        let user_id = &auth_user.0.auth0_id;
+
+        let listing = sqlx::query(
+            r#"
+            SELECT l.seller_id, l.title, u.username,
+                EXISTS (
+                    SELECT 1 FROM marketplace_coupon_reveals r WHERE r.listing_id = l.id AND r.user_id = $2
+                ) as revealed_before
+            FROM marketplace_listings_all l
+            LEFT JOIN users u ON u.auth0_id = $2
+            WHERE l.id = $1
+            "#
+        )
+        .bind(listing_id)
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let seller_id: String = listing.get("seller_id");
+        let title: String = listing.get("title");
+        let username: Option<String> = listing.get("username");
+        let revealed_before: bool = listing.get("revealed_before");
+
+        let reveal_id = Uuid::new_v4();
+        let revealed_at = Utc::now();
+        let reference = watermark_reference(reveal_id, user_id);
+
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_coupon_reveals (
+                id, listing_id, user_id, reference, code_count, ip_address, user_agent, revealed_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#
+        )
+        .bind(reveal_id)
+        .bind(listing_id)
+        .bind(user_id)
+        .bind(&reference)
+        .bind(code_count as i32)
+        .bind(context.ip_address)
+        .bind(context.user_agent)
+        .bind(revealed_at)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        // Sellers hear about the first time each buyer looks at their codes
+        if !revealed_before && seller_id != *user_id {
+            MarketplaceService::new(self.pool.clone()).create_notification(
+                &seller_id,
+                "coupon_revealed",
+                "A buyer revealed your code",
+                &format!("A buyer revealed the code for \"{}\" (reference {})", title, reference),
+                Some(listing_id),
+                None,
+            ).await;
+        }
+
+        Ok(CouponWatermark {
+            reveal_id,
+            label: format!(
+                "Revealed to {} · Ref {} · {}",
+                username.as_deref().unwrap_or("buyer"),
+                reference,
+                revealed_at.format("%Y-%m-%d %H:%M UTC")
+            ),
+            reference,
+            revealed_at,
+        })
+    }
+
+    /// Admin leak tracing by listing and/or watermark reference, newest first
+    pub async fn list_reveals(
+        &self,
+        auth_user: &AuthUser,
+        listing_id: Option<Uuid>,
+        reference: Option<&str>,
+    ) -> Result<Vec<CouponRevealEvent>, AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+
+        if listing_id.is_none() && reference.is_none() {
+            return Err(AppError::BadRequest("Filter by listing_id or reference".to_string()));
+        }
+
+        let mut query = QueryBuilder::<Postgres>::new("SELECT * FROM marketplace_coupon_reveals WHERE 1=1");
+        if let Some(listing_id) = listing_id {
+            query.push(" AND listing_id = ").push_bind(listing_id);
+        }
+        if let Some(reference) = reference {
+            query.push(" AND reference = ").push_bind(reference.trim().to_uppercase());
+        }
+        query.push(" ORDER BY revealed_at DESC LIMIT 200");
+
+        let reveals = query
+            .build_query_as::<CouponRevealEvent>()
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(reveals)
+    }
+}
+
+// Use up a reveal token of the caller for `listing_id`
+async fn consume_token(
+    tx: &mut Transaction<'_, Postgres>,
+    auth_user: &AuthUser,
+    listing_id: Uuid,
+    token: &str,
+) -> Result<(), AppError> {
+    let consumed = sqlx::query(
+        r#"
+        UPDATE marketplace_coupon_reveal_tokens
+        SET used_at = CURRENT_TIMESTAMP
+        WHERE token_hash = $1 AND listing_id = $2 AND user_id = $3
+        AND used_at IS NULL AND expires_at > CURRENT_TIMESTAMP
+        RETURNING token_hash
+        "#
+    )
+    .bind(hash_token(token))
+    .bind(listing_id)
+    .bind(&auth_user.0.auth0_id)
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    if consumed.is_none() {
+        return Err(AppError::Forbidden("Reveal token is invalid, expired or already used".to_string()));
+    }
+    Ok(())
+}
+
+fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+// Short, human-readable id of one reveal, safe to print next to the code
+fn watermark_reference(reveal_id: Uuid, user_id: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(reveal_id.as_bytes());
+    hasher.update(user_id.as_bytes());
+    format!("{:x}", hasher.finalize())[..REFERENCE_LENGTH].to_uppercase()
+}
@@ -0,0 +1,43 @@
+use crate::config::{Config, DatabaseSettings};
+use crate::error::AppError;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::PgPool;
+use std::future::Future;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// Connect the primary pool for `url` with the configured size and timeouts; call once at startup
+pub async fn connect_primary(url: &str) -> Result<PgPool, AppError> {
+    let settings = &Config::get().database;
+    connect(url, settings, settings.max_connections).await
+}
+
+/// Connect a pool of up to `max_connections` with the configured acquire and statement timeouts
+pub async fn connect(url: &str, settings: &DatabaseSettings, max_connections: u32) -> Result<PgPool, AppError> {
+    let mut options = PgConnectOptions::from_str(url)?;
+    if settings.statement_timeout_ms > 0 {
+        // A session setting, so Postgres itself cancels runaway statements
+        options = options.options([("statement_timeout", settings.statement_timeout_ms.to_string())]);
+    }
+
+    let pool = PgPoolOptions::new()
+        .max_connections(max_connections)
+        .min_connections(settings.min_connections.min(max_connections))
+        .acquire_timeout(Duration::from_millis(settings.acquire_timeout_ms))
+        .connect_with(options)
+        .await?;
+    Ok(pool)
+}
+
+/// Await a query, warning with its `name` when it ran longer than DATABASE_SLOW_QUERY_MS
+pub async fn timed<F: Future>(name: &'static str, query: F) -> F::Output {
+    let started = Instant::now();
+    let output = query.await;
+
+    let threshold_ms = Config::get().database.slow_query_ms;
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    if threshold_ms > 0 && elapsed_ms >= threshold_ms {
+        tracing::warn!(query = name, elapsed_ms, threshold_ms, "slow query");
+    }
+    output
+}
@@ -0,0 +1,293 @@
+use crate::error::AppError;
+use crate::marketplace::jobs::Job;
+use crate::marketplace::side_effects::{self, SideEffect};
+use crate::models::marketplace::AccountDeletionRequest;
+use async_trait::async_trait;
+use sqlx::{PgPool, Postgres, Row, Transaction};
+use uuid::Uuid;
+
+// Requests anonymized per job pass
+const DELETION_BATCH_SIZE: i64 = 20;
+
+const DELETION_COLUMNS: &str = "id, status, requested_at, completed_at";
+
+/// Right-to-erasure workflow. Personal data is deleted or replaced by an opaque id
+/// (`deleted-<request id>`), so counterparties keep a consistent transaction history
+/// with amounts intact. Unallocated coupon codes on the user's listings are removed.
+/// The marketplace has no messaging yet, so there are no messages to anonymize.
+pub struct AccountDeletionService {
+    pool: PgPool,
+}
+
+impl AccountDeletionService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn request_deletion(&self, user_id: &str) -> Result<AccountDeletionRequest, AppError> {
+        // Money in flight must settle before the parties can be anonymized
+        if self.open_transaction_count(user_id).await? > 0 {
+            return Err(AppError::Conflict(
+                "Complete or cancel your open transactions before deleting your account".to_string(),
+            ));
+        }
+
+        let query = format!(
+            r#"
+            INSERT INTO marketplace_account_deletions (id, user_id, status, requested_at)
+            VALUES ($1, $2, 'pending', CURRENT_TIMESTAMP)
+            RETURNING {}
+            "#,
+            DELETION_COLUMNS
+        );
+        let request = sqlx::query_as::<_, AccountDeletionRequest>(&query)
+            .bind(Uuid::new_v4())
+            .bind(user_id)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| match AppError::from(e) {
+                AppError::Conflict(_) => AppError::Conflict("Account deletion already requested".to_string()),
+                other => other,
+            })?;
+
+        Ok(request)
+    }
+
+    /// The user's pending or failed request; completed requests no longer reference the user
+    pub async fn get_request(&self, user_id: &str) -> Result<AccountDeletionRequest, AppError> {
+        let query = format!(
+            "SELECT {} FROM marketplace_account_deletions WHERE user_id = $1 ORDER BY requested_at DESC LIMIT 1",
+            DELETION_COLUMNS
+        );
+        sqlx::query_as::<_, AccountDeletionRequest>(&query)
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("No account deletion request".to_string()))
+    }
+
+    /// Anonymize pending accounts. Returns how many were completed.
+    pub async fn process_pending(&self) -> Result<usize, AppError> {
+        let pending = sqlx::query(
+            r#"
+            SELECT id, user_id FROM marketplace_account_deletions
+            WHERE status = 'pending'
+            ORDER BY requested_at
+            LIMIT $1
+            "#
+        )
+        .bind(DELETION_BATCH_SIZE)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut completed = 0;
+        for row in pending {
+            let request_id: Uuid = row.get("id");
+            let user_id: String = row.get("user_id");
+
+            // Transactions opened since the request was made are left to settle first
+            if self.open_transaction_count(&user_id).await? > 0 {
+                continue;
+            }
+
+            let mut tx = self.pool.begin().await?;
+            match anonymize_user(&mut tx, &user_id, &format!("deleted-{}", request_id)).await {
+                Ok(()) => {
+                    sqlx::query(
+                        r#"
+                        UPDATE marketplace_account_deletions
+                        SET status = 'completed', user_id = NULL, completed_at = CURRENT_TIMESTAMP
+                        WHERE id = $1
+                        "#
+                    )
+                    .bind(request_id)
+                    .execute(&mut *tx)
+                    .await?;
+                    tx.commit().await?;
+                    // Cached profile data must not outlive the account
+                    side_effects::perform(&self.pool, SideEffect::ClearUserCaches { user_id }).await;
+                    completed += 1;
+                }
+                Err(e) => {
+                    tx.rollback().await?;
+                    sqlx::query(
+                        "UPDATE marketplace_account_deletions SET status = 'failed', error = $2 WHERE id = $1"
+                    )
+                    .bind(request_id)
+                    .bind(e.to_string())
+                    .execute(&self.pool)
+                    .await?;
+                }
+            }
+        }
+
+        Ok(completed)
+    }
+
+    async fn open_transaction_count(&self, user_id: &str) -> Result<i64, AppError> {
+        let count: i64 = sqlx::query(
+            r#"
+            SELECT COUNT(*) as open_transactions FROM marketplace_transactions
+            WHERE (buyer_id = $1 OR seller_id = $1)
+            AND status IN ('pending', 'escrow', 'disputed')
+            "#
+        )
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?
+        .get("open_transactions");
+
+        Ok(count)
+    }
+}
+
+/// Replace `user_id` with `opaque_id` where records must survive and delete the rest
+async fn anonymize_user(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: &str,
+    opaque_id: &str,
+) -> Result<(), AppError> {
+    // Codes nobody bought leave with the seller
+    sqlx::query(
+        r#"
+        DELETE FROM marketplace_coupon_codes
+        WHERE allocated_transaction_id IS NULL
+        AND listing_id IN (SELECT id FROM marketplace_listings_all WHERE seller_id = $1)
+        "#
+    )
+    .bind(user_id)
+    .execute(&mut **tx)
+    .await?;
+
+    // Edit history holds the listings' earlier text
+    sqlx::query(
+        "DELETE FROM marketplace_listing_revisions WHERE listing_id IN (SELECT id FROM marketplace_listings_all WHERE seller_id = $1)"
+    )
+    .bind(user_id)
+    .execute(&mut **tx)
+    .await?;
+
+    // So do their questions and answers
+    sqlx::query("DELETE FROM marketplace_listing_faq WHERE listing_id IN (SELECT id FROM marketplace_listings WHERE seller_id = $1)")
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+
+    // Listings stay for their buyers' history, without free text or images
+    for table in ["marketplace_listings", "marketplace_listings_archive"] {
+        sqlx::query(&format!(
+            r#"
+            UPDATE {}
+            SET seller_id = $2,
+                description = NULL,
+                description_html = NULL,
+                proof_image_url = NULL,
+                details = NULL,
+                deleted_at = COALESCE(deleted_at, CURRENT_TIMESTAMP),
+                updated_at = CURRENT_TIMESTAMP
+            WHERE seller_id = $1
+            "#,
+            table
+        ))
+        .bind(user_id)
+        .bind(opaque_id)
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    let rekeyed = [
+        "UPDATE marketplace_transactions SET buyer_id = $2 WHERE buyer_id = $1",
+        "UPDATE marketplace_transactions SET seller_id = $2 WHERE seller_id = $1",
+        "UPDATE marketplace_transaction_status_history SET actor_id = $2 WHERE actor_id = $1",
+        "UPDATE marketplace_protection_claims SET buyer_id = $2, details = NULL WHERE buyer_id = $1",
+        "UPDATE marketplace_protection_claims SET seller_id = $2, seller_response = NULL WHERE seller_id = $1",
+        "UPDATE marketplace_dispute_evidence SET uploaded_by = $2 WHERE uploaded_by = $1",
+        "UPDATE marketplace_media SET owner_id = $2 WHERE owner_id = $1",
+        "UPDATE marketplace_checkouts SET buyer_id = $2 WHERE buyer_id = $1",
+        "UPDATE marketplace_bundles SET seller_id = $2, status = 'inactive' WHERE seller_id = $1",
+        "UPDATE marketplace_refunds SET initiated_by = $2 WHERE initiated_by = $1",
+        "UPDATE marketplace_ledger_entries SET user_id = $2 WHERE user_id = $1",
+        "UPDATE marketplace_promotions SET seller_id = $2, status = CASE WHEN status IN ('pending_payment', 'scheduled', 'active') THEN 'cancelled' ELSE status END WHERE seller_id = $1",
+        "UPDATE marketplace_billing_entries SET user_id = $2 WHERE user_id = $1",
+        "UPDATE marketplace_credit_entries SET user_id = $2 WHERE user_id = $1",
+        "UPDATE marketplace_credit_entries SET created_by = $2 WHERE created_by = $1",
+        "UPDATE marketplace_promo_redemptions SET user_id = $2 WHERE user_id = $1",
+        "UPDATE marketplace_loyalty_entries SET user_id = $2 WHERE user_id = $1",
+        "UPDATE marketplace_wallet_topups SET user_id = $2 WHERE user_id = $1",
+        "UPDATE marketplace_shadow_bans SET user_id = $2 WHERE user_id = $1",
+        "UPDATE marketplace_impersonation_sessions SET user_id = $2 WHERE user_id = $1",
+        "UPDATE marketplace_impersonation_audit SET user_id = $2 WHERE user_id = $1",
+        "UPDATE marketplace_partner_accounts SET owner_id = $2 WHERE owner_id = $1",
+        "UPDATE marketplace_wallet_withdrawals SET user_id = $2 WHERE user_id = $1",
+        "UPDATE marketplace_wallet_entries SET user_id = $2 WHERE user_id = $1",
+        "UPDATE marketplace_upi_payments SET user_id = $2, vpa = NULL WHERE user_id = $1",
+        "UPDATE marketplace_paypal_orders SET user_id = $2, payer_id = NULL WHERE user_id = $1",
+        "UPDATE marketplace_payout_accounts SET user_id = $2 WHERE user_id = $1",
+        "UPDATE marketplace_promo_campaigns SET created_by = $2 WHERE created_by = $1",
+        "UPDATE marketplace_coupon_codes SET allocated_to = $2 WHERE allocated_to = $1",
+        "UPDATE marketplace_coupon_access SET user_id = $2 WHERE user_id = $1",
+        "UPDATE marketplace_coupon_reveals SET user_id = $2, ip_address = NULL, user_agent = NULL WHERE user_id = $1",
+        "UPDATE marketplace_price_history SET changed_by = $2 WHERE changed_by = $1",
+        "UPDATE marketplace_listing_revisions SET editor_id = $2 WHERE editor_id = $1",
+        "UPDATE marketplace_reviews SET reviewer_id = $2, review_text = NULL WHERE reviewer_id = $1",
+        "UPDATE marketplace_reviews SET reviewed_user_id = $2 WHERE reviewed_user_id = $1",
+        "UPDATE marketplace_seller_verifications SET reviewer_id = $2 WHERE reviewer_id = $1",
+        "UPDATE marketplace_verification_claims SET verifier_id = $2 WHERE verifier_id = $1",
+    ];
+    for statement in rekeyed {
+        sqlx::query(statement)
+            .bind(user_id)
+            .bind(opaque_id)
+            .execute(&mut **tx)
+            .await?;
+    }
+
+    let deleted = [
+        "DELETE FROM marketplace_notifications WHERE user_id = $1",
+        "DELETE FROM marketplace_notification_queue WHERE user_id = $1",
+        "DELETE FROM marketplace_notification_digests WHERE user_id = $1",
+        "DELETE FROM marketplace_notification_settings WHERE user_id = $1",
+        "DELETE FROM marketplace_favorites WHERE user_id = $1",
+        "DELETE FROM marketplace_cart_items WHERE user_id = $1",
+        "DELETE FROM marketplace_seller_follows WHERE follower_id = $1 OR seller_id = $1",
+        "DELETE FROM marketplace_seller_verifications WHERE user_id = $1",
+        "DELETE FROM marketplace_storefronts WHERE user_id = $1",
+        "DELETE FROM marketplace_trust_scores WHERE user_id = $1",
+        "DELETE FROM marketplace_trust_score_history WHERE user_id = $1",
+        "DELETE FROM marketplace_trust_score_queue WHERE user_id = $1",
+        "DELETE FROM marketplace_seller_quota_overrides WHERE user_id = $1",
+        "DELETE FROM marketplace_seller_badges WHERE user_id = $1",
+        "DELETE FROM marketplace_user_roles WHERE user_id = $1",
+        "DELETE FROM marketplace_rate_limits WHERE user_id = $1",
+        "DELETE FROM marketplace_data_exports WHERE user_id = $1",
+        "DELETE FROM marketplace_coupon_reveal_tokens WHERE user_id = $1",
+        "DELETE FROM marketplace_payment_methods WHERE user_id = $1",
+        "DELETE FROM marketplace_contact_violations WHERE user_id = $1",
+        "DELETE FROM marketplace_expired_code_reports WHERE user_id = $1",
+        "DELETE FROM marketplace_seller_away WHERE user_id = $1",
+        "DELETE FROM marketplace_seller_auto_replies WHERE seller_id = $1",
+    ];
+    for statement in deleted {
+        sqlx::query(statement)
+            .bind(user_id)
+            .execute(&mut **tx)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Anonymizes accounts with pending deletion requests
+pub struct AccountDeletionJob;
+
+#[async_trait]
+impl Job for AccountDeletionJob {
+    fn name(&self) -> &'static str {
+        "account_deletions"
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<(), AppError> {
+        AccountDeletionService::new(pool.clone()).process_pending().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,186 @@
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::marketplace::authorization::AuthorizationPolicy;
+use crate::marketplace::media::{MediaContent, MediaService};
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{DisputeCase, DisputeEvidence, MarketplaceTransaction, TransactionStatus};
+use sqlx::{FromRow, PgPool, Row};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+// Largest evidence file; the upload route's body limit allows for it
+pub const MAX_EVIDENCE_BYTES: usize = 4 * 1024 * 1024;
+
+// Files per transaction, across both parties
+const MAX_EVIDENCE_FILES: i64 = 10;
+
+const EVIDENCE_COLUMNS: &str = r#"
+    e.id, e.transaction_id, e.uploaded_by, e.file_name, m.content_type, m.size_bytes, e.created_at,
+    '/api/v1/marketplace/transactions/' || e.transaction_id || '/dispute/evidence/' || e.id as url
+"#;
+
+const EVIDENCE_FROM: &str = r#"
+    FROM marketplace_dispute_evidence e
+    JOIN marketplace_media m ON m.id = e.media_id
+"#;
+
+/// Screenshots and documents attached to a disputed transaction. Either party may add
+/// them while the dispute is open; only the parties and admins can list or download them.
+pub struct DisputeEvidenceService {
+    pool: PgPool,
+}
+
+impl DisputeEvidenceService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Attach a file to the caller's disputed transaction
+    pub async fn upload(
+        &self,
+        auth_user: &AuthUser,
+        transaction_id: Uuid,
+        content_type: &str,
+        file_name: Option<&str>,
+        data: &[u8],
+    ) -> Result<DisputeEvidence, AppError> {
+        let user_id = &auth_user.0.auth0_id;
+        let mut tx = self.pool.begin().await?;
+
+        // Locked so concurrent uploads cannot exceed the file limit together
+        let transaction = sqlx::query_as::<_, MarketplaceTransaction>(
+            "SELECT * FROM marketplace_transactions WHERE id = $1 FOR UPDATE"
+        )
+        .bind(transaction_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Transaction not found".to_string()))?;
+
+        if transaction.buyer_id != *user_id && transaction.seller_id != *user_id {
+            return Err(AppError::Forbidden("You are not part of this transaction".to_string()));
+        }
+        if transaction.status != TransactionStatus::Disputed {
+            return Err(AppError::Conflict("Evidence can only be added while the transaction is disputed".to_string()));
+        }
+
+        let files: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM marketplace_dispute_evidence WHERE transaction_id = $1")
+            .bind(transaction_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        if files >= MAX_EVIDENCE_FILES {
+            return Err(AppError::Conflict(format!("A dispute can have at most {} evidence files", MAX_EVIDENCE_FILES)));
+        }
+
+        let media_id = MediaService::store(&mut tx, user_id, content_type, data, MAX_EVIDENCE_BYTES).await?;
+
+        let evidence_id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_dispute_evidence (id, transaction_id, uploaded_by, media_id, file_name, created_at)
+            VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP)
+            "#
+        )
+        .bind(evidence_id)
+        .bind(transaction_id)
+        .bind(user_id)
+        .bind(media_id)
+        .bind(file_name.map(str::trim).filter(|name| !name.is_empty()))
+        .execute(&mut *tx)
+        .await?;
+
+        let evidence = sqlx::query_as::<_, DisputeEvidence>(&format!(
+            "SELECT {} {} WHERE e.id = $1",
+            EVIDENCE_COLUMNS, EVIDENCE_FROM
+        ))
+        .bind(evidence_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(evidence)
+    }
+
+    /// Evidence attached to the transaction, oldest first
+    pub async fn list(&self, auth_user: &AuthUser, transaction_id: Uuid) -> Result<Vec<DisputeEvidence>, AppError> {
+        self.ensure_can_view(auth_user, transaction_id).await?;
+
+        let evidence = sqlx::query_as::<_, DisputeEvidence>(&format!(
+            "SELECT {} {} WHERE e.transaction_id = $1 ORDER BY e.created_at",
+            EVIDENCE_COLUMNS, EVIDENCE_FROM
+        ))
+        .bind(transaction_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(evidence)
+    }
+
+    /// A file attached to the transaction, with its name if one was given
+    pub async fn download(
+        &self,
+        auth_user: &AuthUser,
+        transaction_id: Uuid,
+        evidence_id: Uuid,
+    ) -> Result<(MediaContent, Option<String>), AppError> {
+        self.ensure_can_view(auth_user, transaction_id).await?;
+
+        let row = sqlx::query("SELECT media_id, file_name FROM marketplace_dispute_evidence WHERE id = $1 AND transaction_id = $2")
+            .bind(evidence_id)
+            .bind(transaction_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Evidence not found".to_string()))?;
+
+        let content = MediaService::new(self.pool.clone()).fetch(row.get("media_id")).await?;
+        Ok((content, row.get("file_name")))
+    }
+
+    /// Open disputes, oldest first, with their evidence for admins resolving them
+    pub async fn open_disputes(&self, auth_user: &AuthUser) -> Result<Vec<DisputeCase>, AppError> {
+        AuthorizationPolicy::new(self.pool.clone()).can_resolve_dispute(&auth_user.0.auth0_id).await?;
+
+        let rows = sqlx::query(
+            r#"
+            SELECT t.*,
+                   (SELECT c.id FROM marketplace_protection_claims c
+                    WHERE c.transaction_id = t.id ORDER BY c.created_at DESC LIMIT 1) as protection_claim_id
+            FROM marketplace_transactions t
+            WHERE t.status = 'disputed'
+            ORDER BY t.created_at ASC
+            LIMIT 100
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let ids: Vec<Uuid> = rows.iter().map(|row| row.get("id")).collect();
+        let mut evidence_by_transaction: HashMap<Uuid, Vec<DisputeEvidence>> = HashMap::new();
+        let evidence = sqlx::query_as::<_, DisputeEvidence>(&format!(
+            "SELECT {} {} WHERE e.transaction_id = ANY($1) ORDER BY e.created_at",
+            EVIDENCE_COLUMNS, EVIDENCE_FROM
+        ))
+        .bind(&ids)
+        .fetch_all(&self.pool)
+        .await?;
+        for item in evidence {
+            evidence_by_transaction.entry(item.transaction_id).or_default().push(item);
+        }
+
+        rows.iter()
+            .map(|row| {
+                let transaction = MarketplaceTransaction::from_row(row)?;
+                Ok(DisputeCase {
+                    protection_claim_id: row.get("protection_claim_id"),
+                    evidence: evidence_by_transaction.remove(&transaction.id).unwrap_or_default(),
+                    transaction,
+                })
+            })
+            .collect()
+    }
+
+    async fn ensure_can_view(&self, auth_user: &AuthUser, transaction_id: Uuid) -> Result<(), AppError> {
+        let service = MarketplaceService::new(self.pool.clone());
+        let transaction = service.get_transaction_by_id(transaction_id).await?;
+        service.authorization().can_view_transaction(&auth_user.0.auth0_id, &transaction).await
+    }
+}
@@ -14,7 +14,7 @@ impl DuplicateDetector {
     }
 
     /// Generate a fingerprint for a coupon code
-    fn generate_fingerprint(code: &str, category: &str, brand: Option<&str>) -> String {
+    pub(crate) fn generate_fingerprint(code: &str, category: &str, brand: Option<&str>) -> String {
         let mut hasher = Sha256::new();
         
         // Normalize the code (uppercase, remove spaces)
@@ -78,6 +78,7 @@ impl DuplicateDetector {
             FROM marketplace_listings ml
             LEFT JOIN users u ON ml.seller_id = u.auth0_id
             WHERE ml.status = 'active'
+            AND ml.deleted_at IS NULL
             AND ml.category = $1
             AND ml.seller_id != $2
             AND ($3::text IS NULL OR ml.brand_name = $3)
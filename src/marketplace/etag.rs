@@ -0,0 +1,75 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::Request,
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+
+// Larger bodies are not worth hashing; they are returned without an ETag
+const MAX_ETAG_BODY_BYTES: usize = 8 * 1024 * 1024;
+
+/// ETag / If-None-Match for read-heavy GET endpoints. The tag is a hash of the response
+/// body, so clients revalidating an unchanged result get an empty 304 instead of the payload.
+/// Tags are weak because compression further out may change the bytes on the wire.
+pub async fn conditional_get(request: Request, next: Next) -> Response {
+    if request.method() != Method::GET {
+        return next.run(request).await;
+    }
+
+    let if_none_match = request.headers().get(header::IF_NONE_MATCH).cloned();
+    let response = next.run(request).await;
+
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, MAX_ETAG_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!(error = %e, "response body could not be buffered for ETag");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let etag = format!("W/\"{:x}\"", Sha256::digest(&bytes));
+    let Ok(etag_value) = HeaderValue::from_str(&etag) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+
+    parts.headers.insert(header::ETAG, etag_value.clone());
+    // Clients may keep the result but must revalidate before reuse, unless the handler set its own policy
+    let cache_control = parts
+        .headers
+        .get(header::CACHE_CONTROL)
+        .cloned()
+        .unwrap_or_else(|| HeaderValue::from_static("no-cache"));
+    parts.headers.insert(header::CACHE_CONTROL, cache_control.clone());
+
+    if if_none_match.as_ref().is_some_and(|value| matches(value, &etag)) {
+        let mut not_modified = StatusCode::NOT_MODIFIED.into_response();
+        not_modified.headers_mut().insert(header::ETAG, etag_value);
+        not_modified.headers_mut().insert(header::CACHE_CONTROL, cache_control);
+        if let Some(vary) = parts.headers.get(header::VARY) {
+            not_modified.headers_mut().insert(header::VARY, vary.clone());
+        }
+        return not_modified;
+    }
+
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+// If-None-Match uses weak comparison and may list several tags or "*"
+fn matches(if_none_match: &HeaderValue, etag: &str) -> bool {
+    let Ok(value) = if_none_match.to_str() else {
+        return false;
+    };
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let current = opaque(etag);
+
+    value
+        .split(',')
+        .any(|candidate| candidate.trim() == "*" || opaque(candidate) == current)
+}
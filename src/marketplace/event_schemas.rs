@@ -0,0 +1,405 @@
+use crate::auth::AuthUser;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::marketplace::outbox::{self, event_types, OutboxEvent};
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{
+    Chargeback, CommissionTier, EventSchemaInfo, ListingDeletedEvent, ListingStatus, ListingType,
+    MarketplaceListing, MarketplaceReview, MarketplaceTransaction, RefundStatus, SendTestEventRequest,
+    TestEventResponse, TransactionChargedBackEvent, TransactionRefund, TransactionRefundedEvent,
+    TransactionStatus,
+};
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, TimeZone, Utc};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::PgPool;
+use std::str::FromStr;
+use uuid::Uuid;
+
+// Sample events go here instead of the live subjects, so only subscribers testing their parsing see them
+const TEST_SUBJECT_PREFIX: &str = "marketplace.test";
+
+/// The payload contract of one event type. A change subscribers could notice, such as a
+/// removed or renamed field, needs a new version; added fields keep the version.
+pub struct EventSchema {
+    pub event_type: &'static str,
+    pub version: u32,
+    pub aggregate_type: &'static str,
+    pub description: &'static str,
+    sample: fn() -> Value,
+    // Only read by the registry tests, which hold every sample to its payload type
+    #[cfg_attr(not(test), allow(dead_code))]
+    round_trip: fn(&Value) -> Result<Value, serde_json::Error>,
+}
+
+impl EventSchema {
+    /// The event type with its payload version, e.g. `transaction.completed.v1`
+    pub fn versioned_type(&self) -> String {
+        format!("{}.v{}", self.event_type, self.version)
+    }
+
+    /// Whether `payload` has the registered shape: it reads as the payload type and
+    /// writes back unchanged, so no field was added, dropped or retyped on either side
+    #[cfg(test)]
+    fn check(&self, payload: &Value) -> Result<(), String> {
+        let written = (self.round_trip)(payload)
+            .map_err(|e| format!("{} does not match: {}", self.versioned_type(), e))?;
+        if written != *payload {
+            return Err(format!("{} does not round-trip unchanged", self.versioned_type()));
+        }
+        Ok(())
+    }
+}
+
+pub static EVENT_SCHEMAS: [EventSchema; 11] = [
+    EventSchema {
+        event_type: event_types::LISTING_CREATED,
+        version: 1,
+        aggregate_type: "listing",
+        description: "A listing was created; the payload is the listing",
+        sample: || payload(sample_listing()),
+        round_trip: round_trip::<MarketplaceListing>,
+    },
+    EventSchema {
+        event_type: event_types::LISTING_UPDATED,
+        version: 1,
+        aggregate_type: "listing",
+        description: "A listing was edited or changed status; the payload is the listing as updated",
+        sample: || payload(sample_listing()),
+        round_trip: round_trip::<MarketplaceListing>,
+    },
+    EventSchema {
+        event_type: event_types::LISTING_DELETED,
+        version: 1,
+        aggregate_type: "listing",
+        description: "A seller deleted a listing",
+        sample: || payload(ListingDeletedEvent { listing_id: sample_id(1), seller_id: sample_user("seller") }),
+        round_trip: round_trip::<ListingDeletedEvent>,
+    },
+    EventSchema {
+        event_type: event_types::TRANSACTION_CREATED,
+        version: 1,
+        aggregate_type: "transaction",
+        description: "A buyer started a purchase; the payload is the pending transaction",
+        sample: || payload(sample_transaction(TransactionStatus::Pending)),
+        round_trip: round_trip::<MarketplaceTransaction>,
+    },
+    EventSchema {
+        event_type: event_types::TRANSACTION_PAID,
+        version: 1,
+        aggregate_type: "transaction",
+        description: "The buyer's payment was received and is held in escrow",
+        sample: || payload(sample_transaction(TransactionStatus::Escrow)),
+        round_trip: round_trip::<MarketplaceTransaction>,
+    },
+    EventSchema {
+        event_type: event_types::TRANSACTION_COMPLETED,
+        version: 1,
+        aggregate_type: "transaction",
+        description: "The buyer confirmed receipt or escrow ran out; funds go to the seller",
+        sample: || payload(sample_transaction(TransactionStatus::Completed)),
+        round_trip: round_trip::<MarketplaceTransaction>,
+    },
+    EventSchema {
+        event_type: event_types::TRANSACTION_CANCELLED,
+        version: 1,
+        aggregate_type: "transaction",
+        description: "A transaction was cancelled before completing",
+        sample: || payload(sample_transaction(TransactionStatus::Cancelled)),
+        round_trip: round_trip::<MarketplaceTransaction>,
+    },
+    EventSchema {
+        event_type: event_types::TRANSACTION_DISPUTED,
+        version: 1,
+        aggregate_type: "transaction",
+        description: "The buyer disputed a transaction in escrow",
+        sample: || payload(sample_transaction(TransactionStatus::Disputed)),
+        round_trip: round_trip::<MarketplaceTransaction>,
+    },
+    EventSchema {
+        event_type: event_types::TRANSACTION_REFUNDED,
+        version: 1,
+        aggregate_type: "transaction",
+        description: "Part or all of a payment was refunded; the payload has the transaction and the refund",
+        sample: || {
+            let transaction = sample_transaction(TransactionStatus::Cancelled);
+            payload(TransactionRefundedEvent {
+                refund: TransactionRefund {
+                    id: sample_id(3),
+                    transaction_id: transaction.id,
+                    amount: transaction.amount.clone(),
+                    reason: "Code was already used".to_string(),
+                    initiated_by: sample_user("admin"),
+                    status: RefundStatus::Succeeded,
+                    provider_refund_id: Some("rfnd_sample".to_string()),
+                    created_at: sample_time(),
+                    completed_at: Some(sample_time()),
+                },
+                transaction,
+            })
+        },
+        round_trip: round_trip::<TransactionRefundedEvent>,
+    },
+    EventSchema {
+        event_type: event_types::TRANSACTION_CHARGED_BACK,
+        version: 1,
+        aggregate_type: "transaction",
+        description: "The buyer's bank reversed the payment; the payload has the transaction and the chargeback",
+        sample: || {
+            let transaction = sample_transaction(TransactionStatus::ChargedBack);
+            payload(TransactionChargedBackEvent {
+                chargeback: Chargeback {
+                    id: sample_id(4),
+                    reversal_id: sample_id(5),
+                    transaction_id: transaction.id,
+                    amount: transaction.amount.clone(),
+                    seller_clawback: transaction.amount.clone(),
+                    created_at: sample_time(),
+                },
+                transaction,
+            })
+        },
+        round_trip: round_trip::<TransactionChargedBackEvent>,
+    },
+    EventSchema {
+        event_type: event_types::REVIEW_CREATED,
+        version: 1,
+        aggregate_type: "review",
+        description: "A buyer or seller reviewed the other party of a completed transaction",
+        sample: || {
+            payload(MarketplaceReview {
+                id: sample_id(6),
+                transaction_id: sample_id(2),
+                reviewer_id: sample_user("buyer"),
+                reviewed_user_id: sample_user("seller"),
+                rating: 5,
+                review_text: Some("Code worked at checkout".to_string()),
+                deal_verified: true,
+                created_at: sample_time(),
+                is_buyer_review: true,
+            })
+        },
+        round_trip: round_trip::<MarketplaceReview>,
+    },
+];
+
+/// The registered schema of an unversioned event type
+pub fn schema(event_type: &str) -> Option<&'static EventSchema> {
+    EVENT_SCHEMAS.iter().find(|schema| schema.event_type == event_type)
+}
+
+fn round_trip<T: Serialize + DeserializeOwned>(payload: &Value) -> Result<Value, serde_json::Error> {
+    serde_json::to_value(serde_json::from_value::<T>(payload.clone())?)
+}
+
+fn payload<T: Serialize>(value: T) -> Value {
+    serde_json::to_value(value).expect("sample payloads serialize")
+}
+
+// Fixed ids and times, so samples are the same on every call and instance
+fn sample_id(n: u128) -> Uuid {
+    Uuid::from_u128(0x5a3b1e00_0000_4000_8000_000000000000 | n)
+}
+
+fn sample_user(role: &str) -> String {
+    format!("auth0|sample-{}", role)
+}
+
+fn sample_time() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 1, 1, 12, 0, 0).single().expect("valid sample time")
+}
+
+fn sample_amount(amount: &str) -> BigDecimal {
+    BigDecimal::from_str(amount).expect("valid sample amount")
+}
+
+fn sample_listing() -> MarketplaceListing {
+    MarketplaceListing {
+        id: sample_id(1),
+        seller_id: sample_user("seller"),
+        listing_type: ListingType::DiscountCode,
+        title: "20% off electronics".to_string(),
+        description: Some("Valid on orders over ₹2,000".to_string()),
+        description_html: Some("<p>Valid on orders over ₹2,000</p>".to_string()),
+        category: "electronics".to_string(),
+        brand_name: Some("Amazon".to_string()),
+        original_value: Some(sample_amount("500.00")),
+        selling_price: sample_amount("350.00"),
+        discount_percentage: Some(sample_amount("30.00")),
+        expiration_date: Some(sample_time()),
+        proof_image_url: None,
+        status: ListingStatus::Active,
+        created_at: sample_time(),
+        updated_at: sample_time(),
+        view_count: 42,
+        tags: vec!["amazon".to_string(), "electronics".to_string()],
+        is_verified: true,
+        verification_date: Some(sample_time()),
+        details: Some(serde_json::json!({ "minimum_order_value": "2000.00", "single_use": true })),
+        quantity: 1,
+        remaining_quantity: 1,
+        deleted_at: None,
+        market_discount_score: None,
+        market_discount_basis: None,
+        language: "en".to_string(),
+        region: "US".to_string(),
+    }
+}
+
+fn sample_transaction(status: TransactionStatus) -> MarketplaceTransaction {
+    MarketplaceTransaction {
+        id: sample_id(2),
+        listing_id: sample_id(1),
+        buyer_id: sample_user("buyer"),
+        seller_id: sample_user("seller"),
+        amount: sample_amount("350.00"),
+        status,
+        payment_method: Some("upi".to_string()),
+        payment_id: (status != TransactionStatus::Pending).then(|| "pay_sample".to_string()),
+        escrow_release_date: (status == TransactionStatus::Escrow).then(sample_time),
+        created_at: sample_time(),
+        completed_at: (status == TransactionStatus::Completed).then(sample_time),
+        cancellation_reason: (status == TransactionStatus::Cancelled).then(|| "Buyer changed their mind".to_string()),
+        dispute_reason: (status == TransactionStatus::Disputed).then(|| "Code was rejected at checkout".to_string()),
+        commission_tier: Some(CommissionTier::Standard),
+        platform_fee: Some(sample_amount("17.50")),
+        refunded_amount: sample_amount("0.00"),
+        checkout_id: None,
+        discount_amount: sample_amount("0.00"),
+        tax_amount: sample_amount("0.00"),
+    }
+}
+
+/// The event contracts subscribers build against, and sample events to test them with
+pub struct EventSchemaService {
+    pool: PgPool,
+}
+
+impl EventSchemaService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Every published event type with its version and a sample payload
+    pub async fn list(&self, auth_user: &AuthUser) -> Result<Vec<EventSchemaInfo>, AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+
+        Ok(EVENT_SCHEMAS
+            .iter()
+            .map(|schema| EventSchemaInfo {
+                event_type: schema.event_type.to_string(),
+                version: schema.version,
+                versioned_type: schema.versioned_type(),
+                aggregate_type: schema.aggregate_type.to_string(),
+                description: schema.description.to_string(),
+                sample_payload: (schema.sample)(),
+            })
+            .collect())
+    }
+
+    /// Publish a sample of the event type to `marketplace.test.<event_type>`, shaped exactly
+    /// like a live event, so a subscriber can check it parses what will be sent
+    pub async fn send_test_event(
+        &self,
+        auth_user: &AuthUser,
+        request: SendTestEventRequest,
+    ) -> Result<TestEventResponse, AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+
+        let schema = schema(&request.event_type)
+            .ok_or_else(|| AppError::NotFound(format!("Unknown event type: {}", request.event_type)))?;
+        let payload = (schema.sample)();
+        let aggregate_id = payload
+            .get("id")
+            .or_else(|| payload.get("listing_id"))
+            .or_else(|| payload.pointer("/transaction/id"))
+            .and_then(Value::as_str)
+            .and_then(|id| Uuid::parse_str(id).ok())
+            .unwrap_or_else(Uuid::nil);
+
+        let event = OutboxEvent {
+            id: Uuid::new_v4(),
+            aggregate_type: schema.aggregate_type.to_string(),
+            aggregate_id,
+            event_type: schema.event_type.to_string(),
+            schema: Some(schema.versioned_type()),
+            payload,
+            created_at: Utc::now(),
+        };
+        let subject = format!("{}.{}", TEST_SUBJECT_PREFIX, schema.event_type);
+
+        let publisher = outbox::publisher_from_config(Config::get()).await?;
+        publisher.publish(&subject, &event).await?;
+
+        tracing::info!(admin_id = %auth_user.0.auth0_id, %subject, event_id = %event.id, "sent test event");
+        Ok(TestEventResponse {
+            subject,
+            event_id: event.id,
+            schema: schema.versioned_type(),
+            payload: event.payload,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn every_sample_round_trips_through_its_payload_type() {
+        for schema in &EVENT_SCHEMAS {
+            assert_eq!(schema.check(&(schema.sample)()), Ok(()), "{}", schema.versioned_type());
+        }
+    }
+
+    #[test]
+    fn check_rejects_added_and_dropped_fields() {
+        let schema = schema(event_types::LISTING_DELETED).expect("listing.deleted is registered");
+
+        let mut added = (schema.sample)();
+        added["reason"] = Value::from("spam");
+        assert!(schema.check(&added).is_err());
+
+        let mut dropped = (schema.sample)();
+        dropped.as_object_mut().expect("object payload").remove("seller_id");
+        assert!(schema.check(&dropped).is_err());
+    }
+
+    #[test]
+    fn every_event_type_has_one_schema() {
+        let types = [
+            event_types::LISTING_CREATED,
+            event_types::LISTING_UPDATED,
+            event_types::LISTING_DELETED,
+            event_types::TRANSACTION_CREATED,
+            event_types::TRANSACTION_PAID,
+            event_types::TRANSACTION_COMPLETED,
+            event_types::TRANSACTION_CANCELLED,
+            event_types::TRANSACTION_DISPUTED,
+            event_types::TRANSACTION_REFUNDED,
+            event_types::TRANSACTION_CHARGED_BACK,
+            event_types::REVIEW_CREATED,
+        ];
+        for event_type in types {
+            assert_eq!(
+                EVENT_SCHEMAS.iter().filter(|schema| schema.event_type == event_type).count(),
+                1,
+                "{}",
+                event_type
+            );
+        }
+
+        let versioned: HashSet<String> = EVENT_SCHEMAS.iter().map(EventSchema::versioned_type).collect();
+        assert_eq!(versioned.len(), EVENT_SCHEMAS.len());
+    }
+
+    #[test]
+    fn samples_are_the_same_on_every_call() {
+        for schema in &EVENT_SCHEMAS {
+            assert_eq!((schema.sample)(), (schema.sample)(), "{}", schema.versioned_type());
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::marketplace::moderation::ModerationService;
+use crate::marketplace::outbox::{event_types, OutboxService};
+use crate::marketplace::rate_limiter::{ActionType, RateLimiter};
+use crate::marketplace::replica;
+use crate::marketplace::away::SELLER_NOT_AWAY;
+use crate::marketplace::shadow_bans::NOT_SHADOW_BANNED;
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{
+    ExpiredCodeReportOutcome, ListingStatus, MarketplaceListing, ModerationFlag, ModerationReason,
+};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+// Independent reports that take a listing down
+pub const EXPIRED_REPORT_THRESHOLD: i64 = 3;
+
+/// Crowd reports that a discount code has expired. Anyone signed in may report a live
+/// code listing once, whether or not they bought it; enough reports mark the listing
+/// expired, and the seller can appeal to a moderator who puts it back on sale.
+pub struct ExpiredCodeReportService {
+    pool: PgPool,
+}
+
+impl ExpiredCodeReportService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Report the listing's code as expired, taking the listing down at the threshold
+    pub async fn report(&self, auth_user: &AuthUser, listing_id: Uuid) -> Result<ExpiredCodeReportOutcome, AppError> {
+        let user_id = &auth_user.0.auth0_id;
+
+        let rate_limit = RateLimiter::new(self.pool.clone())
+            .check_and_increment(user_id, ActionType::ReportExpiredCode)
+            .await?;
+        if !rate_limit.allowed {
+            return Err(AppError::RateLimited {
+                message: "Too many expired code reports, try again later".to_string(),
+                retry_after: rate_limit.retry_after,
+            });
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        // Locked so concurrent reports count each other and only one takes the listing down
+        let listing = sqlx::query_as::<_, MarketplaceListing>(&format!(
+            r#"
+            SELECT l.* FROM marketplace_listings l
+            WHERE l.id = $1 AND l.status = 'active' AND l.deleted_at IS NULL
+            AND l.listing_type = 'discount_code' AND {} AND {}
+            FOR UPDATE OF l
+            "#,
+            NOT_SHADOW_BANNED, SELLER_NOT_AWAY
+        ))
+        .bind(listing_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Listing not found".to_string()))?;
+
+        if listing.seller_id == *user_id {
+            return Err(AppError::Forbidden("You cannot report your own listing".to_string()));
+        }
+
+        let reported = sqlx::query(
+            r#"
+            INSERT INTO marketplace_expired_code_reports (listing_id, user_id, created_at)
+            VALUES ($1, $2, CURRENT_TIMESTAMP)
+            ON CONFLICT (listing_id, user_id) DO NOTHING
+            "#
+        )
+        .bind(listing_id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        if reported == 0 {
+            return Err(AppError::Conflict("You already reported this code".to_string()));
+        }
+
+        let report_count = report_count(&mut tx, listing_id).await?;
+        let taken_down = report_count >= EXPIRED_REPORT_THRESHOLD;
+
+        let expired = if taken_down {
+            let expired = sqlx::query_as::<_, MarketplaceListing>(
+                r#"
+                UPDATE marketplace_listings
+                SET status = $2, updated_at = CURRENT_TIMESTAMP
+                WHERE id = $1
+                RETURNING *
+                "#
+            )
+            .bind(listing_id)
+            .bind(ListingStatus::Expired)
+            .fetch_one(&mut *tx)
+            .await?;
+
+            OutboxService::record(&mut tx, "listing", listing_id, event_types::LISTING_UPDATED, &expired).await?;
+            Some(expired)
+        } else {
+            None
+        };
+
+        tx.commit().await?;
+
+        if let Some(expired) = expired {
+            replica::listing_written(listing_id);
+            tracing::info!(%listing_id, report_count, "listing taken down after expired code reports");
+
+            MarketplaceService::new(self.pool.clone()).create_notification(
+                &expired.seller_id,
+                "listing_reported_expired",
+                "Listing marked expired",
+                &format!(
+                    "\"{}\" was marked expired after {} people reported that its code no longer works. \
+                     If the code still works, appeal from the listing and tell us how you checked it; \
+                     a moderator will review the appeal and put the listing back on sale.",
+                    expired.title, report_count
+                ),
+                Some(listing_id),
+                None,
+            ).await;
+        }
+
+        Ok(ExpiredCodeReportOutcome {
+            listing_id,
+            report_count,
+            taken_down,
+        })
+    }
+
+    /// Appeal a takedown: the listing is held for a moderator, who relists or suspends it
+    pub async fn appeal(
+        &self,
+        auth_user: &AuthUser,
+        listing_id: Uuid,
+        message: &str,
+    ) -> Result<MarketplaceListing, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let listing = sqlx::query_as::<_, MarketplaceListing>(
+            r#"
+            SELECT * FROM marketplace_listings
+            WHERE id = $1 AND seller_id = $2 AND deleted_at IS NULL
+            FOR UPDATE
+            "#
+        )
+        .bind(listing_id)
+        .bind(&auth_user.0.auth0_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Listing not found".to_string()))?;
+
+        let report_count = report_count(&mut tx, listing_id).await?;
+        if listing.status != ListingStatus::Expired || report_count < EXPIRED_REPORT_THRESHOLD {
+            return Err(AppError::Conflict("Listing was not taken down by expired code reports".to_string()));
+        }
+
+        let held = sqlx::query_as::<_, MarketplaceListing>(
+            r#"
+            UPDATE marketplace_listings
+            SET status = $2, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(listing_id)
+        .bind(ListingStatus::PendingReview)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let flag = ModerationFlag {
+            reason: ModerationReason::ExpiredReports,
+            field: "coupon_code".to_string(),
+            detail: format!("{} expired code reports; seller says: {}", report_count, message.trim()),
+        };
+        ModerationService::enqueue(&mut tx, listing_id, &listing.seller_id, &[flag]).await?;
+
+        // The reports led to this takedown; a relisted code starts over
+        sqlx::query("DELETE FROM marketplace_expired_code_reports WHERE listing_id = $1")
+            .bind(listing_id)
+            .execute(&mut *tx)
+            .await?;
+
+        OutboxService::record(&mut tx, "listing", listing_id, event_types::LISTING_UPDATED, &held).await?;
+        tx.commit().await?;
+
+        replica::listing_written(listing_id);
+        Ok(held)
+    }
+}
+
+async fn report_count(tx: &mut Transaction<'_, Postgres>, listing_id: Uuid) -> Result<i64, AppError> {
+    let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM marketplace_expired_code_reports WHERE listing_id = $1")
+        .bind(listing_id)
+        .fetch_one(&mut **tx)
+        .await?;
+    Ok(count)
+}
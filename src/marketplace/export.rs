@@ -0,0 +1,329 @@
+use crate::error::AppError;
+use crate::marketplace::jobs::Job;
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{
+    DataExport, ExportFormat, ExportStatus, MarketplaceListing, MarketplaceNotification,
+    MarketplaceReview, MarketplaceTransaction, UserDataArchive,
+};
+use async_trait::async_trait;
+use chrono::Utc;
+use serde_json::Value;
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+// Ready archives are downloadable for this many days
+const EXPORT_RETENTION_DAYS: i32 = 7;
+
+// Exports generated per job pass
+const EXPORT_BATCH_SIZE: i64 = 10;
+
+const EXPORT_COLUMNS: &str = "id, user_id, format, status, created_at, completed_at, expires_at";
+
+/// GDPR data portability: users request an archive of their marketplace data,
+/// a background job builds it and notifies them when it can be downloaded.
+/// The marketplace has no messaging yet, so archives contain no messages.
+pub struct DataExportService {
+    pool: PgPool,
+}
+
+impl DataExportService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Return the user's outstanding or downloadable export in `format`, queueing a new one if there is none.
+    /// The flag is true when a new export was queued.
+    pub async fn request_export(&self, user_id: &str, format: ExportFormat) -> Result<(DataExport, bool), AppError> {
+        let query = format!(
+            r#"
+            SELECT {} FROM marketplace_data_exports
+            WHERE user_id = $1 AND format = $2
+            AND (status = 'pending' OR (status = 'ready' AND expires_at > CURRENT_TIMESTAMP))
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#,
+            EXPORT_COLUMNS
+        );
+        let existing = sqlx::query_as::<_, DataExport>(&query)
+            .bind(user_id)
+            .bind(format)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        if let Some(export) = existing {
+            return Ok((export, false));
+        }
+
+        let query = format!(
+            r#"
+            INSERT INTO marketplace_data_exports (id, user_id, format, status, created_at)
+            VALUES ($1, $2, $3, 'pending', CURRENT_TIMESTAMP)
+            RETURNING {}
+            "#,
+            EXPORT_COLUMNS
+        );
+        let export = sqlx::query_as::<_, DataExport>(&query)
+            .bind(Uuid::new_v4())
+            .bind(user_id)
+            .bind(format)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok((export, true))
+    }
+
+    pub async fn get_export(&self, user_id: &str, export_id: Uuid) -> Result<DataExport, AppError> {
+        let query = format!(
+            "SELECT {} FROM marketplace_data_exports WHERE id = $1 AND user_id = $2",
+            EXPORT_COLUMNS
+        );
+        sqlx::query_as::<_, DataExport>(&query)
+            .bind(export_id)
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Export not found".to_string()))
+    }
+
+    /// The finished archive, if it is ready and has not expired
+    pub async fn download(&self, user_id: &str, export_id: Uuid) -> Result<(DataExport, Vec<u8>), AppError> {
+        let export = self.get_export(user_id, export_id).await?;
+
+        if export.status != ExportStatus::Ready {
+            return Err(AppError::Conflict("Export is not ready".to_string()));
+        }
+        if export.expires_at.is_none_or(|expires_at| expires_at <= Utc::now()) {
+            return Err(AppError::NotFound("Export has expired".to_string()));
+        }
+
+        let content: Option<Vec<u8>> = sqlx::query("SELECT content FROM marketplace_data_exports WHERE id = $1")
+            .bind(export_id)
+            .fetch_one(&self.pool)
+            .await?
+            .get("content");
+
+        let content = content.ok_or_else(|| AppError::NotFound("Export has expired".to_string()))?;
+        Ok((export, content))
+    }
+
+    pub async fn build_archive(&self, user_id: &str) -> Result<UserDataArchive, AppError> {
+        let listings = sqlx::query_as::<_, MarketplaceListing>(
+            "SELECT * FROM marketplace_listings_all WHERE seller_id = $1 ORDER BY created_at"
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let transactions = sqlx::query_as::<_, MarketplaceTransaction>(
+            "SELECT * FROM marketplace_transactions WHERE buyer_id = $1 OR seller_id = $1 ORDER BY created_at"
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let reviews_written = sqlx::query_as::<_, MarketplaceReview>(
+            "SELECT * FROM marketplace_reviews WHERE reviewer_id = $1 ORDER BY created_at"
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let reviews_received = sqlx::query_as::<_, MarketplaceReview>(
+            "SELECT * FROM marketplace_reviews WHERE reviewed_user_id = $1 ORDER BY created_at"
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let notifications = sqlx::query_as::<_, MarketplaceNotification>(
+            "SELECT * FROM marketplace_notifications WHERE user_id = $1 ORDER BY created_at"
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let favorite_listing_ids = sqlx::query_scalar::<_, Uuid>(
+            "SELECT listing_id FROM marketplace_favorites WHERE user_id = $1 ORDER BY created_at"
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let followed_seller_ids = sqlx::query_scalar::<_, String>(
+            "SELECT seller_id FROM marketplace_seller_follows WHERE follower_id = $1 ORDER BY created_at"
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(UserDataArchive {
+            user_id: user_id.to_string(),
+            generated_at: Utc::now(),
+            listings,
+            transactions,
+            reviews_written,
+            reviews_received,
+            notifications,
+            favorite_listing_ids,
+            followed_seller_ids,
+        })
+    }
+
+    /// Generate pending exports and drop the content of expired ones. Returns how many were generated.
+    pub async fn process_pending(&self) -> Result<usize, AppError> {
+        sqlx::query(
+            "UPDATE marketplace_data_exports SET content = NULL WHERE expires_at < CURRENT_TIMESTAMP AND content IS NOT NULL"
+        )
+        .execute(&self.pool)
+        .await?;
+
+        let mut tx = self.pool.begin().await?;
+
+        // Skip locked rows so concurrent runners split the queue
+        let query = format!(
+            r#"
+            SELECT {} FROM marketplace_data_exports
+            WHERE status = 'pending'
+            ORDER BY created_at
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+            "#,
+            EXPORT_COLUMNS
+        );
+        let pending = sqlx::query_as::<_, DataExport>(&query)
+            .bind(EXPORT_BATCH_SIZE)
+            .fetch_all(&mut *tx)
+            .await?;
+
+        let mut ready = Vec::new();
+        for export in &pending {
+            let rendered = match self.build_archive(&export.user_id).await {
+                Ok(archive) => render(&archive, export.format),
+                Err(e) => Err(e),
+            };
+
+            match rendered {
+                Ok(content) => {
+                    sqlx::query(
+                        r#"
+                        UPDATE marketplace_data_exports
+                        SET status = 'ready', content = $2, completed_at = CURRENT_TIMESTAMP,
+                            expires_at = CURRENT_TIMESTAMP + make_interval(days => $3)
+                        WHERE id = $1
+                        "#
+                    )
+                    .bind(export.id)
+                    .bind(content)
+                    .bind(EXPORT_RETENTION_DAYS)
+                    .execute(&mut *tx)
+                    .await?;
+                    ready.push(export);
+                }
+                Err(e) => {
+                    sqlx::query(
+                        "UPDATE marketplace_data_exports SET status = 'failed', error = $2, completed_at = CURRENT_TIMESTAMP WHERE id = $1"
+                    )
+                    .bind(export.id)
+                    .bind(e.to_string())
+                    .execute(&mut *tx)
+                    .await?;
+                }
+            }
+        }
+
+        tx.commit().await?;
+
+        let marketplace = MarketplaceService::new(self.pool.clone());
+        for export in &ready {
+            marketplace.create_notification(
+                &export.user_id,
+                "data_export_ready",
+                "Your data export is ready",
+                &format!(
+                    "Download it from /api/v1/marketplace/export/{}/download within {} days",
+                    export.id, EXPORT_RETENTION_DAYS
+                ),
+                None,
+                None,
+            ).await;
+        }
+
+        Ok(ready.len())
+    }
+}
+
+/// Serialize an archive. CSV uses one row per field (`section, record, field, value`)
+/// so every section fits in a single file.
+pub fn render(archive: &UserDataArchive, format: ExportFormat) -> Result<Vec<u8>, AppError> {
+    match format {
+        ExportFormat::Json => serde_json::to_vec_pretty(archive)
+            .map_err(|e| AppError::InternalError(format!("Serialization error: {}", e))),
+        ExportFormat::Csv => {
+            let value = serde_json::to_value(archive)
+                .map_err(|e| AppError::InternalError(format!("Serialization error: {}", e)))?;
+
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            let csv_error = |e: csv::Error| AppError::InternalError(format!("CSV error: {}", e));
+            writer.write_record(["section", "record", "field", "value"]).map_err(csv_error)?;
+
+            if let Value::Object(sections) = value {
+                for (section, records) in sections {
+                    match records {
+                        Value::Array(records) => {
+                            for (index, record) in records.into_iter().enumerate() {
+                                let record_id = index.to_string();
+                                match record {
+                                    Value::Object(fields) => {
+                                        for (field, value) in fields {
+                                            writer
+                                                .write_record([section.as_str(), &record_id, &field, &csv_value(&value)])
+                                                .map_err(csv_error)?;
+                                        }
+                                    }
+                                    other => {
+                                        writer
+                                            .write_record([section.as_str(), &record_id, "", &csv_value(&other)])
+                                            .map_err(csv_error)?;
+                                    }
+                                }
+                            }
+                        }
+                        other => {
+                            writer
+                                .write_record([section.as_str(), "", "", &csv_value(&other)])
+                                .map_err(csv_error)?;
+                        }
+                    }
+                }
+            }
+
+            writer
+                .into_inner()
+                .map_err(|e| AppError::InternalError(format!("CSV error: {}", e)))
+        }
+    }
+}
+
+fn csv_value(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Generates queued data exports
+pub struct DataExportJob;
+
+#[async_trait]
+impl Job for DataExportJob {
+    fn name(&self) -> &'static str {
+        "data_exports"
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<(), AppError> {
+        let service = DataExportService::new(pool.clone());
+        while service.process_pending().await? > 0 {}
+        Ok(())
+    }
+}
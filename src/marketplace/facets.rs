@@ -0,0 +1,136 @@
+use crate::config::Config;
+use crate::error::AppError;
+use crate::marketplace::cache::{cache_ttl, MarketplaceCache};
+use crate::marketplace::database;
+use crate::models::marketplace::{FacetCount, ListingFacets, ListingFilters, PriceBucketCount};
+use sha2::{Digest, Sha256};
+use sqlx::{PgPool, Postgres, QueryBuilder, Row};
+
+use super::{push_listing_filters, LISTING_WITH_SELLER_FROM};
+
+// Lower bounds of the price buckets; the last bucket is open-ended
+const PRICE_BUCKETS: [f64; 6] = [0.0, 10.0, 25.0, 50.0, 100.0, 250.0];
+
+// Values returned per facet, most common first
+const MAX_FACET_VALUES: i64 = 20;
+
+/// Facet counts for the listing search sidebar. All facets come from one grouped query
+/// over the same filters as the results and are cached briefly per filter combination.
+pub struct FacetService {
+    pool: PgPool,
+    cache: MarketplaceCache,
+}
+
+impl FacetService {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            cache: MarketplaceCache::new(Config::get().redis_url.clone()),
+        }
+    }
+
+    pub async fn get_facets(&self, filters: &ListingFilters) -> Result<ListingFacets, AppError> {
+        let key = cache_key(filters);
+        if let Ok(Some(facets)) = self.cache.get_report::<ListingFacets>(&key).await {
+            return Ok(facets);
+        }
+
+        let mut query = QueryBuilder::<Postgres>::new(format!(
+            "WITH filtered AS (SELECT l.category, l.brand_name, l.listing_type, l.selling_price, l.is_verified {} WHERE 1=1",
+            LISTING_WITH_SELLER_FROM
+        ));
+        push_listing_filters(&mut query, filters);
+        query.push(
+            r#"
+            ),
+            buckets AS (
+                SELECT width_bucket(selling_price::float8, "#,
+        );
+        query.push_bind(PRICE_BUCKETS.to_vec());
+        query.push(
+            r#") as bucket, COUNT(*) as count FROM filtered GROUP BY 1
+            )
+            (SELECT 'category' as facet, category as value, COUNT(*) as count
+                FROM filtered GROUP BY category ORDER BY count DESC, value LIMIT "#,
+        );
+        query.push_bind(MAX_FACET_VALUES);
+        query.push(
+            r#")
+            UNION ALL
+            (SELECT 'brand', brand_name, COUNT(*) as count
+                FROM filtered WHERE brand_name IS NOT NULL GROUP BY brand_name ORDER BY count DESC, brand_name LIMIT "#,
+        );
+        query.push_bind(MAX_FACET_VALUES);
+        query.push(
+            r#")
+            UNION ALL
+            (SELECT 'listing_type', listing_type::text, COUNT(*) FROM filtered GROUP BY listing_type)
+            UNION ALL
+            (SELECT 'price', bucket::text, count FROM buckets)
+            UNION ALL
+            (SELECT 'verified', 'true', COUNT(*) FROM filtered WHERE is_verified)
+            "#,
+        );
+
+        let rows = database::timed("listings.facets", query.build().fetch_all(&self.pool)).await?;
+
+        let mut facets = ListingFacets {
+            categories: Vec::new(),
+            brands: Vec::new(),
+            listing_types: Vec::new(),
+            price_buckets: Vec::new(),
+            verified_count: 0,
+        };
+
+        for row in rows {
+            let facet: String = row.get("facet");
+            let value: Option<String> = row.get("value");
+            let count: i64 = row.get("count");
+            let Some(value) = value else { continue };
+
+            match facet.as_str() {
+                "category" => facets.categories.push(FacetCount { value, count }),
+                "brand" => facets.brands.push(FacetCount { value, count }),
+                "listing_type" => facets.listing_types.push(FacetCount { value, count }),
+                "price" => {
+                    // width_bucket numbers buckets from 1; 0 would be a negative price
+                    if let Some(index) = value.parse::<usize>().ok().filter(|&i| i >= 1) {
+                        facets.price_buckets.push(PriceBucketCount {
+                            min_price: PRICE_BUCKETS[index - 1],
+                            max_price: PRICE_BUCKETS.get(index).copied(),
+                            count,
+                        });
+                    }
+                }
+                "verified" => facets.verified_count = count,
+                _ => {}
+            }
+        }
+
+        facets.listing_types.sort_by(|a, b| b.count.cmp(&a.count));
+        facets
+            .price_buckets
+            .sort_by(|a, b| a.min_price.partial_cmp(&b.min_price).unwrap_or(std::cmp::Ordering::Equal));
+
+        let _ = self.cache.cache_report(&key, &facets, cache_ttl::SEARCH_RESULTS).await;
+
+        Ok(facets)
+    }
+}
+
+// Facets depend on the filters only, not on paging or sorting
+fn cache_key(filters: &ListingFilters) -> String {
+    let scope = ListingFilters {
+        page: None,
+        limit: None,
+        sort_by: None,
+        facets: None,
+        ..filters.clone()
+    };
+
+    let mut hasher = Sha256::new();
+    hasher.update(serde_json::to_vec(&scope).unwrap_or_default());
+    hasher.update(scope.followed_by.as_deref().unwrap_or("").as_bytes());
+    hasher.update([scope.include_deleted as u8, scope.include_shadow_banned as u8, scope.include_away as u8]);
+    format!("facets:{:x}", hasher.finalize())
+}
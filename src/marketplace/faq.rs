@@ -0,0 +1,193 @@
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::marketplace::authorization::AuthorizationPolicy;
+use crate::marketplace::moderation::ModerationService;
+use crate::marketplace::replica;
+use crate::marketplace::scrubbing::ContactScrubber;
+use crate::models::marketplace::{
+    ListingFaq, ListingFaqEntry, ReplaceListingFaqRequest, SellerAutoReply, SetAutoReplyRequest,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub const MAX_FAQ_ENTRIES: usize = 20;
+pub const MAX_QUESTION_LENGTH: usize = 200;
+pub const MAX_ANSWER_LENGTH: usize = 1000;
+pub const MAX_AUTO_REPLY_LENGTH: usize = 500;
+
+// Entries shown on the listing detail; the rest are on the FAQ endpoint
+const DETAIL_FAQ_ENTRIES: i64 = 3;
+
+const ENTRY_COLUMNS: &str = "id, question, answer, position, updated_at";
+
+/// Questions and answers sellers attach to their listings, such as whether a code works on
+/// app-only orders, and the auto-reply a seller's buyers get to their first message. The
+/// first few entries are part of the listing detail so buyers see them before asking.
+/// Messages themselves are not exchanged through this service; clients show the auto-reply
+/// from the FAQ endpoint when a buyer first contacts the seller. The text is screened like
+/// listing text: contact details are kept out and entries that fail moderation are rejected.
+pub struct ListingFaqService {
+    pool: PgPool,
+}
+
+impl ListingFaqService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// All of a listing's entries in the seller's order, with the seller's auto-reply
+    pub async fn get(&self, listing_id: Uuid) -> Result<ListingFaq, AppError> {
+        let pool = replica::listing_read_pool(&self.pool, listing_id);
+        let seller_id: String = sqlx::query_scalar(
+            "SELECT seller_id FROM marketplace_listings WHERE id = $1 AND deleted_at IS NULL"
+        )
+        .bind(listing_id)
+        .fetch_optional(&pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Listing not found".to_string()))?;
+
+        let entries = self.entries(&pool, listing_id, MAX_FAQ_ENTRIES as i64).await?;
+        let auto_reply = sqlx::query_scalar("SELECT message FROM marketplace_seller_auto_replies WHERE seller_id = $1")
+            .bind(&seller_id)
+            .fetch_optional(&pool)
+            .await?;
+
+        Ok(ListingFaq {
+            listing_id,
+            entries,
+            auto_reply,
+        })
+    }
+
+    /// The entries shown on the listing detail
+    pub async fn common_questions(&self, listing_id: Uuid) -> Result<Vec<ListingFaqEntry>, AppError> {
+        let pool = replica::listing_read_pool(&self.pool, listing_id);
+        self.entries(&pool, listing_id, DETAIL_FAQ_ENTRIES).await
+    }
+
+    /// Replace a listing's entries with `request.entries`, in that order
+    pub async fn replace(
+        &self,
+        auth_user: &AuthUser,
+        listing_id: Uuid,
+        request: ReplaceListingFaqRequest,
+    ) -> Result<Vec<ListingFaqEntry>, AppError> {
+        let user_id = &auth_user.0.auth0_id;
+        let seller_id: String = sqlx::query_scalar(
+            "SELECT seller_id FROM marketplace_listings WHERE id = $1 AND deleted_at IS NULL"
+        )
+        .bind(listing_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Listing not found".to_string()))?;
+        AuthorizationPolicy::new(self.pool.clone()).can_edit_listing(&auth_user.0.auth0_id, &seller_id)?;
+
+        let scrubber = ContactScrubber::new(self.pool.clone());
+        let moderation = ModerationService::new(self.pool.clone());
+        let mut entries = Vec::with_capacity(request.entries.len());
+        for (i, entry) in request.entries.iter().enumerate() {
+            let question = entry.question.trim();
+            scrubber.reject(user_id, "question", question).await?;
+            let answer = scrubber.mask(user_id, "answer", entry.answer.trim()).await?;
+
+            let flags = moderation.screen_listing(question, Some(&answer), None).await?;
+            if !flags.is_empty() {
+                let details: Vec<String> = flags.iter().map(|flag| format!("{}: {}", flag.field, flag.detail)).collect();
+                return Err(AppError::UnprocessableEntity(format!(
+                    "Question {} did not pass moderation ({})",
+                    i + 1,
+                    details.join("; ")
+                )));
+            }
+            entries.push((question.to_string(), answer));
+        }
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM marketplace_listing_faq WHERE listing_id = $1")
+            .bind(listing_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let mut saved = Vec::with_capacity(entries.len());
+        for (position, (question, answer)) in entries.iter().enumerate() {
+            let entry = sqlx::query_as::<_, ListingFaqEntry>(&format!(
+                r#"
+                INSERT INTO marketplace_listing_faq (id, listing_id, question, answer, position, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+                RETURNING {}
+                "#,
+                ENTRY_COLUMNS
+            ))
+            .bind(Uuid::new_v4())
+            .bind(listing_id)
+            .bind(question)
+            .bind(answer)
+            .bind(position as i32)
+            .fetch_one(&mut *tx)
+            .await?;
+            saved.push(entry);
+        }
+        tx.commit().await?;
+
+        replica::listing_written(listing_id);
+        Ok(saved)
+    }
+
+    pub async fn get_auto_reply(&self, auth_user: &AuthUser) -> Result<SellerAutoReply, AppError> {
+        sqlx::query_as::<_, SellerAutoReply>(
+            "SELECT message, updated_at FROM marketplace_seller_auto_replies WHERE seller_id = $1"
+        )
+        .bind(&auth_user.0.auth0_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("No auto-reply set".to_string()))
+    }
+
+    /// Set the reply to buyers' first messages, replacing any earlier one
+    pub async fn set_auto_reply(&self, auth_user: &AuthUser, request: SetAutoReplyRequest) -> Result<SellerAutoReply, AppError> {
+        let user_id = &auth_user.0.auth0_id;
+        let message = ContactScrubber::new(self.pool.clone())
+            .mask(user_id, "message", request.message.trim())
+            .await?;
+
+        let auto_reply = sqlx::query_as::<_, SellerAutoReply>(
+            r#"
+            INSERT INTO marketplace_seller_auto_replies (seller_id, message, updated_at)
+            VALUES ($1, $2, CURRENT_TIMESTAMP)
+            ON CONFLICT (seller_id) DO UPDATE SET message = EXCLUDED.message, updated_at = EXCLUDED.updated_at
+            RETURNING message, updated_at
+            "#
+        )
+        .bind(user_id)
+        .bind(&message)
+        .fetch_one(&self.pool)
+        .await?;
+        replica::user_written(user_id);
+
+        Ok(auto_reply)
+    }
+
+    pub async fn delete_auto_reply(&self, auth_user: &AuthUser) -> Result<(), AppError> {
+        let result = sqlx::query("DELETE FROM marketplace_seller_auto_replies WHERE seller_id = $1")
+            .bind(&auth_user.0.auth0_id)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("No auto-reply set".to_string()));
+        }
+        replica::user_written(&auth_user.0.auth0_id);
+        Ok(())
+    }
+
+    async fn entries(&self, pool: &PgPool, listing_id: Uuid, limit: i64) -> Result<Vec<ListingFaqEntry>, AppError> {
+        let entries = sqlx::query_as::<_, ListingFaqEntry>(&format!(
+            "SELECT {} FROM marketplace_listing_faq WHERE listing_id = $1 ORDER BY position LIMIT $2",
+            ENTRY_COLUMNS
+        ))
+        .bind(listing_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+        Ok(entries)
+    }
+}
@@ -0,0 +1,141 @@
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{FeatureFlag, UpdateFeatureFlagRequest};
+use crate::validation::FieldError;
+use moka::sync::Cache;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Flag names checked in handlers
+pub mod flags {
+    pub const EXPIRED_CODE_REPORTS: &str = "expired_code_reports";
+}
+
+// Flags are re-read this often, so changes reach every instance within it
+const FLAG_CACHE_TTL: Duration = Duration::from_secs(30);
+
+static FLAGS: OnceLock<Cache<String, Option<FeatureFlag>>> = OnceLock::new();
+
+fn cached_flags() -> &'static Cache<String, Option<FeatureFlag>> {
+    FLAGS.get_or_init(|| Cache::builder().max_capacity(1000).time_to_live(FLAG_CACHE_TTL).build())
+}
+
+/// Feature flags for gradual rollouts. A flag is on for a user when it is enabled and
+/// the user is in its allow-list or falls in its rollout percentage; users keep their
+/// bucket as the percentage grows. Unknown flags are off.
+pub struct FeatureFlagService {
+    pool: PgPool,
+}
+
+impl FeatureFlagService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn is_enabled(&self, name: &str, user_id: &str) -> Result<bool, AppError> {
+        Ok(self.flag(name).await?.is_some_and(|flag| is_on_for(&flag, user_id)))
+    }
+
+    /// Fail as if the route did not exist when the flag is off for the user
+    pub async fn require(&self, name: &str, user_id: &str) -> Result<(), AppError> {
+        if !self.is_enabled(name, user_id).await? {
+            return Err(AppError::NotFound("Not found".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Names of the flags on for the user, for clients deciding what to show
+    pub async fn enabled_for(&self, user_id: &str) -> Result<Vec<String>, AppError> {
+        let flags = sqlx::query_as::<_, FeatureFlag>(
+            "SELECT * FROM marketplace_feature_flags WHERE enabled ORDER BY name"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(flags.into_iter().filter(|flag| is_on_for(flag, user_id)).map(|flag| flag.name).collect())
+    }
+
+    pub async fn list(&self, auth_user: &AuthUser) -> Result<Vec<FeatureFlag>, AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+
+        let flags = sqlx::query_as::<_, FeatureFlag>("SELECT * FROM marketplace_feature_flags ORDER BY name")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(flags)
+    }
+
+    /// Create or change a flag; other instances pick the change up within the cache TTL
+    pub async fn update(
+        &self,
+        auth_user: &AuthUser,
+        name: &str,
+        request: UpdateFeatureFlagRequest,
+    ) -> Result<FeatureFlag, AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+
+        let valid_name = !name.is_empty()
+            && name.len() <= 64
+            && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+        if !valid_name {
+            return Err(AppError::ValidationFailed(vec![FieldError {
+                field: "name".to_string(),
+                message: "must be 1 to 64 lowercase letters, digits or underscores".to_string(),
+            }]));
+        }
+
+        let flag = sqlx::query_as::<_, FeatureFlag>(
+            r#"
+            INSERT INTO marketplace_feature_flags (name, description, enabled, rollout_percent, allow_list, updated_by, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, CURRENT_TIMESTAMP)
+            ON CONFLICT (name) DO UPDATE
+            SET description = COALESCE(EXCLUDED.description, marketplace_feature_flags.description),
+                enabled = EXCLUDED.enabled,
+                rollout_percent = EXCLUDED.rollout_percent,
+                allow_list = EXCLUDED.allow_list,
+                updated_by = EXCLUDED.updated_by,
+                updated_at = EXCLUDED.updated_at
+            RETURNING *
+            "#
+        )
+        .bind(name)
+        .bind(&request.description)
+        .bind(request.enabled)
+        .bind(request.rollout_percent)
+        .bind(&request.allow_list)
+        .bind(&auth_user.0.auth0_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        cached_flags().invalidate(name);
+        tracing::info!(flag = name, enabled = flag.enabled, rollout_percent = flag.rollout_percent, "feature flag updated");
+        Ok(flag)
+    }
+
+    async fn flag(&self, name: &str) -> Result<Option<FeatureFlag>, AppError> {
+        if let Some(flag) = cached_flags().get(name) {
+            return Ok(flag);
+        }
+
+        let flag = sqlx::query_as::<_, FeatureFlag>("SELECT * FROM marketplace_feature_flags WHERE name = $1")
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await?;
+        cached_flags().insert(name.to_string(), flag.clone());
+        Ok(flag)
+    }
+}
+
+fn is_on_for(flag: &FeatureFlag, user_id: &str) -> bool {
+    flag.enabled && (flag.allow_list.iter().any(|allowed| allowed == user_id) || rollout_bucket(&flag.name, user_id) < flag.rollout_percent)
+}
+
+// 0-99, fixed per flag and user; salted with the flag so each rollout reaches different users first
+fn rollout_bucket(flag: &str, user_id: &str) -> i32 {
+    let digest = Sha256::digest(format!("{}:{}", flag, user_id).as_bytes());
+    let mut prefix = [0u8; 8];
+    prefix.copy_from_slice(&digest[..8]);
+    (u64::from_be_bytes(prefix) % 100) as i32
+}
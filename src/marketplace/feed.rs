@@ -0,0 +1,193 @@
+use crate::config::FeedWeights;
+use crate::error::AppError;
+use crate::marketplace::cache::MarketplaceCache;
+use crate::marketplace::follows::FollowService;
+use crate::marketplace::preferences::load_preferences;
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{FeedItem, ListingFilters, ListingStatus, ListingWithSeller};
+use chrono::Utc;
+use sqlx::{PgPool, Row};
+use std::collections::{HashMap, HashSet};
+
+// Candidate pool sizes
+const RECENT_CANDIDATES: i64 = 100;
+const AFFINITY_CATEGORIES: i64 = 3;
+const AFFINITY_CANDIDATES_PER_CATEGORY: i64 = 30;
+
+// Listings older than this contribute no recency score
+const RECENCY_HORIZON_HOURS: f64 = 24.0 * 7.0;
+
+pub struct FeedService {
+    pool: PgPool,
+}
+
+/// Signals collected for a single candidate listing
+#[derive(Debug, Clone, Default)]
+pub struct FeedSignals {
+    pub view_velocity: f64,        // normalized 0-1 against the candidate set
+    pub category_affinity: f64,    // share of the user's purchases and favorites in this category
+    pub from_followed_seller: bool,
+    pub age_hours: f64,
+    pub seller_trust_score: f64,   // 0-100
+}
+
+impl FeedService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Build a personalized feed blending trending, category affinity and followed sellers
+    pub async fn get_feed(
+        &self,
+        cache: &MarketplaceCache,
+        weights: &FeedWeights,
+        user_id: &str,
+        limit: i64,
+    ) -> Result<Vec<FeedItem>, AppError> {
+        let service = MarketplaceService::new(self.pool.clone());
+        let affinity = self.get_category_affinity(user_id).await?;
+        // The feed stays within the user's region
+        let region = Some(load_preferences(&self.pool, user_id).await?.region);
+
+        // Gather candidates: recent active listings plus listings in the user's favourite categories
+        let mut candidates: Vec<ListingWithSeller> = service
+            .get_listings(ListingFilters {
+                status: Some(ListingStatus::Active),
+                region: region.clone(),
+                limit: Some(RECENT_CANDIDATES),
+                ..Default::default()
+            })
+            .await?
+            .items;
+
+        // New listings from followed sellers
+        candidates.extend(
+            service
+                .get_listings(ListingFilters {
+                    followed_by: Some(user_id.to_string()),
+                    status: Some(ListingStatus::Active),
+                    region: region.clone(),
+                    limit: Some(RECENT_CANDIDATES),
+                    ..Default::default()
+                })
+                .await?
+                .items,
+        );
+
+        let mut top_categories: Vec<(&String, &f64)> = affinity.iter().collect();
+        top_categories.sort_by(|a, b| b.1.partial_cmp(a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        for (category, _) in top_categories.into_iter().take(AFFINITY_CATEGORIES as usize) {
+            let listings = service
+                .get_listings(ListingFilters {
+                    category: Some(category.clone()),
+                    status: Some(ListingStatus::Active),
+                    region: region.clone(),
+                    limit: Some(AFFINITY_CANDIDATES_PER_CATEGORY),
+                    ..Default::default()
+                })
+                .await?
+                .items;
+            candidates.extend(listings);
+        }
+
+        let mut seen = HashSet::new();
+        candidates.retain(|c| c.listing.seller_id != user_id && seen.insert(c.listing.id));
+
+        let followed_sellers = FollowService::new(self.pool.clone())
+            .get_followed_sellers(user_id)
+            .await?;
+
+        // View velocity from the hourly Redis counters, falling back to nothing if Redis is down
+        let ids: Vec<_> = candidates.iter().map(|c| c.listing.id).collect();
+        let views = cache.get_view_counts(&ids).await.unwrap_or_default();
+        let max_views = views.values().copied().max().unwrap_or(0).max(1) as f64;
+
+        let now = Utc::now();
+        let mut items: Vec<FeedItem> = candidates
+            .into_iter()
+            .map(|candidate| {
+                let signals = FeedSignals {
+                    view_velocity: views.get(&candidate.listing.id).copied().unwrap_or(0) as f64 / max_views,
+                    category_affinity: affinity.get(&candidate.listing.category).copied().unwrap_or(0.0),
+                    from_followed_seller: followed_sellers.contains(&candidate.listing.seller_id),
+                    age_hours: (now - candidate.listing.created_at).num_minutes().max(0) as f64 / 60.0,
+                    seller_trust_score: candidate.seller_trust_score,
+                };
+
+                FeedItem {
+                    score: score(&signals, weights),
+                    reasons: reasons(&signals),
+                    listing: candidate,
+                }
+            })
+            .collect();
+
+        items.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        items.truncate(limit.clamp(1, 100) as usize);
+
+        Ok(items)
+    }
+
+    /// Share of the listings the user bought or favorited per category
+    async fn get_category_affinity(&self, user_id: &str) -> Result<HashMap<String, f64>, AppError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT l.category, COUNT(*) as listings
+            FROM (
+                SELECT listing_id FROM marketplace_transactions WHERE buyer_id = $1 AND status = 'completed'
+                UNION ALL
+                SELECT listing_id FROM marketplace_favorites WHERE user_id = $1
+            ) interest
+            JOIN marketplace_listings l ON l.id = interest.listing_id
+            GROUP BY l.category
+            "#
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let counts: Vec<(String, i64)> = rows
+            .into_iter()
+            .map(|row| (row.get("category"), row.get("listings")))
+            .collect();
+        let total: i64 = counts.iter().map(|(_, count)| count).sum();
+
+        Ok(counts
+            .into_iter()
+            .map(|(category, count)| (category, count as f64 / total.max(1) as f64))
+            .collect())
+    }
+}
+
+/// Weighted score of a candidate listing
+pub fn score(signals: &FeedSignals, weights: &FeedWeights) -> f64 {
+    let recency = (1.0 - signals.age_hours / RECENCY_HORIZON_HOURS).max(0.0);
+    let followed = if signals.from_followed_seller { 1.0 } else { 0.0 };
+
+    weights.trending * signals.view_velocity
+        + weights.category_affinity * signals.category_affinity
+        + weights.followed_seller * followed
+        + weights.recency * recency
+        + weights.seller_trust * (signals.seller_trust_score / 100.0)
+}
+
+/// Human-readable explanations for why a listing is in the feed
+fn reasons(signals: &FeedSignals) -> Vec<String> {
+    let mut reasons = Vec::new();
+
+    if signals.view_velocity >= 0.5 {
+        reasons.push("trending".to_string());
+    }
+    if signals.category_affinity > 0.0 {
+        reasons.push("similar_to_purchases".to_string());
+    }
+    if signals.from_followed_seller {
+        reasons.push("followed_seller".to_string());
+    }
+    if signals.age_hours < 24.0 {
+        reasons.push("new".to_string());
+    }
+
+    reasons
+}
@@ -0,0 +1,141 @@
+use crate::error::AppError;
+use crate::marketplace::money;
+use crate::models::marketplace::{ListingStatus, ListingType, MarketDiscountBasis};
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sqlx::postgres::PgRow;
+use sqlx::{Postgres, Row};
+use uuid::Uuid;
+
+// Upper bound on the `fields` parameter; there are far fewer fields than this
+pub const MAX_FIELDS_LENGTH: usize = 500;
+
+// A field of `ListingWithSeller` that can be requested on its own
+struct ListingField {
+    name: &'static str,
+    sql: &'static str,
+    decode: fn(&PgRow, &str) -> Result<Value, sqlx::Error>,
+}
+
+// Expressions match `LISTING_WITH_SELLER_COLUMNS` and values are decoded into the same
+// Rust types as `ListingWithSeller`, so a sparse field serializes exactly like the full one
+const LISTING_FIELDS: &[ListingField] = &[
+    ListingField { name: "id", sql: "l.id", decode: column::<Uuid> },
+    ListingField { name: "seller_id", sql: "l.seller_id", decode: column::<String> },
+    ListingField { name: "listing_type", sql: "l.listing_type", decode: column::<ListingType> },
+    ListingField { name: "title", sql: "l.title", decode: column::<String> },
+    ListingField { name: "description", sql: "l.description", decode: column::<Option<String>> },
+    ListingField { name: "description_html", sql: "l.description_html", decode: column::<Option<String>> },
+    ListingField { name: "category", sql: "l.category", decode: column::<String> },
+    ListingField { name: "brand_name", sql: "l.brand_name", decode: column::<Option<String>> },
+    ListingField { name: "original_value", sql: "l.original_value", decode: column::<Option<BigDecimal>> },
+    ListingField { name: "selling_price", sql: "l.selling_price", decode: column::<BigDecimal> },
+    ListingField { name: "discount_percentage", sql: "l.discount_percentage", decode: column::<Option<BigDecimal>> },
+    ListingField { name: "expiration_date", sql: "l.expiration_date", decode: column::<Option<DateTime<Utc>>> },
+    ListingField { name: "proof_image_url", sql: "l.proof_image_url", decode: column::<Option<String>> },
+    ListingField { name: "status", sql: "l.status", decode: column::<ListingStatus> },
+    ListingField { name: "created_at", sql: "l.created_at", decode: column::<DateTime<Utc>> },
+    ListingField { name: "updated_at", sql: "l.updated_at", decode: column::<DateTime<Utc>> },
+    ListingField { name: "view_count", sql: "l.view_count", decode: column::<i32> },
+    ListingField { name: "tags", sql: "l.tags", decode: column::<Vec<String>> },
+    ListingField { name: "is_verified", sql: "l.is_verified", decode: column::<bool> },
+    ListingField { name: "verification_date", sql: "l.verification_date", decode: column::<Option<DateTime<Utc>>> },
+    ListingField { name: "details", sql: "l.details", decode: column::<Option<Value>> },
+    ListingField { name: "quantity", sql: "l.quantity", decode: column::<i32> },
+    ListingField { name: "remaining_quantity", sql: "l.remaining_quantity", decode: column::<i32> },
+    ListingField { name: "deleted_at", sql: "l.deleted_at", decode: column::<Option<DateTime<Utc>>> },
+    ListingField { name: "market_discount_score", sql: "l.market_discount_score", decode: column::<Option<BigDecimal>> },
+    ListingField { name: "market_discount_basis", sql: "l.market_discount_basis", decode: column::<Option<MarketDiscountBasis>> },
+    ListingField { name: "language", sql: "l.language", decode: column::<String> },
+    ListingField { name: "region", sql: "l.region", decode: column::<String> },
+    ListingField { name: "seller_username", sql: "u.username", decode: column::<String> },
+    ListingField { name: "seller_trust_score", sql: "COALESCE(ts.trust_score, 50.0)", decode: column::<f64> },
+    ListingField {
+        name: "seller_badges",
+        sql: "COALESCE((SELECT array_agg(b.badge ORDER BY b.badge) FROM marketplace_seller_badges b WHERE b.user_id = l.seller_id), '{}')",
+        decode: column::<Vec<String>>,
+    },
+    ListingField {
+        name: "price_drop_percentage",
+        sql: r#"(
+            SELECT ((MAX(ph.old_price) - l.selling_price) / MAX(ph.old_price) * 100)::float8
+            FROM marketplace_price_history ph
+            WHERE ph.listing_id = l.id AND ph.changed_at > CURRENT_TIMESTAMP - INTERVAL '30 days'
+            HAVING MAX(ph.old_price) > l.selling_price
+        )"#,
+        decode: column::<Option<f64>>,
+    },
+    ListingField { name: "featured", sql: super::FEATURED_CONDITION, decode: column::<bool> },
+    // Not stored; every amount is in the marketplace currency
+    ListingField { name: "currency", sql: "NULL", decode: currency },
+    ListingField { name: "selling_price_minor", sql: "l.selling_price", decode: minor_units },
+    ListingField { name: "original_value_minor", sql: "l.original_value", decode: minor_units },
+    ListingField {
+        name: "seller_timezone",
+        sql: "COALESCE((SELECT s.timezone FROM marketplace_notification_settings s WHERE s.user_id = l.seller_id), 'UTC')",
+        decode: column::<String>,
+    },
+];
+
+/// Sparse fieldset for listing reads (`?fields=title,selling_price`). Only the requested
+/// columns are selected, and each listing is returned as an object with just those fields.
+/// `id` is always included so clients can key the results.
+pub struct ListingFields {
+    fields: Vec<&'static ListingField>,
+}
+
+impl ListingFields {
+    /// Parse a comma-separated field list; unknown names are rejected rather than ignored
+    pub fn parse(fields: &str) -> Result<Self, AppError> {
+        let mut selected: Vec<&'static ListingField> = vec![&LISTING_FIELDS[0]];
+
+        for name in fields.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            let field = LISTING_FIELDS
+                .iter()
+                .find(|field| field.name == name)
+                .ok_or_else(|| AppError::BadRequest(format!("Unknown listing field: {}", name)))?;
+            if !selected.iter().any(|s| s.name == field.name) {
+                selected.push(field);
+            }
+        }
+
+        Ok(Self { fields: selected })
+    }
+
+    /// Select list for a query over `LISTING_WITH_SELLER_FROM`
+    pub fn columns(&self) -> String {
+        self.fields
+            .iter()
+            .map(|field| format!("{} as {}", field.sql, field.name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    pub fn project(&self, row: &PgRow) -> Result<Value, sqlx::Error> {
+        let mut object = Map::with_capacity(self.fields.len());
+        for field in &self.fields {
+            object.insert(field.name.to_string(), (field.decode)(row, field.name)?);
+        }
+        Ok(Value::Object(object))
+    }
+}
+
+fn currency(_row: &PgRow, _name: &str) -> Result<Value, sqlx::Error> {
+    Ok(Value::from(money::currency()))
+}
+
+// Selected as the decimal amount and converted like `ListingWithSeller` does
+fn minor_units(row: &PgRow, name: &str) -> Result<Value, sqlx::Error> {
+    let amount: Option<BigDecimal> = row.try_get(name)?;
+    Ok(amount.map_or(Value::Null, |amount| Value::from(money::to_minor_units(&amount))))
+}
+
+fn column<T>(row: &PgRow, name: &str) -> Result<Value, sqlx::Error>
+where
+    T: for<'r> sqlx::Decode<'r, Postgres> + sqlx::Type<Postgres> + Serialize,
+{
+    let value: T = row.try_get(name)?;
+    serde_json::to_value(value).map_err(|e| sqlx::Error::Decode(Box::new(e)))
+}
@@ -0,0 +1,107 @@
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::models::marketplace::MarketplaceListing;
+use sqlx::{PgPool, Row};
+use std::collections::HashSet;
+
+pub struct FollowService {
+    pool: PgPool,
+}
+
+impl FollowService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Follow a seller; following twice is a no-op
+    pub async fn follow(&self, auth_user: &AuthUser, seller_id: &str) -> Result<(), AppError> {
+        if seller_id == auth_user.0.auth0_id {
+            return Err(AppError::BadRequest("You cannot follow yourself".to_string()));
+        }
+
+        let seller_exists = sqlx::query("SELECT 1 FROM users WHERE auth0_id = $1")
+            .bind(seller_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        if seller_exists.is_none() {
+            return Err(AppError::NotFound("Seller not found".to_string()));
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_seller_follows (follower_id, seller_id, created_at)
+            VALUES ($1, $2, CURRENT_TIMESTAMP)
+            ON CONFLICT (follower_id, seller_id) DO NOTHING
+            "#
+        )
+        .bind(&auth_user.0.auth0_id)
+        .bind(seller_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn unfollow(&self, auth_user: &AuthUser, seller_id: &str) -> Result<(), AppError> {
+        sqlx::query("DELETE FROM marketplace_seller_follows WHERE follower_id = $1 AND seller_id = $2")
+            .bind(&auth_user.0.auth0_id)
+            .bind(seller_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_follower_count(&self, seller_id: &str) -> Result<i64, AppError> {
+        let row = sqlx::query(
+            "SELECT COUNT(*) as followers FROM marketplace_seller_follows WHERE seller_id = $1"
+        )
+        .bind(seller_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.get("followers"))
+    }
+
+    pub async fn get_followed_sellers(&self, user_id: &str) -> Result<HashSet<String>, AppError> {
+        let sellers = sqlx::query("SELECT seller_id FROM marketplace_seller_follows WHERE follower_id = $1")
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|row| row.get("seller_id"))
+            .collect();
+
+        Ok(sellers)
+    }
+
+    /// Notify every follower of a seller about a newly posted listing
+    pub async fn notify_followers(&self, listing: &MarketplaceListing) -> Result<(), AppError> {
+        // Queued for the fan-out worker, which applies each follower's channel settings.
+        // Shadow-banned sellers' listings raise no alerts.
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_notification_queue (
+                id, user_id, notification_type, title, message,
+                related_listing_id, related_transaction_id, created_at
+            )
+            SELECT gen_random_uuid(), f.follower_id, 'followed_seller_listing',
+                   'New listing from a seller you follow', $2, $3, NULL, CURRENT_TIMESTAMP
+            FROM marketplace_seller_follows f
+            WHERE f.seller_id = $1
+            AND NOT EXISTS (
+                SELECT 1 FROM marketplace_shadow_bans sb
+                WHERE sb.user_id = f.seller_id AND sb.lifted_at IS NULL
+            )
+            "#
+        )
+        .bind(&listing.seller_id)
+        .bind(format!("New listing: {}", listing.title))
+        .bind(listing.id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
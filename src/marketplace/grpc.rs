@@ -0,0 +1,188 @@
+use super::{listing_with_seller_from_row, MarketplaceService, LISTING_WITH_SELLER_COLUMNS, LISTING_WITH_SELLER_FROM};
+use crate::error::AppError;
+use crate::marketplace::badges::BadgeService;
+use crate::models::marketplace::MarketplaceTrustScore;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::net::SocketAddr;
+use tonic::{transport::Server, Request, Response, Status};
+use uuid::Uuid;
+
+pub mod proto {
+    tonic::include_proto!("dealmate.marketplace.v1");
+}
+
+use proto::marketplace_internal_server::{MarketplaceInternal, MarketplaceInternalServer};
+use proto::{
+    GetListingRequest, GetSellerTrustScoreRequest, GetTransactionStatusRequest, Listing,
+    SellerTrustScore, TransactionStatus,
+};
+
+// Metadata key carrying the shared secret for service-to-service calls
+const INTERNAL_TOKEN_HEADER: &str = "x-internal-token";
+
+/// Internal gRPC API for other Dealmate services
+pub struct MarketplaceGrpc {
+    pool: PgPool,
+}
+
+impl MarketplaceGrpc {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[tonic::async_trait]
+impl MarketplaceInternal for MarketplaceGrpc {
+    async fn get_listing(&self, request: Request<GetListingRequest>) -> Result<Response<Listing>, Status> {
+        let listing_id = parse_uuid(&request.get_ref().listing_id, "listing_id")?;
+
+        // Read-only lookup; unlike the HTTP handler this does not count as a view
+        let query = format!(
+            "SELECT {} {} WHERE l.id = $1 AND l.deleted_at IS NULL",
+            LISTING_WITH_SELLER_COLUMNS, LISTING_WITH_SELLER_FROM
+        );
+        let row = sqlx::query(&query)
+            .bind(listing_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(AppError::from)?
+            .ok_or_else(|| Status::not_found("Listing not found"))?;
+        let item = listing_with_seller_from_row(&row).map_err(AppError::from)?;
+        let listing = item.listing;
+
+        Ok(Response::new(Listing {
+            id: listing.id.to_string(),
+            seller_id: listing.seller_id,
+            listing_type: listing.listing_type.as_str().to_string(),
+            title: listing.title,
+            category: listing.category,
+            brand_name: listing.brand_name,
+            original_value: listing.original_value.map(|value| value.to_string()),
+            selling_price: listing.selling_price.to_string(),
+            status: listing.status.as_str().to_string(),
+            remaining_quantity: listing.remaining_quantity,
+            expiration_date: listing.expiration_date.map(timestamp),
+            seller_trust_score: item.seller_trust_score,
+            price_drop_percentage: item.price_drop_percentage,
+            created_at: Some(timestamp(listing.created_at)),
+        }))
+    }
+
+    async fn get_seller_trust_score(
+        &self,
+        request: Request<GetSellerTrustScoreRequest>,
+    ) -> Result<Response<SellerTrustScore>, Status> {
+        let user_id = &request.get_ref().user_id;
+
+        let score = sqlx::query_as::<_, MarketplaceTrustScore>(
+            "SELECT * FROM marketplace_trust_scores WHERE user_id = $1"
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::from)?
+        .ok_or_else(|| Status::not_found("Seller has no trust score"))?;
+
+        let badges = BadgeService::new(self.pool.clone()).get_badges(user_id).await?;
+
+        Ok(Response::new(SellerTrustScore {
+            user_id: score.user_id,
+            trust_score: score.trust_score,
+            total_transactions: score.total_transactions,
+            successful_transactions: score.successful_transactions,
+            average_rating: score.average_rating,
+            total_reviews: score.total_reviews,
+            verified_seller: score.verified_seller,
+            badges: badges.into_iter().map(|b| b.badge).collect(),
+            last_calculated: Some(timestamp(score.last_calculated)),
+        }))
+    }
+
+    async fn get_transaction_status(
+        &self,
+        request: Request<GetTransactionStatusRequest>,
+    ) -> Result<Response<TransactionStatus>, Status> {
+        let transaction_id = parse_uuid(&request.get_ref().transaction_id, "transaction_id")?;
+
+        let transaction = MarketplaceService::new(self.pool.clone())
+            .get_transaction_by_id(transaction_id)
+            .await?;
+
+        Ok(Response::new(TransactionStatus {
+            id: transaction.id.to_string(),
+            listing_id: transaction.listing_id.to_string(),
+            status: transaction.status.as_str().to_string(),
+            amount: transaction.amount.to_string(),
+            created_at: Some(timestamp(transaction.created_at)),
+            escrow_release_date: transaction.escrow_release_date.map(timestamp),
+            completed_at: transaction.completed_at.map(timestamp),
+        }))
+    }
+}
+
+impl From<AppError> for Status {
+    fn from(error: AppError) -> Self {
+        match error {
+            AppError::BadRequest(message) => Status::invalid_argument(message),
+            AppError::NotFound(message) => Status::not_found(message),
+            AppError::Unauthorized(message) => Status::unauthenticated(message),
+            AppError::Forbidden(message) | AppError::StepUpRequired(message) => Status::permission_denied(message),
+            AppError::Conflict(message) => Status::failed_precondition(message),
+            AppError::UnprocessableEntity(message) => Status::invalid_argument(message),
+            AppError::ValidationFailed(_) => Status::invalid_argument("Request validation failed"),
+            AppError::RateLimited { message, .. } => Status::resource_exhausted(message),
+            AppError::ServiceUnavailable(message) => Status::unavailable(message),
+            AppError::InternalError(details) => {
+                tracing::error!(error = %details, "internal error");
+                Status::internal("Internal server error")
+            }
+        }
+    }
+}
+
+fn parse_uuid(value: &str, field: &str) -> Result<Uuid, Status> {
+    Uuid::parse_str(value).map_err(|_| Status::invalid_argument(format!("Invalid {}", field)))
+}
+
+fn timestamp(value: DateTime<Utc>) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: value.timestamp(),
+        nanos: value.timestamp_subsec_nanos() as i32,
+    }
+}
+
+// Looks at every byte whatever the first mismatch, so timing doesn't reveal how much matched
+fn tokens_match(given: &[u8], expected: &[u8]) -> bool {
+    given.len() == expected.len()
+        && given.iter().zip(expected).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Run the gRPC server on its own port alongside the HTTP server.
+/// Callers must send `internal_token` in the `x-internal-token` metadata.
+pub fn spawn_grpc_server(
+    pool: PgPool,
+    addr: SocketAddr,
+    internal_token: String,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let service = MarketplaceInternalServer::with_interceptor(
+            MarketplaceGrpc::new(pool),
+            move |request: Request<()>| {
+                let authorized = request
+                    .metadata()
+                    .get(INTERNAL_TOKEN_HEADER)
+                    .is_some_and(|token| tokens_match(token.as_bytes(), internal_token.as_bytes()));
+                if authorized {
+                    Ok(request)
+                } else {
+                    Err(Status::unauthenticated("Invalid internal token"))
+                }
+            },
+        );
+
+        if let Err(e) = Server::builder().add_service(service).serve(addr).await {
+            tracing::error!(error = %e, %addr, "gRPC server failed");
+        }
+    })
+}
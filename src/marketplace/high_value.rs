@@ -0,0 +1,362 @@
+use crate::auth::AuthUser;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::marketplace::cart;
+use crate::marketplace::notification_queue;
+use crate::marketplace::notifications::{load_settings, notification_sender_from_config, OutgoingMessage};
+use crate::marketplace::preferences::load_preferences;
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{
+    HighValueCheck, MarketplaceTransaction, ReviewHighValueTransactionRequest, TransactionStatus,
+};
+use crate::validation::FieldError;
+use sha2::{Digest, Sha256};
+use sqlx::{PgPool, Postgres, Row, Transaction};
+use uuid::Uuid;
+
+const SYSTEM_ACTOR: &str = "system";
+
+const CHECK_COLUMNS: &str = r#"
+    transaction_id, buyer_confirmation_required, buyer_confirmed_at, seller_review_required,
+    seller_approved, seller_reviewed_by, seller_reviewed_at, seller_review_notes,
+    payment_received_at, created_at
+"#;
+
+// Every required check has passed
+const CLEARED: &str = r#"(NOT buyer_confirmation_required OR buyer_confirmed_at IS NOT NULL)
+    AND (NOT seller_review_required OR seller_approved IS TRUE)"#;
+
+/// Second confirmation of transactions at or above HIGH_VALUE_THRESHOLD: the buyer enters
+/// a code emailed to them and an admin reviews the seller. Until both pass, a payment for
+/// the transaction is held and it stays pending; then it moves to escrow as usual.
+pub struct HighValueService {
+    pool: PgPool,
+}
+
+impl HighValueService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Start the checks for a transaction opened in `tx` when its amount calls for them
+    pub(crate) async fn open(tx: &mut Transaction<'_, Postgres>, transaction: &MarketplaceTransaction) -> Result<(), AppError> {
+        let settings = &Config::get().high_value;
+        if transaction.amount < settings.threshold || !(settings.buyer_confirmation || settings.seller_review) {
+            return Ok(());
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_high_value_checks (
+                transaction_id, buyer_confirmation_required, seller_review_required, created_at
+            ) VALUES ($1, $2, $3, CURRENT_TIMESTAMP)
+            "#
+        )
+        .bind(transaction.id)
+        .bind(settings.buyer_confirmation)
+        .bind(settings.seller_review)
+        .execute(&mut **tx)
+        .await?;
+
+        if settings.buyer_confirmation {
+            notification_queue::enqueue(
+                &mut **tx,
+                &transaction.buyer_id,
+                "high_value_confirmation",
+                "Confirm your purchase",
+                "This is a high-value purchase: request a confirmation code on the transaction and enter it to release your payment to escrow",
+                Some(transaction.listing_id),
+                Some(transaction.id),
+            ).await?;
+        }
+        Ok(())
+    }
+
+    /// Hold the payment of a pending transaction whose checks have not all passed. True when held.
+    pub(crate) async fn hold_payment(
+        tx: &mut Transaction<'_, Postgres>,
+        transaction: &MarketplaceTransaction,
+        payment_id: Option<&str>,
+    ) -> Result<bool, AppError> {
+        if transaction.status != TransactionStatus::Pending {
+            return Ok(false);
+        }
+
+        let held = sqlx::query(&format!(
+            r#"
+            UPDATE marketplace_high_value_checks
+            SET payment_id = COALESCE($2, payment_id),
+                payment_received_at = COALESCE(payment_received_at, CURRENT_TIMESTAMP)
+            WHERE transaction_id = $1 AND NOT ({})
+            "#,
+            CLEARED
+        ))
+        .bind(transaction.id)
+        .bind(payment_id)
+        .execute(&mut **tx)
+        .await?
+        .rows_affected();
+
+        Ok(held > 0)
+    }
+
+    /// The checks of a transaction, for its buyer, its seller and admins
+    pub async fn get_check(&self, auth_user: &AuthUser, transaction_id: Uuid) -> Result<HighValueCheck, AppError> {
+        let marketplace = MarketplaceService::new(self.pool.clone());
+        let transaction = marketplace.get_transaction_by_id(transaction_id).await?;
+        marketplace.authorization().can_view_transaction(&auth_user.0.auth0_id, &transaction).await?;
+
+        sqlx::query_as::<_, HighValueCheck>(&format!(
+            "SELECT {} FROM marketplace_high_value_checks WHERE transaction_id = $1",
+            CHECK_COLUMNS
+        ))
+        .bind(transaction_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Transaction does not need high-value checks".to_string()))
+    }
+
+    /// Email the buyer a new confirmation code, replacing any earlier one
+    pub async fn send_code(&self, auth_user: &AuthUser, transaction_id: Uuid) -> Result<(), AppError> {
+        let settings = &Config::get().high_value;
+        let code = format!("{:06}", Uuid::new_v4().as_u128() % 1_000_000);
+
+        let row = sqlx::query(
+            r#"
+            UPDATE marketplace_high_value_checks c
+            SET code_hash = $3,
+                code_expires_at = CURRENT_TIMESTAMP + make_interval(mins => $4),
+                code_attempts = 0
+            FROM marketplace_transactions t
+            WHERE c.transaction_id = $1 AND t.id = c.transaction_id AND t.buyer_id = $2
+            AND t.status = 'pending' AND c.buyer_confirmation_required AND c.buyer_confirmed_at IS NULL
+            RETURNING t.listing_id
+            "#
+        )
+        .bind(transaction_id)
+        .bind(&auth_user.0.auth0_id)
+        .bind(hash_code(transaction_id, &code))
+        .bind(settings.code_ttl_mins)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::Conflict("Transaction has no purchase confirmation pending".to_string()))?;
+
+        let listing_id: Uuid = row.get("listing_id");
+        let preferences = load_preferences(&self.pool, &auth_user.0.auth0_id).await?;
+        let notification_settings = load_settings(&self.pool, &auth_user.0.auth0_id).await?;
+        let message = OutgoingMessage {
+            user_id: auth_user.0.auth0_id.clone(),
+            channels: vec!["email"],
+            subject: "Your purchase confirmation code".to_string(),
+            body: format!(
+                "Enter {} to confirm your purchase of listing {}. The code expires in {} minutes. If you did not make this purchase, do not share the code and contact support.",
+                code, listing_id, settings.code_ttl_mins
+            ),
+            locale: preferences.locale,
+            timezone: notification_settings.timezone,
+        };
+        notification_sender_from_config(Config::get())?.send(&message).await
+    }
+
+    /// Confirm the purchase with the emailed code, releasing a held payment once the seller review passed too
+    pub async fn confirm_buyer(
+        &self,
+        auth_user: &AuthUser,
+        transaction_id: Uuid,
+        code: &str,
+    ) -> Result<HighValueCheck, AppError> {
+        let max_attempts = Config::get().high_value.max_code_attempts;
+        let mut tx = self.pool.begin().await?;
+
+        let row = sqlx::query(
+            r#"
+            SELECT c.code_hash, c.code_attempts, c.code_expires_at > CURRENT_TIMESTAMP as code_valid, c.buyer_confirmed_at
+            FROM marketplace_high_value_checks c
+            JOIN marketplace_transactions t ON t.id = c.transaction_id
+            WHERE c.transaction_id = $1 AND t.buyer_id = $2 AND c.buyer_confirmation_required
+            FOR UPDATE OF c
+            "#
+        )
+        .bind(transaction_id)
+        .bind(&auth_user.0.auth0_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Transaction has no purchase confirmation".to_string()))?;
+
+        if row.get::<Option<chrono::DateTime<chrono::Utc>>, _>("buyer_confirmed_at").is_some() {
+            return Err(AppError::Conflict("Purchase is already confirmed".to_string()));
+        }
+        let code_hash: Option<String> = row.get("code_hash");
+        let code_valid: Option<bool> = row.get("code_valid");
+        let Some(code_hash) = code_hash.filter(|_| code_valid == Some(true)) else {
+            return Err(AppError::Conflict("No valid confirmation code; request a new one".to_string()));
+        };
+        if row.get::<i32, _>("code_attempts") >= max_attempts {
+            return Err(AppError::Conflict("Too many wrong codes; request a new one".to_string()));
+        }
+
+        if hash_code(transaction_id, code.trim()) != code_hash {
+            sqlx::query("UPDATE marketplace_high_value_checks SET code_attempts = code_attempts + 1 WHERE transaction_id = $1")
+                .bind(transaction_id)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            return Err(AppError::ValidationFailed(vec![FieldError {
+                field: "code".to_string(),
+                message: "is not the code we sent".to_string(),
+            }]));
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE marketplace_high_value_checks
+            SET buyer_confirmed_at = CURRENT_TIMESTAMP, code_hash = NULL, code_expires_at = NULL
+            WHERE transaction_id = $1
+            "#
+        )
+        .bind(transaction_id)
+        .execute(&mut *tx)
+        .await?;
+
+        let released = release_if_cleared(&mut tx, transaction_id).await?;
+        tx.commit().await?;
+
+        if let Some(transaction) = released {
+            self.notify_released(&transaction).await;
+        }
+        self.get_check(auth_user, transaction_id).await
+    }
+
+    /// Transactions waiting on an admin's review of the seller, oldest first
+    pub async fn review_queue(&self, auth_user: &AuthUser) -> Result<Vec<HighValueCheck>, AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+
+        let checks = sqlx::query_as::<_, HighValueCheck>(&format!(
+            r#"
+            SELECT {} FROM marketplace_high_value_checks c
+            WHERE c.seller_review_required AND c.seller_reviewed_at IS NULL
+            AND EXISTS (SELECT 1 FROM marketplace_transactions t WHERE t.id = c.transaction_id AND t.status = 'pending')
+            ORDER BY c.created_at
+            LIMIT 200
+            "#,
+            CHECK_COLUMNS
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(checks)
+    }
+
+    /// Record an admin's review of the seller. Approval releases a held payment once the
+    /// buyer confirmed too; rejection cancels the transaction.
+    pub async fn review_seller(
+        &self,
+        auth_user: &AuthUser,
+        transaction_id: Uuid,
+        request: ReviewHighValueTransactionRequest,
+    ) -> Result<HighValueCheck, AppError> {
+        let marketplace = MarketplaceService::new(self.pool.clone());
+        marketplace.require_admin(auth_user).await?;
+
+        let mut tx = self.pool.begin().await?;
+        let reviewed = sqlx::query(
+            r#"
+            UPDATE marketplace_high_value_checks
+            SET seller_approved = $2, seller_reviewed_by = $3, seller_reviewed_at = CURRENT_TIMESTAMP, seller_review_notes = $4
+            WHERE transaction_id = $1 AND seller_review_required AND seller_reviewed_at IS NULL
+            RETURNING payment_id, payment_received_at IS NOT NULL as payment_held
+            "#
+        )
+        .bind(transaction_id)
+        .bind(request.approved)
+        .bind(&auth_user.0.auth0_id)
+        .bind(&request.review_notes)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::Conflict("Transaction has no seller review pending".to_string()))?;
+
+        if !request.approved {
+            tx.commit().await?;
+
+            let payment_held: bool = reviewed.get("payment_held");
+            if payment_held {
+                // The held payment never reached escrow, so it goes back outside the refund flow
+                let payment_id: Option<String> = reviewed.get("payment_id");
+                tracing::warn!(%transaction_id, payment_id = ?payment_id, "high-value transaction rejected with a held payment to return");
+            }
+            marketplace
+                .cancel_transaction(auth_user, transaction_id, "The purchase did not pass our high-value review")
+                .await?;
+            return self.get_check(auth_user, transaction_id).await;
+        }
+
+        let released = release_if_cleared(&mut tx, transaction_id).await?;
+        tx.commit().await?;
+
+        if let Some(transaction) = released {
+            self.notify_released(&transaction).await;
+        }
+        self.get_check(auth_user, transaction_id).await
+    }
+
+    async fn notify_released(&self, transaction: &MarketplaceTransaction) {
+        let marketplace = MarketplaceService::new(self.pool.clone());
+        marketplace.create_notification(
+            &transaction.seller_id,
+            "payment_confirmed",
+            "Payment Received",
+            "The buyer's payment is held in escrow until they confirm receipt",
+            Some(transaction.listing_id),
+            Some(transaction.id),
+        ).await;
+        marketplace.create_notification(
+            &transaction.buyer_id,
+            "payment_confirmed",
+            "Payment Confirmed",
+            "Your payment is held in escrow and your code can now be revealed",
+            Some(transaction.listing_id),
+            Some(transaction.id),
+        ).await
+    }
+}
+
+// Move a held payment into escrow once every check passed
+async fn release_if_cleared(
+    tx: &mut Transaction<'_, Postgres>,
+    transaction_id: Uuid,
+) -> Result<Option<MarketplaceTransaction>, AppError> {
+    let held = sqlx::query_scalar::<_, Option<String>>(&format!(
+        r#"
+        SELECT payment_id FROM marketplace_high_value_checks
+        WHERE transaction_id = $1 AND payment_received_at IS NOT NULL AND {}
+        "#,
+        CLEARED
+    ))
+    .bind(transaction_id)
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    let Some(payment_id) = held else {
+        return Ok(None);
+    };
+
+    let transaction = sqlx::query_as::<_, MarketplaceTransaction>(
+        "SELECT * FROM marketplace_transactions WHERE id = $1 AND status = 'pending' FOR UPDATE"
+    )
+    .bind(transaction_id)
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    match transaction {
+        Some(transaction) => cart::fund_transaction(tx, &transaction, payment_id.as_deref(), SYSTEM_ACTOR).await,
+        None => Ok(None),
+    }
+}
+
+// Codes are short, so the transaction id salts the hash
+fn hash_code(transaction_id: Uuid, code: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(transaction_id.as_bytes());
+    hasher.update(code.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
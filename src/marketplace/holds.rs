@@ -0,0 +1,272 @@
+use crate::config::Config;
+use crate::error::AppError;
+use crate::marketplace::jobs::Job;
+use crate::marketplace::outbox::{event_types, OutboxService};
+use crate::marketplace::transaction_state::{TransactionEvent, TransactionStateMachine};
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{CheckoutStatus, ListingStatus, ListingWithSeller, MarketplaceTransaction};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use redis::{AsyncCommands, Client};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+const SYSTEM_ACTOR: &str = "system";
+
+// Unpaid purchases cancelled per job pass
+const EXPIRY_BATCH_SIZE: i64 = 200;
+
+fn hold_key(listing_id: Uuid) -> String {
+    format!("checkout_hold:{}", listing_id)
+}
+
+/// Holds on listings whose last units went to checkouts that are not paid yet. A purchase
+/// takes its unit from stock when checkout starts, so nobody else can buy it; if the buyer
+/// does not pay within CHECKOUT_HOLD_MINS the purchase is cancelled and the unit goes back
+/// on sale. While the hold lasts the listing shows as `reserved` rather than sold. Holds
+/// live in Redis with the same expiry, so they lapse by themselves; without Redis listings
+/// simply show as sold until the purchase is paid or cancelled.
+pub struct CheckoutHolds {
+    redis_client: Option<Client>,
+}
+
+impl CheckoutHolds {
+    pub fn from_config() -> Self {
+        let redis_client = Config::get().redis_url.as_ref().and_then(|url| Client::open(url.as_str()).ok());
+        Self { redis_client }
+    }
+
+    /// Hold a listing that just sold out to an unpaid purchase, replacing any earlier hold.
+    /// Best effort: without the hold the listing shows as sold.
+    pub async fn hold(&self, listing_id: Uuid) {
+        let Some(client) = &self.redis_client else {
+            return;
+        };
+        let hold_mins = Config::get().payments.checkout_hold_mins;
+        let until = Utc::now() + Duration::minutes(i64::from(hold_mins));
+
+        let result: Result<(), redis::RedisError> = async {
+            let mut conn = client.get_async_connection().await?;
+            conn.set_ex::<_, _, ()>(hold_key(listing_id), until.to_rfc3339(), u64::from(hold_mins) * 60).await
+        }
+        .await;
+        if let Err(e) = result {
+            tracing::warn!(error = %e, %listing_id, "checkout hold not recorded");
+        }
+    }
+
+    /// Drop a listing's hold once no unpaid purchase is left on it
+    pub async fn release(&self, listing_id: Uuid) {
+        let Some(client) = &self.redis_client else {
+            return;
+        };
+
+        let result: Result<(), redis::RedisError> = async {
+            let mut conn = client.get_async_connection().await?;
+            conn.del::<_, ()>(hold_key(listing_id)).await
+        }
+        .await;
+        if let Err(e) = result {
+            tracing::warn!(error = %e, %listing_id, "checkout hold not released");
+        }
+    }
+
+    /// When the hold on a listing ends, if it is held
+    pub async fn held_until(&self, listing_id: Uuid) -> Option<DateTime<Utc>> {
+        self.holds(&[listing_id]).await.pop().flatten()
+    }
+
+    /// Show sold-out listings that are only held by unpaid checkouts as reserved
+    pub async fn mark_reserved(&self, listings: &mut [ListingWithSeller]) {
+        let sold: Vec<usize> = listings
+            .iter()
+            .enumerate()
+            .filter(|(_, listing)| listing.listing.status == ListingStatus::Sold)
+            .map(|(i, _)| i)
+            .collect();
+        if sold.is_empty() {
+            return;
+        }
+
+        let ids: Vec<Uuid> = sold.iter().map(|i| listings[*i].listing.id).collect();
+        for (i, until) in sold.into_iter().zip(self.holds(&ids).await) {
+            if let Some(until) = until {
+                listings[i].listing.status = ListingStatus::Reserved;
+                listings[i].reserved_until = Some(until);
+            }
+        }
+    }
+
+    // End of the hold on each listing, in order; none where Redis can't say
+    async fn holds(&self, listing_ids: &[Uuid]) -> Vec<Option<DateTime<Utc>>> {
+        let none = || vec![None; listing_ids.len()];
+        let Some(client) = &self.redis_client else {
+            return none();
+        };
+
+        let keys: Vec<String> = listing_ids.iter().map(|id| hold_key(*id)).collect();
+        let result: Result<Vec<Option<String>>, redis::RedisError> = async {
+            let mut conn = client.get_async_connection().await?;
+            redis::cmd("MGET").arg(&keys).query_async(&mut conn).await
+        }
+        .await;
+
+        match result {
+            Ok(values) => values
+                .into_iter()
+                .map(|value| {
+                    value
+                        .and_then(|value| DateTime::parse_from_rfc3339(&value).ok())
+                        .map(|until| until.with_timezone(&Utc))
+                })
+                .collect(),
+            Err(e) => {
+                tracing::warn!(error = %e, "checkout holds not read");
+                none()
+            }
+        }
+    }
+}
+
+/// Whether `listing_id` still has purchases waiting for payment
+pub(crate) async fn has_unpaid(tx: &mut Transaction<'_, Postgres>, listing_id: Uuid) -> Result<bool, AppError> {
+    let unpaid = sqlx::query("SELECT 1 FROM marketplace_transactions WHERE listing_id = $1 AND status = 'pending' LIMIT 1")
+        .bind(listing_id)
+        .fetch_optional(&mut **tx)
+        .await?;
+    Ok(unpaid.is_some())
+}
+
+/// Cancel purchases left unpaid past the checkout hold and put their units back on sale.
+/// Purchases whose payment is already in, held for high-value checks, or still awaiting an
+/// approved UPI request are left alone. Returns how many were cancelled.
+pub async fn expire_unpaid(pool: &PgPool) -> Result<usize, AppError> {
+    let hold_mins = Config::get().payments.checkout_hold_mins as i32;
+    let due = sqlx::query_as::<_, MarketplaceTransaction>(
+        r#"
+        SELECT t.* FROM marketplace_transactions t
+        WHERE t.status = 'pending' AND t.created_at <= CURRENT_TIMESTAMP - make_interval(mins => $1)
+        AND NOT EXISTS (
+            SELECT 1 FROM marketplace_high_value_checks c
+            WHERE c.transaction_id = t.id AND c.payment_received_at IS NOT NULL
+        )
+        AND NOT EXISTS (
+            SELECT 1 FROM marketplace_upi_payments p
+            WHERE p.purpose = 'checkout' AND p.reference_id = t.checkout_id
+            AND p.status = 'pending' AND p.expires_at > CURRENT_TIMESTAMP
+        )
+        ORDER BY t.created_at
+        LIMIT $2
+        "#
+    )
+    .bind(hold_mins)
+    .bind(EXPIRY_BATCH_SIZE)
+    .fetch_all(pool)
+    .await?;
+
+    let holds = CheckoutHolds::from_config();
+    let marketplace = MarketplaceService::new(pool.clone());
+    let mut expired = 0;
+    for transaction in &due {
+        let Some(still_unpaid) = expire(pool, transaction).await? else {
+            continue;
+        };
+        expired += 1;
+        if !still_unpaid {
+            holds.release(transaction.listing_id).await;
+        }
+
+        marketplace.create_notification(
+            &transaction.buyer_id,
+            "transaction_cancelled",
+            "Checkout expired",
+            &format!(
+                "Your purchase was cancelled because it wasn't paid within {} minutes. The listing is back on sale if you still want it.",
+                hold_mins
+            ),
+            Some(transaction.listing_id),
+            Some(transaction.id),
+        ).await;
+    }
+
+    if expired > 0 {
+        tracing::info!(expired, "unpaid purchases cancelled");
+    }
+    Ok(expired)
+}
+
+// Cancel one unpaid purchase, expiring its checkout once nothing in it is left to pay.
+// None when it was paid or cancelled meanwhile; otherwise whether its listing still has
+// other unpaid purchases.
+async fn expire(pool: &PgPool, transaction: &MarketplaceTransaction) -> Result<Option<bool>, AppError> {
+    let mut tx = pool.begin().await?;
+
+    // The checkout is locked before its items, in the order payment takes them, so a payment
+    // arriving now either lands first or finds the checkout expired
+    if let Some(checkout_id) = transaction.checkout_id {
+        let open = sqlx::query("SELECT 1 FROM marketplace_checkouts WHERE id = $1 AND status = $2 FOR UPDATE")
+            .bind(checkout_id)
+            .bind(CheckoutStatus::AwaitingPayment)
+            .fetch_optional(&mut *tx)
+            .await?;
+        if open.is_none() {
+            return Ok(None);
+        }
+    }
+
+    let current = sqlx::query_as::<_, MarketplaceTransaction>(
+        "SELECT * FROM marketplace_transactions WHERE id = $1 AND status = 'pending' FOR UPDATE"
+    )
+    .bind(transaction.id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(current) = current else {
+        return Ok(None);
+    };
+
+    let updated = TransactionStateMachine::apply(
+        &mut tx,
+        &current,
+        TransactionEvent::Cancelled,
+        SYSTEM_ACTOR,
+        Some("Payment was not completed in time"),
+    ).await?;
+    if MarketplaceService::release_coupon_code(&mut tx, &current).await? {
+        MarketplaceService::restock_listing(&mut tx, current.listing_id).await?;
+    }
+    OutboxService::record(&mut tx, "transaction", current.id, event_types::TRANSACTION_CANCELLED, &updated).await?;
+
+    if let Some(checkout_id) = current.checkout_id {
+        sqlx::query(
+            r#"
+            UPDATE marketplace_checkouts SET status = $2
+            WHERE id = $1 AND NOT EXISTS (
+                SELECT 1 FROM marketplace_transactions WHERE checkout_id = $1 AND status = 'pending'
+            )
+            "#
+        )
+        .bind(checkout_id)
+        .bind(CheckoutStatus::Expired)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let still_unpaid = has_unpaid(&mut tx, current.listing_id).await?;
+    tx.commit().await?;
+    Ok(Some(still_unpaid))
+}
+
+/// Cancels purchases not paid within the checkout hold
+pub struct CheckoutHoldExpiryJob;
+
+#[async_trait]
+impl Job for CheckoutHoldExpiryJob {
+    fn name(&self) -> &'static str {
+        "checkout_hold_expiry"
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<(), AppError> {
+        expire_unpaid(pool).await?;
+        Ok(())
+    }
+}
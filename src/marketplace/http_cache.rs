@@ -0,0 +1,137 @@
+use crate::config::{Config, ListingCacheSettings};
+use crate::marketplace::translations;
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    extract::Request,
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use moka::future::Cache;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+// Larger results are served but not kept in memory
+const MAX_CACHED_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+static RESPONSES: OnceLock<Cache<String, CachedResponse>> = OnceLock::new();
+
+#[derive(Clone)]
+struct CachedResponse {
+    content_type: Option<HeaderValue>,
+    content_language: Option<HeaderValue>,
+    body: Bytes,
+}
+
+fn responses(settings: &ListingCacheSettings) -> &'static Cache<String, CachedResponse> {
+    RESPONSES.get_or_init(|| {
+        Cache::builder()
+            .max_capacity(settings.local_capacity)
+            .time_to_live(Duration::from_secs(settings.local_ttl_secs))
+            .build()
+    })
+}
+
+/// Caching of anonymous browse results. Responses say CDNs and proxies may serve them
+/// for a few seconds, and keep serving them stale while refetching; identical requests
+/// reaching this instance meanwhile are answered from memory, with concurrent misses
+/// waiting on a single query. Requests with credentials bypass both.
+pub async fn cache_anonymous(request: Request, next: Next) -> Response {
+    if request.method() != Method::GET || request.headers().contains_key(header::AUTHORIZATION) {
+        return next.run(request).await;
+    }
+
+    let settings = &Config::get().listing_cache;
+    if settings.local_ttl_secs == 0 {
+        return with_shared_caching(next.run(request).await, settings);
+    }
+
+    // Routes are nested, so the key is the same under every version prefix. Listing text is
+    // translated, so requests negotiating different locales are kept apart.
+    let locale = translations::negotiate(
+        request
+            .headers()
+            .get(header::ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok()),
+    );
+    let key = format!("{}|{}", request.uri(), locale.unwrap_or_default());
+    let mut pending = Some((request, next));
+    let mut uncached = None;
+
+    let cached = responses(settings)
+        .optionally_get_with(key, async {
+            let (request, next) = pending.take()?;
+            match buffer(next.run(request).await).await {
+                Ok(cached) => Some(cached),
+                Err(response) => {
+                    uncached = Some(response);
+                    None
+                }
+            }
+        })
+        .await;
+
+    let response = match (cached, uncached, pending) {
+        (Some(cached), _, _) => cached.into_response(),
+        (None, Some(response), _) => response,
+        // Waited on another request whose result was not cacheable
+        (None, None, Some((request, next))) => next.run(request).await,
+        (None, None, None) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    with_shared_caching(response, settings)
+}
+
+// Successful responses small enough to keep; anything else is handed back as it was
+async fn buffer(response: Response) -> Result<CachedResponse, Response> {
+    if response.status() != StatusCode::OK {
+        return Err(response);
+    }
+
+    let (parts, body) = response.into_parts();
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::warn!(error = %e, "listing response body could not be buffered");
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response());
+        }
+    };
+    if body.len() > MAX_CACHED_BODY_BYTES {
+        return Err(Response::from_parts(parts, Body::from(body)));
+    }
+
+    Ok(CachedResponse {
+        content_type: parts.headers.get(header::CONTENT_TYPE).cloned(),
+        content_language: parts.headers.get(header::CONTENT_LANGUAGE).cloned(),
+        body,
+    })
+}
+
+impl IntoResponse for CachedResponse {
+    fn into_response(self) -> Response {
+        let mut response = Response::new(Body::from(self.body));
+        if let Some(content_type) = self.content_type {
+            response.headers_mut().insert(header::CONTENT_TYPE, content_type);
+        }
+        if let Some(content_language) = self.content_language {
+            response.headers_mut().insert(header::CONTENT_LANGUAGE, content_language);
+        }
+        response
+    }
+}
+
+fn with_shared_caching(mut response: Response, settings: &ListingCacheSettings) -> Response {
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+
+    let cache_control = format!(
+        "public, max-age=0, s-maxage={}, stale-while-revalidate={}",
+        settings.s_maxage_secs, settings.stale_while_revalidate_secs
+    );
+    let headers = response.headers_mut();
+    if let Ok(value) = HeaderValue::from_str(&cache_control) {
+        headers.insert(header::CACHE_CONTROL, value);
+    }
+    headers.insert(header::VARY, HeaderValue::from_static("authorization, accept-language"));
+    response
+}
@@ -0,0 +1,318 @@
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{
+    CreateImpersonationRequest, ImpersonationAuditEntry, ImpersonationGrant, ImpersonationSession,
+};
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Request, State},
+    http::{request::Parts, Method},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{Duration, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub const IMPERSONATION_HEADER: &str = "x-impersonation-token";
+
+// Impersonation sessions end on their own this soon after they are issued
+const IMPERSONATION_TTL_MINUTES: i64 = 30;
+
+const SESSION_COLUMNS: &str = "id, admin_id, user_id, reason, created_at, expires_at, revoked_at";
+
+/// Lets support admins see the marketplace as a user, e.g. to debug a listing that
+/// disappeared for them. Sessions are short-lived and read-only, and every request made
+/// in one is written to the impersonation audit log.
+pub struct ImpersonationService {
+    pool: PgPool,
+}
+
+impl ImpersonationService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Issue a token that makes the admin's requests act as `request.user_id`
+    pub async fn issue(
+        &self,
+        auth_user: &AuthUser,
+        request: CreateImpersonationRequest,
+    ) -> Result<ImpersonationGrant, AppError> {
+        let service = MarketplaceService::new(self.pool.clone());
+        service.require_admin(auth_user).await?;
+
+        let admin_id = &auth_user.0.auth0_id;
+        if request.user_id == *admin_id {
+            return Err(AppError::BadRequest("You cannot impersonate yourself".to_string()));
+        }
+        if service.is_admin(&request.user_id).await? {
+            return Err(AppError::Forbidden("Admins cannot be impersonated".to_string()));
+        }
+
+        let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let expires_at = Utc::now() + Duration::minutes(IMPERSONATION_TTL_MINUTES);
+
+        let session = sqlx::query_as::<_, ImpersonationSession>(&format!(
+            r#"
+            INSERT INTO marketplace_impersonation_sessions (id, admin_id, user_id, token_hash, reason, created_at, expires_at)
+            VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP, $6)
+            RETURNING {}
+            "#,
+            SESSION_COLUMNS
+        ))
+        .bind(Uuid::new_v4())
+        .bind(admin_id)
+        .bind(&request.user_id)
+        .bind(hash_token(&token))
+        .bind(request.reason.trim())
+        .bind(expires_at)
+        .fetch_one(&self.pool)
+        .await?;
+
+        tracing::info!(admin_id = %session.admin_id, user_id = %session.user_id, session_id = %session.id, "impersonation session issued");
+        Ok(ImpersonationGrant { session, token })
+    }
+
+    /// End a session before it expires
+    pub async fn revoke(&self, auth_user: &AuthUser, session_id: Uuid) -> Result<ImpersonationSession, AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+
+        sqlx::query_as::<_, ImpersonationSession>(&format!(
+            r#"
+            UPDATE marketplace_impersonation_sessions
+            SET revoked_at = CURRENT_TIMESTAMP
+            WHERE id = $1 AND revoked_at IS NULL AND expires_at > CURRENT_TIMESTAMP
+            RETURNING {}
+            "#,
+            SESSION_COLUMNS
+        ))
+        .bind(session_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Active impersonation session not found".to_string()))
+    }
+
+    /// Requests made in a session, oldest first
+    pub async fn audit(&self, auth_user: &AuthUser, session_id: Uuid) -> Result<Vec<ImpersonationAuditEntry>, AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+
+        let entries = sqlx::query_as::<_, ImpersonationAuditEntry>(
+            r#"
+            SELECT * FROM marketplace_impersonation_audit
+            WHERE session_id = $1
+            ORDER BY created_at ASC
+            LIMIT 1000
+            "#
+        )
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(entries)
+    }
+
+    // Who a request carrying `token` acts as, after recording it in the audit log
+    async fn impersonate(&self, admin: &AuthUser, request: &Request, token: &str) -> Result<Impersonation, AppError> {
+        let service = MarketplaceService::new(self.pool.clone());
+
+        let session = sqlx::query_as::<_, ImpersonationSession>(&format!(
+            r#"
+            SELECT {} FROM marketplace_impersonation_sessions
+            WHERE token_hash = $1 AND admin_id = $2 AND revoked_at IS NULL AND expires_at > CURRENT_TIMESTAMP
+            "#,
+            SESSION_COLUMNS
+        ))
+        .bind(hash_token(token))
+        .bind(&admin.0.auth0_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::Forbidden("Impersonation token is invalid, expired or revoked".to_string()))?;
+
+        // The admin role may have been taken away since the session was issued
+        if !service.is_admin(&admin.0.auth0_id).await? {
+            return Err(AppError::Forbidden("Admin access required".to_string()));
+        }
+
+        // Reads that reveal secrets or start work are refused later, by `refuse` on their routes
+        let read_only = matches!(*request.method(), Method::GET | Method::HEAD);
+
+        let audit_id = Uuid::new_v4();
+        let path = request.uri().path_and_query().map_or_else(|| request.uri().path(), |path| path.as_str());
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_impersonation_audit (id, session_id, admin_id, user_id, method, path, allowed, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, CURRENT_TIMESTAMP)
+            "#
+        )
+        .bind(audit_id)
+        .bind(session.id)
+        .bind(&session.admin_id)
+        .bind(&session.user_id)
+        .bind(request.method().as_str())
+        .bind(path)
+        .bind(read_only)
+        .execute(&self.pool)
+        .await?;
+
+        if !read_only {
+            return Err(AppError::Forbidden("Impersonation is read-only".to_string()));
+        }
+
+        tracing::info!(admin_id = %session.admin_id, user_id = %session.user_id, path, "impersonated request");
+        Ok(Impersonation { user_id: session.user_id, audit_id })
+    }
+}
+
+// Placed in the request extensions by `apply` for requests made in an impersonation session
+#[derive(Debug, Clone)]
+struct Impersonation {
+    user_id: String,
+    audit_id: Uuid, // the request's entry in the audit log
+}
+
+/// An identity an impersonation session can stand in for
+pub trait Impersonatable {
+    fn act_as(&mut self, user_id: &str);
+}
+
+impl Impersonatable for AuthUser {
+    fn act_as(&mut self, user_id: &str) {
+        self.0.auth0_id = user_id.to_string();
+    }
+}
+
+/// The user a request acts as: the impersonated user inside an impersonation session,
+/// otherwise the caller. Handlers and middleware behind `apply` extract this instead of
+/// `AuthUser`, which always names the caller.
+pub struct ActingUser<U = AuthUser>(pub U);
+
+#[async_trait]
+impl<S, U> FromRequestParts<S> for ActingUser<U>
+where
+    S: Send + Sync,
+    U: FromRequestParts<S> + Impersonatable + Send,
+{
+    type Rejection = U::Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let mut user = U::from_request_parts(parts, state).await?;
+        if let Some(impersonation) = parts.extensions.get::<Impersonation>() {
+            user.act_as(&impersonation.user_id);
+        }
+        Ok(ActingUser(user))
+    }
+}
+
+/// Requests with an impersonation token act as the impersonated user, as seen through
+/// `ActingUser`. Requests without the header pass through untouched.
+pub async fn apply(State(pool): State<PgPool>, admin: AuthUser, mut request: Request, next: Next) -> Response {
+    let Some(token) = request
+        .headers()
+        .get(IMPERSONATION_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+    else {
+        return next.run(request).await;
+    };
+
+    match ImpersonationService::new(pool).impersonate(&admin, &request, &token).await {
+        Ok(impersonation) => {
+            request.extensions_mut().insert(impersonation);
+            next.run(request).await
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Marks a read route that reveals secrets or starts work on the user's behalf: inside an
+/// impersonation session it is refused, and the audit log records the refusal. Add it to
+/// the route with `middleware::from_fn_with_state(pool, impersonation::refuse)`.
+pub async fn refuse(State(pool): State<PgPool>, request: Request, next: Next) -> Response {
+    let Some(impersonation) = request.extensions().get::<Impersonation>() else {
+        return next.run(request).await;
+    };
+
+    let recorded = sqlx::query("UPDATE marketplace_impersonation_audit SET allowed = FALSE WHERE id = $1")
+        .bind(impersonation.audit_id)
+        .execute(&pool)
+        .await;
+    if let Err(e) = recorded {
+        tracing::warn!(error = %e, audit_id = %impersonation.audit_id, "recording refused impersonated request failed");
+    }
+
+    AppError::Forbidden("This is not available while impersonating".to_string()).into_response()
+}
+
+fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Stand-in for `AuthUser`, naming the caller in a header
+    struct TestUser(String);
+
+    #[async_trait]
+    impl<S: Send + Sync> FromRequestParts<S> for TestUser {
+        type Rejection = AppError;
+
+        async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+            parts
+                .headers
+                .get("x-test-user")
+                .and_then(|value| value.to_str().ok())
+                .map(|user_id| TestUser(user_id.to_string()))
+                .ok_or_else(|| AppError::Unauthorized("Not signed in".to_string()))
+        }
+    }
+
+    impl Impersonatable for TestUser {
+        fn act_as(&mut self, user_id: &str) {
+            self.0 = user_id.to_string();
+        }
+    }
+
+    fn request_parts(caller: Option<&str>, impersonating: Option<&str>) -> Parts {
+        let mut builder = axum::http::Request::builder().uri("/dashboard");
+        if let Some(caller) = caller {
+            builder = builder.header("x-test-user", caller);
+        }
+        let (mut parts, _) = builder.body(()).unwrap().into_parts();
+        if let Some(user_id) = impersonating {
+            parts.extensions.insert(Impersonation { user_id: user_id.to_string(), audit_id: Uuid::new_v4() });
+        }
+        parts
+    }
+
+    #[tokio::test]
+    async fn acting_user_is_the_impersonated_user_inside_a_session() {
+        let mut parts = request_parts(Some("admin"), Some("user"));
+
+        let ActingUser(user) = ActingUser::<TestUser>::from_request_parts(&mut parts, &()).await.unwrap();
+
+        assert_eq!(user.0, "user");
+    }
+
+    #[tokio::test]
+    async fn acting_user_is_the_caller_outside_a_session() {
+        let mut parts = request_parts(Some("seller"), None);
+
+        let ActingUser(user) = ActingUser::<TestUser>::from_request_parts(&mut parts, &()).await.unwrap();
+
+        assert_eq!(user.0, "seller");
+    }
+
+    #[tokio::test]
+    async fn acting_user_still_requires_the_caller_to_be_signed_in() {
+        let mut parts = request_parts(None, Some("user"));
+
+        let result = ActingUser::<TestUser>::from_request_parts(&mut parts, &()).await;
+
+        assert!(matches!(result, Err(AppError::Unauthorized(_))));
+    }
+}
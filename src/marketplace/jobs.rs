@@ -0,0 +1,221 @@
+use crate::error::AppError;
+use crate::marketplace::anomaly::ActivityPurgeJob;
+use crate::marketplace::archive::ListingArchiveJob;
+use crate::marketplace::away::SellerReactivationJob;
+use crate::marketplace::purchases::CodeExpiryReminderJob;
+use crate::marketplace::holds::CheckoutHoldExpiryJob;
+use crate::marketplace::side_effects::SideEffectRetryJob;
+use crate::marketplace::badges::BadgeJob;
+use crate::marketplace::commission::CommissionTierJob;
+use crate::marketplace::deletion::AccountDeletionJob;
+use crate::marketplace::export::DataExportJob;
+use crate::marketplace::keyring::CouponReencryptionJob;
+use crate::marketplace::loyalty::LoyaltyExpiryJob;
+use crate::marketplace::markdown::DescriptionRenderJob;
+use crate::marketplace::market_discount::MarketDiscountJob;
+use crate::marketplace::notifications::NotificationDeliveryJob;
+use crate::marketplace::payout_accounts::PayoutAccountSyncJob;
+use crate::marketplace::promotions::PromotionScheduleJob;
+use crate::marketplace::protection::ProtectionClaimJob;
+use crate::marketplace::retention::{PurgeDeletedListingsJob, RetentionCleanupJob};
+use crate::marketplace::reversals::ChargebackReconciliationJob;
+use crate::marketplace::seo::SeoFeedJob;
+use crate::marketplace::translations::MachineTranslationJob;
+use crate::marketplace::trending::TrendingRefreshJob;
+use crate::marketplace::trust_scores::{TrustScoreQueueJob, TrustScoreRecomputeJob};
+use crate::marketplace::upi::UpiReconciliationJob;
+use crate::marketplace::verification::VerificationSlaJob;
+use async_trait::async_trait;
+use chrono::{DateTime, TimeZone, Utc};
+use sqlx::{PgPool, Row};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Scheduled maintenance work. `run` is called at most once per scheduled slot across
+/// all replicas, so jobs don't need their own locking.
+#[async_trait]
+pub trait Job: Send + Sync {
+    /// Stable identifier, used for the advisory lock and the run bookkeeping
+    fn name(&self) -> &'static str;
+
+    async fn run(&self, pool: &PgPool) -> Result<(), AppError>;
+}
+
+#[derive(Debug, Clone)]
+pub enum Schedule {
+    /// Fixed period aligned to the Unix epoch, so every replica computes the same slots
+    Every(Duration),
+    /// Cron expression with a leading seconds field, e.g. `0 30 3 * * *`, evaluated in UTC
+    Cron(Box<cron::Schedule>),
+}
+
+impl Schedule {
+    pub fn every(period: Duration) -> Self {
+        Schedule::Every(period)
+    }
+
+    pub fn cron(expression: &str) -> Result<Self, AppError> {
+        cron::Schedule::from_str(expression)
+            .map(|schedule| Schedule::Cron(Box::new(schedule)))
+            .map_err(|e| AppError::InternalError(format!("Invalid cron expression {:?}: {}", expression, e)))
+    }
+
+    /// The first slot strictly after `now`
+    pub fn next_after(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Schedule::Every(period) => {
+                let period_ms = (period.as_millis() as i64).max(1);
+                let next = (now.timestamp_millis() / period_ms + 1) * period_ms;
+                Utc.timestamp_millis_opt(next).single()
+            }
+            Schedule::Cron(schedule) => schedule.after(&now).next(),
+        }
+    }
+}
+
+/// Runs registered jobs on their schedules. Each run takes a Postgres advisory lock
+/// so overlapping runs are skipped, and claims its slot in `marketplace_job_runs`
+/// so a replica whose timer fires late cannot repeat a slot another replica finished.
+pub struct JobRunner {
+    pool: PgPool,
+    jobs: Vec<(Arc<dyn Job>, Schedule)>,
+}
+
+impl JobRunner {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool, jobs: Vec::new() }
+    }
+
+    /// The marketplace maintenance jobs on their default schedules
+    pub fn maintenance(pool: PgPool) -> Result<Self, AppError> {
+        Ok(Self::new(pool)
+            .add(PurgeDeletedListingsJob, Schedule::cron("0 0 4 * * *")?)
+            .add(ListingArchiveJob, Schedule::cron("0 30 4 * * *")?)
+            .add(RetentionCleanupJob, Schedule::cron("0 0 5 * * *")?)
+            .add(TrustScoreRecomputeJob, Schedule::cron("0 0 2 * * *")?)
+            .add(BadgeJob, Schedule::cron("0 30 2 * * *")?)
+            .add(CommissionTierJob, Schedule::cron("0 45 2 * * *")?)
+            .add(MarketDiscountJob, Schedule::cron("0 0 3 * * *")?)
+            .add(CouponReencryptionJob, Schedule::every(Duration::from_secs(3600)))
+            .add(DataExportJob, Schedule::every(Duration::from_secs(60)))
+            .add(AccountDeletionJob, Schedule::every(Duration::from_secs(300)))
+            .add(ProtectionClaimJob, Schedule::every(Duration::from_secs(900)))
+            .add(VerificationSlaJob, Schedule::every(Duration::from_secs(300)))
+            .add(PromotionScheduleJob, Schedule::every(Duration::from_secs(60)))
+            .add(SellerReactivationJob, Schedule::every(Duration::from_secs(300)))
+            .add(CodeExpiryReminderJob, Schedule::every(Duration::from_secs(3600)))
+            .add(CheckoutHoldExpiryJob, Schedule::every(Duration::from_secs(60)))
+            .add(SideEffectRetryJob, Schedule::every(Duration::from_secs(30)))
+            .add(LoyaltyExpiryJob, Schedule::cron("0 30 3 * * *")?)
+            .add(UpiReconciliationJob, Schedule::every(Duration::from_secs(300)))
+            .add(ChargebackReconciliationJob, Schedule::every(Duration::from_secs(600)))
+            .add(PayoutAccountSyncJob, Schedule::every(Duration::from_secs(900)))
+            .add(NotificationDeliveryJob, Schedule::every(Duration::from_secs(60)))
+            .add(DescriptionRenderJob, Schedule::every(Duration::from_secs(600)))
+            .add(MachineTranslationJob, Schedule::every(Duration::from_secs(60)))
+            .add(TrendingRefreshJob, Schedule::every(Duration::from_secs(300)))
+            .add(SeoFeedJob, Schedule::every(Duration::from_secs(3600)))
+            .add(TrustScoreQueueJob, Schedule::every(Duration::from_secs(60)))
+            .add(ActivityPurgeJob, Schedule::cron("0 15 4 * * *")?))
+    }
+
+    pub fn add(mut self, job: impl Job + 'static, schedule: Schedule) -> Self {
+        self.jobs.push((Arc::new(job), schedule));
+        self
+    }
+
+    pub fn spawn(self) -> Vec<tokio::task::JoinHandle<()>> {
+        self.jobs
+            .into_iter()
+            .map(|(job, schedule)| {
+                let pool = self.pool.clone();
+                tokio::spawn(async move {
+                    while let Some(slot) = schedule.next_after(Utc::now()) {
+                        let wait = (slot - Utc::now()).to_std().unwrap_or_default();
+                        tokio::time::sleep(wait).await;
+
+                        if let Err(e) = run_once(&pool, job.as_ref(), slot).await {
+                            tracing::error!(job = job.name(), error = %e, "job failed");
+                        }
+                    }
+                })
+            })
+            .collect()
+    }
+}
+
+/// Run `job` for `slot` unless another replica holds its lock or already ran that slot.
+/// Returns whether this call ran the job.
+pub async fn run_once(pool: &PgPool, job: &dyn Job, slot: DateTime<Utc>) -> Result<bool, AppError> {
+    // Session-level advisory locks belong to the connection, so lock and unlock on the same one
+    let mut conn = pool.acquire().await?;
+    let key = lock_key(job.name());
+
+    let locked: bool = sqlx::query("SELECT pg_try_advisory_lock($1) as locked")
+        .bind(key)
+        .fetch_one(&mut *conn)
+        .await?
+        .get("locked");
+
+    if !locked {
+        return Ok(false);
+    }
+
+    let result = claim_and_run(pool, job, slot).await;
+
+    sqlx::query("SELECT pg_advisory_unlock($1)")
+        .bind(key)
+        .execute(&mut *conn)
+        .await?;
+
+    result
+}
+
+async fn claim_and_run(pool: &PgPool, job: &dyn Job, slot: DateTime<Utc>) -> Result<bool, AppError> {
+    let claimed = sqlx::query(
+        r#"
+        INSERT INTO marketplace_job_runs (job_name, last_scheduled_at, last_started_at)
+        VALUES ($1, $2, CURRENT_TIMESTAMP)
+        ON CONFLICT (job_name) DO UPDATE
+        SET last_scheduled_at = EXCLUDED.last_scheduled_at,
+            last_started_at = EXCLUDED.last_started_at
+        WHERE marketplace_job_runs.last_scheduled_at < EXCLUDED.last_scheduled_at
+        RETURNING job_name
+        "#
+    )
+    .bind(job.name())
+    .bind(slot)
+    .fetch_optional(pool)
+    .await?;
+
+    if claimed.is_none() {
+        return Ok(false);
+    }
+
+    let result = job.run(pool).await;
+
+    sqlx::query(
+        r#"
+        UPDATE marketplace_job_runs
+        SET last_finished_at = CURRENT_TIMESTAMP, last_error = $2
+        WHERE job_name = $1
+        "#
+    )
+    .bind(job.name())
+    .bind(result.as_ref().err().map(|e| e.to_string()))
+    .execute(pool)
+    .await?;
+
+    result.map(|_| true)
+}
+
+// FNV-1a, so the key for a job name is the same on every build and replica
+fn lock_key(name: &str) -> i64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in name.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash as i64
+}
@@ -0,0 +1,171 @@
+use crate::config::Config;
+use crate::error::AppError;
+use crate::marketplace::jobs::Job;
+use crate::services::encryption::EncryptionService;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Row};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+// Codes re-encrypted per job pass
+const REENCRYPTION_BATCH_SIZE: i64 = 500;
+
+/// Versioned coupon code encryption. Stored values are `v<version>:<ciphertext>:<nonce>`;
+/// values written before versioning (`<ciphertext>:<nonce>`) are read as version 1.
+pub struct CouponKeyring {
+    current_version: u32,
+    services: HashMap<u32, EncryptionService>,
+}
+
+impl CouponKeyring {
+    pub fn from_config(config: &Config) -> Result<Self, AppError> {
+        let keys = &config.encryption;
+        let current = keys
+            .current
+            .as_ref()
+            .ok_or_else(|| AppError::InternalError("ENCRYPTION_KEY is not configured".to_string()))?;
+
+        let mut services = HashMap::new();
+        services.insert(keys.current_version, EncryptionService::new(current.expose())?);
+        for (version, key) in &keys.retired {
+            services.insert(*version, EncryptionService::new(key.expose())?);
+        }
+
+        Ok(Self {
+            current_version: keys.current_version,
+            services,
+        })
+    }
+
+    pub fn current_version(&self) -> u32 {
+        self.current_version
+    }
+
+    /// Encrypt with the current key
+    pub fn encrypt(&self, plaintext: &str) -> Result<String, AppError> {
+        let (ciphertext, nonce) = self.services[&self.current_version].encrypt_string(plaintext)?;
+        Ok(format!("v{}:{}:{}", self.current_version, ciphertext, nonce))
+    }
+
+    /// Decrypt with whichever key version the value was written with
+    pub fn decrypt(&self, stored: &str) -> Result<String, AppError> {
+        let (version, ciphertext, nonce) = Self::split(stored)?;
+        let service = self.services.get(&version).ok_or_else(|| {
+            AppError::InternalError(format!("No encryption key configured for version {}", version))
+        })?;
+
+        Ok(service.decrypt_string(ciphertext, nonce)?)
+    }
+
+    fn split(stored: &str) -> Result<(u32, &str, &str), AppError> {
+        let invalid = || AppError::InternalError("Invalid encrypted data format".to_string());
+        let parts: Vec<&str> = stored.split(':').collect();
+
+        match parts.as_slice() {
+            [ciphertext, nonce] => Ok((1, ciphertext, nonce)),
+            [version, ciphertext, nonce] => {
+                let version = version
+                    .strip_prefix('v')
+                    .and_then(|v| v.parse().ok())
+                    .ok_or_else(invalid)?;
+                Ok((version, ciphertext, nonce))
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+pub struct KeyRotationService {
+    pool: PgPool,
+}
+
+impl KeyRotationService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Re-encrypt a batch of codes still on an older key, returning how many were looked at.
+    /// Codes that cannot be decrypted are left as they are and marked with the error; those
+    /// marked since `run_started_at` are not picked up again, earlier failures are retried.
+    pub async fn reencrypt_batch(
+        &self,
+        keyring: &CouponKeyring,
+        run_started_at: DateTime<Utc>,
+    ) -> Result<usize, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let rows = sqlx::query(
+            r#"
+            SELECT id, encrypted_code FROM marketplace_coupon_codes
+            WHERE encrypted_code NOT LIKE $1
+            AND (reencryption_failed_at IS NULL OR reencryption_failed_at < $3)
+            ORDER BY id
+            LIMIT $2
+            FOR UPDATE SKIP LOCKED
+            "#
+        )
+        .bind(format!("v{}:%", keyring.current_version()))
+        .bind(REENCRYPTION_BATCH_SIZE)
+        .bind(run_started_at)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for row in &rows {
+            let id: Uuid = row.get("id");
+            let stored: String = row.get("encrypted_code");
+
+            match keyring.decrypt(&stored).and_then(|code| keyring.encrypt(&code)) {
+                Ok(reencrypted) => {
+                    sqlx::query(
+                        r#"
+                        UPDATE marketplace_coupon_codes
+                        SET encrypted_code = $1, reencryption_failed_at = NULL, reencryption_error = NULL
+                        WHERE id = $2
+                        "#
+                    )
+                    .bind(&reencrypted)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                Err(e) => {
+                    tracing::warn!(code_id = %id, error = %e, "coupon code could not be re-encrypted");
+                    sqlx::query(
+                        "UPDATE marketplace_coupon_codes SET reencryption_failed_at = $1, reencryption_error = $2 WHERE id = $3"
+                    )
+                    .bind(Utc::now())
+                    .bind(e.to_string())
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+            }
+        }
+
+        tx.commit().await?;
+        Ok(rows.len())
+    }
+}
+
+/// Moves stored coupon codes onto the current key; once no code is left on an old key,
+/// retired keys can be removed from `ENCRYPTION_KEYS_RETIRED`. Codes marked with a
+/// `reencryption_error` need a look first.
+pub struct CouponReencryptionJob;
+
+#[async_trait]
+impl Job for CouponReencryptionJob {
+    fn name(&self) -> &'static str {
+        "coupon_reencryption"
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<(), AppError> {
+        let keyring = CouponKeyring::from_config(Config::get())?;
+        let service = KeyRotationService::new(pool.clone());
+        let started_at = Utc::now();
+
+        // Drain everything on old keys in one run
+        while service.reencrypt_batch(&keyring, started_at).await? > 0 {}
+        Ok(())
+    }
+}
@@ -0,0 +1,159 @@
+use crate::config::Config;
+use crate::error::AppError;
+use crate::models::marketplace::{ListingAvailabilityEvent, MarketplaceListing};
+use axum::response::sse::Event;
+use futures::{stream, Stream, StreamExt};
+use redis::AsyncCommands;
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+use std::time::Duration;
+use uuid::Uuid;
+
+// Postgres channel the listings trigger notifies on
+const PG_CHANNEL: &str = "marketplace_listing_events";
+
+// Every instance receives each notification; the first to claim it publishes it
+const CLAIM_TTL_SECS: u64 = 60;
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+fn redis_channel(listing_id: Uuid) -> String {
+    format!("listing-events:{}", listing_id)
+}
+
+impl ListingAvailabilityEvent {
+    /// SSE event name: `price_changed`, `status_changed` or `stock_changed`
+    pub fn kind(&self) -> &'static str {
+        if self.selling_price != self.previous_price {
+            "price_changed"
+        } else if self.status != self.previous_status || self.deleted {
+            "status_changed"
+        } else {
+            "stock_changed"
+        }
+    }
+
+    fn snapshot(listing: &MarketplaceListing) -> Self {
+        Self {
+            event_id: Uuid::new_v4(),
+            listing_id: listing.id,
+            status: listing.status,
+            previous_status: listing.status,
+            selling_price: listing.selling_price.clone(),
+            previous_price: listing.selling_price.clone(),
+            remaining_quantity: listing.remaining_quantity,
+            deleted: listing.deleted_at.is_some(),
+            occurred_at: listing.updated_at,
+        }
+    }
+}
+
+/// Live status, price and stock changes of listings. A database trigger announces each
+/// committed change; `spawn_listing_event_forwarder` relays it to Redis pub/sub, where
+/// every instance serving a listing's event stream is subscribed.
+pub struct ListingEventService {
+    pool: PgPool,
+    redis_client: redis::Client,
+}
+
+impl ListingEventService {
+    pub fn new(pool: PgPool) -> Result<Self, AppError> {
+        let redis_url = Config::get()
+            .redis_url
+            .as_deref()
+            .ok_or_else(|| AppError::ServiceUnavailable("Live listing updates are not available".to_string()))?;
+        let redis_client = redis::Client::open(redis_url)
+            .map_err(|e| AppError::InternalError(format!("Redis connection error: {}", e)))?;
+        Ok(Self { pool, redis_client })
+    }
+
+    /// Server-sent events for a listing: a `snapshot` of its current state, then one event per change
+    pub async fn subscribe(
+        &self,
+        listing_id: Uuid,
+    ) -> Result<impl Stream<Item = Result<Event, axum::Error>>, AppError> {
+        // Subscribe before reading the snapshot so no change falls in between
+        let mut pubsub = self
+            .redis_client
+            .get_async_connection()
+            .await
+            .map_err(|e| AppError::InternalError(format!("Redis connection error: {}", e)))?
+            .into_pubsub();
+        pubsub
+            .subscribe(redis_channel(listing_id))
+            .await
+            .map_err(|e| AppError::InternalError(format!("Redis subscribe error: {}", e)))?;
+
+        let listing = sqlx::query_as::<_, MarketplaceListing>(
+            "SELECT * FROM marketplace_listings WHERE id = $1 AND deleted_at IS NULL"
+        )
+        .bind(listing_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Listing not found".to_string()))?;
+
+        let snapshot = Event::default()
+            .event("snapshot")
+            .json_data(ListingAvailabilityEvent::snapshot(&listing));
+
+        let changes = pubsub.into_on_message().filter_map(|message| async move {
+            let payload: String = message.get_payload().ok()?;
+            let event: ListingAvailabilityEvent = serde_json::from_str(&payload).ok()?;
+            Some(Event::default().id(event.event_id.to_string()).event(event.kind()).json_data(&event))
+        });
+
+        Ok(stream::once(async move { snapshot }).chain(changes))
+    }
+}
+
+/// Relay listing changes announced by Postgres to Redis pub/sub, reconnecting on failure
+pub fn spawn_listing_event_forwarder(pool: PgPool, redis_url: String) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = forward_listing_events(&pool, &redis_url).await {
+                tracing::warn!(error = %e, "listing event forwarder stopped, reconnecting");
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    })
+}
+
+async fn forward_listing_events(pool: &PgPool, redis_url: &str) -> Result<(), AppError> {
+    let redis_error = |e: redis::RedisError| AppError::InternalError(format!("Redis error: {}", e));
+
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.listen(PG_CHANNEL).await?;
+    let mut conn = redis::Client::open(redis_url)
+        .map_err(redis_error)?
+        .get_async_connection()
+        .await
+        .map_err(redis_error)?;
+
+    loop {
+        let notification = listener.recv().await?;
+        let event: ListingAvailabilityEvent = match serde_json::from_str(notification.payload()) {
+            Ok(event) => event,
+            Err(e) => {
+                tracing::warn!(error = %e, "malformed listing event");
+                continue;
+            }
+        };
+
+        let claimed: Option<String> = redis::cmd("SET")
+            .arg(format!("listing-event-claim:{}", event.event_id))
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(CLAIM_TTL_SECS)
+            .query_async(&mut conn)
+            .await
+            .map_err(redis_error)?;
+        if claimed.is_none() {
+            continue;
+        }
+
+        conn.publish::<_, _, ()>(redis_channel(event.listing_id), notification.payload())
+            .await
+            .map_err(redis_error)?;
+    }
+}
@@ -0,0 +1,233 @@
+use crate::config::Config;
+use crate::error::AppError;
+use crate::marketplace::cart::spread_discount;
+use crate::marketplace::jobs::Job;
+use crate::models::marketplace::{
+    Checkout, LoyaltyBalance, LoyaltyEntry, LoyaltyEntryKind, LoyaltyHistory, MarketplaceTransaction,
+};
+use async_trait::async_trait;
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{Duration, Utc};
+use sqlx::{PgConnection, PgPool, Postgres, Row, Transaction};
+use uuid::Uuid;
+
+// How far ahead the balance warns about lapsing points
+const EXPIRY_WARNING_DAYS: i64 = 30;
+
+// Ledger history returned with the balance
+const HISTORY_LIMIT: i64 = 50;
+
+// Points redeemed on a checkout are given back once every item of it is cancelled
+const LIVE_ENTRY: &str = r#"
+    (e.checkout_id IS NULL OR EXISTS (
+        SELECT 1 FROM marketplace_transactions t
+        WHERE t.checkout_id = e.checkout_id AND t.status <> 'cancelled'
+    ))
+"#;
+
+/// Loyalty points. Buyers earn points on every completed purchase in proportion to what they
+/// paid and redeem them as a discount at checkout, funded by the platform like promo codes.
+///
+/// Earned points lapse after the configured number of months. Spending is taken from the
+/// oldest points first, so the points that lapse are whatever was earned before the cutoff
+/// and has not been covered by redemptions and earlier expiries.
+pub struct LoyaltyService {
+    pool: PgPool,
+}
+
+impl LoyaltyService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn balance(&self, user_id: &str) -> Result<LoyaltyBalance, AppError> {
+        let mut conn = self.pool.acquire().await?;
+        points_balance(&mut *conn, user_id).await
+    }
+
+    pub async fn history(&self, user_id: &str) -> Result<LoyaltyHistory, AppError> {
+        let balance = self.balance(user_id).await?;
+
+        let entries = sqlx::query_as::<_, LoyaltyEntry>(
+            "SELECT * FROM marketplace_loyalty_entries WHERE user_id = $1 ORDER BY created_at DESC LIMIT $2"
+        )
+        .bind(user_id)
+        .bind(HISTORY_LIMIT)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(LoyaltyHistory { balance, entries })
+    }
+
+    /// Credit the buyer for a completed transaction; does nothing if already credited
+    pub(crate) async fn award(tx: &mut Transaction<'_, Postgres>, transaction: &MarketplaceTransaction) -> Result<(), AppError> {
+        let settings = &Config::get().loyalty;
+
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_loyalty_entries (id, user_id, points, kind, transaction_id, expires_at, created_at)
+            SELECT $1, buyer_id, earned, $2, id, CURRENT_TIMESTAMP + make_interval(months => $3), CURRENT_TIMESTAMP
+            FROM (
+                SELECT id, buyer_id, FLOOR((amount - discount_amount - refunded_amount) * $4)::bigint as earned
+                FROM marketplace_transactions WHERE id = $5
+            ) paid
+            WHERE earned > 0
+            ON CONFLICT (transaction_id) WHERE kind = 'earned' DO NOTHING
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(LoyaltyEntryKind::Earned)
+        .bind(settings.expiry_months as i32)
+        .bind(settings.points_per_unit_spent)
+        .bind(transaction.id)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Spend points on a checkout being created in `tx`, lowering what every item costs
+    pub(crate) async fn redeem(
+        tx: &mut Transaction<'_, Postgres>,
+        checkout: &Checkout,
+        points: i64,
+        transactions: &mut [MarketplaceTransaction],
+    ) -> Result<Checkout, AppError> {
+        let settings = &Config::get().loyalty;
+
+        // Serialize redemptions per buyer so the same points cannot be spent twice
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+            .bind(format!("loyalty:{}", checkout.buyer_id))
+            .execute(&mut **tx)
+            .await?;
+
+        let balance = points_balance(&mut **tx, &checkout.buyer_id).await?;
+        if points > balance.points {
+            return Err(AppError::Conflict(format!("You only have {} points", balance.points)));
+        }
+
+        let due = &checkout.total_amount - &checkout.discount_amount;
+        let max_discount = (due * BigDecimal::from(settings.max_redeem_percent) / BigDecimal::from(100)).round(2);
+        let discount = points_value(points);
+
+        if discount > max_discount {
+            return Err(AppError::UnprocessableEntity(format!(
+                "Points can pay for at most {}% of an order, {} here",
+                settings.max_redeem_percent, max_discount
+            )));
+        }
+        if discount.is_zero() {
+            return Err(AppError::UnprocessableEntity("Too few points to take anything off".to_string()));
+        }
+
+        spread_discount(tx, transactions.iter_mut().collect(), &discount).await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_loyalty_entries (id, user_id, points, kind, checkout_id, created_at)
+            VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP)
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(&checkout.buyer_id)
+        .bind(-points)
+        .bind(LoyaltyEntryKind::Redeemed)
+        .bind(checkout.id)
+        .execute(&mut **tx)
+        .await?;
+
+        let checkout = sqlx::query_as::<_, Checkout>(
+            r#"
+            UPDATE marketplace_checkouts
+            SET discount_amount = discount_amount + $2, points_redeemed = $3
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(checkout.id)
+        .bind(&discount)
+        .bind(points)
+        .fetch_one(&mut **tx)
+        .await?;
+
+        Ok(checkout)
+    }
+
+    /// Record expiry entries for points that lapsed unspent
+    pub async fn expire_lapsed(&self) -> Result<u64, AppError> {
+        let result = sqlx::query(&format!(
+            r#"
+            INSERT INTO marketplace_loyalty_entries (id, user_id, points, kind, created_at)
+            SELECT gen_random_uuid(), user_id, spent - lapsed, $1, CURRENT_TIMESTAMP
+            FROM (
+                SELECT
+                    e.user_id,
+                    COALESCE(SUM(e.points) FILTER (WHERE e.kind = 'earned' AND e.expires_at <= CURRENT_TIMESTAMP), 0) as lapsed,
+                    COALESCE(-SUM(e.points) FILTER (WHERE e.points < 0), 0) as spent
+                FROM marketplace_loyalty_entries e
+                WHERE {}
+                GROUP BY e.user_id
+            ) totals
+            WHERE lapsed > spent
+            "#,
+            LIVE_ENTRY
+        ))
+        .bind(LoyaltyEntryKind::Expired)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+// What `points` take off at checkout
+fn points_value(points: i64) -> BigDecimal {
+    (BigDecimal::from(points) / BigDecimal::from(Config::get().loyalty.points_per_unit_discount)).round(2)
+}
+
+// Spendable points, excluding points that have lapsed but not been expired by the job yet
+async fn points_balance(conn: &mut PgConnection, user_id: &str) -> Result<LoyaltyBalance, AppError> {
+    let now = Utc::now();
+    let row = sqlx::query(&format!(
+        r#"
+        SELECT
+            COALESCE(SUM(e.points), 0)::bigint as total,
+            COALESCE(SUM(e.points) FILTER (WHERE e.kind = 'earned' AND e.expires_at <= $2), 0)::bigint as lapsed,
+            COALESCE(SUM(e.points) FILTER (WHERE e.kind = 'earned' AND e.expires_at <= $3), 0)::bigint as lapsing,
+            COALESCE(-SUM(e.points) FILTER (WHERE e.points < 0), 0)::bigint as spent
+        FROM marketplace_loyalty_entries e
+        WHERE e.user_id = $1 AND {}
+        "#,
+        LIVE_ENTRY
+    ))
+    .bind(user_id)
+    .bind(now)
+    .bind(now + Duration::days(EXPIRY_WARNING_DAYS))
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let spent: i64 = row.get("spent");
+    let lapsed = (row.get::<i64, _>("lapsed") - spent).max(0);
+    let lapsing = (row.get::<i64, _>("lapsing") - spent).max(0);
+    let points = (row.get::<i64, _>("total") - lapsed).max(0);
+
+    Ok(LoyaltyBalance {
+        points,
+        value: points_value(points),
+        expiring_soon: lapsing - lapsed,
+    })
+}
+
+pub struct LoyaltyExpiryJob;
+
+#[async_trait]
+impl Job for LoyaltyExpiryJob {
+    fn name(&self) -> &'static str {
+        "loyalty_expiry"
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<(), AppError> {
+        LoyaltyService::new(pool.clone()).expire_lapsed().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+use crate::error::AppError;
+use crate::marketplace::jobs::Job;
+use ammonia::Builder;
+use async_trait::async_trait;
+use pulldown_cmark::{html, Event, Options, Parser};
+use sqlx::PgPool;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+const BACKFILL_BATCH_SIZE: i64 = 500;
+
+// Formatting only: no images, embeds, forms, scripts or styles
+const ALLOWED_TAGS: &[&str] = &[
+    "p", "br", "hr", "strong", "em", "del", "code", "pre", "blockquote", "ul", "ol", "li",
+    "h3", "h4", "h5", "h6", "a", "table", "thead", "tbody", "tr", "th", "td",
+];
+
+const ALLOWED_URL_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+/// Sanitized HTML for a markdown listing description, or `None` when it is blank.
+/// HTML written into the markdown is shown as text rather than rendered.
+pub fn description_html(description: Option<&str>) -> Option<String> {
+    let markdown = description.map(str::trim).filter(|description| !description.is_empty())?;
+
+    let events = Parser::new_ext(markdown, Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH).map(|event| match event {
+        Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+        other => other,
+    });
+    let mut rendered = String::new();
+    html::push_html(&mut rendered, events);
+
+    // Headings are demoted below the page's own; links leave the site without referrer or ranking
+    let rendered = rendered
+        .replace("<h1>", "<h3>").replace("</h1>", "</h3>")
+        .replace("<h2>", "<h4>").replace("</h2>", "</h4>");
+    let sanitized = Builder::default()
+        .tags(ALLOWED_TAGS.iter().copied().collect::<HashSet<_>>())
+        .url_schemes(ALLOWED_URL_SCHEMES.iter().copied().collect::<HashSet<_>>())
+        .link_rel(Some("nofollow noopener noreferrer ugc"))
+        .clean(&rendered)
+        .to_string();
+
+    Some(sanitized)
+}
+
+/// Render descriptions of listings saved before descriptions were rendered
+pub struct DescriptionRenderJob;
+
+#[async_trait]
+impl Job for DescriptionRenderJob {
+    fn name(&self) -> &'static str {
+        "description_render"
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<(), AppError> {
+        let listings = sqlx::query_as::<_, (Uuid, String)>(
+            r#"
+            SELECT id, description FROM marketplace_listings
+            WHERE description IS NOT NULL AND description_html IS NULL
+            LIMIT $1
+            "#
+        )
+        .bind(BACKFILL_BATCH_SIZE)
+        .fetch_all(pool)
+        .await?;
+
+        for (id, description) in listings {
+            // Blank descriptions render to an empty string so they are not picked up again
+            let html = description_html(Some(&description)).unwrap_or_default();
+            sqlx::query("UPDATE marketplace_listings SET description_html = $2 WHERE id = $1 AND description = $3")
+                .bind(id)
+                .bind(html)
+                .bind(&description)
+                .execute(pool)
+                .await?;
+        }
+        Ok(())
+    }
+}
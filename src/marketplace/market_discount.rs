@@ -0,0 +1,107 @@
+use crate::error::AppError;
+use crate::marketplace::jobs::Job;
+use async_trait::async_trait;
+use sqlx::PgPool;
+
+// Days of asking and sold prices that make up what is typical
+const COMPARISON_WINDOW_DAYS: i32 = 90;
+
+// Prices a brand or category needs before it is compared against
+const MIN_COMPARABLE_PRICES: i64 = 5;
+
+/// Scores each active listing by how much cheaper it is than typical, so buyers can
+/// compare deals across sellers. Prices are compared relative to face value, since a
+/// brand's cards come in many denominations: the typical price is the median price-to-value
+/// ratio of the brand's listings in the category over the window, counting earlier prices
+/// from the price history, or of the whole category when the brand has too few.
+pub struct MarketDiscountService {
+    pool: PgPool,
+}
+
+impl MarketDiscountService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Recompute every active listing's score, clearing it where there is nothing to compare
+    /// against or the listing is no longer for sale. Returns the listings whose score changed.
+    pub async fn recompute(&self) -> Result<u64, AppError> {
+        let changed = sqlx::query(
+            r#"
+            WITH prices AS (
+                SELECT l.category, LOWER(l.brand_name) as brand, (l.selling_price / l.original_value)::float8 as ratio
+                FROM marketplace_listings l
+                WHERE l.original_value > 0 AND l.deleted_at IS NULL
+                AND (l.status = 'active' OR (l.status = 'sold' AND l.updated_at > CURRENT_TIMESTAMP - make_interval(days => $1)))
+                UNION ALL
+                SELECT l.category, LOWER(l.brand_name), (ph.old_price / l.original_value)::float8
+                FROM marketplace_price_history ph
+                JOIN marketplace_listings l ON l.id = ph.listing_id
+                WHERE l.original_value > 0 AND ph.changed_at > CURRENT_TIMESTAMP - make_interval(days => $1)
+            ),
+            brands AS (
+                SELECT category, brand, PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY ratio) as typical
+                FROM prices
+                WHERE brand IS NOT NULL
+                GROUP BY category, brand
+                HAVING COUNT(*) >= $2
+            ),
+            categories AS (
+                SELECT category, PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY ratio) as typical
+                FROM prices
+                GROUP BY category
+                HAVING COUNT(*) >= $2
+            ),
+            scores AS (
+                SELECT l.id,
+                       CASE WHEN b.typical > 0 THEN 'brand' ELSE 'category' END as basis,
+                       GREATEST(
+                           ROUND(((1 - (l.selling_price / l.original_value)::float8
+                               / CASE WHEN b.typical > 0 THEN b.typical ELSE c.typical END) * 100)::numeric, 2),
+                           -999.99
+                       ) as score
+                FROM marketplace_listings l
+                LEFT JOIN brands b ON b.category = l.category AND b.brand = LOWER(l.brand_name)
+                LEFT JOIN categories c ON c.category = l.category
+                WHERE l.status = 'active' AND l.deleted_at IS NULL AND l.original_value > 0
+                AND (b.typical > 0 OR c.typical > 0)
+            ),
+            -- Listings scored now, and those scored before that no longer are
+            targets AS (
+                SELECT l.id, s.score, s.basis
+                FROM marketplace_listings l
+                LEFT JOIN scores s ON s.id = l.id
+                WHERE s.id IS NOT NULL OR l.market_discount_score IS NOT NULL
+            )
+            UPDATE marketplace_listings l
+            SET market_discount_score = t.score, market_discount_basis = t.basis
+            FROM targets t
+            WHERE l.id = t.id
+            AND (l.market_discount_score IS DISTINCT FROM t.score OR l.market_discount_basis IS DISTINCT FROM t.basis)
+            "#
+        )
+        .bind(COMPARISON_WINDOW_DAYS)
+        .bind(MIN_COMPARABLE_PRICES)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        tracing::info!(changed, "recomputed market discount scores");
+        Ok(changed)
+    }
+}
+
+/// Recomputes market discount scores from the day's prices
+pub struct MarketDiscountJob;
+
+#[async_trait]
+impl Job for MarketDiscountJob {
+    fn name(&self) -> &'static str {
+        "market_discount"
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<(), AppError> {
+        MarketDiscountService::new(pool.clone()).recompute().await?;
+        Ok(())
+    }
+}
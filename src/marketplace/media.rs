@@ -0,0 +1,99 @@
+use crate::error::AppError;
+use crate::validation::FieldError;
+use sha2::{Digest, Sha256};
+use sqlx::{PgPool, Postgres, Row, Transaction};
+use uuid::Uuid;
+
+// Types accepted for uploads, each recognised by the file's leading bytes
+const ALLOWED_TYPES: &[(&str, &[u8])] = &[
+    ("image/png", b"\x89PNG\r\n\x1a\n"),
+    ("image/jpeg", b"\xff\xd8\xff"),
+    ("image/webp", b"RIFF"),
+    ("application/pdf", b"%PDF-"),
+];
+
+/// A stored file's contents
+pub struct MediaContent {
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+/// Uploaded files. Callers own access control: a file is only served through the
+/// record that references it.
+pub struct MediaService {
+    pool: PgPool,
+}
+
+impl MediaService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Store an upload after checking its size and that its bytes match `content_type`
+    pub async fn store(
+        tx: &mut Transaction<'_, Postgres>,
+        owner_id: &str,
+        content_type: &str,
+        data: &[u8],
+        max_bytes: usize,
+    ) -> Result<Uuid, AppError> {
+        let content_type = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+        check_upload(&content_type, data, max_bytes)?;
+
+        let id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_media (id, owner_id, content_type, size_bytes, sha256, data, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, CURRENT_TIMESTAMP)
+            "#
+        )
+        .bind(id)
+        .bind(owner_id)
+        .bind(&content_type)
+        .bind(data.len() as i64)
+        .bind(format!("{:x}", Sha256::digest(data)))
+        .bind(data)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(id)
+    }
+
+    pub async fn fetch(&self, media_id: Uuid) -> Result<MediaContent, AppError> {
+        let row = sqlx::query("SELECT content_type, data FROM marketplace_media WHERE id = $1")
+            .bind(media_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("File not found".to_string()))?;
+
+        Ok(MediaContent {
+            content_type: row.get("content_type"),
+            data: row.get("data"),
+        })
+    }
+}
+
+fn check_upload(content_type: &str, data: &[u8], max_bytes: usize) -> Result<(), AppError> {
+    let invalid = |message: String| AppError::ValidationFailed(vec![FieldError {
+        field: "file".to_string(),
+        message,
+    }]);
+
+    if data.is_empty() {
+        return Err(invalid("must not be empty".to_string()));
+    }
+    if data.len() > max_bytes {
+        return Err(invalid(format!("must be at most {} KB", max_bytes / 1024)));
+    }
+
+    let Some((_, signature)) = ALLOWED_TYPES.iter().find(|(allowed, _)| *allowed == content_type) else {
+        let allowed: Vec<&str> = ALLOWED_TYPES.iter().map(|(allowed, _)| *allowed).collect();
+        return Err(invalid(format!("must be one of {}", allowed.join(", "))));
+    };
+    let matches = data.starts_with(signature)
+        && (content_type != "image/webp" || data.get(8..12) == Some(b"WEBP".as_slice()));
+    if !matches {
+        return Err(invalid(format!("content is not {}", content_type)));
+    }
+    Ok(())
+}
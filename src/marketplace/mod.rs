@@ -2,36 +2,279 @@ pub mod routes;
 pub mod duplicate_detector;
 pub mod rate_limiter;
 pub mod cache;
+pub mod badges;
+pub mod verification;
+pub mod categories;
+pub mod brands;
+pub mod feed;
+pub mod follows;
+pub mod bulk;
+pub mod retention;
+pub mod tasks;
+pub mod seed;
+pub mod price_history;
+pub mod openapi;
+pub mod grpc;
+pub mod outbox;
+pub mod event_schemas;
+pub mod keyring;
+pub mod repository;
+pub mod transaction_state;
+pub mod jobs;
+pub mod analytics;
+pub mod export;
+pub mod deletion;
+pub mod anomaly;
+pub mod moderation;
+pub mod admin_bulk;
+pub mod commission;
+pub mod coupon_reveal;
+pub mod protection;
+pub mod payments;
+pub mod refunds;
+pub mod cart;
+pub mod bundles;
+pub mod pricing;
+pub mod tags;
+pub mod facets;
+pub mod search;
+pub mod etag;
+pub mod http_cache;
+pub mod replica;
+pub mod database;
+pub mod archive;
+pub mod trending;
+pub mod market_discount;
+pub mod expired_reports;
+pub mod media;
+pub mod dispute_evidence;
+pub mod impersonation;
+pub mod feature_flags;
+pub mod partner_keys;
+pub mod seo;
+pub mod trust_scores;
+pub mod notification_queue;
+pub mod quotas;
+pub mod shadow_bans;
+pub mod high_value;
+pub mod versioning;
+pub mod fields;
+pub mod storefront;
+pub mod promotions;
+pub mod promo_codes;
+pub mod loyalty;
+pub mod wallet;
+pub mod upi;
+pub mod paypal;
+pub mod payment_methods;
+pub mod reversals;
+pub mod payout_accounts;
+pub mod notifications;
+pub mod listing_events;
+pub mod revisions;
+pub mod translations;
+pub mod money;
+pub mod preferences;
+pub mod policy;
+pub mod regions;
+pub mod step_up;
+pub mod sessions;
+pub mod authorization;
+pub mod away;
+pub mod purchases;
+pub mod faq;
+pub mod holds;
+pub mod side_effects;
+pub mod timeline;
+pub mod markdown;
+pub mod scrubbing;
 
 use crate::auth::AuthUser;
+use crate::config::Config;
 use crate::error::AppError;
 use crate::models::marketplace::*;
-use crate::services::encryption::EncryptionService;
+use bigdecimal::BigDecimal;
 use chrono::Utc;
-use sqlx::{PgPool, Row};
+use sqlx::postgres::PgRow;
+use sqlx::{FromRow, PgPool, Postgres, QueryBuilder, Row, Transaction};
 use uuid::Uuid;
 use self::duplicate_detector::DuplicateDetector;
 use self::rate_limiter::{RateLimiter, ActionType};
 use self::cache::{MarketplaceCache, cache_ttl};
+use self::badges::BadgeService;
+use self::categories::{CategoryService, push_category_subtree};
+use self::brands::BrandService;
+use self::follows::FollowService;
+use self::price_history::PriceHistoryService;
+use self::outbox::{event_types, OutboxService};
+use self::quotas::SellerQuotaService;
+use self::high_value::HighValueService;
+use self::keyring::CouponKeyring;
+use self::repository::{compute_trust_score, Repositories};
+use self::transaction_state::{TransactionEvent, TransactionStateMachine};
+use self::moderation::ModerationService;
+use self::scrubbing::ContactScrubber;
+use self::policy::{ListingPolicyService, PolicySubject};
+use self::revisions::ListingRevisionService;
+use self::commission::CommissionService;
+use self::tags::TagService;
+use self::fields::ListingFields;
+use self::loyalty::LoyaltyService;
+use self::authorization::AuthorizationPolicy;
+use self::holds::CheckoutHolds;
+use self::side_effects::SideEffect;
+
+// Columns selected for a listing joined with its seller's public info
+const LISTING_WITH_SELLER_COLUMNS: &str = r#"
+    l.*,
+    u.username as seller_username,
+    COALESCE(ts.trust_score, 50.0) as seller_trust_score,
+    COALESCE(
+        (SELECT array_agg(b.badge ORDER BY b.badge) FROM marketplace_seller_badges b WHERE b.user_id = l.seller_id),
+        '{}'
+    ) as seller_badges,
+    (
+        SELECT ((MAX(ph.old_price) - l.selling_price) / MAX(ph.old_price) * 100)::float8
+        FROM marketplace_price_history ph
+        WHERE ph.listing_id = l.id AND ph.changed_at > CURRENT_TIMESTAMP - INTERVAL '30 days'
+        HAVING MAX(ph.old_price) > l.selling_price
+    ) as price_drop_percentage,
+    EXISTS (
+        SELECT 1 FROM marketplace_promotions p
+        WHERE p.listing_id = l.id AND p.status IN ('scheduled', 'active')
+        AND p.starts_at <= CURRENT_TIMESTAMP AND p.ends_at > CURRENT_TIMESTAMP
+    ) as featured,
+    COALESCE(
+        (SELECT s.timezone FROM marketplace_notification_settings s WHERE s.user_id = l.seller_id),
+        'UTC'
+    ) as seller_timezone
+"#;
+
+// Same as the `featured` column above; featured listings rank first in the default sort
+const FEATURED_CONDITION: &str = r#"EXISTS (
+    SELECT 1 FROM marketplace_promotions p
+    WHERE p.listing_id = l.id AND p.status IN ('scheduled', 'active')
+    AND p.starts_at <= CURRENT_TIMESTAMP AND p.ends_at > CURRENT_TIMESTAMP
+)"#;
+
+const LISTING_WITH_SELLER_FROM: &str = r#"
+    FROM marketplace_listings l
+    LEFT JOIN users u ON l.seller_id = u.auth0_id
+    LEFT JOIN marketplace_trust_scores ts ON l.seller_id = ts.user_id
+"#;
+
+// Upper bound on units in a single listing
+const MAX_LISTING_QUANTITY: i32 = 1000;
 
 pub struct MarketplaceService {
     pool: PgPool,
+    repos: Repositories,
 }
 
 impl MarketplaceService {
     pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+        let repos = Repositories::postgres(pool.clone());
+        Self { pool, repos }
+    }
+
+    /// Use the given repositories for the rules that go through them,
+    /// e.g. an in-memory store when testing without a database
+    pub fn with_repositories(pool: PgPool, repos: Repositories) -> Self {
+        Self { pool, repos }
+    }
+
+    /// Repositories backed by `store` alone, for tests. The pool never connects, so a rule
+    /// that reaches past the repositories fails instead of touching a database.
+    #[cfg(test)]
+    pub(crate) fn in_memory(store: std::sync::Arc<repository::InMemoryRepository>) -> Self {
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .connect_lazy("postgres://in-memory.invalid/marketplace")
+            .expect("static database URL parses");
+        Self::with_repositories(pool, Repositories::in_memory(store))
     }
 
     // Listing Management
     pub async fn create_listing(
         &self,
         auth_user: &AuthUser,
-        request: CreateListingRequest,
+        mut request: CreateListingRequest,
     ) -> Result<MarketplaceListing, AppError> {
+        // Validate discount code listings have coupon codes
+        if request.listing_type == ListingType::DiscountCode && request.coupon_code.is_none() {
+            return Err(AppError::BadRequest(
+                "Discount code listings must include a coupon code".to_string()
+            ));
+        }
+
+        // Validate inventory; discount codes need one distinct code per unit
+        let quantity = request.quantity.unwrap_or(1);
+        if !(1..=MAX_LISTING_QUANTITY).contains(&quantity) {
+            return Err(AppError::BadRequest(format!(
+                "Quantity must be between 1 and {}",
+                MAX_LISTING_QUANTITY
+            )));
+        }
+
+        let coupon_codes: Vec<String> = request
+            .coupon_code
+            .iter()
+            .chain(request.coupon_codes.iter())
+            .cloned()
+            .collect();
+
+        if request.listing_type == ListingType::DiscountCode {
+            if coupon_codes.len() != quantity as usize {
+                return Err(AppError::BadRequest(
+                    "Provide exactly one coupon code per unit of quantity".to_string()
+                ));
+            }
+
+            let distinct: std::collections::HashSet<&String> = coupon_codes.iter().collect();
+            if distinct.len() != coupon_codes.len() {
+                return Err(AppError::BadRequest("Coupon codes must be distinct".to_string()));
+            }
+        }
+
         let listing_id = Uuid::new_v4();
         let now = Utc::now();
 
+        // Categories must come from the managed taxonomy
+        let category = CategoryService::new(self.pool.clone())
+            .validate_category(&request.category)
+            .await?;
+
+        // Validate structured details for the listing type
+        let details = match request.details.clone() {
+            Some(value) => Some(
+                ListingDetails::parse(&request.listing_type, value).map_err(AppError::UnprocessableEntity)?
+            ),
+            None if ListingDetails::is_required(&request.listing_type) => {
+                return Err(AppError::UnprocessableEntity(
+                    "Details are required for this listing type".to_string()
+                ));
+            }
+            None => None,
+        };
+        let details = details
+            .map(serde_json::to_value)
+            .transpose()
+            .map_err(|e| AppError::InternalError(format!("Serialization error: {}", e)))?;
+
+        // Contact details would take the deal off-platform: rejected in titles, masked in descriptions
+        let scrubber = ContactScrubber::new(self.pool.clone());
+        scrubber.reject(&auth_user.0.auth0_id, "title", &request.title).await?;
+        request.description = scrubber
+            .mask_optional(&auth_user.0.auth0_id, "description", request.description.as_deref())
+            .await?;
+
+        // Map free-text brand names onto the brand registry
+        let brand = match &request.brand_name {
+            Some(name) => BrandService::new(self.pool.clone()).resolve(name).await?,
+            None => None,
+        };
+        let brand_id = brand.as_ref().map(|b| b.id);
+        let brand_name = brand.map(|b| b.name).or_else(|| request.brand_name.clone());
+
         // Calculate discount percentage if original value is provided
         let discount_percentage = request.original_value.as_ref().map(|original| {
             let hundred = bigdecimal::BigDecimal::from(100);
@@ -40,12 +283,41 @@ impl MarketplaceService {
             percentage
         });
 
+        // Listings are sold in the seller's region
+        let region = preferences::load_preferences(&self.pool, &auth_user.0.auth0_id).await?.region;
+
+        // Prohibited items are rejected outright
+        ListingPolicyService::new(self.pool.clone())
+            .check(&PolicySubject {
+                category: &category,
+                brand_name: brand_name.as_deref(),
+                title: &request.title,
+                description: request.description.as_deref(),
+                tags: &request.tags,
+                region: &region,
+            })
+            .await?;
+
+        // Listings with moderation findings are held for review instead of going live
+        let moderation_flags = ModerationService::new(self.pool.clone())
+            .screen_listing(&request.title, request.description.as_deref(), request.proof_image_url.as_deref())
+            .await?;
+        let status = if moderation_flags.is_empty() {
+            ListingStatus::Active
+        } else {
+            ListingStatus::PendingReview
+        };
+
+        let mut tx = self.pool.begin().await?;
+        SellerQuotaService::ensure_capacity(&mut tx, &auth_user.0.auth0_id, 1).await?;
+
         let query = r#"
             INSERT INTO marketplace_listings (
                 id, seller_id, listing_type, title, description, category,
                 brand_name, original_value, selling_price, discount_percentage,
-                expiration_date, proof_image_url, tags, created_at, updated_at
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+                expiration_date, proof_image_url, tags, created_at, updated_at, brand_id,
+                details, quantity, remaining_quantity, status, description_html, language, region
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $18, $19, $20, $21, $22)
             RETURNING *
         "#;
 
@@ -55,238 +327,227 @@ impl MarketplaceService {
             .bind(&request.listing_type)
             .bind(&request.title)
             .bind(&request.description)
-            .bind(&request.category)
-            .bind(&request.brand_name)
+            .bind(&category)
+            .bind(&brand_name)
             .bind(request.original_value)
             .bind(request.selling_price)
             .bind(discount_percentage)
             .bind(request.expiration_date)
             .bind(&request.proof_image_url)
-            .bind(&request.tags)
+            .bind(TagService::normalize(&request.tags))
             .bind(now)
             .bind(now)
-            .fetch_one(&self.pool)
+            .bind(brand_id)
+            .bind(&details)
+            .bind(quantity)
+            .bind(status)
+            .bind(markdown::description_html(request.description.as_deref()))
+            .bind(request.language.as_deref().unwrap_or(translations::DEFAULT_LOCALE))
+            .bind(&region)
+            .fetch_one(&mut *tx)
             .await?;
 
-        // Store coupon code securely if it's a discount code listing
+        if !moderation_flags.is_empty() {
+            ModerationService::enqueue(&mut tx, listing_id, &auth_user.0.auth0_id, &moderation_flags).await?;
+        }
+
+        // Store coupon codes securely if it's a discount code listing
         if request.listing_type == ListingType::DiscountCode {
-            if let Some(coupon_code) = request.coupon_code {
-                // Get encryption key from environment or generate one
-                let encryption_key = std::env::var("ENCRYPTION_KEY")
-                    .unwrap_or_else(|_| EncryptionService::generate_key());
-                let encryption_service = EncryptionService::new(&encryption_key)?;
-                
-                // Encrypt the coupon code
-                let (encrypted_code, nonce) = encryption_service.encrypt_string(&coupon_code)?;
-                
-                // Store encrypted code with nonce
-                let combined = format!("{}:{}", encrypted_code, nonce);
-                
+            let keyring = CouponKeyring::from_config(Config::get())?;
+
+            for coupon_code in coupon_codes {
+                // Encrypt with the current key version
+                let combined = keyring.encrypt(&coupon_code)?;
+
                 sqlx::query(
                     "INSERT INTO marketplace_coupon_codes (listing_id, encrypted_code) VALUES ($1, $2)"
                 )
                 .bind(listing_id)
                 .bind(&combined)
-                .execute(&self.pool)
+                .execute(&mut *tx)
                 .await?;
             }
         }
 
+        OutboxService::record(&mut tx, "listing", listing_id, event_types::LISTING_CREATED, &listing).await?;
+        tx.commit().await?;
+        replica::listing_written(listing_id);
+        replica::user_written(&auth_user.0.auth0_id);
+
         // Create trust score entry for new sellers
         self.ensure_trust_score(&auth_user.0.auth0_id).await?;
 
+        // Let followers know about the new listing; held listings notify them once approved.
+        // The listing is already created, so a failed notification must not fail it.
+        if listing.status == ListingStatus::Active {
+            if let Err(e) = FollowService::new(self.pool.clone()).notify_followers(&listing).await {
+                tracing::warn!(error = %e, listing_id = %listing.id, "follower notification failed");
+            }
+        } else {
+            self.create_notification(
+                &auth_user.0.auth0_id,
+                "listing_under_review",
+                "Listing under review",
+                &format!("\"{}\" will be published once a moderator has reviewed it", listing.title),
+                Some(listing.id),
+                None,
+            ).await;
+        }
+
         Ok(listing)
     }
 
-    pub async fn get_listing(&self, listing_id: Uuid) -> Result<ListingWithSeller, AppError> {
-        // Increment view count
-        sqlx::query("UPDATE marketplace_listings SET view_count = view_count + 1 WHERE id = $1")
-            .bind(listing_id)
-            .execute(&self.pool)
-            .await?;
-
-        let query = r#"
-            SELECT 
-                l.*,
-                u.username as seller_username,
-                COALESCE(ts.trust_score, 50.0) as seller_trust_score,
-                u.email as seller_profile_image
-            FROM marketplace_listings l
-            LEFT JOIN users u ON l.seller_id = u.auth0_id
-            LEFT JOIN marketplace_trust_scores ts ON l.seller_id = ts.user_id
-            WHERE l.id = $1
-        "#;
+    /// A soft-deleted listing is still returned to its seller and to admins
+    pub async fn get_listing(
+        &self,
+        listing_id: Uuid,
+        viewer: Option<&AuthUser>,
+    ) -> Result<ListingWithSeller, AppError> {
+        let row = self.fetch_listing_row(listing_id, LISTING_WITH_SELLER_COLUMNS, viewer).await?;
+        Ok(listing_with_seller_from_row(&row)?)
+    }
 
-        let row = sqlx::query(query)
-            .bind(listing_id)
-            .fetch_optional(&self.pool)
-            .await?
-            .ok_or_else(|| AppError::NotFound("Listing not found".to_string()))?;
+    /// `get_listing` with only the requested fields selected and returned
+    pub async fn get_listing_sparse(
+        &self,
+        listing_id: Uuid,
+        fields: &ListingFields,
+        viewer: Option<&AuthUser>,
+    ) -> Result<serde_json::Value, AppError> {
+        let row = self.fetch_listing_row(listing_id, &fields.columns(), viewer).await?;
+        Ok(fields.project(&row)?)
+    }
 
-        let listing = MarketplaceListing {
-            id: row.get("id"),
-            seller_id: row.get("seller_id"),
-            listing_type: row.get("listing_type"),
-            title: row.get("title"),
-            description: row.get("description"),
-            category: row.get("category"),
-            brand_name: row.get("brand_name"),
-            original_value: row.get("original_value"),
-            selling_price: row.get("selling_price"),
-            discount_percentage: row.get("discount_percentage"),
-            expiration_date: row.get("expiration_date"),
-            proof_image_url: row.get("proof_image_url"),
-            status: row.get("status"),
-            created_at: row.get("created_at"),
-            updated_at: row.get("updated_at"),
-            view_count: row.get("view_count"),
-            tags: row.get("tags"),
-            is_verified: row.get("is_verified"),
-            verification_date: row.get("verification_date"),
+    async fn fetch_listing_row(
+        &self,
+        listing_id: Uuid,
+        columns: &str,
+        viewer: Option<&AuthUser>,
+    ) -> Result<PgRow, AppError> {
+        trending::record_view(&self.pool, listing_id).await?;
+
+        let (viewer_id, viewer_is_admin) = match viewer {
+            Some(user) => (Some(user.0.auth0_id.as_str()), self.is_admin(&user.0.auth0_id).await?),
+            None => (None, false),
         };
 
-        Ok(ListingWithSeller {
-            listing,
-            seller_username: row.get("seller_username"),
-            seller_trust_score: row.get("seller_trust_score"),
-            seller_profile_image: row.get("seller_profile_image"),
-        })
+        let query = format!(
+            "SELECT {} {} WHERE l.id = $1 AND (l.deleted_at IS NULL OR l.seller_id = $2 OR $3)",
+            columns, LISTING_WITH_SELLER_FROM
+        );
+
+        let pool = replica::listing_read_pool(&self.pool, listing_id);
+        let query = sqlx::query(&query).bind(listing_id).bind(viewer_id).bind(viewer_is_admin);
+        database::timed("listings.get", query.fetch_optional(&pool))
+            .await?
+            .ok_or_else(|| AppError::NotFound("Listing not found".to_string()))
     }
 
     pub async fn get_listings(
         &self,
         filters: ListingFilters,
-    ) -> Result<Vec<ListingWithSeller>, AppError> {
-        let mut query = r#"
-            SELECT 
-                l.*,
-                u.username as seller_username,
-                COALESCE(ts.trust_score, 50.0) as seller_trust_score,
-                u.email as seller_profile_image
-            FROM marketplace_listings l
-            LEFT JOIN users u ON l.seller_id = u.auth0_id
-            LEFT JOIN marketplace_trust_scores ts ON l.seller_id = ts.user_id
-            WHERE 1=1
-        "#.to_string();
-
-        let mut bindings = vec![];
-        let mut bind_count = 1;
+    ) -> Result<PaginatedResponse<ListingWithSeller>, AppError> {
+        self.query_listings(filters, LISTING_WITH_SELLER_COLUMNS, listing_with_seller_from_row).await
+    }
 
-        // Apply filters
-        if let Some(category) = &filters.category {
-            query.push_str(&format!(" AND l.category = ${}", bind_count));
-            bindings.push(category.clone());
-            bind_count += 1;
-        }
+    /// `get_listings` with only the requested fields selected and returned
+    pub async fn get_listings_sparse(
+        &self,
+        filters: ListingFilters,
+        fields: &ListingFields,
+    ) -> Result<PaginatedResponse<serde_json::Value>, AppError> {
+        self.query_listings(filters, &fields.columns(), |row| fields.project(row)).await
+    }
 
-        if let Some(listing_type) = &filters.listing_type {
-            query.push_str(&format!(" AND l.listing_type = ${}", bind_count));
-            bindings.push(listing_type.clone());
-            bind_count += 1;
+    /// Listings still for sale among `ids`, in the order given
+    pub(crate) async fn get_active_listings_by_ids(&self, ids: &[Uuid]) -> Result<Vec<ListingWithSeller>, AppError> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
         }
 
-        if let Some(min_price) = filters.min_price {
-            query.push_str(&format!(" AND l.selling_price >= ${}", bind_count));
-            bindings.push(min_price.to_string());
-            bind_count += 1;
-        }
+        let query = format!(
+            r#"
+            SELECT {} {}
+            WHERE l.id = ANY($1) AND l.status = 'active' AND l.deleted_at IS NULL AND {} AND {}
+            ORDER BY array_position($1, l.id)
+            "#,
+            LISTING_WITH_SELLER_COLUMNS, LISTING_WITH_SELLER_FROM, shadow_bans::NOT_SHADOW_BANNED, away::SELLER_NOT_AWAY
+        );
 
-        if let Some(max_price) = filters.max_price {
-            query.push_str(&format!(" AND l.selling_price <= ${}", bind_count));
-            bindings.push(max_price.to_string());
-            bind_count += 1;
-        }
+        let pool = replica::read_pool(&self.pool);
+        let rows = database::timed("listings.by_ids", sqlx::query(&query).bind(ids).fetch_all(&pool)).await?;
 
-        if let Some(seller_id) = &filters.seller_id {
-            query.push_str(&format!(" AND l.seller_id = ${}", bind_count));
-            bindings.push(seller_id.clone());
-            bind_count += 1;
-        }
+        Ok(rows.iter().map(listing_with_seller_from_row).collect::<Result<_, _>>()?)
+    }
 
-        if let Some(status) = &filters.status {
-            query.push_str(&format!(" AND l.status = ${}", bind_count));
-            bindings.push(status.clone());
-            bind_count += 1;
-        }
+    async fn query_listings<T>(
+        &self,
+        filters: ListingFilters,
+        columns: &str,
+        map_row: impl Fn(&PgRow) -> Result<T, sqlx::Error>,
+    ) -> Result<PaginatedResponse<T>, AppError> {
+        // A seller's own listings are read from the primary right after they change them
+        let pool = match &filters.seller_id {
+            Some(seller_id) => replica::user_read_pool(&self.pool, seller_id),
+            None => replica::read_pool(&self.pool),
+        };
 
-        if let Some(is_verified) = filters.is_verified {
-            query.push_str(&format!(" AND l.is_verified = ${}", bind_count));
-            bindings.push(is_verified.to_string());
-            bind_count += 1;
-        }
+        let mut query = QueryBuilder::<Postgres>::new(format!(
+            "SELECT {}, COUNT(*) OVER() as total_count {} WHERE 1=1",
+            columns, LISTING_WITH_SELLER_FROM
+        ));
 
-        if let Some(search_query) = &filters.search_query {
-            query.push_str(&format!(
-                " AND (l.title ILIKE ${} OR l.description ILIKE ${} OR l.brand_name ILIKE ${})",
-                bind_count,
-                bind_count + 1,
-                bind_count + 2
-            ));
-            let search_pattern = format!("%{}%", search_query);
-            bindings.push(search_pattern.clone());
-            bindings.push(search_pattern.clone());
-            bindings.push(search_pattern);
-            bind_count += 3;
-        }
+        push_listing_filters(&mut query, &filters);
 
-        // Apply sorting
+        // Apply sorting; only these fixed clauses ever reach the SQL
         match filters.sort_by.as_deref() {
-            Some("price_asc") => query.push_str(" ORDER BY l.selling_price ASC"),
-            Some("price_desc") => query.push_str(" ORDER BY l.selling_price DESC"),
-            Some("popularity") => query.push_str(" ORDER BY l.view_count DESC"),
-            _ => query.push_str(" ORDER BY l.created_at DESC"),
-        }
+            Some("price_asc") => query.push(" ORDER BY l.selling_price ASC"),
+            Some("price_desc") => query.push(" ORDER BY l.selling_price DESC"),
+            Some("popularity") => query.push(" ORDER BY l.view_count DESC"),
+            Some("trending") => query.push(
+                " ORDER BY COALESCE((SELECT t.score FROM marketplace_trending_listings t WHERE t.listing_id = l.id), 0) DESC, l.created_at DESC"
+            ),
+            Some("market_discount") => query.push(" ORDER BY l.market_discount_score DESC NULLS LAST, l.created_at DESC"),
+            _ => query.push(format!(" ORDER BY {} DESC, l.created_at DESC", FEATURED_CONDITION)),
+        };
 
         // Apply pagination
-        let limit = filters.limit.unwrap_or(20).min(100);
-        let offset = filters.page.unwrap_or(0) * limit;
-        query.push_str(&format!(" LIMIT {} OFFSET {}", limit, offset));
-
-        // Execute query with dynamic bindings
-        let mut sql_query = sqlx::query(&query);
-        for binding in bindings {
-            sql_query = sql_query.bind(binding);
-        }
-
-        let rows = sql_query
-            .fetch_all(&self.pool)
-            .await?;
-
-        let listings = rows
-            .into_iter()
-            .map(|row| {
-                let listing = MarketplaceListing {
-                    id: row.get("id"),
-                    seller_id: row.get("seller_id"),
-                    listing_type: row.get("listing_type"),
-                    title: row.get("title"),
-                    description: row.get("description"),
-                    category: row.get("category"),
-                    brand_name: row.get("brand_name"),
-                    original_value: row.get("original_value"),
-                    selling_price: row.get("selling_price"),
-                    discount_percentage: row.get("discount_percentage"),
-                    expiration_date: row.get("expiration_date"),
-                    proof_image_url: row.get("proof_image_url"),
-                    status: row.get("status"),
-                    created_at: row.get("created_at"),
-                    updated_at: row.get("updated_at"),
-                    view_count: row.get("view_count"),
-                    tags: row.get("tags"),
-                    is_verified: row.get("is_verified"),
-                    verification_date: row.get("verification_date"),
-                };
-
-                ListingWithSeller {
-                    listing,
-                    seller_username: row.get("seller_username"),
-                    seller_trust_score: row.get("seller_trust_score"),
-                    seller_profile_image: row.get("seller_profile_image"),
-                }
-            })
-            .collect();
+        let limit = filters.limit.unwrap_or(20).clamp(1, 100);
+        let page = filters.page.unwrap_or(0).max(0);
+        let offset = page
+            .checked_mul(limit)
+            .ok_or_else(|| AppError::BadRequest("page is out of range".to_string()))?;
+        query.push(" LIMIT ").push_bind(limit).push(" OFFSET ").push_bind(offset);
+
+        let rows = database::timed("listings.search", query.build().fetch_all(&pool)).await?;
+
+        // The window count is identical on every row of the page; a page past the end has no
+        // rows to carry it, so it is counted separately
+        let total_count: i64 = match rows.first() {
+            Some(row) => row.get("total_count"),
+            None if offset > 0 => {
+                let mut count = QueryBuilder::<Postgres>::new(format!(
+                    "SELECT COUNT(*) {} WHERE 1=1",
+                    LISTING_WITH_SELLER_FROM
+                ));
+                push_listing_filters(&mut count, &filters);
+                database::timed("listings.count", count.build_query_scalar::<i64>().fetch_one(&pool)).await?
+            }
+            None => 0,
+        };
 
-        Ok(listings)
+        let listings: Vec<T> = rows
+            .iter()
+            .map(&map_row)
+            .collect::<Result<_, _>>()?;
+
+        Ok(PaginatedResponse {
+            has_more: offset + (listings.len() as i64) < total_count,
+            items: listings,
+            total_count,
+            page,
+            limit,
+        })
     }
 
     pub async fn update_listing(
@@ -295,43 +556,164 @@ impl MarketplaceService {
         listing_id: Uuid,
         request: UpdateListingRequest,
     ) -> Result<MarketplaceListing, AppError> {
-        // Verify ownership
-        let existing = sqlx::query("SELECT seller_id FROM marketplace_listings WHERE id = $1")
-            .bind(listing_id)
-            .fetch_optional(&self.pool)
+        let existing = self.find_own_listing(&auth_user.0.auth0_id, listing_id).await?;
+
+        self.apply_listing_update(auth_user, existing, request, None).await
+    }
+
+    /// A listing that has not been deleted, if `user_id` may change it
+    pub(crate) async fn find_own_listing(&self, user_id: &str, listing_id: Uuid) -> Result<MarketplaceListing, AppError> {
+        let listing = self.repos.listings
+            .find_listing(listing_id)
             .await?
             .ok_or_else(|| AppError::NotFound("Listing not found".to_string()))?;
+        self.authorization().can_edit_listing(user_id, &listing.seller_id)?;
+        Ok(listing)
+    }
+
+    /// Apply an edit to `existing` and record it as a revision; `reverts` is the revision
+    /// a revert restores
+    pub(crate) async fn apply_listing_update(
+        &self,
+        auth_user: &AuthUser,
+        existing: MarketplaceListing,
+        mut request: UpdateListingRequest,
+        reverts: Option<i32>,
+    ) -> Result<MarketplaceListing, AppError> {
+        let listing_id = existing.id;
 
-        let seller_id: String = existing.get("seller_id");
-        if seller_id != auth_user.0.auth0_id {
-            return Err(AppError::NotFound("You can only update your own listings".to_string()));
+        let scrubber = ContactScrubber::new(self.pool.clone());
+        if let Some(title) = &request.title {
+            scrubber.reject(&auth_user.0.auth0_id, "title", title).await?;
         }
+        request.description = scrubber
+            .mask_optional(&auth_user.0.auth0_id, "description", request.description.as_deref())
+            .await?;
 
         // Build update query dynamically
-        let mut query = "UPDATE marketplace_listings SET updated_at = CURRENT_TIMESTAMP".to_string();
-        let mut bindings = vec![];
-        let mut bind_count = 1;
+        let mut query = QueryBuilder::<Postgres>::new("UPDATE marketplace_listings SET updated_at = CURRENT_TIMESTAMP");
 
         if let Some(title) = &request.title {
-            query.push_str(&format!(", title = ${}", bind_count));
-            bindings.push(title.clone());
-            bind_count += 1;
+            query.push(", title = ").push_bind(title.clone());
+        }
+        if let Some(description) = &request.description {
+            query.push(", description = ").push_bind(description.clone());
+            query.push(", description_html = ").push_bind(markdown::description_html(Some(description)));
         }
 
-        // Add other fields similarly...
+        // Edited text of listings on sale goes through moderation again and is held if flagged
+        let mut moderation_flags = Vec::new();
+        if (request.title.is_some() || request.description.is_some())
+            && matches!(existing.status, ListingStatus::Active | ListingStatus::PendingReview)
+        {
+            moderation_flags = ModerationService::new(self.pool.clone())
+                .screen_listing(
+                    request.title.as_deref().unwrap_or(&existing.title),
+                    request.description.as_deref(),
+                    None,
+                )
+                .await?;
+        }
+        if !moderation_flags.is_empty() {
+            query.push(", status = ").push_bind(ListingStatus::PendingReview);
+        }
+
+        let category = match &request.category {
+            Some(category) => Some(CategoryService::new(self.pool.clone()).validate_category(category).await?),
+            None => None,
+        };
+        if let Some(category) = &category {
+            query.push(", category = ").push_bind(category.clone());
+        }
+
+        if let Some(tags) = &request.tags {
+            query.push(", tags = ").push_bind(TagService::normalize(tags));
+        }
+
+        // A new brand name is mapped onto the brand registry again, as on creation
+        let brand_name = match &request.brand_name {
+            Some(name) => {
+                let brand = BrandService::new(self.pool.clone()).resolve(name).await?;
+                let brand_id = brand.as_ref().map(|b| b.id);
+                let brand_name = brand.map(|b| b.name).unwrap_or_else(|| name.trim().to_string());
+                query.push(", brand_id = ").push_bind(brand_id);
+                query.push(", brand_name = ").push_bind(brand_name.clone());
+                Some(brand_name)
+            }
+            None => None,
+        };
+
+        // The listing as edited must still be allowed
+        ListingPolicyService::new(self.pool.clone())
+            .check(&PolicySubject {
+                category: category.as_deref().unwrap_or(&existing.category),
+                brand_name: brand_name.as_deref().or(existing.brand_name.as_deref()),
+                title: request.title.as_deref().unwrap_or(&existing.title),
+                description: request.description.as_deref().or(existing.description.as_deref()),
+                tags: request.tags.as_deref().unwrap_or(&existing.tags),
+                region: &existing.region,
+            })
+            .await?;
 
-        query.push_str(&format!(" WHERE id = ${} RETURNING *", bind_count));
+        if let Some(details) = &request.details {
+            let listing_type = existing.listing_type;
+            let details = ListingDetails::parse(&listing_type, details.clone())
+                .map_err(AppError::UnprocessableEntity)?;
+            let details = serde_json::to_value(&details)
+                .map_err(|e| AppError::InternalError(format!("Serialization error: {}", e)))?;
+            query.push(", details = ").push_bind(details);
+        }
 
-        let mut sql_query = sqlx::query_as::<_, MarketplaceListing>(&query);
-        for binding in bindings {
-            sql_query = sql_query.bind(binding);
+        // Price changes also refresh the discount and are recorded in the price history
+        let old_price = existing.selling_price.clone();
+        let new_price = match &request.selling_price {
+            Some(price) if *price > BigDecimal::from(0) => Some(price.round(2)),
+            Some(_) => return Err(AppError::BadRequest("Selling price must be positive".to_string())),
+            None => None,
         }
-        sql_query = sql_query.bind(listing_id);
+        .filter(|price| *price != old_price);
+
+        if let Some(price) = &new_price {
+            query
+                .push(", selling_price = ")
+                .push_bind(price.clone())
+                .push(", discount_percentage = CASE WHEN original_value > 0 THEN (original_value - ")
+                .push_bind(price.clone())
+                .push(") / original_value * 100 END");
+        }
+
+        // Add other fields similarly...
+
+        query.push(" WHERE id = ").push_bind(listing_id).push(" RETURNING *");
 
-        let listing = sql_query
-            .fetch_one(&self.pool)
+        let mut tx = self.pool.begin().await?;
+
+        let listing = query
+            .build_query_as::<MarketplaceListing>()
+            .fetch_one(&mut *tx)
             .await?;
 
+        if let Some(price) = &new_price {
+            PriceHistoryService::record_change(&mut tx, listing_id, &old_price, price, &auth_user.0.auth0_id).await?;
+        }
+
+        if !moderation_flags.is_empty() {
+            ModerationService::enqueue(&mut tx, listing_id, &existing.seller_id, &moderation_flags).await?;
+        }
+
+        ListingRevisionService::record(&mut tx, &existing, &listing, &auth_user.0.auth0_id, reverts).await?;
+        OutboxService::record(&mut tx, "listing", listing_id, event_types::LISTING_UPDATED, &listing).await?;
+        tx.commit().await?;
+        replica::listing_written(listing_id);
+        side_effects::perform(&self.pool, SideEffect::InvalidateListing { listing_id }).await;
+
+        if let Some(price) = &new_price {
+            let title = existing.title.clone();
+            PriceHistoryService::new(self.pool.clone())
+                .notify_price_drop(listing_id, &title, &old_price, price)
+                .await?;
+        }
+
         Ok(listing)
     }
 
@@ -340,18 +722,44 @@ impl MarketplaceService {
         auth_user: &AuthUser,
         listing_id: Uuid,
     ) -> Result<(), AppError> {
+        let existing = self.find_own_listing(&auth_user.0.auth0_id, listing_id).await?;
+
+        let mut tx = self.pool.begin().await?;
+
+        // Soft delete so transaction and report history keep their references
         let result = sqlx::query(
-            "DELETE FROM marketplace_listings WHERE id = $1 AND seller_id = $2"
+            r#"
+            UPDATE marketplace_listings
+            SET deleted_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1 AND seller_id = $2 AND deleted_at IS NULL
+            "#
         )
         .bind(listing_id)
         .bind(&auth_user.0.auth0_id)
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await?;
 
         if result.rows_affected() == 0 {
-            return Err(AppError::NotFound("Listing not found or you don't have permission".to_string()));
+            // Deleted since it was loaded
+            return Err(AppError::NotFound("Listing not found".to_string()));
         }
 
+        OutboxService::record(
+            &mut tx,
+            "listing",
+            listing_id,
+            event_types::LISTING_DELETED,
+            &ListingDeletedEvent {
+                listing_id,
+                seller_id: auth_user.0.auth0_id.clone(),
+            },
+        )
+        .await?;
+        tx.commit().await?;
+        replica::listing_written(listing_id);
+        replica::user_written(&auth_user.0.auth0_id);
+        side_effects::perform(&self.pool, SideEffect::InvalidateListing { listing_id }).await;
+
         Ok(())
     }
 
@@ -360,65 +768,139 @@ impl MarketplaceService {
         &self,
         auth_user: &AuthUser,
         request: CreateTransactionRequest,
+    ) -> Result<MarketplaceTransaction, AppError> {
+        if wallet::is_wallet_payment(&request.payment_method) {
+            return Err(AppError::BadRequest("Pay by wallet through the cart checkout".to_string()));
+        }
+
+        let mut tx = self.pool.begin().await?;
+        let transaction = Self::open_transaction(
+            &mut tx,
+            &auth_user.0.auth0_id,
+            request.listing_id,
+            &request.payment_method,
+            None,
+            None,
+        ).await?;
+        tx.commit().await?;
+
+        // Create notification for seller
+        self.create_notification(
+            &transaction.seller_id,
+            "new_sale",
+            "New Sale!",
+            &format!("Your listing has been purchased"),
+            Some(request.listing_id),
+            Some(transaction.id),
+        ).await;
+
+        Ok(transaction)
+    }
+
+    /// Reserve one unit of `listing_id` for the buyer and create its pending transaction.
+    /// Runs in the caller's database transaction so a checkout can open several atomically.
+    /// `price` replaces the listing's selling price, e.g. for a unit sold as part of a bundle.
+    pub(crate) async fn open_transaction(
+        tx: &mut Transaction<'_, Postgres>,
+        buyer_id: &str,
+        listing_id: Uuid,
+        payment_method: &str,
+        checkout_id: Option<Uuid>,
+        price: Option<BigDecimal>,
     ) -> Result<MarketplaceTransaction, AppError> {
         // Get listing details
         let listing = sqlx::query(
-            "SELECT seller_id, selling_price, status FROM marketplace_listings WHERE id = $1"
+            "SELECT seller_id, selling_price, status, region, title, brand_name FROM marketplace_listings WHERE id = $1"
         )
-        .bind(request.listing_id)
-        .fetch_optional(&self.pool)
+        .bind(listing_id)
+        .fetch_optional(&mut **tx)
         .await?
         .ok_or_else(|| AppError::NotFound("Listing not found".to_string()))?;
 
         let seller_id: String = listing.get("seller_id");
-        let selling_price: f64 = listing.get("selling_price");
-        let status: String = listing.get("status");
+        let selling_price: BigDecimal = price.unwrap_or_else(|| listing.get("selling_price"));
+        let status: ListingStatus = listing.get("status");
+        let region: String = listing.get("region");
+        // Kept on the transaction so the buyer's history shows what they bought, whatever edits follow
+        let title: String = listing.get("title");
+        let brand_name: Option<String> = listing.get("brand_name");
 
         // Verify listing is active
-        if status != "active" {
-            return Err(AppError::NotFound("Listing is not available for purchase".to_string()));
+        if status != ListingStatus::Active {
+            return Err(AppError::Conflict("Listing is not available for purchase".to_string()));
         }
 
         // Prevent self-purchase
-        if seller_id == auth_user.0.auth0_id {
-            return Err(AppError::NotFound("You cannot purchase your own listing".to_string()));
+        if seller_id == buyer_id {
+            return Err(AppError::Forbidden("You cannot purchase your own listing".to_string()));
+        }
+        away::ensure_selling(&mut **tx, &seller_id).await?;
+
+        // Reserve one unit of stock; the listing sells out when the last unit goes, and is
+        // held for this checkout until it is paid or the hold runs out
+        let reserved = sqlx::query(
+            r#"
+            UPDATE marketplace_listings
+            SET remaining_quantity = remaining_quantity - 1,
+                status = CASE WHEN remaining_quantity - 1 = 0 THEN 'sold' ELSE status END,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1 AND status = 'active' AND remaining_quantity > 0 AND deleted_at IS NULL
+            RETURNING remaining_quantity
+            "#
+        )
+        .bind(listing_id)
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        let Some(reserved) = reserved else {
+            if let Some(until) = CheckoutHolds::from_config().held_until(listing_id).await {
+                return Err(AppError::Conflict(format!(
+                    "Listing is reserved by another buyer's checkout; it goes back on sale at {} unless they pay",
+                    until.format("%H:%M UTC")
+                )));
+            }
+            return Err(AppError::Conflict("Listing is out of stock".to_string()));
+        };
+        replica::listing_written(listing_id);
+        if reserved.get::<i32, _>("remaining_quantity") == 0 {
+            CheckoutHolds::from_config().hold(listing_id).await;
         }
 
+        // The seller's commission tier at purchase time and the listing's region set the
+        // platform fee; the region's tax on that fee is withheld alongside it
+        let (commission_tier, platform_fee) =
+            CommissionService::fee_for(tx, &seller_id, &selling_price, &region).await?;
+        let tax_amount = regions::tax_on_fee(&platform_fee, &region);
+
         // Create transaction
         let transaction_id = Uuid::new_v4();
         let query = r#"
             INSERT INTO marketplace_transactions (
                 id, listing_id, buyer_id, seller_id, amount, 
-                payment_method, status, created_at
-            ) VALUES ($1, $2, $3, $4, $5, $6, 'pending', CURRENT_TIMESTAMP)
+                payment_method, status, created_at, commission_tier, platform_fee, checkout_id, tax_amount,
+                listing_title, listing_brand_name
+            ) VALUES ($1, $2, $3, $4, $5, $6, 'pending', CURRENT_TIMESTAMP, $7, $8, $9, $10, $11, $12)
             RETURNING *
         "#;
 
         let transaction = sqlx::query_as::<_, MarketplaceTransaction>(query)
             .bind(transaction_id)
-            .bind(request.listing_id)
-            .bind(&auth_user.0.auth0_id)
+            .bind(listing_id)
+            .bind(buyer_id)
             .bind(&seller_id)
             .bind(selling_price)
-            .bind(&request.payment_method)
-            .fetch_one(&self.pool)
-            .await?;
-
-        // Update listing status
-        sqlx::query("UPDATE marketplace_listings SET status = 'sold' WHERE id = $1")
-            .bind(request.listing_id)
-            .execute(&self.pool)
+            .bind(payment_method)
+            .bind(commission_tier)
+            .bind(platform_fee)
+            .bind(checkout_id)
+            .bind(tax_amount)
+            .bind(title)
+            .bind(brand_name)
+            .fetch_one(&mut **tx)
             .await?;
 
-        // Create notification for seller
-        self.create_notification(
-            &seller_id,
-            "new_sale",
-            "New Sale!",
-            &format!("Your listing has been purchased"),
-            Some(request.listing_id),
-            Some(transaction_id),
-        ).await?;
+        OutboxService::record(tx, "transaction", transaction_id, event_types::TRANSACTION_CREATED, &transaction).await?;
+        HighValueService::open(tx, &transaction).await?;
 
         Ok(transaction)
     }
@@ -433,40 +915,30 @@ impl MarketplaceService {
 
         // Verify buyer
         if transaction.buyer_id != auth_user.0.auth0_id {
-            return Err(AppError::NotFound("Only the buyer can complete this transaction".to_string()));
+            return Err(AppError::Forbidden("Only the buyer can complete this transaction".to_string()));
         }
 
-        // Verify status
-        if transaction.status != "escrow" {
-            return Err(AppError::NotFound("Transaction is not in escrow status".to_string()));
+        // Only a transaction held in escrow can be released by the buyer
+        if transaction.status != TransactionStatus::Escrow {
+            return Err(AppError::Conflict("Transaction is not in escrow status".to_string()));
         }
 
-        // Update transaction
-        let query = r#"
-            UPDATE marketplace_transactions 
-            SET status = 'completed', completed_at = CURRENT_TIMESTAMP
-            WHERE id = $1
-            RETURNING *
-        "#;
+        let mut tx = self.pool.begin().await?;
 
-        let updated = sqlx::query_as::<_, MarketplaceTransaction>(query)
-            .bind(transaction_id)
-            .fetch_one(&self.pool)
-            .await?;
+        let updated = TransactionStateMachine::apply(
+            &mut tx,
+            &transaction,
+            TransactionEvent::EscrowReleased,
+            &auth_user.0.auth0_id,
+            None,
+        ).await?;
 
-        // Grant access to coupon code if applicable
-        sqlx::query(
-            r#"
-            INSERT INTO marketplace_coupon_access (listing_id, user_id, transaction_id)
-            VALUES ($1, $2, $3)
-            ON CONFLICT (listing_id, user_id) DO NOTHING
-            "#
-        )
-        .bind(transaction.listing_id)
-        .bind(&auth_user.0.auth0_id)
-        .bind(transaction_id)
-        .execute(&self.pool)
-        .await?;
+        // Codes are normally allocated at payment; this covers transactions paid before that
+        Self::allocate_coupon_code(&mut tx, &transaction).await?;
+        LoyaltyService::award(&mut tx, &updated).await?;
+
+        OutboxService::record(&mut tx, "transaction", transaction_id, event_types::TRANSACTION_COMPLETED, &updated).await?;
+        tx.commit().await?;
 
         // Update trust scores
         self.update_trust_score_after_transaction(&transaction.seller_id, true).await?;
@@ -479,71 +951,245 @@ impl MarketplaceService {
             "Your sale has been completed and funds will be released",
             Some(transaction.listing_id),
             Some(transaction_id),
-        ).await?;
+        ).await;
 
         Ok(updated)
     }
 
-    // Review Management
-    pub async fn create_review(
+    /// Mark the buyer's payment as received, moving the funds into escrow
+    pub async fn confirm_payment(
         &self,
         auth_user: &AuthUser,
-        request: CreateReviewRequest,
-    ) -> Result<MarketplaceReview, AppError> {
-        // Get transaction details
-        let transaction = self.get_transaction_by_id(request.transaction_id).await?;
+        transaction_id: Uuid,
+    ) -> Result<MarketplaceTransaction, AppError> {
+        self.require_admin(auth_user).await?;
+        let transaction = self.get_transaction_by_id(transaction_id).await?;
 
-        // Verify transaction is completed
-        if transaction.status != "completed" {
-            return Err(AppError::NotFound("Can only review completed transactions".to_string()));
-        }
+        let mut tx = self.pool.begin().await?;
+        let funded = cart::fund_transaction(&mut tx, &transaction, None, &auth_user.0.auth0_id).await?;
+        tx.commit().await?;
 
-        // Determine if this is a buyer or seller review
-        let (reviewed_user_id, is_buyer_review) = if transaction.buyer_id == auth_user.0.auth0_id {
-            (transaction.seller_id.clone(), true)
-        } else if transaction.seller_id == auth_user.0.auth0_id {
-            (transaction.buyer_id.clone(), false)
-        } else {
-            return Err(AppError::NotFound("You are not part of this transaction".to_string()));
+        let Some(updated) = funded else {
+            // A high-value transaction stays pending until its checks pass
+            return self.get_transaction_by_id(transaction_id).await;
         };
 
-        // Check if already reviewed
-        let existing = sqlx::query(
-            "SELECT id FROM marketplace_reviews WHERE transaction_id = $1 AND reviewer_id = $2"
-        )
-        .bind(request.transaction_id)
-        .bind(&auth_user.0.auth0_id)
-        .fetch_optional(&self.pool)
-        .await?;
+        self.create_notification(
+            &transaction.seller_id,
+            "payment_confirmed",
+            "Payment Received",
+            "The buyer's payment is held in escrow until they confirm receipt",
+            Some(transaction.listing_id),
+            Some(transaction_id),
+        ).await;
 
-        if existing.is_some() {
-            return Err(AppError::NotFound("You have already reviewed this transaction".to_string()));
-        }
+        self.create_notification(
+            &transaction.buyer_id,
+            "payment_confirmed",
+            "Payment Confirmed",
+            "Your payment is held in escrow and your code can now be revealed",
+            Some(transaction.listing_id),
+            Some(transaction_id),
+        ).await;
 
-        // Create review
-        let review_id = Uuid::new_v4();
-        let query = r#"
-            INSERT INTO marketplace_reviews (
-                id, transaction_id, reviewer_id, reviewed_user_id, 
-                rating, review_text, deal_verified, is_buyer_review, created_at
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, CURRENT_TIMESTAMP)
-            RETURNING *
-        "#;
+        Ok(updated)
+    }
 
-        let review = sqlx::query_as::<_, MarketplaceReview>(query)
-            .bind(review_id)
-            .bind(request.transaction_id)
-            .bind(&auth_user.0.auth0_id)
+    pub async fn cancel_transaction(
+        &self,
+        auth_user: &AuthUser,
+        transaction_id: Uuid,
+        reason: &str,
+    ) -> Result<MarketplaceTransaction, AppError> {
+        let transaction = self.get_transaction_by_id(transaction_id).await?;
+        let user_id = &auth_user.0.auth0_id;
+
+        // Either party may back out before payment; afterwards refunds go through an admin
+        let is_party = transaction.buyer_id == *user_id || transaction.seller_id == *user_id;
+        let is_admin = self.is_admin(user_id).await?;
+        if !is_party && !is_admin {
+            return Err(AppError::Forbidden("You are not part of this transaction".to_string()));
+        }
+        if transaction.status != TransactionStatus::Pending && !is_admin {
+            return Err(AppError::Forbidden("Only an admin can cancel a paid transaction".to_string()));
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        let updated = TransactionStateMachine::apply(
+            &mut tx,
+            &transaction,
+            TransactionEvent::Cancelled,
+            user_id,
+            Some(reason),
+        ).await?;
+
+        // Return the reserved unit to stock unless its code was already revealed
+        if Self::release_coupon_code(&mut tx, &transaction).await? {
+            Self::restock_listing(&mut tx, transaction.listing_id).await?;
+        }
+
+        OutboxService::record(&mut tx, "transaction", transaction_id, event_types::TRANSACTION_CANCELLED, &updated).await?;
+        tx.commit().await?;
+
+        let counterparty = if transaction.buyer_id == *user_id {
+            &transaction.seller_id
+        } else {
+            &transaction.buyer_id
+        };
+        self.create_notification(
+            counterparty,
+            "transaction_cancelled",
+            "Transaction Cancelled",
+            reason,
+            Some(transaction.listing_id),
+            Some(transaction_id),
+        ).await;
+
+        Ok(updated)
+    }
+
+    pub async fn dispute_transaction(
+        &self,
+        auth_user: &AuthUser,
+        transaction_id: Uuid,
+        reason: &str,
+    ) -> Result<MarketplaceTransaction, AppError> {
+        let transaction = self.get_transaction_by_id(transaction_id).await?;
+        let user_id = &auth_user.0.auth0_id;
+
+        if transaction.buyer_id != *user_id && transaction.seller_id != *user_id {
+            return Err(AppError::Forbidden("You are not part of this transaction".to_string()));
+        }
+
+        let mut tx = self.pool.begin().await?;
+        let updated = TransactionStateMachine::apply(
+            &mut tx,
+            &transaction,
+            TransactionEvent::Disputed,
+            user_id,
+            Some(reason),
+        ).await?;
+        OutboxService::record(&mut tx, "transaction", transaction_id, event_types::TRANSACTION_DISPUTED, &updated).await?;
+        tx.commit().await?;
+
+        let counterparty = if transaction.buyer_id == *user_id {
+            &transaction.seller_id
+        } else {
+            &transaction.buyer_id
+        };
+        self.create_notification(
+            counterparty,
+            "transaction_disputed",
+            "Transaction Disputed",
+            reason,
+            Some(transaction.listing_id),
+            Some(transaction_id),
+        ).await;
+
+        Ok(updated)
+    }
+
+    pub async fn get_transaction(
+        &self,
+        auth_user: &AuthUser,
+        transaction_id: Uuid,
+    ) -> Result<TransactionDetail, AppError> {
+        let transaction = self.get_transaction_by_id(transaction_id).await?;
+        let user_id = &auth_user.0.auth0_id;
+
+        self.authorization().can_view_transaction(&auth_user.0.auth0_id, &transaction).await?;
+        let is_party = transaction.buyer_id == *user_id || transaction.seller_id == *user_id;
+
+        // Listings of old transactions may have moved to the archive
+        let listing = sqlx::query_as::<_, MarketplaceListing>(
+            "SELECT * FROM marketplace_listings_all WHERE id = $1"
+        )
+        .bind(transaction.listing_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let details = sqlx::query(
+            r#"
+            SELECT
+                COALESCE((SELECT username FROM users WHERE auth0_id = $3), 'unknown') as buyer_username,
+                COALESCE((SELECT username FROM users WHERE auth0_id = $4), 'unknown') as seller_username,
+                COALESCE((SELECT timezone FROM marketplace_notification_settings WHERE user_id = $4), 'UTC') as seller_timezone,
+                EXISTS (
+                    SELECT 1 FROM marketplace_reviews
+                    WHERE transaction_id = $1 AND reviewer_id = $2
+                ) as has_reviewed
+            "#
+        )
+        .bind(transaction_id)
+        .bind(user_id)
+        .bind(&transaction.buyer_id)
+        .bind(&transaction.seller_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let has_reviewed: bool = details.get("has_reviewed");
+        let can_review = is_party && transaction.status == TransactionStatus::Completed && !has_reviewed;
+
+        Ok(TransactionDetail {
+            buyer_username: details.get("buyer_username"),
+            seller_username: details.get("seller_username"),
+            currency: money::currency().to_string(),
+            amount_minor: money::to_minor_units(&transaction.amount),
+            refunded_amount_minor: money::to_minor_units(&transaction.refunded_amount),
+            seller_timezone: details.get("seller_timezone"),
+            transaction,
+            listing,
+            can_review,
+            has_reviewed,
+        })
+    }
+
+    // Review Management
+    pub async fn create_review(
+        &self,
+        auth_user: &AuthUser,
+        request: CreateReviewRequest,
+    ) -> Result<MarketplaceReview, AppError> {
+        let transaction = self.get_transaction_by_id(request.transaction_id).await?;
+        let (reviewed_user_id, is_buyer_review) = self.review_target(&auth_user.0.auth0_id, &transaction).await?;
+
+        // Reviews are public, so contact details in them are masked like in descriptions
+        let review_text = ContactScrubber::new(self.pool.clone())
+            .mask_optional(&auth_user.0.auth0_id, "review_text", request.review_text.as_deref())
+            .await?;
+
+        // Create review
+        let review_id = Uuid::new_v4();
+        let query = r#"
+            INSERT INTO marketplace_reviews (
+                id, transaction_id, reviewer_id, reviewed_user_id, 
+                rating, review_text, deal_verified, is_buyer_review, created_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, CURRENT_TIMESTAMP)
+            RETURNING *
+        "#;
+
+        let mut tx = self.pool.begin().await?;
+
+        let review = sqlx::query_as::<_, MarketplaceReview>(query)
+            .bind(review_id)
+            .bind(request.transaction_id)
+            .bind(&auth_user.0.auth0_id)
             .bind(&reviewed_user_id)
             .bind(request.rating)
-            .bind(&request.review_text)
+            .bind(&review_text)
             .bind(request.deal_verified)
             .bind(is_buyer_review)
-            .fetch_one(&self.pool)
+            .fetch_one(&mut *tx)
             .await?;
 
-        // Update trust score
-        self.recalculate_trust_score(&reviewed_user_id).await?;
+        OutboxService::record(&mut tx, "review", review_id, event_types::REVIEW_CREATED, &review).await?;
+        tx.commit().await?;
+        replica::user_written(&reviewed_user_id);
+        replica::listing_written(transaction.listing_id);
+
+        // The trust score picks up the review once the queue worker recomputes it
+        trust_scores::enqueue(&self.pool, &reviewed_user_id).await?;
 
         // Create notification
         self.create_notification(
@@ -553,134 +1199,110 @@ impl MarketplaceService {
             &format!("You received a {}-star review", request.rating),
             None,
             Some(request.transaction_id),
-        ).await?;
+        ).await;
 
         Ok(review)
     }
 
-    // Trust Score Management
-    async fn ensure_trust_score(&self, user_id: &str) -> Result<(), AppError> {
-        sqlx::query(
-            r#"
-            INSERT INTO marketplace_trust_scores (user_id, trust_score, last_calculated)
-            VALUES ($1, 50.0, CURRENT_TIMESTAMP)
-            ON CONFLICT (user_id) DO NOTHING
-            "#
-        )
-        .bind(user_id)
-        .execute(&self.pool)
-        .await?;
-
-        Ok(())
-    }
-
-    async fn update_trust_score_after_transaction(
+    /// Reviews of a user, newest first
+    pub async fn get_user_reviews(
         &self,
         user_id: &str,
-        successful: bool,
-    ) -> Result<(), AppError> {
-        let query = if successful {
-            r#"
-            UPDATE marketplace_trust_scores 
-            SET total_transactions = total_transactions + 1,
-                successful_transactions = successful_transactions + 1,
-                last_calculated = CURRENT_TIMESTAMP
-            WHERE user_id = $1
-            "#
-        } else {
-            r#"
-            UPDATE marketplace_trust_scores 
-            SET total_transactions = total_transactions + 1,
-                last_calculated = CURRENT_TIMESTAMP
-            WHERE user_id = $1
-            "#
-        };
-
-        sqlx::query(query)
-            .bind(user_id)
-            .execute(&self.pool)
-            .await?;
+        filters: ReviewFilters,
+    ) -> Result<Vec<MarketplaceReview>, AppError> {
+        let mut query = QueryBuilder::<Postgres>::new("SELECT r.* FROM marketplace_reviews r WHERE r.reviewed_user_id = ");
+        query.push_bind(user_id.to_string());
 
-        self.recalculate_trust_score(user_id).await?;
-        Ok(())
+        let pool = replica::user_read_pool(&self.pool, user_id);
+        query_reviews(query, filters, &pool).await
     }
 
-    async fn recalculate_trust_score(&self, user_id: &str) -> Result<(), AppError> {
-        // Get current stats
-        let stats = sqlx::query(
-            r#"
-            SELECT 
-                ts.total_transactions,
-                ts.successful_transactions,
-                ts.verified_seller,
-                COUNT(r.id) as review_count,
-                AVG(r.rating) as avg_rating
-            FROM marketplace_trust_scores ts
-            LEFT JOIN marketplace_reviews r ON r.reviewed_user_id = ts.user_id
-            WHERE ts.user_id = $1
-            GROUP BY ts.user_id, ts.total_transactions, ts.successful_transactions, ts.verified_seller
-            "#
-        )
-        .bind(user_id)
-        .fetch_optional(&self.pool)
-        .await?;
-
-        if let Some(row) = stats {
-            let total_transactions: i32 = row.get("total_transactions");
-            let successful_transactions: i32 = row.get("successful_transactions");
-            let verified_seller: bool = row.get("verified_seller");
-            let review_count: i64 = row.get("review_count");
-            let avg_rating: Option<f64> = row.get("avg_rating");
+    /// Reviews left on purchases of a listing, newest first
+    pub async fn get_listing_reviews(
+        &self,
+        listing_id: Uuid,
+        filters: ReviewFilters,
+    ) -> Result<Vec<MarketplaceReview>, AppError> {
+        let mut query = QueryBuilder::<Postgres>::new(
+            "SELECT r.* FROM marketplace_reviews r JOIN marketplace_transactions t ON t.id = r.transaction_id WHERE t.listing_id = "
+        );
+        query.push_bind(listing_id);
+
+        let pool = replica::listing_read_pool(&self.pool, listing_id);
+        query_reviews(query, filters, &pool).await
+    }
 
-            // Calculate trust score (0-100)
-            let mut score: f64 = 50.0; // Base score
+    /// Who `reviewer_id` reviews for the transaction and whether they review as its buyer.
+    /// Only the parties of a completed transaction review it, once each.
+    async fn review_target(
+        &self,
+        reviewer_id: &str,
+        transaction: &MarketplaceTransaction,
+    ) -> Result<(String, bool), AppError> {
+        // Verify transaction is completed
+        if transaction.status != TransactionStatus::Completed {
+            return Err(AppError::Conflict("Can only review completed transactions".to_string()));
+        }
 
-            // Transaction success rate (up to 30 points)
-            if total_transactions > 0 {
-                let success_rate = successful_transactions as f64 / total_transactions as f64;
-                score += success_rate * 30.0;
-            }
+        // Determine if this is a buyer or seller review
+        let target = if transaction.buyer_id == reviewer_id {
+            (transaction.seller_id.clone(), true)
+        } else if transaction.seller_id == reviewer_id {
+            (transaction.buyer_id.clone(), false)
+        } else {
+            return Err(AppError::Forbidden("You are not part of this transaction".to_string()));
+        };
 
-            // Average rating (up to 30 points)
-            if let Some(rating) = avg_rating {
-                score += (rating / 5.0) * 30.0;
-            }
+        // Check if already reviewed
+        if self.repos.transactions.has_reviewed(transaction.id, reviewer_id).await? {
+            return Err(AppError::Conflict("You have already reviewed this transaction".to_string()));
+        }
+        Ok(target)
+    }
 
-            // Review count bonus (up to 10 points)
-            score += (review_count as f64).min(10.0);
+    // Trust Score Management
+    async fn ensure_trust_score(&self, user_id: &str) -> Result<(), AppError> {
+        self.repos.trust_scores.ensure(user_id).await
+    }
 
-            // Verified seller bonus
-            if verified_seller {
-                score += 10.0;
-            }
+    pub(crate) async fn update_trust_score_after_transaction(
+        &self,
+        user_id: &str,
+        successful: bool,
+    ) -> Result<(), AppError> {
+        self.repos.trust_scores.record_transaction(user_id, successful).await?;
+        trust_scores::enqueue(&self.pool, user_id).await?;
+        Ok(())
+    }
 
-            // Cap at 100
-            score = score.min(100.0);
+    /// A refunded sale counts against the seller: completed sales lose their success,
+    /// sales refunded before completion are recorded as unsuccessful
+    pub(crate) async fn update_trust_score_after_refund(
+        &self,
+        seller_id: &str,
+        was_completed: bool,
+    ) -> Result<(), AppError> {
+        if was_completed {
+            self.repos.trust_scores.reverse_success(seller_id).await?;
+        } else {
+            self.repos.trust_scores.record_transaction(seller_id, false).await?;
+        }
+        trust_scores::enqueue(&self.pool, seller_id).await?;
+        Ok(())
+    }
 
-            // Update score
-            sqlx::query(
-                r#"
-                UPDATE marketplace_trust_scores 
-                SET trust_score = $1,
-                    average_rating = $2,
-                    total_reviews = $3,
-                    last_calculated = CURRENT_TIMESTAMP
-                WHERE user_id = $4
-                "#
-            )
-            .bind(score)
-            .bind(avg_rating.unwrap_or(0.0))
-            .bind(review_count as i32)
-            .bind(user_id)
-            .execute(&self.pool)
-            .await?;
+    /// Recompute the score right away; writes that change its inputs enqueue the user instead
+    pub(crate) async fn recalculate_trust_score(&self, user_id: &str) -> Result<(), AppError> {
+        if let Some(stats) = self.repos.trust_scores.get_stats(user_id).await? {
+            let score = compute_trust_score(&stats);
+            self.repos.trust_scores.save_score(user_id, score, &stats).await?;
         }
 
         Ok(())
     }
 
     // Notification Management
-    async fn create_notification(
+    pub(crate) async fn create_notification(
         &self,
         user_id: &str,
         notification_type: &str,
@@ -688,52 +1310,84 @@ impl MarketplaceService {
         message: &str,
         listing_id: Option<Uuid>,
         transaction_id: Option<Uuid>,
-    ) -> Result<(), AppError> {
-        let notification_id = Uuid::new_v4();
-        let query = r#"
-            INSERT INTO marketplace_notifications (
-                id, user_id, notification_type, title, message,
-                related_listing_id, related_transaction_id, created_at
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, CURRENT_TIMESTAMP)
-        "#;
+    ) {
+        // Stored and delivered by the fan-out worker, so callers never wait on delivery. The
+        // change being notified about is already made, so a failure is retried, not returned.
+        side_effects::perform(&self.pool, SideEffect::Notification {
+            user_id: user_id.to_string(),
+            notification_type: notification_type.to_string(),
+            title: title.to_string(),
+            message: message.to_string(),
+            listing_id,
+            transaction_id,
+        }).await
+    }
 
-        sqlx::query(query)
-            .bind(notification_id)
-            .bind(user_id)
-            .bind(notification_type)
-            .bind(title)
-            .bind(message)
-            .bind(listing_id)
-            .bind(transaction_id)
-            .execute(&self.pool)
-            .await?;
+    // Helper Methods
+    pub(crate) async fn get_transaction_by_id(&self, transaction_id: Uuid) -> Result<MarketplaceTransaction, AppError> {
+        self.repos.transactions
+            .find_transaction(transaction_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Transaction not found".to_string()))
+    }
+
+    /// Check whether the user holds the marketplace admin role
+    pub(crate) async fn is_admin(&self, user_id: &str) -> Result<bool, AppError> {
+        self.repos.roles.is_admin(user_id).await
+    }
 
+    /// Notify every admin
+    pub(crate) async fn notify_admins(&self, notification_type: &str, title: &str, message: &str) -> Result<(), AppError> {
+        let admins: Vec<String> = sqlx::query("SELECT user_id FROM marketplace_user_roles WHERE role = 'admin'")
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|row| row.get("user_id"))
+            .collect();
+
+        for admin_id in admins {
+            self.create_notification(&admin_id, notification_type, title, message, None, None).await;
+        }
         Ok(())
     }
 
-    // Helper Methods
-    async fn get_transaction_by_id(&self, transaction_id: Uuid) -> Result<MarketplaceTransaction, AppError> {
-        sqlx::query_as::<_, MarketplaceTransaction>(
-            "SELECT * FROM marketplace_transactions WHERE id = $1"
-        )
-        .bind(transaction_id)
-        .fetch_optional(&self.pool)
-        .await?
-        .ok_or_else(|| AppError::NotFound("Transaction not found".to_string()))
+    /// Ensure the user holds the marketplace admin role
+    pub(crate) async fn require_admin(&self, auth_user: &AuthUser) -> Result<(), AppError> {
+        if !self.is_admin(&auth_user.0.auth0_id).await? {
+            return Err(AppError::Forbidden("Admin access required".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Ensure the user may review seller verifications
+    pub(crate) async fn require_verifier(&self, auth_user: &AuthUser) -> Result<(), AppError> {
+        if !self.repos.roles.is_verifier(&auth_user.0.auth0_id).await? {
+            return Err(AppError::Forbidden("Verifier access required".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Authorization rules, with roles from this service's repositories
+    pub(crate) fn authorization(&self) -> AuthorizationPolicy {
+        AuthorizationPolicy::with_roles(self.repos.roles.clone())
     }
 
     pub async fn get_user_profile(
         &self,
         user_id: &str,
     ) -> Result<MarketplaceProfile, AppError> {
+        let read_pool = replica::user_read_pool(&self.pool, user_id);
+
         // Get user info
-        let user = sqlx::query("SELECT username, email, created_at FROM users WHERE auth0_id = $1")
+        let user = sqlx::query("SELECT username, created_at FROM users WHERE auth0_id = $1")
             .bind(user_id)
-            .fetch_optional(&self.pool)
+            .fetch_optional(&read_pool)
             .await?
             .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
 
-        // Get trust score
+        // Get trust score; read from the primary, where a missing one was just created
         self.ensure_trust_score(user_id).await?;
         let trust_score = sqlx::query_as::<_, MarketplaceTrustScore>(
             "SELECT * FROM marketplace_trust_scores WHERE user_id = $1"
@@ -742,83 +1396,678 @@ impl MarketplaceService {
         .fetch_one(&self.pool)
         .await?;
 
+        // Get badges and followers
+        let badges = BadgeService::new(self.pool.clone()).get_badges(user_id).await?;
+        let follower_count = FollowService::new(self.pool.clone()).get_follower_count(user_id).await?;
+        let preferences = preferences::load_preferences(&read_pool, user_id).await?;
+        let settings = notifications::load_settings(&read_pool, user_id).await?;
+
         // Get listing stats
-        let listing_stats = sqlx::query(
+        let listing_stats = database::timed("profile.listing_stats", sqlx::query(
             r#"
             SELECT 
                 COUNT(*) as total_listings,
                 COUNT(*) FILTER (WHERE status = 'active') as active_listings,
                 COUNT(*) FILTER (WHERE status = 'sold') as completed_sales
             FROM marketplace_listings
-            WHERE seller_id = $1
+            WHERE seller_id = $1 AND deleted_at IS NULL
             "#
         )
         .bind(user_id)
-        .fetch_one(&self.pool)
+        .fetch_one(&read_pool))
         .await?;
 
         Ok(MarketplaceProfile {
             user_id: user_id.to_string(),
             username: user.get("username"),
-            profile_image_url: user.get("email"),
+            profile_image_url: None, // users have no profile image yet; never their email
             trust_score,
+            badges,
+            follower_count,
             total_listings: listing_stats.get("total_listings"),
             active_listings: listing_stats.get("active_listings"),
             completed_sales: listing_stats.get("completed_sales"),
             member_since: user.get("created_at"),
+            locale: preferences.locale,
+            region: preferences.region,
+            timezone: settings.timezone,
         })
     }
 
-    // Coupon Code Management
-    pub async fn get_coupon_code(
+    // Dashboard Aggregation
+    pub async fn get_transaction_summary(&self, user_id: &str) -> Result<TransactionSummary, AppError> {
+        let row = sqlx::query(
+            r#"
+            SELECT
+                COALESCE(SUM(amount - refunded_amount) FILTER (WHERE seller_id = $1 AND status = 'completed'), 0)::numeric as total_sales,
+                COALESCE(SUM(amount - refunded_amount) FILTER (WHERE buyer_id = $1 AND status = 'completed'), 0)::numeric as total_purchases,
+                COUNT(*) FILTER (WHERE status IN ('pending', 'escrow')) as pending_transactions,
+                COUNT(*) FILTER (WHERE status = 'completed' AND refunded_amount < amount) as completed_transactions,
+                ROUND(COALESCE(AVG(amount - refunded_amount) FILTER (WHERE status = 'completed' AND refunded_amount < amount), 0), 2) as average_transaction_value
+            FROM marketplace_transactions
+            WHERE buyer_id = $1 OR seller_id = $1
+            "#
+        )
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(TransactionSummary {
+            total_sales: row.get("total_sales"),
+            total_purchases: row.get("total_purchases"),
+            pending_transactions: row.get("pending_transactions"),
+            completed_transactions: row.get("completed_transactions"),
+            average_transaction_value: row.get("average_transaction_value"),
+        })
+    }
+
+    pub async fn get_recent_transactions(
         &self,
-        auth_user: &AuthUser,
-        listing_id: Uuid,
-    ) -> Result<Option<String>, AppError> {
-        // Check if user has access (either seller or has purchased)
-        let has_access = sqlx::query(
+        user_id: &str,
+        limit: i64,
+    ) -> Result<Vec<TransactionDetail>, AppError> {
+        let transactions = sqlx::query_as::<_, MarketplaceTransaction>(
             r#"
-            SELECT 1 FROM marketplace_listings WHERE id = $1 AND seller_id = $2
-            UNION
-            SELECT 1 FROM marketplace_coupon_access WHERE listing_id = $1 AND user_id = $2
+            SELECT * FROM marketplace_transactions
+            WHERE buyer_id = $1 OR seller_id = $1
+            ORDER BY created_at DESC
+            LIMIT $2
             "#
         )
-        .bind(listing_id)
-        .bind(&auth_user.0.auth0_id)
-        .fetch_optional(&self.pool)
+        .bind(user_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
         .await?;
 
-        if has_access.is_none() {
-            return Ok(None);
+        if transactions.is_empty() {
+            return Ok(vec![]);
         }
 
-        // Get encrypted code
-        let result = sqlx::query(
-            "SELECT encrypted_code FROM marketplace_coupon_codes WHERE listing_id = $1"
+        let listing_ids: Vec<Uuid> = transactions.iter().map(|t| t.listing_id).collect();
+        let transaction_ids: Vec<Uuid> = transactions.iter().map(|t| t.id).collect();
+        let user_ids: Vec<String> = transactions
+            .iter()
+            .flat_map(|t| [t.buyer_id.clone(), t.seller_id.clone()])
+            .collect();
+
+        // Batch the joins instead of one lookup per transaction
+        let listings: std::collections::HashMap<Uuid, MarketplaceListing> =
+            sqlx::query_as::<_, MarketplaceListing>(
+                "SELECT * FROM marketplace_listings_all WHERE id = ANY($1)"
+            )
+            .bind(&listing_ids)
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|listing| (listing.id, listing))
+            .collect();
+
+        let usernames: std::collections::HashMap<String, String> = sqlx::query(
+            "SELECT auth0_id, username FROM users WHERE auth0_id = ANY($1)"
         )
-        .bind(listing_id)
-        .fetch_optional(&self.pool)
+        .bind(&user_ids)
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| (row.get("auth0_id"), row.get("username")))
+        .collect();
+
+        let timezones: std::collections::HashMap<String, String> = sqlx::query(
+            "SELECT user_id, timezone FROM marketplace_notification_settings WHERE user_id = ANY($1)"
+        )
+        .bind(&user_ids)
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| (row.get("user_id"), row.get("timezone")))
+        .collect();
+
+        let reviewed: std::collections::HashSet<Uuid> = sqlx::query(
+            "SELECT transaction_id FROM marketplace_reviews WHERE transaction_id = ANY($1) AND reviewer_id = $2"
+        )
+        .bind(&transaction_ids)
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| row.get("transaction_id"))
+        .collect();
+
+        let details = transactions
+            .into_iter()
+            .filter_map(|transaction| {
+                let listing = listings.get(&transaction.listing_id)?.clone();
+                let has_reviewed = reviewed.contains(&transaction.id);
+                let username = |id: &str| usernames.get(id).cloned().unwrap_or_else(|| "unknown".to_string());
+
+                Some(TransactionDetail {
+                    buyer_username: username(&transaction.buyer_id),
+                    seller_username: username(&transaction.seller_id),
+                    currency: money::currency().to_string(),
+                    amount_minor: money::to_minor_units(&transaction.amount),
+                    refunded_amount_minor: money::to_minor_units(&transaction.refunded_amount),
+                    seller_timezone: timezones.get(&transaction.seller_id).cloned().unwrap_or_else(|| "UTC".to_string()),
+                    can_review: transaction.status == TransactionStatus::Completed && !has_reviewed,
+                    has_reviewed,
+                    listing,
+                    transaction,
+                })
+            })
+            .collect();
+
+        Ok(details)
+    }
+
+    pub async fn get_unread_notification_count(&self, user_id: &str) -> Result<i64, AppError> {
+        let row = sqlx::query(
+            "SELECT COUNT(*) as unread FROM marketplace_notifications WHERE user_id = $1 AND is_read = FALSE"
+        )
+        .bind(user_id)
+        .fetch_one(&self.pool)
         .await?;
 
-        if let Some(row) = result {
-            let encrypted_code: String = row.get("encrypted_code");
-            
-            // Split the encrypted code and nonce
-            let parts: Vec<&str> = encrypted_code.split(':').collect();
-            if parts.len() != 2 {
-                return Err(AppError::InternalError("Invalid encrypted data format".to_string()));
+        Ok(row.get("unread"))
+    }
+
+    // Coupon Code Management
+    /// Assign an unused code to the buyer unless one is already assigned;
+    /// concurrent allocations skip locked codes
+    pub(crate) async fn allocate_coupon_code(
+        tx: &mut Transaction<'_, Postgres>,
+        transaction: &MarketplaceTransaction,
+    ) -> Result<(), AppError> {
+        let already_allocated = sqlx::query("SELECT 1 FROM marketplace_coupon_codes WHERE allocated_transaction_id = $1")
+            .bind(transaction.id)
+            .fetch_optional(&mut **tx)
+            .await?;
+
+        if already_allocated.is_some() {
+            return Ok(());
+        }
+
+        let allocated = sqlx::query(
+            r#"
+            UPDATE marketplace_coupon_codes
+            SET allocated_transaction_id = $1,
+                allocated_to = $2,
+                allocated_at = CURRENT_TIMESTAMP
+            WHERE id = (
+                SELECT id FROM marketplace_coupon_codes
+                WHERE listing_id = $3 AND allocated_transaction_id IS NULL
+                ORDER BY id
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id
+            "#
+        )
+        .bind(transaction.id)
+        .bind(&transaction.buyer_id)
+        .bind(transaction.listing_id)
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        if allocated.is_none() {
+            let has_codes = sqlx::query("SELECT 1 FROM marketplace_coupon_codes WHERE listing_id = $1 LIMIT 1")
+                .bind(transaction.listing_id)
+                .fetch_optional(&mut **tx)
+                .await?;
+
+            if has_codes.is_some() {
+                return Err(AppError::Conflict("No unallocated coupon code left for this listing".to_string()));
             }
-            
-            // Get encryption key from environment
-            let encryption_key = std::env::var("ENCRYPTION_KEY")
-                .unwrap_or_else(|_| EncryptionService::generate_key());
-            let encryption_service = EncryptionService::new(&encryption_key)?;
-            
-            // Decrypt the coupon code
-            let decrypted_code = encryption_service.decrypt_string(parts[0], parts[1])?;
-            Ok(Some(decrypted_code))
+        }
+
+        // Grant access to coupon code if applicable
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_coupon_access (listing_id, user_id, transaction_id)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (transaction_id) DO NOTHING
+            "#
+        )
+        .bind(transaction.listing_id)
+        .bind(&transaction.buyer_id)
+        .bind(transaction.id)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Revoke the buyer's access to the transaction's code and return the code to the
+    /// pool if the buyer never revealed it. Returns false when a revealed code was burned,
+    /// so the unit must not go back on sale.
+    pub(crate) async fn release_coupon_code(
+        tx: &mut Transaction<'_, Postgres>,
+        transaction: &MarketplaceTransaction,
+    ) -> Result<bool, AppError> {
+        sqlx::query("DELETE FROM marketplace_coupon_access WHERE transaction_id = $1")
+            .bind(transaction.id)
+            .execute(&mut **tx)
+            .await?;
+
+        sqlx::query(
+            r#"
+            UPDATE marketplace_coupon_codes c
+            SET allocated_transaction_id = NULL, allocated_to = NULL, allocated_at = NULL
+            WHERE c.allocated_transaction_id = $1
+            AND NOT EXISTS (
+                SELECT 1 FROM marketplace_coupon_reveals r
+                WHERE r.listing_id = c.listing_id AND r.user_id = c.allocated_to
+                AND r.revealed_at >= c.allocated_at
+            )
+            "#
+        )
+        .bind(transaction.id)
+        .execute(&mut **tx)
+        .await?;
+
+        let burned = sqlx::query("SELECT 1 FROM marketplace_coupon_codes WHERE allocated_transaction_id = $1")
+            .bind(transaction.id)
+            .fetch_optional(&mut **tx)
+            .await?;
+
+        Ok(burned.is_none())
+    }
+
+    pub(crate) async fn restock_listing(
+        tx: &mut Transaction<'_, Postgres>,
+        listing_id: Uuid,
+    ) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            UPDATE marketplace_listings
+            SET remaining_quantity = remaining_quantity + 1,
+                status = CASE WHEN status = 'sold' THEN 'active' ELSE status END,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1 AND deleted_at IS NULL
+            "#
+        )
+        .bind(listing_id)
+        .execute(&mut **tx)
+        .await?;
+        replica::listing_written(listing_id);
+
+        Ok(())
+    }
+
+    /// Codes visible to the user: every code for the seller, allocated codes for buyers
+    pub async fn get_coupon_codes(
+        &self,
+        auth_user: &AuthUser,
+        listing_id: Uuid,
+    ) -> Result<Vec<String>, AppError> {
+        let user_id = &auth_user.0.auth0_id;
+
+        let is_seller = sqlx::query("SELECT 1 FROM marketplace_listings_all WHERE id = $1 AND seller_id = $2")
+            .bind(listing_id)
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .is_some();
+
+        let rows = if is_seller {
+            sqlx::query(
+                "SELECT encrypted_code FROM marketplace_coupon_codes WHERE listing_id = $1 ORDER BY id"
+            )
+            .bind(listing_id)
+            .fetch_all(&self.pool)
+            .await?
         } else {
-            Ok(None)
+            sqlx::query(
+                r#"
+                SELECT c.encrypted_code
+                FROM marketplace_coupon_codes c
+                JOIN marketplace_coupon_access a ON a.transaction_id = c.allocated_transaction_id
+                WHERE c.listing_id = $1 AND a.user_id = $2
+                ORDER BY c.allocated_at
+                "#
+            )
+            .bind(listing_id)
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await?
+        };
+
+        if rows.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let keyring = CouponKeyring::from_config(Config::get())?;
+
+        rows.into_iter()
+            .map(|row| {
+                let encrypted_code: String = row.get("encrypted_code");
+                keyring.decrypt(&encrypted_code)
+            })
+            .collect()
+    }
+}
+
+/// WHERE conditions for `filters`, appended to a query over `LISTING_WITH_SELLER_FROM`
+fn push_listing_filters(query: &mut QueryBuilder<'_, Postgres>, filters: &ListingFilters) {
+    if !filters.include_deleted {
+        query.push(" AND l.deleted_at IS NULL");
+    }
+
+    if !filters.include_shadow_banned {
+        query.push(format!(" AND {}", shadow_bans::NOT_SHADOW_BANNED));
+    }
+
+    if !filters.include_away {
+        query.push(format!(" AND {}", away::SELLER_NOT_AWAY));
+    }
+
+    if let Some(category) = &filters.category {
+        // Parent categories match all of their descendants
+        push_category_subtree(query, "l.category", CategoryService::normalize_slug(category));
+    }
+
+    if let Some(listing_type) = &filters.listing_type {
+        query.push(" AND l.listing_type = ").push_bind(*listing_type);
+    }
+
+    if let Some(min_price) = filters.min_price {
+        query.push(" AND l.selling_price >= ").push_bind(min_price).push("::numeric");
+    }
+
+    if let Some(max_price) = filters.max_price {
+        query.push(" AND l.selling_price <= ").push_bind(max_price).push("::numeric");
+    }
+
+    if let Some(brand) = &filters.brand {
+        query
+            .push(" AND l.brand_id = (SELECT id FROM marketplace_brands WHERE slug = ")
+            .push_bind(brand.clone())
+            .push(")");
+    }
+
+    if let Some(language) = &filters.language {
+        query
+            .push(" AND (l.language = ")
+            .push_bind(language.clone())
+            .push(" OR EXISTS (SELECT 1 FROM marketplace_listing_translations t WHERE t.listing_id = l.id AND t.locale = ")
+            .push_bind(language.clone())
+            .push("))");
+    }
+
+    if let Some(region) = &filters.region {
+        query.push(" AND l.region = ").push_bind(region.clone());
+    }
+
+    if let Some(tags) = &filters.tags {
+        let tags: Vec<String> = TagService::normalize(&tags.split(',').map(str::to_string).collect::<Vec<_>>());
+        if !tags.is_empty() {
+            // Containment is served by the GIN index on tags
+            query.push(" AND l.tags @> ").push_bind(tags);
+        }
+    }
+
+    if let Some(follower_id) = &filters.followed_by {
+        query
+            .push(" AND l.seller_id IN (SELECT seller_id FROM marketplace_seller_follows WHERE follower_id = ")
+            .push_bind(follower_id.clone())
+            .push(")");
+    }
+
+    if let Some(seller_id) = &filters.seller_id {
+        query.push(" AND l.seller_id = ").push_bind(seller_id.clone());
+    }
+
+    if let Some(status) = &filters.status {
+        query.push(" AND l.status = ").push_bind(*status);
+    }
+
+    if let Some(is_verified) = filters.is_verified {
+        query.push(" AND l.is_verified = ").push_bind(is_verified);
+    }
+
+    if let Some(verified_seller) = filters.verified_seller {
+        query.push(" AND COALESCE(ts.verified_seller, FALSE) = ").push_bind(verified_seller);
+    }
+
+    if let Some(search_query) = &filters.search_query {
+        let search_pattern = format!("%{}%", search_query);
+        query
+            .push(" AND (l.title ILIKE ")
+            .push_bind(search_pattern.clone())
+            .push(" OR l.description ILIKE ")
+            .push_bind(search_pattern.clone())
+            .push(" OR l.brand_name ILIKE ")
+            .push_bind(search_pattern)
+            .push(")");
+    }
+}
+
+/// Map a listing row joined with seller columns
+fn listing_with_seller_from_row(row: &PgRow) -> Result<ListingWithSeller, sqlx::Error> {
+    let listing = MarketplaceListing::from_row(row)?;
+    Ok(ListingWithSeller {
+        seller_username: row.try_get("seller_username")?,
+        seller_trust_score: row.try_get("seller_trust_score")?,
+        seller_badges: row.try_get("seller_badges")?,
+        price_drop_percentage: row.try_get("price_drop_percentage")?,
+        featured: row.try_get("featured")?,
+        currency: money::currency().to_string(),
+        selling_price_minor: money::to_minor_units(&listing.selling_price),
+        original_value_minor: listing.original_value.as_ref().map(money::to_minor_units),
+        seller_timezone: row.try_get("seller_timezone")?,
+        translated_from: None,
+        faq: Vec::new(),
+        reserved_until: None,
+        listing,
+    })
+}
+
+/// Apply `filters` and paging to a query over `marketplace_reviews r` and run it
+async fn query_reviews(
+    mut query: QueryBuilder<'_, Postgres>,
+    filters: ReviewFilters,
+    pool: &PgPool,
+) -> Result<Vec<MarketplaceReview>, AppError> {
+    if let Some(is_buyer_review) = filters.is_buyer_review {
+        query.push(" AND r.is_buyer_review = ").push_bind(is_buyer_review);
+    }
+    if let Some(min_rating) = filters.min_rating {
+        query.push(" AND r.rating >= ").push_bind(min_rating);
+    }
+
+    let limit = filters.limit.unwrap_or(20).clamp(1, 100);
+    let page = filters.page.unwrap_or(0).max(0);
+    let offset = page
+        .checked_mul(limit)
+        .ok_or_else(|| AppError::BadRequest("page is out of range".to_string()))?;
+    query
+        .push(" ORDER BY r.created_at DESC LIMIT ")
+        .push_bind(limit)
+        .push(" OFFSET ")
+        .push_bind(offset);
+
+    let reviews = database::timed("reviews.list", query.build_query_as::<MarketplaceReview>().fetch_all(pool)).await?;
+    Ok(reviews)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::repository::fixtures::{listing, transaction};
+    use super::repository::{InMemoryRepository, TrustStats};
+    use super::*;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn ensure_trust_score_starts_new_users_at_the_base_score() {
+        let store = Arc::new(InMemoryRepository::new());
+        let service = MarketplaceService::in_memory(store.clone());
+
+        service.ensure_trust_score("seller").await.unwrap();
+
+        assert_eq!(store.trust_scores.lock().unwrap().get("seller"), Some(&50.0));
+        assert_eq!(store.trust_stats.lock().unwrap().get("seller"), Some(&TrustStats::default()));
+    }
+
+    #[tokio::test]
+    async fn ensure_trust_score_keeps_an_existing_score() {
+        let store = Arc::new(InMemoryRepository::new());
+        store.trust_scores.lock().unwrap().insert("seller".to_string(), 87.5);
+        let service = MarketplaceService::in_memory(store.clone());
+
+        service.ensure_trust_score("seller").await.unwrap();
+
+        assert_eq!(store.trust_scores.lock().unwrap().get("seller"), Some(&87.5));
+    }
+
+    #[tokio::test]
+    async fn recalculate_trust_score_saves_the_score_of_the_stored_stats() {
+        let store = Arc::new(InMemoryRepository::new());
+        let stats = TrustStats {
+            total_transactions: 4,
+            successful_transactions: 3,
+            verified_seller: true,
+            review_count: 2,
+            avg_rating: Some(4.0),
+        };
+        store.trust_stats.lock().unwrap().insert("seller".to_string(), stats.clone());
+        let service = MarketplaceService::in_memory(store.clone());
+
+        service.recalculate_trust_score("seller").await.unwrap();
+
+        // 50 base + 22.5 success rate + 24 rating + 2 reviews + 10 verified, capped
+        assert_eq!(store.trust_scores.lock().unwrap().get("seller"), Some(&100.0));
+    }
+
+    async fn recalculated_score(stats: TrustStats) -> f64 {
+        let store = Arc::new(InMemoryRepository::new());
+        store.trust_stats.lock().unwrap().insert("seller".to_string(), stats);
+        let service = MarketplaceService::in_memory(store.clone());
+
+        service.recalculate_trust_score("seller").await.unwrap();
+
+        let scores = store.trust_scores.lock().unwrap();
+        scores["seller"]
+    }
+
+    #[tokio::test]
+    async fn review_count_adds_at_most_ten_points() {
+        let score = recalculated_score(TrustStats { review_count: 25, ..TrustStats::default() }).await;
+
+        assert_eq!(score, 60.0);
+    }
+
+    #[tokio::test]
+    async fn only_verified_sellers_get_the_verification_bonus() {
+        let stats = TrustStats {
+            total_transactions: 4,
+            successful_transactions: 2,
+            verified_seller: false,
+            review_count: 3,
+            avg_rating: Some(2.5),
+        };
+
+        assert_eq!(recalculated_score(stats.clone()).await, 83.0);
+        assert_eq!(recalculated_score(TrustStats { verified_seller: true, ..stats }).await, 93.0);
+    }
+
+    #[tokio::test]
+    async fn find_own_listing_returns_the_listing_to_its_seller_only() {
+        let store = Arc::new(InMemoryRepository::new());
+        store.admins.lock().unwrap().insert("admin".to_string());
+        let stored = listing("seller");
+        store.listings.lock().unwrap().insert(stored.id, stored.clone());
+        let service = MarketplaceService::in_memory(store);
+
+        assert_eq!(service.find_own_listing("seller", stored.id).await.unwrap().id, stored.id);
+        for user_id in ["admin", "stranger"] {
+            let result = service.find_own_listing(user_id, stored.id).await;
+            assert!(matches!(result, Err(AppError::Forbidden(_))), "{}", user_id);
+        }
+    }
+
+    #[tokio::test]
+    async fn find_own_listing_reports_deleted_listings_as_not_found() {
+        let store = Arc::new(InMemoryRepository::new());
+        let mut stored = listing("seller");
+        stored.deleted_at = Some(Utc::now());
+        store.listings.lock().unwrap().insert(stored.id, stored.clone());
+        let service = MarketplaceService::in_memory(store);
+
+        let result = service.find_own_listing("seller", stored.id).await;
+
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn each_party_of_a_completed_transaction_reviews_the_other() {
+        let service = MarketplaceService::in_memory(Arc::new(InMemoryRepository::new()));
+        let completed = transaction(TransactionStatus::Completed);
+
+        assert_eq!(service.review_target("buyer", &completed).await.unwrap(), ("seller".to_string(), true));
+        assert_eq!(service.review_target("seller", &completed).await.unwrap(), ("buyer".to_string(), false));
+        assert!(matches!(service.review_target("stranger", &completed).await, Err(AppError::Forbidden(_))));
+    }
+
+    #[tokio::test]
+    async fn only_completed_transactions_are_reviewed() {
+        let service = MarketplaceService::in_memory(Arc::new(InMemoryRepository::new()));
+
+        for status in [TransactionStatus::Pending, TransactionStatus::Escrow, TransactionStatus::Disputed] {
+            let result = service.review_target("buyer", &transaction(status)).await;
+            assert!(matches!(result, Err(AppError::Conflict(_))), "{:?}", status);
         }
     }
+
+    #[tokio::test]
+    async fn a_party_reviews_a_transaction_once() {
+        let store = Arc::new(InMemoryRepository::new());
+        let completed = transaction(TransactionStatus::Completed);
+        store.reviews.lock().unwrap().insert((completed.id, "buyer".to_string()));
+        let service = MarketplaceService::in_memory(store);
+
+        assert!(matches!(service.review_target("buyer", &completed).await, Err(AppError::Conflict(_))));
+        assert!(service.review_target("seller", &completed).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn recalculate_trust_score_leaves_users_without_stats_alone() {
+        let store = Arc::new(InMemoryRepository::new());
+        let service = MarketplaceService::in_memory(store.clone());
+
+        service.recalculate_trust_score("stranger").await.unwrap();
+
+        assert!(store.trust_scores.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn get_transaction_by_id_returns_the_stored_transaction() {
+        let store = Arc::new(InMemoryRepository::new());
+        let stored = transaction(TransactionStatus::Escrow);
+        store.transactions.lock().unwrap().insert(stored.id, stored.clone());
+        let service = MarketplaceService::in_memory(store);
+
+        let found = service.get_transaction_by_id(stored.id).await.unwrap();
+
+        assert_eq!(found.id, stored.id);
+        assert_eq!(found.status, TransactionStatus::Escrow);
+    }
+
+    #[tokio::test]
+    async fn get_transaction_by_id_reports_unknown_transactions_as_not_found() {
+        let service = MarketplaceService::in_memory(Arc::new(InMemoryRepository::new()));
+
+        let result = service.get_transaction_by_id(Uuid::new_v4()).await;
+
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn is_admin_follows_the_stored_roles() {
+        let store = Arc::new(InMemoryRepository::new());
+        store.admins.lock().unwrap().insert("admin".to_string());
+        store.verifiers.lock().unwrap().insert("verifier".to_string());
+        let service = MarketplaceService::in_memory(store);
+
+        assert!(service.is_admin("admin").await.unwrap());
+        assert!(!service.is_admin("verifier").await.unwrap());
+        assert!(!service.is_admin("stranger").await.unwrap());
+    }
 }
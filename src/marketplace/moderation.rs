@@ -0,0 +1,361 @@
+use crate::auth::AuthUser;
+use crate::config::{Config, ImageModeration};
+use crate::error::AppError;
+use crate::marketplace::follows::FollowService;
+use crate::marketplace::outbox::{event_types, OutboxService};
+use crate::marketplace::scrubbing;
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{
+    MarketplaceListing, ModerationCase, ModerationFlag, ModerationReason, ModerationStatus,
+    ReviewModerationCaseRequest,
+};
+use serde::Deserialize;
+use sqlx::types::Json;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::time::Duration;
+use uuid::Uuid;
+
+// Matched against whole words, or runs of whole words for phrases
+const PROHIBITED_TERMS: &[&str] = &[
+    "stolen", "carding", "cvv", "fullz", "counterfeit", "fake id", "hacked account",
+    "cracked account", "bin method", "refund method", "money flip",
+];
+
+const PROFANITY: &[&str] = &[
+    "fuck", "fucking", "shit", "bitch", "cunt", "asshole", "bastard", "dickhead", "motherfucker",
+];
+
+// Off-platform contact channels used to route around escrow
+const CONTACT_TERMS: &[&str] = &[
+    "whatsapp", "telegram", "signal me", "snapchat", "dm me", "text me", "call me",
+];
+
+const SPAM_PHRASES: &[&str] = &[
+    "click here", "act now", "100% working", "guaranteed working", "limited time only", "buy now buy now",
+];
+
+// Shouting: share of uppercase letters in text with at least this many letters
+const SPAM_CAPS_RATIO: f64 = 0.7;
+const SPAM_CAPS_MIN_LETTERS: usize = 12;
+
+// The same character this many times in a row ("!!!!!", "sooooo")
+const SPAM_REPEATED_CHARS: usize = 5;
+
+// The same word this many times, making up a large share of the text
+const SPAM_REPEATED_WORD_COUNT: usize = 5;
+const SPAM_REPEATED_WORD_SHARE: f64 = 0.3;
+
+/// Screens listing content before it is published. Listings with findings are held
+/// as `pending_review` and queued for a moderator instead of going live.
+pub struct ModerationService {
+    pool: PgPool,
+}
+
+impl ModerationService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Screen listing text and, when the image moderation API is configured, the proof image
+    pub async fn screen_listing(
+        &self,
+        title: &str,
+        description: Option<&str>,
+        proof_image_url: Option<&str>,
+    ) -> Result<Vec<ModerationFlag>, AppError> {
+        let mut flags = screen_text("title", title);
+        if let Some(description) = description {
+            flags.extend(screen_text("description", description));
+        }
+
+        if let Some(image_url) = proof_image_url.filter(|url| !url.trim().is_empty()) {
+            flags.extend(screen_image(&Config::get().image_moderation, image_url).await);
+        }
+
+        Ok(flags)
+    }
+
+    /// Open a moderation case for a listing, or add the flags to its open case
+    pub async fn enqueue(
+        tx: &mut Transaction<'_, Postgres>,
+        listing_id: Uuid,
+        seller_id: &str,
+        flags: &[ModerationFlag],
+    ) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_moderation_queue (id, listing_id, seller_id, flags, status, created_at)
+            VALUES ($1, $2, $3, $4, 'pending', CURRENT_TIMESTAMP)
+            ON CONFLICT (listing_id) WHERE status = 'pending'
+            DO UPDATE SET flags = marketplace_moderation_queue.flags || EXCLUDED.flags
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(listing_id)
+        .bind(seller_id)
+        .bind(Json(flags))
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Admin review queue, oldest cases first
+    pub async fn get_queue(
+        &self,
+        auth_user: &AuthUser,
+        status: Option<ModerationStatus>,
+    ) -> Result<Vec<ModerationCase>, AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+
+        let queue = sqlx::query_as::<_, ModerationCase>(
+            r#"
+            SELECT * FROM marketplace_moderation_queue
+            WHERE status = $1
+            ORDER BY created_at ASC
+            LIMIT 100
+            "#
+        )
+        .bind(status.unwrap_or(ModerationStatus::Pending))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(queue)
+    }
+
+    /// Publish or suspend a held listing
+    pub async fn review(
+        &self,
+        auth_user: &AuthUser,
+        case_id: Uuid,
+        request: ReviewModerationCaseRequest,
+    ) -> Result<ModerationCase, AppError> {
+        let service = MarketplaceService::new(self.pool.clone());
+        service.require_admin(auth_user).await?;
+
+        let (case_status, listing_status) = if request.approved {
+            (ModerationStatus::Approved, "active")
+        } else {
+            (ModerationStatus::Rejected, "suspended")
+        };
+
+        let mut tx = self.pool.begin().await?;
+
+        let case = sqlx::query_as::<_, ModerationCase>(
+            r#"
+            UPDATE marketplace_moderation_queue
+            SET status = $1,
+                reviewer_id = $2,
+                review_notes = $3,
+                reviewed_at = CURRENT_TIMESTAMP
+            WHERE id = $4 AND status = 'pending'
+            RETURNING *
+            "#
+        )
+        .bind(case_status)
+        .bind(&auth_user.0.auth0_id)
+        .bind(&request.review_notes)
+        .bind(case_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Open moderation case not found".to_string()))?;
+
+        // The seller may have deleted the listing while it was held
+        let listing = sqlx::query_as::<_, MarketplaceListing>(
+            r#"
+            UPDATE marketplace_listings
+            SET status = $1, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $2 AND status = 'pending_review' AND deleted_at IS NULL
+            RETURNING *
+            "#
+        )
+        .bind(listing_status)
+        .bind(case.listing_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if let Some(listing) = &listing {
+            OutboxService::record(&mut tx, "listing", listing.id, event_types::LISTING_UPDATED, listing).await?;
+        }
+
+        tx.commit().await?;
+
+        let Some(listing) = listing else {
+            return Ok(case);
+        };
+
+        if request.approved {
+            if let Err(e) = FollowService::new(self.pool.clone()).notify_followers(&listing).await {
+                tracing::warn!(error = %e, listing_id = %listing.id, "follower notification failed");
+            }
+
+            service.create_notification(
+                &listing.seller_id,
+                "listing_approved",
+                "Your listing is live",
+                &format!("\"{}\" passed review and is now visible to buyers", listing.title),
+                Some(listing.id),
+                None,
+            ).await;
+        } else {
+            service.create_notification(
+                &listing.seller_id,
+                "listing_rejected",
+                "Listing not approved",
+                &format!(
+                    "\"{}\" was not approved: {}",
+                    listing.title,
+                    request.review_notes.as_deref().unwrap_or("it does not meet the marketplace content rules")
+                ),
+                Some(listing.id),
+                None,
+            ).await;
+        }
+
+        Ok(case)
+    }
+}
+
+/// Screen a text field for prohibited items, profanity, contact details and spam
+pub fn screen_text(field: &str, text: &str) -> Vec<ModerationFlag> {
+    let mut flags = Vec::new();
+    let mut flag = |reason: ModerationReason, detail: String| {
+        flags.push(ModerationFlag { reason, field: field.to_string(), detail });
+    };
+
+    let words = words(text);
+    let normalized = format!(" {} ", words.join(" "));
+    let contains_term = |term: &str| normalized.contains(&format!(" {} ", term));
+
+    for term in PROHIBITED_TERMS.iter().filter(|term| contains_term(term)) {
+        flag(ModerationReason::ProhibitedItem, format!("prohibited term \"{}\"", term));
+    }
+
+    if let Some(term) = PROFANITY.iter().find(|term| contains_term(term)) {
+        flag(ModerationReason::Profanity, format!("profanity \"{}\"", term));
+    }
+
+    for kind in scrubbing::contact_kinds(text) {
+        flag(ModerationReason::ContactInfo, kind.label().to_string());
+    }
+    if let Some(term) = CONTACT_TERMS.iter().find(|term| contains_term(term)) {
+        flag(ModerationReason::ContactInfo, format!("off-platform contact \"{}\"", term));
+    }
+
+    if let Some(phrase) = SPAM_PHRASES.iter().find(|phrase| contains_term(phrase)) {
+        flag(ModerationReason::Spam, format!("spam phrase \"{}\"", phrase));
+    }
+    if is_shouting(text) {
+        flag(ModerationReason::Spam, "excessive capitals".to_string());
+    }
+    if has_repeated_chars(text) {
+        flag(ModerationReason::Spam, "repeated characters".to_string());
+    }
+    if let Some(word) = repeated_word(&words) {
+        flag(ModerationReason::Spam, format!("repeated word \"{}\"", word));
+    }
+
+    flags
+}
+
+#[derive(Debug, Deserialize)]
+struct ImageModerationResponse {
+    flagged: bool,
+    #[serde(default)]
+    categories: Vec<String>,
+}
+
+// Images that cannot be screened are held for manual review rather than published
+async fn screen_image(config: &ImageModeration, image_url: &str) -> Vec<ModerationFlag> {
+    let Some(endpoint) = &config.url else {
+        return Vec::new();
+    };
+
+    let image_flag = |detail: String| {
+        vec![ModerationFlag {
+            reason: ModerationReason::Image,
+            field: "proof_image_url".to_string(),
+            detail,
+        }]
+    };
+
+    let client = match reqwest::Client::builder()
+        .timeout(Duration::from_millis(config.timeout_ms))
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => return image_flag(format!("image could not be screened: {}", e)),
+    };
+
+    let mut request = client
+        .post(endpoint)
+        .json(&serde_json::json!({ "image_url": image_url }));
+    if let Some(api_key) = &config.api_key {
+        request = request.bearer_auth(api_key.expose());
+    }
+
+    let result = match request.send().await.and_then(|response| response.error_for_status()) {
+        Ok(response) => response.json::<ImageModerationResponse>().await,
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(result) if result.flagged => {
+            let detail = if result.categories.is_empty() {
+                "image flagged".to_string()
+            } else {
+                format!("image flagged: {}", result.categories.join(", "))
+            };
+            image_flag(detail)
+        }
+        Ok(_) => Vec::new(),
+        Err(e) => {
+            tracing::warn!(error = %e, "image moderation request failed");
+            image_flag("image could not be screened".to_string())
+        }
+    }
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '%')
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+fn is_shouting(text: &str) -> bool {
+    let letters: Vec<char> = text.chars().filter(|c| c.is_alphabetic()).collect();
+    if letters.len() < SPAM_CAPS_MIN_LETTERS {
+        return false;
+    }
+    let upper = letters.iter().filter(|c| c.is_uppercase()).count();
+    upper as f64 / letters.len() as f64 > SPAM_CAPS_RATIO
+}
+
+fn has_repeated_chars(text: &str) -> bool {
+    let mut previous = None;
+    let mut run = 0;
+    for c in text.chars().filter(|c| !c.is_whitespace() && !c.is_ascii_digit()) {
+        run = if Some(c) == previous { run + 1 } else { 1 };
+        if run >= SPAM_REPEATED_CHARS {
+            return true;
+        }
+        previous = Some(c);
+    }
+    false
+}
+
+fn repeated_word(words: &[String]) -> Option<&str> {
+    let mut counts = std::collections::HashMap::new();
+    for word in words.iter().filter(|word| word.len() >= 3) {
+        *counts.entry(word.as_str()).or_insert(0usize) += 1;
+    }
+    counts
+        .into_iter()
+        .filter(|(_, count)| {
+            *count >= SPAM_REPEATED_WORD_COUNT
+                && *count as f64 / words.len() as f64 > SPAM_REPEATED_WORD_SHARE
+        })
+        .max_by_key(|(_, count)| *count)
+        .map(|(word, _)| word)
+}
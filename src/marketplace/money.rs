@@ -0,0 +1,24 @@
+use crate::config::Config;
+use bigdecimal::{BigDecimal, ToPrimitive};
+
+/// ISO 4217 code of listing prices and transaction amounts
+pub fn currency() -> &'static str {
+    &Config::get().currency
+}
+
+/// Decimal places of the currency's minor unit: 2 for USD cents or INR paise, 0 for JPY
+pub fn minor_unit_exponent(currency: &str) -> u32 {
+    match currency {
+        "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF" | "UGX" | "VND" | "VUV"
+        | "XAF" | "XOF" | "XPF" => 0,
+        "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+        _ => 2,
+    }
+}
+
+/// `amount` as a whole number of minor units of the marketplace currency, so clients can
+/// format it without parsing decimals. Amounts are stored as NUMERIC(12, 2) and fit easily.
+pub fn to_minor_units(amount: &BigDecimal) -> i64 {
+    let scale = BigDecimal::from(10_i64.pow(minor_unit_exponent(currency())));
+    (amount * scale).round(0).to_i64().unwrap_or(i64::MAX)
+}
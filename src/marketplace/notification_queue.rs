@@ -0,0 +1,213 @@
+use crate::error::AppError;
+use crate::marketplace::notifications::{self, NotificationService};
+use crate::marketplace::side_effects::{self, SideEffect};
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgExecutor, PgPool, Postgres, Transaction};
+use std::time::Duration;
+use uuid::Uuid;
+
+// Queued notifications fanned out per pass
+const FANOUT_BATCH_SIZE: i64 = 200;
+
+// Entries that keep failing are moved to the dead letters for an admin to replay
+const MAX_FANOUT_ATTEMPTS: i32 = 10;
+
+#[derive(Debug, Clone, FromRow)]
+struct QueuedNotification {
+    id: Uuid,
+    user_id: String,
+    notification_type: String,
+    title: String,
+    message: String,
+    related_listing_id: Option<Uuid>,
+    related_transaction_id: Option<Uuid>,
+    created_at: DateTime<Utc>,
+    attempts: i32,
+}
+
+/// Queue a notification for the fan-out worker, which applies the user's channel settings,
+/// stores it and triggers delivery. Queued inside a transaction, it is only sent if that commits.
+pub async fn enqueue<'e>(
+    executor: impl PgExecutor<'e>,
+    user_id: &str,
+    notification_type: &str,
+    title: &str,
+    message: &str,
+    listing_id: Option<Uuid>,
+    transaction_id: Option<Uuid>,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        INSERT INTO marketplace_notification_queue (
+            id, user_id, notification_type, title, message,
+            related_listing_id, related_transaction_id, created_at
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, CURRENT_TIMESTAMP)
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(notification_type)
+    .bind(title)
+    .bind(message)
+    .bind(listing_id)
+    .bind(transaction_id)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+pub struct NotificationFanout {
+    pool: PgPool,
+}
+
+impl NotificationFanout {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Store the oldest queued notifications, returning how many were stored. An entry that
+    /// fails is kept with its error and retried without holding up the rest, until its last
+    /// try makes it a dead letter.
+    pub async fn fan_out_batch(&self) -> Result<usize, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let queued = sqlx::query_as::<_, QueuedNotification>(
+            r#"
+            SELECT id, user_id, notification_type, title, message,
+                   related_listing_id, related_transaction_id, created_at, attempts
+            FROM marketplace_notification_queue
+            ORDER BY created_at
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+            "#
+        )
+        .bind(FANOUT_BATCH_SIZE)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut stored = 0;
+        for notification in &queued {
+            // A savepoint per entry, so a failed insert leaves the batch usable
+            let mut entry = tx.begin().await?;
+            match self.store(&mut entry, notification).await {
+                Ok(()) => {
+                    sqlx::query("DELETE FROM marketplace_notification_queue WHERE id = $1")
+                        .bind(notification.id)
+                        .execute(&mut *entry)
+                        .await?;
+                    entry.commit().await?;
+                    stored += 1;
+                }
+                Err(e) if notification.attempts + 1 >= MAX_FANOUT_ATTEMPTS => {
+                    entry.rollback().await?;
+                    let effect = SideEffect::Notification {
+                        user_id: notification.user_id.clone(),
+                        notification_type: notification.notification_type.clone(),
+                        title: notification.title.clone(),
+                        message: notification.message.clone(),
+                        listing_id: notification.related_listing_id,
+                        transaction_id: notification.related_transaction_id,
+                    };
+                    side_effects::dead_letter(
+                        &mut *tx,
+                        notification.id,
+                        effect.kind(),
+                        &effect.to_payload()?,
+                        notification.attempts + 1,
+                        &e.to_string(),
+                        notification.created_at,
+                    ).await?;
+                    sqlx::query("DELETE FROM marketplace_notification_queue WHERE id = $1")
+                        .bind(notification.id)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+                Err(e) => {
+                    entry.rollback().await?;
+                    tracing::warn!(queued_id = %notification.id, error = %e, "notification fan-out failed");
+                    sqlx::query(
+                        "UPDATE marketplace_notification_queue SET attempts = attempts + 1, last_error = $2 WHERE id = $1"
+                    )
+                    .bind(notification.id)
+                    .bind(e.to_string())
+                    .execute(&mut *tx)
+                    .await?;
+                }
+            }
+        }
+
+        tx.commit().await?;
+        Ok(stored)
+    }
+
+    // Types the user turned off everywhere are not stored; ones not emailed or pushed
+    // are stored as already delivered
+    async fn store(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        notification: &QueuedNotification,
+    ) -> Result<(), AppError> {
+        let channels = notifications::load_settings(&self.pool, &notification.user_id)
+            .await?
+            .channels(&notification.notification_type);
+        if !(channels.email || channels.push || channels.in_app) {
+            return Ok(());
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_notifications (
+                id, user_id, notification_type, title, message,
+                related_listing_id, related_transaction_id, in_app, delivered_at, created_at
+            ) VALUES (
+                $1, $2, $3, $4, $5, $6, $7, $8,
+                CASE WHEN $9 THEN NULL ELSE CURRENT_TIMESTAMP END,
+                $10
+            )
+            ON CONFLICT (id) DO NOTHING
+            "#
+        )
+        .bind(notification.id)
+        .bind(&notification.user_id)
+        .bind(&notification.notification_type)
+        .bind(&notification.title)
+        .bind(&notification.message)
+        .bind(notification.related_listing_id)
+        .bind(notification.related_transaction_id)
+        .bind(channels.in_app)
+        .bind(channels.email || channels.push)
+        .bind(notification.created_at)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Fan out queued notifications on a fixed interval and send the immediate ones right
+/// away; call once at startup. Digests are still sent by the delivery job.
+pub fn spawn_notification_fanout(pool: PgPool, interval: Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let fanout = NotificationFanout::new(pool.clone());
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+
+            match fanout.fan_out_batch().await {
+                Ok(0) => {}
+                Ok(_) => {
+                    let delivered = match NotificationService::new(pool.clone()) {
+                        Ok(service) => service.deliver_immediate().await,
+                        Err(e) => Err(e),
+                    };
+                    if let Err(e) = delivered {
+                        tracing::warn!(error = %e, "immediate notification delivery failed");
+                    }
+                }
+                Err(e) => tracing::warn!(error = %e, "notification fan-out failed"),
+            }
+        }
+    })
+}
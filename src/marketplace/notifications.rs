@@ -0,0 +1,545 @@
+use crate::config::{Config, NotificationDelivery};
+use crate::error::AppError;
+use crate::validation::FieldError;
+use crate::marketplace::jobs::Job;
+use crate::marketplace::preferences::load_preferences;
+use crate::models::marketplace::{
+    MarketplaceNotification, NotificationChannels, NotificationDigest, NotificationDigestMode, NotificationSettings,
+    DEFAULT_CODE_EXPIRY_REMINDER_DAYS,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+const DELIVERY_BATCH_SIZE: i64 = 200;
+
+const DIGEST_BATCH_SIZE: i64 = 100;
+
+// Groups listed in a digest before the rest are only counted
+const MAX_DIGEST_GROUPS: usize = 20;
+
+// Users on a digest mode whose current period is over
+const DIGEST_DUE: &str = r#"
+    s.digest_mode <> 'immediate'
+    AND s.last_digest_at <= CURRENT_TIMESTAMP - CASE s.digest_mode WHEN 'hourly' THEN INTERVAL '1 hour' ELSE INTERVAL '1 day' END
+"#;
+
+// Users whose local time is within their quiet hours, which may run past midnight.
+// False for users without settings or quiet hours.
+const IN_QUIET_HOURS: &str = r#"
+    COALESCE(
+        CASE WHEN s.quiet_hours_start < s.quiet_hours_end
+            THEN (CURRENT_TIMESTAMP AT TIME ZONE s.timezone)::time >= s.quiet_hours_start
+                AND (CURRENT_TIMESTAMP AT TIME ZONE s.timezone)::time < s.quiet_hours_end
+            ELSE (CURRENT_TIMESTAMP AT TIME ZONE s.timezone)::time >= s.quiet_hours_start
+                OR (CURRENT_TIMESTAMP AT TIME ZONE s.timezone)::time < s.quiet_hours_end
+        END,
+        FALSE
+    )
+"#;
+
+/// Email or push message sent for one notification or a digest of them
+#[derive(Debug, Clone, Serialize)]
+pub struct OutgoingMessage {
+    pub user_id: String,
+    pub channels: Vec<&'static str>, // email and/or push
+    pub subject: String,
+    pub body: String,
+    pub locale: String,   // from the user's profile; the messaging service picks its template by it
+    pub timezone: String, // IANA zone dates in the template are shown in
+}
+
+#[async_trait]
+pub trait NotificationSender: Send + Sync {
+    async fn send(&self, message: &OutgoingMessage) -> Result<(), AppError>;
+}
+
+/// Logs messages instead of sending them; the default when no delivery service is configured
+pub struct LogNotificationSender;
+
+#[async_trait]
+impl NotificationSender for LogNotificationSender {
+    async fn send(&self, message: &OutgoingMessage) -> Result<(), AppError> {
+        tracing::info!(
+            channels = %message.channels.join("+"),
+            user_id = %message.user_id,
+            subject = %message.subject,
+            "notification logged, not sent",
+        );
+        Ok(())
+    }
+}
+
+/// Posts messages to the messaging service, which looks up the user's address and devices
+pub struct HttpNotificationSender {
+    client: reqwest::Client,
+    url: String,
+    api_key: Option<String>,
+}
+
+impl HttpNotificationSender {
+    pub fn new(config: &NotificationDelivery) -> Result<Self, AppError> {
+        let url = config
+            .url
+            .clone()
+            .ok_or_else(|| AppError::InternalError("NOTIFICATION_DELIVERY_URL is not set".to_string()))?;
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .build()
+            .map_err(|e| AppError::InternalError(format!("Notification delivery client error: {}", e)))?;
+
+        Ok(Self {
+            client,
+            url,
+            api_key: config.api_key.as_ref().map(|key| key.expose().to_string()),
+        })
+    }
+}
+
+#[async_trait]
+impl NotificationSender for HttpNotificationSender {
+    async fn send(&self, message: &OutgoingMessage) -> Result<(), AppError> {
+        let mut request = self.client.post(&self.url).json(message);
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| AppError::InternalError(format!("Notification delivery error: {}", e)))?;
+        Ok(())
+    }
+}
+
+pub fn notification_sender_from_config(config: &Config) -> Result<Arc<dyn NotificationSender>, AppError> {
+    match config.notification_delivery.url {
+        Some(_) => Ok(Arc::new(HttpNotificationSender::new(&config.notification_delivery)?)),
+        None => Ok(Arc::new(LogNotificationSender)),
+    }
+}
+
+/// Emails and pushes in-app notifications. Users in immediate mode get one message per
+/// notification; hourly and daily users get a single summary of everything since their
+/// last one. A notification is sent at most once, tracked by `delivered_at`, on the
+/// channels its type is set to. Nothing goes out during a user's quiet hours; it is held
+/// until they end.
+pub struct NotificationService {
+    pool: PgPool,
+    sender: Arc<dyn NotificationSender>,
+}
+
+impl NotificationService {
+    pub fn new(pool: PgPool) -> Result<Self, AppError> {
+        Ok(Self {
+            pool,
+            sender: notification_sender_from_config(Config::get())?,
+        })
+    }
+
+    pub fn with_sender(pool: PgPool, sender: Arc<dyn NotificationSender>) -> Self {
+        Self { pool, sender }
+    }
+
+    pub async fn get_settings(&self, user_id: &str) -> Result<NotificationSettings, AppError> {
+        load_settings(&self.pool, user_id).await
+    }
+
+    /// Save the user's settings. Changing digest mode starts a new digest period.
+    pub async fn update_settings(
+        &self,
+        user_id: &str,
+        settings: &NotificationSettings,
+    ) -> Result<NotificationSettings, AppError> {
+        // Quiet hours are evaluated by Postgres, so its zone names are the ones accepted
+        let known_zone = sqlx::query("SELECT 1 FROM pg_timezone_names WHERE name = $1")
+            .bind(&settings.timezone)
+            .fetch_optional(&self.pool)
+            .await?;
+        if known_zone.is_none() {
+            return Err(AppError::ValidationFailed(vec![FieldError {
+                field: "timezone".to_string(),
+                message: "must be an IANA time zone such as Europe/Berlin".to_string(),
+            }]));
+        }
+
+        let settings = sqlx::query_as::<_, NotificationSettings>(
+            r#"
+            INSERT INTO marketplace_notification_settings (
+                user_id, email_notifications, push_notifications, new_listing_alerts, price_drop_alerts,
+                transaction_updates, review_notifications, digest_mode, channel_preferences,
+                quiet_hours_start, quiet_hours_end, timezone, code_expiry_reminder_days, last_digest_at, updated_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+            ON CONFLICT (user_id) DO UPDATE SET
+                email_notifications = EXCLUDED.email_notifications,
+                push_notifications = EXCLUDED.push_notifications,
+                new_listing_alerts = EXCLUDED.new_listing_alerts,
+                price_drop_alerts = EXCLUDED.price_drop_alerts,
+                transaction_updates = EXCLUDED.transaction_updates,
+                review_notifications = EXCLUDED.review_notifications,
+                digest_mode = EXCLUDED.digest_mode,
+                channel_preferences = EXCLUDED.channel_preferences,
+                quiet_hours_start = EXCLUDED.quiet_hours_start,
+                quiet_hours_end = EXCLUDED.quiet_hours_end,
+                timezone = EXCLUDED.timezone,
+                code_expiry_reminder_days = EXCLUDED.code_expiry_reminder_days,
+                last_digest_at = CASE
+                    WHEN marketplace_notification_settings.digest_mode = EXCLUDED.digest_mode
+                    THEN marketplace_notification_settings.last_digest_at
+                    ELSE CURRENT_TIMESTAMP
+                END,
+                updated_at = CURRENT_TIMESTAMP
+            RETURNING *
+            "#
+        )
+        .bind(user_id)
+        .bind(settings.email_notifications)
+        .bind(settings.push_notifications)
+        .bind(settings.new_listing_alerts)
+        .bind(settings.price_drop_alerts)
+        .bind(settings.transaction_updates)
+        .bind(settings.review_notifications)
+        .bind(settings.digest_mode)
+        .bind(&settings.channel_preferences)
+        .bind(settings.quiet_hours_start)
+        .bind(settings.quiet_hours_end)
+        .bind(&settings.timezone)
+        .bind(settings.code_expiry_reminder_days)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(settings)
+    }
+
+    /// Send undelivered notifications of users in immediate mode and outside quiet hours,
+    /// one message each. Returns how many were delivered.
+    pub async fn deliver_immediate(&self) -> Result<u64, AppError> {
+        let notifications = sqlx::query_as::<_, MarketplaceNotification>(&format!(
+            r#"
+            SELECT n.* FROM marketplace_notifications n
+            LEFT JOIN marketplace_notification_settings s ON s.user_id = n.user_id
+            WHERE n.delivered_at IS NULL
+              AND COALESCE(s.digest_mode, 'immediate') = 'immediate'
+              AND NOT {}
+            ORDER BY n.created_at
+            LIMIT $1
+            "#,
+            IN_QUIET_HOURS
+        ))
+        .bind(DELIVERY_BATCH_SIZE)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut delivered = 0;
+        for notification in notifications {
+            match self.deliver_one(&notification).await {
+                Ok(true) => delivered += 1,
+                Ok(false) => {}
+                Err(e) => tracing::warn!(notification_id = %notification.id, error = %e, "notification delivery failed"),
+            }
+        }
+        Ok(delivered)
+    }
+
+    /// Send one summary to each hourly or daily user whose period is over and who has
+    /// undelivered notifications, unless it is their quiet hours. Returns how many digests
+    /// were sent.
+    pub async fn send_digests(&self) -> Result<u64, AppError> {
+        let user_ids = sqlx::query_scalar::<_, String>(&format!(
+            r#"
+            SELECT user_id FROM marketplace_notification_settings s
+            WHERE {}
+              AND NOT {}
+              AND EXISTS (
+                  SELECT 1 FROM marketplace_notifications n
+                  WHERE n.user_id = s.user_id AND n.delivered_at IS NULL
+              )
+            ORDER BY s.last_digest_at
+            LIMIT $1
+            "#,
+            DIGEST_DUE, IN_QUIET_HOURS
+        ))
+        .bind(DIGEST_BATCH_SIZE)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut sent = 0;
+        for user_id in user_ids {
+            match self.send_digest(&user_id).await {
+                Ok(Some(_)) => sent += 1,
+                Ok(None) => {}
+                Err(e) => tracing::warn!(user_id = %user_id, error = %e, "notification digest failed"),
+            }
+        }
+        Ok(sent)
+    }
+
+    // Marked delivered before sending so a concurrent run skips it; a failed send rolls back
+    async fn deliver_one(&self, notification: &MarketplaceNotification) -> Result<bool, AppError> {
+        let settings = self.get_settings(&notification.user_id).await?;
+        let preferences = load_preferences(&self.pool, &notification.user_id).await?;
+        let mut tx = self.pool.begin().await?;
+
+        let claimed = sqlx::query(
+            r#"
+            UPDATE marketplace_notifications SET delivered_at = CURRENT_TIMESTAMP
+            WHERE id = $1 AND delivered_at IS NULL
+            "#
+        )
+        .bind(notification.id)
+        .execute(&mut *tx)
+        .await?;
+        if claimed.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        let enabled = outgoing_channels(settings.channels(&notification.notification_type));
+        if !enabled.is_empty() {
+            self.sender
+                .send(&OutgoingMessage {
+                    user_id: notification.user_id.clone(),
+                    channels: enabled,
+                    subject: notification.title.clone(),
+                    body: notification.message.clone(),
+                    locale: preferences.locale,
+                    timezone: settings.timezone,
+                })
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    async fn send_digest(&self, user_id: &str) -> Result<Option<NotificationDigest>, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        // Skip users another run is already summarizing
+        let due = sqlx::query_as::<_, DigestSettings>(&format!(
+            "SELECT * FROM marketplace_notification_settings s WHERE user_id = $1 AND {} AND NOT {} FOR UPDATE SKIP LOCKED",
+            DIGEST_DUE, IN_QUIET_HOURS
+        ))
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(due) = due else {
+            return Ok(None);
+        };
+        let preferences = load_preferences(&self.pool, user_id).await?;
+
+        let notifications = sqlx::query_as::<_, MarketplaceNotification>(
+            r#"
+            SELECT * FROM marketplace_notifications
+            WHERE user_id = $1 AND delivered_at IS NULL
+            ORDER BY created_at
+            FOR UPDATE
+            "#
+        )
+        .bind(user_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        if notifications.is_empty() {
+            return Ok(None);
+        }
+
+        let digest = sqlx::query_as::<_, NotificationDigest>(
+            r#"
+            INSERT INTO marketplace_notification_digests (id, user_id, digest_mode, notification_count, period_start, sent_at)
+            VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP)
+            RETURNING *
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(due.settings.digest_mode)
+        .bind(notifications.len() as i32)
+        .bind(due.last_digest_at)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let ids: Vec<Uuid> = notifications.iter().map(|notification| notification.id).collect();
+        sqlx::query(
+            "UPDATE marketplace_notifications SET delivered_at = CURRENT_TIMESTAMP, digest_id = $2 WHERE id = ANY($1)"
+        )
+        .bind(&ids)
+        .bind(digest.id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE marketplace_notification_settings SET last_digest_at = CURRENT_TIMESTAMP WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        // Each channel summarizes the notifications whose type is sent on it
+        for channel in ["email", "push"] {
+            let included: Vec<&MarketplaceNotification> = notifications
+                .iter()
+                .filter(|notification| {
+                    outgoing_channels(due.settings.channels(&notification.notification_type)).contains(&channel)
+                })
+                .collect();
+            if !included.is_empty() {
+                self.sender
+                    .send(&summarize(user_id, channel, &due.settings, &preferences.locale, &included))
+                    .await?;
+            }
+        }
+
+        tx.commit().await?;
+        Ok(Some(digest))
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct DigestSettings {
+    #[sqlx(flatten)]
+    settings: NotificationSettings,
+    last_digest_at: DateTime<Utc>,
+}
+
+/// The user's settings, or the defaults when they never saved any
+pub(crate) async fn load_settings(pool: &PgPool, user_id: &str) -> Result<NotificationSettings, AppError> {
+    let settings = sqlx::query_as::<_, NotificationSettings>(
+        "SELECT * FROM marketplace_notification_settings WHERE user_id = $1"
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(settings.unwrap_or_else(default_settings))
+}
+
+fn default_settings() -> NotificationSettings {
+    NotificationSettings {
+        email_notifications: true,
+        push_notifications: false,
+        new_listing_alerts: true,
+        price_drop_alerts: true,
+        transaction_updates: true,
+        review_notifications: true,
+        digest_mode: NotificationDigestMode::Immediate,
+        channel_preferences: Default::default(),
+        quiet_hours_start: None,
+        quiet_hours_end: None,
+        timezone: "UTC".to_string(),
+        code_expiry_reminder_days: DEFAULT_CODE_EXPIRY_REMINDER_DAYS,
+    }
+}
+
+// In-app notifications are the stored rows themselves; only email and push are sent
+fn outgoing_channels(channels: NotificationChannels) -> Vec<&'static str> {
+    let mut outgoing = Vec::new();
+    if channels.email {
+        outgoing.push("email");
+    }
+    if channels.push {
+        outgoing.push("push");
+    }
+    outgoing
+}
+
+// Notifications with the same title are grouped, most frequent first, showing the latest message
+fn summarize(
+    user_id: &str,
+    channel: &'static str,
+    settings: &NotificationSettings,
+    locale: &str,
+    notifications: &[&MarketplaceNotification],
+) -> OutgoingMessage {
+    let text = DigestText::for_locale(locale);
+    let mut groups: Vec<(&str, usize, &str)> = Vec::new();
+    for notification in notifications {
+        match groups.iter_mut().find(|(title, _, _)| *title == notification.title) {
+            Some(group) => {
+                group.1 += 1;
+                group.2 = &notification.message;
+            }
+            None => groups.push((&notification.title, 1, &notification.message)),
+        }
+    }
+    groups.sort_by(|a, b| b.1.cmp(&a.1));
+
+    let mut lines: Vec<String> = groups
+        .iter()
+        .take(MAX_DIGEST_GROUPS)
+        .map(|(title, count, latest)| match count {
+            1 => format!("{}: {}", title, latest),
+            _ => format!("{} ({}), {}: {}", title, count, text.latest, latest),
+        })
+        .collect();
+    if groups.len() > MAX_DIGEST_GROUPS {
+        let rest: usize = groups[MAX_DIGEST_GROUPS..].iter().map(|(_, count, _)| count).sum();
+        lines.push(text.and_more.replace("{}", &rest.to_string()));
+    }
+
+    let summary = match settings.digest_mode {
+        NotificationDigestMode::Hourly => text.hourly,
+        _ => text.daily,
+    };
+    OutgoingMessage {
+        user_id: user_id.to_string(),
+        channels: vec![channel],
+        subject: format!("{}: {} {}", summary, notifications.len(), text.new_notifications),
+        body: lines.join("\n"),
+        locale: locale.to_string(),
+        timezone: settings.timezone.clone(),
+    }
+}
+
+// Wording of the digest, which is put together here rather than by the messaging service
+struct DigestText {
+    hourly: &'static str,
+    daily: &'static str,
+    new_notifications: &'static str,
+    latest: &'static str,
+    and_more: &'static str, // {} is the number left out
+}
+
+impl DigestText {
+    fn for_locale(locale: &str) -> Self {
+        match locale {
+            "hi" => DigestText {
+                hourly: "आपका प्रति घंटा सारांश",
+                daily: "आपका दैनिक सारांश",
+                new_notifications: "नई सूचनाएं",
+                latest: "नवीनतम",
+                and_more: "...और {} अन्य",
+            },
+            "es" => DigestText {
+                hourly: "Tu resumen de la última hora",
+                daily: "Tu resumen diario",
+                new_notifications: "notificaciones nuevas",
+                latest: "la más reciente",
+                and_more: "...y {} más",
+            },
+            _ => DigestText {
+                hourly: "Your hourly summary",
+                daily: "Your daily summary",
+                new_notifications: "new notifications",
+                latest: "latest",
+                and_more: "...and {} more",
+            },
+        }
+    }
+}
+
+pub struct NotificationDeliveryJob;
+
+#[async_trait]
+impl Job for NotificationDeliveryJob {
+    fn name(&self) -> &'static str {
+        "notification_delivery"
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<(), AppError> {
+        let service = NotificationService::new(pool.clone())?;
+        service.deliver_immediate().await?;
+        service.send_digests().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,283 @@
+use crate::error::ErrorBody;
+use crate::marketplace::routes;
+use crate::validation::FieldError;
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+/// OpenAPI document generated from the handler and model annotations.
+/// Every handler wired in `routes.rs` must be listed in `paths` here.
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Dealmate Marketplace API", description = "Buy and sell coupons, gift cards and other deals"),
+    paths(
+        // Public
+        routes::get_listings,
+        routes::get_listing,
+        routes::get_price_history,
+        routes::stream_listing_events,
+        routes::get_listing_history,
+        routes::revert_listing,
+        routes::get_listing_translations,
+        routes::upsert_listing_translation,
+        routes::delete_listing_translation,
+        routes::get_listing_faq,
+        routes::replace_listing_faq,
+        routes::get_categories,
+        routes::get_category_stats,
+        routes::get_brands,
+        routes::get_brand,
+        routes::get_brand_listings,
+        routes::get_user_profile,
+        routes::get_trust_history,
+        routes::get_popular_tags,
+        routes::get_search_suggestions,
+        routes::get_bundles,
+        routes::get_bundle,
+        routes::get_storefront_page,
+        routes::get_sitemap_index,
+        routes::get_sitemap,
+        routes::get_merchant_feed_xml,
+        routes::get_merchant_feed_csv,
+        routes::handle_payment_webhook,
+        routes::handle_paypal_webhook,
+        // Listings
+        routes::create_listing,
+        routes::create_listings_bulk,
+        routes::get_pricing_suggestion,
+        routes::update_listing,
+        routes::delete_listing,
+        routes::submit_for_verification,
+        routes::issue_coupon_reveal_token,
+        routes::get_coupon_code,
+        routes::report_expired_code,
+        routes::appeal_expired_takedown,
+        routes::add_favorite,
+        routes::remove_favorite,
+        // Transactions
+        routes::create_transaction,
+        routes::get_user_transactions,
+        routes::get_transaction,
+        routes::complete_transaction,
+        routes::cancel_transaction,
+        routes::dispute_transaction,
+        routes::upload_dispute_evidence,
+        routes::get_dispute_evidence,
+        routes::download_dispute_evidence,
+        routes::refund_transaction,
+        // Purchase history
+        routes::get_purchases,
+        routes::export_purchases_csv,
+        routes::get_purchase_code,
+        routes::get_transaction_refunds,
+        // Bundles
+        routes::create_bundle,
+        routes::deactivate_bundle,
+        routes::purchase_bundle,
+        // Storefronts
+        routes::get_my_storefront,
+        routes::update_storefront,
+        // Promotions
+        routes::create_promotion,
+        routes::get_my_promotions,
+        routes::cancel_promotion,
+        routes::get_credit_balance,
+        // Loyalty
+        routes::get_loyalty_points,
+        // Wallet
+        routes::get_wallet,
+        routes::create_wallet_topup,
+        routes::withdraw_from_wallet,
+        // UPI
+        routes::pay_checkout_by_upi,
+        routes::pay_wallet_topup_by_upi,
+        routes::get_upi_payment,
+        // PayPal
+        routes::pay_checkout_by_paypal,
+        routes::capture_paypal_order,
+        // Cart
+        routes::get_cart,
+        routes::add_cart_item,
+        routes::update_cart_item,
+        routes::remove_cart_item,
+        routes::clear_cart,
+        routes::checkout_cart,
+        routes::get_promo_quote,
+        routes::get_checkout,
+        routes::open_protection_claim,
+        routes::get_protection_claim,
+        routes::respond_to_protection_claim,
+        routes::get_high_value_check,
+        routes::send_high_value_confirmation_code,
+        routes::confirm_high_value_purchase,
+        // Reviews
+        routes::create_review,
+        routes::get_user_reviews,
+        routes::get_listing_reviews,
+        // Payment methods
+        routes::add_payment_method,
+        routes::get_payment_methods,
+        routes::delete_payment_method,
+        routes::set_default_payment_method,
+        routes::pay_checkout_with_payment_method,
+        routes::get_payout_account,
+        routes::create_payout_onboarding_link,
+        // Notifications
+        routes::get_notifications,
+        routes::mark_notification_read,
+        routes::get_notification_settings,
+        routes::update_notification_settings,
+        routes::get_profile_preferences,
+        routes::update_profile_preferences,
+        // Seller verification
+        routes::submit_seller_verification,
+        routes::get_seller_verification,
+        routes::get_seller_verification_queue,
+        routes::review_seller_verification,
+        routes::claim_seller_verification,
+        routes::get_verifier_metrics,
+        routes::get_coupon_reveals,
+        routes::get_moderation_queue,
+        routes::review_moderation_case,
+        routes::get_contact_offenders,
+        routes::get_shadow_bans,
+        routes::create_shadow_ban,
+        routes::lift_shadow_ban,
+        routes::get_my_feature_flags,
+        routes::get_feature_flags,
+        routes::update_feature_flag,
+        routes::start_impersonation,
+        routes::revoke_impersonation,
+        routes::get_impersonation_audit,
+        routes::get_retention_status,
+        routes::get_event_schemas,
+        routes::send_test_event,
+        routes::bulk_suspend_seller_listings,
+        routes::bulk_dismiss_expired_reports,
+        routes::bulk_reverify_listings,
+        routes::get_partner_listing,
+        routes::get_partner_keys,
+        routes::create_partner_key,
+        routes::revoke_partner_key,
+        routes::get_partner_key_usage,
+        routes::create_partner,
+        routes::get_partners,
+        routes::set_partner_key_limits,
+        routes::get_high_value_review_queue,
+        routes::review_high_value_transaction,
+        routes::get_archived_listings,
+        routes::get_archived_listing,
+        // Admin
+        routes::create_brand,
+        routes::get_admin_listings,
+        routes::confirm_payment,
+        routes::get_transaction_timeline,
+        routes::confirm_checkout_payment,
+        routes::confirm_promotion_payment,
+        routes::confirm_wallet_topup,
+        routes::grant_credits,
+        routes::set_seller_quota,
+        routes::remove_seller_quota,
+        routes::create_promo_campaign,
+        routes::get_promo_campaigns,
+        routes::deactivate_promo_campaign,
+        routes::get_protection_claim_queue,
+        routes::get_open_disputes,
+        routes::resolve_protection_claim,
+        routes::get_analytics_kpis,
+        routes::get_analytics_gmv,
+        routes::get_analytics_categories,
+        routes::get_analytics_cohorts,
+        routes::get_account_restrictions,
+        routes::lift_account_restriction,
+        routes::get_payment_reversals,
+        routes::get_dead_letters,
+        routes::replay_dead_letter,
+        routes::get_policy_rules,
+        routes::create_policy_rule,
+        routes::update_policy_rule,
+        routes::delete_policy_rule,
+        // Recommendations and follows
+        routes::get_feed,
+        routes::get_following_feed,
+        routes::follow_seller,
+        routes::unfollow_seller,
+        // Dashboard
+        routes::get_dashboard,
+        routes::get_commission_history,
+        routes::get_my_listings,
+        routes::get_my_limits,
+        // Data export
+        routes::request_data_export,
+        routes::get_data_export,
+        routes::download_data_export,
+        // Account deletion
+        routes::request_account_deletion,
+        routes::get_account_deletion,
+        routes::get_away_status,
+        routes::start_vacation,
+        routes::deactivate_account,
+        routes::reactivate_account,
+        routes::get_auto_reply,
+        routes::set_auto_reply,
+        routes::delete_auto_reply,
+        routes::send_step_up_code,
+        routes::verify_step_up,
+        routes::get_sessions,
+        routes::revoke_other_sessions,
+        routes::revoke_session,
+    ),
+    components(schemas(ErrorBody, FieldError)),
+    modifiers(&BearerAuth),
+    tags(
+        (name = "listings", description = "Listing management"),
+        (name = "categories", description = "Category taxonomy"),
+        (name = "brands", description = "Brand registry"),
+        (name = "profiles", description = "Public seller profiles"),
+        (name = "favorites", description = "Saved listings and price-drop alerts"),
+        (name = "transactions", description = "Purchases and escrow"),
+        (name = "cart", description = "Shopping cart and bundled checkout"),
+        (name = "bundles", description = "Discounted bundles of listings"),
+        (name = "storefronts", description = "Seller storefront pages"),
+        (name = "seo", description = "Sitemaps and merchant product feeds of active listings"),
+        (name = "promotions", description = "Paid featured placement and loyalty credits"),
+        (name = "loyalty", description = "Points earned on purchases and redeemed at checkout"),
+        (name = "wallet", description = "Wallet balance, top-ups, withdrawals and paying by wallet"),
+        (name = "upi", description = "UPI intent and collect payments for checkouts and wallet top-ups"),
+        (name = "paypal", description = "PayPal checkout: orders approved on PayPal and captured on return"),
+        (name = "reviews", description = "Transaction reviews"),
+        (name = "payment-methods", description = "Saved payment methods"),
+        (name = "payouts", description = "Seller payout accounts and onboarding"),
+        (name = "notifications", description = "User notifications"),
+        (name = "seller-verification", description = "Seller identity verification"),
+        (name = "admin", description = "Admin-only endpoints"),
+        (name = "partner", description = "API keys for aggregator partners and the read-only partner API"),
+        (name = "feed", description = "Personalized recommendations"),
+        (name = "follows", description = "Following sellers"),
+        (name = "dashboard", description = "User dashboard"),
+        (name = "data-export", description = "Personal data export"),
+        (name = "purchase-protection", description = "Money-back guarantee claims"),
+        (name = "account", description = "Account deletion, deactivation, vacation mode and the auto-reply to buyers"),
+        (name = "step-up", description = "One-time codes that unlock sensitive actions"),
+        (name = "sessions", description = "Signed-in devices and signing them out"),
+        (name = "purchases", description = "Buyer purchase history, code re-reveals and CSV export"),
+    )
+)]
+pub struct ApiDoc;
+
+// Registers the Auth0 bearer token scheme referenced by authenticated paths
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+    }
+}
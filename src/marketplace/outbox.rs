@@ -0,0 +1,236 @@
+use crate::config::Config;
+use crate::error::AppError;
+use crate::marketplace::event_schemas;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, PgPool, Postgres, Transaction};
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+// Event types published to the broker as `marketplace.<event_type>`
+pub mod event_types {
+    pub const LISTING_CREATED: &str = "listing.created";
+    pub const LISTING_UPDATED: &str = "listing.updated";
+    pub const LISTING_DELETED: &str = "listing.deleted";
+    pub const TRANSACTION_CREATED: &str = "transaction.created";
+    pub const TRANSACTION_PAID: &str = "transaction.payment_confirmed";
+    pub const TRANSACTION_COMPLETED: &str = "transaction.completed";
+    pub const TRANSACTION_CANCELLED: &str = "transaction.cancelled";
+    pub const TRANSACTION_DISPUTED: &str = "transaction.disputed";
+    pub const TRANSACTION_REFUNDED: &str = "transaction.refunded";
+    pub const TRANSACTION_CHARGED_BACK: &str = "transaction.charged_back";
+    pub const REVIEW_CREATED: &str = "review.created";
+}
+
+// Events published per relay pass
+const RELAY_BATCH_SIZE: i64 = 100;
+
+// Events that keep failing are left for manual inspection, and hold back every later
+// event of their aggregate until someone does
+const MAX_PUBLISH_ATTEMPTS: i32 = 10;
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct OutboxEvent {
+    pub id: Uuid,
+    pub aggregate_type: String,
+    pub aggregate_id: Uuid,
+    pub event_type: String,
+    #[sqlx(skip)]
+    pub schema: Option<String>, // Versioned event type, e.g. `transaction.completed.v1`; set when published
+    pub payload: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Destination for outbox events (Kafka, NATS, RabbitMQ, ...)
+#[async_trait]
+pub trait EventPublisher: Send + Sync {
+    async fn publish(&self, subject: &str, event: &OutboxEvent) -> Result<(), AppError>;
+}
+
+/// Logs events; the default when no broker is configured
+pub struct LogPublisher;
+
+#[async_trait]
+impl EventPublisher for LogPublisher {
+    async fn publish(&self, subject: &str, event: &OutboxEvent) -> Result<(), AppError> {
+        tracing::info!(subject, aggregate_id = %event.aggregate_id, event_id = %event.id, "outbox event");
+        Ok(())
+    }
+}
+
+pub struct NatsPublisher {
+    client: async_nats::Client,
+}
+
+impl NatsPublisher {
+    pub async fn connect(url: &str) -> Result<Self, AppError> {
+        let client = async_nats::connect(url)
+            .await
+            .map_err(|e| AppError::InternalError(format!("NATS connection error: {}", e)))?;
+        Ok(Self { client })
+    }
+}
+
+#[async_trait]
+impl EventPublisher for NatsPublisher {
+    async fn publish(&self, subject: &str, event: &OutboxEvent) -> Result<(), AppError> {
+        let body = serde_json::to_vec(event)
+            .map_err(|e| AppError::InternalError(format!("Serialization error: {}", e)))?;
+
+        self.client
+            .publish(subject.to_string(), body.into())
+            .await
+            .map_err(|e| AppError::InternalError(format!("NATS publish error: {}", e)))?;
+        self.client
+            .flush()
+            .await
+            .map_err(|e| AppError::InternalError(format!("NATS flush error: {}", e)))?;
+
+        Ok(())
+    }
+}
+
+/// Build the publisher selected by `EVENT_BROKER`
+pub async fn publisher_from_config(config: &Config) -> Result<Arc<dyn EventPublisher>, AppError> {
+    match config.event_broker.as_str() {
+        "nats" => {
+            let url = config.nats_url.as_deref().unwrap_or("nats://localhost:4222");
+            Ok(Arc::new(NatsPublisher::connect(url).await?))
+        }
+        "log" => Ok(Arc::new(LogPublisher)),
+        other => Err(AppError::InternalError(format!("Unsupported event broker: {}", other))),
+    }
+}
+
+pub struct OutboxService {
+    pool: PgPool,
+}
+
+impl OutboxService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Record an event inside the caller's transaction so it commits with the change
+    pub async fn record<T: Serialize>(
+        tx: &mut Transaction<'_, Postgres>,
+        aggregate_type: &str,
+        aggregate_id: Uuid,
+        event_type: &str,
+        payload: &T,
+    ) -> Result<(), AppError> {
+        let payload = serde_json::to_value(payload)
+            .map_err(|e| AppError::InternalError(format!("Serialization error: {}", e)))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_outbox (id, aggregate_type, aggregate_id, event_type, payload, created_at)
+            VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP)
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(aggregate_type)
+        .bind(aggregate_id)
+        .bind(event_type)
+        .bind(payload)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Publish the oldest pending events, returning how many were published.
+    /// Stops at the first failure, and skips aggregates with an event that ran out of
+    /// attempts, so consumers see each aggregate's events in order.
+    pub async fn relay_batch(&self, publisher: &dyn EventPublisher) -> Result<usize, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let mut events = sqlx::query_as::<_, OutboxEvent>(
+            r#"
+            SELECT o.id, o.aggregate_type, o.aggregate_id, o.event_type, o.payload, o.created_at
+            FROM marketplace_outbox o
+            WHERE o.published_at IS NULL AND o.attempts < $1
+            AND NOT EXISTS (
+                SELECT 1 FROM marketplace_outbox stuck
+                WHERE stuck.aggregate_type = o.aggregate_type
+                AND stuck.aggregate_id = o.aggregate_id
+                AND stuck.published_at IS NULL
+                AND stuck.attempts >= $1
+            )
+            ORDER BY o.created_at
+            LIMIT $2
+            FOR UPDATE SKIP LOCKED
+            "#
+        )
+        .bind(MAX_PUBLISH_ATTEMPTS)
+        .bind(RELAY_BATCH_SIZE)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut published = 0;
+        for event in &mut events {
+            let subject = format!("marketplace.{}", event.event_type);
+
+            // Payload shapes are checked against the registry by its tests, not per event
+            match event_schemas::schema(&event.event_type) {
+                Some(schema) => event.schema = Some(schema.versioned_type()),
+                None => tracing::warn!(event_id = %event.id, event_type = %event.event_type, "outbox event has no registered schema"),
+            }
+
+            match publisher.publish(&subject, &*event).await {
+                Ok(()) => {
+                    sqlx::query("UPDATE marketplace_outbox SET published_at = CURRENT_TIMESTAMP WHERE id = $1")
+                        .bind(event.id)
+                        .execute(&mut *tx)
+                        .await?;
+                    published += 1;
+                }
+                Err(e) => {
+                    let attempts: i32 = sqlx::query_scalar(
+                        "UPDATE marketplace_outbox SET attempts = attempts + 1, last_error = $2 WHERE id = $1 RETURNING attempts"
+                    )
+                    .bind(event.id)
+                    .bind(e.to_string())
+                    .fetch_one(&mut *tx)
+                    .await?;
+
+                    if attempts >= MAX_PUBLISH_ATTEMPTS {
+                        tracing::error!(
+                            event_id = %event.id,
+                            aggregate_type = %event.aggregate_type,
+                            aggregate_id = %event.aggregate_id,
+                            error = %e,
+                            "outbox event gave up; later events of its aggregate are held back",
+                        );
+                    }
+                    break;
+                }
+            }
+        }
+
+        tx.commit().await?;
+        Ok(published)
+    }
+}
+
+/// Relay pending outbox events to the broker on a fixed interval
+pub fn spawn_outbox_relay(
+    pool: PgPool,
+    publisher: Arc<dyn EventPublisher>,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let service = OutboxService::new(pool);
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+
+            if let Err(e) = service.relay_batch(publisher.as_ref()).await {
+                tracing::error!(error = %e, "outbox relay failed");
+            }
+        }
+    })
+}
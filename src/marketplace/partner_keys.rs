@@ -0,0 +1,348 @@
+use crate::auth::AuthUser;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{
+    CreatePartnerAccountRequest, CreatePartnerApiKeyRequest, PartnerAccount, PartnerApiKey, PartnerApiKeyGrant,
+    PartnerKeyUsage, UpdatePartnerKeyLimitsRequest,
+};
+use axum::{
+    extract::{Request, State},
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{Duration, Timelike, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+const RATE_LIMIT_LIMIT: HeaderName = HeaderName::from_static("x-ratelimit-limit");
+const RATE_LIMIT_REMAINING: HeaderName = HeaderName::from_static("x-ratelimit-remaining");
+const QUOTA_REMAINING: HeaderName = HeaderName::from_static("x-daily-quota-remaining");
+
+// Keys start with this, so leaked ones are easy to recognise
+const KEY_PREFIX: &str = "dmp_";
+
+// Characters of a key shown when listing keys
+const SHOWN_KEY_CHARS: usize = 12;
+
+// Usage history a partner can look back over
+pub const MAX_USAGE_DAYS: i64 = 90;
+
+const KEY_COLUMNS: &str =
+    "id, partner_id, name, key_prefix, requests_per_minute, daily_quota, created_by, created_at, revoked_at";
+
+/// Read-only API access for aggregator partners. Admins create partner accounts, each
+/// managed by one user who issues and revokes its keys. Every key has its own per-minute
+/// rate limit and daily quota, and its requests are metered per UTC day.
+pub struct PartnerKeyService {
+    pool: PgPool,
+}
+
+// What a metered request may still do in its minute and day
+struct Allowance {
+    requests_per_minute: i32,
+    remaining_this_minute: i64,
+    remaining_today: i64,
+}
+
+impl PartnerKeyService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create_partner(
+        &self,
+        auth_user: &AuthUser,
+        request: CreatePartnerAccountRequest,
+    ) -> Result<PartnerAccount, AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+
+        let partner = sqlx::query_as::<_, PartnerAccount>(
+            r#"
+            INSERT INTO marketplace_partner_accounts (id, name, owner_id, contact_email, created_by, created_at)
+            VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP)
+            RETURNING *
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(request.name.trim())
+        .bind(&request.owner_id)
+        .bind(request.contact_email.as_deref().map(str::trim))
+        .bind(&auth_user.0.auth0_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        tracing::info!(partner_id = %partner.id, owner_id = %partner.owner_id, "partner account created");
+        Ok(partner)
+    }
+
+    pub async fn list_partners(&self, auth_user: &AuthUser) -> Result<Vec<PartnerAccount>, AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+
+        let partners = sqlx::query_as::<_, PartnerAccount>(
+            "SELECT * FROM marketplace_partner_accounts ORDER BY created_at DESC"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(partners)
+    }
+
+    /// Issue a key for a partner the user manages, with the default limits
+    pub async fn issue_key(
+        &self,
+        auth_user: &AuthUser,
+        request: CreatePartnerApiKeyRequest,
+    ) -> Result<PartnerApiKeyGrant, AppError> {
+        let user_id = &auth_user.0.auth0_id;
+        let owner_id: String = sqlx::query("SELECT owner_id FROM marketplace_partner_accounts WHERE id = $1")
+            .bind(request.partner_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Partner not found".to_string()))?
+            .get("owner_id");
+        if owner_id != *user_id && !MarketplaceService::new(self.pool.clone()).is_admin(user_id).await? {
+            return Err(AppError::NotFound("Partner not found".to_string()));
+        }
+
+        let api_key = format!("{}{}{}", KEY_PREFIX, Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let settings = &Config::get().partner_api;
+
+        let key = sqlx::query_as::<_, PartnerApiKey>(&format!(
+            r#"
+            INSERT INTO marketplace_partner_keys
+                (id, partner_id, name, key_prefix, key_hash, requests_per_minute, daily_quota, created_by, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, CURRENT_TIMESTAMP)
+            RETURNING {}
+            "#,
+            KEY_COLUMNS
+        ))
+        .bind(Uuid::new_v4())
+        .bind(request.partner_id)
+        .bind(request.name.trim())
+        .bind(&api_key[..SHOWN_KEY_CHARS])
+        .bind(hash_key(&api_key))
+        .bind(settings.default_requests_per_minute)
+        .bind(settings.default_daily_quota)
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        tracing::info!(partner_id = %key.partner_id, key_id = %key.id, "partner API key issued");
+        Ok(PartnerApiKeyGrant { key, api_key })
+    }
+
+    /// Keys of the partners the user manages, newest first
+    pub async fn list_keys(&self, auth_user: &AuthUser) -> Result<Vec<PartnerApiKey>, AppError> {
+        let keys = sqlx::query_as::<_, PartnerApiKey>(&format!(
+            r#"
+            SELECT {} FROM marketplace_partner_keys
+            WHERE partner_id IN (SELECT id FROM marketplace_partner_accounts WHERE owner_id = $1)
+            ORDER BY created_at DESC
+            "#,
+            KEY_COLUMNS
+        ))
+        .bind(&auth_user.0.auth0_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(keys)
+    }
+
+    /// Stop a key working; by the partner's manager or an admin
+    pub async fn revoke_key(&self, auth_user: &AuthUser, key_id: Uuid) -> Result<PartnerApiKey, AppError> {
+        self.require_key_access(auth_user, key_id).await?;
+
+        let key = sqlx::query_as::<_, PartnerApiKey>(&format!(
+            r#"
+            UPDATE marketplace_partner_keys
+            SET revoked_at = CURRENT_TIMESTAMP
+            WHERE id = $1 AND revoked_at IS NULL
+            RETURNING {}
+            "#,
+            KEY_COLUMNS
+        ))
+        .bind(key_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::Conflict("API key already revoked".to_string()))?;
+
+        tracing::info!(key_id = %key.id, revoked_by = %auth_user.0.auth0_id, "partner API key revoked");
+        Ok(key)
+    }
+
+    /// Change a key's rate limit and daily quota; admins only
+    pub async fn set_limits(
+        &self,
+        auth_user: &AuthUser,
+        key_id: Uuid,
+        request: UpdatePartnerKeyLimitsRequest,
+    ) -> Result<PartnerApiKey, AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+
+        sqlx::query_as::<_, PartnerApiKey>(&format!(
+            r#"
+            UPDATE marketplace_partner_keys
+            SET requests_per_minute = $2, daily_quota = $3
+            WHERE id = $1
+            RETURNING {}
+            "#,
+            KEY_COLUMNS
+        ))
+        .bind(key_id)
+        .bind(request.requests_per_minute)
+        .bind(request.daily_quota)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("API key not found".to_string()))
+    }
+
+    /// Requests per UTC day over the last `days` days, most recent first
+    pub async fn usage(&self, auth_user: &AuthUser, key_id: Uuid, days: i64) -> Result<Vec<PartnerKeyUsage>, AppError> {
+        self.require_key_access(auth_user, key_id).await?;
+
+        let since = (Utc::now() - Duration::days(days.clamp(1, MAX_USAGE_DAYS) - 1)).date_naive();
+        let usage = sqlx::query_as::<_, PartnerKeyUsage>(
+            r#"
+            SELECT day, requests, rejected FROM marketplace_partner_key_usage
+            WHERE key_id = $1 AND day >= $2
+            ORDER BY day DESC
+            "#
+        )
+        .bind(key_id)
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(usage)
+    }
+
+    // Keys are visible to their partner's manager and admins; to anyone else they do not exist
+    async fn require_key_access(&self, auth_user: &AuthUser, key_id: Uuid) -> Result<(), AppError> {
+        let user_id = &auth_user.0.auth0_id;
+        let owner_id: String = sqlx::query(
+            r#"
+            SELECT p.owner_id FROM marketplace_partner_keys k
+            JOIN marketplace_partner_accounts p ON p.id = k.partner_id
+            WHERE k.id = $1
+            "#
+        )
+        .bind(key_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("API key not found".to_string()))?
+        .get("owner_id");
+
+        if owner_id != *user_id && !MarketplaceService::new(self.pool.clone()).is_admin(user_id).await? {
+            return Err(AppError::NotFound("API key not found".to_string()));
+        }
+        Ok(())
+    }
+
+    // Count a request against the key and fail when it is over its minute's rate limit or
+    // its day's quota. Rejected requests still count towards the minute, so a client that
+    // keeps retrying stays limited until it backs off.
+    async fn meter(&self, api_key: &str) -> Result<Allowance, AppError> {
+        let key = sqlx::query(
+            "SELECT id, requests_per_minute, daily_quota FROM marketplace_partner_keys WHERE key_hash = $1 AND revoked_at IS NULL"
+        )
+        .bind(hash_key(api_key))
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::Unauthorized("API key is invalid or revoked".to_string()))?;
+
+        let key_id: Uuid = key.get("id");
+        let requests_per_minute: i32 = key.get("requests_per_minute");
+        let daily_quota: i32 = key.get("daily_quota");
+
+        let usage = sqlx::query(
+            r#"
+            INSERT INTO marketplace_partner_key_usage (key_id, day, requests, minute_start, minute_requests)
+            VALUES ($1, (CURRENT_TIMESTAMP AT TIME ZONE 'UTC')::date, 1, date_trunc('minute', CURRENT_TIMESTAMP), 1)
+            ON CONFLICT (key_id, day) DO UPDATE SET
+                requests = marketplace_partner_key_usage.requests + 1,
+                minute_requests = CASE
+                    WHEN marketplace_partner_key_usage.minute_start = EXCLUDED.minute_start
+                    THEN marketplace_partner_key_usage.minute_requests + 1
+                    ELSE 1
+                END,
+                minute_start = EXCLUDED.minute_start
+            RETURNING requests - rejected as served, minute_requests
+            "#
+        )
+        .bind(key_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let served: i64 = usage.get("served");
+        let minute_requests: i32 = usage.get("minute_requests");
+
+        let now = Utc::now();
+        let rejection = if minute_requests > requests_per_minute {
+            Some(AppError::RateLimited {
+                message: format!("Rate limit of {} requests per minute exceeded", requests_per_minute),
+                retry_after: 60 - u64::from(now.second()),
+            })
+        } else if served > i64::from(daily_quota) {
+            let midnight = (now.date_naive() + Duration::days(1)).and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+            Some(AppError::RateLimited {
+                message: format!("Daily quota of {} requests used up", daily_quota),
+                retry_after: (midnight - now).num_seconds().max(1) as u64,
+            })
+        } else {
+            None
+        };
+
+        if let Some(rejection) = rejection {
+            sqlx::query(
+                r#"
+                UPDATE marketplace_partner_key_usage SET rejected = rejected + 1
+                WHERE key_id = $1 AND day = (CURRENT_TIMESTAMP AT TIME ZONE 'UTC')::date
+                "#
+            )
+            .bind(key_id)
+            .execute(&self.pool)
+            .await?;
+            return Err(rejection);
+        }
+
+        Ok(Allowance {
+            requests_per_minute,
+            remaining_this_minute: i64::from(requests_per_minute - minute_requests),
+            remaining_today: i64::from(daily_quota) - served,
+        })
+    }
+}
+
+/// Authenticates partner API requests by their `X-Api-Key` and meters them against the
+/// key's limits. Responses say how many requests are left this minute and today.
+pub async fn authenticate(State(pool): State<PgPool>, request: Request, next: Next) -> Response {
+    let Some(api_key) = request
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+    else {
+        return AppError::Unauthorized("X-Api-Key header required".to_string()).into_response();
+    };
+
+    let allowance = match PartnerKeyService::new(pool).meter(&api_key).await {
+        Ok(allowance) => allowance,
+        Err(e) => return e.into_response(),
+    };
+
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    headers.insert(RATE_LIMIT_LIMIT, HeaderValue::from(allowance.requests_per_minute));
+    headers.insert(RATE_LIMIT_REMAINING, HeaderValue::from(allowance.remaining_this_minute));
+    headers.insert(QUOTA_REMAINING, HeaderValue::from(allowance.remaining_today));
+    response
+}
+
+fn hash_key(api_key: &str) -> String {
+    format!("{:x}", Sha256::digest(api_key.as_bytes()))
+}
@@ -0,0 +1,299 @@
+use crate::auth::AuthUser;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::marketplace::cart::{mark_paid, CartService};
+use crate::marketplace::paypal::is_paypal_payment;
+use crate::marketplace::payments::{
+    payment_provider_from_config, PaymentProvider, ProviderChargeRequest, ProviderPaymentStatus, ProviderRefundRequest,
+};
+use crate::models::marketplace::{
+    CheckoutDetail, CheckoutStatus, CreatePaymentMethodRequest, PaymentType, UserPaymentMethod,
+};
+use bigdecimal::{BigDecimal, Zero};
+use sqlx::{PgPool, Postgres, Transaction};
+use std::sync::Arc;
+use uuid::Uuid;
+
+const SYSTEM_ACTOR: &str = "system";
+
+/// Payment methods saved against a user's provider accounts. Cards are tokenized by the
+/// client straight with the payment provider, so only the provider's token and display
+/// details are stored. A user has at most one default method, enforced by a unique index.
+pub struct PaymentMethodService {
+    pool: PgPool,
+    provider: Arc<dyn PaymentProvider>,
+}
+
+impl PaymentMethodService {
+    pub fn new(pool: PgPool) -> Result<Self, AppError> {
+        Ok(Self {
+            pool,
+            provider: payment_provider_from_config(Config::get())?,
+        })
+    }
+
+    pub fn with_provider(pool: PgPool, provider: Arc<dyn PaymentProvider>) -> Self {
+        Self { pool, provider }
+    }
+
+    pub async fn list(&self, user_id: &str) -> Result<Vec<UserPaymentMethod>, AppError> {
+        let methods = sqlx::query_as::<_, UserPaymentMethod>(
+            "SELECT * FROM marketplace_payment_methods WHERE user_id = $1 ORDER BY is_default DESC, created_at DESC"
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(methods)
+    }
+
+    /// Save a payment method the client tokenized with the provider. Adding one already
+    /// saved refreshes its details. The first method becomes the default.
+    pub async fn add(&self, auth_user: &AuthUser, request: &CreatePaymentMethodRequest) -> Result<UserPaymentMethod, AppError> {
+        let user_id = &auth_user.0.auth0_id;
+        let method = self.provider.retrieve_payment_method(&request.payment_token).await?;
+
+        let mut tx = self.pool.begin().await?;
+        lock_methods(&mut tx, user_id).await?;
+
+        let saved = sqlx::query_as::<_, UserPaymentMethod>(
+            r#"
+            INSERT INTO marketplace_payment_methods (
+                id, user_id, payment_type, provider_customer_id, last_four, card_brand, is_default, created_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, FALSE, CURRENT_TIMESTAMP)
+            ON CONFLICT (user_id, payment_type, provider_customer_id)
+            DO UPDATE SET last_four = EXCLUDED.last_four, card_brand = EXCLUDED.card_brand
+            RETURNING *
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(method.payment_type)
+        .bind(&method.token)
+        .bind(&method.last_four)
+        .bind(&method.card_brand)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let saved = if request.is_default || !has_default(&mut tx, user_id).await? {
+            make_default(&mut tx, user_id, saved.id).await?
+        } else {
+            saved
+        };
+
+        tx.commit().await?;
+        Ok(saved)
+    }
+
+    pub async fn set_default(&self, user_id: &str, method_id: Uuid) -> Result<UserPaymentMethod, AppError> {
+        let mut tx = self.pool.begin().await?;
+        lock_methods(&mut tx, user_id).await?;
+
+        let exists = sqlx::query("SELECT 1 FROM marketplace_payment_methods WHERE id = $1 AND user_id = $2")
+            .bind(method_id)
+            .bind(user_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        if exists.is_none() {
+            return Err(AppError::NotFound("Payment method not found".to_string()));
+        }
+
+        let method = make_default(&mut tx, user_id, method_id).await?;
+        tx.commit().await?;
+        Ok(method)
+    }
+
+    /// Remove a payment method; when it was the default the newest remaining one takes over
+    pub async fn delete(&self, user_id: &str, method_id: Uuid) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+        lock_methods(&mut tx, user_id).await?;
+
+        let was_default = sqlx::query_scalar::<_, bool>(
+            "DELETE FROM marketplace_payment_methods WHERE id = $1 AND user_id = $2 RETURNING is_default"
+        )
+        .bind(method_id)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Payment method not found".to_string()))?;
+
+        if was_default {
+            sqlx::query(
+                r#"
+                UPDATE marketplace_payment_methods SET is_default = TRUE
+                WHERE id = (
+                    SELECT id FROM marketplace_payment_methods
+                    WHERE user_id = $1
+                    ORDER BY created_at DESC
+                    LIMIT 1
+                )
+                "#
+            )
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Pay what is due on a checkout with a saved method. A charge the provider is still
+    /// processing leaves the checkout awaiting payment.
+    pub async fn pay_checkout(
+        &self,
+        auth_user: &AuthUser,
+        checkout_id: Uuid,
+        method_id: Uuid,
+    ) -> Result<CheckoutDetail, AppError> {
+        let user_id = &auth_user.0.auth0_id;
+        let cart = CartService::new(self.pool.clone());
+        let checkout = cart.fetch_checkout(checkout_id).await?;
+
+        if checkout.buyer_id != *user_id {
+            return Err(AppError::Forbidden("This checkout belongs to another user".to_string()));
+        }
+        if checkout.status == CheckoutStatus::Expired {
+            return Err(AppError::Conflict("Checkout expired before it was paid".to_string()));
+        }
+        if checkout.status != CheckoutStatus::AwaitingPayment {
+            return Err(AppError::Conflict("Checkout has already been paid".to_string()));
+        }
+
+        let method = sqlx::query_as::<_, UserPaymentMethod>(
+            "SELECT * FROM marketplace_payment_methods WHERE id = $1 AND user_id = $2"
+        )
+        .bind(method_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Payment method not found".to_string()))?;
+
+        let Some(token) = method.provider_customer_id.as_deref() else {
+            return Err(AppError::UnprocessableEntity("This payment method cannot be charged".to_string()));
+        };
+        // Saved PayPal accounts are paid through PayPal checkout, not charged directly
+        if is_paypal_payment(&method.payment_type) {
+            return Err(AppError::UnprocessableEntity("PayPal accounts are paid through PayPal checkout".to_string()));
+        }
+
+        let due = &checkout.total_amount - &checkout.discount_amount;
+        if due <= BigDecimal::zero() {
+            return Err(AppError::UnprocessableEntity("Nothing is due".to_string()));
+        }
+
+        // Retrying with the same method reuses the provider's answer for the first attempt
+        let request = ProviderChargeRequest::new(
+            token,
+            &due,
+            "Marketplace order",
+            format!("checkout-{}-{}", checkout.id, method.id),
+        );
+        let charge = self.provider.charge_payment_method(&request).await?;
+
+        match charge.status {
+            ProviderPaymentStatus::Captured => {}
+            ProviderPaymentStatus::Pending => return cart.get_checkout(auth_user, checkout_id).await,
+            ProviderPaymentStatus::Failed(reason) => {
+                return Err(AppError::UnprocessableEntity(format!("The payment was declined: {}", reason)));
+            }
+        }
+
+        let mut tx = self.pool.begin().await?;
+        let paid = mark_paid(&mut tx, checkout_id, Some(&charge.payment_id), SYSTEM_ACTOR).await;
+        let (checkout, pending) = match paid {
+            Ok((checkout, pending)) if !pending.is_empty() => (checkout, pending),
+            Ok(_) | Err(AppError::Conflict(_)) => {
+                tx.rollback().await?;
+
+                // A retried request got the same charge back and it already paid the checkout
+                let settled = cart.fetch_checkout(checkout_id).await?;
+                if settled.payment_id.as_deref() == Some(charge.payment_id.as_str()) {
+                    return cart.get_checkout(auth_user, checkout_id).await;
+                }
+
+                // Paid meanwhile by another payment, or every item was cancelled: give the charge back
+                let refund = ProviderRefundRequest::new(
+                    &charge.payment_id,
+                    &due,
+                    "Checkout was already settled",
+                    format!("unapplied-{}", charge.payment_id),
+                );
+                self.provider.refund(&refund).await?;
+                return Err(AppError::Conflict("Checkout has already been paid; the charge was refunded".to_string()));
+            }
+            Err(e) => return Err(e),
+        };
+        tx.commit().await?;
+
+        cart.notify_paid(&checkout, &pending).await;
+        cart.get_checkout(auth_user, checkout_id).await
+    }
+
+    /// Remember the provider account a payment came from; already saved accounts are kept as they are
+    pub(crate) async fn save(
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+        payment_type: PaymentType,
+        provider_customer_id: &str,
+    ) -> Result<(), AppError> {
+        lock_methods(tx, user_id).await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_payment_methods (id, user_id, payment_type, provider_customer_id, is_default, created_at)
+            VALUES (
+                $1, $2, $3, $4,
+                NOT EXISTS (SELECT 1 FROM marketplace_payment_methods WHERE user_id = $2 AND is_default),
+                CURRENT_TIMESTAMP
+            )
+            ON CONFLICT (user_id, payment_type, provider_customer_id) DO NOTHING
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(payment_type)
+        .bind(provider_customer_id)
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+}
+
+// Serialize changes to one user's methods so the default moves atomically
+async fn lock_methods(tx: &mut Transaction<'_, Postgres>, user_id: &str) -> Result<(), AppError> {
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+        .bind(format!("payment_methods:{}", user_id))
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+async fn has_default(tx: &mut Transaction<'_, Postgres>, user_id: &str) -> Result<bool, AppError> {
+    let found = sqlx::query("SELECT 1 FROM marketplace_payment_methods WHERE user_id = $1 AND is_default")
+        .bind(user_id)
+        .fetch_optional(&mut **tx)
+        .await?;
+    Ok(found.is_some())
+}
+
+// The old default is cleared first; the unique index allows one default per user
+async fn make_default(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: &str,
+    method_id: Uuid,
+) -> Result<UserPaymentMethod, AppError> {
+    sqlx::query("UPDATE marketplace_payment_methods SET is_default = FALSE WHERE user_id = $1 AND is_default AND id <> $2")
+        .bind(user_id)
+        .bind(method_id)
+        .execute(&mut **tx)
+        .await?;
+
+    let method = sqlx::query_as::<_, UserPaymentMethod>(
+        "UPDATE marketplace_payment_methods SET is_default = TRUE WHERE id = $1 RETURNING *"
+    )
+    .bind(method_id)
+    .fetch_one(&mut **tx)
+    .await?;
+    Ok(method)
+}
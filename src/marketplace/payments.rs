@@ -0,0 +1,967 @@
+use crate::config::{Config, Environment, PaymentProviderConfig, PaypalSettings};
+use crate::error::AppError;
+use crate::models::marketplace::{PaymentReversalKind, PaymentType};
+use async_trait::async_trait;
+use axum::http::HeaderMap;
+use bigdecimal::{BigDecimal, ToPrimitive};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::Sha256;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+// Provider calls that take longer are treated as failed and can be retried
+const PROVIDER_TIMEOUT_SECS: u64 = 15;
+
+const RAZORPAY_API_URL: &str = "https://api.razorpay.com";
+
+// UPI only moves rupees
+pub const UPI_CURRENCY: &str = "INR";
+
+// Header carrying the hex HMAC-SHA256 of a Razorpay webhook body
+const RAZORPAY_SIGNATURE_HEADER: &str = "x-razorpay-signature";
+
+// Headers PayPal signs its webhooks with, checked by PayPal's verification API
+const PAYPAL_SIGNATURE_HEADERS: [(&str, &str); 5] = [
+    ("auth_algo", "paypal-auth-algo"),
+    ("cert_url", "paypal-cert-url"),
+    ("transmission_id", "paypal-transmission-id"),
+    ("transmission_sig", "paypal-transmission-sig"),
+    ("transmission_time", "paypal-transmission-time"),
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderRefundRequest {
+    pub payment_id: String,
+    pub amount: String,
+    pub reason: String,
+    /// Stable per refund so a retried call never pays out twice
+    #[serde(skip)]
+    pub idempotency_key: String,
+}
+
+impl ProviderRefundRequest {
+    pub fn new(payment_id: &str, amount: &BigDecimal, reason: &str, idempotency_key: String) -> Self {
+        Self {
+            payment_id: payment_id.to_string(),
+            amount: amount.round(2).to_string(),
+            reason: reason.to_string(),
+            idempotency_key,
+        }
+    }
+}
+
+/// A charge to a saved payment method, made without the buyer present
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderChargeRequest {
+    /// The provider's token for the payment method
+    pub payment_method: String,
+    pub amount: String,
+    pub description: String,
+    /// Stable per charge attempt so a retried call never charges twice
+    #[serde(skip)]
+    pub idempotency_key: String,
+}
+
+impl ProviderChargeRequest {
+    pub fn new(payment_method: &str, amount: &BigDecimal, description: &str, idempotency_key: String) -> Self {
+        Self {
+            payment_method: payment_method.to_string(),
+            amount: amount.round(2).to_string(),
+            description: description.to_string(),
+            idempotency_key,
+        }
+    }
+}
+
+/// A payment method tokenized by the provider. Card numbers stay with the provider;
+/// only the token and what is safe to display are kept.
+#[derive(Debug, Clone)]
+pub struct ProviderPaymentMethod {
+    pub token: String,
+    pub payment_type: PaymentType,
+    pub last_four: Option<String>,
+    pub card_brand: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct UpiPaymentRequest {
+    /// Our payment id, sent as the provider's receipt
+    pub reference: String,
+    pub amount: String,
+    pub description: String,
+    /// Collect from this VPA; without one the buyer pays through a UPI intent link
+    pub vpa: Option<String>,
+    pub expiry_mins: u32,
+}
+
+impl UpiPaymentRequest {
+    pub fn new(reference: String, amount: &BigDecimal, description: &str, vpa: Option<&str>, expiry_mins: u32) -> Self {
+        Self {
+            reference,
+            amount: amount.round(2).to_string(),
+            description: description.to_string(),
+            vpa: vpa.map(str::to_string),
+            expiry_mins,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UpiPaymentSession {
+    pub payment_id: String,
+    pub order_id: Option<String>,
+    /// `upi://pay` link for the intent flow, opened in the buyer's UPI app
+    pub intent_url: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProviderPaymentStatus {
+    Pending,
+    Captured,
+    Failed(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct ProviderOrderRequest {
+    /// Our order id, sent as the provider's reference
+    pub reference: String,
+    pub amount: String,
+    pub description: String,
+}
+
+impl ProviderOrderRequest {
+    pub fn new(reference: String, amount: &BigDecimal, description: &str) -> Self {
+        Self {
+            reference,
+            amount: amount.round(2).to_string(),
+            description: description.to_string(),
+        }
+    }
+}
+
+/// An order the buyer approves on the provider's site before it is captured
+#[derive(Debug, Clone)]
+pub struct ProviderOrder {
+    pub order_id: String,
+    pub approval_url: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProviderCapture {
+    pub payment_id: String,
+    /// The payer's account at the provider, saved as a payment method
+    pub customer_id: Option<String>,
+    pub status: ProviderPaymentStatus,
+}
+
+/// Money taken back from the platform after a payment was captured
+#[derive(Debug, Clone)]
+pub struct ProviderReversal {
+    /// Stable per reversal, so redelivered webhooks are recorded once
+    pub event_id: String,
+    pub payment_id: String,
+    pub kind: PaymentReversalKind,
+    pub amount: Option<String>,
+    pub reason: String,
+}
+
+/// A verified provider webhook
+#[derive(Debug, Clone)]
+pub enum ProviderWebhookEvent {
+    Payment { payment_id: String, status: ProviderPaymentStatus },
+    OrderCaptured { order_id: String, payment_id: String },
+    Reversal(ProviderReversal),
+}
+
+/// Payment processor that takes payments and moves money back to the buyer.
+/// Only refunds are required; UPI and approval-based orders are optional per provider.
+#[async_trait]
+pub trait PaymentProvider: Send + Sync {
+    /// Refund part or all of a captured payment. Returns the provider's refund id.
+    async fn refund(&self, request: &ProviderRefundRequest) -> Result<String, AppError>;
+
+    /// Start a UPI payment, either an intent link or a collect request sent to a VPA
+    async fn create_upi_payment(&self, _request: &UpiPaymentRequest) -> Result<UpiPaymentSession, AppError> {
+        Err(upi_unsupported())
+    }
+
+    /// Current state of a payment at the provider
+    async fn payment_status(&self, _payment_id: &str) -> Result<ProviderPaymentStatus, AppError> {
+        Err(upi_unsupported())
+    }
+
+    /// Look up a payment method the client tokenized directly with the provider
+    async fn retrieve_payment_method(&self, _token: &str) -> Result<ProviderPaymentMethod, AppError> {
+        Err(unsupported("Saved payment methods"))
+    }
+
+    /// Charge a saved payment method
+    async fn charge_payment_method(&self, _request: &ProviderChargeRequest) -> Result<ProviderCapture, AppError> {
+        Err(unsupported("Saved payment methods"))
+    }
+
+    /// Create an order the buyer approves on the provider's site
+    async fn create_order(&self, _request: &ProviderOrderRequest) -> Result<ProviderOrder, AppError> {
+        Err(unsupported("Orders"))
+    }
+
+    /// Capture an order the buyer approved
+    async fn capture_order(&self, _order_id: &str) -> Result<ProviderCapture, AppError> {
+        Err(unsupported("Orders"))
+    }
+
+    /// Verify a webhook's signature and read the event it carries.
+    /// `None` for authentic events this service does not act on.
+    async fn parse_webhook(&self, _payload: &[u8], _headers: &HeaderMap) -> Result<Option<ProviderWebhookEvent>, AppError> {
+        Err(AppError::Forbidden("The configured payment provider does not send webhooks".to_string()))
+    }
+}
+
+fn upi_unsupported() -> AppError {
+    unsupported("UPI payments")
+}
+
+fn unsupported(feature: &str) -> AppError {
+    AppError::InternalError(format!("{} are not supported by the configured payment provider", feature))
+}
+
+/// Logs refunds instead of sending them; only for development, where no provider is configured
+pub struct LogPaymentProvider;
+
+#[async_trait]
+impl PaymentProvider for LogPaymentProvider {
+    async fn refund(&self, request: &ProviderRefundRequest) -> Result<String, AppError> {
+        tracing::info!(
+            amount = %request.amount,
+            payment_id = %request.payment_id,
+            idempotency_key = %request.idempotency_key,
+            "refund logged, not sent",
+        );
+        Ok(format!("log_{}", request.idempotency_key))
+    }
+
+    // Payments stay pending; confirm them through the admin endpoints
+    async fn create_upi_payment(&self, request: &UpiPaymentRequest) -> Result<UpiPaymentSession, AppError> {
+        tracing::info!(
+            amount = %request.amount,
+            reference = %request.reference,
+            vpa = request.vpa.as_deref().unwrap_or("by intent"),
+            "UPI payment logged, not sent",
+        );
+        Ok(UpiPaymentSession {
+            payment_id: format!("log_{}", request.reference),
+            order_id: None,
+            intent_url: request.vpa.is_none().then(|| {
+                format!("upi://pay?tr={}&am={}&cu={}", request.reference, request.amount, UPI_CURRENCY)
+            }),
+        })
+    }
+
+    async fn payment_status(&self, _payment_id: &str) -> Result<ProviderPaymentStatus, AppError> {
+        Ok(ProviderPaymentStatus::Pending)
+    }
+}
+
+pub struct HttpPaymentProvider {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HttpRefundResponse {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct HttpPaymentMethod {
+    id: String,
+    #[serde(rename = "type")]
+    method_type: String,
+    last_four: Option<String>,
+    brand: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HttpCharge {
+    id: String,
+    status: String, // succeeded, pending or failed
+    failure_reason: Option<String>,
+}
+
+impl HttpPaymentProvider {
+    pub fn new(config: &PaymentProviderConfig) -> Result<Self, AppError> {
+        let base_url = config
+            .url
+            .clone()
+            .ok_or_else(|| AppError::InternalError("PAYMENT_PROVIDER_URL is not set".to_string()))?;
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(PROVIDER_TIMEOUT_SECS))
+            .build()
+            .map_err(|e| AppError::InternalError(format!("Payment provider client error: {}", e)))?;
+
+        Ok(Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: config.api_key.as_ref().map(|key| key.expose().to_string()),
+        })
+    }
+
+    async fn send<T: serde::de::DeserializeOwned>(&self, mut call: reqwest::RequestBuilder, action: &str) -> Result<T, AppError> {
+        if let Some(api_key) = &self.api_key {
+            call = call.bearer_auth(api_key);
+        }
+
+        call.send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| AppError::InternalError(format!("Payment provider {} error: {}", action, e)))?
+            .json::<T>()
+            .await
+            .map_err(|e| AppError::InternalError(format!("Payment provider response error: {}", e)))
+    }
+}
+
+#[async_trait]
+impl PaymentProvider for HttpPaymentProvider {
+    async fn refund(&self, request: &ProviderRefundRequest) -> Result<String, AppError> {
+        let call = self
+            .client
+            .post(format!("{}/refunds", self.base_url))
+            .header("Idempotency-Key", &request.idempotency_key)
+            .json(request);
+        let refund: HttpRefundResponse = self.send(call, "refund").await?;
+        Ok(refund.id)
+    }
+
+    async fn retrieve_payment_method(&self, token: &str) -> Result<ProviderPaymentMethod, AppError> {
+        let call = self.client.get(format!("{}/payment-methods/{}", self.base_url, token));
+        let method: HttpPaymentMethod = self.send(call, "payment method lookup").await?;
+
+        let payment_type = match method.method_type.as_str() {
+            "card" => PaymentType::Card,
+            "upi" => PaymentType::Upi,
+            other => return Err(AppError::UnprocessableEntity(format!("Unsupported payment method type: {}", other))),
+        };
+        Ok(ProviderPaymentMethod {
+            token: method.id,
+            payment_type,
+            last_four: method.last_four,
+            card_brand: method.brand,
+        })
+    }
+
+    async fn charge_payment_method(&self, request: &ProviderChargeRequest) -> Result<ProviderCapture, AppError> {
+        let call = self
+            .client
+            .post(format!("{}/charges", self.base_url))
+            .header("Idempotency-Key", &request.idempotency_key)
+            .json(request);
+        let charge: HttpCharge = self.send(call, "charge").await?;
+
+        let status = match charge.status.as_str() {
+            "succeeded" => ProviderPaymentStatus::Captured,
+            "failed" => ProviderPaymentStatus::Failed(charge.failure_reason.unwrap_or_else(|| "Payment failed".to_string())),
+            _ => ProviderPaymentStatus::Pending,
+        };
+        Ok(ProviderCapture {
+            payment_id: charge.id,
+            customer_id: None,
+            status,
+        })
+    }
+}
+
+/// Razorpay, for UPI payments in India. Amounts are sent in paise.
+pub struct RazorpayPaymentProvider {
+    client: reqwest::Client,
+    base_url: String,
+    key_id: String,
+    key_secret: String,
+    webhook_secret: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RazorpayEntity {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RazorpayUpiPaymentResponse {
+    razorpay_payment_id: String,
+    link: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RazorpayPayment {
+    id: String,
+    status: String,
+    error_description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RazorpayWebhook {
+    event: String,
+    payload: RazorpayWebhookPayload,
+}
+
+#[derive(Debug, Deserialize)]
+struct RazorpayWebhookPayload {
+    payment: Option<RazorpayWebhookPayment>,
+    dispute: Option<RazorpayWebhookDispute>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RazorpayWebhookPayment {
+    entity: RazorpayPayment,
+}
+
+#[derive(Debug, Deserialize)]
+struct RazorpayWebhookDispute {
+    entity: RazorpayDispute,
+}
+
+#[derive(Debug, Deserialize)]
+struct RazorpayDispute {
+    id: String,
+    payment_id: String,
+    amount: i64, // paise
+    reason_description: Option<String>,
+}
+
+impl RazorpayPaymentProvider {
+    pub fn new(config: &PaymentProviderConfig) -> Result<Self, AppError> {
+        let missing = |name: &str| AppError::InternalError(format!("{} is not set", name));
+        let key_id = config.key_id.clone().ok_or_else(|| missing("PAYMENT_PROVIDER_KEY_ID"))?;
+        let key_secret = config.api_key.as_ref().ok_or_else(|| missing("PAYMENT_PROVIDER_API_KEY"))?;
+        let webhook_secret = config.webhook_secret.as_ref().ok_or_else(|| missing("PAYMENT_WEBHOOK_SECRET"))?;
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(PROVIDER_TIMEOUT_SECS))
+            .build()
+            .map_err(|e| AppError::InternalError(format!("Payment provider client error: {}", e)))?;
+
+        Ok(Self {
+            client,
+            base_url: config.url.as_deref().unwrap_or(RAZORPAY_API_URL).trim_end_matches('/').to_string(),
+            key_id,
+            key_secret: key_secret.expose().to_string(),
+            webhook_secret: webhook_secret.expose().to_string(),
+        })
+    }
+
+    async fn call<T: serde::de::DeserializeOwned>(&self, request: reqwest::RequestBuilder, action: &str) -> Result<T, AppError> {
+        let response = request
+            .basic_auth(&self.key_id, Some(&self.key_secret))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| AppError::InternalError(format!("Razorpay {} error: {}", action, e)))?;
+
+        response
+            .json::<T>()
+            .await
+            .map_err(|e| AppError::InternalError(format!("Razorpay {} response error: {}", action, e)))
+    }
+}
+
+#[async_trait]
+impl PaymentProvider for RazorpayPaymentProvider {
+    async fn refund(&self, request: &ProviderRefundRequest) -> Result<String, AppError> {
+        let body = json!({
+            "amount": minor_units(&request.amount)?,
+            "receipt": request.idempotency_key,
+            "notes": { "reason": request.reason },
+        });
+        let refund: RazorpayEntity = self
+            .call(
+                self.client
+                    .post(format!("{}/v1/payments/{}/refund", self.base_url, request.payment_id))
+                    .header("Idempotency-Key", &request.idempotency_key)
+                    .json(&body),
+                "refund",
+            )
+            .await?;
+        Ok(refund.id)
+    }
+
+    async fn create_upi_payment(&self, request: &UpiPaymentRequest) -> Result<UpiPaymentSession, AppError> {
+        let amount = minor_units(&request.amount)?;
+
+        let order: RazorpayEntity = self
+            .call(
+                self.client.post(format!("{}/v1/orders", self.base_url)).json(&json!({
+                    "amount": amount,
+                    "currency": UPI_CURRENCY,
+                    "receipt": request.reference,
+                    "payment_capture": 1,
+                    "notes": { "description": request.description },
+                })),
+                "order",
+            )
+            .await?;
+
+        let upi = match &request.vpa {
+            Some(vpa) => json!({ "flow": "collect", "vpa": vpa, "expiry_time": request.expiry_mins }),
+            None => json!({ "flow": "intent" }),
+        };
+        let payment: RazorpayUpiPaymentResponse = self
+            .call(
+                self.client.post(format!("{}/v1/payments/create/upi", self.base_url)).json(&json!({
+                    "amount": amount,
+                    "currency": UPI_CURRENCY,
+                    "order_id": order.id,
+                    "method": "upi",
+                    "description": request.description,
+                    "upi": upi,
+                })),
+                "UPI payment",
+            )
+            .await?;
+
+        Ok(UpiPaymentSession {
+            payment_id: payment.razorpay_payment_id,
+            order_id: Some(order.id),
+            intent_url: payment.link,
+        })
+    }
+
+    async fn payment_status(&self, payment_id: &str) -> Result<ProviderPaymentStatus, AppError> {
+        let payment: RazorpayPayment = self
+            .call(self.client.get(format!("{}/v1/payments/{}", self.base_url, payment_id)), "payment lookup")
+            .await?;
+        Ok(razorpay_status(&payment))
+    }
+
+    async fn parse_webhook(&self, payload: &[u8], headers: &HeaderMap) -> Result<Option<ProviderWebhookEvent>, AppError> {
+        let signature = headers
+            .get(RAZORPAY_SIGNATURE_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(|| AppError::Forbidden("Missing webhook signature".to_string()))?;
+        if !verify_signature(self.webhook_secret.as_bytes(), payload, signature) {
+            return Err(AppError::Forbidden("Invalid webhook signature".to_string()));
+        }
+
+        let webhook: RazorpayWebhook = serde_json::from_slice(payload)
+            .map_err(|e| AppError::BadRequest(format!("Invalid webhook payload: {}", e)))?;
+
+        match (webhook.event.as_str(), webhook.payload) {
+            ("payment.captured" | "payment.failed", RazorpayWebhookPayload { payment: Some(payment), .. }) => {
+                Ok(Some(ProviderWebhookEvent::Payment {
+                    status: razorpay_status(&payment.entity),
+                    payment_id: payment.entity.id,
+                }))
+            }
+            // Razorpay debits the disputed amount as soon as the card network raises the dispute
+            ("payment.dispute.created", RazorpayWebhookPayload { dispute: Some(dispute), .. }) => {
+                let dispute = dispute.entity;
+                Ok(Some(ProviderWebhookEvent::Reversal(ProviderReversal {
+                    event_id: dispute.id,
+                    payment_id: dispute.payment_id,
+                    kind: PaymentReversalKind::Chargeback,
+                    amount: Some(major_units(dispute.amount)),
+                    reason: dispute
+                        .reason_description
+                        .unwrap_or_else(|| "Card holder disputed the payment".to_string()),
+                })))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+// Authorized payments are captured automatically, so they are still pending here
+fn razorpay_status(payment: &RazorpayPayment) -> ProviderPaymentStatus {
+    match payment.status.as_str() {
+        "captured" | "refunded" => ProviderPaymentStatus::Captured,
+        "failed" => ProviderPaymentStatus::Failed(
+            payment.error_description.clone().unwrap_or_else(|| "Payment failed".to_string()),
+        ),
+        _ => ProviderPaymentStatus::Pending,
+    }
+}
+
+// Hex HMAC-SHA256 of the raw body, compared in constant time
+fn verify_signature(secret: &[u8], payload: &[u8], signature: &str) -> bool {
+    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(secret) else {
+        return false;
+    };
+    mac.update(payload);
+    let expected = format!("{:x}", mac.finalize().into_bytes());
+
+    let signature = signature.trim().to_ascii_lowercase();
+    expected.len() == signature.len()
+        && expected.bytes().zip(signature.bytes()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+// "12.34" as 1234 paise
+fn minor_units(amount: &str) -> Result<i64, AppError> {
+    BigDecimal::from_str(amount)
+        .ok()
+        .and_then(|amount| (amount * BigDecimal::from(100)).round(0).to_i64())
+        .ok_or_else(|| AppError::InternalError(format!("Invalid payment amount: {}", amount)))
+}
+
+// 1234 paise as "12.34"
+fn major_units(amount: i64) -> String {
+    (BigDecimal::from(amount) / BigDecimal::from(100)).round(2).to_string()
+}
+
+/// PayPal checkout: orders approved on PayPal and captured on return. Offered alongside the
+/// main provider, so PayPal payments are refunded here whatever `PAYMENT_PROVIDER` is.
+pub struct PaypalPaymentProvider {
+    client: reqwest::Client,
+    base_url: String,
+    client_id: String,
+    client_secret: String,
+    webhook_id: String,
+    currency: String,
+    return_url: Option<String>,
+    cancel_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaypalToken {
+    access_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaypalLink {
+    href: String,
+    rel: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaypalOrderResponse {
+    id: String,
+    #[serde(default)]
+    links: Vec<PaypalLink>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaypalCaptureResponse {
+    payer: Option<PaypalPayer>,
+    #[serde(default)]
+    purchase_units: Vec<PaypalPurchaseUnit>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaypalPayer {
+    payer_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaypalPurchaseUnit {
+    payments: Option<PaypalPayments>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaypalPayments {
+    #[serde(default)]
+    captures: Vec<PaypalCapture>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaypalCapture {
+    id: String,
+    status: String,
+    status_details: Option<PaypalStatusDetails>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaypalStatusDetails {
+    reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaypalRefund {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaypalVerification {
+    verification_status: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaypalWebhook {
+    id: String,
+    event_type: String,
+    resource: serde_json::Value,
+}
+
+impl PaypalPaymentProvider {
+    pub fn new(settings: &PaypalSettings) -> Result<Self, AppError> {
+        let missing = |name: &str| AppError::InternalError(format!("{} is not set", name));
+        let client_id = settings.client_id.clone().ok_or_else(|| missing("PAYPAL_CLIENT_ID"))?;
+        let client_secret = settings.client_secret.as_ref().ok_or_else(|| missing("PAYPAL_CLIENT_SECRET"))?;
+        let webhook_id = settings.webhook_id.clone().ok_or_else(|| missing("PAYPAL_WEBHOOK_ID"))?;
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(PROVIDER_TIMEOUT_SECS))
+            .build()
+            .map_err(|e| AppError::InternalError(format!("Payment provider client error: {}", e)))?;
+
+        Ok(Self {
+            client,
+            base_url: settings.api_url.trim_end_matches('/').to_string(),
+            client_id,
+            client_secret: client_secret.expose().to_string(),
+            webhook_id,
+            currency: settings.currency.clone(),
+            return_url: settings.return_url.clone(),
+            cancel_url: settings.cancel_url.clone(),
+        })
+    }
+
+    // Tokens are short-lived and providers are built per request, so one is fetched per call
+    async fn access_token(&self) -> Result<String, AppError> {
+        let token: PaypalToken = self
+            .client
+            .post(format!("{}/v1/oauth2/token", self.base_url))
+            .basic_auth(&self.client_id, Some(&self.client_secret))
+            .form(&[("grant_type", "client_credentials")])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| AppError::InternalError(format!("PayPal authentication error: {}", e)))?
+            .json()
+            .await
+            .map_err(|e| AppError::InternalError(format!("PayPal authentication response error: {}", e)))?;
+        Ok(token.access_token)
+    }
+
+    async fn call<T: serde::de::DeserializeOwned>(
+        &self,
+        request: reqwest::RequestBuilder,
+        request_id: Option<&str>,
+        action: &str,
+    ) -> Result<T, AppError> {
+        let mut request = request.bearer_auth(self.access_token().await?);
+        if let Some(request_id) = request_id {
+            request = request.header("PayPal-Request-Id", request_id);
+        }
+
+        let response = request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| AppError::InternalError(format!("PayPal {} error: {}", action, e)))?;
+
+        response
+            .json::<T>()
+            .await
+            .map_err(|e| AppError::InternalError(format!("PayPal {} response error: {}", action, e)))
+    }
+}
+
+#[async_trait]
+impl PaymentProvider for PaypalPaymentProvider {
+    async fn refund(&self, request: &ProviderRefundRequest) -> Result<String, AppError> {
+        let body = json!({
+            "amount": { "value": request.amount, "currency_code": self.currency },
+            "note_to_payer": request.reason,
+        });
+        let refund: PaypalRefund = self
+            .call(
+                self.client
+                    .post(format!("{}/v2/payments/captures/{}/refund", self.base_url, request.payment_id))
+                    .json(&body),
+                Some(&request.idempotency_key),
+                "refund",
+            )
+            .await?;
+        Ok(refund.id)
+    }
+
+    async fn payment_status(&self, payment_id: &str) -> Result<ProviderPaymentStatus, AppError> {
+        let capture: PaypalCapture = self
+            .call(self.client.get(format!("{}/v2/payments/captures/{}", self.base_url, payment_id)), None, "capture lookup")
+            .await?;
+        Ok(paypal_status(&capture))
+    }
+
+    async fn create_order(&self, request: &ProviderOrderRequest) -> Result<ProviderOrder, AppError> {
+        let body = json!({
+            "intent": "CAPTURE",
+            "purchase_units": [{
+                "reference_id": request.reference,
+                "custom_id": request.reference,
+                "description": request.description,
+                "amount": { "currency_code": self.currency, "value": request.amount },
+            }],
+            "application_context": {
+                "user_action": "PAY_NOW",
+                "shipping_preference": "NO_SHIPPING",
+                "return_url": self.return_url,
+                "cancel_url": self.cancel_url,
+            },
+        });
+        let order: PaypalOrderResponse = self
+            .call(
+                self.client.post(format!("{}/v2/checkout/orders", self.base_url)).json(&body),
+                Some(&request.reference),
+                "order",
+            )
+            .await?;
+
+        let approval_url = order
+            .links
+            .iter()
+            .find(|link| link.rel == "approve" || link.rel == "payer-action")
+            .map(|link| link.href.clone())
+            .ok_or_else(|| AppError::InternalError("PayPal order has no approval link".to_string()))?;
+
+        Ok(ProviderOrder { order_id: order.id, approval_url })
+    }
+
+    async fn capture_order(&self, order_id: &str) -> Result<ProviderCapture, AppError> {
+        let order: PaypalCaptureResponse = self
+            .call(
+                self.client
+                    .post(format!("{}/v2/checkout/orders/{}/capture", self.base_url, order_id))
+                    .json(&json!({})),
+                Some(&format!("capture-{}", order_id)),
+                "capture",
+            )
+            .await?;
+
+        let capture = order
+            .purchase_units
+            .iter()
+            .filter_map(|unit| unit.payments.as_ref())
+            .flat_map(|payments| payments.captures.iter())
+            .next()
+            .ok_or_else(|| AppError::InternalError("PayPal capture returned no payment".to_string()))?;
+
+        Ok(ProviderCapture {
+            payment_id: capture.id.clone(),
+            customer_id: order.payer.and_then(|payer| payer.payer_id),
+            status: paypal_status(capture),
+        })
+    }
+
+    async fn parse_webhook(&self, payload: &[u8], headers: &HeaderMap) -> Result<Option<ProviderWebhookEvent>, AppError> {
+        let event: serde_json::Value = serde_json::from_slice(payload)
+            .map_err(|e| AppError::BadRequest(format!("Invalid webhook payload: {}", e)))?;
+
+        let mut verification = serde_json::Map::new();
+        for (field, header) in PAYPAL_SIGNATURE_HEADERS {
+            let value = headers
+                .get(header)
+                .and_then(|value| value.to_str().ok())
+                .ok_or_else(|| AppError::Forbidden("Missing webhook signature".to_string()))?;
+            verification.insert(field.to_string(), json!(value));
+        }
+        verification.insert("webhook_id".to_string(), json!(self.webhook_id));
+        verification.insert("webhook_event".to_string(), event.clone());
+
+        let result: PaypalVerification = self
+            .call(
+                self.client
+                    .post(format!("{}/v1/notifications/verify-webhook-signature", self.base_url))
+                    .json(&verification),
+                None,
+                "webhook verification",
+            )
+            .await?;
+        if result.verification_status != "SUCCESS" {
+            return Err(AppError::Forbidden("Invalid webhook signature".to_string()));
+        }
+
+        let webhook: PaypalWebhook = serde_json::from_value(event)
+            .map_err(|e| AppError::BadRequest(format!("Invalid webhook payload: {}", e)))?;
+        let resource = &webhook.resource;
+        let text = |pointer: &str| resource.pointer(pointer).and_then(|value| value.as_str()).map(str::to_string);
+
+        let event = match webhook.event_type.as_str() {
+            "PAYMENT.CAPTURE.COMPLETED" => match (text("/supplementary_data/related_ids/order_id"), text("/id")) {
+                (Some(order_id), Some(payment_id)) => ProviderWebhookEvent::OrderCaptured { order_id, payment_id },
+                _ => return Ok(None),
+            },
+            "PAYMENT.CAPTURE.DENIED" => match text("/id") {
+                Some(payment_id) => ProviderWebhookEvent::Payment {
+                    payment_id,
+                    status: ProviderPaymentStatus::Failed("PayPal denied the payment".to_string()),
+                },
+                None => return Ok(None),
+            },
+            "PAYMENT.CAPTURE.REVERSED" => {
+                // The resource is the reversal; its "up" link points at the capture
+                let capture_id = resource
+                    .get("links")
+                    .and_then(|links| links.as_array())
+                    .and_then(|links| {
+                        links.iter().find(|link| link.get("rel").and_then(|rel| rel.as_str()) == Some("up"))
+                    })
+                    .and_then(|link| link.get("href").and_then(|href| href.as_str()))
+                    .and_then(|href| href.rsplit('/').next())
+                    .map(str::to_string)
+                    .or_else(|| text("/id"));
+                let Some(payment_id) = capture_id else {
+                    return Ok(None);
+                };
+                ProviderWebhookEvent::Reversal(ProviderReversal {
+                    event_id: webhook.id.clone(),
+                    payment_id,
+                    kind: PaymentReversalKind::Reversal,
+                    amount: text("/amount/value"),
+                    reason: text("/note_to_payer").unwrap_or_else(|| "PayPal reversed the payment".to_string()),
+                })
+            }
+            "CUSTOMER.DISPUTE.CREATED" => {
+                let Some(payment_id) = text("/disputed_transactions/0/seller_transaction_id") else {
+                    return Ok(None);
+                };
+                ProviderWebhookEvent::Reversal(ProviderReversal {
+                    event_id: webhook.id.clone(),
+                    payment_id,
+                    kind: PaymentReversalKind::Chargeback,
+                    amount: text("/dispute_amount/value"),
+                    reason: text("/reason").unwrap_or_else(|| "Buyer disputed the payment".to_string()),
+                })
+            }
+            _ => return Ok(None),
+        };
+
+        Ok(Some(event))
+    }
+}
+
+fn paypal_status(capture: &PaypalCapture) -> ProviderPaymentStatus {
+    match capture.status.as_str() {
+        "COMPLETED" | "REFUNDED" | "PARTIALLY_REFUNDED" => ProviderPaymentStatus::Captured,
+        "DECLINED" | "FAILED" => ProviderPaymentStatus::Failed(
+            capture
+                .status_details
+                .as_ref()
+                .and_then(|details| details.reason.clone())
+                .unwrap_or_else(|| "PayPal declined the payment".to_string()),
+        ),
+        _ => ProviderPaymentStatus::Pending,
+    }
+}
+
+/// PayPal, when `PAYPAL_CLIENT_ID` is set
+pub fn paypal_provider_from_config(config: &Config) -> Result<Option<Arc<dyn PaymentProvider>>, AppError> {
+    if config.paypal.client_id.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(Arc::new(PaypalPaymentProvider::new(&config.paypal)?)))
+}
+
+/// Build the provider selected by `PAYMENT_PROVIDER`
+pub fn payment_provider_from_config(config: &Config) -> Result<Arc<dyn PaymentProvider>, AppError> {
+    match config.payments.provider.as_str() {
+        "http" => Ok(Arc::new(HttpPaymentProvider::new(&config.payments)?)),
+        "razorpay" => Ok(Arc::new(RazorpayPaymentProvider::new(&config.payments)?)),
+        // Its refunds report success without moving money, so never outside development
+        "log" if config.environment == Environment::Development => Ok(Arc::new(LogPaymentProvider)),
+        "log" => Err(AppError::InternalError("The log payment provider is only available in development".to_string())),
+        other => Err(AppError::InternalError(format!("Unsupported payment provider: {}", other))),
+    }
+}
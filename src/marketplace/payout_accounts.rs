@@ -0,0 +1,405 @@
+use crate::auth::AuthUser;
+use crate::config::{Config, StripeConnectSettings};
+use crate::error::AppError;
+use crate::marketplace::jobs::Job;
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{PayoutAccount, PayoutAccountStatus, PayoutOnboardingLink, TransactionStatus};
+use async_trait::async_trait;
+use chrono::{TimeZone, Utc};
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+
+const PROVIDER_TIMEOUT_SECS: u64 = 10;
+
+// Unfinished accounts are polled at most this often
+const RECHECK_AFTER_MINS: i32 = 15;
+
+// Sellers who have not touched onboarding for this long are no longer polled
+const POLL_WINDOW_DAYS: i32 = 30;
+
+const SYNC_BATCH_SIZE: i64 = 100;
+
+/// Connected account as the payout provider reports it
+#[derive(Debug, Clone)]
+pub struct ConnectedAccountState {
+    pub details_submitted: bool,
+    pub payouts_enabled: bool,
+    pub transfers_active: bool,
+    pub requirements_due: Vec<String>,
+    pub disabled_reason: Option<String>,
+}
+
+impl ConnectedAccountState {
+    fn status(&self) -> PayoutAccountStatus {
+        if self.payouts_enabled && self.transfers_active {
+            PayoutAccountStatus::Enabled
+        } else if self.details_submitted {
+            PayoutAccountStatus::Restricted
+        } else {
+            PayoutAccountStatus::Pending
+        }
+    }
+}
+
+/// Provider holding the accounts sellers are paid out to
+#[async_trait]
+pub trait PayoutAccountProvider: Send + Sync {
+    /// Create a connected account for a seller; returns the provider's account id
+    async fn create_account(&self, user_id: &str) -> Result<String, AppError>;
+
+    /// Link to the provider's hosted onboarding for the account
+    async fn onboarding_link(&self, account_id: &str) -> Result<PayoutOnboardingLink, AppError>;
+
+    async fn account_state(&self, account_id: &str) -> Result<ConnectedAccountState, AppError>;
+}
+
+/// Stripe Connect with Express accounts; Stripe hosts onboarding and identity checks
+pub struct StripeConnectProvider {
+    client: reqwest::Client,
+    base_url: String,
+    secret_key: String,
+    country: String,
+    return_url: String,
+    refresh_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct StripeAccount {
+    id: String,
+    #[serde(default)]
+    details_submitted: bool,
+    #[serde(default)]
+    payouts_enabled: bool,
+    capabilities: Option<StripeCapabilities>,
+    requirements: Option<StripeRequirements>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StripeCapabilities {
+    transfers: Option<String>, // active, inactive or pending
+}
+
+#[derive(Debug, Deserialize)]
+struct StripeRequirements {
+    #[serde(default)]
+    currently_due: Vec<String>,
+    disabled_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StripeAccountLink {
+    url: String,
+    expires_at: i64,
+}
+
+impl StripeConnectProvider {
+    pub fn new(settings: &StripeConnectSettings) -> Result<Self, AppError> {
+        let missing = |name: &str| AppError::InternalError(format!("{} is not set", name));
+        let secret_key = settings.secret_key.as_ref().ok_or_else(|| missing("STRIPE_SECRET_KEY"))?;
+        let return_url = settings.return_url.clone().ok_or_else(|| missing("STRIPE_CONNECT_RETURN_URL"))?;
+        let refresh_url = settings.refresh_url.clone().ok_or_else(|| missing("STRIPE_CONNECT_REFRESH_URL"))?;
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(PROVIDER_TIMEOUT_SECS))
+            .build()
+            .map_err(|e| AppError::InternalError(format!("Payout provider client error: {}", e)))?;
+
+        Ok(Self {
+            client,
+            base_url: settings.api_url.trim_end_matches('/').to_string(),
+            secret_key: secret_key.expose().to_string(),
+            country: settings.country.clone(),
+            return_url,
+            refresh_url,
+        })
+    }
+
+    async fn call<T: serde::de::DeserializeOwned>(&self, request: reqwest::RequestBuilder, action: &str) -> Result<T, AppError> {
+        request
+            .basic_auth(&self.secret_key, None::<&str>)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| AppError::InternalError(format!("Stripe {} error: {}", action, e)))?
+            .json::<T>()
+            .await
+            .map_err(|e| AppError::InternalError(format!("Stripe {} response error: {}", action, e)))
+    }
+}
+
+#[async_trait]
+impl PayoutAccountProvider for StripeConnectProvider {
+    async fn create_account(&self, user_id: &str) -> Result<String, AppError> {
+        let form = [
+            ("type", "express"),
+            ("country", self.country.as_str()),
+            ("capabilities[transfers][requested]", "true"),
+            ("metadata[user_id]", user_id),
+        ];
+        // Stripe replays the first response for a repeated key, so a retry never opens a second account
+        let account: StripeAccount = self
+            .call(
+                self.client
+                    .post(format!("{}/v1/accounts", self.base_url))
+                    .header("Idempotency-Key", format!("payout-account-{}", user_id))
+                    .form(&form),
+                "account creation",
+            )
+            .await?;
+        Ok(account.id)
+    }
+
+    async fn onboarding_link(&self, account_id: &str) -> Result<PayoutOnboardingLink, AppError> {
+        let form = [
+            ("account", account_id),
+            ("type", "account_onboarding"),
+            ("return_url", self.return_url.as_str()),
+            ("refresh_url", self.refresh_url.as_str()),
+        ];
+        let link: StripeAccountLink = self
+            .call(
+                self.client.post(format!("{}/v1/account_links", self.base_url)).form(&form),
+                "onboarding link",
+            )
+            .await?;
+
+        let expires_at = Utc
+            .timestamp_opt(link.expires_at, 0)
+            .single()
+            .ok_or_else(|| AppError::InternalError("Stripe returned an invalid link expiry".to_string()))?;
+        Ok(PayoutOnboardingLink { url: link.url, expires_at })
+    }
+
+    async fn account_state(&self, account_id: &str) -> Result<ConnectedAccountState, AppError> {
+        let account: StripeAccount = self
+            .call(self.client.get(format!("{}/v1/accounts/{}", self.base_url, account_id)), "account lookup")
+            .await?;
+
+        let requirements = account.requirements;
+        Ok(ConnectedAccountState {
+            details_submitted: account.details_submitted,
+            payouts_enabled: account.payouts_enabled,
+            transfers_active: account
+                .capabilities
+                .and_then(|capabilities| capabilities.transfers)
+                .is_some_and(|status| status == "active"),
+            requirements_due: requirements
+                .as_ref()
+                .map(|requirements| requirements.currently_due.clone())
+                .unwrap_or_default(),
+            disabled_reason: requirements.and_then(|requirements| requirements.disabled_reason),
+        })
+    }
+}
+
+/// Stripe Connect, when `STRIPE_SECRET_KEY` is set
+pub fn payout_provider_from_config(config: &Config) -> Result<Option<Arc<dyn PayoutAccountProvider>>, AppError> {
+    if config.stripe_connect.secret_key.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(Arc::new(StripeConnectProvider::new(&config.stripe_connect)?)))
+}
+
+/// Seller onboarding to direct payouts. Sellers open a connected account through the
+/// provider's hosted onboarding; its capabilities are polled until payouts are enabled.
+/// Once a seller has enough completed sales, new listings wait for finished onboarding.
+/// Without a configured provider onboarding is unavailable and listing is never gated.
+pub struct PayoutAccountService {
+    pool: PgPool,
+    provider: Option<Arc<dyn PayoutAccountProvider>>,
+}
+
+impl PayoutAccountService {
+    pub fn new(pool: PgPool) -> Result<Self, AppError> {
+        Ok(Self {
+            pool,
+            provider: payout_provider_from_config(Config::get())?,
+        })
+    }
+
+    pub fn with_provider(pool: PgPool, provider: Arc<dyn PayoutAccountProvider>) -> Self {
+        Self { pool, provider: Some(provider) }
+    }
+
+    /// The caller's payout account, checked with the provider while onboarding is unfinished
+    pub async fn get_account(&self, user_id: &str) -> Result<PayoutAccount, AppError> {
+        let account = self
+            .fetch(user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("No payout account yet; start onboarding first".to_string()))?;
+
+        if account.status == PayoutAccountStatus::Enabled {
+            return Ok(account);
+        }
+        self.refresh(&account).await
+    }
+
+    /// Link to continue onboarding, opening the connected account on first use
+    pub async fn onboarding_link(&self, auth_user: &AuthUser) -> Result<PayoutOnboardingLink, AppError> {
+        let user_id = &auth_user.0.auth0_id;
+        let provider = self.provider()?;
+
+        let account = match self.fetch(user_id).await? {
+            Some(account) => account,
+            None => {
+                let account_id = provider.create_account(user_id).await?;
+                sqlx::query(
+                    r#"
+                    INSERT INTO marketplace_payout_accounts (user_id, provider_account_id, status, created_at, checked_at)
+                    VALUES ($1, $2, $3, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+                    ON CONFLICT (user_id) DO NOTHING
+                    "#
+                )
+                .bind(user_id)
+                .bind(&account_id)
+                .bind(PayoutAccountStatus::Pending)
+                .execute(&self.pool)
+                .await?;
+
+                self.fetch(user_id)
+                    .await?
+                    .ok_or_else(|| AppError::InternalError("Payout account was not saved".to_string()))?
+            }
+        };
+
+        if account.status == PayoutAccountStatus::Enabled {
+            return Err(AppError::Conflict("Payout onboarding is already complete".to_string()));
+        }
+        provider.onboarding_link(&account.provider_account_id).await
+    }
+
+    /// Fail while the seller has enough completed sales to need payouts but has not finished onboarding
+    pub async fn ensure_can_list(&self, user_id: &str) -> Result<(), AppError> {
+        if self.provider.is_none() {
+            return Ok(());
+        }
+
+        let threshold = Config::get().stripe_connect.required_after_sales;
+        let sales: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM marketplace_transactions WHERE seller_id = $1 AND status = $2"
+        )
+        .bind(user_id)
+        .bind(TransactionStatus::Completed)
+        .fetch_one(&self.pool)
+        .await?;
+        if sales < threshold {
+            return Ok(());
+        }
+
+        let enabled = match self.fetch(user_id).await? {
+            Some(account) if account.status == PayoutAccountStatus::Enabled => true,
+            Some(account) => self.refresh(&account).await?.status == PayoutAccountStatus::Enabled,
+            None => false,
+        };
+        if enabled {
+            return Ok(());
+        }
+
+        Err(AppError::Forbidden(format!(
+            "You have {} completed sales; finish payout onboarding to create more listings",
+            sales
+        )))
+    }
+
+    /// Poll unfinished accounts. Returns how many became enabled.
+    pub async fn sync_unfinished(&self) -> Result<u64, AppError> {
+        if self.provider.is_none() {
+            return Ok(0);
+        }
+
+        let accounts = sqlx::query_as::<_, PayoutAccount>(
+            r#"
+            SELECT * FROM marketplace_payout_accounts
+            WHERE status <> $1
+              AND checked_at <= CURRENT_TIMESTAMP - make_interval(mins => $2)
+              AND created_at > CURRENT_TIMESTAMP - make_interval(days => $3)
+            ORDER BY checked_at
+            LIMIT $4
+            "#
+        )
+        .bind(PayoutAccountStatus::Enabled)
+        .bind(RECHECK_AFTER_MINS)
+        .bind(POLL_WINDOW_DAYS)
+        .bind(SYNC_BATCH_SIZE)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut enabled = 0;
+        for account in accounts {
+            match self.refresh(&account).await {
+                Ok(updated) if updated.status == PayoutAccountStatus::Enabled => enabled += 1,
+                Ok(_) => {}
+                Err(e) => tracing::warn!(user_id = %account.user_id, error = %e, "payout account sync failed"),
+            }
+        }
+        Ok(enabled)
+    }
+
+    // Store the provider's view of the account, telling the seller when payouts open up
+    async fn refresh(&self, account: &PayoutAccount) -> Result<PayoutAccount, AppError> {
+        let state = self.provider()?.account_state(&account.provider_account_id).await?;
+        let status = state.status();
+
+        let updated = sqlx::query_as::<_, PayoutAccount>(
+            r#"
+            UPDATE marketplace_payout_accounts
+            SET status = $2, details_submitted = $3, payouts_enabled = $4, requirements_due = $5,
+                disabled_reason = $6, checked_at = CURRENT_TIMESTAMP,
+                enabled_at = CASE WHEN $2 = 'enabled' THEN COALESCE(enabled_at, CURRENT_TIMESTAMP) ELSE enabled_at END
+            WHERE user_id = $1
+            RETURNING *
+            "#
+        )
+        .bind(&account.user_id)
+        .bind(status)
+        .bind(state.details_submitted)
+        .bind(state.payouts_enabled)
+        .bind(&state.requirements_due)
+        .bind(&state.disabled_reason)
+        .fetch_one(&self.pool)
+        .await?;
+
+        if status == PayoutAccountStatus::Enabled && account.status != PayoutAccountStatus::Enabled {
+            MarketplaceService::new(self.pool.clone()).create_notification(
+                &account.user_id,
+                "payouts_enabled",
+                "Payouts Enabled",
+                "Your payout account is verified and ready to receive your earnings",
+                None,
+                None,
+            ).await;
+        }
+
+        Ok(updated)
+    }
+
+    async fn fetch(&self, user_id: &str) -> Result<Option<PayoutAccount>, AppError> {
+        let account = sqlx::query_as::<_, PayoutAccount>("SELECT * FROM marketplace_payout_accounts WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(account)
+    }
+
+    fn provider(&self) -> Result<&Arc<dyn PayoutAccountProvider>, AppError> {
+        self.provider
+            .as_ref()
+            .ok_or_else(|| AppError::ServiceUnavailable("Payout onboarding is not available".to_string()))
+    }
+}
+
+pub struct PayoutAccountSyncJob;
+
+#[async_trait]
+impl Job for PayoutAccountSyncJob {
+    fn name(&self) -> &'static str {
+        "payout_account_sync"
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<(), AppError> {
+        PayoutAccountService::new(pool.clone())?.sync_unfinished().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,286 @@
+use crate::auth::AuthUser;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::marketplace::cart::{mark_paid, CartService};
+use crate::marketplace::payment_methods::PaymentMethodService;
+use crate::marketplace::payments::{
+    paypal_provider_from_config, PaymentProvider, ProviderCapture, ProviderOrderRequest, ProviderPaymentStatus,
+    ProviderRefundRequest, ProviderWebhookEvent,
+};
+use crate::marketplace::reversals::PaymentReversalService;
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{
+    CheckoutDetail, CheckoutStatus, PaymentType, PaypalOrder, PaypalOrderStatus, TransactionStatus,
+};
+use axum::http::HeaderMap;
+use bigdecimal::{BigDecimal, Zero};
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// `payment_method` of checkouts paid through PayPal
+pub const PAYPAL_PAYMENT_METHOD: &str = "paypal";
+
+// Reported as the provider of PayPal reversals
+const PROVIDER_NAME: &str = "paypal";
+
+const SYSTEM_ACTOR: &str = "system";
+
+pub fn is_paypal_payment(payment_method: &str) -> bool {
+    payment_method.trim().eq_ignore_ascii_case(PAYPAL_PAYMENT_METHOD)
+}
+
+/// PayPal checkout. The buyer approves an order on PayPal and the order is captured when
+/// they return; a capture that completes later is picked up from the webhook. The PayPal
+/// payer is saved as a payment method and reversals and disputes are recorded for admins.
+pub struct PaypalService {
+    pool: PgPool,
+    provider: Arc<dyn PaymentProvider>,
+}
+
+impl PaypalService {
+    pub fn new(pool: PgPool) -> Result<Self, AppError> {
+        let provider = paypal_provider_from_config(Config::get())?
+            .ok_or_else(|| AppError::ServiceUnavailable("PayPal is not available".to_string()))?;
+        Ok(Self { pool, provider })
+    }
+
+    pub fn with_provider(pool: PgPool, provider: Arc<dyn PaymentProvider>) -> Self {
+        Self { pool, provider }
+    }
+
+    /// Create a PayPal order for what is due on a checkout
+    pub async fn create_order(&self, auth_user: &AuthUser, checkout_id: Uuid) -> Result<PaypalOrder, AppError> {
+        let user_id = &auth_user.0.auth0_id;
+        let checkout = CartService::new(self.pool.clone()).fetch_checkout(checkout_id).await?;
+
+        if checkout.buyer_id != *user_id {
+            return Err(AppError::Forbidden("This checkout belongs to another user".to_string()));
+        }
+        if checkout.status == CheckoutStatus::Expired {
+            return Err(AppError::Conflict("Checkout expired before it was paid".to_string()));
+        }
+        if checkout.status != CheckoutStatus::AwaitingPayment {
+            return Err(AppError::Conflict("Checkout has already been paid".to_string()));
+        }
+
+        let due = &checkout.total_amount - &checkout.discount_amount;
+        if due <= BigDecimal::zero() {
+            return Err(AppError::UnprocessableEntity("Nothing is due".to_string()));
+        }
+
+        let id = Uuid::new_v4();
+        let order = self
+            .provider
+            .create_order(&ProviderOrderRequest::new(id.to_string(), &due, "Marketplace order"))
+            .await?;
+
+        let order = sqlx::query_as::<_, PaypalOrder>(
+            r#"
+            INSERT INTO marketplace_paypal_orders (
+                id, user_id, checkout_id, provider_order_id, amount, currency, approval_url, status, created_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, CURRENT_TIMESTAMP)
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(user_id)
+        .bind(checkout.id)
+        .bind(&order.order_id)
+        .bind(&due)
+        .bind(&Config::get().paypal.currency)
+        .bind(&order.approval_url)
+        .bind(PaypalOrderStatus::Created)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(order)
+    }
+
+    /// Capture an order the buyer approved. A capture PayPal holds for review leaves the
+    /// checkout awaiting payment until the webhook reports it completed.
+    pub async fn capture(&self, auth_user: &AuthUser, order_id: Uuid) -> Result<CheckoutDetail, AppError> {
+        let user_id = &auth_user.0.auth0_id;
+        let order = sqlx::query_as::<_, PaypalOrder>("SELECT * FROM marketplace_paypal_orders WHERE id = $1")
+            .bind(order_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("PayPal order not found".to_string()))?;
+
+        if order.user_id != *user_id {
+            return Err(AppError::Forbidden("This order belongs to another user".to_string()));
+        }
+        if order.status != PaypalOrderStatus::Created {
+            return Err(AppError::Conflict("PayPal order has already been captured".to_string()));
+        }
+
+        let capture = self.provider.capture_order(&order.provider_order_id).await?;
+        match &capture.status {
+            ProviderPaymentStatus::Captured => self.settle(&order.provider_order_id, &capture).await?,
+            ProviderPaymentStatus::Failed(reason) => {
+                self.fail(&order.provider_order_id, reason).await?;
+                return Err(AppError::UnprocessableEntity(format!("PayPal declined the payment: {}", reason)));
+            }
+            ProviderPaymentStatus::Pending => {
+                sqlx::query("UPDATE marketplace_paypal_orders SET payment_id = $2, payer_id = $3 WHERE id = $1")
+                    .bind(order.id)
+                    .bind(&capture.payment_id)
+                    .bind(&capture.customer_id)
+                    .execute(&self.pool)
+                    .await?;
+            }
+        }
+
+        CartService::new(self.pool.clone()).get_checkout(auth_user, order.checkout_id).await
+    }
+
+    /// Verify and apply a PayPal webhook
+    pub async fn handle_webhook(&self, payload: &[u8], headers: &HeaderMap) -> Result<(), AppError> {
+        let Some(event) = self.provider.parse_webhook(payload, headers).await? else {
+            return Ok(());
+        };
+
+        match event {
+            ProviderWebhookEvent::OrderCaptured { order_id, payment_id } => {
+                let payer_id = sqlx::query_scalar::<_, Option<String>>(
+                    "SELECT payer_id FROM marketplace_paypal_orders WHERE provider_order_id = $1"
+                )
+                .bind(&order_id)
+                .fetch_optional(&self.pool)
+                .await?
+                .flatten();
+
+                let capture = ProviderCapture {
+                    payment_id,
+                    customer_id: payer_id,
+                    status: ProviderPaymentStatus::Captured,
+                };
+                self.settle(&order_id, &capture).await
+            }
+            ProviderWebhookEvent::Payment { payment_id, status: ProviderPaymentStatus::Failed(reason) } => {
+                let order_id = sqlx::query_scalar::<_, String>(
+                    "SELECT provider_order_id FROM marketplace_paypal_orders WHERE payment_id = $1"
+                )
+                .bind(&payment_id)
+                .fetch_optional(&self.pool)
+                .await?;
+                match order_id {
+                    Some(order_id) => self.fail(&order_id, &reason).await,
+                    None => Ok(()),
+                }
+            }
+            ProviderWebhookEvent::Payment { .. } => Ok(()),
+            ProviderWebhookEvent::Reversal(reversal) => {
+                PaymentReversalService::new(self.pool.clone()).record(PROVIDER_NAME, &reversal).await
+            }
+        }
+    }
+
+    // Pay the checkout with a completed capture; repeated calls do nothing
+    async fn settle(&self, provider_order_id: &str, capture: &ProviderCapture) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let order = sqlx::query_as::<_, PaypalOrder>(
+            r#"
+            UPDATE marketplace_paypal_orders
+            SET status = $2, payment_id = $3, payer_id = COALESCE($4, payer_id), captured_at = CURRENT_TIMESTAMP
+            WHERE provider_order_id = $1 AND status = $5
+            RETURNING *
+            "#
+        )
+        .bind(provider_order_id)
+        .bind(PaypalOrderStatus::Captured)
+        .bind(&capture.payment_id)
+        .bind(&capture.customer_id)
+        .bind(PaypalOrderStatus::Created)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(order) = order else {
+            return Ok(());
+        };
+
+        // Refunds follow the payment method to the provider that holds the money
+        sqlx::query("UPDATE marketplace_checkouts SET payment_method = $2 WHERE id = $1 AND status = $3")
+            .bind(order.checkout_id)
+            .bind(PAYPAL_PAYMENT_METHOD)
+            .bind(CheckoutStatus::AwaitingPayment)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE marketplace_transactions SET payment_method = $2 WHERE checkout_id = $1 AND status = $3")
+            .bind(order.checkout_id)
+            .bind(PAYPAL_PAYMENT_METHOD)
+            .bind(TransactionStatus::Pending)
+            .execute(&mut *tx)
+            .await?;
+
+        let (checkout, pending) = match mark_paid(&mut tx, order.checkout_id, Some(&capture.payment_id), SYSTEM_ACTOR).await {
+            Ok((_, pending)) if pending.is_empty() => {
+                tx.rollback().await?;
+                return self.refund_unapplied(&order, &capture.payment_id, "Every item of the order was cancelled").await;
+            }
+            Ok(paid) => paid,
+            Err(AppError::Conflict(_)) => {
+                tx.rollback().await?;
+                return self.refund_unapplied(&order, &capture.payment_id, "Already paid by another payment").await;
+            }
+            Err(e) => return Err(e),
+        };
+
+        if let Some(payer_id) = &order.payer_id {
+            PaymentMethodService::save(&mut tx, &order.user_id, PaymentType::Paypal, payer_id).await?;
+        }
+
+        tx.commit().await?;
+        CartService::new(self.pool.clone()).notify_paid(&checkout, &pending).await;
+        Ok(())
+    }
+
+    // Give back a capture there is nothing left to apply to
+    async fn refund_unapplied(&self, order: &PaypalOrder, payment_id: &str, reason: &str) -> Result<(), AppError> {
+        let request = ProviderRefundRequest::new(payment_id, &order.amount, reason, order.id.to_string());
+        self.provider.refund(&request).await?;
+
+        sqlx::query(
+            r#"
+            UPDATE marketplace_paypal_orders
+            SET status = $2, payment_id = $3, failure_reason = $4, captured_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            "#
+        )
+        .bind(order.id)
+        .bind(PaypalOrderStatus::Refunded)
+        .bind(payment_id)
+        .bind(reason)
+        .execute(&self.pool)
+        .await?;
+
+        MarketplaceService::new(self.pool.clone()).create_notification(
+            &order.user_id,
+            "paypal_payment_refunded",
+            "Payment refunded",
+            &format!("Your PayPal payment of {} was refunded: {}", order.amount, reason.to_lowercase()),
+            None,
+            None,
+        ).await;
+        Ok(())
+    }
+
+    async fn fail(&self, provider_order_id: &str, reason: &str) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            UPDATE marketplace_paypal_orders
+            SET status = $2, failure_reason = $3
+            WHERE provider_order_id = $1 AND status = $4
+            "#
+        )
+        .bind(provider_order_id)
+        .bind(PaypalOrderStatus::Failed)
+        .bind(reason)
+        .bind(PaypalOrderStatus::Created)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
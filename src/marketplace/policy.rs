@@ -0,0 +1,246 @@
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::marketplace::brands::BrandService;
+use crate::marketplace::categories::CategoryService;
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{ListingPolicyRule, ListingPolicyRuleRequest};
+use crate::validation::FieldError;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// What a listing is checked on: its values after the create or edit being made
+pub struct PolicySubject<'a> {
+    pub category: &'a str,
+    pub brand_name: Option<&'a str>,
+    pub title: &'a str,
+    pub description: Option<&'a str>,
+    pub tags: &'a [String],
+    pub region: &'a str,
+}
+
+/// Prohibited items. Admins keep rules on categories, brands and keywords, optionally for
+/// one region only (e.g. alcohol vouchers where they can't be sold). Listings are checked
+/// when they are created and edited, and a matching rule rejects the change with its reason
+/// on the field that broke it, so the seller knows what to change.
+pub struct ListingPolicyService {
+    pool: PgPool,
+}
+
+impl ListingPolicyService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Reject the listing when an active rule matches it
+    pub async fn check(&self, subject: &PolicySubject<'_>) -> Result<(), AppError> {
+        let brand_name = subject.brand_name.map(str::trim).filter(|name| !name.is_empty());
+
+        // Category rules cover subcategories, so match on the listing's category and its ancestors
+        let rules = sqlx::query_as::<_, ListingPolicyRule>(
+            r#"
+            WITH RECURSIVE ancestors AS (
+                SELECT slug, parent_slug FROM marketplace_categories WHERE slug = $2
+                UNION ALL
+                SELECT c.slug, c.parent_slug FROM marketplace_categories c JOIN ancestors a ON c.slug = a.parent_slug
+            )
+            SELECT r.* FROM marketplace_listing_policy_rules r
+            WHERE r.is_active
+              AND (r.region IS NULL OR r.region = $1)
+              AND (r.category IS NULL OR r.category IN (SELECT slug FROM ancestors))
+              AND (r.brand_id IS NULL OR r.brand_id IN (
+                  SELECT id FROM marketplace_brands WHERE LOWER(name) = LOWER($3)
+                  UNION
+                  SELECT brand_id FROM marketplace_brand_aliases WHERE alias = $4
+              ))
+            ORDER BY r.created_at
+            "#
+        )
+        .bind(subject.region)
+        .bind(subject.category)
+        .bind(brand_name)
+        .bind(brand_name.map(BrandService::normalize_alias))
+        .fetch_all(&self.pool)
+        .await?;
+
+        let violations: Vec<FieldError> = rules
+            .iter()
+            .filter_map(|rule| violation(rule, subject))
+            .collect();
+        if violations.is_empty() {
+            return Ok(());
+        }
+
+        tracing::info!(
+            category = subject.category,
+            region = subject.region,
+            rules = violations.len(),
+            "listing rejected by policy rules"
+        );
+        Err(AppError::ValidationFailed(violations))
+    }
+
+    /// All rules, active ones first, newest first
+    pub async fn list_rules(&self, auth_user: &AuthUser) -> Result<Vec<ListingPolicyRule>, AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+
+        let rules = sqlx::query_as::<_, ListingPolicyRule>(
+            "SELECT * FROM marketplace_listing_policy_rules ORDER BY is_active DESC, created_at DESC"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rules)
+    }
+
+    pub async fn create_rule(
+        &self,
+        auth_user: &AuthUser,
+        request: ListingPolicyRuleRequest,
+    ) -> Result<ListingPolicyRule, AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+        let (category, brand_id) = self.resolve_conditions(&request).await?;
+
+        let rule = sqlx::query_as::<_, ListingPolicyRule>(
+            r#"
+            INSERT INTO marketplace_listing_policy_rules (
+                id, category, brand_id, keyword, region, reason, is_active, created_by, created_at, updated_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+            RETURNING *
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(category)
+        .bind(brand_id)
+        .bind(request.keyword.as_deref().map(normalize_keyword))
+        .bind(&request.region)
+        .bind(request.reason.trim())
+        .bind(request.is_active.unwrap_or(true))
+        .bind(&auth_user.0.auth0_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        tracing::info!(rule_id = %rule.id, created_by = %rule.created_by, "listing policy rule created");
+        Ok(rule)
+    }
+
+    /// Replace a rule's conditions and reason. Listings already live are not re-checked.
+    pub async fn update_rule(
+        &self,
+        auth_user: &AuthUser,
+        rule_id: Uuid,
+        request: ListingPolicyRuleRequest,
+    ) -> Result<ListingPolicyRule, AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+        let (category, brand_id) = self.resolve_conditions(&request).await?;
+
+        let rule = sqlx::query_as::<_, ListingPolicyRule>(
+            r#"
+            UPDATE marketplace_listing_policy_rules
+            SET category = $2, brand_id = $3, keyword = $4, region = $5, reason = $6,
+                is_active = $7, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(rule_id)
+        .bind(category)
+        .bind(brand_id)
+        .bind(request.keyword.as_deref().map(normalize_keyword))
+        .bind(&request.region)
+        .bind(request.reason.trim())
+        .bind(request.is_active.unwrap_or(true))
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Policy rule not found".to_string()))?;
+
+        tracing::info!(rule_id = %rule.id, updated_by = %auth_user.0.auth0_id, "listing policy rule updated");
+        Ok(rule)
+    }
+
+    pub async fn delete_rule(&self, auth_user: &AuthUser, rule_id: Uuid) -> Result<(), AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+
+        let result = sqlx::query("DELETE FROM marketplace_listing_policy_rules WHERE id = $1")
+            .bind(rule_id)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Policy rule not found".to_string()));
+        }
+
+        tracing::info!(%rule_id, deleted_by = %auth_user.0.auth0_id, "listing policy rule deleted");
+        Ok(())
+    }
+
+    // The category must be in the taxonomy and the brand in the registry
+    async fn resolve_conditions(
+        &self,
+        request: &ListingPolicyRuleRequest,
+    ) -> Result<(Option<String>, Option<Uuid>), AppError> {
+        let category = match request.category.as_deref() {
+            Some(category) => Some(CategoryService::new(self.pool.clone()).validate_category(category).await?),
+            None => None,
+        };
+        let brand_id = match request.brand_name.as_deref() {
+            Some(name) => Some(
+                BrandService::new(self.pool.clone())
+                    .resolve(name)
+                    .await?
+                    .ok_or_else(|| AppError::UnprocessableEntity(format!("Unknown brand: {}", name)))?
+                    .id,
+            ),
+            None => None,
+        };
+        Ok((category, brand_id))
+    }
+}
+
+/// Keywords are stored as their lowercase words separated by single spaces
+pub fn normalize_keyword(keyword: &str) -> String {
+    words(keyword).join(" ")
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// Whole words only, so "rum" does not match "instrument"
+fn mentions(text: &str, keyword: &str) -> bool {
+    let keyword = words(keyword);
+    !keyword.is_empty() && words(text).windows(keyword.len()).any(|window| window == keyword.as_slice())
+}
+
+// The rule's reason on the field that matched it: the one with the keyword, else the brand,
+// else the category. `None` when the rule's keyword is not mentioned anywhere.
+fn violation(rule: &ListingPolicyRule, subject: &PolicySubject<'_>) -> Option<FieldError> {
+    let region = rule.region.as_ref().map(|region| format!(" in {}", region)).unwrap_or_default();
+
+    let (field, what) = match &rule.keyword {
+        Some(keyword) => {
+            let field = if mentions(subject.title, keyword) {
+                "title"
+            } else if subject.description.is_some_and(|description| mentions(description, keyword)) {
+                "description"
+            } else if subject.tags.iter().any(|tag| mentions(tag, keyword)) {
+                "tags"
+            } else {
+                return None;
+            };
+            (field, format!("mentions \"{}\", which", keyword))
+        }
+        None if rule.brand_id.is_some() => (
+            "brand_name",
+            format!("{} items", subject.brand_name.map_or("This brand's", str::trim)),
+        ),
+        None => ("category", format!("Items in {}", subject.category)),
+    };
+
+    Some(FieldError {
+        field: field.to_string(),
+        message: format!("{} can't be listed{}: {}", what, region, rule.reason),
+    })
+}
@@ -0,0 +1,58 @@
+use crate::error::AppError;
+use crate::marketplace::{regions, replica};
+use crate::marketplace::translations::DEFAULT_LOCALE;
+use crate::models::marketplace::ProfilePreferences;
+use sqlx::PgPool;
+
+/// Display preferences on a user's profile. The locale is shown on the profile and sent
+/// with the user's emails and pushes so the messaging service renders them in it. The
+/// region scopes the user's feed and is where their new listings are sold.
+pub struct ProfilePreferenceService {
+    pool: PgPool,
+}
+
+impl ProfilePreferenceService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn get(&self, user_id: &str) -> Result<ProfilePreferences, AppError> {
+        load_preferences(&self.pool, user_id).await
+    }
+
+    pub async fn update(&self, user_id: &str, preferences: &ProfilePreferences) -> Result<ProfilePreferences, AppError> {
+        let preferences = sqlx::query_as::<_, ProfilePreferences>(
+            r#"
+            INSERT INTO marketplace_profile_preferences (user_id, locale, region, updated_at)
+            VALUES ($1, $2, $3, CURRENT_TIMESTAMP)
+            ON CONFLICT (user_id) DO UPDATE SET
+                locale = EXCLUDED.locale,
+                region = EXCLUDED.region,
+                updated_at = CURRENT_TIMESTAMP
+            RETURNING locale, region
+            "#
+        )
+        .bind(user_id)
+        .bind(&preferences.locale)
+        .bind(&preferences.region)
+        .fetch_one(&self.pool)
+        .await?;
+
+        replica::user_written(user_id);
+        Ok(preferences)
+    }
+}
+
+/// The user's preferences, or the defaults when they never saved any
+pub(crate) async fn load_preferences(pool: &PgPool, user_id: &str) -> Result<ProfilePreferences, AppError> {
+    let preferences = sqlx::query_as::<_, ProfilePreferences>(
+        "SELECT locale, region FROM marketplace_profile_preferences WHERE user_id = $1"
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(preferences.unwrap_or_else(|| ProfilePreferences {
+        locale: DEFAULT_LOCALE.to_string(),
+        region: regions::default_region().to_string(),
+    }))
+}
@@ -0,0 +1,130 @@
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::marketplace::away::SELLER_NOT_AWAY;
+use crate::marketplace::shadow_bans::NOT_SHADOW_BANNED;
+use crate::models::marketplace::PriceHistoryEntry;
+use bigdecimal::BigDecimal;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+pub struct PriceHistoryService {
+    pool: PgPool,
+}
+
+impl PriceHistoryService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Record a price change as part of the listing update transaction
+    pub async fn record_change(
+        tx: &mut Transaction<'_, Postgres>,
+        listing_id: Uuid,
+        old_price: &BigDecimal,
+        new_price: &BigDecimal,
+        changed_by: &str,
+    ) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_price_history (id, listing_id, old_price, new_price, changed_by, changed_at)
+            VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP)
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(listing_id)
+        .bind(old_price)
+        .bind(new_price)
+        .bind(changed_by)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_history(&self, listing_id: Uuid) -> Result<Vec<PriceHistoryEntry>, AppError> {
+        let history = sqlx::query_as::<_, PriceHistoryEntry>(
+            r#"
+            SELECT h.listing_id, h.old_price, h.new_price, h.changed_at
+            FROM marketplace_price_history h
+            JOIN marketplace_listings l ON l.id = h.listing_id
+            WHERE h.listing_id = $1 AND l.deleted_at IS NULL
+            ORDER BY h.changed_at ASC
+            "#
+        )
+        .bind(listing_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(history)
+    }
+
+    /// Alert everyone who saved the listing that its price dropped
+    pub async fn notify_price_drop(
+        &self,
+        listing_id: Uuid,
+        title: &str,
+        old_price: &BigDecimal,
+        new_price: &BigDecimal,
+    ) -> Result<(), AppError> {
+        if new_price >= old_price {
+            return Ok(());
+        }
+
+        // Queued for the fan-out worker, which applies each user's channel settings.
+        // Shadow-banned sellers' listings raise no alerts.
+        sqlx::query(&format!(
+            r#"
+            INSERT INTO marketplace_notification_queue (
+                id, user_id, notification_type, title, message,
+                related_listing_id, related_transaction_id, created_at
+            )
+            SELECT gen_random_uuid(), f.user_id, 'price_drop', 'Price Drop!', $2, $1, NULL, CURRENT_TIMESTAMP
+            FROM marketplace_favorites f
+            JOIN marketplace_listings l ON l.id = f.listing_id
+            WHERE f.listing_id = $1 AND {} AND {}
+            "#,
+            NOT_SHADOW_BANNED, SELLER_NOT_AWAY
+        ))
+        .bind(listing_id)
+        .bind(format!("{} dropped from {} to {}", title, old_price.round(2), new_price.round(2)))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn add_favorite(&self, auth_user: &AuthUser, listing_id: Uuid) -> Result<(), AppError> {
+        let listing = sqlx::query("SELECT 1 FROM marketplace_listings WHERE id = $1 AND deleted_at IS NULL")
+            .bind(listing_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        if listing.is_none() {
+            return Err(AppError::NotFound("Listing not found".to_string()));
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_favorites (user_id, listing_id, created_at)
+            VALUES ($1, $2, CURRENT_TIMESTAMP)
+            ON CONFLICT (user_id, listing_id) DO NOTHING
+            "#
+        )
+        .bind(&auth_user.0.auth0_id)
+        .bind(listing_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove_favorite(&self, auth_user: &AuthUser, listing_id: Uuid) -> Result<(), AppError> {
+        sqlx::query("DELETE FROM marketplace_favorites WHERE user_id = $1 AND listing_id = $2")
+            .bind(&auth_user.0.auth0_id)
+            .bind(listing_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,139 @@
+use crate::error::AppError;
+use crate::marketplace::brands::BrandService;
+use crate::models::marketplace::{ListingType, PricingBasis, PricingSuggestion};
+use bigdecimal::BigDecimal;
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+// Only recent sales reflect what buyers pay today
+const PRICING_WINDOW_DAYS: i32 = 180;
+
+// Fewer sales than this are too noisy to suggest a price from
+const MIN_SAMPLE_SIZE: i64 = 5;
+
+/// What a seller is about to list
+pub struct PricingQuery<'a> {
+    pub category: &'a str,
+    pub listing_type: Option<ListingType>,
+    pub brand_name: Option<&'a str>,
+    pub original_value: Option<&'a BigDecimal>,
+}
+
+// Quartiles of completed sales in one scope
+struct SaleStats {
+    sample_size: i64,
+    prices: Option<Vec<f64>>,
+    ratio_sample_size: i64,
+    ratios: Option<Vec<f64>>, // Sale price as a fraction of face value
+}
+
+/// Price suggestions from completed sales of similar listings. Sales of the same brand
+/// are preferred; with too few of those the whole category is used. When the face value
+/// is known the suggestion follows the typical discount depth, otherwise the typical price.
+pub struct PricingService {
+    pool: PgPool,
+}
+
+impl PricingService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn suggest(&self, query: &PricingQuery<'_>) -> Result<PricingSuggestion, AppError> {
+        let brand_id = match query.brand_name {
+            Some(name) => BrandService::new(self.pool.clone()).resolve(name).await?.map(|brand| brand.id),
+            None => None,
+        };
+
+        let mut scopes = Vec::with_capacity(2);
+        if brand_id.is_some() {
+            scopes.push((PricingBasis::Brand, brand_id));
+        }
+        scopes.push((PricingBasis::Category, None));
+
+        for (basis, brand_id) in scopes {
+            let stats = self.sale_stats(query, brand_id).await?;
+            if let Some(suggestion) = suggestion_from(basis, &stats, query.original_value) {
+                return Ok(suggestion);
+            }
+        }
+
+        Ok(PricingSuggestion {
+            basis: PricingBasis::None,
+            sample_size: 0,
+            suggested_price: None,
+            price_low: None,
+            price_high: None,
+            typical_discount_percentage: None,
+        })
+    }
+
+    async fn sale_stats(&self, query: &PricingQuery<'_>, brand_id: Option<Uuid>) -> Result<SaleStats, AppError> {
+        let row = sqlx::query(
+            r#"
+            SELECT
+                COUNT(*) as sample_size,
+                percentile_cont(ARRAY[0.25, 0.5, 0.75]) WITHIN GROUP (ORDER BY t.amount::float8) as prices,
+                COUNT(*) FILTER (WHERE l.original_value > 0) as ratio_sample_size,
+                percentile_cont(ARRAY[0.25, 0.5, 0.75]) WITHIN GROUP (ORDER BY (t.amount / l.original_value)::float8)
+                    FILTER (WHERE l.original_value > 0) as ratios
+            FROM marketplace_transactions t
+            JOIN marketplace_listings l ON l.id = t.listing_id
+            WHERE t.status = 'completed'
+            AND t.completed_at >= CURRENT_TIMESTAMP - make_interval(days => $1)
+            AND LOWER(l.category) = LOWER($2)
+            AND ($3::text IS NULL OR l.listing_type = $3)
+            AND ($4::uuid IS NULL OR l.brand_id = $4)
+            "#
+        )
+        .bind(PRICING_WINDOW_DAYS)
+        .bind(query.category)
+        .bind(query.listing_type)
+        .bind(brand_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(SaleStats {
+            sample_size: row.get("sample_size"),
+            prices: row.get("prices"),
+            ratio_sample_size: row.get("ratio_sample_size"),
+            ratios: row.get("ratios"),
+        })
+    }
+}
+
+fn suggestion_from(
+    basis: PricingBasis,
+    stats: &SaleStats,
+    original_value: Option<&BigDecimal>,
+) -> Option<PricingSuggestion> {
+    let ratios = stats.ratios.as_deref().filter(|_| stats.ratio_sample_size >= MIN_SAMPLE_SIZE);
+    let typical_discount_percentage = ratios.map(|r| ((1.0 - r[1]) * 1000.0).round() / 10.0);
+
+    // Scale the typical discount depth to this listing's face value
+    if let (Some(original), Some(ratios)) = (original_value, ratios) {
+        let price_at = |ratio: f64| (original * to_decimal(ratio)).round(2);
+        return Some(PricingSuggestion {
+            basis,
+            sample_size: stats.ratio_sample_size,
+            suggested_price: Some(price_at(ratios[1])),
+            price_low: Some(price_at(ratios[0])),
+            price_high: Some(price_at(ratios[2])),
+            typical_discount_percentage,
+        });
+    }
+
+    let prices = stats.prices.as_deref().filter(|_| stats.sample_size >= MIN_SAMPLE_SIZE)?;
+    Some(PricingSuggestion {
+        basis,
+        sample_size: stats.sample_size,
+        suggested_price: Some(to_decimal(prices[1]).round(2)),
+        price_low: Some(to_decimal(prices[0]).round(2)),
+        price_high: Some(to_decimal(prices[2]).round(2)),
+        typical_discount_percentage,
+    })
+}
+
+fn to_decimal(value: f64) -> BigDecimal {
+    value.to_string().parse::<BigDecimal>().unwrap_or_default()
+}
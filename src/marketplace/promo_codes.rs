@@ -0,0 +1,363 @@
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::marketplace::cart::{spread_discount, CartService};
+use crate::marketplace::categories::{category_subtree_condition, CategoryService};
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{
+    Checkout, CreatePromoCampaignRequest, MarketplaceTransaction, PromoCampaign, PromoCampaignUsage,
+    PromoDiscountType, PromoQuote,
+};
+use bigdecimal::{BigDecimal, Zero};
+use chrono::Utc;
+use sqlx::{PgConnection, PgPool, Postgres, Row, Transaction};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+pub const MIN_CODE_LENGTH: usize = 3;
+pub const MAX_CODE_LENGTH: usize = 32;
+
+// What a code takes off one order
+struct Redemption {
+    eligible: HashSet<Uuid>, // Listings the discount applies to
+    eligible_amount: BigDecimal,
+    discount_amount: BigDecimal,
+}
+
+// Redemptions only count while their checkout still has an item that was not cancelled
+const LIVE_REDEMPTION: &str = r#"
+    EXISTS (
+        SELECT 1 FROM marketplace_transactions t
+        WHERE t.checkout_id = r.checkout_id AND t.status <> 'cancelled'
+    )
+"#;
+
+/// Codes are matched case-insensitively and stored uppercase
+pub fn normalize_code(code: &str) -> String {
+    code.trim().to_uppercase()
+}
+
+pub fn is_valid_code(code: &str) -> bool {
+    (MIN_CODE_LENGTH..=MAX_CODE_LENGTH).contains(&code.chars().count())
+        && code.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Platform promo codes. Admins run campaigns with eligibility rules (first purchase only,
+/// one category, a minimum spend) and usage caps per user and overall. A buyer redeems a
+/// code at checkout: the discount lowers what they pay, is spread over the eligible items,
+/// and is funded by the platform, so sellers are still paid their full price.
+pub struct PromoCodeService {
+    pool: PgPool,
+}
+
+impl PromoCodeService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create_campaign(
+        &self,
+        auth_user: &AuthUser,
+        request: CreatePromoCampaignRequest,
+    ) -> Result<PromoCampaign, AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+
+        let code = normalize_code(&request.code);
+        let category = match request.category.as_deref() {
+            Some(category) => Some(CategoryService::new(self.pool.clone()).validate_category(category).await?),
+            None => None,
+        };
+
+        let exists = sqlx::query("SELECT 1 FROM marketplace_promo_campaigns WHERE code = $1")
+            .bind(&code)
+            .fetch_optional(&self.pool)
+            .await?;
+        if exists.is_some() {
+            return Err(AppError::Conflict(format!("Promo code {} already exists", code)));
+        }
+
+        let campaign = sqlx::query_as::<_, PromoCampaign>(
+            r#"
+            INSERT INTO marketplace_promo_campaigns (
+                id, code, description, discount_type, discount_value, max_discount, first_purchase_only,
+                category, min_amount, max_redemptions, max_per_user, starts_at, ends_at, is_active,
+                created_by, created_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, TRUE, $14, CURRENT_TIMESTAMP)
+            RETURNING *
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(&code)
+        .bind(request.description.as_deref().map(str::trim).filter(|d| !d.is_empty()))
+        .bind(request.discount_type)
+        .bind(request.discount_value.round(2))
+        .bind(request.max_discount.map(|max| max.round(2)))
+        .bind(request.first_purchase_only)
+        .bind(category)
+        .bind(request.min_amount.map(|min| min.round(2)))
+        .bind(request.max_redemptions)
+        .bind(request.max_per_user.unwrap_or(1))
+        .bind(request.starts_at.unwrap_or_else(Utc::now))
+        .bind(request.ends_at)
+        .bind(&auth_user.0.auth0_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(campaign)
+    }
+
+    /// All campaigns, newest first, with their live redemptions
+    pub async fn list_campaigns(&self, auth_user: &AuthUser) -> Result<Vec<PromoCampaignUsage>, AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+
+        let campaigns = sqlx::query_as::<_, PromoCampaign>(
+            "SELECT * FROM marketplace_promo_campaigns ORDER BY created_at DESC LIMIT 200"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let ids: Vec<Uuid> = campaigns.iter().map(|campaign| campaign.id).collect();
+        let usage = sqlx::query(&format!(
+            r#"
+            SELECT r.campaign_id, COUNT(*) as redemption_count, SUM(r.discount_amount) as discount_total
+            FROM marketplace_promo_redemptions r
+            WHERE r.campaign_id = ANY($1) AND {}
+            GROUP BY r.campaign_id
+            "#,
+            LIVE_REDEMPTION
+        ))
+        .bind(&ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(campaigns
+            .into_iter()
+            .map(|campaign| {
+                let row = usage.iter().find(|row| row.get::<Uuid, _>("campaign_id") == campaign.id);
+                PromoCampaignUsage {
+                    redemption_count: row.map_or(0, |row| row.get("redemption_count")),
+                    discount_total: row.map_or_else(BigDecimal::zero, |row| row.get("discount_total")),
+                    campaign,
+                }
+            })
+            .collect())
+    }
+
+    /// Stop a campaign; checkouts that already redeemed it keep their discount
+    pub async fn deactivate(&self, auth_user: &AuthUser, campaign_id: Uuid) -> Result<PromoCampaign, AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+
+        sqlx::query_as::<_, PromoCampaign>(
+            "UPDATE marketplace_promo_campaigns SET is_active = FALSE WHERE id = $1 RETURNING *"
+        )
+        .bind(campaign_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Promo campaign not found".to_string()))
+    }
+
+    /// What the code would take off the buyer's cart if they checked out now
+    pub async fn quote(&self, user_id: &str, code: &str) -> Result<PromoQuote, AppError> {
+        let cart = CartService::new(self.pool.clone()).get_cart(user_id).await?;
+        if cart.items.is_empty() {
+            return Err(AppError::BadRequest("Your cart is empty".to_string()));
+        }
+
+        let lines: Vec<(Uuid, BigDecimal)> = cart
+            .items
+            .iter()
+            .map(|item| (item.listing_id, &item.unit_price * BigDecimal::from(item.quantity)))
+            .collect();
+
+        let mut conn = self.pool.acquire().await?;
+        let campaign = find_campaign(&mut *conn, code, false).await?;
+        let redemption = check_redemption(&mut *conn, &campaign, user_id, &lines).await?;
+
+        Ok(PromoQuote {
+            code: campaign.code,
+            eligible_amount: redemption.eligible_amount,
+            amount_due: &cart.total_amount - &redemption.discount_amount,
+            discount_amount: redemption.discount_amount,
+            total_amount: cart.total_amount,
+        })
+    }
+
+    /// Apply a code to a checkout being created in `tx`. The discount is spread over the
+    /// eligible items and recorded on the checkout and the campaign's redemptions.
+    pub(crate) async fn redeem(
+        tx: &mut Transaction<'_, Postgres>,
+        checkout: &Checkout,
+        code: &str,
+        transactions: &mut [MarketplaceTransaction],
+    ) -> Result<Checkout, AppError> {
+        // Locking the campaign serializes redemptions so the caps hold under concurrent checkouts
+        let campaign = find_campaign(&mut **tx, code, true).await?;
+
+        let lines: Vec<(Uuid, BigDecimal)> = transactions
+            .iter()
+            .map(|transaction| (transaction.listing_id, transaction.amount.clone()))
+            .collect();
+        let Redemption { eligible, discount_amount, .. } =
+            check_redemption(&mut **tx, &campaign, &checkout.buyer_id, &lines).await?;
+
+        let eligible_transactions = transactions
+            .iter_mut()
+            .filter(|transaction| eligible.contains(&transaction.listing_id))
+            .collect();
+        spread_discount(tx, eligible_transactions, &discount_amount).await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_promo_redemptions (id, campaign_id, user_id, checkout_id, discount_amount, created_at)
+            VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP)
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(campaign.id)
+        .bind(&checkout.buyer_id)
+        .bind(checkout.id)
+        .bind(&discount_amount)
+        .execute(&mut **tx)
+        .await?;
+
+        let checkout = sqlx::query_as::<_, Checkout>(
+            "UPDATE marketplace_checkouts SET promo_code = $2, discount_amount = discount_amount + $3 WHERE id = $1 RETURNING *"
+        )
+        .bind(checkout.id)
+        .bind(&campaign.code)
+        .bind(&discount_amount)
+        .fetch_one(&mut **tx)
+        .await?;
+
+        Ok(checkout)
+    }
+}
+
+// A campaign that can be redeemed right now
+async fn find_campaign(conn: &mut PgConnection, code: &str, for_update: bool) -> Result<PromoCampaign, AppError> {
+    let campaign = sqlx::query_as::<_, PromoCampaign>(&format!(
+        "SELECT * FROM marketplace_promo_campaigns WHERE code = $1{}",
+        if for_update { " FOR UPDATE" } else { "" }
+    ))
+    .bind(normalize_code(code))
+    .fetch_optional(&mut *conn)
+    .await?
+    .filter(|campaign| campaign.is_active)
+    .ok_or_else(|| AppError::NotFound("Promo code not found".to_string()))?;
+
+    let now = Utc::now();
+    if campaign.starts_at > now {
+        return Err(AppError::UnprocessableEntity("This promo code is not valid yet".to_string()));
+    }
+    if campaign.ends_at.is_some_and(|ends_at| ends_at <= now) {
+        return Err(AppError::UnprocessableEntity("This promo code has expired".to_string()));
+    }
+
+    Ok(campaign)
+}
+
+// Check the eligibility rules and caps for an order of (listing, amount) lines
+async fn check_redemption(
+    conn: &mut PgConnection,
+    campaign: &PromoCampaign,
+    user_id: &str,
+    lines: &[(Uuid, BigDecimal)],
+) -> Result<Redemption, AppError> {
+    let usage = sqlx::query(&format!(
+        r#"
+        SELECT
+            COUNT(*) as total_count,
+            COUNT(*) FILTER (WHERE r.user_id = $2) as user_count
+        FROM marketplace_promo_redemptions r
+        WHERE r.campaign_id = $1 AND {}
+        "#,
+        LIVE_REDEMPTION
+    ))
+    .bind(campaign.id)
+    .bind(user_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if campaign
+        .max_redemptions
+        .is_some_and(|max| usage.get::<i64, _>("total_count") >= max as i64)
+    {
+        return Err(AppError::Conflict("This promo code has been fully redeemed".to_string()));
+    }
+    if usage.get::<i64, _>("user_count") >= campaign.max_per_user as i64 {
+        return Err(AppError::Conflict("You have already used this promo code".to_string()));
+    }
+
+    if campaign.first_purchase_only {
+        let purchased = sqlx::query(
+            "SELECT 1 FROM marketplace_transactions WHERE buyer_id = $1 AND status IN ('escrow', 'completed', 'disputed') LIMIT 1"
+        )
+        .bind(user_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+        if purchased.is_some() {
+            return Err(AppError::UnprocessableEntity("This promo code is only valid on your first purchase".to_string()));
+        }
+    }
+
+    let eligible = eligible_listings(conn, campaign, lines).await?;
+    let eligible_amount: BigDecimal = lines
+        .iter()
+        .filter(|(listing_id, _)| eligible.contains(listing_id))
+        .map(|(_, amount)| amount.clone())
+        .sum();
+
+    if eligible_amount.is_zero() {
+        return Err(AppError::UnprocessableEntity(format!(
+            "This promo code only applies to {} listings",
+            campaign.category.as_deref().unwrap_or("other")
+        )));
+    }
+    if let Some(min_amount) = campaign.min_amount.as_ref().filter(|min| eligible_amount < **min) {
+        return Err(AppError::UnprocessableEntity(format!(
+            "Spend at least {} on eligible items to use this promo code",
+            min_amount
+        )));
+    }
+
+    let discount_amount = discount_for(campaign, &eligible_amount);
+    if discount_amount.is_zero() {
+        return Err(AppError::UnprocessableEntity("This promo code takes nothing off these items".to_string()));
+    }
+
+    Ok(Redemption { eligible, eligible_amount, discount_amount })
+}
+
+// Listings of the order the campaign applies to; a category campaign includes its subcategories
+async fn eligible_listings(
+    conn: &mut PgConnection,
+    campaign: &PromoCampaign,
+    lines: &[(Uuid, BigDecimal)],
+) -> Result<HashSet<Uuid>, AppError> {
+    let listing_ids: Vec<Uuid> = lines.iter().map(|(listing_id, _)| *listing_id).collect();
+    let Some(category) = campaign.category.as_deref() else {
+        return Ok(listing_ids.into_iter().collect());
+    };
+
+    let eligible = sqlx::query_scalar::<_, Uuid>(&format!(
+        "SELECT id FROM marketplace_listings WHERE id = ANY($1){}",
+        category_subtree_condition("category", 2)
+    ))
+    .bind(&listing_ids)
+    .bind(category)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(eligible.into_iter().collect())
+}
+
+fn discount_for(campaign: &PromoCampaign, eligible_amount: &BigDecimal) -> BigDecimal {
+    let discount = match campaign.discount_type {
+        PromoDiscountType::Percentage => (eligible_amount * &campaign.discount_value / BigDecimal::from(100)).round(2),
+        PromoDiscountType::Fixed => campaign.discount_value.clone(),
+    };
+    let discount = match &campaign.max_discount {
+        Some(max) => discount.min(max.clone()),
+        None => discount,
+    };
+    discount.min(eligible_amount.clone())
+}
@@ -0,0 +1,502 @@
+use crate::auth::AuthUser;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::marketplace::authorization::AuthorizationPolicy;
+use crate::marketplace::jobs::Job;
+use crate::marketplace::payments::{payment_provider_from_config, ProviderRefundRequest};
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{
+    CreatePromotionRequest, CreditBalance, CreditEntry, GrantCreditsRequest, ListingStatus,
+    Promotion, PromotionPayment, PromotionStatus,
+};
+use async_trait::async_trait;
+use bigdecimal::BigDecimal;
+use chrono::{Duration, Utc};
+use sqlx::{PgPool, Postgres, Row, Transaction};
+use std::str::FromStr;
+use uuid::Uuid;
+
+pub const MIN_PROMOTION_DAYS: i32 = 1;
+pub const MAX_PROMOTION_DAYS: i32 = 30;
+
+// Price of one day of featured placement, in the marketplace currency
+const DAILY_PRICE: &str = "2.00";
+
+// Promotions can be booked this far ahead
+const MAX_SCHEDULE_AHEAD_DAYS: i64 = 90;
+
+// Featured listings per category at any one time, so featuring stays meaningful
+const FEATURED_SLOTS_PER_CATEGORY: i64 = 10;
+
+// Unpaid card promotions give up their slot after this long
+const PAYMENT_WINDOW_MINUTES: i32 = 30;
+
+// Credit history returned with the balance
+const CREDIT_HISTORY_LIMIT: i64 = 50;
+
+/// Paid featured placement. A promotion books one of a limited number of featured slots in
+/// the listing's category for a time window; while the window is open the listing carries
+/// `featured` and ranks first in the default sort. Sellers pay by card, confirmed like
+/// checkouts, or with loyalty credits, and every charge and refund is a billing entry.
+pub struct PromotionService {
+    pool: PgPool,
+}
+
+impl PromotionService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(&self, auth_user: &AuthUser, request: CreatePromotionRequest) -> Result<Promotion, AppError> {
+        let seller_id = &auth_user.0.auth0_id;
+
+        let listing = sqlx::query(
+            "SELECT seller_id, category, status FROM marketplace_listings WHERE id = $1 AND deleted_at IS NULL"
+        )
+        .bind(request.listing_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Listing not found".to_string()))?;
+
+        AuthorizationPolicy::new(self.pool.clone()).can_edit_listing(&auth_user.0.auth0_id, &listing.get::<String, _>("seller_id"))?;
+        if listing.get::<ListingStatus, _>("status") != ListingStatus::Active {
+            return Err(AppError::Conflict("Only active listings can be promoted".to_string()));
+        }
+        let category: String = listing.get("category");
+
+        let now = Utc::now();
+        let starts_at = request.starts_at.unwrap_or(now).max(now);
+        if starts_at > now + Duration::days(MAX_SCHEDULE_AHEAD_DAYS) {
+            return Err(AppError::BadRequest(format!(
+                "Promotions can be scheduled at most {} days ahead",
+                MAX_SCHEDULE_AHEAD_DAYS
+            )));
+        }
+        let ends_at = starts_at + Duration::days(request.days as i64);
+        let price = daily_price() * BigDecimal::from(request.days);
+
+        let mut tx = self.pool.begin().await?;
+
+        // Serialize bookings per category so two sellers cannot take the last slot at once
+        lock(&mut tx, &format!("promotion-slots:{}", category)).await?;
+
+        let overlapping = sqlx::query(
+            r#"
+            SELECT
+                COUNT(*) FILTER (WHERE listing_id = $1) as listing_count,
+                COUNT(*) as category_count
+            FROM marketplace_promotions
+            WHERE category = $2
+            AND status IN ('pending_payment', 'scheduled', 'active')
+            AND starts_at < $4 AND ends_at > $3
+            "#
+        )
+        .bind(request.listing_id)
+        .bind(&category)
+        .bind(starts_at)
+        .bind(ends_at)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if overlapping.get::<i64, _>("listing_count") > 0 {
+            return Err(AppError::Conflict("This listing is already promoted during that time".to_string()));
+        }
+        if overlapping.get::<i64, _>("category_count") >= FEATURED_SLOTS_PER_CATEGORY {
+            return Err(AppError::Conflict(
+                "All featured slots in this category are booked for that time".to_string()
+            ));
+        }
+
+        let status = match request.paid_with {
+            PromotionPayment::Card => PromotionStatus::PendingPayment,
+            PromotionPayment::Credits if starts_at <= now => PromotionStatus::Active,
+            PromotionPayment::Credits => PromotionStatus::Scheduled,
+        };
+
+        let promotion = sqlx::query_as::<_, Promotion>(
+            r#"
+            INSERT INTO marketplace_promotions (
+                id, listing_id, seller_id, category, starts_at, ends_at, status, price, paid_with, created_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, CURRENT_TIMESTAMP)
+            RETURNING *
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(request.listing_id)
+        .bind(seller_id)
+        .bind(&category)
+        .bind(starts_at)
+        .bind(ends_at)
+        .bind(status)
+        .bind(&price)
+        .bind(request.paid_with)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if request.paid_with == PromotionPayment::Credits {
+            let balance = locked_credit_balance(&mut tx, seller_id).await?;
+            if balance < price {
+                return Err(AppError::Conflict(format!(
+                    "Not enough credits: the promotion costs {} and your balance is {}",
+                    price, balance
+                )));
+            }
+
+            record_credit_entry(&mut tx, seller_id, &(-price.clone()), "Listing promotion", Some(promotion.id), seller_id).await?;
+            record_billing_entry(&mut tx, &promotion, &price, None, "Listing promotion").await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(promotion)
+    }
+
+    /// Promotions of the current seller, newest first
+    pub async fn list_mine(&self, auth_user: &AuthUser) -> Result<Vec<Promotion>, AppError> {
+        let promotions = sqlx::query_as::<_, Promotion>(
+            "SELECT * FROM marketplace_promotions WHERE seller_id = $1 ORDER BY created_at DESC LIMIT 100"
+        )
+        .bind(&auth_user.0.auth0_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(promotions)
+    }
+
+    /// Admin confirmation that the card payment for a promotion was captured
+    pub async fn confirm_payment(&self, auth_user: &AuthUser, promotion_id: Uuid, payment_id: &str) -> Result<Promotion, AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+
+        let mut tx = self.pool.begin().await?;
+
+        let promotion = sqlx::query_as::<_, Promotion>(
+            r#"
+            UPDATE marketplace_promotions
+            SET status = CASE WHEN starts_at <= CURRENT_TIMESTAMP THEN 'active' ELSE 'scheduled' END,
+                payment_id = $2
+            WHERE id = $1 AND status = 'pending_payment'
+            RETURNING *
+            "#
+        )
+        .bind(promotion_id)
+        .bind(payment_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(promotion) = promotion else {
+            self.fetch(promotion_id).await?;
+            return Err(AppError::Conflict("Promotion is not awaiting payment".to_string()));
+        };
+
+        record_billing_entry(&mut tx, &promotion, &promotion.price, Some(payment_id), "Listing promotion").await?;
+
+        tx.commit().await?;
+
+        Ok(promotion)
+    }
+
+    /// Cancel a promotion that has not started; anything already paid is refunded
+    pub async fn cancel(&self, auth_user: &AuthUser, promotion_id: Uuid) -> Result<Promotion, AppError> {
+        let user_id = &auth_user.0.auth0_id;
+        let marketplace = MarketplaceService::new(self.pool.clone());
+
+        let mut tx = self.pool.begin().await?;
+
+        let promotion = sqlx::query_as::<_, Promotion>(
+            "SELECT * FROM marketplace_promotions WHERE id = $1 FOR UPDATE"
+        )
+        .bind(promotion_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Promotion not found".to_string()))?;
+
+        if promotion.seller_id != *user_id && !marketplace.is_admin(user_id).await? {
+            return Err(AppError::Forbidden("You can only cancel your own promotions".to_string()));
+        }
+        if !matches!(promotion.status, PromotionStatus::PendingPayment | PromotionStatus::Scheduled) {
+            return Err(AppError::Conflict("Only promotions that have not started can be cancelled".to_string()));
+        }
+
+        let refunded = promotion.status == PromotionStatus::Scheduled;
+        if refunded {
+            match promotion.paid_with {
+                PromotionPayment::Credits => {
+                    record_credit_entry(
+                        &mut tx,
+                        &promotion.seller_id,
+                        &promotion.price,
+                        "Cancelled listing promotion",
+                        Some(promotion.id),
+                        user_id,
+                    ).await?;
+                    record_billing_entry(&mut tx, &promotion, &(-promotion.price.clone()), None, "Cancelled listing promotion").await?;
+                }
+                PromotionPayment::Card => {
+                    let payment_id = promotion.payment_id.as_deref().ok_or_else(|| {
+                        AppError::Conflict("Promotion has no captured payment to refund".to_string())
+                    })?;
+                    // The row stays locked and unchanged if the provider fails, so cancelling can be retried
+                    let request = ProviderRefundRequest::new(
+                        payment_id,
+                        &promotion.price,
+                        "Cancelled listing promotion",
+                        format!("promotion-{}", promotion.id),
+                    );
+                    let refund_id = payment_provider_from_config(Config::get())?
+                        .refund(&request)
+                        .await
+                        .map_err(|e| {
+                            tracing::warn!(promotion_id = %promotion.id, error = %e, "promotion refund failed");
+                            AppError::InternalError("The payment provider could not process the refund".to_string())
+                        })?;
+                    record_billing_entry(
+                        &mut tx,
+                        &promotion,
+                        &(-promotion.price.clone()),
+                        Some(&refund_id),
+                        "Cancelled listing promotion",
+                    ).await?;
+                }
+            }
+        }
+
+        let promotion = sqlx::query_as::<_, Promotion>(
+            r#"
+            UPDATE marketplace_promotions
+            SET status = 'cancelled', cancelled_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(promotion_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        if refunded && promotion.seller_id != *user_id {
+            marketplace.create_notification(
+                &promotion.seller_id,
+                "promotion_cancelled",
+                "Promotion Cancelled",
+                &format!("Your listing promotion was cancelled and {} was refunded", promotion.price),
+                Some(promotion.listing_id),
+                None,
+            ).await;
+        }
+
+        Ok(promotion)
+    }
+
+    pub async fn credit_balance(&self, auth_user: &AuthUser) -> Result<CreditBalance, AppError> {
+        let user_id = &auth_user.0.auth0_id;
+
+        let balance: BigDecimal = sqlx::query(
+            "SELECT COALESCE(SUM(amount), 0)::numeric as balance FROM marketplace_credit_entries WHERE user_id = $1"
+        )
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?
+        .get("balance");
+
+        let entries = sqlx::query_as::<_, CreditEntry>(
+            "SELECT * FROM marketplace_credit_entries WHERE user_id = $1 ORDER BY created_at DESC LIMIT $2"
+        )
+        .bind(user_id)
+        .bind(CREDIT_HISTORY_LIMIT)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(CreditBalance { balance, entries })
+    }
+
+    /// Admin grant of loyalty credits; a negative amount corrects an earlier grant
+    pub async fn grant_credits(&self, auth_user: &AuthUser, request: GrantCreditsRequest) -> Result<CreditEntry, AppError> {
+        let marketplace = MarketplaceService::new(self.pool.clone());
+        marketplace.require_admin(auth_user).await?;
+
+        let amount = request.amount.round(2);
+        let mut tx = self.pool.begin().await?;
+
+        let balance = locked_credit_balance(&mut tx, &request.user_id).await?;
+        if balance.clone() + &amount < BigDecimal::from(0) {
+            return Err(AppError::Conflict(format!(
+                "The user's balance of {} cannot go below zero",
+                balance
+            )));
+        }
+
+        let entry = record_credit_entry(
+            &mut tx,
+            &request.user_id,
+            &amount,
+            request.reason.trim(),
+            None,
+            &auth_user.0.auth0_id,
+        ).await?;
+
+        tx.commit().await?;
+
+        if amount > BigDecimal::from(0) {
+            marketplace.create_notification(
+                &request.user_id,
+                "credits_granted",
+                "You've received credits",
+                &format!("{} credits were added to your balance: {}", amount, request.reason.trim()),
+                None,
+                None,
+            ).await;
+        }
+
+        Ok(entry)
+    }
+
+    /// Start due promotions, end finished ones and release unpaid card bookings
+    pub async fn advance_schedule(&self) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            UPDATE marketplace_promotions SET status = 'active'
+            WHERE status = 'scheduled' AND starts_at <= CURRENT_TIMESTAMP AND ends_at > CURRENT_TIMESTAMP
+            "#
+        )
+        .execute(&self.pool)
+        .await?;
+
+        let expired = sqlx::query_as::<_, Promotion>(
+            r#"
+            UPDATE marketplace_promotions SET status = 'expired'
+            WHERE status IN ('scheduled', 'active') AND ends_at <= CURRENT_TIMESTAMP
+            RETURNING *
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            UPDATE marketplace_promotions SET status = 'cancelled', cancelled_at = CURRENT_TIMESTAMP
+            WHERE status = 'pending_payment' AND created_at <= CURRENT_TIMESTAMP - make_interval(mins => $1)
+            "#
+        )
+        .bind(PAYMENT_WINDOW_MINUTES)
+        .execute(&self.pool)
+        .await?;
+
+        let marketplace = MarketplaceService::new(self.pool.clone());
+        for promotion in expired {
+            marketplace.create_notification(
+                &promotion.seller_id,
+                "promotion_ended",
+                "Promotion Ended",
+                "Your listing is no longer featured. Promote it again to keep it at the top of search",
+                Some(promotion.listing_id),
+                None,
+            ).await;
+        }
+
+        Ok(())
+    }
+
+    async fn fetch(&self, promotion_id: Uuid) -> Result<Promotion, AppError> {
+        sqlx::query_as::<_, Promotion>("SELECT * FROM marketplace_promotions WHERE id = $1")
+            .bind(promotion_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Promotion not found".to_string()))
+    }
+}
+
+fn daily_price() -> BigDecimal {
+    BigDecimal::from_str(DAILY_PRICE).unwrap_or_default()
+}
+
+async fn lock(tx: &mut Transaction<'_, Postgres>, key: &str) -> Result<(), AppError> {
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+        .bind(key)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+// Balance with the user's credits locked until the transaction ends
+async fn locked_credit_balance(tx: &mut Transaction<'_, Postgres>, user_id: &str) -> Result<BigDecimal, AppError> {
+    lock(tx, &format!("credits:{}", user_id)).await?;
+
+    let balance = sqlx::query(
+        "SELECT COALESCE(SUM(amount), 0)::numeric as balance FROM marketplace_credit_entries WHERE user_id = $1"
+    )
+    .bind(user_id)
+    .fetch_one(&mut **tx)
+    .await?
+    .get("balance");
+
+    Ok(balance)
+}
+
+async fn record_credit_entry(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: &str,
+    amount: &BigDecimal,
+    reason: &str,
+    promotion_id: Option<Uuid>,
+    created_by: &str,
+) -> Result<CreditEntry, AppError> {
+    let entry = sqlx::query_as::<_, CreditEntry>(
+        r#"
+        INSERT INTO marketplace_credit_entries (id, user_id, amount, reason, promotion_id, created_by, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, CURRENT_TIMESTAMP)
+        RETURNING *
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(amount)
+    .bind(reason)
+    .bind(promotion_id)
+    .bind(created_by)
+    .fetch_one(&mut **tx)
+    .await?;
+
+    Ok(entry)
+}
+
+async fn record_billing_entry(
+    tx: &mut Transaction<'_, Postgres>,
+    promotion: &Promotion,
+    amount: &BigDecimal,
+    provider_reference: Option<&str>,
+    description: &str,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        INSERT INTO marketplace_billing_entries (
+            id, user_id, promotion_id, amount, paid_with, provider_reference, description, created_at
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, CURRENT_TIMESTAMP)
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(&promotion.seller_id)
+    .bind(promotion.id)
+    .bind(amount)
+    .bind(promotion.paid_with)
+    .bind(provider_reference)
+    .bind(description)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Moves promotions through their schedule and releases unpaid bookings
+pub struct PromotionScheduleJob;
+
+#[async_trait]
+impl Job for PromotionScheduleJob {
+    fn name(&self) -> &'static str {
+        "promotion_schedule"
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<(), AppError> {
+        PromotionService::new(pool.clone()).advance_schedule().await
+    }
+}
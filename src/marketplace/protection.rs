@@ -0,0 +1,406 @@
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::marketplace::jobs::Job;
+use crate::marketplace::loyalty::LoyaltyService;
+use crate::marketplace::outbox::{event_types, OutboxService};
+use crate::marketplace::refunds::RefundService;
+use crate::marketplace::transaction_state::{TransactionEvent, TransactionStateMachine};
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{
+    CodeValidity, MarketplaceTransaction, ProtectionClaim, ProtectionClaimStatus, TransactionStatus,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{PgPool, Postgres, Row, Transaction};
+use uuid::Uuid;
+
+// Buyers can claim within this many hours of first revealing their code
+const CLAIM_WINDOW_HOURS: i64 = 48;
+
+// Sellers who don't respond within this many hours are refunded against automatically
+const SELLER_RESPONSE_HOURS: i32 = 24;
+
+// Overdue claims refunded per job pass
+const OVERDUE_BATCH_SIZE: i64 = 50;
+
+// Actor recorded for automatic decisions
+const SYSTEM_ACTOR: &str = "system";
+
+const CLAIM_REASON: &str = "Purchase protection: code does not work";
+
+/// Money-back guarantee for coupon purchases. A claim freezes escrow by disputing the
+/// transaction, then resolves as a refund (cancellation with a refund event) or a release
+/// to the seller. Claims are refunded without review when the automatic check shows the
+/// code is invalid or the seller does not respond in time.
+pub struct PurchaseProtectionService {
+    pool: PgPool,
+}
+
+impl PurchaseProtectionService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn open_claim(
+        &self,
+        auth_user: &AuthUser,
+        transaction_id: Uuid,
+        details: Option<&str>,
+    ) -> Result<ProtectionClaim, AppError> {
+        let marketplace = MarketplaceService::new(self.pool.clone());
+        let transaction = marketplace.get_transaction_by_id(transaction_id).await?;
+
+        if transaction.buyer_id != auth_user.0.auth0_id {
+            return Err(AppError::Forbidden("Only the buyer can claim purchase protection".to_string()));
+        }
+        if transaction.status != TransactionStatus::Escrow {
+            return Err(AppError::Conflict(
+                "Purchase protection only applies while the payment is held in escrow".to_string(),
+            ));
+        }
+
+        let first_reveal = self
+            .first_reveal(transaction_id)
+            .await?
+            .ok_or_else(|| AppError::Conflict("Reveal your code before reporting that it doesn't work".to_string()))?;
+        if Utc::now() > first_reveal + Duration::hours(CLAIM_WINDOW_HOURS) {
+            return Err(AppError::Conflict(format!(
+                "Claims must be made within {} hours of revealing the code",
+                CLAIM_WINDOW_HOURS
+            )));
+        }
+
+        let (validity, validity_notes) = self.check_code(&transaction).await?;
+
+        let mut tx = self.pool.begin().await?;
+
+        let disputed = TransactionStateMachine::apply(
+            &mut tx,
+            &transaction,
+            TransactionEvent::Disputed,
+            &auth_user.0.auth0_id,
+            Some(CLAIM_REASON),
+        ).await?;
+
+        let claim = sqlx::query_as::<_, ProtectionClaim>(
+            r#"
+            INSERT INTO marketplace_protection_claims (
+                id, transaction_id, buyer_id, seller_id, details, status,
+                validity_check, validity_notes, response_due_at, created_at
+            ) VALUES (
+                $1, $2, $3, $4, $5, 'awaiting_seller',
+                $6, $7, CURRENT_TIMESTAMP + make_interval(hours => $8), CURRENT_TIMESTAMP
+            )
+            RETURNING *
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(transaction_id)
+        .bind(&transaction.buyer_id)
+        .bind(&transaction.seller_id)
+        .bind(details)
+        .bind(validity)
+        .bind(&validity_notes)
+        .bind(SELLER_RESPONSE_HOURS)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        OutboxService::record(&mut tx, "transaction", transaction_id, event_types::TRANSACTION_DISPUTED, &disputed).await?;
+        tx.commit().await?;
+
+        if validity == CodeValidity::Invalid {
+            return self.refund(&claim, SYSTEM_ACTOR, validity_notes.as_deref()).await;
+        }
+
+        marketplace.create_notification(
+            &transaction.seller_id,
+            "protection_claim_opened",
+            "A buyer reports your code doesn't work",
+            &format!(
+                "The payment is frozen. Respond within {} hours or the buyer is refunded automatically.",
+                SELLER_RESPONSE_HOURS
+            ),
+            Some(transaction.listing_id),
+            Some(transaction_id),
+        ).await;
+
+        Ok(claim)
+    }
+
+    /// Visible to both parties and admins
+    pub async fn get_claim(&self, auth_user: &AuthUser, transaction_id: Uuid) -> Result<ProtectionClaim, AppError> {
+        let claim = sqlx::query_as::<_, ProtectionClaim>(
+            "SELECT * FROM marketplace_protection_claims WHERE transaction_id = $1"
+        )
+        .bind(transaction_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("No purchase protection claim for this transaction".to_string()))?;
+
+        let user_id = &auth_user.0.auth0_id;
+        let is_party = claim.buyer_id == *user_id || claim.seller_id == *user_id;
+        if !is_party && !MarketplaceService::new(self.pool.clone()).is_admin(user_id).await? {
+            return Err(AppError::Forbidden("You are not part of this transaction".to_string()));
+        }
+
+        Ok(claim)
+    }
+
+    /// Seller's rebuttal; the claim then waits for an admin decision
+    pub async fn respond(
+        &self,
+        auth_user: &AuthUser,
+        transaction_id: Uuid,
+        message: &str,
+    ) -> Result<ProtectionClaim, AppError> {
+        let claim = self.get_claim(auth_user, transaction_id).await?;
+        if claim.seller_id != auth_user.0.auth0_id {
+            return Err(AppError::Forbidden("Only the seller can respond to this claim".to_string()));
+        }
+
+        let claim = sqlx::query_as::<_, ProtectionClaim>(
+            r#"
+            UPDATE marketplace_protection_claims
+            SET status = 'seller_responded', seller_response = $2, seller_responded_at = CURRENT_TIMESTAMP
+            WHERE id = $1 AND status = 'awaiting_seller' AND response_due_at > CURRENT_TIMESTAMP
+            RETURNING *
+            "#
+        )
+        .bind(claim.id)
+        .bind(message)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::Conflict("This claim no longer accepts a response".to_string()))?;
+
+        MarketplaceService::new(self.pool.clone()).create_notification(
+            &claim.buyer_id,
+            "protection_claim_responded",
+            "The seller responded to your claim",
+            "Our team will review both sides and decide on your refund",
+            None,
+            Some(transaction_id),
+        ).await;
+
+        Ok(claim)
+    }
+
+    /// Admin queue; without a status filter, all claims still awaiting a decision
+    pub async fn get_queue(
+        &self,
+        auth_user: &AuthUser,
+        status: Option<ProtectionClaimStatus>,
+    ) -> Result<Vec<ProtectionClaim>, AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+
+        let queue = sqlx::query_as::<_, ProtectionClaim>(
+            r#"
+            SELECT * FROM marketplace_protection_claims
+            WHERE status = $1 OR ($1 IS NULL AND status IN ('awaiting_seller', 'seller_responded'))
+            ORDER BY created_at ASC
+            LIMIT 100
+            "#
+        )
+        .bind(status)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(queue)
+    }
+
+    /// Admin decision: refund the buyer or release the escrow to the seller
+    pub async fn resolve(
+        &self,
+        auth_user: &AuthUser,
+        claim_id: Uuid,
+        refund: bool,
+        notes: Option<&str>,
+    ) -> Result<ProtectionClaim, AppError> {
+        MarketplaceService::new(self.pool.clone()).authorization().can_resolve_dispute(&auth_user.0.auth0_id).await?;
+
+        let claim = sqlx::query_as::<_, ProtectionClaim>("SELECT * FROM marketplace_protection_claims WHERE id = $1")
+            .bind(claim_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Claim not found".to_string()))?;
+
+        if refund {
+            self.refund(&claim, &auth_user.0.auth0_id, notes).await
+        } else {
+            self.reject(&claim, &auth_user.0.auth0_id, notes).await
+        }
+    }
+
+    /// Refund claims whose seller let the response deadline pass. Returns how many were refunded.
+    pub async fn refund_overdue(&self) -> Result<usize, AppError> {
+        let overdue = sqlx::query_as::<_, ProtectionClaim>(
+            r#"
+            SELECT * FROM marketplace_protection_claims
+            WHERE status = 'awaiting_seller' AND response_due_at <= CURRENT_TIMESTAMP
+            ORDER BY response_due_at
+            LIMIT $1
+            "#
+        )
+        .bind(OVERDUE_BATCH_SIZE)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut refunded = 0;
+        for claim in overdue {
+            match self.refund(&claim, SYSTEM_ACTOR, Some("The seller did not respond in time")).await {
+                Ok(_) => refunded += 1,
+                Err(e) => tracing::warn!(claim_id = %claim.id, error = %e, "automatic refund failed"),
+            }
+        }
+
+        Ok(refunded)
+    }
+
+    async fn refund(&self, claim: &ProtectionClaim, actor_id: &str, notes: Option<&str>) -> Result<ProtectionClaim, AppError> {
+        let marketplace = MarketplaceService::new(self.pool.clone());
+        let transaction = marketplace.get_transaction_by_id(claim.transaction_id).await?;
+
+        // The claim stays open if the provider fails, so the job retries overdue ones
+        RefundService::new(self.pool.clone())?
+            .refund_transaction(transaction.id, None, "Refunded under purchase protection", actor_id)
+            .await?;
+
+        let mut tx = self.pool.begin().await?;
+        let claim = close_claim(&mut tx, claim.id, ProtectionClaimStatus::Refunded, actor_id, notes).await?;
+        tx.commit().await?;
+
+        self.notify_outcome(&marketplace, &transaction, true, notes).await;
+
+        Ok(claim)
+    }
+
+    async fn reject(&self, claim: &ProtectionClaim, actor_id: &str, notes: Option<&str>) -> Result<ProtectionClaim, AppError> {
+        let marketplace = MarketplaceService::new(self.pool.clone());
+        let transaction = marketplace.get_transaction_by_id(claim.transaction_id).await?;
+
+        let mut tx = self.pool.begin().await?;
+
+        let claim = close_claim(&mut tx, claim.id, ProtectionClaimStatus::Rejected, actor_id, notes).await?;
+
+        let completed = TransactionStateMachine::apply(
+            &mut tx,
+            &transaction,
+            TransactionEvent::EscrowReleased,
+            actor_id,
+            None,
+        ).await?;
+        // As when the buyer completes it; covers transactions paid before codes were allocated at payment
+        MarketplaceService::allocate_coupon_code(&mut tx, &transaction).await?;
+        LoyaltyService::award(&mut tx, &completed).await?;
+
+        OutboxService::record(&mut tx, "transaction", transaction.id, event_types::TRANSACTION_COMPLETED, &completed).await?;
+        tx.commit().await?;
+
+        marketplace.update_trust_score_after_transaction(&transaction.seller_id, true).await?;
+        self.notify_outcome(&marketplace, &transaction, false, notes).await;
+
+        Ok(claim)
+    }
+
+    async fn notify_outcome(
+        &self,
+        marketplace: &MarketplaceService,
+        transaction: &MarketplaceTransaction,
+        refunded: bool,
+        notes: Option<&str>,
+    ) {
+        let (buyer_title, seller_title) = if refunded {
+            ("You've been refunded", "Buyer refunded under purchase protection")
+        } else {
+            ("Your claim was not accepted", "Claim rejected, payment released")
+        };
+        let message = notes.unwrap_or("The purchase protection claim has been resolved");
+
+        for (user_id, title) in [(&transaction.buyer_id, buyer_title), (&transaction.seller_id, seller_title)] {
+            marketplace.create_notification(
+                user_id,
+                "protection_claim_resolved",
+                title,
+                message,
+                Some(transaction.listing_id),
+                Some(transaction.id),
+            ).await;
+        }
+    }
+
+    // When the buyer first revealed the code allocated to this transaction
+    async fn first_reveal(&self, transaction_id: Uuid) -> Result<Option<DateTime<Utc>>, AppError> {
+        let first_reveal: Option<DateTime<Utc>> = sqlx::query(
+            r#"
+            SELECT MIN(r.revealed_at) as first_reveal
+            FROM marketplace_coupon_codes c
+            JOIN marketplace_coupon_reveals r
+                ON r.listing_id = c.listing_id AND r.user_id = c.allocated_to AND r.revealed_at >= c.allocated_at
+            WHERE c.allocated_transaction_id = $1
+            "#
+        )
+        .bind(transaction_id)
+        .fetch_one(&self.pool)
+        .await?
+        .get("first_reveal");
+
+        Ok(first_reveal)
+    }
+
+    // Codes can't be redeemed from here, so the check covers what the listing itself proves
+    async fn check_code(&self, transaction: &MarketplaceTransaction) -> Result<(CodeValidity, Option<String>), AppError> {
+        let expiration_date: Option<DateTime<Utc>> = sqlx::query(
+            "SELECT expiration_date FROM marketplace_listings_all WHERE id = $1"
+        )
+        .bind(transaction.listing_id)
+        .fetch_one(&self.pool)
+        .await?
+        .get("expiration_date");
+
+        match expiration_date {
+            Some(expired_at) if expired_at <= Utc::now() => Ok((
+                CodeValidity::Invalid,
+                Some(format!("The code expired on {}", expired_at.format("%Y-%m-%d"))),
+            )),
+            _ => Ok((CodeValidity::Unknown, None)),
+        }
+    }
+}
+
+async fn close_claim(
+    tx: &mut Transaction<'_, Postgres>,
+    claim_id: Uuid,
+    status: ProtectionClaimStatus,
+    actor_id: &str,
+    notes: Option<&str>,
+) -> Result<ProtectionClaim, AppError> {
+    sqlx::query_as::<_, ProtectionClaim>(
+        r#"
+        UPDATE marketplace_protection_claims
+        SET status = $2, resolved_by = $3, resolution_notes = $4, resolved_at = CURRENT_TIMESTAMP
+        WHERE id = $1 AND status IN ('awaiting_seller', 'seller_responded')
+        RETURNING *
+        "#
+    )
+    .bind(claim_id)
+    .bind(status)
+    .bind(actor_id)
+    .bind(notes)
+    .fetch_optional(&mut **tx)
+    .await?
+    .ok_or_else(|| AppError::Conflict("Claim has already been resolved".to_string()))
+}
+
+/// Refunds buyers whose claims the seller left unanswered
+pub struct ProtectionClaimJob;
+
+#[async_trait]
+impl Job for ProtectionClaimJob {
+    fn name(&self) -> &'static str {
+        "protection_claim_refunds"
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<(), AppError> {
+        PurchaseProtectionService::new(pool.clone()).refund_overdue().await?;
+        Ok(())
+    }
+}
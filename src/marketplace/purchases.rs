@@ -0,0 +1,243 @@
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::marketplace::jobs::Job;
+use crate::marketplace::money;
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{
+    PaginatedResponse, Purchase, PurchaseFilters, TransactionStatus, DEFAULT_CODE_EXPIRY_REMINDER_DAYS,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::postgres::PgRow;
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+// Reminders sent per job pass
+const REMINDER_BATCH_SIZE: i64 = 200;
+
+// Rows in one CSV export
+const MAX_EXPORT_ROWS: i64 = 10_000;
+
+// Listing fields come from the snapshot taken at the sale. The code is available while the
+// buyer keeps access to it, i.e. until the purchase is cancelled or refunded.
+const PURCHASE_SELECT: &str = r#"
+    SELECT t.id as transaction_id, t.listing_id,
+           COALESCE(t.listing_title, l.title) as title,
+           COALESCE(t.listing_brand_name, l.brand_name) as brand_name,
+           t.seller_id, COALESCE(u.username, 'unknown') as seller_username,
+           t.amount, t.status, t.created_at as purchased_at, t.completed_at,
+           l.expiration_date as code_expires_at,
+           EXISTS (SELECT 1 FROM marketplace_coupon_access a WHERE a.transaction_id = t.id) as code_available,
+           (
+               SELECT MAX(r.revealed_at) FROM marketplace_coupon_reveals r
+               WHERE r.listing_id = t.listing_id AND r.user_id = t.buyer_id AND r.revealed_at >= t.created_at
+           ) as last_revealed_at
+    FROM marketplace_transactions t
+    JOIN marketplace_listings_all l ON l.id = t.listing_id
+    LEFT JOIN users u ON u.auth0_id = t.seller_id
+    WHERE t.buyer_id = $1 AND ($2::text IS NULL OR t.status = $2)
+    ORDER BY t.created_at DESC, t.id
+"#;
+
+/// A buyer's purchase history. Each purchase shows the listing's title and brand as they
+/// were when it was sold, and whether its code can still be revealed again; revealing it
+/// goes through the usual reveal token and is logged like any other reveal. Buyers are
+/// reminded before codes they never looked at expire, as many days ahead as their
+/// notification settings say.
+pub struct PurchaseHistoryService {
+    pool: PgPool,
+}
+
+impl PurchaseHistoryService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// The caller's purchases, newest first
+    pub async fn list(&self, auth_user: &AuthUser, filters: PurchaseFilters) -> Result<PaginatedResponse<Purchase>, AppError> {
+        let limit = filters.limit.unwrap_or(20).clamp(1, 100);
+        let page = filters.page.unwrap_or(0).max(0);
+        let offset = page
+            .checked_mul(limit)
+            .ok_or_else(|| AppError::BadRequest("page is out of range".to_string()))?;
+        let status = filters.status.map(|status| status.as_str());
+
+        let total_count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM marketplace_transactions WHERE buyer_id = $1 AND ($2::text IS NULL OR status = $2)"
+        )
+        .bind(&auth_user.0.auth0_id)
+        .bind(status)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let purchases = sqlx::query(&format!("{} LIMIT $3 OFFSET $4", PURCHASE_SELECT))
+            .bind(&auth_user.0.auth0_id)
+            .bind(status)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await?
+            .iter()
+            .map(purchase_from_row)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(PaginatedResponse {
+            has_more: offset + (purchases.len() as i64) < total_count,
+            items: purchases,
+            total_count,
+            page,
+            limit,
+        })
+    }
+
+    /// The caller's purchases as CSV, one row per purchase and newest first. Codes are never
+    /// included; they are only shown through a logged reveal.
+    pub async fn export_csv(&self, auth_user: &AuthUser, filters: PurchaseFilters) -> Result<Vec<u8>, AppError> {
+        let purchases = sqlx::query(&format!("{} LIMIT $3", PURCHASE_SELECT))
+            .bind(&auth_user.0.auth0_id)
+            .bind(filters.status.map(|status| status.as_str()))
+            .bind(MAX_EXPORT_ROWS)
+            .fetch_all(&self.pool)
+            .await?
+            .iter()
+            .map(purchase_from_row)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        let csv_error = |e: csv::Error| AppError::InternalError(format!("CSV error: {}", e));
+        writer
+            .write_record([
+                "transaction_id",
+                "purchased_at",
+                "title",
+                "brand",
+                "seller",
+                "amount",
+                "currency",
+                "status",
+                "completed_at",
+                "code_expires_at",
+                "last_revealed_at",
+            ])
+            .map_err(csv_error)?;
+
+        let date = |date: Option<DateTime<Utc>>| date.map(|date| date.to_rfc3339()).unwrap_or_default();
+        for purchase in &purchases {
+            writer
+                .write_record([
+                    purchase.transaction_id.to_string(),
+                    purchase.purchased_at.to_rfc3339(),
+                    purchase.title.clone(),
+                    purchase.brand_name.clone().unwrap_or_default(),
+                    purchase.seller_username.clone(),
+                    purchase.amount.round(2).to_string(),
+                    purchase.currency.clone(),
+                    purchase.status.as_str().to_string(),
+                    date(purchase.completed_at),
+                    date(purchase.code_expires_at),
+                    date(purchase.last_revealed_at),
+                ])
+                .map_err(csv_error)?;
+        }
+
+        writer
+            .into_inner()
+            .map_err(|e| AppError::InternalError(format!("CSV error: {}", e)))
+    }
+
+    /// Remind buyers of codes they never revealed that expire within their reminder window,
+    /// once per purchase. Returns how many reminders were sent.
+    pub async fn remind_expiring_codes(&self) -> Result<usize, AppError> {
+        let due = sqlx::query(
+            r#"
+            UPDATE marketplace_transactions t
+            SET code_expiry_reminded_at = CURRENT_TIMESTAMP
+            FROM marketplace_listings_all l
+            WHERE t.id IN (
+                SELECT pt.id FROM marketplace_transactions pt
+                JOIN marketplace_listings_all pl ON pl.id = pt.listing_id
+                LEFT JOIN marketplace_notification_settings s ON s.user_id = pt.buyer_id
+                WHERE pt.status IN ('escrow', 'completed') AND pt.code_expiry_reminded_at IS NULL
+                AND COALESCE(s.code_expiry_reminder_days, $1) > 0
+                AND pl.expiration_date > CURRENT_TIMESTAMP
+                AND pl.expiration_date <= CURRENT_TIMESTAMP + make_interval(days => COALESCE(s.code_expiry_reminder_days, $1))
+                AND EXISTS (SELECT 1 FROM marketplace_coupon_access a WHERE a.transaction_id = pt.id)
+                AND NOT EXISTS (
+                    SELECT 1 FROM marketplace_coupon_reveals r
+                    WHERE r.listing_id = pt.listing_id AND r.user_id = pt.buyer_id AND r.revealed_at >= pt.created_at
+                )
+                ORDER BY pl.expiration_date
+                LIMIT $2
+            )
+            AND l.id = t.listing_id
+            RETURNING t.id, t.buyer_id, t.listing_id, COALESCE(t.listing_title, l.title) as title, l.expiration_date
+            "#
+        )
+        .bind(DEFAULT_CODE_EXPIRY_REMINDER_DAYS)
+        .bind(REMINDER_BATCH_SIZE)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let marketplace = MarketplaceService::new(self.pool.clone());
+        for row in &due {
+            let transaction_id: Uuid = row.get("id");
+            let buyer_id: String = row.get("buyer_id");
+            let title: String = row.get("title");
+            let expires_at: DateTime<Utc> = row.get("expiration_date");
+
+            marketplace.create_notification(
+                &buyer_id,
+                "code_expiring",
+                "Your code expires soon",
+                &format!(
+                    "You haven't used your code for \"{}\" yet. It expires on {}; find it under Purchases.",
+                    title,
+                    expires_at.format("%Y-%m-%d")
+                ),
+                Some(row.get("listing_id")),
+                Some(transaction_id),
+            ).await;
+        }
+
+        if !due.is_empty() {
+            tracing::info!(reminded = due.len(), "code expiry reminders sent");
+        }
+        Ok(due.len())
+    }
+}
+
+fn purchase_from_row(row: &PgRow) -> Result<Purchase, sqlx::Error> {
+    let amount = row.try_get("amount")?;
+    Ok(Purchase {
+        transaction_id: row.try_get("transaction_id")?,
+        listing_id: row.try_get("listing_id")?,
+        title: row.try_get("title")?,
+        brand_name: row.try_get("brand_name")?,
+        seller_id: row.try_get("seller_id")?,
+        seller_username: row.try_get("seller_username")?,
+        currency: money::currency().to_string(),
+        amount_minor: money::to_minor_units(&amount),
+        amount,
+        status: row.try_get::<TransactionStatus, _>("status")?,
+        purchased_at: row.try_get("purchased_at")?,
+        completed_at: row.try_get("completed_at")?,
+        code_expires_at: row.try_get("code_expires_at")?,
+        code_available: row.try_get("code_available")?,
+        last_revealed_at: row.try_get("last_revealed_at")?,
+    })
+}
+
+/// Reminds buyers of unrevealed codes that are about to expire
+pub struct CodeExpiryReminderJob;
+
+#[async_trait]
+impl Job for CodeExpiryReminderJob {
+    fn name(&self) -> &'static str {
+        "code_expiry_reminders"
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<(), AppError> {
+        PurchaseHistoryService::new(pool.clone()).remind_expiring_codes().await?;
+        Ok(())
+    }
+}
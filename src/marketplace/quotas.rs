@@ -0,0 +1,148 @@
+use crate::auth::AuthUser;
+use crate::config::{Config, SellerQuotaSettings};
+use crate::error::AppError;
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{SellerLimits, SellerQuotaOverride, SellerQuotaTier, SetSellerQuotaRequest};
+use sqlx::{PgConnection, PgPool, Row};
+
+/// Quota tier a seller falls in. Verification only raises the limit for sellers who also
+/// hold the trusted score.
+pub fn quota_tier(trust_score: f64, verified_seller: bool, settings: &SellerQuotaSettings) -> SellerQuotaTier {
+    if trust_score < settings.trusted_min_score {
+        SellerQuotaTier::Standard
+    } else if verified_seller {
+        SellerQuotaTier::Verified
+    } else {
+        SellerQuotaTier::Trusted
+    }
+}
+
+pub fn tier_max_listings(tier: SellerQuotaTier, settings: &SellerQuotaSettings) -> i64 {
+    match tier {
+        SellerQuotaTier::Standard => settings.standard_max_listings,
+        SellerQuotaTier::Trusted => settings.trusted_max_listings,
+        SellerQuotaTier::Verified => settings.verified_max_listings,
+    }
+}
+
+/// Limits on how many listings a seller may have live or in review at once
+pub struct SellerQuotaService {
+    pool: PgPool,
+}
+
+impl SellerQuotaService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn get_limits(&self, user_id: &str) -> Result<SellerLimits, AppError> {
+        let mut conn = self.pool.acquire().await?;
+        limits(&mut conn, user_id).await
+    }
+
+    /// Fail unless the seller can add `additional` listings. Call inside the transaction
+    /// creating them: it holds a per-seller lock until commit so concurrent creates are
+    /// counted against each other.
+    pub async fn ensure_capacity(conn: &mut PgConnection, user_id: &str, additional: i64) -> Result<(), AppError> {
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext('seller_quota:' || $1))")
+            .bind(user_id)
+            .execute(&mut *conn)
+            .await?;
+
+        let limits = limits(conn, user_id).await?;
+        if limits.remaining_listings < additional {
+            let hint = match (limits.overridden, limits.tier) {
+                (false, SellerQuotaTier::Standard) => "; a higher trust score raises the limit",
+                (false, SellerQuotaTier::Trusted) => "; verified sellers get a higher limit",
+                _ => "",
+            };
+            return Err(AppError::Forbidden(format!(
+                "Active listing limit reached: {} of {} in use. Mark listings sold or delete some to list more{}",
+                limits.active_listings, limits.max_active_listings, hint
+            )));
+        }
+        Ok(())
+    }
+
+    pub async fn set_override(
+        &self,
+        auth_user: &AuthUser,
+        user_id: &str,
+        request: SetSellerQuotaRequest,
+    ) -> Result<SellerQuotaOverride, AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+
+        let quota = sqlx::query_as::<_, SellerQuotaOverride>(
+            r#"
+            INSERT INTO marketplace_seller_quota_overrides (user_id, max_active_listings, reason, set_by, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+            ON CONFLICT (user_id) DO UPDATE SET
+                max_active_listings = EXCLUDED.max_active_listings,
+                reason = EXCLUDED.reason,
+                set_by = EXCLUDED.set_by,
+                updated_at = CURRENT_TIMESTAMP
+            RETURNING *
+            "#
+        )
+        .bind(user_id)
+        .bind(request.max_active_listings)
+        .bind(&request.reason)
+        .bind(&auth_user.0.auth0_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        tracing::info!(user_id, max_active_listings = quota.max_active_listings, set_by = %quota.set_by, "seller quota overridden");
+        Ok(quota)
+    }
+
+    /// Put the seller back on their tier's limit
+    pub async fn remove_override(&self, auth_user: &AuthUser, user_id: &str) -> Result<(), AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+
+        let removed = sqlx::query("DELETE FROM marketplace_seller_quota_overrides WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        if removed == 0 {
+            return Err(AppError::NotFound("Seller has no quota override".to_string()));
+        }
+        Ok(())
+    }
+}
+
+async fn limits(conn: &mut PgConnection, user_id: &str) -> Result<SellerLimits, AppError> {
+    let row = sqlx::query(
+        r#"
+        SELECT
+            COALESCE(ts.trust_score, 50.0)::float8 as trust_score,
+            COALESCE(ts.verified_seller, FALSE) as verified_seller,
+            o.max_active_listings as override_max,
+            (
+                SELECT COUNT(*) FROM marketplace_listings l
+                WHERE l.seller_id = $1 AND l.status IN ('active', 'pending_review') AND l.deleted_at IS NULL
+            ) as active_listings
+        FROM (SELECT $1::text as user_id) u
+        LEFT JOIN marketplace_trust_scores ts ON ts.user_id = u.user_id
+        LEFT JOIN marketplace_seller_quota_overrides o ON o.user_id = u.user_id
+        "#
+    )
+    .bind(user_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let settings = &Config::get().seller_quotas;
+    let tier = quota_tier(row.get("trust_score"), row.get("verified_seller"), settings);
+    let override_max: Option<i32> = row.get("override_max");
+    let max_active_listings = override_max.map_or_else(|| tier_max_listings(tier, settings), i64::from);
+    let active_listings: i64 = row.get("active_listings");
+
+    Ok(SellerLimits {
+        tier,
+        max_active_listings,
+        active_listings,
+        remaining_listings: (max_active_listings - active_listings).max(0),
+        overridden: override_max.is_some(),
+    })
+}
@@ -11,9 +11,12 @@ pub struct RateLimiter {
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 pub enum ActionType {
     CreateListing,
+    BulkCreateListing,
     CreateTransaction,
     CreateReview,
     SendMessage,
+    ReportExpiredCode,
+    SendStepUpCode,
 }
 
 #[derive(Debug, Clone)]
@@ -32,6 +35,11 @@ impl RateLimiter {
             window_minutes: 60, // 10 listings per hour
         });
         
+        limits.insert(ActionType::BulkCreateListing, RateLimit {
+            max_attempts: 200,
+            window_minutes: 60, // 200 bulk-imported rows per hour
+        });
+        
         limits.insert(ActionType::CreateTransaction, RateLimit {
             max_attempts: 50,
             window_minutes: 60, // 50 purchases per hour
@@ -46,6 +54,16 @@ impl RateLimiter {
             max_attempts: 100,
             window_minutes: 60, // 100 messages per hour
         });
+        
+        limits.insert(ActionType::ReportExpiredCode, RateLimit {
+            max_attempts: 10,
+            window_minutes: 60, // 10 expired code reports per hour
+        });
+        
+        limits.insert(ActionType::SendStepUpCode, RateLimit {
+            max_attempts: 5,
+            window_minutes: 60, // 5 security codes emailed per hour
+        });
 
         Self { pool, limits }
     }
@@ -55,6 +73,16 @@ impl RateLimiter {
         &self,
         user_id: &str,
         action: ActionType,
+    ) -> Result<RateLimitResult, AppError> {
+        self.check_and_increment_by(user_id, action, 1).await
+    }
+
+    /// Check if `amount` actions are allowed and charge them as a single increment
+    pub async fn check_and_increment_by(
+        &self,
+        user_id: &str,
+        action: ActionType,
+        amount: i32,
     ) -> Result<RateLimitResult, AppError> {
         let limit = self.limits.get(&action)
             .ok_or_else(|| AppError::InternalError("Unknown action type".to_string()))?;
@@ -87,19 +115,19 @@ impl RateLimiter {
                 let count = row.count.unwrap_or(0);
                 let window_start_time = row.window_start.unwrap_or(Utc::now().naive_utc());
                 
-                if count >= limit.max_attempts {
+                if count + amount > limit.max_attempts {
                     // Rate limit exceeded
                     let reset_time = window_start_time + Duration::minutes(limit.window_minutes as i64);
                     return Ok(RateLimitResult {
                         allowed: false,
-                        remaining: 0,
+                        remaining: (limit.max_attempts - count).max(0),
                         reset_at: chrono::DateTime::<Utc>::from_naive_utc_and_offset(reset_time, Utc),
                         retry_after: (reset_time - Utc::now().naive_utc()).num_seconds().max(0) as u64,
                     });
                 }
 
                 // Increment counter
-                let new_count = count + 1;
+                let new_count = count + amount;
                 sqlx::query!(
                     r#"
                     UPDATE marketplace_rate_limits
@@ -124,24 +152,34 @@ impl RateLimiter {
                 })
             }
             None => {
+                if amount > limit.max_attempts {
+                    return Ok(RateLimitResult {
+                        allowed: false,
+                        remaining: limit.max_attempts,
+                        reset_at: Utc::now(),
+                        retry_after: 0,
+                    });
+                }
+
                 // No record or expired, create new one
                 sqlx::query!(
                     r#"
                     INSERT INTO marketplace_rate_limits (user_id, action_type, count, window_start)
-                    VALUES ($1, $2, 1, $3)
+                    VALUES ($1, $2, $4, $3)
                     ON CONFLICT (user_id, action_type) 
-                    DO UPDATE SET count = 1, window_start = $3
+                    DO UPDATE SET count = $4, window_start = $3
                     "#,
                     user_id,
                     action_str,
-                    Utc::now().naive_utc()
+                    Utc::now().naive_utc(),
+                    amount
                 )
                 .execute(&self.pool)
                 .await?;
 
                 Ok(RateLimitResult {
                     allowed: true,
-                    remaining: limit.max_attempts - 1,
+                    remaining: limit.max_attempts - amount,
                     reset_at: Utc::now() + Duration::minutes(limit.window_minutes as i64),
                     retry_after: 0,
                 })
@@ -149,6 +187,31 @@ impl RateLimiter {
         }
     }
 
+    /// Give back `amount` actions that were charged but did not happen
+    pub async fn release(
+        &self,
+        user_id: &str,
+        action: ActionType,
+        amount: i32,
+    ) -> Result<(), AppError> {
+        let action_str = self.action_to_string(&action);
+
+        sqlx::query!(
+            r#"
+            UPDATE marketplace_rate_limits
+            SET count = GREATEST(count - $1, 0)
+            WHERE user_id = $2 AND action_type = $3
+            "#,
+            amount,
+            user_id,
+            action_str
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
     /// Check rate limit without incrementing
     pub async fn check_only(
         &self,
@@ -216,9 +279,12 @@ impl RateLimiter {
     fn action_to_string(&self, action: &ActionType) -> &'static str {
         match action {
             ActionType::CreateListing => "create_listing",
+            ActionType::BulkCreateListing => "bulk_create_listing",
             ActionType::CreateTransaction => "create_transaction",
             ActionType::CreateReview => "create_review",
             ActionType::SendMessage => "send_message",
+            ActionType::ReportExpiredCode => "report_expired_code",
+            ActionType::SendStepUpCode => "send_step_up_code",
         }
     }
 }
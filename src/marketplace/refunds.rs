@@ -0,0 +1,344 @@
+use crate::auth::AuthUser;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::marketplace::outbox::{event_types, OutboxService};
+use crate::marketplace::payments::{
+    payment_provider_from_config, paypal_provider_from_config, PaymentProvider, ProviderRefundRequest,
+};
+use crate::marketplace::paypal::is_paypal_payment;
+use crate::marketplace::transaction_state::{TransactionEvent, TransactionStateMachine};
+use crate::marketplace::wallet::{is_wallet_payment, WalletService, WALLET_PAYMENT_METHOD};
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{
+    MarketplaceTransaction, RefundStatus, TransactionRefund, TransactionRefundedEvent, TransactionStatus,
+};
+use bigdecimal::{BigDecimal, Zero};
+use sqlx::{PgPool, Postgres, Transaction};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Full and partial refunds. The refunded amount is reserved on the transaction before
+/// the payment provider is called, so concurrent refunds can never exceed what was paid;
+/// a failed provider call releases the reservation again.
+pub struct RefundService {
+    pool: PgPool,
+    provider: Arc<dyn PaymentProvider>,
+    paypal: Option<Arc<dyn PaymentProvider>>,
+}
+
+impl RefundService {
+    pub fn new(pool: PgPool) -> Result<Self, AppError> {
+        Ok(Self {
+            pool,
+            provider: payment_provider_from_config(Config::get())?,
+            paypal: paypal_provider_from_config(Config::get())?,
+        })
+    }
+
+    /// Refund every payment, PayPal included, through `provider`
+    pub fn with_provider(pool: PgPool, provider: Arc<dyn PaymentProvider>) -> Self {
+        Self { pool, paypal: Some(provider.clone()), provider }
+    }
+
+    /// Seller- or admin-initiated refund. Without `amount` the remaining balance is refunded.
+    pub async fn refund(
+        &self,
+        auth_user: &AuthUser,
+        transaction_id: Uuid,
+        amount: Option<BigDecimal>,
+        reason: &str,
+    ) -> Result<TransactionRefund, AppError> {
+        let user_id = &auth_user.0.auth0_id;
+        let marketplace = MarketplaceService::new(self.pool.clone());
+        let transaction = marketplace.get_transaction_by_id(transaction_id).await?;
+
+        if transaction.seller_id != *user_id && !marketplace.is_admin(user_id).await? {
+            return Err(AppError::Forbidden("Only the seller or an admin can refund this transaction".to_string()));
+        }
+
+        let refund = self.refund_transaction(transaction_id, amount, reason, user_id).await?;
+
+        let message = format!("You've been refunded {} for this purchase", refund.amount);
+        marketplace.create_notification(
+            &transaction.buyer_id,
+            "transaction_refunded",
+            "You've been refunded",
+            &message,
+            Some(transaction.listing_id),
+            Some(transaction_id),
+        ).await;
+
+        Ok(refund)
+    }
+
+    /// Refund without a permission check, for flows that already decided the buyer is owed money
+    pub(crate) async fn refund_transaction(
+        &self,
+        transaction_id: Uuid,
+        amount: Option<BigDecimal>,
+        reason: &str,
+        actor_id: &str,
+    ) -> Result<TransactionRefund, AppError> {
+        let (transaction, refund) = self.reserve(transaction_id, amount, reason, actor_id).await?;
+
+        let Some(payment_id) = transaction.payment_id.as_deref() else {
+            self.fail(&refund, "transaction has no payment id").await?;
+            return Err(AppError::Conflict("Transaction has no captured payment to refund".to_string()));
+        };
+
+        // Wallet purchases are refunded to the wallet rather than through the provider
+        let to_wallet = transaction.payment_method.as_deref().is_some_and(is_wallet_payment);
+        let provider_refund_id = if to_wallet {
+            format!("{}:{}", WALLET_PAYMENT_METHOD, refund.id)
+        } else {
+            // PayPal purchases go back through PayPal, everything else through the main provider
+            let provider = if transaction.payment_method.as_deref().is_some_and(is_paypal_payment) {
+                self.paypal.as_ref()
+            } else {
+                Some(&self.provider)
+            };
+            let Some(provider) = provider else {
+                self.fail(&refund, "PayPal is not configured").await?;
+                return Err(AppError::InternalError("PayPal is not configured".to_string()));
+            };
+
+            let request = ProviderRefundRequest::new(payment_id, &refund.amount, reason, refund.id.to_string());
+            match provider.refund(&request).await {
+                Ok(id) => id,
+                Err(e) => {
+                    tracing::warn!(refund_id = %refund.id, error = %e, "payment provider refund failed");
+                    self.fail(&refund, &e.to_string()).await?;
+                    return Err(AppError::InternalError("The payment provider could not process the refund".to_string()));
+                }
+            }
+        };
+
+        let fully_refunded = transaction.refunded_amount.clone() + &refund.amount >= refundable(&transaction);
+
+        let mut tx = self.pool.begin().await?;
+
+        let refund = sqlx::query_as::<_, TransactionRefund>(
+            r#"
+            UPDATE marketplace_refunds
+            SET status = $2, provider_refund_id = $3, completed_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            RETURNING id, transaction_id, amount, reason, initiated_by, status, provider_refund_id, created_at, completed_at
+            "#
+        )
+        .bind(refund.id)
+        .bind(RefundStatus::Succeeded)
+        .bind(&provider_refund_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        record_ledger_entries(&mut tx, &transaction, "refund", Some(refund.id), &refund.amount).await?;
+        if to_wallet {
+            WalletService::credit_refund(&mut tx, &transaction, &refund).await?;
+        }
+
+        // A fully refunded sale that is still in escrow never completes; the code goes back on sale if unseen
+        let mut updated = fetch_transaction(&mut tx, transaction_id).await?;
+        if fully_refunded && matches!(transaction.status, TransactionStatus::Escrow | TransactionStatus::Disputed) {
+            updated = TransactionStateMachine::apply(
+                &mut tx,
+                &updated,
+                TransactionEvent::Cancelled,
+                actor_id,
+                Some(reason),
+            ).await?;
+
+            if MarketplaceService::release_coupon_code(&mut tx, &transaction).await? {
+                MarketplaceService::restock_listing(&mut tx, transaction.listing_id).await?;
+            }
+        }
+
+        OutboxService::record(
+            &mut tx,
+            "transaction",
+            transaction_id,
+            event_types::TRANSACTION_REFUNDED,
+            &TransactionRefundedEvent { transaction: updated, refund: refund.clone() },
+        ).await?;
+        tx.commit().await?;
+
+        // Partial refunds are goodwill and leave the seller's record alone
+        if fully_refunded {
+            MarketplaceService::new(self.pool.clone())
+                .update_trust_score_after_refund(
+                    &transaction.seller_id,
+                    transaction.status == TransactionStatus::Completed,
+                )
+                .await?;
+        }
+
+        Ok(refund)
+    }
+
+    /// Refunds of a transaction, visible to its buyer, seller and admins
+    pub async fn list_refunds(&self, auth_user: &AuthUser, transaction_id: Uuid) -> Result<Vec<TransactionRefund>, AppError> {
+        let marketplace = MarketplaceService::new(self.pool.clone());
+        let transaction = marketplace.get_transaction_by_id(transaction_id).await?;
+        marketplace.authorization().can_view_transaction(&auth_user.0.auth0_id, &transaction).await?;
+
+        let refunds = sqlx::query_as::<_, TransactionRefund>(
+            r#"
+            SELECT id, transaction_id, amount, reason, initiated_by, status, provider_refund_id, created_at, completed_at
+            FROM marketplace_refunds
+            WHERE transaction_id = $1
+            ORDER BY created_at DESC
+            "#
+        )
+        .bind(transaction_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(refunds)
+    }
+
+    // Validate the amount against what is left and hold it while the provider is called
+    async fn reserve(
+        &self,
+        transaction_id: Uuid,
+        amount: Option<BigDecimal>,
+        reason: &str,
+        actor_id: &str,
+    ) -> Result<(MarketplaceTransaction, TransactionRefund), AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let transaction = sqlx::query_as::<_, MarketplaceTransaction>(
+            "SELECT * FROM marketplace_transactions WHERE id = $1 FOR UPDATE"
+        )
+        .bind(transaction_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Transaction not found".to_string()))?;
+
+        if !matches!(
+            transaction.status,
+            TransactionStatus::Escrow | TransactionStatus::Disputed | TransactionStatus::Completed
+        ) {
+            return Err(AppError::Conflict(format!(
+                "Cannot refund a {} transaction",
+                transaction.status.as_str()
+            )));
+        }
+
+        let remaining = refundable(&transaction) - &transaction.refunded_amount;
+        let amount = amount.unwrap_or_else(|| remaining.clone()).round(2);
+
+        if amount <= BigDecimal::zero() {
+            return Err(AppError::BadRequest("Refund amount must be greater than zero".to_string()));
+        }
+        if amount > remaining {
+            return Err(AppError::BadRequest(format!("At most {} can still be refunded", remaining)));
+        }
+
+        let refund = sqlx::query_as::<_, TransactionRefund>(
+            r#"
+            INSERT INTO marketplace_refunds (id, transaction_id, amount, reason, initiated_by, status, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, CURRENT_TIMESTAMP)
+            RETURNING id, transaction_id, amount, reason, initiated_by, status, provider_refund_id, created_at, completed_at
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(transaction_id)
+        .bind(&amount)
+        .bind(reason)
+        .bind(actor_id)
+        .bind(RefundStatus::Pending)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE marketplace_transactions SET refunded_amount = refunded_amount + $2 WHERE id = $1")
+            .bind(transaction_id)
+            .bind(&amount)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok((transaction, refund))
+    }
+
+    // The provider rejected the refund: release the reserved amount
+    async fn fail(&self, refund: &TransactionRefund, error: &str) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            "UPDATE marketplace_refunds SET status = $2, error = $3, completed_at = CURRENT_TIMESTAMP WHERE id = $1"
+        )
+        .bind(refund.id)
+        .bind(RefundStatus::Failed)
+        .bind(error)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE marketplace_transactions SET refunded_amount = refunded_amount - $2 WHERE id = $1")
+            .bind(refund.transaction_id)
+            .bind(&refund.amount)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+async fn fetch_transaction(tx: &mut Transaction<'_, Postgres>, transaction_id: Uuid) -> Result<MarketplaceTransaction, AppError> {
+    let transaction = sqlx::query_as::<_, MarketplaceTransaction>("SELECT * FROM marketplace_transactions WHERE id = $1")
+        .bind(transaction_id)
+        .fetch_one(&mut **tx)
+        .await?;
+    Ok(transaction)
+}
+
+// What the buyer paid for the item; a promo discount was funded by the platform and is not refunded
+pub(crate) fn refundable(transaction: &MarketplaceTransaction) -> BigDecimal {
+    &transaction.amount - &transaction.discount_amount
+}
+
+// The buyer gets `amount` back; the seller gives up their share pro rata and the platform
+// covers the rest, which includes its fee, the regional tax withheld on it and any promo
+// discount it funded.
+// Returns the seller's share.
+pub(crate) async fn record_ledger_entries(
+    tx: &mut Transaction<'_, Postgres>,
+    transaction: &MarketplaceTransaction,
+    entry_type: &str,
+    refund_id: Option<Uuid>,
+    amount: &BigDecimal,
+) -> Result<BigDecimal, AppError> {
+    let fee = transaction.platform_fee.clone().unwrap_or_default() + &transaction.tax_amount;
+    let paid = refundable(transaction);
+    let seller_share = if paid.is_zero() {
+        BigDecimal::zero()
+    } else {
+        ((&transaction.amount - fee) * amount / &paid).round(2)
+    };
+    let platform_share = amount - &seller_share;
+
+    let entries = [
+        ("buyer", Some(transaction.buyer_id.as_str()), amount.clone()),
+        ("seller", Some(transaction.seller_id.as_str()), -seller_share.clone()),
+        ("platform", None, -platform_share),
+    ];
+
+    for (account, user_id, amount) in entries {
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_ledger_entries (transaction_id, refund_id, entry_type, account, user_id, amount, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, CURRENT_TIMESTAMP)
+            "#
+        )
+        .bind(transaction.id)
+        .bind(refund_id)
+        .bind(entry_type)
+        .bind(account)
+        .bind(user_id)
+        .bind(amount)
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    Ok(seller_share)
+}
@@ -0,0 +1,68 @@
+use crate::config::{Config, RegionSettings};
+use crate::error::AppError;
+use crate::models::marketplace::ListingFilters;
+use bigdecimal::BigDecimal;
+
+fn settings() -> &'static RegionSettings {
+    &Config::get().regions
+}
+
+/// Region of anonymous browsing and of users who never chose one
+pub fn default_region() -> &'static str {
+    &settings().default_region
+}
+
+/// ISO 3166-1 alpha-2 codes of the regions the marketplace operates in
+pub fn enabled() -> &'static [String] {
+    &settings().enabled
+}
+
+pub fn is_supported(region: &str) -> bool {
+    enabled().iter().any(|enabled| enabled == region)
+}
+
+/// The region a browse or search is scoped to: the one asked for, else `home` (the caller's
+/// region when known), else the default region. `all_regions=true` opts into browsing
+/// every region instead and gives `None`.
+pub fn resolve(
+    region: Option<&str>,
+    all_regions: Option<bool>,
+    home: Option<&str>,
+) -> Result<Option<String>, AppError> {
+    match region {
+        Some(region) if !is_supported(region) => Err(AppError::UnprocessableEntity(format!(
+            "Unknown region {}; must be one of {}",
+            region,
+            enabled().join(", ")
+        ))),
+        Some(region) => Ok(Some(region.to_string())),
+        None if all_regions == Some(true) => Ok(None),
+        None => Ok(Some(home.unwrap_or_else(default_region).to_string())),
+    }
+}
+
+/// Scope listing filters with `resolve`
+pub fn scope(filters: &mut ListingFilters, home: Option<&str>) -> Result<(), AppError> {
+    filters.region = resolve(filters.region.as_deref(), filters.all_regions, home)?;
+    Ok(())
+}
+
+/// Base commission rate on sales in the region, before the seller's tier applies
+pub fn fee_rate(region: &str) -> f64 {
+    settings()
+        .fee_rates
+        .iter()
+        .find(|(code, _)| code == region)
+        .map_or(Config::get().platform_fee_rate, |(_, rate)| *rate)
+}
+
+/// Tax the region charges on a platform fee, rounded to cents
+pub fn tax_on_fee(fee: &BigDecimal, region: &str) -> BigDecimal {
+    let rate = settings()
+        .tax_rates
+        .iter()
+        .find(|(code, _)| code == region)
+        .map_or(0.0, |(_, rate)| *rate);
+    let rate = rate.to_string().parse::<BigDecimal>().unwrap_or_default();
+    (fee * rate).round(2)
+}
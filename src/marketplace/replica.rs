@@ -0,0 +1,148 @@
+use crate::config::{DatabaseSettings, ReadReplicaSettings};
+use crate::error::AppError;
+use crate::marketplace::database;
+use moka::sync::Cache;
+use sqlx::PgPool;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+use uuid::Uuid;
+
+// How often the replica's replay lag is measured
+const LAG_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+// Recently written listings and users remembered for read-your-writes
+const RECENT_WRITES_CAPACITY: u64 = 100_000;
+
+// Replay lag in milliseconds, or this while unknown or the replica cannot be reached
+const LAG_UNKNOWN: u64 = u64::MAX;
+
+static REPLICA: OnceLock<ReadReplica> = OnceLock::new();
+
+struct ReadReplica {
+    pool: PgPool,
+    max_lag_ms: u64,
+    lag_ms: AtomicU64,
+    recent_writes: Cache<String, ()>,
+}
+
+/// Connect the read replica named by DATABASE_READ_URL and start measuring its lag; call
+/// once at startup. Without a replica every read uses the primary.
+pub async fn init(settings: &ReadReplicaSettings, database: &DatabaseSettings) -> Result<(), AppError> {
+    let Some(url) = &settings.url else {
+        return Ok(());
+    };
+
+    let pool = database::connect(url.expose(), database, settings.max_connections).await?;
+
+    // A write is visible on the replica once it is no further behind than the allowed lag,
+    // which is only known as of the last check
+    let pinned_for = Duration::from_millis(settings.max_lag_ms) + LAG_CHECK_INTERVAL;
+    let replica = ReadReplica {
+        pool,
+        max_lag_ms: settings.max_lag_ms,
+        lag_ms: AtomicU64::new(LAG_UNKNOWN),
+        recent_writes: Cache::builder()
+            .max_capacity(RECENT_WRITES_CAPACITY)
+            .time_to_live(pinned_for)
+            .build(),
+    };
+    if REPLICA.set(replica).is_ok() {
+        tokio::spawn(monitor_lag());
+    }
+    Ok(())
+}
+
+/// Pool for reads that tolerate replication lag: the replica while it is within
+/// DATABASE_READ_MAX_LAG_MS of the primary, otherwise `primary`
+pub fn read_pool(primary: &PgPool) -> PgPool {
+    match REPLICA.get() {
+        Some(replica) if replica.is_current() => replica.pool.clone(),
+        _ => primary.clone(),
+    }
+}
+
+/// `read_pool` for reads of a listing, which stay on the primary right after it was written
+pub fn listing_read_pool(primary: &PgPool, listing_id: Uuid) -> PgPool {
+    pinned_read_pool(primary, &listing_key(listing_id))
+}
+
+/// `read_pool` for reads of a user's profile, listings and reviews, which stay on the
+/// primary right after any of them were written
+pub fn user_read_pool(primary: &PgPool, user_id: &str) -> PgPool {
+    pinned_read_pool(primary, &user_key(user_id))
+}
+
+/// Send reads of the listing on this instance to the primary until the replica has caught up
+pub fn listing_written(listing_id: Uuid) {
+    if let Some(replica) = REPLICA.get() {
+        replica.recent_writes.insert(listing_key(listing_id), ());
+    }
+}
+
+/// Send reads of the user's data on this instance to the primary until the replica has caught up
+pub fn user_written(user_id: &str) {
+    if let Some(replica) = REPLICA.get() {
+        replica.recent_writes.insert(user_key(user_id), ());
+    }
+}
+
+impl ReadReplica {
+    fn is_current(&self) -> bool {
+        self.lag_ms.load(Ordering::Relaxed) <= self.max_lag_ms
+    }
+}
+
+fn pinned_read_pool(primary: &PgPool, key: &str) -> PgPool {
+    match REPLICA.get() {
+        Some(replica) if replica.is_current() && !replica.recent_writes.contains_key(key) => replica.pool.clone(),
+        _ => primary.clone(),
+    }
+}
+
+fn listing_key(listing_id: Uuid) -> String {
+    format!("listing:{}", listing_id)
+}
+
+fn user_key(user_id: &str) -> String {
+    format!("user:{}", user_id)
+}
+
+async fn monitor_lag() {
+    let Some(replica) = REPLICA.get() else {
+        return;
+    };
+
+    loop {
+        // An idle primary leaves the last replay timestamp behind; a replica that has
+        // replayed everything it received is current
+        let lag = sqlx::query_scalar::<_, f64>(
+            r#"
+            SELECT CASE
+                WHEN pg_last_wal_receive_lsn() = pg_last_wal_replay_lsn() THEN 0
+                ELSE COALESCE(EXTRACT(EPOCH FROM (now() - pg_last_xact_replay_timestamp())) * 1000, 0)
+            END::float8
+            "#
+        )
+        .fetch_one(&replica.pool)
+        .await;
+
+        let lag_ms = match lag {
+            Ok(lag) => lag.max(0.0) as u64,
+            Err(e) => {
+                tracing::warn!(error = %e, "read replica lag check failed");
+                LAG_UNKNOWN
+            }
+        };
+
+        let was_current = replica.is_current();
+        replica.lag_ms.store(lag_ms, Ordering::Relaxed);
+        match (was_current, replica.is_current()) {
+            (true, false) => tracing::warn!(lag_ms, "read replica behind, reading from the primary"),
+            (false, true) => tracing::info!(lag_ms, "read replica caught up, reading from the replica"),
+            _ => {}
+        }
+
+        tokio::time::sleep(LAG_CHECK_INTERVAL).await;
+    }
+}
@@ -0,0 +1,441 @@
+use crate::error::AppError;
+use crate::models::marketplace::{MarketplaceListing, MarketplaceTransaction};
+use async_trait::async_trait;
+use sqlx::{PgPool, Row};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+// Inputs to the trust score rules
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrustStats {
+    pub total_transactions: i32,
+    pub successful_transactions: i32,
+    pub verified_seller: bool,
+    pub review_count: i64,
+    pub avg_rating: Option<f64>,
+}
+
+/// Trust score on a 0-100 scale from transaction history, reviews and verification
+pub fn compute_trust_score(stats: &TrustStats) -> f64 {
+    let mut score: f64 = 50.0; // Base score
+
+    // Transaction success rate (up to 30 points)
+    if stats.total_transactions > 0 {
+        let success_rate = stats.successful_transactions as f64 / stats.total_transactions as f64;
+        score += success_rate * 30.0;
+    }
+
+    // Average rating (up to 30 points)
+    if let Some(rating) = stats.avg_rating {
+        score += (rating / 5.0) * 30.0;
+    }
+
+    // Review count bonus (up to 10 points)
+    score += (stats.review_count as f64).min(10.0);
+
+    // Verified seller bonus
+    if stats.verified_seller {
+        score += 10.0;
+    }
+
+    // Cap at 100
+    score.min(100.0)
+}
+
+#[async_trait]
+pub trait ListingRepository: Send + Sync {
+    /// A listing that has not been deleted
+    async fn find_listing(&self, listing_id: Uuid) -> Result<Option<MarketplaceListing>, AppError>;
+}
+
+#[async_trait]
+pub trait TransactionRepository: Send + Sync {
+    async fn find_transaction(&self, transaction_id: Uuid) -> Result<Option<MarketplaceTransaction>, AppError>;
+    async fn has_reviewed(&self, transaction_id: Uuid, reviewer_id: &str) -> Result<bool, AppError>;
+}
+
+#[async_trait]
+pub trait TrustScoreRepository: Send + Sync {
+    /// Create the default score for a user if they have none yet
+    async fn ensure(&self, user_id: &str) -> Result<(), AppError>;
+    async fn record_transaction(&self, user_id: &str, successful: bool) -> Result<(), AppError>;
+    /// A completed sale was fully refunded and no longer counts as successful
+    async fn reverse_success(&self, user_id: &str) -> Result<(), AppError>;
+    async fn get_stats(&self, user_id: &str) -> Result<Option<TrustStats>, AppError>;
+    async fn save_score(&self, user_id: &str, score: f64, stats: &TrustStats) -> Result<(), AppError>;
+}
+
+#[async_trait]
+pub trait RoleRepository: Send + Sync {
+    async fn is_admin(&self, user_id: &str) -> Result<bool, AppError>;
+    /// Verifiers review seller verification submissions; admins may too
+    async fn is_verifier(&self, user_id: &str) -> Result<bool, AppError>;
+}
+
+/// The storage dependencies of `MarketplaceService`
+#[derive(Clone)]
+pub struct Repositories {
+    pub listings: Arc<dyn ListingRepository>,
+    pub transactions: Arc<dyn TransactionRepository>,
+    pub trust_scores: Arc<dyn TrustScoreRepository>,
+    pub roles: Arc<dyn RoleRepository>,
+}
+
+impl Repositories {
+    pub fn postgres(pool: PgPool) -> Self {
+        let repository = Arc::new(PgRepository::new(pool));
+        Self {
+            listings: repository.clone(),
+            transactions: repository.clone(),
+            trust_scores: repository.clone(),
+            roles: repository,
+        }
+    }
+
+    /// All repositories backed by one shared in-memory store
+    pub fn in_memory(store: Arc<InMemoryRepository>) -> Self {
+        Self {
+            listings: store.clone(),
+            transactions: store.clone(),
+            trust_scores: store.clone(),
+            roles: store,
+        }
+    }
+}
+
+pub struct PgRepository {
+    pool: PgPool,
+}
+
+impl PgRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl ListingRepository for PgRepository {
+    async fn find_listing(&self, listing_id: Uuid) -> Result<Option<MarketplaceListing>, AppError> {
+        let listing = sqlx::query_as::<_, MarketplaceListing>(
+            "SELECT * FROM marketplace_listings WHERE id = $1 AND deleted_at IS NULL"
+        )
+        .bind(listing_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(listing)
+    }
+}
+
+#[async_trait]
+impl TransactionRepository for PgRepository {
+    async fn find_transaction(&self, transaction_id: Uuid) -> Result<Option<MarketplaceTransaction>, AppError> {
+        let transaction = sqlx::query_as::<_, MarketplaceTransaction>(
+            "SELECT * FROM marketplace_transactions WHERE id = $1"
+        )
+        .bind(transaction_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(transaction)
+    }
+
+    async fn has_reviewed(&self, transaction_id: Uuid, reviewer_id: &str) -> Result<bool, AppError> {
+        let existing = sqlx::query(
+            "SELECT id FROM marketplace_reviews WHERE transaction_id = $1 AND reviewer_id = $2"
+        )
+        .bind(transaction_id)
+        .bind(reviewer_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(existing.is_some())
+    }
+}
+
+#[async_trait]
+impl TrustScoreRepository for PgRepository {
+    async fn ensure(&self, user_id: &str) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_trust_scores (user_id, trust_score, last_calculated)
+            VALUES ($1, 50.0, CURRENT_TIMESTAMP)
+            ON CONFLICT (user_id) DO NOTHING
+            "#
+        )
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn record_transaction(&self, user_id: &str, successful: bool) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            UPDATE marketplace_trust_scores
+            SET total_transactions = total_transactions + 1,
+                successful_transactions = successful_transactions + CASE WHEN $2 THEN 1 ELSE 0 END,
+                last_calculated = CURRENT_TIMESTAMP
+            WHERE user_id = $1
+            "#
+        )
+        .bind(user_id)
+        .bind(successful)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn reverse_success(&self, user_id: &str) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            UPDATE marketplace_trust_scores
+            SET successful_transactions = GREATEST(successful_transactions - 1, 0),
+                last_calculated = CURRENT_TIMESTAMP
+            WHERE user_id = $1
+            "#
+        )
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_stats(&self, user_id: &str) -> Result<Option<TrustStats>, AppError> {
+        let row = sqlx::query(
+            r#"
+            SELECT
+                ts.total_transactions,
+                ts.successful_transactions,
+                ts.verified_seller,
+                COUNT(r.id) as review_count,
+                AVG(r.rating)::float8 as avg_rating
+            FROM marketplace_trust_scores ts
+            LEFT JOIN marketplace_reviews r ON r.reviewed_user_id = ts.user_id
+            WHERE ts.user_id = $1
+            GROUP BY ts.user_id, ts.total_transactions, ts.successful_transactions, ts.verified_seller
+            "#
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| TrustStats {
+            total_transactions: row.get("total_transactions"),
+            successful_transactions: row.get("successful_transactions"),
+            verified_seller: row.get("verified_seller"),
+            review_count: row.get("review_count"),
+            avg_rating: row.get("avg_rating"),
+        }))
+    }
+
+    async fn save_score(&self, user_id: &str, score: f64, stats: &TrustStats) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            UPDATE marketplace_trust_scores
+            SET trust_score = $1,
+                average_rating = $2,
+                total_reviews = $3,
+                last_calculated = CURRENT_TIMESTAMP
+            WHERE user_id = $4
+            "#
+        )
+        .bind(score)
+        .bind(stats.avg_rating.unwrap_or(0.0))
+        .bind(stats.review_count as i32)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl RoleRepository for PgRepository {
+    async fn is_admin(&self, user_id: &str) -> Result<bool, AppError> {
+        let row = sqlx::query(
+            "SELECT 1 FROM marketplace_user_roles WHERE user_id = $1 AND role = 'admin'"
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.is_some())
+    }
+
+    async fn is_verifier(&self, user_id: &str) -> Result<bool, AppError> {
+        let row = sqlx::query(
+            "SELECT 1 FROM marketplace_user_roles WHERE user_id = $1 AND role IN ('verifier', 'admin') LIMIT 1"
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.is_some())
+    }
+}
+
+/// In-memory double for exercising service rules without a database
+#[derive(Default)]
+pub struct InMemoryRepository {
+    pub listings: Mutex<HashMap<Uuid, MarketplaceListing>>,
+    pub transactions: Mutex<HashMap<Uuid, MarketplaceTransaction>>,
+    pub reviews: Mutex<HashSet<(Uuid, String)>>, // (transaction_id, reviewer_id)
+    pub trust_stats: Mutex<HashMap<String, TrustStats>>,
+    pub trust_scores: Mutex<HashMap<String, f64>>,
+    pub admins: Mutex<HashSet<String>>,
+    pub verifiers: Mutex<HashSet<String>>,
+}
+
+impl InMemoryRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ListingRepository for InMemoryRepository {
+    async fn find_listing(&self, listing_id: Uuid) -> Result<Option<MarketplaceListing>, AppError> {
+        let listings = self.listings.lock().unwrap();
+        Ok(listings
+            .get(&listing_id)
+            .filter(|listing| listing.deleted_at.is_none())
+            .cloned())
+    }
+}
+
+#[async_trait]
+impl TransactionRepository for InMemoryRepository {
+    async fn find_transaction(&self, transaction_id: Uuid) -> Result<Option<MarketplaceTransaction>, AppError> {
+        Ok(self.transactions.lock().unwrap().get(&transaction_id).cloned())
+    }
+
+    async fn has_reviewed(&self, transaction_id: Uuid, reviewer_id: &str) -> Result<bool, AppError> {
+        Ok(self
+            .reviews
+            .lock()
+            .unwrap()
+            .contains(&(transaction_id, reviewer_id.to_string())))
+    }
+}
+
+#[async_trait]
+impl TrustScoreRepository for InMemoryRepository {
+    async fn ensure(&self, user_id: &str) -> Result<(), AppError> {
+        self.trust_stats.lock().unwrap().entry(user_id.to_string()).or_default();
+        self.trust_scores.lock().unwrap().entry(user_id.to_string()).or_insert(50.0);
+        Ok(())
+    }
+
+    async fn record_transaction(&self, user_id: &str, successful: bool) -> Result<(), AppError> {
+        if let Some(stats) = self.trust_stats.lock().unwrap().get_mut(user_id) {
+            stats.total_transactions += 1;
+            if successful {
+                stats.successful_transactions += 1;
+            }
+        }
+        Ok(())
+    }
+
+    async fn reverse_success(&self, user_id: &str) -> Result<(), AppError> {
+        if let Some(stats) = self.trust_stats.lock().unwrap().get_mut(user_id) {
+            stats.successful_transactions = (stats.successful_transactions - 1).max(0);
+        }
+        Ok(())
+    }
+
+    async fn get_stats(&self, user_id: &str) -> Result<Option<TrustStats>, AppError> {
+        Ok(self.trust_stats.lock().unwrap().get(user_id).cloned())
+    }
+
+    async fn save_score(&self, user_id: &str, score: f64, _stats: &TrustStats) -> Result<(), AppError> {
+        self.trust_scores.lock().unwrap().insert(user_id.to_string(), score);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl RoleRepository for InMemoryRepository {
+    async fn is_admin(&self, user_id: &str) -> Result<bool, AppError> {
+        Ok(self.admins.lock().unwrap().contains(user_id))
+    }
+
+    async fn is_verifier(&self, user_id: &str) -> Result<bool, AppError> {
+        Ok(self.verifiers.lock().unwrap().contains(user_id) || self.admins.lock().unwrap().contains(user_id))
+    }
+}
+
+/// Records to fill an `InMemoryRepository` with in tests
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use crate::models::marketplace::{
+        ListingStatus, ListingType, MarketplaceListing, MarketplaceTransaction, TransactionStatus,
+    };
+    use bigdecimal::BigDecimal;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    /// An active discount code listing of `seller_id`
+    pub fn listing(seller_id: &str) -> MarketplaceListing {
+        MarketplaceListing {
+            id: Uuid::new_v4(),
+            seller_id: seller_id.to_string(),
+            listing_type: ListingType::DiscountCode,
+            title: "20% off electronics".to_string(),
+            description: None,
+            description_html: None,
+            category: "electronics".to_string(),
+            brand_name: Some("Amazon".to_string()),
+            original_value: None,
+            selling_price: BigDecimal::from(25),
+            discount_percentage: None,
+            expiration_date: None,
+            proof_image_url: None,
+            status: ListingStatus::Active,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            view_count: 0,
+            tags: Vec::new(),
+            is_verified: false,
+            verification_date: None,
+            details: None,
+            quantity: 1,
+            remaining_quantity: 1,
+            deleted_at: None,
+            market_discount_score: None,
+            market_discount_basis: None,
+            language: "en".to_string(),
+            region: "US".to_string(),
+        }
+    }
+
+    /// A purchase by `buyer` from `seller` in `status`
+    pub fn transaction(status: TransactionStatus) -> MarketplaceTransaction {
+        MarketplaceTransaction {
+            id: Uuid::new_v4(),
+            listing_id: Uuid::new_v4(),
+            buyer_id: "buyer".to_string(),
+            seller_id: "seller".to_string(),
+            amount: BigDecimal::from(25),
+            status,
+            payment_method: Some("card".to_string()),
+            payment_id: None,
+            escrow_release_date: None,
+            created_at: Utc::now(),
+            completed_at: None,
+            cancellation_reason: None,
+            dispute_reason: None,
+            commission_tier: None,
+            platform_fee: None,
+            refunded_amount: BigDecimal::from(0),
+            checkout_id: None,
+            discount_amount: BigDecimal::from(0),
+            tax_amount: BigDecimal::from(0),
+        }
+    }
+}
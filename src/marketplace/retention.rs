@@ -0,0 +1,299 @@
+use crate::auth::AuthUser;
+use crate::config::{Config, RetentionSettings};
+use crate::error::AppError;
+use crate::marketplace::jobs::Job;
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::RetentionPolicyStatus;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Row};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+// Soft-deleted listings are purged after this many days
+pub const DELETED_LISTING_RETENTION_DAYS: i32 = 90;
+
+// Cleanup run history is itself kept this long
+const RETENTION_RUN_HISTORY_DAYS: i32 = 365;
+
+// Pause between delete batches so cleanup does not crowd out regular traffic
+const BATCH_PAUSE: Duration = Duration::from_millis(50);
+
+/// Rows of `table` older than `days` by `age_column` (and matching `condition`) are deleted
+struct RetentionPolicy {
+    name: &'static str,
+    table: &'static str,
+    age_column: &'static str,
+    condition: &'static str,
+    days: i32,
+}
+
+fn policies(settings: &RetentionSettings) -> Vec<RetentionPolicy> {
+    vec![
+        RetentionPolicy {
+            name: "notifications",
+            table: "marketplace_notifications",
+            age_column: "created_at",
+            condition: "TRUE",
+            days: settings.notifications_days,
+        },
+        RetentionPolicy {
+            name: "rate_limits",
+            table: "marketplace_rate_limits",
+            // Windows are stored as UTC without a time zone
+            age_column: "(window_start AT TIME ZONE 'UTC')",
+            condition: "TRUE",
+            days: settings.rate_limits_days,
+        },
+        RetentionPolicy {
+            name: "impersonation_audit",
+            table: "marketplace_impersonation_audit",
+            age_column: "created_at",
+            condition: "TRUE",
+            days: settings.audit_days,
+        },
+        RetentionPolicy {
+            name: "contact_violations",
+            table: "marketplace_contact_violations",
+            age_column: "created_at",
+            condition: "TRUE",
+            days: settings.audit_days,
+        },
+        RetentionPolicy {
+            name: "published_events",
+            table: "marketplace_outbox",
+            age_column: "published_at",
+            condition: "published_at IS NOT NULL",
+            days: settings.outbox_days,
+        },
+        RetentionPolicy {
+            name: "coupon_reveal_tokens",
+            table: "marketplace_coupon_reveal_tokens",
+            age_column: "expires_at",
+            condition: "TRUE",
+            days: settings.reveal_tokens_days,
+        },
+        RetentionPolicy {
+            name: "step_up_codes",
+            table: "marketplace_step_up_codes",
+            age_column: "expires_at",
+            condition: "TRUE",
+            days: settings.reveal_tokens_days,
+        },
+        RetentionPolicy {
+            name: "step_up_tokens",
+            table: "marketplace_step_up_tokens",
+            age_column: "expires_at",
+            condition: "TRUE",
+            days: settings.reveal_tokens_days,
+        },
+        RetentionPolicy {
+            name: "sessions",
+            table: "marketplace_sessions",
+            age_column: "last_seen_at",
+            condition: "TRUE",
+            days: settings.audit_days,
+        },
+        RetentionPolicy {
+            name: "partner_key_usage",
+            table: "marketplace_partner_key_usage",
+            age_column: "day",
+            condition: "TRUE",
+            days: settings.partner_usage_days,
+        },
+        RetentionPolicy {
+            name: "retention_runs",
+            table: "marketplace_retention_runs",
+            age_column: "created_at",
+            condition: "TRUE",
+            days: RETENTION_RUN_HISTORY_DAYS,
+        },
+    ]
+}
+
+pub struct RetentionService {
+    pool: PgPool,
+}
+
+impl RetentionService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Hard-delete soft-deleted listings past the retention period that no transaction,
+    /// bundle or promotion references, along with their price history, reveals and revisions
+    pub async fn purge_deleted_listings(&self, retention_days: i32) -> Result<u64, AppError> {
+        // History tables no longer cascade from the listing (see the listing archive), so
+        // dependent rows are removed alongside it
+        let result = sqlx::query(
+            r#"
+            WITH purged AS (
+                DELETE FROM marketplace_listings l
+                WHERE l.deleted_at < CURRENT_TIMESTAMP - make_interval(days => $1)
+                AND NOT EXISTS (
+                    SELECT 1 FROM marketplace_transactions t WHERE t.listing_id = l.id
+                )
+                AND NOT EXISTS (
+                    SELECT 1 FROM marketplace_bundle_items bi WHERE bi.listing_id = l.id
+                )
+                AND NOT EXISTS (
+                    SELECT 1 FROM marketplace_promotions p WHERE p.listing_id = l.id
+                )
+                RETURNING l.id
+            ),
+            price_history AS (
+                DELETE FROM marketplace_price_history WHERE listing_id IN (SELECT id FROM purged)
+            ),
+            reveals AS (
+                DELETE FROM marketplace_coupon_reveals WHERE listing_id IN (SELECT id FROM purged)
+            ),
+            revisions AS (
+                DELETE FROM marketplace_listing_revisions WHERE listing_id IN (SELECT id FROM purged)
+            )
+            SELECT COUNT(*) as purged FROM purged
+            "#
+        )
+        .bind(retention_days)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(result.get::<i64, _>("purged") as u64)
+    }
+
+    /// Apply every retention policy, deleting in batches of `batch_size` so no statement
+    /// holds its locks for long, and record how many rows each one purged
+    pub async fn purge_expired_rows(&self, settings: &RetentionSettings) -> Result<u64, AppError> {
+        let mut total = 0;
+
+        for policy in policies(settings).iter().filter(|policy| policy.days > 0) {
+            let started = Instant::now();
+            let purged = self.purge_policy(policy, settings.batch_size).await?;
+            let duration_ms = started.elapsed().as_millis() as i64;
+
+            sqlx::query(
+                r#"
+                INSERT INTO marketplace_retention_runs (id, policy, rows_purged, duration_ms, created_at)
+                VALUES ($1, $2, $3, $4, CURRENT_TIMESTAMP)
+                "#
+            )
+            .bind(Uuid::new_v4())
+            .bind(policy.name)
+            .bind(purged as i64)
+            .bind(duration_ms)
+            .execute(&self.pool)
+            .await?;
+
+            if purged > 0 {
+                tracing::info!(policy = policy.name, purged, duration_ms, "retention purged rows");
+            }
+            total += purged;
+        }
+
+        Ok(total)
+    }
+
+    async fn purge_policy(&self, policy: &RetentionPolicy, batch_size: i64) -> Result<u64, AppError> {
+        let query = format!(
+            r#"
+            DELETE FROM {table} WHERE ctid IN (
+                SELECT ctid FROM {table}
+                WHERE {age_column} < CURRENT_TIMESTAMP - make_interval(days => $1) AND {condition}
+                LIMIT $2
+            )
+            "#,
+            table = policy.table,
+            age_column = policy.age_column,
+            condition = policy.condition,
+        );
+
+        let mut purged = 0;
+        loop {
+            let deleted = sqlx::query(&query)
+                .bind(policy.days)
+                .bind(batch_size)
+                .execute(&self.pool)
+                .await?
+                .rows_affected();
+
+            purged += deleted;
+            if deleted < batch_size as u64 {
+                break;
+            }
+            tokio::time::sleep(BATCH_PAUSE).await;
+        }
+        Ok(purged)
+    }
+
+    /// Each retention policy with its latest run and rows purged over the last 30 days
+    pub async fn policy_status(&self, auth_user: &AuthUser) -> Result<Vec<RetentionPolicyStatus>, AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+
+        let rows = sqlx::query(
+            r#"
+            SELECT DISTINCT ON (policy)
+                policy,
+                created_at as last_run_at,
+                rows_purged as last_rows_purged,
+                SUM(rows_purged) OVER (PARTITION BY policy)::bigint as rows_purged_30d
+            FROM marketplace_retention_runs
+            WHERE created_at > CURRENT_TIMESTAMP - INTERVAL '30 days'
+            ORDER BY policy, created_at DESC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let settings = &Config::get().retention;
+        let status = policies(settings)
+            .into_iter()
+            .map(|policy| {
+                let run = rows.iter().find(|row| row.get::<String, _>("policy") == policy.name);
+                RetentionPolicyStatus {
+                    policy: policy.name.to_string(),
+                    table_name: policy.table.to_string(),
+                    retention_days: policy.days,
+                    last_run_at: run.map(|row| row.get::<DateTime<Utc>, _>("last_run_at")),
+                    last_rows_purged: run.map(|row| row.get("last_rows_purged")),
+                    rows_purged_30d: run.map_or(0, |row| row.get("rows_purged_30d")),
+                }
+            })
+            .collect();
+
+        Ok(status)
+    }
+}
+
+/// Purges expired soft-deleted listings
+pub struct PurgeDeletedListingsJob;
+
+#[async_trait]
+impl Job for PurgeDeletedListingsJob {
+    fn name(&self) -> &'static str {
+        "purge_deleted_listings"
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<(), AppError> {
+        RetentionService::new(pool.clone())
+            .purge_deleted_listings(DELETED_LISTING_RETENTION_DAYS)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Deletes notifications, rate-limit windows, audit entries and other rows past their
+/// configured retention
+pub struct RetentionCleanupJob;
+
+#[async_trait]
+impl Job for RetentionCleanupJob {
+    fn name(&self) -> &'static str {
+        "retention_cleanup"
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<(), AppError> {
+        RetentionService::new(pool.clone())
+            .purge_expired_rows(&Config::get().retention)
+            .await?;
+        Ok(())
+    }
+}
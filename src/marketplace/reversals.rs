@@ -0,0 +1,311 @@
+use crate::error::AppError;
+use crate::marketplace::anomaly::AnomalyDetector;
+use crate::marketplace::jobs::Job;
+use crate::marketplace::outbox::{event_types, OutboxService};
+use crate::marketplace::payments::ProviderReversal;
+use crate::marketplace::refunds::{record_ledger_entries, refundable};
+use crate::marketplace::transaction_state::{TransactionEvent, TransactionStateMachine};
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{
+    Chargeback, MarketplaceTransaction, PaymentReversal, PaymentReversalKind, TransactionChargedBackEvent,
+    TransactionStatus,
+};
+use async_trait::async_trait;
+use bigdecimal::{BigDecimal, Zero};
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use std::str::FromStr;
+use uuid::Uuid;
+
+const SYSTEM_ACTOR: &str = "system";
+
+// Chargebacks younger than this are left to the request that recorded them
+const RECONCILE_AFTER_MINS: i32 = 2;
+
+// A chargeback whose payment never settled within this long is left to the admins
+const RECONCILE_WINDOW_DAYS: i32 = 14;
+
+const RECONCILE_BATCH_SIZE: i64 = 100;
+
+/// Reversals and chargebacks reported by payment provider webhooks, each recorded once.
+///
+/// A chargeback moves the purchases its payment paid for to `charged_back`, claws the
+/// seller's share back in the ledger, holds the buyer's purchases for review and tells the
+/// admins. One that arrives before its payment settled is retried by
+/// `ChargebackReconciliationJob`. Other reversals are only raised with the admins.
+pub struct PaymentReversalService {
+    pool: PgPool,
+}
+
+impl PaymentReversalService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Record a reversal reported by `provider`; redelivered events are ignored
+    pub(crate) async fn record(&self, provider: &str, reversal: &ProviderReversal) -> Result<(), AppError> {
+        let amount = reversal.amount.as_deref().and_then(|amount| BigDecimal::from_str(amount).ok());
+
+        let recorded = sqlx::query_as::<_, PaymentReversal>(
+            r#"
+            INSERT INTO marketplace_payment_reversals (id, provider, provider_event_id, payment_id, kind, amount, reason, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, CURRENT_TIMESTAMP)
+            ON CONFLICT (provider, provider_event_id) DO NOTHING
+            RETURNING *
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(provider)
+        .bind(&reversal.event_id)
+        .bind(&reversal.payment_id)
+        .bind(reversal.kind)
+        .bind(&amount)
+        .bind(&reversal.reason)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let Some(recorded) = recorded else {
+            return Ok(());
+        };
+
+        match recorded.kind {
+            PaymentReversalKind::Chargeback => {
+                if !self.apply_chargeback(&recorded).await? {
+                    tracing::warn!(
+                        reversal_id = %recorded.id,
+                        payment_id = %recorded.payment_id,
+                        "chargeback for a payment with no settled purchases, left to reconciliation"
+                    );
+                }
+                Ok(())
+            }
+            PaymentReversalKind::Reversal => self.notify_reversed(&recorded).await,
+        }
+    }
+
+    /// Recorded reversals, newest first; admins reconcile the chargebacks still unapplied
+    pub async fn list(&self, unapplied_only: bool) -> Result<Vec<PaymentReversal>, AppError> {
+        let mut query = QueryBuilder::<Postgres>::new("SELECT * FROM marketplace_payment_reversals WHERE 1=1");
+        if unapplied_only {
+            query.push(" AND kind = ").push_bind(PaymentReversalKind::Chargeback);
+            query.push(" AND applied_at IS NULL");
+        }
+        query.push(" ORDER BY created_at DESC LIMIT 200");
+
+        let reversals = query
+            .build_query_as::<PaymentReversal>()
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(reversals)
+    }
+
+    /// Retry chargebacks that arrived before their payment settled.
+    /// Returns how many were applied.
+    pub async fn reconcile_chargebacks(&self) -> Result<u64, AppError> {
+        let unapplied = sqlx::query_as::<_, PaymentReversal>(
+            r#"
+            SELECT * FROM marketplace_payment_reversals
+            WHERE kind = $1
+              AND applied_at IS NULL
+              AND created_at <= CURRENT_TIMESTAMP - make_interval(mins => $2)
+              AND created_at > CURRENT_TIMESTAMP - make_interval(days => $3)
+            ORDER BY created_at
+            LIMIT $4
+            "#
+        )
+        .bind(PaymentReversalKind::Chargeback)
+        .bind(RECONCILE_AFTER_MINS)
+        .bind(RECONCILE_WINDOW_DAYS)
+        .bind(RECONCILE_BATCH_SIZE)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut applied = 0;
+        for reversal in unapplied {
+            match self.apply_chargeback(&reversal).await {
+                Ok(true) => applied += 1,
+                Ok(false) => {}
+                Err(e) => tracing::warn!(reversal_id = %reversal.id, error = %e, "chargeback reconciliation failed"),
+            }
+        }
+        Ok(applied)
+    }
+
+    // Charge back the purchases paid by the reversal's payment. False while nothing was
+    // paid with it yet; a chargeback that finds its purchases already refunded is applied
+    // without touching them.
+    async fn apply_chargeback(&self, reversal: &PaymentReversal) -> Result<bool, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let pending = sqlx::query_scalar::<_, Uuid>(
+            "SELECT id FROM marketplace_payment_reversals WHERE id = $1 AND applied_at IS NULL FOR UPDATE"
+        )
+        .bind(reversal.id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        if pending.is_none() {
+            return Ok(true);
+        }
+
+        let transactions = sqlx::query_as::<_, MarketplaceTransaction>(
+            "SELECT * FROM marketplace_transactions WHERE payment_id = $1 ORDER BY created_at FOR UPDATE"
+        )
+        .bind(&reversal.payment_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        if transactions.is_empty() {
+            return Ok(false);
+        }
+
+        // Without an amount the whole payment was charged back; otherwise purchases are
+        // charged back in order until the amount is used up
+        let mut left = reversal.amount.clone();
+        let mut charged_back = Vec::new();
+
+        for transaction in transactions {
+            if !matches!(
+                transaction.status,
+                TransactionStatus::Escrow | TransactionStatus::Disputed | TransactionStatus::Completed
+            ) {
+                continue;
+            }
+
+            let remaining = refundable(&transaction) - &transaction.refunded_amount;
+            let amount = match &left {
+                Some(left) => remaining.min(left.clone()),
+                None => remaining,
+            };
+            if amount <= BigDecimal::zero() {
+                continue;
+            }
+            if let Some(left) = left.as_mut() {
+                *left = &*left - &amount;
+            }
+
+            let seller_clawback = record_ledger_entries(&mut tx, &transaction, "chargeback", None, &amount).await?;
+            let updated = TransactionStateMachine::apply(
+                &mut tx,
+                &transaction,
+                TransactionEvent::ChargedBack,
+                SYSTEM_ACTOR,
+                Some(&reversal.reason),
+            ).await?;
+
+            let chargeback = sqlx::query_as::<_, Chargeback>(
+                r#"
+                INSERT INTO marketplace_chargebacks (id, reversal_id, transaction_id, amount, seller_clawback, created_at)
+                VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP)
+                RETURNING *
+                "#
+            )
+            .bind(Uuid::new_v4())
+            .bind(reversal.id)
+            .bind(transaction.id)
+            .bind(&amount)
+            .bind(&seller_clawback)
+            .fetch_one(&mut *tx)
+            .await?;
+
+            OutboxService::record(
+                &mut tx,
+                "transaction",
+                transaction.id,
+                event_types::TRANSACTION_CHARGED_BACK,
+                &TransactionChargedBackEvent { transaction: updated.clone(), chargeback: chargeback.clone() },
+            ).await?;
+
+            charged_back.push((updated, chargeback));
+        }
+
+        sqlx::query("UPDATE marketplace_payment_reversals SET applied_at = CURRENT_TIMESTAMP WHERE id = $1")
+            .bind(reversal.id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        self.notify_charged_back(reversal, &charged_back).await?;
+        Ok(true)
+    }
+
+    async fn notify_charged_back(
+        &self,
+        reversal: &PaymentReversal,
+        charged_back: &[(MarketplaceTransaction, Chargeback)],
+    ) -> Result<(), AppError> {
+        let marketplace = MarketplaceService::new(self.pool.clone());
+        let detector = AnomalyDetector::new(self.pool.clone());
+
+        let mut buyers: Vec<&str> = Vec::new();
+        for (transaction, chargeback) in charged_back {
+            if !buyers.contains(&transaction.buyer_id.as_str()) {
+                buyers.push(&transaction.buyer_id);
+            }
+
+            marketplace.create_notification(
+                &transaction.seller_id,
+                "transaction_charged_back",
+                "Sale Charged Back",
+                &format!(
+                    "The buyer's bank reversed their payment; {} of your proceeds was taken back",
+                    chargeback.seller_clawback
+                ),
+                Some(transaction.listing_id),
+                Some(transaction.id),
+            ).await;
+        }
+
+        for buyer_id in &buyers {
+            detector.flag_chargeback(buyer_id, &reversal.reason).await?;
+        }
+
+        let total = charged_back
+            .iter()
+            .fold(BigDecimal::zero(), |total, (_, chargeback)| total + &chargeback.amount);
+        marketplace
+            .notify_admins(
+                "payment_charged_back",
+                "Payment Charged Back",
+                &format!(
+                    "{} charged back payment {} ({}): {} across {} transaction(s); buyer purchases held for review",
+                    reversal.provider,
+                    reversal.payment_id,
+                    reversal.reason,
+                    total,
+                    charged_back.len()
+                ),
+            )
+            .await
+    }
+
+    async fn notify_reversed(&self, reversal: &PaymentReversal) -> Result<(), AppError> {
+        let affected: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM marketplace_transactions WHERE payment_id = $1")
+            .bind(&reversal.payment_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        MarketplaceService::new(self.pool.clone())
+            .notify_admins(
+                "payment_reversed",
+                "Payment Reversed",
+                &format!(
+                    "{} reversed payment {} ({}), affecting {} transaction(s)",
+                    reversal.provider, reversal.payment_id, reversal.reason, affected
+                ),
+            )
+            .await
+    }
+}
+
+pub struct ChargebackReconciliationJob;
+
+#[async_trait]
+impl Job for ChargebackReconciliationJob {
+    fn name(&self) -> &'static str {
+        "chargeback_reconciliation"
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<(), AppError> {
+        PaymentReversalService::new(pool.clone()).reconcile_chargebacks().await?;
+        Ok(())
+    }
+}
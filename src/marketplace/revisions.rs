@@ -0,0 +1,195 @@
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{FieldChange, ListingRevision, MarketplaceListing, UpdateListingRequest};
+use bigdecimal::BigDecimal;
+use serde_json::Value;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+// Listing fields sellers edit, as named in the listing's JSON
+const TRACKED_FIELDS: &[&str] = &["title", "description", "category", "tags", "details", "selling_price"];
+
+/// Revisions of listing edits. Each edit stores the fields it changed; reverting to a
+/// revision restores those fields to their values right after it, as a new edit.
+pub struct ListingRevisionService {
+    pool: PgPool,
+}
+
+impl ListingRevisionService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Edits of a listing, newest first; visible to its seller and admins
+    pub async fn history(&self, auth_user: &AuthUser, listing_id: Uuid) -> Result<Vec<ListingRevision>, AppError> {
+        self.editable_listing(auth_user, listing_id).await?;
+
+        let revisions = sqlx::query_as::<_, ListingRevision>(
+            "SELECT * FROM marketplace_listing_revisions WHERE listing_id = $1 ORDER BY revision DESC"
+        )
+        .bind(listing_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(revisions)
+    }
+
+    /// Restore the listing as it was right after `revision`; 0 restores it as created
+    pub async fn revert(
+        &self,
+        auth_user: &AuthUser,
+        listing_id: Uuid,
+        revision: i32,
+    ) -> Result<MarketplaceListing, AppError> {
+        let listing = self.editable_listing(auth_user, listing_id).await?;
+
+        if revision > 0 {
+            let exists = sqlx::query("SELECT 1 FROM marketplace_listing_revisions WHERE listing_id = $1 AND revision = $2")
+                .bind(listing_id)
+                .bind(revision)
+                .fetch_optional(&self.pool)
+                .await?;
+            if exists.is_none() {
+                return Err(AppError::NotFound("Revision not found".to_string()));
+            }
+        }
+
+        let later = sqlx::query_as::<_, ListingRevision>(
+            "SELECT * FROM marketplace_listing_revisions WHERE listing_id = $1 AND revision > $2 ORDER BY revision"
+        )
+        .bind(listing_id)
+        .bind(revision)
+        .fetch_all(&self.pool)
+        .await?;
+
+        // A field's value after `revision` is what the first later edit of it changed it from
+        let mut restored: BTreeMap<&str, &Value> = BTreeMap::new();
+        for later_revision in &later {
+            for (field, change) in later_revision.changes.iter() {
+                restored.entry(field.as_str()).or_insert(&change.from);
+            }
+        }
+
+        let current = to_fields(&listing)?;
+        restored.retain(|field, value| current.get(*field) != Some(*value));
+        if restored.is_empty() {
+            return Err(AppError::Conflict("The listing already matches that revision".to_string()));
+        }
+
+        let request = restore_request(&restored)?;
+        MarketplaceService::new(self.pool.clone())
+            .apply_listing_update(auth_user, listing, request, Some(revision))
+            .await
+    }
+
+    /// Record the fields an edit changed, in the edit's transaction. Edits that changed
+    /// none of the tracked fields are not recorded.
+    pub(crate) async fn record(
+        tx: &mut Transaction<'_, Postgres>,
+        before: &MarketplaceListing,
+        after: &MarketplaceListing,
+        editor_id: &str,
+        reverts: Option<i32>,
+    ) -> Result<Option<ListingRevision>, AppError> {
+        let old_fields = to_fields(before)?;
+        let new_fields = to_fields(after)?;
+
+        let changes: BTreeMap<String, FieldChange> = TRACKED_FIELDS
+            .iter()
+            .filter(|field| old_fields.get(**field) != new_fields.get(**field))
+            .map(|field| {
+                let change = FieldChange {
+                    from: old_fields.get(*field).cloned().unwrap_or(Value::Null),
+                    to: new_fields.get(*field).cloned().unwrap_or(Value::Null),
+                };
+                (field.to_string(), change)
+            })
+            .collect();
+        if changes.is_empty() {
+            return Ok(None);
+        }
+
+        // The edit's UPDATE holds the listing's row lock, so numbering cannot race
+        let revision = sqlx::query_as::<_, ListingRevision>(
+            r#"
+            INSERT INTO marketplace_listing_revisions (id, listing_id, revision, editor_id, changes, reverts_revision, created_at)
+            VALUES (
+                $1, $2,
+                (SELECT COALESCE(MAX(revision), 0) + 1 FROM marketplace_listing_revisions WHERE listing_id = $2),
+                $3, $4, $5, CURRENT_TIMESTAMP
+            )
+            RETURNING *
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(after.id)
+        .bind(editor_id)
+        .bind(sqlx::types::Json(&changes))
+        .bind(reverts)
+        .fetch_one(&mut **tx)
+        .await?;
+        Ok(Some(revision))
+    }
+
+    async fn editable_listing(&self, auth_user: &AuthUser, listing_id: Uuid) -> Result<MarketplaceListing, AppError> {
+        let listing = sqlx::query_as::<_, MarketplaceListing>(
+            "SELECT * FROM marketplace_listings WHERE id = $1 AND deleted_at IS NULL"
+        )
+        .bind(listing_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Listing not found".to_string()))?;
+
+        if listing.seller_id != auth_user.0.auth0_id
+            && !MarketplaceService::new(self.pool.clone()).is_admin(&auth_user.0.auth0_id).await?
+        {
+            return Err(AppError::Forbidden("Only the seller and admins can see a listing's history".to_string()));
+        }
+        Ok(listing)
+    }
+}
+
+fn to_fields(listing: &MarketplaceListing) -> Result<serde_json::Map<String, Value>, AppError> {
+    match serde_json::to_value(listing) {
+        Ok(Value::Object(fields)) => Ok(fields),
+        Ok(_) => Err(AppError::InternalError("Listing did not serialize to an object".to_string())),
+        Err(e) => Err(AppError::InternalError(format!("Serialization error: {}", e))),
+    }
+}
+
+// The edit that sets the restored fields; a cleared description is restored as empty
+fn restore_request(restored: &BTreeMap<&str, &Value>) -> Result<UpdateListingRequest, AppError> {
+    let text = |field: &str| restored.get(field).map(|value| value.as_str().unwrap_or_default().to_string());
+
+    let tags = match restored.get("tags") {
+        Some(tags) => Some(
+            serde_json::from_value::<Vec<String>>((*tags).clone())
+                .map_err(|e| AppError::InternalError(format!("Stored tags are invalid: {}", e)))?,
+        ),
+        None => None,
+    };
+    let selling_price = match restored.get("selling_price") {
+        Some(price) => Some(
+            price
+                .as_str()
+                .and_then(|price| price.parse::<BigDecimal>().ok())
+                .ok_or_else(|| AppError::InternalError("Stored selling price is invalid".to_string()))?,
+        ),
+        None => None,
+    };
+
+    Ok(UpdateListingRequest {
+        title: text("title"),
+        description: text("description"),
+        category: text("category"),
+        brand_name: None,
+        original_value: None,
+        selling_price,
+        discount_percentage: None,
+        expiration_date: None,
+        proof_image_url: None,
+        tags,
+        details: restored.get("details").filter(|details| !details.is_null()).map(|details| (*details).clone()),
+    })
+}
@@ -1,107 +1,1051 @@
 use crate::auth::AuthUser;
-use crate::error::AppError;
+use crate::config::Config;
+use crate::error::{AppError, ErrorBody};
+use crate::logging;
 use crate::marketplace::MarketplaceService;
+use crate::marketplace::badges::BadgeService;
+use crate::marketplace::verification::SellerVerificationService;
+use crate::marketplace::moderation::ModerationService;
+use crate::marketplace::scrubbing::ContactScrubber;
+use crate::marketplace::expired_reports::ExpiredCodeReportService;
+use crate::marketplace::admin_bulk::{AdminBulkService, MAX_BULK_ITEMS};
+use crate::marketplace::commission::CommissionService;
+use crate::marketplace::coupon_reveal::{CouponRevealService, RevealContext};
+use crate::marketplace::protection::PurchaseProtectionService;
+use crate::marketplace::dispute_evidence::{self, DisputeEvidenceService};
+use crate::marketplace::high_value::HighValueService;
+use crate::marketplace::refunds::RefundService;
+use crate::marketplace::cart::{self, CartService, CheckoutDiscounts};
+use crate::marketplace::bundles::{self, BundleService};
+use crate::marketplace::pricing::{PricingQuery, PricingService};
+use crate::marketplace::tags::TagService;
+use crate::marketplace::storefront::StorefrontService;
+use crate::marketplace::promotions::PromotionService;
+use crate::marketplace::promo_codes::PromoCodeService;
+use crate::marketplace::loyalty::LoyaltyService;
+use crate::marketplace::wallet::WalletService;
+use crate::marketplace::upi::UpiPaymentService;
+use crate::marketplace::notifications::NotificationService;
+use crate::marketplace::preferences::ProfilePreferenceService;
+use crate::marketplace::paypal::PaypalService;
+use crate::marketplace::payout_accounts::PayoutAccountService;
+use crate::marketplace::payment_methods::PaymentMethodService;
+use crate::marketplace::quotas::SellerQuotaService;
+use crate::marketplace::shadow_bans::ShadowBanService;
+use crate::marketplace::impersonation::{self, ActingUser, ImpersonationService};
+use crate::marketplace::away::AwayService;
+use crate::marketplace::purchases::PurchaseHistoryService;
+use crate::marketplace::holds::CheckoutHolds;
+use crate::marketplace::side_effects::SideEffectService;
+use crate::marketplace::timeline::TransactionTimelineService;
+use crate::marketplace::sessions::{self, SessionService};
+use crate::marketplace::step_up::{self, StepUpService};
+use crate::marketplace::feature_flags::{flags, FeatureFlagService};
+use crate::marketplace::partner_keys::{self, PartnerKeyService};
+use crate::marketplace::reversals::PaymentReversalService;
+use crate::marketplace::policy::ListingPolicyService;
+use crate::marketplace::facets::FacetService;
+use crate::marketplace::search::{self, SearchSuggestService};
+use crate::marketplace::seo::{self, SeoDocument, SeoService};
+use crate::marketplace::categories::CategoryService;
+use crate::marketplace::brands::BrandService;
+use crate::marketplace::cache::{CategoryStats, MarketplaceCache};
+use crate::marketplace::feed::FeedService;
+use crate::marketplace::follows::FollowService;
+use crate::marketplace::bulk::BulkListingService;
+use crate::marketplace::price_history::PriceHistoryService;
+use crate::marketplace::revisions::ListingRevisionService;
+use crate::marketplace::translations::{self, ListingTranslationService};
+use crate::marketplace::faq::ListingFaqService;
+use crate::marketplace::listing_events::ListingEventService;
+use crate::marketplace::analytics::AnalyticsService;
+use crate::marketplace::export::DataExportService;
+use crate::marketplace::deletion::AccountDeletionService;
+use crate::marketplace::anomaly::{self, AnomalyDetector};
+use crate::marketplace::archive::ListingArchiveService;
+use crate::marketplace::retention::RetentionService;
+use crate::marketplace::event_schemas::EventSchemaService;
+use crate::marketplace::etag;
+use crate::marketplace::replica;
+use crate::marketplace::regions;
+use crate::marketplace::fields::{self, ListingFields};
+use crate::marketplace::versioning;
+use crate::marketplace::openapi::ApiDoc;
 use crate::models::marketplace::*;
+use crate::validation::{FieldError, Validate, Validator};
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    response::IntoResponse,
+    body::Bytes,
+    extract::{DefaultBodyLimit, Path, Query, Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::{self, Next},
+    response::{sse::{KeepAlive, Sse}, IntoResponse, Response},
     routing::{delete, get, post, put},
     Json, Router,
 };
+use bigdecimal::BigDecimal;
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use utoipa_swagger_ui::SwaggerUi;
 use uuid::Uuid;
 
+// Route paths are relative to the API version; `versioning::mount` adds the prefixes
 pub fn public_routes(pool: PgPool) -> Router {
-    Router::new()
-        .route("/api/marketplace/listings", get(get_listings))
-        .route("/api/marketplace/listings/:id", get(get_listing))
-        .route("/api/marketplace/profile/:user_id", get(get_user_profile))
-        .with_state(pool)
+    // Listing and profile reads are fetched repeatedly; unchanged results revalidate with a 304.
+    // Anonymous browsing is also cached briefly so traffic spikes do not reach Postgres.
+    let conditional = Router::new()
+        .route("/listings", get(get_listings).layer(middleware::from_fn(http_cache::cache_anonymous)))
+        .route("/listings/:id", get(get_listing))
+        .route("/brands/:slug/listings", get(get_brand_listings))
+        .route("/profile/:user_id", get(get_user_profile))
+        .route("/profile/:user_id/trust-history", get(get_trust_history))
+        .route("/store/:slug", get(get_storefront_page))
+        .route_layer(middleware::from_fn(etag::conditional_get));
+
+    let routes = Router::new()
+        .merge(conditional)
+        .route("/listings/:id/price-history", get(get_price_history))
+        .route("/listings/:id/events", get(stream_listing_events))
+        .route("/listings/:id/translations", get(get_listing_translations))
+        .route("/listings/:id/faq", get(get_listing_faq))
+        .route("/categories", get(get_categories))
+        .route("/categories/:category/stats", get(get_category_stats))
+        .route("/brands", get(get_brands))
+        .route("/brands/:slug", get(get_brand))
+        .route("/tags/popular", get(get_popular_tags))
+        .route("/search/suggest", get(get_search_suggestions))
+        .route("/bundles", get(get_bundles))
+        .route("/bundles/:id", get(get_bundle))
+        .route("/sitemap.xml", get(get_sitemap_index))
+        .route("/sitemaps/:name", get(get_sitemap))
+        .route("/feeds/merchant.xml", get(get_merchant_feed_xml))
+        .route("/feeds/merchant.csv", get(get_merchant_feed_csv))
+        .route("/webhooks/payments", post(handle_payment_webhook))
+        .route("/webhooks/paypal", post(handle_paypal_webhook))
+        .with_state(pool);
+
+    versioning::mount(routes)
+        // Spec generated from the handler annotations, with Swagger UI on top
+        .merge(SwaggerUi::new("/api/v1/marketplace/docs").url("/api/v1/marketplace/openapi.json", ApiDoc::openapi()))
 }
 
 pub fn authenticated_routes(pool: PgPool) -> Router {
-    Router::new()
+    let routes = Router::new()
         // Listing management
-        .route("/api/marketplace/listings", post(create_listing))
-        .route("/api/marketplace/listings/:id", put(update_listing))
-        .route("/api/marketplace/listings/:id", delete(delete_listing))
-        .route("/api/marketplace/listings/:id/verify", post(submit_for_verification))
-        .route("/api/marketplace/listings/:id/coupon", get(get_coupon_code))
+        .route("/listings", post(create_listing))
+        .route("/listings/bulk", post(create_listings_bulk))
+        .route("/pricing-suggestion", get(get_pricing_suggestion))
+        .route("/listings/:id", put(update_listing))
+        .route("/listings/:id", delete(delete_listing))
+        .route("/listings/:id/history", get(get_listing_history))
+        .route("/listings/:id/history/:revision/revert", post(revert_listing))
+        .route("/listings/:id/translations/:locale", put(upsert_listing_translation))
+        .route("/listings/:id/translations/:locale", delete(delete_listing_translation))
+        .route("/listings/:id/faq", put(replace_listing_faq))
+        .route("/listings/:id/verify", post(submit_for_verification))
+        .route(
+            "/listings/:id/coupon",
+            // Reveals the codes, so refused while impersonating
+            get(get_coupon_code).layer(middleware::from_fn_with_state(pool.clone(), impersonation::refuse)),
+        )
+        .route("/listings/:id/coupon/reveal-token", post(issue_coupon_reveal_token))
+        .route("/listings/:id/favorite", post(add_favorite))
+        .route("/listings/:id/favorite", delete(remove_favorite))
+        .route("/listings/:id/expired-reports", post(report_expired_code))
+        .route("/listings/:id/expired-reports/appeal", post(appeal_expired_takedown))
         
         // Transaction management
-        .route("/api/marketplace/transactions", post(create_transaction))
-        .route("/api/marketplace/transactions", get(get_user_transactions))
-        .route("/api/marketplace/transactions/:id", get(get_transaction))
-        .route("/api/marketplace/transactions/:id/complete", put(complete_transaction))
-        .route("/api/marketplace/transactions/:id/cancel", put(cancel_transaction))
-        .route("/api/marketplace/transactions/:id/dispute", post(dispute_transaction))
+        .route("/transactions", post(create_transaction))
+        .route("/transactions", get(get_user_transactions))
+        .route("/transactions/:id", get(get_transaction))
+        .route("/transactions/:id/complete", put(complete_transaction))
+        .route("/transactions/:id/cancel", put(cancel_transaction))
+        .route("/transactions/:id/dispute", post(dispute_transaction))
+        .route(
+            "/transactions/:id/dispute/evidence",
+            // Room for the largest file on top of the default limit
+            post(upload_dispute_evidence).layer(DefaultBodyLimit::max(dispute_evidence::MAX_EVIDENCE_BYTES + 64 * 1024)),
+        )
+        .route("/transactions/:id/dispute/evidence", get(get_dispute_evidence))
+        .route("/transactions/:id/dispute/evidence/:evidence_id", get(download_dispute_evidence))
+        .route("/transactions/:id/refund", post(refund_transaction))
+        .route("/transactions/:id/refunds", get(get_transaction_refunds))
+        
+        // Purchase history
+        .route("/purchases", get(get_purchases))
+        .route("/purchases.csv", get(export_purchases_csv))
+        .route("/purchases/:id/code", get(get_purchase_code))
+        
+        .route("/bundles", post(create_bundle))
+        .route("/bundles/:id", delete(deactivate_bundle))
+        .route("/bundles/:id/purchase", post(purchase_bundle))
+        .route("/storefront", get(get_my_storefront))
+        .route("/storefront", put(update_storefront))
+        .route("/promotions", post(create_promotion))
+        .route("/promotions", get(get_my_promotions))
+        .route("/promotions/:id", delete(cancel_promotion))
+        .route("/credits", get(get_credit_balance))
+        .route("/loyalty", get(get_loyalty_points))
+        .route("/wallet", get(get_wallet))
+        .route("/wallet/topups", post(create_wallet_topup))
+        .route("/wallet/topups/:id/upi", post(pay_wallet_topup_by_upi))
+        .route("/wallet/withdrawals", post(withdraw_from_wallet))
+        .route("/cart", get(get_cart))
+        .route("/cart", delete(clear_cart))
+        .route("/cart/items", post(add_cart_item))
+        .route("/cart/items/:listing_id", put(update_cart_item))
+        .route("/cart/items/:listing_id", delete(remove_cart_item))
+        .route("/cart/checkout", post(checkout_cart))
+        .route("/cart/promo-codes/:code", get(get_promo_quote))
+        .route("/checkouts/:id", get(get_checkout))
+        .route("/checkouts/:id/upi", post(pay_checkout_by_upi))
+        .route("/upi-payments/:id", get(get_upi_payment))
+        .route("/checkouts/:id/paypal", post(pay_checkout_by_paypal))
+        .route("/checkouts/:id/payment-method", post(pay_checkout_with_payment_method))
+        .route("/paypal-orders/:id/capture", post(capture_paypal_order))
+        .route("/transactions/:id/protection-claim", post(open_protection_claim))
+        .route("/transactions/:id/protection-claim", get(get_protection_claim))
+        .route("/transactions/:id/protection-claim/response", post(respond_to_protection_claim))
+        .route("/transactions/:id/high-value-check", get(get_high_value_check))
+        .route("/transactions/:id/confirmation-code", post(send_high_value_confirmation_code))
+        .route("/transactions/:id/confirm-purchase", post(confirm_high_value_purchase))
         
         // Review management
-        .route("/api/marketplace/reviews", post(create_review))
-        .route("/api/marketplace/reviews/user/:user_id", get(get_user_reviews))
-        .route("/api/marketplace/reviews/listing/:listing_id", get(get_listing_reviews))
+        .route("/reviews", post(create_review))
+        .route("/reviews/user/:user_id", get(get_user_reviews))
+        .route("/reviews/listing/:listing_id", get(get_listing_reviews))
         
         // Payment methods
-        .route("/api/marketplace/payment-methods", post(add_payment_method))
-        .route("/api/marketplace/payment-methods", get(get_payment_methods))
-        .route("/api/marketplace/payment-methods/:id", delete(delete_payment_method))
+        .route("/payment-methods", post(add_payment_method))
+        .route("/payment-methods", get(get_payment_methods))
+        .route("/payment-methods/:id", delete(delete_payment_method))
+        .route("/payment-methods/:id/default", put(set_default_payment_method))
+        
+        // Seller payouts
+        .route("/payouts/account", get(get_payout_account))
+        .route("/payouts/onboarding-link", post(create_payout_onboarding_link))
         
         // Notifications
-        .route("/api/marketplace/notifications", get(get_notifications))
-        .route("/api/marketplace/notifications/:id/read", put(mark_notification_read))
-        .route("/api/marketplace/notifications/settings", get(get_notification_settings))
-        .route("/api/marketplace/notifications/settings", put(update_notification_settings))
+        .route("/notifications", get(get_notifications))
+        .route("/notifications/:id/read", put(mark_notification_read))
+        .route("/notifications/settings", get(get_notification_settings))
+        .route("/notifications/settings", put(update_notification_settings))
+        .route("/profile/preferences", get(get_profile_preferences))
+        .route("/profile/preferences", put(update_profile_preferences))
+        
+        // Archived listings
+        .route("/archive/listings", get(get_archived_listings))
+        .route("/archive/listings/:id", get(get_archived_listing))
+        
+        // Seller verification
+        .route("/seller-verification", post(submit_seller_verification))
+        .route("/seller-verification", get(get_seller_verification))
+        .route("/admin/seller-verifications", get(get_seller_verification_queue))
+        .route("/admin/seller-verifications/:id", put(review_seller_verification))
+        .route("/admin/verification/claim", post(claim_seller_verification))
+        .route("/admin/verification/metrics", get(get_verifier_metrics))
+
+        // Content moderation
+        .route("/admin/moderation", get(get_moderation_queue))
+        .route("/admin/moderation/:id", put(review_moderation_case))
+        .route("/admin/moderation/contact-offenders", get(get_contact_offenders))
+        .route("/admin/shadow-bans", get(get_shadow_bans))
+        .route("/admin/shadow-bans", post(create_shadow_ban))
+        .route("/admin/shadow-bans/:user_id", delete(lift_shadow_ban))
+        
+        // Coupon code reveal audit
+        .route("/admin/coupon-reveals", get(get_coupon_reveals))
+        
+        // Disputes and purchase protection claims
+        .route("/admin/disputes", get(get_open_disputes))
+        .route("/admin/protection-claims", get(get_protection_claim_queue))
+        .route("/admin/protection-claims/:id", put(resolve_protection_claim))
+        
+        // Feature flags
+        .route("/feature-flags", get(get_my_feature_flags))
+        .route("/admin/feature-flags", get(get_feature_flags))
+        .route("/admin/feature-flags/:name", put(update_feature_flag))
+        
+        // Partner API keys
+        .route("/partner/keys", get(get_partner_keys))
+        .route("/partner/keys", post(create_partner_key))
+        .route("/partner/keys/:id", delete(revoke_partner_key))
+        .route("/partner/keys/:id/usage", get(get_partner_key_usage))
+        .route("/admin/partners", post(create_partner))
+        .route("/admin/partners", get(get_partners))
+        .route("/admin/partner-keys/:id/limits", put(set_partner_key_limits))
+        
+        // Support impersonation
+        .route("/admin/impersonation", post(start_impersonation))
+        .route("/admin/impersonation/:id", delete(revoke_impersonation))
+        .route("/admin/impersonation/:id/audit", get(get_impersonation_audit))
+        
+        // Brand registry
+        .route("/admin/brands", post(create_brand))
+        
+        // Admin listing oversight
+        .route("/admin/listings", get(get_admin_listings))
+        .route("/admin/transactions/:id/confirm-payment", put(confirm_payment))
+        .route("/admin/transactions/:id/timeline", get(get_transaction_timeline))
+        .route("/admin/high-value-reviews", get(get_high_value_review_queue))
+        .route("/admin/transactions/:id/high-value-review", put(review_high_value_transaction))
+        .route("/admin/checkouts/:id/confirm-payment", put(confirm_checkout_payment))
+        .route("/admin/promotions/:id/confirm-payment", put(confirm_promotion_payment))
+        .route("/admin/wallet/topups/:id/confirm-payment", put(confirm_wallet_topup))
+        .route("/admin/credits", post(grant_credits))
+        .route("/admin/sellers/:user_id/quota", put(set_seller_quota))
+        .route("/admin/sellers/:user_id/quota", delete(remove_seller_quota))
+        .route("/admin/promo-campaigns", post(create_promo_campaign))
+        .route("/admin/promo-campaigns", get(get_promo_campaigns))
+        .route("/admin/promo-campaigns/:id", delete(deactivate_promo_campaign))
+        
+        // Admin bulk actions
+        .route("/admin/bulk/suspend-seller-listings", post(bulk_suspend_seller_listings))
+        .route("/admin/bulk/dismiss-expired-reports", post(bulk_dismiss_expired_reports))
+        .route("/admin/bulk/reverify-listings", post(bulk_reverify_listings))
+        
+        // Data retention
+        .route("/admin/retention", get(get_retention_status))
+        
+        // Event contracts
+        .route("/admin/events/schemas", get(get_event_schemas))
+        .route("/admin/events/test", post(send_test_event))
+        
+        // Admin analytics
+        .route("/admin/analytics", get(get_analytics_kpis))
+        .route("/admin/analytics/gmv", get(get_analytics_gmv))
+        .route("/admin/analytics/categories", get(get_analytics_categories))
+        .route("/admin/analytics/cohorts", get(get_analytics_cohorts))
+        
+        // Automatic account restrictions
+        .route("/admin/restrictions", get(get_account_restrictions))
+        .route("/admin/restrictions/:id/lift", put(lift_account_restriction))
+        
+        // Payment reversals and chargebacks
+        .route("/admin/payment-reversals", get(get_payment_reversals))
+        
+        // Side effects that ran out of retries
+        .route("/admin/dead-letters", get(get_dead_letters))
+        .route("/admin/dead-letters/:id/replay", put(replay_dead_letter))
+        
+        // Prohibited-items policy
+        .route("/admin/policy-rules", get(get_policy_rules))
+        .route("/admin/policy-rules", post(create_policy_rule))
+        .route("/admin/policy-rules/:id", put(update_policy_rule))
+        .route("/admin/policy-rules/:id", delete(delete_policy_rule))
+        
+        // Recommendations
+        .route("/feed", get(get_feed))
+        .route("/feed/following", get(get_following_feed))
+        
+        // Following sellers
+        .route("/sellers/:user_id/follow", post(follow_seller))
+        .route("/sellers/:user_id/follow", delete(unfollow_seller))
         
         // Dashboard
-        .route("/api/marketplace/dashboard", get(get_dashboard))
-        .route("/api/marketplace/my-listings", get(get_my_listings))
-        .with_state(pool)
+        .route("/dashboard", get(get_dashboard))
+        .route("/commission/history", get(get_commission_history))
+        .route("/my-listings", get(get_my_listings))
+        .route("/my-limits", get(get_my_limits))
+        
+        // Personal data export
+        // Exports hand over all of the user's data, so refused while impersonating
+        .route(
+            "/export",
+            get(request_data_export).layer(middleware::from_fn_with_state(pool.clone(), impersonation::refuse)),
+        )
+        .route("/export/:id", get(get_data_export))
+        .route(
+            "/export/:id/download",
+            get(download_data_export).layer(middleware::from_fn_with_state(pool.clone(), impersonation::refuse)),
+        )
+        
+        // Account deletion
+        .route("/account/deletion", post(request_account_deletion))
+        .route("/account/deletion", get(get_account_deletion))
+        
+        // Vacation mode and account deactivation
+        .route("/account/away", get(get_away_status))
+        .route("/account/away", delete(reactivate_account))
+        .route("/account/vacation", put(start_vacation))
+        .route("/account/deactivation", post(deactivate_account))
+        
+        // Reply to buyers' first messages
+        .route("/account/auto-reply", get(get_auto_reply))
+        .route("/account/auto-reply", put(set_auto_reply))
+        .route("/account/auto-reply", delete(delete_auto_reply))
+        
+        // Step-up authentication for sensitive actions
+        .route("/step-up/code", post(send_step_up_code))
+        .route("/step-up/verify", post(verify_step_up))
+        
+        // Signed-in sessions
+        .route("/sessions", get(get_sessions))
+        .route("/sessions", delete(revoke_other_sessions))
+        .route("/sessions/:id", delete(revoke_session))
+        .route_layer(middleware::from_fn(record_request_user))
+        .route_layer(middleware::from_fn_with_state(pool.clone(), step_up::require))
+        // So everything inside sees the impersonated user
+        .route_layer(middleware::from_fn_with_state(pool.clone(), impersonation::apply))
+        // Outermost, so sessions are those of whoever holds the token, not who they impersonate
+        .route_layer(middleware::from_fn_with_state(pool.clone(), sessions::track))
+        .with_state(pool);
+
+    versioning::mount(routes)
+}
+
+/// Read-only listing endpoints for aggregator partners, authenticated and metered by API key
+pub fn partner_routes(pool: PgPool) -> Router {
+    let routes = Router::new()
+        .route("/listings", get(get_listings))
+        .route("/listings/:id", get(get_partner_listing))
+        .route("/categories", get(get_categories))
+        .route("/brands", get(get_brands))
+        .route("/brands/:slug", get(get_brand))
+        .route("/brands/:slug/listings", get(get_brand_listings))
+        .route_layer(middleware::from_fn_with_state(pool.clone(), partner_keys::authenticate))
+        .with_state(pool);
+
+    Router::new().nest(versioning::PARTNER_PREFIX, routes)
+}
+
+// Adds the authenticated user id to the request log span
+async fn record_request_user(ActingUser(auth_user): ActingUser, request: Request, next: Next) -> Response {
+    logging::record_user_id(&auth_user.0.auth0_id);
+    next.run(request).await
+}
+
+// Locale listing text is shown in, from Accept-Language
+fn negotiated_locale(headers: &HeaderMap) -> Option<&'static str> {
+    translations::negotiate(headers.get(header::ACCEPT_LANGUAGE).and_then(|value| value.to_str().ok()))
+}
+
+// Tells clients and caches which locale a listing response was negotiated for
+fn localized(mut response: Response, locale: Option<&'static str>) -> Response {
+    let headers = response.headers_mut();
+    if let Some(locale) = locale {
+        headers.insert(header::CONTENT_LANGUAGE, HeaderValue::from_static(locale));
+    }
+    headers.insert(header::VARY, HeaderValue::from_static("accept-language"));
+    response
 }
 
 // Public endpoints
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/listings",
+    tag = "listings",
+    params(
+        ListingFilters,
+        ListingFieldsParams,
+        ("Accept-Language" = Option<String>, Header, description = "Preferred locales; listing text is translated into the best supported one where a translation exists"),
+    ),
+    responses(
+        (status = 200, description = "Paginated listings with facet counts for the same filters; only the requested fields when `fields` is given. Sold-out listings held by an unpaid checkout show as `reserved` in full responses", body = ListingSearchResponse<ListingWithSeller>),
+        (status = 304, description = "Unchanged since the ETag sent in If-None-Match"),
+        (status = 400, description = "Unknown field requested", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    )
+)]
 async fn get_listings(
     State(pool): State<PgPool>,
-    Query(filters): Query<ListingFilters>,
-) -> Result<impl IntoResponse, AppError> {
-    let service = MarketplaceService::new(pool);
-    let listings = service.get_listings(filters).await?;
-    Ok(Json(listings))
+    headers: HeaderMap,
+    Query(mut filters): Query<ListingFilters>,
+    Query(params): Query<ListingFieldsParams>,
+) -> Result<Response, AppError> {
+    params.validate()?;
+    regions::scope(&mut filters, None)?;
+
+    let locale = negotiated_locale(&headers);
+    let translation_service = ListingTranslationService::new(pool.clone());
+    let service = MarketplaceService::new(pool.clone());
+    let facet_service = FacetService::new(replica::read_pool(&pool));
+    let facets = async {
+        match filters.facets.unwrap_or(true) {
+            true => facet_service.get_facets(&filters).await.map(Some),
+            false => Ok(None),
+        }
+    };
+
+    match params.listing_fields()? {
+        Some(fields) => {
+            let (mut results, facets) = tokio::try_join!(service.get_listings_sparse(filters.clone(), &fields), facets)?;
+            if let Some(locale) = locale {
+                translation_service.localize_sparse(&mut results.items, locale).await?;
+            }
+            Ok(localized(Json(ListingSearchResponse { results, facets }).into_response(), locale))
+        }
+        None => {
+            let (mut results, facets) = tokio::try_join!(service.get_listings(filters.clone()), facets)?;
+            if let Some(locale) = locale {
+                translation_service.localize(&mut results.items, locale).await?;
+            }
+            CheckoutHolds::from_config().mark_reserved(&mut results.items).await;
+            Ok(localized(Json(ListingSearchResponse { results, facets }).into_response(), locale))
+        }
+    }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/listings/{id}",
+    tag = "listings",
+    params(
+        ("id" = Uuid, Path, description = "Listing ID"),
+        ListingFieldsParams,
+        ("Accept-Language" = Option<String>, Header, description = "Preferred locales; listing text is translated into the best supported one where a translation exists"),
+    ),
+    responses(
+        (status = 200, description = "Listing with seller info and the first FAQ entries; only the requested fields when `fields` is given. A sold-out listing held by an unpaid checkout shows as `reserved` until `reserved_until` in full responses", body = ListingWithSeller),
+        (status = 304, description = "Unchanged since the ETag sent in If-None-Match"),
+        (status = 400, description = "Unknown field requested", body = ErrorBody),
+        (status = 404, description = "Listing not found", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    )
+)]
 async fn get_listing(
     State(pool): State<PgPool>,
+    viewer: Option<AuthUser>,
+    headers: HeaderMap,
     Path(id): Path<Uuid>,
+    Query(params): Query<ListingFieldsParams>,
+) -> Result<Response, AppError> {
+    params.validate()?;
+
+    let locale = negotiated_locale(&headers);
+    let translation_service = ListingTranslationService::new(pool.clone());
+    let faq_service = ListingFaqService::new(pool.clone());
+    let service = MarketplaceService::new(pool);
+    let response = match params.listing_fields()? {
+        Some(fields) => {
+            let mut listing = service.get_listing_sparse(id, &fields, viewer.as_ref()).await?;
+            if let Some(locale) = locale {
+                translation_service.localize_sparse(std::slice::from_mut(&mut listing), locale).await?;
+            }
+            Json(listing).into_response()
+        }
+        None => {
+            let mut listing = service.get_listing(id, viewer.as_ref()).await?;
+            if let Some(locale) = locale {
+                translation_service.localize(std::slice::from_mut(&mut listing), locale).await?;
+            }
+            listing.faq = faq_service.common_questions(id).await?;
+            CheckoutHolds::from_config().mark_reserved(std::slice::from_mut(&mut listing)).await;
+            Json(listing).into_response()
+        }
+    };
+    let response = localized(response, locale);
+
+    // Feed the hourly view counters used for trending; best effort
+    let cache = MarketplaceCache::new(Config::get().redis_url.clone());
+    let _ = cache.increment_view_count(&id).await;
+
+    Ok(response)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/categories",
+    tag = "categories",
+    responses(
+        (status = 200, description = "Category tree with listing counts", body = Vec<CategoryNode>),
+    )
+)]
+async fn get_categories(
+    State(pool): State<PgPool>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = CategoryService::new(pool);
+    let categories = service.get_tree().await?;
+    Ok(Json(categories))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/categories/{category}/stats",
+    tag = "categories",
+    params(("category" = String, Path, description = "Category slug")),
+    responses(
+        (status = 200, description = "Price distribution and top brands", body = CategoryStats),
+        (status = 422, description = "Unknown category", body = ErrorBody),
+    )
+)]
+async fn get_category_stats(
+    State(pool): State<PgPool>,
+    Path(category): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = CategoryService::new(pool);
+    let cache = MarketplaceCache::new(Config::get().redis_url.clone());
+    let stats = service.get_stats(&cache, &category).await?;
+    Ok(Json(stats))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/brands",
+    tag = "brands",
+    responses(
+        (status = 200, description = "Registered brands", body = Vec<MarketplaceBrand>),
+    )
+)]
+async fn get_brands(
+    State(pool): State<PgPool>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = BrandService::new(pool);
+    let brands = service.list_brands().await?;
+    Ok(Json(brands))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/brands/{slug}",
+    tag = "brands",
+    params(("slug" = String, Path, description = "Brand slug")),
+    responses(
+        (status = 200, description = "Brand landing page", body = BrandLanding),
+        (status = 404, description = "Brand not found", body = ErrorBody),
+    )
+)]
+async fn get_brand(
+    State(pool): State<PgPool>,
+    Path(slug): Path<String>,
 ) -> Result<impl IntoResponse, AppError> {
+    let service = BrandService::new(pool);
+    let landing = service.get_landing(&slug).await?;
+    Ok(Json(landing))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/brands/{slug}/listings",
+    tag = "brands",
+    params(
+        ("slug" = String, Path, description = "Brand slug"),
+        ListingFilters,
+        ListingFieldsParams,
+        ("Accept-Language" = Option<String>, Header, description = "Preferred locales; listing text is translated into the best supported one where a translation exists"),
+    ),
+    responses(
+        (status = 200, description = "Paginated listings for the brand; only the requested fields when `fields` is given", body = PaginatedResponse<ListingWithSeller>),
+        (status = 304, description = "Unchanged since the ETag sent in If-None-Match"),
+        (status = 400, description = "Unknown field requested", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    )
+)]
+async fn get_brand_listings(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    Path(slug): Path<String>,
+    Query(mut filters): Query<ListingFilters>,
+    Query(params): Query<ListingFieldsParams>,
+) -> Result<Response, AppError> {
+    params.validate()?;
+
+    let locale = negotiated_locale(&headers);
+    let translation_service = ListingTranslationService::new(pool.clone());
     let service = MarketplaceService::new(pool);
-    let listing = service.get_listing(id).await?;
+    filters.brand = Some(slug);
+    filters.status.get_or_insert(ListingStatus::Active);
+    regions::scope(&mut filters, None)?;
+    let response = match params.listing_fields()? {
+        Some(fields) => {
+            let mut listings = service.get_listings_sparse(filters, &fields).await?;
+            if let Some(locale) = locale {
+                translation_service.localize_sparse(&mut listings.items, locale).await?;
+            }
+            Json(listings).into_response()
+        }
+        None => {
+            let mut listings = service.get_listings(filters).await?;
+            if let Some(locale) = locale {
+                translation_service.localize(&mut listings.items, locale).await?;
+            }
+            Json(listings).into_response()
+        }
+    };
+    Ok(localized(response, locale))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/listings/{id}/price-history",
+    tag = "listings",
+    params(("id" = Uuid, Path, description = "Listing ID")),
+    responses(
+        (status = 200, description = "Price changes, oldest first", body = Vec<PriceHistoryEntry>),
+    )
+)]
+async fn get_price_history(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = PriceHistoryService::new(pool);
+    let history = service.get_history(id).await?;
+    Ok(Json(history))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/listings/{id}/events",
+    tag = "listings",
+    params(("id" = Uuid, Path, description = "Listing ID")),
+    responses(
+        (status = 200, description = "Server-sent events: a snapshot, then status_changed, price_changed and stock_changed as they happen", body = ListingAvailabilityEvent, content_type = "text/event-stream"),
+        (status = 404, description = "Listing not found", body = ErrorBody),
+        (status = 503, description = "Live listing updates are not available", body = ErrorBody),
+    )
+)]
+async fn stream_listing_events(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = ListingEventService::new(pool)?;
+    let events = service.subscribe(id).await?;
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/listings/{id}/history",
+    tag = "listings",
+    params(("id" = Uuid, Path, description = "Listing ID")),
+    responses(
+        (status = 200, description = "Edits of the listing with the fields each changed, newest first", body = Vec<ListingRevision>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not the listing's seller or an admin", body = ErrorBody),
+        (status = 404, description = "Listing not found", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_listing_history(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = ListingRevisionService::new(pool);
+    let history = service.history(&auth_user, id).await?;
+    Ok(Json(history))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/listings/{id}/history/{revision}/revert",
+    tag = "listings",
+    params(
+        ("id" = Uuid, Path, description = "Listing ID"),
+        ("revision" = i32, Path, description = "Revision to restore; 0 restores the listing as created"),
+    ),
+    responses(
+        (status = 200, description = "Listing with the revision's fields restored, recorded as a new revision", body = MarketplaceListing),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not the listing's seller or an admin", body = ErrorBody),
+        (status = 404, description = "Listing or revision not found", body = ErrorBody),
+        (status = 409, description = "The listing already matches the revision", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn revert_listing(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path((id, revision)): Path<(Uuid, i32)>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = ListingRevisionService::new(pool);
+    let listing = service.revert(&auth_user, id, revision).await?;
+    Ok(Json(listing))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/listings/{id}/translations",
+    tag = "listings",
+    params(("id" = Uuid, Path, description = "Listing ID")),
+    responses(
+        (status = 200, description = "Translations of the listing by locale, written by the seller or machine-translated", body = Vec<ListingTranslation>),
+    )
+)]
+async fn get_listing_translations(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = ListingTranslationService::new(pool);
+    let translations = service.list(id).await?;
+    Ok(Json(translations))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/marketplace/listings/{id}/translations/{locale}",
+    tag = "listings",
+    params(
+        ("id" = Uuid, Path, description = "Listing ID"),
+        ("locale" = String, Path, description = "Locale of the translation: en, hi or es"),
+    ),
+    request_body = UpsertListingTranslationRequest,
+    responses(
+        (status = 200, description = "Translation saved, replacing any machine translation", body = ListingTranslation),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not the listing's seller", body = ErrorBody),
+        (status = 404, description = "Listing not found", body = ErrorBody),
+        (status = 422, description = "Unsupported locale, the listing's own language, or the text failed validation or moderation", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn upsert_listing_translation(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path((id, locale)): Path<(Uuid, String)>,
+    Json(request): Json<UpsertListingTranslationRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let service = ListingTranslationService::new(pool);
+    let translation = service.upsert(&auth_user, id, &locale, request).await?;
+    Ok(Json(translation))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/marketplace/listings/{id}/translations/{locale}",
+    tag = "listings",
+    params(
+        ("id" = Uuid, Path, description = "Listing ID"),
+        ("locale" = String, Path, description = "Locale of the translation"),
+    ),
+    responses(
+        (status = 204, description = "Translation removed"),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not the listing's seller", body = ErrorBody),
+        (status = 404, description = "Listing or translation not found", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn delete_listing_translation(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path((id, locale)): Path<(Uuid, String)>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = ListingTranslationService::new(pool);
+    service.delete(&auth_user, id, &locale).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/listings/{id}/faq",
+    tag = "listings",
+    params(("id" = Uuid, Path, description = "Listing ID")),
+    responses(
+        (status = 200, description = "The seller's questions and answers in order, with the seller's auto-reply", body = ListingFaq),
+        (status = 404, description = "Listing not found", body = ErrorBody),
+    )
+)]
+async fn get_listing_faq(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let faq = ListingFaqService::new(pool).get(id).await?;
+    Ok(Json(faq))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/marketplace/listings/{id}/faq",
+    tag = "listings",
+    params(("id" = Uuid, Path, description = "Listing ID")),
+    request_body = ReplaceListingFaqRequest,
+    responses(
+        (status = 200, description = "The listing's questions and answers, replaced by the ones sent", body = Vec<ListingFaqEntry>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not the listing's seller", body = ErrorBody),
+        (status = 404, description = "Listing not found", body = ErrorBody),
+        (status = 422, description = "The text failed validation, contact screening or moderation", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn replace_listing_faq(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<ReplaceListingFaqRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let entries = ListingFaqService::new(pool).replace(&auth_user, id, request).await?;
+    Ok(Json(entries))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/listings/{id}/expired-reports",
+    tag = "listings",
+    params(("id" = Uuid, Path, description = "Listing ID")),
+    responses(
+        (status = 200, description = "Report counted; enough reports mark the listing expired", body = ExpiredCodeReportOutcome),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "No live discount code listing with this ID", body = ErrorBody),
+        (status = 409, description = "Already reported by the caller", body = ErrorBody),
+        (status = 429, description = "Too many reports", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn report_expired_code(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    FeatureFlagService::new(pool.clone())
+        .require(flags::EXPIRED_CODE_REPORTS, &auth_user.0.auth0_id)
+        .await?;
+
+    let outcome = ExpiredCodeReportService::new(pool).report(&auth_user, id).await?;
+    Ok(Json(outcome))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/listings/{id}/expired-reports/appeal",
+    tag = "listings",
+    params(("id" = Uuid, Path, description = "Listing ID")),
+    request_body = AppealExpiredTakedownRequest,
+    responses(
+        (status = 200, description = "Listing held for a moderator, who relists or suspends it", body = MarketplaceListing),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 404, description = "Listing not found", body = ErrorBody),
+        (status = 409, description = "Listing was not taken down by expired code reports", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn appeal_expired_takedown(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<AppealExpiredTakedownRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let listing = ExpiredCodeReportService::new(pool).appeal(&auth_user, id, &request.message).await?;
     Ok(Json(listing))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/listings/{id}/favorite",
+    tag = "favorites",
+    params(("id" = Uuid, Path, description = "Listing ID")),
+    responses(
+        (status = 204, description = "Listing saved"),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 404, description = "Listing not found", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn add_favorite(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = PriceHistoryService::new(pool);
+    service.add_favorite(&auth_user, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/marketplace/listings/{id}/favorite",
+    tag = "favorites",
+    params(("id" = Uuid, Path, description = "Listing ID")),
+    responses(
+        (status = 204, description = "Listing removed from favorites"),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn remove_favorite(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = PriceHistoryService::new(pool);
+    service.remove_favorite(&auth_user, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/listings/{id}/coupon/reveal-token",
+    tag = "listings",
+    params(
+        ("id" = Uuid, Path, description = "Listing ID"),
+        ("X-Step-Up-Token" = Option<String>, Header, description = "Step-up token, when the route is in STEP_UP_ROUTES"),
+    ),
+    responses(
+        (status = 201, description = "Single-use token for one coupon reveal", body = CouponRevealToken),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "No coupon codes to reveal, or step-up authentication required", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn issue_coupon_reveal_token(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(listing_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = CouponRevealService::new(pool);
+    let token = service.issue_token(&auth_user, listing_id).await?;
+    Ok((StatusCode::CREATED, Json(token)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/listings/{id}/coupon",
+    tag = "listings",
+    params(("id" = Uuid, Path, description = "Listing ID"), CouponRevealQuery),
+    responses(
+        (status = 200, description = "Coupon codes visible to the caller, with the watermark of this reveal", body = CouponResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Reveal token invalid, expired or already used", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
 async fn get_coupon_code(
     State(pool): State<PgPool>,
-    auth_user: AuthUser,
+    ActingUser(auth_user): ActingUser,
+    headers: HeaderMap,
     Path(listing_id): Path<Uuid>,
+    Query(query): Query<CouponRevealQuery>,
 ) -> Result<impl IntoResponse, AppError> {
-    let service = MarketplaceService::new(pool);
-    let coupon_code = service.get_coupon_code(&auth_user, listing_id).await?;
-    
-    #[derive(Debug, Clone, Serialize)]
-    struct CouponResponse {
-        coupon_code: Option<String>,
-        has_access: bool,
-    }
+    let ip_address = anomaly::client_ip(&headers);
+    let context = RevealContext {
+        ip_address: ip_address.as_deref(),
+        user_agent: headers.get(header::USER_AGENT).and_then(|value| value.to_str().ok()),
+    };
+
+    let service = CouponRevealService::new(pool);
+    let (coupon_codes, watermark) = service.reveal(&auth_user, listing_id, &query.token, context).await?;
     
     let response = CouponResponse {
-        has_access: coupon_code.is_some(),
-        coupon_code,
+        has_access: !coupon_codes.is_empty(),
+        coupon_code: coupon_codes.first().cloned(),
+        coupon_codes,
+        watermark,
     };
     
     Ok(Json(response))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/profile/{user_id}",
+    tag = "profiles",
+    params(("user_id" = String, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "Public marketplace profile", body = MarketplaceProfile),
+        (status = 304, description = "Unchanged since the ETag sent in If-None-Match"),
+        (status = 404, description = "User not found", body = ErrorBody),
+    )
+)]
 async fn get_user_profile(
     State(pool): State<PgPool>,
     Path(user_id): Path<String>,
@@ -111,40 +1055,162 @@ async fn get_user_profile(
     Ok(Json(profile))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/profile/{user_id}/trust-history",
+    tag = "profiles",
+    params(
+        ("user_id" = String, Path, description = "User ID"),
+        TrustHistoryParams,
+    ),
+    responses(
+        (status = 200, description = "Daily trust score snapshots", body = Vec<TrustScoreSnapshot>),
+        (status = 304, description = "Unchanged since the ETag sent in If-None-Match"),
+    )
+)]
+async fn get_trust_history(
+    State(pool): State<PgPool>,
+    Path(user_id): Path<String>,
+    Query(params): Query<TrustHistoryParams>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = BadgeService::new(pool);
+    let history = service.get_history(&user_id, params.limit.unwrap_or(90)).await?;
+    Ok(Json(history))
+}
+
 // Authenticated endpoints
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/listings",
+    tag = "listings",
+    request_body = CreateListingRequest,
+    responses(
+        (status = 201, description = "Listing created; listings flagged by moderation are held as pending_review", body = MarketplaceListing),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Payout onboarding must be finished first, or the active listing limit is reached", body = ErrorBody),
+        (status = 422, description = "Request validation failed, or the listing is a prohibited item", body = ErrorBody),
+        (status = 429, description = "Account temporarily restricted", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
 async fn create_listing(
     State(pool): State<PgPool>,
-    auth_user: AuthUser,
+    ActingUser(auth_user): ActingUser,
+    headers: HeaderMap,
     Json(request): Json<CreateListingRequest>,
 ) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let user_id = &auth_user.0.auth0_id;
+    let detector = AnomalyDetector::new(pool.clone());
+    detector.ensure_allowed(user_id, RestrictedAction::CreateListing).await?;
+    PayoutAccountService::new(pool.clone())?.ensure_can_list(user_id).await?;
+
     let service = MarketplaceService::new(pool);
-    
-    // Validate discount code listings have coupon codes
-    if request.listing_type == ListingType::DiscountCode && request.coupon_code.is_none() {
-        return Err(AppError::BadRequest(
-            "Discount code listings must include a coupon code".to_string()
-        ));
-    }
-    
     let listing = service.create_listing(&auth_user, request).await?;
+
+    detector
+        .record_activity(user_id, RestrictedAction::CreateListing, anomaly::client_ip(&headers).as_deref())
+        .await?;
+    detector.evaluate_listing_velocity(user_id).await?;
+
     Ok((StatusCode::CREATED, Json(listing)))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/listings/bulk",
+    tag = "listings",
+    request_body(content = Vec<CreateListingRequest>, description = "JSON array of listings, or a text/csv upload with one listing per row"),
+    responses(
+        (status = 201, description = "Created listings and per-row errors", body = BulkCreateListingResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Payout onboarding must be finished first", body = ErrorBody),
+        (status = 429, description = "Bulk listing limit reached or account temporarily restricted", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn create_listings_bulk(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<impl IntoResponse, AppError> {
+    let user_id = &auth_user.0.auth0_id;
+    let detector = AnomalyDetector::new(pool.clone());
+    detector.ensure_allowed(user_id, RestrictedAction::CreateListing).await?;
+    PayoutAccountService::new(pool.clone())?.ensure_can_list(user_id).await?;
+
+    let is_csv = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/csv"));
+
+    // Accept either a CSV upload or a JSON array of listings
+    let (rows, errors) = if is_csv {
+        BulkListingService::parse_csv(&body)
+    } else {
+        let requests: Vec<CreateListingRequest> = serde_json::from_slice(&body)
+            .map_err(|e| AppError::BadRequest(format!("Invalid JSON body: {}", e)))?;
+        (requests.into_iter().enumerate().collect(), vec![])
+    };
+
+    let service = BulkListingService::new(pool);
+    let response = service.create_listings(&auth_user, rows, errors).await?;
+
+    detector
+        .record_activity(user_id, RestrictedAction::CreateListing, anomaly::client_ip(&headers).as_deref())
+        .await?;
+    detector.evaluate_listing_velocity(user_id).await?;
+
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/marketplace/listings/{id}",
+    tag = "listings",
+    params(("id" = Uuid, Path, description = "Listing ID")),
+    request_body = UpdateListingRequest,
+    responses(
+        (status = 200, description = "Updated listing", body = MarketplaceListing),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Listing not found", body = ErrorBody),
+        (status = 422, description = "Request validation failed, or the edited listing is a prohibited item", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
 async fn update_listing(
     State(pool): State<PgPool>,
-    auth_user: AuthUser,
+    ActingUser(auth_user): ActingUser,
     Path(id): Path<Uuid>,
     Json(request): Json<UpdateListingRequest>,
 ) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
     let service = MarketplaceService::new(pool);
     let listing = service.update_listing(&auth_user, id, request).await?;
     Ok(Json(listing))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/marketplace/listings/{id}",
+    tag = "listings",
+    params(("id" = Uuid, Path, description = "Listing ID")),
+    responses(
+        (status = 204, description = "Listing deleted"),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Listing belongs to another seller", body = ErrorBody),
+        (status = 404, description = "Listing not found", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
 async fn delete_listing(
     State(pool): State<PgPool>,
-    auth_user: AuthUser,
+    ActingUser(auth_user): ActingUser,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, AppError> {
     let service = MarketplaceService::new(pool);
@@ -152,6 +1218,17 @@ async fn delete_listing(
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/listings/{id}/verify",
+    tag = "listings",
+    params(("id" = Uuid, Path, description = "Listing ID")),
+    responses(
+        (status = 202, description = "Verification requested"),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
 async fn submit_for_verification(
     State(_pool): State<PgPool>,
     _auth_user: AuthUser,
@@ -161,16 +1238,48 @@ async fn submit_for_verification(
     Ok(StatusCode::ACCEPTED)
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/transactions",
+    tag = "transactions",
+    request_body = CreateTransactionRequest,
+    responses(
+        (status = 201, description = "Transaction created", body = MarketplaceTransaction),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 409, description = "Listing unavailable or out of stock", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+        (status = 429, description = "Account temporarily restricted", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
 async fn create_transaction(
     State(pool): State<PgPool>,
-    auth_user: AuthUser,
+    ActingUser(auth_user): ActingUser,
     Json(request): Json<CreateTransactionRequest>,
 ) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    AnomalyDetector::new(pool.clone())
+        .ensure_allowed(&auth_user.0.auth0_id, RestrictedAction::CreateTransaction)
+        .await?;
+
     let service = MarketplaceService::new(pool);
     let transaction = service.create_transaction(&auth_user, request).await?;
     Ok((StatusCode::CREATED, Json(transaction)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/transactions",
+    tag = "transactions",
+    params(TransactionFilters),
+    responses(
+        (status = 200, description = "The caller's transactions", body = Vec<MarketplaceTransaction>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
 async fn get_user_transactions(
     State(_pool): State<PgPool>,
     _auth_user: AuthUser,
@@ -180,18 +1289,45 @@ async fn get_user_transactions(
     Ok(Json(Vec::<MarketplaceTransaction>::new()))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/transactions/{id}",
+    tag = "transactions",
+    params(("id" = Uuid, Path, description = "Transaction ID")),
+    responses(
+        (status = 200, description = "Transaction detail", body = TransactionDetail),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Transaction not found", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
 async fn get_transaction(
-    State(_pool): State<PgPool>,
-    _auth_user: AuthUser,
-    Path(_id): Path<Uuid>,
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, AppError> {
-    // TODO: Implement get transaction with auth check
-    Ok(StatusCode::OK)
+    let service = MarketplaceService::new(pool);
+    let transaction = service.get_transaction(&auth_user, id).await?;
+    Ok(Json(transaction))
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/marketplace/transactions/{id}/complete",
+    tag = "transactions",
+    params(("id" = Uuid, Path, description = "Transaction ID")),
+    responses(
+        (status = 200, description = "Completed transaction", body = MarketplaceTransaction),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 409, description = "Transaction is not in escrow", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
 async fn complete_transaction(
     State(pool): State<PgPool>,
-    auth_user: AuthUser,
+    ActingUser(auth_user): ActingUser,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, AppError> {
     let service = MarketplaceService::new(pool);
@@ -199,158 +1335,3894 @@ async fn complete_transaction(
     Ok(Json(transaction))
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/marketplace/transactions/{id}/cancel",
+    tag = "transactions",
+    params(("id" = Uuid, Path, description = "Transaction ID")),
+    request_body = CancelTransactionRequest,
+    responses(
+        (status = 200, description = "Cancelled transaction", body = MarketplaceTransaction),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 409, description = "Transaction can no longer be cancelled", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
 async fn cancel_transaction(
-    State(_pool): State<PgPool>,
-    _auth_user: AuthUser,
-    Path(_id): Path<Uuid>,
-    Json(_request): Json<CancelTransactionRequest>,
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<CancelTransactionRequest>,
 ) -> Result<impl IntoResponse, AppError> {
-    // TODO: Implement cancel transaction
-    Ok(StatusCode::OK)
+    request.validate()?;
+
+    let service = MarketplaceService::new(pool);
+    let transaction = service.cancel_transaction(&auth_user, id, &request.reason).await?;
+    Ok(Json(transaction))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/transactions/{id}/dispute",
+    tag = "transactions",
+    params(("id" = Uuid, Path, description = "Transaction ID")),
+    request_body = DisputeTransactionRequest,
+    responses(
+        (status = 202, description = "Dispute opened", body = MarketplaceTransaction),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 409, description = "Transaction can no longer be disputed", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+        (status = 429, description = "Account temporarily restricted", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
 async fn dispute_transaction(
-    State(_pool): State<PgPool>,
-    _auth_user: AuthUser,
-    Path(_id): Path<Uuid>,
-    Json(_request): Json<DisputeTransactionRequest>,
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<DisputeTransactionRequest>,
 ) -> Result<impl IntoResponse, AppError> {
-    // TODO: Implement dispute transaction
-    Ok(StatusCode::ACCEPTED)
+    request.validate()?;
+
+    // Evidence is kept alongside the reason until disputes get their own table
+    let reason = match &request.evidence {
+        Some(evidence) => format!("{}\n\nEvidence: {}", request.reason, evidence),
+        None => request.reason.clone(),
+    };
+
+    let detector = AnomalyDetector::new(pool.clone());
+    detector.ensure_allowed(&auth_user.0.auth0_id, RestrictedAction::OpenDispute).await?;
+
+    let service = MarketplaceService::new(pool);
+    let transaction = service.dispute_transaction(&auth_user, id, &reason).await?;
+
+    if transaction.buyer_id == auth_user.0.auth0_id {
+        detector.evaluate_dispute_rate(&transaction.buyer_id).await?;
+    }
+
+    Ok((StatusCode::ACCEPTED, Json(transaction)))
 }
 
-async fn create_review(
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/transactions/{id}/dispute/evidence",
+    tag = "transactions",
+    params(("id" = Uuid, Path, description = "Transaction ID"), DisputeEvidenceUploadParams),
+    request_body(content = Vec<u8>, description = "The file, as a PNG, JPEG, WebP or PDF of at most 4 MB with a matching Content-Type", content_type = "application/octet-stream"),
+    responses(
+        (status = 201, description = "Evidence attached to the dispute", body = DisputeEvidence),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 409, description = "Transaction is not disputed or has the most evidence files allowed", body = ErrorBody),
+        (status = 413, description = "File too large"),
+        (status = 422, description = "File empty, too large or of an unsupported type", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn upload_dispute_evidence(
     State(pool): State<PgPool>,
-    auth_user: AuthUser,
-    Json(request): Json<CreateReviewRequest>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+    Query(params): Query<DisputeEvidenceUploadParams>,
+    headers: HeaderMap,
+    body: Bytes,
 ) -> Result<impl IntoResponse, AppError> {
-    let service = MarketplaceService::new(pool);
-    let review = service.create_review(&auth_user, request).await?;
-    Ok((StatusCode::CREATED, Json(review)))
+    params.validate()?;
+
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+
+    let service = DisputeEvidenceService::new(pool);
+    let evidence = service
+        .upload(&auth_user, id, content_type, params.file_name.as_deref(), &body)
+        .await?;
+    Ok((StatusCode::CREATED, Json(evidence)))
 }
 
-async fn get_user_reviews(
-    State(_pool): State<PgPool>,
-    Path(_user_id): Path<String>,
-    Query(_params): Query<ReviewFilters>,
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/transactions/{id}/dispute/evidence",
+    tag = "transactions",
+    params(("id" = Uuid, Path, description = "Transaction ID")),
+    responses(
+        (status = 200, description = "Evidence attached to the transaction, oldest first", body = Vec<DisputeEvidence>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Transaction not found", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_dispute_evidence(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, AppError> {
-    // TODO: Implement get user reviews
-    Ok(Json(Vec::<MarketplaceReview>::new()))
+    let evidence = DisputeEvidenceService::new(pool).list(&auth_user, id).await?;
+    Ok(Json(evidence))
 }
 
-async fn get_listing_reviews(
-    State(_pool): State<PgPool>,
-    Path(_listing_id): Path<Uuid>,
-    Query(_params): Query<ReviewFilters>,
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/transactions/{id}/dispute/evidence/{evidence_id}",
+    tag = "transactions",
+    params(
+        ("id" = Uuid, Path, description = "Transaction ID"),
+        ("evidence_id" = Uuid, Path, description = "Evidence ID"),
+    ),
+    responses(
+        (status = 200, description = "The evidence file, as an attachment", body = Vec<u8>, content_type = "application/octet-stream"),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Evidence not found", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn download_dispute_evidence(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path((id, evidence_id)): Path<(Uuid, Uuid)>,
 ) -> Result<impl IntoResponse, AppError> {
-    // TODO: Implement get listing reviews
-    Ok(Json(Vec::<MarketplaceReview>::new()))
+    let service = DisputeEvidenceService::new(pool);
+    let (content, file_name) = service.download(&auth_user, id, evidence_id).await?;
+
+    // Always a download, and never sniffed into something the browser would render
+    let file_name: String = file_name
+        .unwrap_or_else(|| format!("evidence-{}", evidence_id))
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+        .collect();
+    Ok((
+        [
+            (header::CONTENT_TYPE, content.content_type),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", file_name)),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+            (header::CACHE_CONTROL, "private, no-store".to_string()),
+        ],
+        content.data,
+    ))
 }
 
-async fn add_payment_method(
-    State(_pool): State<PgPool>,
-    _auth_user: AuthUser,
-    Json(_request): Json<CreatePaymentMethodRequest>,
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/transactions/{id}/refund",
+    tag = "transactions",
+    params(("id" = Uuid, Path, description = "Transaction ID")),
+    request_body = RefundTransactionRequest,
+    responses(
+        (status = 201, description = "Refund paid out; a full refund of an unfinished sale also cancels it", body = TransactionRefund),
+        (status = 400, description = "Amount is not positive or exceeds what is left to refund", body = ErrorBody),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Only the seller or an admin can refund", body = ErrorBody),
+        (status = 404, description = "Transaction not found", body = ErrorBody),
+        (status = 409, description = "Transaction is not paid or already cancelled", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+        (status = 500, description = "Payment provider rejected the refund", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn refund_transaction(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<RefundTransactionRequest>,
 ) -> Result<impl IntoResponse, AppError> {
-    // TODO: Implement add payment method with Stripe
-    Ok(StatusCode::CREATED)
+    request.validate()?;
+
+    let service = RefundService::new(pool)?;
+    let refund = service.refund(&auth_user, id, request.amount, &request.reason).await?;
+    Ok((StatusCode::CREATED, Json(refund)))
 }
 
-async fn get_payment_methods(
-    State(_pool): State<PgPool>,
-    _auth_user: AuthUser,
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/transactions/{id}/refunds",
+    tag = "transactions",
+    params(("id" = Uuid, Path, description = "Transaction ID")),
+    responses(
+        (status = 200, description = "Refunds of the transaction, newest first", body = [TransactionRefund]),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Transaction not found", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_transaction_refunds(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, AppError> {
-    // TODO: Implement get payment methods
-    Ok(Json(Vec::<UserPaymentMethod>::new()))
+    let service = RefundService::new(pool)?;
+    let refunds = service.list_refunds(&auth_user, id).await?;
+    Ok(Json(refunds))
 }
 
-async fn delete_payment_method(
-    State(_pool): State<PgPool>,
-    _auth_user: AuthUser,
-    Path(_id): Path<Uuid>,
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/purchases",
+    tag = "purchases",
+    params(PurchaseFilters),
+    responses(
+        (status = 200, description = "The caller's purchases with the listing as it was sold, newest first", body = PaginatedResponse<Purchase>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_purchases(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Query(filters): Query<PurchaseFilters>,
 ) -> Result<impl IntoResponse, AppError> {
-    // TODO: Implement delete payment method
-    Ok(StatusCode::NO_CONTENT)
+    let purchases = PurchaseHistoryService::new(pool).list(&auth_user, filters).await?;
+    Ok(Json(purchases))
 }
 
-async fn get_notifications(
-    State(_pool): State<PgPool>,
-    _auth_user: AuthUser,
-    Query(_params): Query<NotificationFilters>,
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/purchases.csv",
+    tag = "purchases",
+    params(PurchaseFilters),
+    responses(
+        (status = 200, description = "The caller's purchases, one per row and without codes", body = String, content_type = "text/csv"),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn export_purchases_csv(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Query(filters): Query<PurchaseFilters>,
 ) -> Result<impl IntoResponse, AppError> {
-    // TODO: Implement get notifications
-    Ok(Json(Vec::<MarketplaceNotification>::new()))
+    let csv = PurchaseHistoryService::new(pool).export_csv(&auth_user, filters).await?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+            (header::CONTENT_DISPOSITION, "attachment; filename=\"purchases.csv\""),
+        ],
+        csv,
+    ))
 }
 
-async fn mark_notification_read(
-    State(_pool): State<PgPool>,
-    _auth_user: AuthUser,
-    Path(_id): Path<Uuid>,
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/purchases/{id}/code",
+    tag = "purchases",
+    params(("id" = Uuid, Path, description = "Transaction ID of the purchase"), CouponRevealQuery),
+    responses(
+        (status = 200, description = "The purchase's codes, with the watermark of this reveal", body = CouponResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not the buyer, no code left to reveal, or reveal token invalid, expired or already used", body = ErrorBody),
+        (status = 404, description = "Purchase not found", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_purchase_code(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Query(query): Query<CouponRevealQuery>,
 ) -> Result<impl IntoResponse, AppError> {
-    // TODO: Implement mark notification as read
-    Ok(StatusCode::OK)
+    let ip_address = anomaly::client_ip(&headers);
+    let context = RevealContext {
+        ip_address: ip_address.as_deref(),
+        user_agent: headers.get(header::USER_AGENT).and_then(|value| value.to_str().ok()),
+    };
+
+    let service = CouponRevealService::new(pool);
+    let (coupon_codes, watermark) = service.reveal_purchase(&auth_user, id, &query.token, context).await?;
+
+    Ok(Json(CouponResponse {
+        has_access: true,
+        coupon_code: coupon_codes.first().cloned(),
+        coupon_codes,
+        watermark,
+    }))
 }
 
-async fn get_notification_settings(
-    State(_pool): State<PgPool>,
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/pricing-suggestion",
+    tag = "listings",
+    params(PricingSuggestionParams),
+    responses(
+        (status = 200, description = "Suggested price from recent sales of similar listings", body = PricingSuggestion),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_pricing_suggestion(
+    State(pool): State<PgPool>,
     _auth_user: AuthUser,
+    Query(params): Query<PricingSuggestionParams>,
 ) -> Result<impl IntoResponse, AppError> {
-    // TODO: Implement get notification settings
-    Ok(Json(NotificationSettings {
-        email_notifications: true,
-        push_notifications: false,
-        new_listing_alerts: true,
-        price_drop_alerts: true,
-        transaction_updates: true,
-        review_notifications: true,
-    }))
+    params.validate()?;
+
+    let service = PricingService::new(pool);
+    let suggestion = service
+        .suggest(&PricingQuery {
+            category: &params.category,
+            listing_type: params.listing_type,
+            brand_name: params.brand_name.as_deref(),
+            original_value: params.original_value.as_ref(),
+        })
+        .await?;
+    Ok(Json(suggestion))
 }
 
-async fn update_notification_settings(
-    State(_pool): State<PgPool>,
-    _auth_user: AuthUser,
-    Json(settings): Json<NotificationSettings>,
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/tags/popular",
+    tag = "listings",
+    params(PopularTagsParams),
+    responses(
+        (status = 200, description = "Most used tags on active listings with listing counts", body = Vec<TagCount>),
+    )
+)]
+async fn get_popular_tags(
+    State(pool): State<PgPool>,
+    Query(params): Query<PopularTagsParams>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = TagService::new(pool);
+    let tags = service
+        .popular_tags(params.category.as_deref(), params.limit.unwrap_or(20))
+        .await?;
+    Ok(Json(tags))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/search/suggest",
+    tag = "listings",
+    params(SearchSuggestParams),
+    responses(
+        (status = 200, description = "Brand, category and title completions, best first", body = Vec<SearchSuggestion>),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    )
+)]
+async fn get_search_suggestions(
+    State(pool): State<PgPool>,
+    Query(params): Query<SearchSuggestParams>,
+) -> Result<impl IntoResponse, AppError> {
+    params.validate()?;
+
+    let region = regions::resolve(params.region.as_deref(), params.all_regions, None)?;
+    let service = SearchSuggestService::new(pool);
+    let suggestions = service.suggest(&params.q, region.as_deref()).await?;
+    Ok(Json(suggestions))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/bundles",
+    tag = "bundles",
+    params(BundleFilters),
+    responses(
+        (status = 200, description = "Bundles on sale, newest first", body = Vec<BundleWithItems>),
+    )
+)]
+async fn get_bundles(
+    State(pool): State<PgPool>,
+    Query(filters): Query<BundleFilters>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = BundleService::new(pool);
+    let bundles = service
+        .list_bundles(filters.seller_id.as_deref(), filters.limit.unwrap_or(20))
+        .await?;
+    Ok(Json(bundles))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/bundles/{id}",
+    tag = "bundles",
+    params(("id" = Uuid, Path, description = "Bundle ID")),
+    responses(
+        (status = 200, description = "Bundle with its listings and savings", body = BundleWithItems),
+        (status = 404, description = "Bundle not found", body = ErrorBody),
+    )
+)]
+async fn get_bundle(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = BundleService::new(pool);
+    let bundle = service.get_bundle(id).await?;
+    Ok(Json(bundle))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/store/{slug}",
+    tag = "storefronts",
+    params(
+        ("slug" = String, Path, description = "Storefront slug"),
+        StorefrontPageParams,
+    ),
+    responses(
+        (status = 200, description = "Seller profile, featured listings and a page of active listings", body = StorefrontPage),
+        (status = 304, description = "Unchanged since the ETag sent in If-None-Match"),
+        (status = 404, description = "Storefront not found", body = ErrorBody),
+    )
+)]
+async fn get_storefront_page(
+    State(pool): State<PgPool>,
+    Path(slug): Path<String>,
+    Query(params): Query<StorefrontPageParams>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = StorefrontService::new(pool);
+    let page = service.get_page(&slug, params.page, params.limit).await?;
+    Ok(Json(page))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/sitemap.xml",
+    tag = "seo",
+    responses(
+        (status = 200, description = "Sitemap index of the listing sitemaps", body = String, content_type = "application/xml"),
+        (status = 304, description = "Not generated again since If-Modified-Since"),
+        (status = 404, description = "Not generated yet", body = ErrorBody),
+    )
+)]
+async fn get_sitemap_index(State(pool): State<PgPool>, headers: HeaderMap) -> Result<Response, AppError> {
+    let document = SeoService::new(pool).document(seo::SITEMAP_INDEX).await?;
+    Ok(seo_document_response(document, &headers))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/sitemaps/{name}",
+    tag = "seo",
+    params(("name" = String, Path, description = "Sitemap page named in the index, e.g. listings-1.xml")),
+    responses(
+        (status = 200, description = "Up to 50,000 listing URLs", body = String, content_type = "application/xml"),
+        (status = 304, description = "Not generated again since If-Modified-Since"),
+        (status = 404, description = "Sitemap not found", body = ErrorBody),
+    )
+)]
+async fn get_sitemap(
+    State(pool): State<PgPool>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    // Only sitemap pages, not the feeds stored alongside them
+    if !name.starts_with("listings-") {
+        return Err(AppError::NotFound("Document not found".to_string()));
+    }
+    let document = SeoService::new(pool).document(&name).await?;
+    Ok(seo_document_response(document, &headers))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/feeds/merchant.xml",
+    tag = "seo",
+    responses(
+        (status = 200, description = "Google Merchant product feed (RSS 2.0)", body = String, content_type = "application/xml"),
+        (status = 304, description = "Not generated again since If-Modified-Since"),
+        (status = 404, description = "Not generated yet", body = ErrorBody),
+    )
+)]
+async fn get_merchant_feed_xml(State(pool): State<PgPool>, headers: HeaderMap) -> Result<Response, AppError> {
+    let document = SeoService::new(pool).document(seo::MERCHANT_FEED_XML).await?;
+    Ok(seo_document_response(document, &headers))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/feeds/merchant.csv",
+    tag = "seo",
+    responses(
+        (status = 200, description = "Google Merchant product feed, one listing per row", body = String, content_type = "text/csv"),
+        (status = 304, description = "Not generated again since If-Modified-Since"),
+        (status = 404, description = "Not generated yet", body = ErrorBody),
+    )
+)]
+async fn get_merchant_feed_csv(State(pool): State<PgPool>, headers: HeaderMap) -> Result<Response, AppError> {
+    let document = SeoService::new(pool).document(seo::MERCHANT_FEED_CSV).await?;
+    Ok(seo_document_response(document, &headers))
+}
+
+// Documents change only when regenerated, so crawlers and CDNs may keep them for the
+// job's interval and revalidate by date. Too large to hash for an ETag.
+fn seo_document_response(document: SeoDocument, headers: &HeaderMap) -> Response {
+    let last_modified = document.generated_at.format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+    let cache_headers = [
+        (header::CACHE_CONTROL, "public, max-age=3600".to_string()),
+        (header::LAST_MODIFIED, last_modified),
+    ];
+
+    let unchanged = headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| chrono::DateTime::parse_from_rfc2822(value).ok())
+        .is_some_and(|since| document.generated_at.timestamp() <= since.timestamp());
+    if unchanged {
+        return (StatusCode::NOT_MODIFIED, cache_headers).into_response();
+    }
+
+    let mut response = (cache_headers, document.body).into_response();
+    if let Ok(content_type) = HeaderValue::from_str(&document.content_type) {
+        response.headers_mut().insert(header::CONTENT_TYPE, content_type);
+    }
+    response
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/bundles",
+    tag = "bundles",
+    request_body = CreateBundleRequest,
+    responses(
+        (status = 201, description = "Bundle created", body = BundleWithItems),
+        (status = 400, description = "Duplicate listings or price not below the combined price", body = ErrorBody),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "A listing belongs to another seller", body = ErrorBody),
+        (status = 404, description = "A listing was not found", body = ErrorBody),
+        (status = 409, description = "A listing is not active or too many bundles", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn create_bundle(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Json(request): Json<CreateBundleRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let service = BundleService::new(pool);
+    let bundle = service
+        .create_bundle(
+            &auth_user,
+            &request.title,
+            request.description.as_deref(),
+            &request.listing_ids,
+            &request.bundle_price,
+        )
+        .await?;
+    Ok((StatusCode::CREATED, Json(bundle)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/marketplace/bundles/{id}",
+    tag = "bundles",
+    params(("id" = Uuid, Path, description = "Bundle ID")),
+    responses(
+        (status = 204, description = "Bundle taken off sale"),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 404, description = "Bundle not found", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn deactivate_bundle(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = BundleService::new(pool);
+    service.deactivate_bundle(&auth_user, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/bundles/{id}/purchase",
+    tag = "bundles",
+    params(("id" = Uuid, Path, description = "Bundle ID")),
+    request_body = CheckoutRequest,
+    responses(
+        (status = 201, description = "Checkout with one pending transaction per bundled listing", body = CheckoutDetail),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Bundle or promo code not found", body = ErrorBody),
+        (status = 409, description = "Bundle off sale, a listing sold out, the promo code used up, too few points or wallet funds; nothing was reserved", body = ErrorBody),
+        (status = 422, description = "Request validation failed or the bundle is not eligible for the promo code or points", body = ErrorBody),
+        (status = 429, description = "Account temporarily restricted", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn purchase_bundle(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<CheckoutRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    AnomalyDetector::new(pool.clone())
+        .ensure_allowed(&auth_user.0.auth0_id, RestrictedAction::CreateTransaction)
+        .await?;
+
+    let service = BundleService::new(pool);
+    let checkout = service
+        .purchase(&auth_user, id, &request.payment_method, &request.discounts())
+        .await?;
+    Ok((StatusCode::CREATED, Json(checkout)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/storefront",
+    tag = "storefronts",
+    responses(
+        (status = 200, description = "Your storefront, created with a default slug on first access", body = Storefront),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_my_storefront(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let service = StorefrontService::new(pool);
+    let storefront = service.get_my_storefront(&auth_user).await?;
+    Ok(Json(storefront))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/marketplace/storefront",
+    tag = "storefronts",
+    request_body = UpdateStorefrontRequest,
+    responses(
+        (status = 200, description = "Updated storefront", body = Storefront),
+        (status = 400, description = "Featured listing is not your own active listing", body = ErrorBody),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 409, description = "Slug already taken", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn update_storefront(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Json(request): Json<UpdateStorefrontRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let service = StorefrontService::new(pool);
+    let storefront = service.update_storefront(&auth_user, request).await?;
+    Ok(Json(storefront))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/promotions",
+    tag = "promotions",
+    request_body = CreatePromotionRequest,
+    responses(
+        (status = 201, description = "Promotion booked; card promotions await payment confirmation", body = Promotion),
+        (status = 400, description = "Start is too far ahead", body = ErrorBody),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not your listing", body = ErrorBody),
+        (status = 404, description = "Listing not found", body = ErrorBody),
+        (status = 409, description = "Listing inactive or already promoted, no free slot, or not enough credits", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn create_promotion(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Json(request): Json<CreatePromotionRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let service = PromotionService::new(pool);
+    let promotion = service.create(&auth_user, request).await?;
+    Ok((StatusCode::CREATED, Json(promotion)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/promotions",
+    tag = "promotions",
+    responses(
+        (status = 200, description = "Your promotions, newest first", body = Vec<Promotion>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_my_promotions(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let service = PromotionService::new(pool);
+    let promotions = service.list_mine(&auth_user).await?;
+    Ok(Json(promotions))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/marketplace/promotions/{id}",
+    tag = "promotions",
+    params(("id" = Uuid, Path, description = "Promotion ID")),
+    responses(
+        (status = 200, description = "Cancelled promotion; a paid one is refunded", body = Promotion),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Promotion not found", body = ErrorBody),
+        (status = 409, description = "Promotion has already started", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn cancel_promotion(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = PromotionService::new(pool);
+    let promotion = service.cancel(&auth_user, id).await?;
+    Ok(Json(promotion))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/credits",
+    tag = "promotions",
+    responses(
+        (status = 200, description = "Loyalty credit balance and recent entries", body = CreditBalance),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_credit_balance(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let service = PromotionService::new(pool);
+    let balance = service.credit_balance(&auth_user).await?;
+    Ok(Json(balance))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/loyalty",
+    tag = "loyalty",
+    responses(
+        (status = 200, description = "Spendable loyalty points and recent entries", body = LoyaltyHistory),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_loyalty_points(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let service = LoyaltyService::new(pool);
+    let history = service.history(&auth_user.0.auth0_id).await?;
+    Ok(Json(history))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/wallet",
+    tag = "wallet",
+    responses(
+        (status = 200, description = "Wallet balance, withdrawable amount and recent entries", body = Wallet),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_wallet(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let service = WalletService::new(pool)?;
+    let wallet = service.get_wallet(&auth_user.0.auth0_id).await?;
+    Ok(Json(wallet))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/wallet/topups",
+    tag = "wallet",
+    request_body = WalletTopupRequest,
+    responses(
+        (status = 201, description = "Pending top-up, credited once its payment is confirmed", body = WalletTopup),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 409, description = "The wallet would exceed its maximum balance", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn create_wallet_topup(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Json(request): Json<WalletTopupRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let service = WalletService::new(pool)?;
+    let topup = service.create_topup(&auth_user, request).await?;
+    Ok((StatusCode::CREATED, Json(topup)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/wallet/withdrawals",
+    tag = "wallet",
+    request_body = WalletWithdrawalRequest,
+    responses(
+        (status = 201, description = "One withdrawal per top-up refunded; failed ones are returned to the wallet", body = Vec<WalletWithdrawal>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 409, description = "Amount exceeds the balance or what can go back to the funding payments", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn withdraw_from_wallet(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Json(request): Json<WalletWithdrawalRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let service = WalletService::new(pool)?;
+    let withdrawals = service.withdraw(&auth_user, &request.amount).await?;
+    Ok((StatusCode::CREATED, Json(withdrawals)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/wallet/topups/{id}/upi",
+    tag = "upi",
+    params(("id" = Uuid, Path, description = "Top-up ID")),
+    request_body = StartUpiPaymentRequest,
+    responses(
+        (status = 201, description = "Pending payment with an intent link, or a collect request sent to the VPA", body = UpiPayment),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Top-up not found", body = ErrorBody),
+        (status = 409, description = "Top-up not awaiting payment or a UPI payment is in progress", body = ErrorBody),
+        (status = 422, description = "Request validation failed or the top-up is funded by card", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn pay_wallet_topup_by_upi(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<StartUpiPaymentRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let service = UpiPaymentService::new(pool)?;
+    let payment = service.pay_topup(&auth_user, id, &request).await?;
+    Ok((StatusCode::CREATED, Json(payment)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/cart",
+    tag = "cart",
+    responses(
+        (status = 200, description = "The caller's cart", body = Cart),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_cart(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let service = CartService::new(pool);
+    let cart = service.get_cart(&auth_user.0.auth0_id).await?;
+    Ok(Json(cart))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/cart/items",
+    tag = "cart",
+    request_body = AddCartItemRequest,
+    responses(
+        (status = 200, description = "Listing added to the cart", body = Cart),
+        (status = 400, description = "Too many units of one listing", body = ErrorBody),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Listing belongs to the caller", body = ErrorBody),
+        (status = 404, description = "Listing not found", body = ErrorBody),
+        (status = 409, description = "Listing unavailable, out of stock or cart full", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn add_cart_item(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Json(request): Json<AddCartItemRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let service = CartService::new(pool);
+    let cart = service
+        .add_item(&auth_user.0.auth0_id, request.listing_id, request.quantity.unwrap_or(1))
+        .await?;
+    Ok(Json(cart))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/marketplace/cart/items/{listing_id}",
+    tag = "cart",
+    params(("listing_id" = Uuid, Path, description = "Listing ID")),
+    request_body = UpdateCartItemRequest,
+    responses(
+        (status = 200, description = "Quantity updated", body = Cart),
+        (status = 400, description = "Too many units of one listing", body = ErrorBody),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 404, description = "Listing is not in the cart", body = ErrorBody),
+        (status = 409, description = "Listing unavailable or out of stock", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn update_cart_item(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(listing_id): Path<Uuid>,
+    Json(request): Json<UpdateCartItemRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let service = CartService::new(pool);
+    let cart = service.update_item(&auth_user.0.auth0_id, listing_id, request.quantity).await?;
+    Ok(Json(cart))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/marketplace/cart/items/{listing_id}",
+    tag = "cart",
+    params(("listing_id" = Uuid, Path, description = "Listing ID")),
+    responses(
+        (status = 200, description = "Listing removed from the cart", body = Cart),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 404, description = "Listing is not in the cart", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn remove_cart_item(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(listing_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = CartService::new(pool);
+    let cart = service.remove_item(&auth_user.0.auth0_id, listing_id).await?;
+    Ok(Json(cart))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/marketplace/cart",
+    tag = "cart",
+    responses(
+        (status = 204, description = "Cart emptied"),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn clear_cart(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let service = CartService::new(pool);
+    service.clear(&auth_user.0.auth0_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/cart/checkout",
+    tag = "cart",
+    request_body = CheckoutRequest,
+    responses(
+        (status = 201, description = "One pending transaction per unit, all under one payment", body = CheckoutDetail),
+        (status = 400, description = "Cart is empty", body = ErrorBody),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Unknown or inactive promo code", body = ErrorBody),
+        (status = 409, description = "An item is unavailable or out of stock, the promo code is used up, too few points or wallet funds; nothing was reserved", body = ErrorBody),
+        (status = 422, description = "Request validation failed or the cart is not eligible for the promo code or points", body = ErrorBody),
+        (status = 429, description = "Account temporarily restricted", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn checkout_cart(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Json(request): Json<CheckoutRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    AnomalyDetector::new(pool.clone())
+        .ensure_allowed(&auth_user.0.auth0_id, RestrictedAction::CreateTransaction)
+        .await?;
+
+    let service = CartService::new(pool);
+    let checkout = service
+        .checkout(&auth_user, &request.payment_method, &request.discounts())
+        .await?;
+    Ok((StatusCode::CREATED, Json(checkout)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/cart/promo-codes/{code}",
+    tag = "cart",
+    params(("code" = String, Path, description = "Promo code, case-insensitive")),
+    responses(
+        (status = 200, description = "Discount the code would give on the current cart", body = PromoQuote),
+        (status = 400, description = "Cart is empty", body = ErrorBody),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 404, description = "Unknown or inactive promo code", body = ErrorBody),
+        (status = 409, description = "Usage limit reached", body = ErrorBody),
+        (status = 422, description = "Cart is not eligible for the code", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_promo_quote(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(code): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = PromoCodeService::new(pool);
+    let quote = service.quote(&auth_user.0.auth0_id, &code).await?;
+    Ok(Json(quote))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/checkouts/{id}",
+    tag = "cart",
+    params(("id" = Uuid, Path, description = "Checkout ID")),
+    responses(
+        (status = 200, description = "The checkout and the escrow status of each item", body = CheckoutDetail),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Checkout not found", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_checkout(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = CartService::new(pool);
+    let checkout = service.get_checkout(&auth_user, id).await?;
+    Ok(Json(checkout))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/checkouts/{id}/upi",
+    tag = "upi",
+    params(("id" = Uuid, Path, description = "Checkout ID")),
+    request_body = StartUpiPaymentRequest,
+    responses(
+        (status = 201, description = "Pending payment with an intent link, or a collect request sent to the VPA", body = UpiPayment),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Checkout not found", body = ErrorBody),
+        (status = 409, description = "Checkout already paid or a UPI payment is in progress", body = ErrorBody),
+        (status = 422, description = "Request validation failed or nothing is due", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn pay_checkout_by_upi(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<StartUpiPaymentRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let service = UpiPaymentService::new(pool)?;
+    let payment = service.pay_checkout(&auth_user, id, &request).await?;
+    Ok((StatusCode::CREATED, Json(payment)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/upi-payments/{id}",
+    tag = "upi",
+    params(("id" = Uuid, Path, description = "UPI payment ID")),
+    responses(
+        (status = 200, description = "The payment, checked with the provider while pending", body = UpiPayment),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Payment not found", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_upi_payment(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = UpiPaymentService::new(pool)?;
+    let payment = service.get_payment(&auth_user, id).await?;
+    Ok(Json(payment))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/webhooks/payments",
+    tag = "upi",
+    request_body(content = String, description = "Provider webhook payload, signed in the X-Razorpay-Signature header"),
+    responses(
+        (status = 200, description = "Event applied or ignored"),
+        (status = 400, description = "Malformed payload", body = ErrorBody),
+        (status = 403, description = "Missing or invalid signature", body = ErrorBody),
+    )
+)]
+async fn handle_payment_webhook(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<impl IntoResponse, AppError> {
+    let service = UpiPaymentService::new(pool)?;
+    service.handle_webhook(&body, &headers).await?;
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/checkouts/{id}/payment-method",
+    tag = "payment-methods",
+    params(("id" = Uuid, Path, description = "Checkout ID")),
+    request_body = PayWithPaymentMethodRequest,
+    responses(
+        (status = 200, description = "The checkout, paid unless the provider is still processing the charge", body = CheckoutDetail),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Checkout or payment method not found", body = ErrorBody),
+        (status = 409, description = "Checkout already paid", body = ErrorBody),
+        (status = 422, description = "Payment declined, the method cannot be charged or nothing is due", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn pay_checkout_with_payment_method(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<PayWithPaymentMethodRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = PaymentMethodService::new(pool)?;
+    let checkout = service.pay_checkout(&auth_user, id, request.payment_method_id).await?;
+    Ok(Json(checkout))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/checkouts/{id}/paypal",
+    tag = "paypal",
+    params(("id" = Uuid, Path, description = "Checkout ID")),
+    responses(
+        (status = 201, description = "PayPal order; send the buyer to its approval URL", body = PaypalOrder),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Checkout not found", body = ErrorBody),
+        (status = 409, description = "Checkout already paid", body = ErrorBody),
+        (status = 422, description = "Nothing is due", body = ErrorBody),
+        (status = 503, description = "PayPal is not available", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn pay_checkout_by_paypal(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = PaypalService::new(pool)?;
+    let order = service.create_order(&auth_user, id).await?;
+    Ok((StatusCode::CREATED, Json(order)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/paypal-orders/{id}/capture",
+    tag = "paypal",
+    params(("id" = Uuid, Path, description = "PayPal order ID")),
+    responses(
+        (status = 200, description = "The checkout, paid unless PayPal is still reviewing the capture", body = CheckoutDetail),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Order not found", body = ErrorBody),
+        (status = 409, description = "Order already captured", body = ErrorBody),
+        (status = 422, description = "PayPal declined the payment", body = ErrorBody),
+        (status = 503, description = "PayPal is not available", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn capture_paypal_order(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = PaypalService::new(pool)?;
+    let checkout = service.capture(&auth_user, id).await?;
+    Ok(Json(checkout))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/webhooks/paypal",
+    tag = "paypal",
+    request_body(content = String, description = "PayPal webhook event, verified with PayPal from its PAYPAL-TRANSMISSION-* headers"),
+    responses(
+        (status = 200, description = "Event applied or ignored"),
+        (status = 400, description = "Malformed payload", body = ErrorBody),
+        (status = 403, description = "Missing or invalid signature", body = ErrorBody),
+        (status = 503, description = "PayPal is not available", body = ErrorBody),
+    )
+)]
+async fn handle_paypal_webhook(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<impl IntoResponse, AppError> {
+    let service = PaypalService::new(pool)?;
+    service.handle_webhook(&body, &headers).await?;
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/transactions/{id}/protection-claim",
+    tag = "purchase-protection",
+    params(("id" = Uuid, Path, description = "Transaction ID")),
+    request_body = OpenProtectionClaimRequest,
+    responses(
+        (status = 201, description = "Claim opened and escrow frozen; refunded at once if the code is provably invalid", body = ProtectionClaim),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 409, description = "Not in escrow, code not revealed, claim window closed or claim already open", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+        (status = 429, description = "Account temporarily restricted", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn open_protection_claim(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<OpenProtectionClaimRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let user_id = &auth_user.0.auth0_id;
+    let detector = AnomalyDetector::new(pool.clone());
+    detector.ensure_allowed(user_id, RestrictedAction::OpenDispute).await?;
+
+    let service = PurchaseProtectionService::new(pool);
+    let claim = service.open_claim(&auth_user, id, request.details.as_deref()).await?;
+
+    detector.evaluate_dispute_rate(user_id).await?;
+
+    Ok((StatusCode::CREATED, Json(claim)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/transactions/{id}/protection-claim",
+    tag = "purchase-protection",
+    params(("id" = Uuid, Path, description = "Transaction ID")),
+    responses(
+        (status = 200, description = "The transaction's purchase protection claim", body = ProtectionClaim),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "No claim for this transaction", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_protection_claim(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = PurchaseProtectionService::new(pool);
+    let claim = service.get_claim(&auth_user, id).await?;
+    Ok(Json(claim))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/transactions/{id}/protection-claim/response",
+    tag = "purchase-protection",
+    params(("id" = Uuid, Path, description = "Transaction ID")),
+    request_body = ProtectionClaimResponseRequest,
+    responses(
+        (status = 200, description = "Seller's response recorded for admin review", body = ProtectionClaim),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "No claim for this transaction", body = ErrorBody),
+        (status = 409, description = "Response deadline passed or already responded", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn respond_to_protection_claim(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<ProtectionClaimResponseRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let service = PurchaseProtectionService::new(pool);
+    let claim = service.respond(&auth_user, id, &request.message).await?;
+    Ok(Json(claim))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/transactions/{id}/high-value-check",
+    tag = "transactions",
+    params(("id" = Uuid, Path, description = "Transaction ID")),
+    responses(
+        (status = 200, description = "Buyer confirmation and seller review of a high-value transaction", body = HighValueCheck),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Transaction does not need high-value checks", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_high_value_check(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let check = HighValueService::new(pool).get_check(&auth_user, id).await?;
+    Ok(Json(check))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/transactions/{id}/confirmation-code",
+    tag = "transactions",
+    params(("id" = Uuid, Path, description = "Transaction ID")),
+    responses(
+        (status = 204, description = "Confirmation code emailed to the buyer, replacing any earlier one"),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 409, description = "No purchase confirmation pending for the caller", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn send_high_value_confirmation_code(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    HighValueService::new(pool).send_code(&auth_user, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/transactions/{id}/confirm-purchase",
+    tag = "transactions",
+    params(("id" = Uuid, Path, description = "Transaction ID")),
+    request_body = ConfirmHighValueTransactionRequest,
+    responses(
+        (status = 200, description = "Purchase confirmed; a held payment moves to escrow once the seller review passed", body = HighValueCheck),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 404, description = "No purchase confirmation for the caller", body = ErrorBody),
+        (status = 409, description = "Already confirmed, or no valid code", body = ErrorBody),
+        (status = 422, description = "Wrong code or request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn confirm_high_value_purchase(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<ConfirmHighValueTransactionRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let check = HighValueService::new(pool).confirm_buyer(&auth_user, id, &request.code).await?;
+    Ok(Json(check))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/reviews",
+    tag = "reviews",
+    request_body = CreateReviewRequest,
+    responses(
+        (status = 201, description = "Review created", body = MarketplaceReview),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 409, description = "Transaction not completed or already reviewed", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn create_review(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Json(request): Json<CreateReviewRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let service = MarketplaceService::new(pool);
+    let review = service.create_review(&auth_user, request).await?;
+    Ok((StatusCode::CREATED, Json(review)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/reviews/user/{user_id}",
+    tag = "reviews",
+    params(
+        ("user_id" = String, Path, description = "User ID"),
+        ReviewFilters,
+    ),
+    responses(
+        (status = 200, description = "Reviews of the user", body = Vec<MarketplaceReview>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_user_reviews(
+    State(pool): State<PgPool>,
+    Path(user_id): Path<String>,
+    Query(params): Query<ReviewFilters>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = MarketplaceService::new(pool);
+    let reviews = service.get_user_reviews(&user_id, params).await?;
+    Ok(Json(reviews))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/reviews/listing/{listing_id}",
+    tag = "reviews",
+    params(
+        ("listing_id" = Uuid, Path, description = "Listing ID"),
+        ReviewFilters,
+    ),
+    responses(
+        (status = 200, description = "Reviews for the listing", body = Vec<MarketplaceReview>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_listing_reviews(
+    State(pool): State<PgPool>,
+    Path(listing_id): Path<Uuid>,
+    Query(params): Query<ReviewFilters>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = MarketplaceService::new(pool);
+    let reviews = service.get_listing_reviews(listing_id, params).await?;
+    Ok(Json(reviews))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/payment-methods",
+    tag = "payment-methods",
+    request_body = CreatePaymentMethodRequest,
+    responses(
+        (status = 201, description = "Payment method saved from the provider's token", body = UserPaymentMethod),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 422, description = "Request validation failed or unsupported payment method type", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn add_payment_method(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Json(request): Json<CreatePaymentMethodRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let service = PaymentMethodService::new(pool)?;
+    let method = service.add(&auth_user, &request).await?;
+    Ok((StatusCode::CREATED, Json(method)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/payment-methods",
+    tag = "payment-methods",
+    responses(
+        (status = 200, description = "Saved payment methods", body = Vec<UserPaymentMethod>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_payment_methods(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let service = PaymentMethodService::new(pool)?;
+    let methods = service.list(&auth_user.0.auth0_id).await?;
+    Ok(Json(methods))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/marketplace/payment-methods/{id}",
+    tag = "payment-methods",
+    params(("id" = Uuid, Path, description = "Payment method ID")),
+    responses(
+        (status = 204, description = "Payment method removed"),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 404, description = "Payment method not found", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn delete_payment_method(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = PaymentMethodService::new(pool)?;
+    service.delete(&auth_user.0.auth0_id, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/marketplace/payment-methods/{id}/default",
+    tag = "payment-methods",
+    params(("id" = Uuid, Path, description = "Payment method ID")),
+    responses(
+        (status = 200, description = "The new default; the previous one no longer is", body = UserPaymentMethod),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 404, description = "Payment method not found", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn set_default_payment_method(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = PaymentMethodService::new(pool)?;
+    let method = service.set_default(&auth_user.0.auth0_id, id).await?;
+    Ok(Json(method))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/payouts/account",
+    tag = "payouts",
+    responses(
+        (status = 200, description = "The caller's payout account, with what the provider still needs", body = PayoutAccount),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 404, description = "Onboarding not started", body = ErrorBody),
+        (status = 503, description = "Payout onboarding is not available", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_payout_account(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let service = PayoutAccountService::new(pool)?;
+    let account = service.get_account(&auth_user.0.auth0_id).await?;
+    Ok(Json(account))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/payouts/onboarding-link",
+    tag = "payouts",
+    params(("X-Step-Up-Token" = Option<String>, Header, description = "Step-up token, when the route is in STEP_UP_ROUTES")),
+    responses(
+        (status = 201, description = "Link to the provider's hosted onboarding; the account is opened on first use", body = PayoutOnboardingLink),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Step-up authentication required", body = ErrorBody),
+        (status = 409, description = "Onboarding is already complete", body = ErrorBody),
+        (status = 503, description = "Payout onboarding is not available", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn create_payout_onboarding_link(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let service = PayoutAccountService::new(pool)?;
+    let link = service.onboarding_link(&auth_user).await?;
+    Ok((StatusCode::CREATED, Json(link)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/notifications",
+    tag = "notifications",
+    params(NotificationFilters),
+    responses(
+        (status = 200, description = "The caller's notifications", body = Vec<MarketplaceNotification>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_notifications(
+    State(_pool): State<PgPool>,
+    _auth_user: AuthUser,
+    Query(_params): Query<NotificationFilters>,
+) -> Result<impl IntoResponse, AppError> {
+    // TODO: Implement get notifications
+    Ok(Json(Vec::<MarketplaceNotification>::new()))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/marketplace/notifications/{id}/read",
+    tag = "notifications",
+    params(("id" = Uuid, Path, description = "Notification ID")),
+    responses(
+        (status = 200, description = "Notification marked as read"),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn mark_notification_read(
+    State(_pool): State<PgPool>,
+    _auth_user: AuthUser,
+    Path(_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    // TODO: Implement mark notification as read
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/notifications/settings",
+    tag = "notifications",
+    responses(
+        (status = 200, description = "Notification settings", body = NotificationSettings),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_notification_settings(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let service = NotificationService::new(pool)?;
+    let settings = service.get_settings(&auth_user.0.auth0_id).await?;
+    Ok(Json(settings))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/marketplace/notifications/settings",
+    tag = "notifications",
+    request_body = NotificationSettings,
+    responses(
+        (status = 200, description = "Updated notification settings; changing digest_mode starts a new digest period", body = NotificationSettings),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 422, description = "Request validation failed or unknown time zone", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn update_notification_settings(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Json(settings): Json<NotificationSettings>,
+) -> Result<impl IntoResponse, AppError> {
+    settings.validate()?;
+
+    let service = NotificationService::new(pool)?;
+    let settings = service.update_settings(&auth_user.0.auth0_id, &settings).await?;
+    Ok(Json(settings))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/profile/preferences",
+    tag = "profiles",
+    responses(
+        (status = 200, description = "The caller's profile preferences", body = ProfilePreferences),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_profile_preferences(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let service = ProfilePreferenceService::new(pool);
+    let preferences = service.get(&auth_user.0.auth0_id).await?;
+    Ok(Json(preferences))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/marketplace/profile/preferences",
+    tag = "profiles",
+    request_body = ProfilePreferences,
+    responses(
+        (status = 200, description = "Updated profile preferences; emails and pushes use the locale from now on", body = ProfilePreferences),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 422, description = "Unsupported locale", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn update_profile_preferences(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Json(preferences): Json<ProfilePreferences>,
+) -> Result<impl IntoResponse, AppError> {
+    preferences.validate()?;
+
+    let service = ProfilePreferenceService::new(pool);
+    let preferences = service.update(&auth_user.0.auth0_id, &preferences).await?;
+    Ok(Json(preferences))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/seller-verification",
+    tag = "seller-verification",
+    request_body = SubmitSellerVerificationRequest,
+    responses(
+        (status = 201, description = "Verification submitted", body = SellerVerification),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 409, description = "Already verified or under review", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn submit_seller_verification(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Json(request): Json<SubmitSellerVerificationRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let service = SellerVerificationService::new(pool);
+    let verification = service.submit(&auth_user, request).await?;
+    Ok((StatusCode::CREATED, Json(verification)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/seller-verification",
+    tag = "seller-verification",
+    responses(
+        (status = 200, description = "Latest verification request, if any", body = Option<SellerVerification>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_seller_verification(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let service = SellerVerificationService::new(pool);
+    let verification = service.get_my_verification(&auth_user).await?;
+    Ok(Json(verification))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/admin/seller-verifications",
+    tag = "admin",
+    params(VerificationQueueFilters),
+    responses(
+        (status = 200, description = "Verification review queue, escalated submissions first", body = Vec<SellerVerification>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_seller_verification_queue(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Query(params): Query<VerificationQueueFilters>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = SellerVerificationService::new(pool);
+    let queue = service.get_queue(&auth_user, params.status).await?;
+    Ok(Json(queue))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/marketplace/admin/seller-verifications/{id}",
+    tag = "admin",
+    params(("id" = Uuid, Path, description = "Verification ID")),
+    request_body = ReviewSellerVerificationRequest,
+    responses(
+        (status = 200, description = "Reviewed verification", body = SellerVerification),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Verification request not found", body = ErrorBody),
+        (status = 409, description = "Verifier does not hold an active claim on the request", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn review_seller_verification(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<ReviewSellerVerificationRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = SellerVerificationService::new(pool);
+    let verification = service.review(&auth_user, id, request).await?;
+    Ok(Json(verification))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/admin/verification/claim",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Claimed verification request; the claim expires after the review SLA", body = ClaimedVerification),
+        (status = 204, description = "Nothing waiting for review"),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn claim_seller_verification(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<Response, AppError> {
+    let service = SellerVerificationService::new(pool);
+    match service.claim_next(&auth_user).await? {
+        Some(claimed) => Ok(Json(claimed).into_response()),
+        None => Ok(StatusCode::NO_CONTENT.into_response()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/admin/verification/metrics",
+    tag = "admin",
+    params(VerifierMetricsParams),
+    responses(
+        (status = 200, description = "Claims and decisions per verifier", body = Vec<VerifierMetrics>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_verifier_metrics(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Query(params): Query<VerifierMetricsParams>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = SellerVerificationService::new(pool);
+    let metrics = service.verifier_metrics(&auth_user, params.days.unwrap_or(30)).await?;
+    Ok(Json(metrics))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/admin/disputes",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Open disputes, oldest first, with links to their evidence", body = Vec<DisputeCase>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_open_disputes(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let disputes = DisputeEvidenceService::new(pool).open_disputes(&auth_user).await?;
+    Ok(Json(disputes))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/admin/protection-claims",
+    tag = "admin",
+    params(ProtectionClaimFilters),
+    responses(
+        (status = 200, description = "Purchase protection claims, oldest first", body = Vec<ProtectionClaim>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_protection_claim_queue(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Query(filters): Query<ProtectionClaimFilters>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = PurchaseProtectionService::new(pool);
+    let queue = service.get_queue(&auth_user, filters.status).await?;
+    Ok(Json(queue))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/marketplace/admin/protection-claims/{id}",
+    tag = "admin",
+    params(("id" = Uuid, Path, description = "Claim ID")),
+    request_body = ResolveProtectionClaimRequest,
+    responses(
+        (status = 200, description = "Claim resolved; the buyer is refunded or the escrow released", body = ProtectionClaim),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Claim not found", body = ErrorBody),
+        (status = 409, description = "Claim already resolved", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn resolve_protection_claim(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<ResolveProtectionClaimRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = PurchaseProtectionService::new(pool);
+    let claim = service
+        .resolve(&auth_user, id, request.refund, request.notes.as_deref())
+        .await?;
+    Ok(Json(claim))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/admin/coupon-reveals",
+    tag = "admin",
+    params(CouponRevealFilters),
+    responses(
+        (status = 200, description = "Coupon reveal log for tracing leaked codes", body = Vec<CouponRevealEvent>),
+        (status = 400, description = "No filter given", body = ErrorBody),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_coupon_reveals(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Query(filters): Query<CouponRevealFilters>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = CouponRevealService::new(pool);
+    let reveals = service
+        .list_reveals(&auth_user, filters.listing_id, filters.reference.as_deref())
+        .await?;
+    Ok(Json(reveals))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/admin/moderation",
+    tag = "admin",
+    params(ModerationQueueFilters),
+    responses(
+        (status = 200, description = "Listings held by content moderation", body = Vec<ModerationCase>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_moderation_queue(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Query(params): Query<ModerationQueueFilters>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = ModerationService::new(pool);
+    let queue = service.get_queue(&auth_user, params.status).await?;
+    Ok(Json(queue))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/marketplace/admin/moderation/{id}",
+    tag = "admin",
+    params(("id" = Uuid, Path, description = "Moderation case ID")),
+    request_body = ReviewModerationCaseRequest,
+    responses(
+        (status = 200, description = "Reviewed case; approved listings are published, rejected ones suspended", body = ModerationCase),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Moderation case not found", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn review_moderation_case(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<ReviewModerationCaseRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = ModerationService::new(pool);
+    let case = service.review(&auth_user, id, request).await?;
+    Ok(Json(case))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/admin/high-value-reviews",
+    tag = "admin",
+    responses(
+        (status = 200, description = "High-value transactions awaiting a seller review, oldest first", body = Vec<HighValueCheck>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_high_value_review_queue(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let checks = HighValueService::new(pool).review_queue(&auth_user).await?;
+    Ok(Json(checks))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/marketplace/admin/transactions/{id}/high-value-review",
+    tag = "admin",
+    params(("id" = Uuid, Path, description = "Transaction ID")),
+    request_body = ReviewHighValueTransactionRequest,
+    responses(
+        (status = 200, description = "Seller reviewed; approval releases a held payment once the buyer confirmed, rejection cancels the transaction", body = HighValueCheck),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 409, description = "No seller review pending", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn review_high_value_transaction(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<ReviewHighValueTransactionRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let check = HighValueService::new(pool).review_seller(&auth_user, id, request).await?;
+    Ok(Json(check))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/admin/shadow-bans",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Shadow bans in force, most recent first", body = Vec<ShadowBan>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_shadow_bans(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let bans = ShadowBanService::new(pool).active_bans(&auth_user).await?;
+    Ok(Json(bans))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/admin/shadow-bans",
+    tag = "admin",
+    request_body = CreateShadowBanRequest,
+    responses(
+        (status = 201, description = "Seller's listings hidden from everyone else", body = ShadowBan),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 409, description = "Seller is already shadow-banned", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn create_shadow_ban(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Json(request): Json<CreateShadowBanRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let ban = ShadowBanService::new(pool).ban(&auth_user, request).await?;
+    Ok((StatusCode::CREATED, Json(ban)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/marketplace/admin/shadow-bans/{user_id}",
+    tag = "admin",
+    params(("user_id" = String, Path, description = "Seller ID")),
+    responses(
+        (status = 200, description = "Ban lifted; the seller's listings are shown again", body = ShadowBan),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Seller is not shadow-banned", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn lift_shadow_ban(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(user_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let ban = ShadowBanService::new(pool).lift(&auth_user, &user_id).await?;
+    Ok(Json(ban))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/feature-flags",
+    tag = "dashboard",
+    responses(
+        (status = 200, description = "Names of the feature flags on for the caller", body = Vec<String>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_my_feature_flags(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let enabled = FeatureFlagService::new(pool).enabled_for(&auth_user.0.auth0_id).await?;
+    Ok(Json(enabled))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/admin/feature-flags",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Every feature flag with its rollout", body = Vec<FeatureFlag>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_feature_flags(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let flags = FeatureFlagService::new(pool).list(&auth_user).await?;
+    Ok(Json(flags))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/marketplace/admin/feature-flags/{name}",
+    tag = "admin",
+    params(("name" = String, Path, description = "Flag name")),
+    request_body = UpdateFeatureFlagRequest,
+    responses(
+        (status = 200, description = "Flag created or updated; every instance applies it within 30 seconds", body = FeatureFlag),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn update_feature_flag(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(name): Path<String>,
+    Json(request): Json<UpdateFeatureFlagRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let flag = FeatureFlagService::new(pool).update(&auth_user, &name, request).await?;
+    Ok(Json(flag))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/admin/impersonation",
+    tag = "admin",
+    request_body = CreateImpersonationRequest,
+    responses(
+        (status = 201, description = "Read-only session as the user; send the token as X-Impersonation-Token with your own credentials", body = ImpersonationGrant),
+        (status = 400, description = "Cannot impersonate yourself", body = ErrorBody),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user, or the user is an admin", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn start_impersonation(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Json(request): Json<CreateImpersonationRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let grant = ImpersonationService::new(pool).issue(&auth_user, request).await?;
+    Ok((StatusCode::CREATED, Json(grant)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/marketplace/admin/impersonation/{id}",
+    tag = "admin",
+    params(("id" = Uuid, Path, description = "Impersonation session ID")),
+    responses(
+        (status = 200, description = "Session ended", body = ImpersonationSession),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "No active session with this ID", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn revoke_impersonation(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let session = ImpersonationService::new(pool).revoke(&auth_user, id).await?;
+    Ok(Json(session))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/admin/impersonation/{id}/audit",
+    tag = "admin",
+    params(("id" = Uuid, Path, description = "Impersonation session ID")),
+    responses(
+        (status = 200, description = "Requests made in the session, oldest first", body = Vec<ImpersonationAuditEntry>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_impersonation_audit(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let entries = ImpersonationService::new(pool).audit(&auth_user, id).await?;
+    Ok(Json(entries))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/admin/retention",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Retention policies with their latest cleanup and rows purged over the last 30 days", body = Vec<RetentionPolicyStatus>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_retention_status(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let status = RetentionService::new(pool).policy_status(&auth_user).await?;
+    Ok(Json(status))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/admin/events/schemas",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Published event types with their payload version and a sample payload", body = Vec<EventSchemaInfo>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_event_schemas(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let schemas = EventSchemaService::new(pool).list(&auth_user).await?;
+    Ok(Json(schemas))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/admin/events/test",
+    tag = "admin",
+    request_body = SendTestEventRequest,
+    responses(
+        (status = 200, description = "Sample event published to marketplace.test.<event_type>", body = TestEventResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Unknown event type", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn send_test_event(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Json(request): Json<SendTestEventRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let event = EventSchemaService::new(pool).send_test_event(&auth_user, request).await?;
+    Ok(Json(event))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/admin/bulk/suspend-seller-listings",
+    tag = "admin",
+    request_body = BulkSuspendSellersRequest,
+    responses(
+        (status = 200, description = "Every active or held listing of each seller suspended; one result per seller", body = BulkActionResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn bulk_suspend_seller_listings(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Json(request): Json<BulkSuspendSellersRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let response = AdminBulkService::new(pool).suspend_seller_listings(&auth_user, request).await?;
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/admin/bulk/dismiss-expired-reports",
+    tag = "admin",
+    request_body = BulkListingsRequest,
+    responses(
+        (status = 200, description = "Expired code reports dismissed and listings they took down relisted; one result per listing", body = BulkActionResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn bulk_dismiss_expired_reports(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Json(request): Json<BulkListingsRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let response = AdminBulkService::new(pool).dismiss_expired_reports(&auth_user, request).await?;
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/admin/bulk/reverify-listings",
+    tag = "admin",
+    request_body = BulkListingsRequest,
+    responses(
+        (status = 200, description = "Active listings screened against the current content rules: verified, or held for review; one result per listing", body = BulkActionResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn bulk_reverify_listings(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Json(request): Json<BulkListingsRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let response = AdminBulkService::new(pool).reverify_listings(&auth_user, request).await?;
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/partner/marketplace/listings/{id}",
+    tag = "partner",
+    params(
+        ("id" = Uuid, Path, description = "Listing ID"),
+        ("X-Api-Key" = String, Header, description = "Partner API key"),
+        ListingFieldsParams,
+    ),
+    responses(
+        (status = 200, description = "Listing with seller info; the partner API also serves /listings, /categories and /brands as in the public API", body = ListingWithSeller),
+        (status = 400, description = "Unknown field requested", body = ErrorBody),
+        (status = 401, description = "Missing, invalid or revoked API key", body = ErrorBody),
+        (status = 404, description = "Listing not found", body = ErrorBody),
+        (status = 429, description = "Over the key's rate limit or daily quota", body = ErrorBody),
+    )
+)]
+async fn get_partner_listing(
+    State(pool): State<PgPool>,
+    Path(id): Path<Uuid>,
+    Query(params): Query<ListingFieldsParams>,
+) -> Result<Response, AppError> {
+    params.validate()?;
+
+    // Unlike the public endpoint, partner fetches are not views and do not feed trending
+    let service = MarketplaceService::new(pool);
+    match params.listing_fields()? {
+        Some(fields) => Ok(Json(service.get_listing_sparse(id, &fields, None).await?).into_response()),
+        None => Ok(Json(service.get_listing(id, None).await?).into_response()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/partner/keys",
+    tag = "partner",
+    responses(
+        (status = 200, description = "API keys of the partners you manage, newest first", body = Vec<PartnerApiKey>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_partner_keys(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let keys = PartnerKeyService::new(pool).list_keys(&auth_user).await?;
+    Ok(Json(keys))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/partner/keys",
+    tag = "partner",
+    request_body = CreatePartnerApiKeyRequest,
+    responses(
+        (status = 201, description = "Key issued; the key itself is only shown in this response", body = PartnerApiKeyGrant),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 404, description = "Partner not found or not managed by you", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn create_partner_key(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Json(request): Json<CreatePartnerApiKeyRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let grant = PartnerKeyService::new(pool).issue_key(&auth_user, request).await?;
+    Ok((StatusCode::CREATED, Json(grant)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/marketplace/partner/keys/{id}",
+    tag = "partner",
+    params(("id" = Uuid, Path, description = "API key ID")),
+    responses(
+        (status = 200, description = "Key revoked", body = PartnerApiKey),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 404, description = "API key not found", body = ErrorBody),
+        (status = 409, description = "Already revoked", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn revoke_partner_key(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let key = PartnerKeyService::new(pool).revoke_key(&auth_user, id).await?;
+    Ok(Json(key))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/partner/keys/{id}/usage",
+    tag = "partner",
+    params(
+        ("id" = Uuid, Path, description = "API key ID"),
+        PartnerKeyUsageParams,
+    ),
+    responses(
+        (status = 200, description = "Requests per UTC day, most recent first; days without requests are left out", body = Vec<PartnerKeyUsage>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 404, description = "API key not found", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_partner_key_usage(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+    Query(params): Query<PartnerKeyUsageParams>,
+) -> Result<impl IntoResponse, AppError> {
+    let days = params.days.unwrap_or(30);
+    let usage = PartnerKeyService::new(pool).usage(&auth_user, id, days).await?;
+    Ok(Json(usage))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/admin/partners",
+    tag = "admin",
+    request_body = CreatePartnerAccountRequest,
+    responses(
+        (status = 201, description = "Partner account created; its owner can now issue API keys", body = PartnerAccount),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn create_partner(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Json(request): Json<CreatePartnerAccountRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let partner = PartnerKeyService::new(pool).create_partner(&auth_user, request).await?;
+    Ok((StatusCode::CREATED, Json(partner)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/admin/partners",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Partner accounts, newest first", body = Vec<PartnerAccount>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_partners(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let partners = PartnerKeyService::new(pool).list_partners(&auth_user).await?;
+    Ok(Json(partners))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/marketplace/admin/partner-keys/{id}/limits",
+    tag = "admin",
+    params(("id" = Uuid, Path, description = "API key ID")),
+    request_body = UpdatePartnerKeyLimitsRequest,
+    responses(
+        (status = 200, description = "Limits updated; they apply from the next request", body = PartnerApiKey),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "API key not found", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn set_partner_key_limits(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdatePartnerKeyLimitsRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let key = PartnerKeyService::new(pool).set_limits(&auth_user, id, request).await?;
+    Ok(Json(key))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/archive/listings",
+    tag = "listings",
+    params(ArchivedListingFilters),
+    responses(
+        (status = 200, description = "The caller's archived listings, most recently changed first", body = PaginatedResponse<MarketplaceListing>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_archived_listings(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Query(filters): Query<ArchivedListingFilters>,
+) -> Result<impl IntoResponse, AppError> {
+    let listings = ListingArchiveService::new(pool).seller_listings(&auth_user, filters).await?;
+    Ok(Json(listings))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/archive/listings/{id}",
+    tag = "listings",
+    params(("id" = Uuid, Path, description = "Listing ID")),
+    responses(
+        (status = 200, description = "Archived listing, for its seller, its buyers and admins", body = MarketplaceListing),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 404, description = "Archived listing not found", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_archived_listing(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(listing_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let listing = ListingArchiveService::new(pool).get_listing(&auth_user, listing_id).await?;
+    Ok(Json(listing))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/admin/moderation/contact-offenders",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Users who repeatedly posted contact details in the last 30 days", body = Vec<ContactOffender>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_contact_offenders(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let offenders = ContactScrubber::new(pool).repeat_offenders(&auth_user).await?;
+    Ok(Json(offenders))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/marketplace/admin/transactions/{id}/confirm-payment",
+    tag = "admin",
+    params(("id" = Uuid, Path, description = "Transaction ID")),
+    responses(
+        (status = 200, description = "Transaction moved to escrow", body = MarketplaceTransaction),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 409, description = "Transaction is not awaiting payment", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn confirm_payment(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = MarketplaceService::new(pool);
+    let transaction = service.confirm_payment(&auth_user, id).await?;
+    Ok(Json(transaction))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/admin/transactions/{id}/timeline",
+    tag = "admin",
+    params(("id" = Uuid, Path, description = "Transaction ID")),
+    responses(
+        (status = 200, description = "The transaction with its status changes, payment provider events, refunds, chargebacks, notifications, disputes, reveals and claim messages, oldest first", body = TransactionTimeline),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Transaction not found", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_transaction_timeline(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    MarketplaceService::new(pool.clone()).require_admin(&auth_user).await?;
+
+    let timeline = TransactionTimelineService::new(pool).get(id).await?;
+    Ok(Json(timeline))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/admin/brands",
+    tag = "admin",
+    request_body = CreateBrandRequest,
+    responses(
+        (status = 201, description = "Brand registered", body = MarketplaceBrand),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 409, description = "Brand already exists", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn create_brand(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Json(request): Json<CreateBrandRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let service = BrandService::new(pool);
+    let brand = service.create_brand(&auth_user, request).await?;
+    Ok((StatusCode::CREATED, Json(brand)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/feed",
+    tag = "feed",
+    params(FeedParams),
+    responses(
+        (status = 200, description = "Personalized feed", body = Vec<FeedItem>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_feed(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Query(params): Query<FeedParams>,
+) -> Result<impl IntoResponse, AppError> {
+    let config = Config::get();
+    let service = FeedService::new(pool);
+    let cache = MarketplaceCache::new(config.redis_url.clone());
+    let feed = service
+        .get_feed(&cache, &config.feed, &auth_user.0.auth0_id, params.limit.unwrap_or(20))
+        .await?;
+    Ok(Json(feed))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/feed/following",
+    tag = "feed",
+    params(ListingFilters),
+    responses(
+        (status = 200, description = "Listings from followed sellers", body = PaginatedResponse<ListingWithSeller>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_following_feed(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Query(mut filters): Query<ListingFilters>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = MarketplaceService::new(pool);
+    filters.followed_by = Some(auth_user.0.auth0_id);
+    filters.status.get_or_insert(ListingStatus::Active);
+    let listings = service.get_listings(filters).await?;
+    Ok(Json(listings))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/sellers/{user_id}/follow",
+    tag = "follows",
+    params(("user_id" = String, Path, description = "Seller user ID")),
+    responses(
+        (status = 204, description = "Seller followed"),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 404, description = "Seller not found", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn follow_seller(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(user_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = FollowService::new(pool);
+    service.follow(&auth_user, &user_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/marketplace/sellers/{user_id}/follow",
+    tag = "follows",
+    params(("user_id" = String, Path, description = "Seller user ID")),
+    responses(
+        (status = 204, description = "Seller unfollowed"),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn unfollow_seller(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(user_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = FollowService::new(pool);
+    service.unfollow(&auth_user, &user_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/marketplace/admin/checkouts/{id}/confirm-payment",
+    tag = "admin",
+    params(("id" = Uuid, Path, description = "Checkout ID")),
+    request_body = ConfirmCheckoutPaymentRequest,
+    responses(
+        (status = 200, description = "Every item awaiting payment moved to escrow", body = CheckoutDetail),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Checkout not found", body = ErrorBody),
+        (status = 409, description = "Checkout has already been paid", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn confirm_checkout_payment(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<ConfirmCheckoutPaymentRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let service = CartService::new(pool);
+    let checkout = service.confirm_payment(&auth_user, id, request.payment_id.as_deref()).await?;
+    Ok(Json(checkout))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/marketplace/admin/promotions/{id}/confirm-payment",
+    tag = "admin",
+    params(("id" = Uuid, Path, description = "Promotion ID")),
+    request_body = ConfirmPromotionPaymentRequest,
+    responses(
+        (status = 200, description = "Paid promotion, scheduled or active", body = Promotion),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Promotion not found", body = ErrorBody),
+        (status = 409, description = "Promotion is not awaiting payment", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn confirm_promotion_payment(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<ConfirmPromotionPaymentRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let service = PromotionService::new(pool);
+    let promotion = service.confirm_payment(&auth_user, id, request.payment_id.trim()).await?;
+    Ok(Json(promotion))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/marketplace/admin/wallet/topups/{id}/confirm-payment",
+    tag = "admin",
+    params(("id" = Uuid, Path, description = "Top-up ID")),
+    request_body = ConfirmWalletTopupRequest,
+    responses(
+        (status = 200, description = "Top-up completed and credited to the wallet", body = WalletTopup),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Top-up not found", body = ErrorBody),
+        (status = 409, description = "Top-up is not awaiting payment", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn confirm_wallet_topup(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<ConfirmWalletTopupRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let service = WalletService::new(pool)?;
+    let topup = service.confirm_topup(&auth_user, id, request.payment_id.trim()).await?;
+    Ok(Json(topup))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/marketplace/admin/sellers/{user_id}/quota",
+    tag = "admin",
+    params(("user_id" = String, Path, description = "Seller ID")),
+    request_body = SetSellerQuotaRequest,
+    responses(
+        (status = 200, description = "Seller's active listing limit, replacing their tier's", body = SellerQuotaOverride),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn set_seller_quota(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(user_id): Path<String>,
+    Json(request): Json<SetSellerQuotaRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let quota = SellerQuotaService::new(pool).set_override(&auth_user, &user_id, request).await?;
+    Ok(Json(quota))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/marketplace/admin/sellers/{user_id}/quota",
+    tag = "admin",
+    params(("user_id" = String, Path, description = "Seller ID")),
+    responses(
+        (status = 204, description = "Seller is back on their tier's limit"),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Seller has no quota override", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn remove_seller_quota(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(user_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    SellerQuotaService::new(pool).remove_override(&auth_user, &user_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/admin/credits",
+    tag = "admin",
+    request_body = GrantCreditsRequest,
+    responses(
+        (status = 201, description = "Credit entry recorded", body = CreditEntry),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 409, description = "Balance would go below zero", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn grant_credits(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Json(request): Json<GrantCreditsRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let service = PromotionService::new(pool);
+    let entry = service.grant_credits(&auth_user, request).await?;
+    Ok((StatusCode::CREATED, Json(entry)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/admin/promo-campaigns",
+    tag = "admin",
+    request_body = CreatePromoCampaignRequest,
+    responses(
+        (status = 201, description = "Campaign created", body = PromoCampaign),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 409, description = "Code already in use", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn create_promo_campaign(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Json(request): Json<CreatePromoCampaignRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let service = PromoCodeService::new(pool);
+    let campaign = service.create_campaign(&auth_user, request).await?;
+    Ok((StatusCode::CREATED, Json(campaign)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/admin/promo-campaigns",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Campaigns with their redemptions, newest first", body = Vec<PromoCampaignUsage>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_promo_campaigns(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let service = PromoCodeService::new(pool);
+    let campaigns = service.list_campaigns(&auth_user).await?;
+    Ok(Json(campaigns))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/marketplace/admin/promo-campaigns/{id}",
+    tag = "admin",
+    params(("id" = Uuid, Path, description = "Campaign ID")),
+    responses(
+        (status = 200, description = "Campaign deactivated; past redemptions keep their discount", body = PromoCampaign),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Campaign not found", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn deactivate_promo_campaign(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = PromoCodeService::new(pool);
+    let campaign = service.deactivate(&auth_user, id).await?;
+    Ok(Json(campaign))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/admin/listings",
+    tag = "admin",
+    params(ListingFilters),
+    responses(
+        (status = 200, description = "All listings including deleted ones", body = PaginatedResponse<ListingWithSeller>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_admin_listings(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Query(mut filters): Query<ListingFilters>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = MarketplaceService::new(pool);
+    service.require_admin(&auth_user).await?;
+    filters.include_deleted = true;
+    filters.include_shadow_banned = true;
+    filters.include_away = true;
+    let listings = service.get_listings(filters).await?;
+    Ok(Json(listings))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/admin/analytics",
+    tag = "admin",
+    params(AnalyticsQuery),
+    responses(
+        (status = 200, description = "GMV, take rate, conversion and dispute rate", body = MarketplaceKpis),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_analytics_kpis(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    MarketplaceService::new(pool.clone()).require_admin(&auth_user).await?;
+
+    let config = Config::get();
+    let cache = MarketplaceCache::new(config.redis_url.clone());
+    let kpis = AnalyticsService::new(pool)
+        .get_kpis(&cache, query.days.unwrap_or(30), config.platform_fee_rate)
+        .await?;
+    Ok(Json(kpis))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/admin/analytics/gmv",
+    tag = "admin",
+    params(AnalyticsQuery),
+    responses(
+        (status = 200, description = "Completed GMV over time", body = Vec<GmvPoint>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_analytics_gmv(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    MarketplaceService::new(pool.clone()).require_admin(&auth_user).await?;
+
+    let cache = MarketplaceCache::new(Config::get().redis_url.clone());
+    let series = AnalyticsService::new(pool)
+        .get_gmv_series(&cache, query.interval.unwrap_or(AnalyticsInterval::Day), query.days.unwrap_or(30))
+        .await?;
+    Ok(Json(series))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/admin/analytics/categories",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Active listings per category", body = Vec<CategoryActivity>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_analytics_categories(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    MarketplaceService::new(pool.clone()).require_admin(&auth_user).await?;
+
+    let cache = MarketplaceCache::new(Config::get().redis_url.clone());
+    let categories = AnalyticsService::new(pool).get_active_listings_by_category(&cache).await?;
+    Ok(Json(categories))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/admin/analytics/cohorts",
+    tag = "admin",
+    params(AnalyticsQuery),
+    responses(
+        (status = 200, description = "New sellers by month of first listing", body = Vec<SellerCohort>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_analytics_cohorts(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    MarketplaceService::new(pool.clone()).require_admin(&auth_user).await?;
+
+    let cache = MarketplaceCache::new(Config::get().redis_url.clone());
+    let cohorts = AnalyticsService::new(pool)
+        .get_seller_cohorts(&cache, query.months.unwrap_or(6))
+        .await?;
+    Ok(Json(cohorts))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/admin/restrictions",
+    tag = "admin",
+    params(RestrictionFilters),
+    responses(
+        (status = 200, description = "Automatically applied account restrictions, newest first", body = Vec<AccountRestriction>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_account_restrictions(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Query(filters): Query<RestrictionFilters>,
+) -> Result<impl IntoResponse, AppError> {
+    MarketplaceService::new(pool.clone()).require_admin(&auth_user).await?;
+
+    let restrictions = AnomalyDetector::new(pool)
+        .list_restrictions(filters.user_id.as_deref(), filters.active_only.unwrap_or(true))
+        .await?;
+    Ok(Json(restrictions))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/marketplace/admin/restrictions/{id}/lift",
+    tag = "admin",
+    params(("id" = Uuid, Path, description = "Restriction ID")),
+    responses(
+        (status = 200, description = "Restriction lifted", body = AccountRestriction),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Active restriction not found", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn lift_account_restriction(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    MarketplaceService::new(pool.clone()).require_admin(&auth_user).await?;
+
+    let restriction = AnomalyDetector::new(pool).lift_restriction(&auth_user, id).await?;
+    Ok(Json(restriction))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/admin/payment-reversals",
+    tag = "admin",
+    params(PaymentReversalFilters),
+    responses(
+        (status = 200, description = "Reversals and chargebacks reported by payment providers, newest first", body = Vec<PaymentReversal>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_payment_reversals(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Query(filters): Query<PaymentReversalFilters>,
+) -> Result<impl IntoResponse, AppError> {
+    MarketplaceService::new(pool.clone()).require_admin(&auth_user).await?;
+
+    let reversals = PaymentReversalService::new(pool)
+        .list(filters.unapplied_only.unwrap_or(false))
+        .await?;
+    Ok(Json(reversals))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/admin/dead-letters",
+    tag = "admin",
+    params(DeadLetterFilters),
+    responses(
+        (status = 200, description = "Notifications and cache invalidations that failed every retry, most recent first", body = Vec<DeadLetter>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_dead_letters(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Query(filters): Query<DeadLetterFilters>,
+) -> Result<impl IntoResponse, AppError> {
+    MarketplaceService::new(pool.clone()).require_admin(&auth_user).await?;
+
+    let letters = SideEffectService::new(pool)
+        .dead_letters(filters.kind.as_deref(), filters.unreplayed_only.unwrap_or(true))
+        .await?;
+    Ok(Json(letters))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/marketplace/admin/dead-letters/{id}/replay",
+    tag = "admin",
+    params(("id" = Uuid, Path, description = "Dead letter ID")),
+    responses(
+        (status = 200, description = "Side effect run again; the dead letter is marked replayed", body = DeadLetter),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Dead letter not found", body = ErrorBody),
+        (status = 409, description = "Already replayed, or the replay failed again", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn replay_dead_letter(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    MarketplaceService::new(pool.clone()).require_admin(&auth_user).await?;
+
+    let letter = SideEffectService::new(pool).replay(&auth_user, id).await?;
+    Ok(Json(letter))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/admin/policy-rules",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Prohibited-items rules, active ones first, newest first", body = Vec<ListingPolicyRule>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_policy_rules(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let service = ListingPolicyService::new(pool);
+    let rules = service.list_rules(&auth_user).await?;
+    Ok(Json(rules))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/admin/policy-rules",
+    tag = "admin",
+    request_body = ListingPolicyRuleRequest,
+    responses(
+        (status = 201, description = "Rule created; new listings and edits are checked against it", body = ListingPolicyRule),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 422, description = "Request validation failed, or unknown category or brand", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn create_policy_rule(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Json(request): Json<ListingPolicyRuleRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let service = ListingPolicyService::new(pool);
+    let rule = service.create_rule(&auth_user, request).await?;
+    Ok((StatusCode::CREATED, Json(rule)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/marketplace/admin/policy-rules/{id}",
+    tag = "admin",
+    params(("id" = Uuid, Path, description = "Rule ID")),
+    request_body = ListingPolicyRuleRequest,
+    responses(
+        (status = 200, description = "Rule replaced; listings already live are not re-checked", body = ListingPolicyRule),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Rule not found", body = ErrorBody),
+        (status = 422, description = "Request validation failed, or unknown category or brand", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn update_policy_rule(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+    Json(request): Json<ListingPolicyRuleRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let service = ListingPolicyService::new(pool);
+    let rule = service.update_rule(&auth_user, id, request).await?;
+    Ok(Json(rule))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/marketplace/admin/policy-rules/{id}",
+    tag = "admin",
+    params(("id" = Uuid, Path, description = "Rule ID")),
+    responses(
+        (status = 204, description = "Rule deleted"),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Rule not found", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn delete_policy_rule(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = ListingPolicyService::new(pool);
+    service.delete_rule(&auth_user, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/dashboard",
+    tag = "dashboard",
+    responses(
+        (status = 200, description = "Dashboard aggregates", body = DashboardData),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_dashboard(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let service = MarketplaceService::new(pool.clone());
+    let commission_service = CommissionService::new(pool.clone());
+    let loyalty_service = LoyaltyService::new(pool);
+    let user_id = &auth_user.0.auth0_id;
+
+    let recent_listing_filters = ListingFilters {
+        seller_id: Some(user_id.clone()),
+        page: Some(0),
+        limit: Some(5),
+        include_away: true,
+        ..Default::default()
+    };
+
+    // Run the aggregations concurrently
+    let (profile, transaction_summary, recent_listings, recent_transactions, unread_notifications, commission, loyalty) = tokio::try_join!(
+        service.get_user_profile(user_id),
+        service.get_transaction_summary(user_id),
+        service.get_listings(recent_listing_filters),
+        service.get_recent_transactions(user_id, 5),
+        service.get_unread_notification_count(user_id),
+        commission_service.get_commission(user_id),
+        loyalty_service.balance(user_id),
+    )?;
+
+    let dashboard = DashboardData {
+        profile,
+        transaction_summary,
+        recent_listings: recent_listings.items,
+        recent_transactions,
+        unread_notifications,
+        commission,
+        loyalty,
+    };
+    Ok(Json(dashboard))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/commission/history",
+    tag = "dashboard",
+    params(CommissionHistoryParams),
+    responses(
+        (status = 200, description = "The caller's commission tier changes, most recent first", body = Vec<CommissionTierChange>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_commission_history(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Query(params): Query<CommissionHistoryParams>,
 ) -> Result<impl IntoResponse, AppError> {
-    // TODO: Implement update notification settings
-    Ok(Json(settings))
+    let service = CommissionService::new(pool);
+    let history = service.get_history(&auth_user.0.auth0_id, params.limit.unwrap_or(20)).await?;
+    Ok(Json(history))
 }
 
-async fn get_dashboard(
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/my-limits",
+    tag = "dashboard",
+    responses(
+        (status = 200, description = "The caller's active listing limit and remaining capacity", body = SellerLimits),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_my_limits(
     State(pool): State<PgPool>,
-    auth_user: AuthUser,
+    ActingUser(auth_user): ActingUser,
 ) -> Result<impl IntoResponse, AppError> {
-    let service = MarketplaceService::new(pool);
-    // TODO: Implement dashboard data aggregation
-    let dashboard = DashboardData {
-        profile: service.get_user_profile(&auth_user.0.auth0_id).await?,
-        transaction_summary: TransactionSummary {
-            total_sales: 0.0,
-            total_purchases: 0.0,
-            pending_transactions: 0,
-            completed_transactions: 0,
-            average_transaction_value: 0.0,
-        },
-        recent_listings: vec![],
-        recent_transactions: vec![],
-        unread_notifications: 0,
-    };
-    Ok(Json(dashboard))
+    let limits = SellerQuotaService::new(pool).get_limits(&auth_user.0.auth0_id).await?;
+    Ok(Json(limits))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/my-listings",
+    tag = "dashboard",
+    params(ListingFilters),
+    responses(
+        (status = 200, description = "The caller's listings including deleted ones", body = PaginatedResponse<ListingWithSeller>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
 async fn get_my_listings(
     State(pool): State<PgPool>,
-    auth_user: AuthUser,
+    ActingUser(auth_user): ActingUser,
     Query(mut filters): Query<ListingFilters>,
 ) -> Result<impl IntoResponse, AppError> {
     let service = MarketplaceService::new(pool);
     filters.seller_id = Some(auth_user.0.auth0_id);
+    filters.include_deleted = true;
+    filters.include_shadow_banned = true;
+    filters.include_away = true;
     let listings = service.get_listings(filters).await?;
     Ok(Json(listings))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/export",
+    tag = "data-export",
+    params(ExportQuery),
+    responses(
+        (status = 200, description = "A finished export is ready to download", body = DataExport),
+        (status = 202, description = "Export queued; a notification is sent when it is ready", body = DataExport),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn request_data_export(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Query(query): Query<ExportQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = DataExportService::new(pool);
+    let format = query.format.unwrap_or(ExportFormat::Json);
+    let (export, _) = service.request_export(&auth_user.0.auth0_id, format).await?;
+
+    let status = if export.status == ExportStatus::Ready {
+        StatusCode::OK
+    } else {
+        StatusCode::ACCEPTED
+    };
+    Ok((status, Json(export)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/export/{id}",
+    tag = "data-export",
+    params(("id" = Uuid, Path, description = "Export ID")),
+    responses(
+        (status = 200, description = "Export status", body = DataExport),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 404, description = "Export not found", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_data_export(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = DataExportService::new(pool);
+    let export = service.get_export(&auth_user.0.auth0_id, id).await?;
+    Ok(Json(export))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/export/{id}/download",
+    tag = "data-export",
+    params(("id" = Uuid, Path, description = "Export ID")),
+    responses(
+        (status = 200, description = "The archive as JSON or CSV"),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 404, description = "Export not found or expired", body = ErrorBody),
+        (status = 409, description = "Export is not ready", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn download_data_export(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let service = DataExportService::new(pool);
+    let (export, content) = service.download(&auth_user.0.auth0_id, id).await?;
+
+    let disposition = format!(
+        "attachment; filename=\"marketplace-export-{}.{}\"",
+        export.id,
+        export.format.as_str()
+    );
+    Ok((
+        [
+            (header::CONTENT_TYPE, export.format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        content,
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/account/deletion",
+    tag = "account",
+    responses(
+        (status = 202, description = "Deletion queued; personal data is anonymized in the background", body = AccountDeletionRequest),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 409, description = "Open transactions or a pending request exist", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn request_account_deletion(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let service = AccountDeletionService::new(pool);
+    let request = service.request_deletion(&auth_user.0.auth0_id).await?;
+    Ok((StatusCode::ACCEPTED, Json(request)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/account/deletion",
+    tag = "account",
+    responses(
+        (status = 200, description = "The caller's deletion request", body = AccountDeletionRequest),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 404, description = "No deletion request", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_account_deletion(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let service = AccountDeletionService::new(pool);
+    let request = service.get_request(&auth_user.0.auth0_id).await?;
+    Ok(Json(request))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/account/away",
+    tag = "account",
+    responses(
+        (status = 200, description = "The caller's vacation or deactivation", body = SellerAway),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 404, description = "Not on vacation or deactivated", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_away_status(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let away = AwayService::new(pool).get(&auth_user).await?;
+    Ok(Json(away))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/marketplace/account/vacation",
+    tag = "account",
+    request_body = VacationModeRequest,
+    responses(
+        (status = 200, description = "Listings hidden and purchases declined with the message until reactivate_at", body = SellerAway),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 409, description = "Account is deactivated", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn start_vacation(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Json(request): Json<VacationModeRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let away = AwayService::new(pool).start_vacation(&auth_user, request).await?;
+    Ok(Json(away))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/account/deactivation",
+    tag = "account",
+    responses(
+        (status = 201, description = "Account deactivated, listings hidden and pending transactions cancelled", body = AccountDeactivated),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 409, description = "Account is already deactivated", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn deactivate_account(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let deactivated = AwayService::new(pool).deactivate(&auth_user).await?;
+    Ok((StatusCode::CREATED, Json(deactivated)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/marketplace/account/away",
+    tag = "account",
+    responses(
+        (status = 204, description = "Vacation ended or account reactivated; listings are visible again"),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 404, description = "Not on vacation or deactivated", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn reactivate_account(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    AwayService::new(pool).reactivate(&auth_user).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/account/auto-reply",
+    tag = "account",
+    responses(
+        (status = 200, description = "The caller's reply to buyers' first messages", body = SellerAutoReply),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 404, description = "No auto-reply set", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_auto_reply(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let auto_reply = ListingFaqService::new(pool).get_auto_reply(&auth_user).await?;
+    Ok(Json(auto_reply))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/marketplace/account/auto-reply",
+    tag = "account",
+    request_body = SetAutoReplyRequest,
+    responses(
+        (status = 200, description = "Auto-reply saved, replacing any earlier one; contact details are masked", body = SellerAutoReply),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 422, description = "Request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn set_auto_reply(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Json(request): Json<SetAutoReplyRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let auto_reply = ListingFaqService::new(pool).set_auto_reply(&auth_user, request).await?;
+    Ok(Json(auto_reply))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/marketplace/account/auto-reply",
+    tag = "account",
+    responses(
+        (status = 204, description = "Auto-reply removed"),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 404, description = "No auto-reply set", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn delete_auto_reply(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    ListingFaqService::new(pool).delete_auto_reply(&auth_user).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/step-up/code",
+    tag = "step-up",
+    responses(
+        (status = 201, description = "One-time code emailed to the caller, replacing any earlier one", body = StepUpChallenge),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 409, description = "Too many wrong codes until the current one expires", body = ErrorBody),
+        (status = 429, description = "Too many codes requested", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn send_step_up_code(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+) -> Result<impl IntoResponse, AppError> {
+    let challenge = StepUpService::new(pool).send_code(&auth_user).await?;
+    Ok((StatusCode::CREATED, Json(challenge)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/marketplace/step-up/verify",
+    tag = "step-up",
+    request_body = VerifyStepUpRequest,
+    responses(
+        (status = 201, description = "Step-up token to send in X-Step-Up-Token to sensitive routes until it expires", body = StepUpToken),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 409, description = "No valid code, or too many wrong ones; request a new one", body = ErrorBody),
+        (status = 422, description = "Wrong code or request validation failed", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn verify_step_up(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Json(request): Json<VerifyStepUpRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    request.validate()?;
+
+    let token = StepUpService::new(pool).verify(&auth_user, &request.code).await?;
+    Ok((StatusCode::CREATED, Json(token)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/marketplace/sessions",
+    tag = "sessions",
+    responses(
+        (status = 200, description = "Active sessions of the caller, most recently used first; `current` marks the one making the request", body = Vec<MarketplaceSession>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_sessions(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    let sessions = SessionService::new(pool).list(&auth_user, &headers).await?;
+    Ok(Json(sessions))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/marketplace/sessions",
+    tag = "sessions",
+    responses(
+        (status = 200, description = "Every session but the current one signed out", body = SessionsRevoked),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn revoke_other_sessions(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    let revoked = SessionService::new(pool).revoke_others(&auth_user, &headers).await?;
+    Ok(Json(SessionsRevoked { revoked }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/marketplace/sessions/{id}",
+    tag = "sessions",
+    params(("id" = Uuid, Path, description = "Session ID")),
+    responses(
+        (status = 204, description = "Session signed out; its token is refused from now on"),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorBody),
+        (status = 404, description = "No active session with this ID", body = ErrorBody),
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn revoke_session(
+    State(pool): State<PgPool>,
+    ActingUser(auth_user): ActingUser,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    SessionService::new(pool).revoke(&auth_user, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
 // Additional types for API
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct TransactionFilters {
     pub status: Option<String>,
     pub role: Option<String>, // "buyer" or "seller"
@@ -358,15 +5230,26 @@ pub struct TransactionFilters {
     pub limit: Option<i64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ReviewFilters {
-    pub is_buyer_review: Option<bool>,
-    pub min_rating: Option<i32>,
-    pub page: Option<i64>,
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PartnerKeyUsageParams {
+    pub days: Option<i64>, // Default 30, at most 90
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TrustHistoryParams {
     pub limit: Option<i64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FeedParams {
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct NotificationFilters {
     pub is_read: Option<bool>,
     pub notification_type: Option<String>,
@@ -374,22 +5257,547 @@ pub struct NotificationFilters {
     pub limit: Option<i64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct VerificationQueueFilters {
+    pub status: Option<VerificationStatus>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct VerifierMetricsParams {
+    pub days: Option<i32>, // Default: 30, max 365
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CouponRevealQuery {
+    pub token: String, // From POST /listings/{id}/coupon/reveal-token
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CouponRevealFilters {
+    pub listing_id: Option<Uuid>,
+    pub reference: Option<String>, // Watermark reference seen on a leaked code
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CommissionHistoryParams {
+    pub limit: Option<i64>, // Default 20, max 100
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ModerationQueueFilters {
+    pub status: Option<ModerationStatus>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RestrictionFilters {
+    pub user_id: Option<String>,
+    pub active_only: Option<bool>, // Default true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PaymentReversalFilters {
+    pub unapplied_only: Option<bool>, // Chargebacks not yet applied to their purchases; default false
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeadLetterFilters {
+    pub kind: Option<String>,          // notification, invalidate_listing or clear_user_caches
+    pub unreplayed_only: Option<bool>, // Default true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportQuery {
+    pub format: Option<ExportFormat>, // Default json
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AnalyticsQuery {
+    pub days: Option<i32>,                     // Reporting window, default 30
+    pub interval: Option<AnalyticsInterval>,   // GMV bucket size, default day
+    pub months: Option<i32>,                   // Cohort months, default 6
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CancelTransactionRequest {
     pub reason: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct DisputeTransactionRequest {
     pub reason: String,
     pub evidence: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DisputeEvidenceUploadParams {
+    pub file_name: Option<String>, // Shown to the other party and admins, e.g. "order-screenshot.png"
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListingFieldsParams {
+    pub fields: Option<String>, // Comma-separated listing fields to return, e.g. "title,selling_price"; default: all
+}
+
+impl ListingFieldsParams {
+    fn listing_fields(&self) -> Result<Option<ListingFields>, AppError> {
+        self.fields
+            .as_deref()
+            .filter(|fields| !fields.trim().is_empty())
+            .map(ListingFields::parse)
+            .transpose()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchSuggestParams {
+    pub q: String, // What the user has typed so far
+    pub region: Option<String>, // Default: the marketplace's default region
+    pub all_regions: Option<bool>, // true to suggest from every region when no region is given
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PopularTagsParams {
+    pub category: Option<String>,
+    pub limit: Option<i64>, // Default: 20, max 100
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PricingSuggestionParams {
+    pub category: String,
+    pub listing_type: Option<ListingType>,
+    pub brand_name: Option<String>,
+    #[param(value_type = Option<String>)]
+    pub original_value: Option<BigDecimal>, // Face value; enables discount-based suggestions
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateBundleRequest {
+    pub title: String,
+    pub description: Option<String>,
+    pub listing_ids: Vec<Uuid>,
+    #[schema(value_type = String)]
+    pub bundle_price: BigDecimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BundleFilters {
+    pub seller_id: Option<String>,
+    pub limit: Option<i64>, // Default: 20, max 100
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StorefrontPageParams {
+    pub page: Option<i64>,
+    pub limit: Option<i64>, // Default: 20, max 100
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AddCartItemRequest {
+    pub listing_id: Uuid,
+    pub quantity: Option<i32>, // Default: 1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UpdateCartItemRequest {
+    pub quantity: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CheckoutRequest {
+    pub payment_method: String, // "wallet" pays from the wallet balance at once
+    pub promo_code: Option<String>,
+    pub redeem_points: Option<i64>, // Loyalty points to spend on this checkout
+}
+
+impl CheckoutRequest {
+    fn discounts(&self) -> CheckoutDiscounts<'_> {
+        CheckoutDiscounts {
+            promo_code: self.promo_code.as_deref(),
+            points: self.redeem_points,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ConfirmCheckoutPaymentRequest {
+    pub payment_id: Option<String>, // Payment provider reference, used for refunds
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ConfirmPromotionPaymentRequest {
+    pub payment_id: String, // Payment provider reference, used for refunds
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ConfirmWalletTopupRequest {
+    pub payment_id: String, // Payment provider reference, refunded against on withdrawal
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RefundTransactionRequest {
+    #[schema(value_type = Option<String>)]
+    pub amount: Option<BigDecimal>, // Default: everything not yet refunded
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OpenProtectionClaimRequest {
+    pub details: Option<String>, // What happened when the code was used
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ProtectionClaimResponseRequest {
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ResolveProtectionClaimRequest {
+    pub refund: bool,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ProtectionClaimFilters {
+    pub status: Option<ProtectionClaimStatus>, // Default: all open claims
+}
+
+const MAX_REASON_LENGTH: usize = 1000;
+const MAX_EVIDENCE_LENGTH: usize = 5000;
+
+impl Validate for CancelTransactionRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .length("reason", &self.reason, 1, MAX_REASON_LENGTH)
+            .finish()
+    }
+}
+
+impl Validate for DisputeTransactionRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .length("reason", &self.reason, 1, MAX_REASON_LENGTH)
+            .optional_length("evidence", self.evidence.as_deref(), 0, MAX_EVIDENCE_LENGTH)
+            .finish()
+    }
+}
+
+impl Validate for ListingFieldsParams {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .optional_length("fields", self.fields.as_deref(), 0, fields::MAX_FIELDS_LENGTH)
+            .finish()
+    }
+}
+
+impl Validate for SearchSuggestParams {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .check(
+                self.q.chars().count() <= search::MAX_QUERY_LENGTH,
+                "q",
+                format!("must be at most {} characters", search::MAX_QUERY_LENGTH),
+            )
+            .finish()
+    }
+}
+
+impl Validate for PricingSuggestionParams {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .length("category", &self.category, 1, MAX_CATEGORY_LENGTH)
+            .optional_length("brand_name", self.brand_name.as_deref(), 1, MAX_BRAND_NAME_LENGTH)
+            .check(
+                self.original_value.as_ref().is_none_or(|value| *value > BigDecimal::from(0)),
+                "original_value",
+                "must be greater than zero",
+            )
+            .finish()
+    }
+}
+
+impl Validate for CreateBundleRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .length("title", &self.title, 3, MAX_TITLE_LENGTH)
+            .optional_length("description", self.description.as_deref(), 0, MAX_DESCRIPTION_LENGTH)
+            .check(
+                (bundles::MIN_BUNDLE_LISTINGS..=bundles::MAX_BUNDLE_LISTINGS).contains(&self.listing_ids.len()),
+                "listing_ids",
+                format!(
+                    "must contain between {} and {} listings",
+                    bundles::MIN_BUNDLE_LISTINGS,
+                    bundles::MAX_BUNDLE_LISTINGS
+                ),
+            )
+            .check(self.bundle_price > BigDecimal::from(0), "bundle_price", "must be greater than zero")
+            .finish()
+    }
+}
+
+impl Validate for AddCartItemRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let quantity = self.quantity.unwrap_or(1);
+        Validator::new()
+            .check(
+                (1..=cart::MAX_ITEM_QUANTITY).contains(&quantity),
+                "quantity",
+                format!("must be between 1 and {}", cart::MAX_ITEM_QUANTITY),
+            )
+            .finish()
+    }
+}
+
+impl Validate for UpdateCartItemRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .check(
+                (1..=cart::MAX_ITEM_QUANTITY).contains(&self.quantity),
+                "quantity",
+                format!("must be between 1 and {}", cart::MAX_ITEM_QUANTITY),
+            )
+            .finish()
+    }
+}
+
+impl Validate for CheckoutRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .length("payment_method", &self.payment_method, 1, 50)
+            .optional_length("promo_code", self.promo_code.as_deref(), 1, 64)
+            .check(self.redeem_points.is_none_or(|points| points > 0), "redeem_points", "must be positive")
+            .finish()
+    }
+}
+
+impl Validate for ConfirmCheckoutPaymentRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .optional_length("payment_id", self.payment_id.as_deref(), 1, 255)
+            .finish()
+    }
+}
+
+impl Validate for ConfirmPromotionPaymentRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .length("payment_id", &self.payment_id, 1, 255)
+            .finish()
+    }
+}
+
+impl Validate for ConfirmWalletTopupRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .length("payment_id", &self.payment_id, 1, 255)
+            .finish()
+    }
+}
+
+impl Validate for RefundTransactionRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .length("reason", &self.reason, 1, MAX_REASON_LENGTH)
+            .finish()
+    }
+}
+
+impl Validate for DisputeEvidenceUploadParams {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .optional_length("file_name", self.file_name.as_deref(), 1, 255)
+            .finish()
+    }
+}
+
+impl Validate for UpdateFeatureFlagRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .optional_length("description", self.description.as_deref(), 1, MAX_REASON_LENGTH)
+            .check((0..=100).contains(&self.rollout_percent), "rollout_percent", "must be between 0 and 100")
+            .check(self.allow_list.len() <= 1000, "allow_list", "must have at most 1000 users")
+            .finish()
+    }
+}
+
+impl Validate for CreatePartnerAccountRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .length("name", &self.name, 1, 200)
+            .length("owner_id", &self.owner_id, 1, 255)
+            .optional_length("contact_email", self.contact_email.as_deref(), 3, 255)
+            .check(
+                self.contact_email.as_deref().is_none_or(|email| email.contains('@')),
+                "contact_email",
+                "must be an email address",
+            )
+            .finish()
+    }
+}
+
+impl Validate for CreatePartnerApiKeyRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .length("name", &self.name, 1, 100)
+            .finish()
+    }
+}
+
+impl Validate for UpdatePartnerKeyLimitsRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .check((1..=10_000).contains(&self.requests_per_minute), "requests_per_minute", "must be between 1 and 10000")
+            .check((1..=10_000_000).contains(&self.daily_quota), "daily_quota", "must be between 1 and 10000000")
+            .finish()
+    }
+}
+
+impl Validate for BulkSuspendSellersRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .check(
+                (1..=MAX_BULK_ITEMS).contains(&self.seller_ids.len()),
+                "seller_ids",
+                format!("must have between 1 and {} sellers", MAX_BULK_ITEMS),
+            )
+            .check(
+                self.seller_ids.iter().all(|id| (1..=255).contains(&id.len())),
+                "seller_ids",
+                "must not contain empty or overlong ids",
+            )
+            .length("reason", &self.reason, 1, MAX_REASON_LENGTH)
+            .finish()
+    }
+}
+
+impl Validate for BulkListingsRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .check(
+                (1..=MAX_BULK_ITEMS).contains(&self.listing_ids.len()),
+                "listing_ids",
+                format!("must have between 1 and {} listings", MAX_BULK_ITEMS),
+            )
+            .finish()
+    }
+}
+
+impl Validate for SendTestEventRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .length("event_type", &self.event_type, 1, 100)
+            .finish()
+    }
+}
+
+impl Validate for CreateImpersonationRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .length("user_id", &self.user_id, 1, 255)
+            .length("reason", &self.reason, 1, MAX_REASON_LENGTH)
+            .finish()
+    }
+}
+
+impl Validate for AppealExpiredTakedownRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .length("message", &self.message, 1, MAX_REASON_LENGTH)
+            .finish()
+    }
+}
+
+impl Validate for ConfirmHighValueTransactionRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .length("code", &self.code, 1, 20)
+            .finish()
+    }
+}
+
+impl Validate for VerifyStepUpRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .length("code", &self.code, 1, 20)
+            .finish()
+    }
+}
+
+impl Validate for ReviewHighValueTransactionRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .optional_length("review_notes", self.review_notes.as_deref(), 1, MAX_REASON_LENGTH)
+            .finish()
+    }
+}
+
+impl Validate for CreateShadowBanRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .length("user_id", &self.user_id, 1, 255)
+            .length("reason", &self.reason, 1, MAX_REASON_LENGTH)
+            .finish()
+    }
+}
+
+impl Validate for SetSellerQuotaRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .check(self.max_active_listings >= 0, "max_active_listings", "must not be negative")
+            .optional_length("reason", self.reason.as_deref(), 0, MAX_REASON_LENGTH)
+            .finish()
+    }
+}
+
+impl Validate for OpenProtectionClaimRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .optional_length("details", self.details.as_deref(), 0, MAX_EVIDENCE_LENGTH)
+            .finish()
+    }
+}
+
+impl Validate for ProtectionClaimResponseRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .length("message", &self.message, 1, MAX_EVIDENCE_LENGTH)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct DashboardData {
     pub profile: MarketplaceProfile,
     pub transaction_summary: TransactionSummary,
     pub recent_listings: Vec<ListingWithSeller>,
     pub recent_transactions: Vec<TransactionDetail>,
     pub unread_notifications: i64,
+    pub commission: SellerCommission,
+    pub loyalty: LoyaltyBalance,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CouponResponse {
+    pub coupon_code: Option<String>,
+    pub coupon_codes: Vec<String>,
+    pub has_access: bool,
+    pub watermark: CouponWatermark, // Display with the codes
 }
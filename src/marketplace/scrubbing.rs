@@ -0,0 +1,288 @@
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{ContactDetailKind, ContactOffender};
+use crate::validation::FieldError;
+use sqlx::types::Json;
+use sqlx::{PgPool, Row};
+use std::ops::Range;
+use uuid::Uuid;
+
+// Messaging app links, matched at the start of a word or after a scheme
+const MESSAGING_LINKS: &[&str] = &[
+    "wa.me/", "chat.whatsapp.com/", "t.me/", "telegram.me/", "telegram.dog/", "m.me/",
+    "signal.me/", "discord.gg/", "discord.com/invite/", "snapchat.com/add/", "line.me/",
+];
+
+// Digits in a phone-number-like run before it counts as a phone number
+const MIN_PHONE_DIGITS: usize = 9;
+
+// Characters after the `@` of a handle such as "@dealsguy"
+const MIN_HANDLE_LENGTH: usize = 4;
+
+const MASK: &str = "[contact removed]";
+
+// Users with this many violations within the window have text rejected rather than masked
+const REPEAT_OFFENDER_THRESHOLD: i64 = 3;
+const REPEAT_OFFENDER_WINDOW_DAYS: i32 = 30;
+
+/// Keeps emails, phone numbers and messaging app contacts out of user text so deals
+/// are not taken off-platform. Every violation is recorded without the text itself;
+/// repeat offenders have their text rejected instead of masked.
+pub struct ContactScrubber {
+    pool: PgPool,
+}
+
+impl ContactScrubber {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// `text` with contact details masked
+    pub async fn mask(&self, user_id: &str, field: &str, text: &str) -> Result<String, AppError> {
+        let (masked, kinds) = mask_contact_details(text);
+        if kinds.is_empty() {
+            return Ok(masked);
+        }
+
+        if self.record(user_id, field, &kinds, false).await? {
+            return Err(rejection(field, &kinds));
+        }
+        Ok(masked)
+    }
+
+    pub async fn mask_optional(
+        &self,
+        user_id: &str,
+        field: &str,
+        text: Option<&str>,
+    ) -> Result<Option<String>, AppError> {
+        match text {
+            Some(text) => Ok(Some(self.mask(user_id, field, text).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Fail when `text` contains contact details, for fields masking would leave unusable
+    pub async fn reject(&self, user_id: &str, field: &str, text: &str) -> Result<(), AppError> {
+        let kinds = contact_kinds(text);
+        if kinds.is_empty() {
+            return Ok(());
+        }
+
+        self.record(user_id, field, &kinds, true).await?;
+        Err(rejection(field, &kinds))
+    }
+
+    /// Users at or past the repeat offender threshold within the window
+    pub async fn repeat_offenders(&self, auth_user: &AuthUser) -> Result<Vec<ContactOffender>, AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+
+        let offenders = sqlx::query_as::<_, ContactOffender>(
+            r#"
+            SELECT user_id,
+                   COUNT(*) as violations,
+                   COUNT(*) FILTER (WHERE rejected) as rejected,
+                   MAX(created_at) as last_violation_at
+            FROM marketplace_contact_violations
+            WHERE created_at > CURRENT_TIMESTAMP - make_interval(days => $1)
+            GROUP BY user_id
+            HAVING COUNT(*) >= $2
+            ORDER BY violations DESC, last_violation_at DESC
+            LIMIT 200
+            "#
+        )
+        .bind(REPEAT_OFFENDER_WINDOW_DAYS)
+        .bind(REPEAT_OFFENDER_THRESHOLD)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(offenders)
+    }
+
+    // Record a violation and tell whether the text is rejected: always when `reject`, and for repeat offenders
+    async fn record(
+        &self,
+        user_id: &str,
+        field: &str,
+        kinds: &[ContactDetailKind],
+        reject: bool,
+    ) -> Result<bool, AppError> {
+        let previous: i64 = sqlx::query(
+            r#"
+            SELECT COUNT(*) as violations FROM marketplace_contact_violations
+            WHERE user_id = $1 AND created_at > CURRENT_TIMESTAMP - make_interval(days => $2)
+            "#
+        )
+        .bind(user_id)
+        .bind(REPEAT_OFFENDER_WINDOW_DAYS)
+        .fetch_one(&self.pool)
+        .await?
+        .get("violations");
+
+        let violations = previous + 1;
+        let repeat_offender = violations >= REPEAT_OFFENDER_THRESHOLD;
+        let rejected = reject || repeat_offender;
+
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_contact_violations (id, user_id, field, kinds, rejected, created_at)
+            VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP)
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(field)
+        .bind(Json(kinds))
+        .bind(rejected)
+        .execute(&self.pool)
+        .await?;
+
+        if repeat_offender {
+            tracing::warn!(user_id, field, violations, "repeat offender posting contact details");
+        }
+        Ok(rejected)
+    }
+}
+
+fn rejection(field: &str, kinds: &[ContactDetailKind]) -> AppError {
+    let found: Vec<&str> = kinds.iter().map(|kind| kind.label()).collect();
+    AppError::ValidationFailed(vec![FieldError {
+        field: field.to_string(),
+        message: format!(
+            "must not contain contact details ({}); buyers and sellers talk through the marketplace",
+            found.join(", ")
+        ),
+    }])
+}
+
+/// Kinds of contact details in `text`, each once
+pub fn contact_kinds(text: &str) -> Vec<ContactDetailKind> {
+    let mut kinds = Vec::new();
+    for (_, kind) in find_contact_details(text) {
+        if !kinds.contains(&kind) {
+            kinds.push(kind);
+        }
+    }
+    kinds
+}
+
+/// `text` with each contact detail replaced by a marker, and the kinds that were replaced
+pub fn mask_contact_details(text: &str) -> (String, Vec<ContactDetailKind>) {
+    let mut masked = String::with_capacity(text.len());
+    let mut kinds = Vec::new();
+    let mut copied = 0;
+
+    for (range, kind) in find_contact_details(text) {
+        masked.push_str(&text[copied..range.start]);
+        masked.push_str(MASK);
+        copied = range.end;
+        if !kinds.contains(&kind) {
+            kinds.push(kind);
+        }
+    }
+    masked.push_str(&text[copied..]);
+
+    (masked, kinds)
+}
+
+/// Contact details in `text` as byte ranges, in order of position
+pub fn find_contact_details(text: &str) -> Vec<(Range<usize>, ContactDetailKind)> {
+    let mut found = Vec::new();
+
+    for (range, word) in words(text) {
+        let kind = if is_messaging_link(&word.to_lowercase()) {
+            ContactDetailKind::MessagingLink
+        } else if looks_like_email(word) {
+            ContactDetailKind::Email
+        } else if is_handle(word) {
+            ContactDetailKind::Handle
+        } else {
+            continue;
+        };
+        found.push((range, kind));
+    }
+
+    // Digits inside a link or address are part of it
+    for range in phone_numbers(text) {
+        if !found.iter().any(|(other, _)| other.start < range.end && range.start < other.end) {
+            found.push((range, ContactDetailKind::Phone));
+        }
+    }
+
+    found.sort_by_key(|(range, _)| range.start);
+    found
+}
+
+// Whitespace-separated words without surrounding punctuation, keeping a leading `@`
+fn words(text: &str) -> Vec<(Range<usize>, &str)> {
+    let mut words = Vec::new();
+    let mut start = None;
+
+    for (i, c) in text.char_indices().chain(std::iter::once((text.len(), ' '))) {
+        match (c.is_whitespace(), start) {
+            (false, None) => start = Some(i),
+            (true, Some(word_start)) => {
+                let word = &text[word_start..i];
+                let leading = word.len() - word.trim_start_matches(|c: char| !c.is_alphanumeric() && c != '@').len();
+                let trimmed = word[leading..].trim_end_matches(|c: char| !c.is_alphanumeric() && c != '/');
+                if !trimmed.is_empty() {
+                    let trimmed_start = word_start + leading;
+                    words.push((trimmed_start..trimmed_start + trimmed.len(), trimmed));
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    words
+}
+
+fn is_messaging_link(word: &str) -> bool {
+    MESSAGING_LINKS.iter().any(|link| {
+        word.match_indices(link)
+            .any(|(i, _)| word[..i].chars().last().is_none_or(|c| !c.is_alphanumeric()))
+    })
+}
+
+fn looks_like_email(word: &str) -> bool {
+    match word.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && domain
+                    .rsplit_once('.')
+                    .is_some_and(|(host, tld)| !host.is_empty() && tld.len() >= 2)
+        }
+        None => false,
+    }
+}
+
+fn is_handle(word: &str) -> bool {
+    word.strip_prefix('@').is_some_and(|name| {
+        name.chars().count() >= MIN_HANDLE_LENGTH
+            && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '.')
+    })
+}
+
+// Digit runs that may be broken up by the usual phone number separators
+fn phone_numbers(text: &str) -> Vec<Range<usize>> {
+    let mut numbers = Vec::new();
+    // Start, end and digit count of the current run
+    let mut run: Option<(usize, usize, usize)> = None;
+
+    for (i, c) in text.char_indices().chain(std::iter::once((text.len(), '\0'))) {
+        if c.is_ascii_digit() {
+            run = Some(match run {
+                Some((start, _, digits)) => (start, i + 1, digits + 1),
+                None => (text[..i].trim_end_matches(['+', '(']).len(), i + 1, 1),
+            });
+        } else if !matches!(c, ' ' | '-' | '.' | '(' | ')' | '+') {
+            if let Some((start, end, digits)) = run.take() {
+                if digits >= MIN_PHONE_DIGITS {
+                    numbers.push(start..end);
+                }
+            }
+        }
+    }
+    numbers
+}
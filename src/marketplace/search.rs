@@ -0,0 +1,115 @@
+use crate::config::Config;
+use crate::error::AppError;
+use crate::marketplace::cache::{cache_ttl, MarketplaceCache};
+use crate::marketplace::database;
+use crate::marketplace::away::SELLER_NOT_AWAY;
+use crate::marketplace::shadow_bans::NOT_SHADOW_BANNED;
+use crate::models::marketplace::{SearchSuggestion, SuggestionKind};
+use sqlx::{PgPool, Row};
+
+// Shorter prefixes match too much to be useful
+pub const MIN_QUERY_LENGTH: usize = 2;
+pub const MAX_QUERY_LENGTH: usize = 100;
+
+// Suggestions returned per request
+const MAX_SUGGESTIONS: i64 = 10;
+
+/// Search-as-you-type completions for brands, categories and listing titles.
+///
+/// Matching uses `pg_trgm` word similarity so misspelled and partial words still match,
+/// served from trigram indexes. Each match is weighted by popularity (active listings for
+/// brands and categories, views for titles) so common completions rank first. Results are
+/// cached briefly per query and region since the same prefixes are typed over and over.
+/// Brand and category popularity and title matches only count listings in `region`, or
+/// every region when it is `None`.
+pub struct SearchSuggestService {
+    pool: PgPool,
+    cache: MarketplaceCache,
+}
+
+impl SearchSuggestService {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            cache: MarketplaceCache::new(Config::get().redis_url.clone()),
+        }
+    }
+
+    pub async fn suggest(&self, query: &str, region: Option<&str>) -> Result<Vec<SearchSuggestion>, AppError> {
+        let term = query.trim().to_lowercase();
+        if term.chars().count() < MIN_QUERY_LENGTH {
+            return Ok(Vec::new());
+        }
+
+        let key = format!("search:suggest:{}:{}", region.unwrap_or("all"), term);
+        if let Ok(Some(suggestions)) = self.cache.get_report::<Vec<SearchSuggestion>>(&key).await {
+            return Ok(suggestions);
+        }
+
+        // `column %> term` is word_similarity(term, column) above the threshold, which the GIN trigram indexes serve
+        let query = format!(
+            r#"
+            SELECT kind, text, slug, score FROM (
+                (SELECT 'brand' as kind, b.name as text, b.slug as slug,
+                    (word_similarity($1, LOWER(b.name)) * (1 + LN(1 + (
+                        SELECT COUNT(*) FROM marketplace_listings l
+                        WHERE l.brand_id = b.id AND l.status = 'active' AND l.deleted_at IS NULL AND {not_banned} AND {not_away} AND ($3::text IS NULL OR l.region = $3)
+                    )) / 5))::float8 as score
+                FROM marketplace_brands b
+                WHERE LOWER(b.name) %> $1
+                ORDER BY score DESC
+                LIMIT $2)
+                UNION ALL
+                (SELECT 'category', c.name, c.slug,
+                    (word_similarity($1, LOWER(c.name)) * (1 + LN(1 + (
+                        SELECT COUNT(*) FROM marketplace_listings l
+                        WHERE l.category = c.slug AND l.status = 'active' AND l.deleted_at IS NULL AND {not_banned} AND {not_away} AND ($3::text IS NULL OR l.region = $3)
+                    )) / 5))::float8 as score
+                FROM marketplace_categories c
+                WHERE c.is_active AND LOWER(c.name) %> $1
+                ORDER BY score DESC
+                LIMIT $2)
+                UNION ALL
+                (SELECT 'title', MIN(l.title), NULL,
+                    (MAX(word_similarity($1, LOWER(l.title))) * (1 + LN(1 + SUM(l.view_count)) / 5))::float8 as score
+                FROM marketplace_listings l
+                WHERE l.status = 'active' AND l.deleted_at IS NULL AND {not_banned} AND {not_away} AND ($3::text IS NULL OR l.region = $3) AND LOWER(l.title) %> $1
+                GROUP BY LOWER(l.title)
+                ORDER BY score DESC
+                LIMIT $2)
+            ) suggestions
+            ORDER BY score DESC
+            LIMIT $2
+            "#,
+            not_banned = NOT_SHADOW_BANNED,
+            not_away = SELLER_NOT_AWAY
+        );
+        let rows = database::timed(
+            "search.suggest",
+            sqlx::query(&query).bind(&term).bind(MAX_SUGGESTIONS).bind(region).fetch_all(&self.pool),
+        )
+        .await?;
+
+        let suggestions: Vec<SearchSuggestion> = rows
+            .into_iter()
+            .filter_map(|row| {
+                let kind = match row.get::<String, _>("kind").as_str() {
+                    "brand" => SuggestionKind::Brand,
+                    "category" => SuggestionKind::Category,
+                    "title" => SuggestionKind::Title,
+                    _ => return None,
+                };
+                Some(SearchSuggestion {
+                    kind,
+                    text: row.get("text"),
+                    slug: row.get("slug"),
+                    score: row.get("score"),
+                })
+            })
+            .collect();
+
+        let _ = self.cache.cache_report(&key, &suggestions, cache_ttl::SEARCH_SUGGESTIONS).await;
+
+        Ok(suggestions)
+    }
+}
@@ -0,0 +1,399 @@
+use crate::config::Config;
+use crate::error::AppError;
+use crate::marketplace::keyring::CouponKeyring;
+use crate::marketplace::markdown;
+use crate::marketplace::outbox::{event_types, OutboxService};
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{
+    ListingStatus, ListingType, MarketplaceListing, MarketplaceTransaction, TransactionStatus,
+};
+use bigdecimal::BigDecimal;
+use chrono::{Duration, Utc};
+use fake::faker::address::en::{CityName, StreetName};
+use fake::faker::company::en::CompanyName;
+use fake::faker::internet::en::DomainSuffix;
+use fake::faker::lorem::en::Sentences;
+use fake::Fake;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde_json::json;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+// Prefix of the user ids of seeded sellers and buyers, so they are told apart from real accounts
+pub const SEED_USER_PREFIX: &str = "seed|";
+
+pub const DEFAULT_SEED_SELLERS: usize = 10;
+
+// Listings per seeded seller; enough that each seller has every listing type
+const LISTINGS_PER_SELLER: usize = 8;
+
+// Seeded buyers per seeded seller
+const BUYERS_PER_SELLER: usize = 2;
+
+// Brands listings are made for, with the category each belongs to
+const BRANDS: [(&str, &str); 12] = [
+    ("Amazon", "electronics"),
+    ("Flipkart", "electronics"),
+    ("Apple", "phones"),
+    ("Myntra", "clothing"),
+    ("Nike", "shoes"),
+    ("Swiggy", "food-delivery"),
+    ("Zomato", "restaurants"),
+    ("BigBasket", "groceries"),
+    ("MakeMyTrip", "flights"),
+    ("Uber", "ride-hailing"),
+    ("Netflix", "streaming"),
+    ("Steam", "gaming"),
+];
+
+const LISTING_TYPES: [ListingType; 6] = [
+    ListingType::DiscountCode,
+    ListingType::GiftCard,
+    ListingType::ReferralLink,
+    ListingType::LocationDeal,
+    ListingType::CashbackOffer,
+    ListingType::LoyaltyPoints,
+];
+
+// Listing statuses drawn with these weights; most listings are for sale
+const LISTING_STATUSES: [(ListingStatus, u32); 4] = [
+    (ListingStatus::Active, 14),
+    (ListingStatus::Sold, 3),
+    (ListingStatus::Expired, 2),
+    (ListingStatus::PendingReview, 1),
+];
+
+// Transaction statuses drawn with these weights for a purchase of a listing
+const TRANSACTION_STATUSES: [(TransactionStatus, u32); 5] = [
+    (TransactionStatus::Completed, 6),
+    (TransactionStatus::Escrow, 3),
+    (TransactionStatus::Pending, 2),
+    (TransactionStatus::Cancelled, 1),
+    (TransactionStatus::Disputed, 1),
+];
+
+/// What a seed run created
+#[derive(Debug, Clone, Default)]
+pub struct SeedSummary {
+    pub sellers: usize,
+    pub buyers: usize,
+    pub listings: usize,
+    pub transactions: usize,
+    pub reviews: usize,
+}
+
+/// Fills a local database with fake sellers, listings of every type, transactions in each
+/// status and reviews, so the marketplace can be browsed and bought from without writing SQL.
+/// Only for development databases: it refuses to touch one holding real listings. Run it
+/// with `tasks seed [--sellers N]`.
+pub struct SeedService {
+    pool: PgPool,
+}
+
+impl SeedService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Create `sellers` sellers with their listings, buyers who purchased some of them, and
+    /// reviews of the completed purchases. Each run adds to what earlier runs seeded.
+    pub async fn seed(&self, sellers: usize) -> Result<SeedSummary, AppError> {
+        self.require_development_data().await?;
+
+        let keyring = CouponKeyring::from_config(Config::get())?;
+        let mut rng = StdRng::from_entropy();
+        let mut summary = SeedSummary::default();
+
+        let seller_ids: Vec<String> = (0..sellers).map(|_| seed_user_id()).collect();
+        let buyer_ids: Vec<String> = (0..sellers * BUYERS_PER_SELLER).map(|_| seed_user_id()).collect();
+
+        let mut tx = self.pool.begin().await?;
+        for user_id in seller_ids.iter().chain(&buyer_ids) {
+            sqlx::query(
+                r#"
+                INSERT INTO marketplace_trust_scores (user_id, trust_score, last_calculated)
+                VALUES ($1, 50.0, CURRENT_TIMESTAMP)
+                ON CONFLICT (user_id) DO NOTHING
+                "#
+            )
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        for seller_id in &seller_ids {
+            for index in 0..LISTINGS_PER_SELLER {
+                let listing_type = LISTING_TYPES[index % LISTING_TYPES.len()];
+                let listing = insert_listing(&mut tx, &mut rng, &keyring, seller_id, listing_type).await?;
+                summary.listings += 1;
+
+                // Listings still for sale are only sometimes bought; sold ones always were
+                let bought = match listing.status {
+                    ListingStatus::Sold => true,
+                    ListingStatus::Active => rng.gen_bool(0.4),
+                    _ => false,
+                };
+                if !bought {
+                    continue;
+                }
+
+                let buyer_id = buyer_ids.choose(&mut rng).expect("at least one buyer per seller");
+                let status = if listing.status == ListingStatus::Sold {
+                    TransactionStatus::Completed
+                } else {
+                    weighted(&mut rng, &TRANSACTION_STATUSES)
+                };
+                let transaction = insert_transaction(&mut tx, &mut rng, &listing, buyer_id, status).await?;
+                summary.transactions += 1;
+
+                if transaction.status == TransactionStatus::Completed {
+                    summary.reviews += insert_reviews(&mut tx, &mut rng, &transaction).await?;
+                }
+            }
+        }
+        tx.commit().await?;
+
+        // Scores from the seeded sales and reviews, instead of the neutral starting score
+        let service = MarketplaceService::new(self.pool.clone());
+        for user_id in seller_ids.iter().chain(&buyer_ids) {
+            service.recalculate_trust_score(user_id).await?;
+        }
+
+        summary.sellers = seller_ids.len();
+        summary.buyers = buyer_ids.len();
+        tracing::info!(?summary, "seeded development data");
+        Ok(summary)
+    }
+
+    // Seeding next to real listings would put fake ones in front of buyers
+    async fn require_development_data(&self) -> Result<(), AppError> {
+        let real_listing = sqlx::query("SELECT 1 FROM marketplace_listings WHERE seller_id NOT LIKE $1 LIMIT 1")
+            .bind(format!("{}%", SEED_USER_PREFIX))
+            .fetch_optional(&self.pool)
+            .await?;
+        if real_listing.is_some() {
+            return Err(AppError::Conflict(
+                "The database has listings that were not seeded; seed only an empty development database".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn seed_user_id() -> String {
+    format!("{}{}", SEED_USER_PREFIX, Uuid::new_v4().simple())
+}
+
+fn weighted<T: Copy>(rng: &mut StdRng, choices: &[(T, u32)]) -> T {
+    choices
+        .choose_weighted(rng, |(_, weight)| *weight)
+        .map(|(choice, _)| *choice)
+        .expect("weights are positive")
+}
+
+// An amount in whole rupees and paise between `min` and `max` rupees
+fn amount(rng: &mut StdRng, min: i64, max: i64) -> BigDecimal {
+    BigDecimal::new(rng.gen_range(min * 100..=max * 100).into(), 2)
+}
+
+async fn insert_listing(
+    tx: &mut Transaction<'_, Postgres>,
+    rng: &mut StdRng,
+    keyring: &CouponKeyring,
+    seller_id: &str,
+    listing_type: ListingType,
+) -> Result<MarketplaceListing, AppError> {
+    let (brand, category) = *BRANDS.choose(rng).expect("brands are listed");
+    let status = weighted(rng, &LISTING_STATUSES);
+
+    let original_value = amount(rng, 100, 5000);
+    let percent_off = rng.gen_range(10..=60);
+    let selling_price = (&original_value * BigDecimal::from(100 - percent_off) / BigDecimal::from(100)).round(2);
+
+    let (title, details) = match listing_type {
+        ListingType::DiscountCode => (
+            format!("{}% off at {}", percent_off, brand),
+            json!({ "minimum_order_value": amount(rng, 0, 1000).to_string(), "single_use": rng.gen_bool(0.7) }),
+        ),
+        ListingType::GiftCard => (
+            format!("{} gift card worth ₹{}", brand, original_value.round(0)),
+            json!({ "merchant": brand, "balance": original_value.to_string(), "currency": "INR", "pin_required": rng.gen_bool(0.5) }),
+        ),
+        ListingType::ReferralLink => (
+            format!("{} referral: extra ₹{} on your first order", brand, rng.gen_range(50..=500)),
+            json!({
+                "referral_url": format!("https://{}.{}/r/{}", brand.to_lowercase(), DomainSuffix().fake_with_rng::<String, _>(rng), Uuid::new_v4().simple()),
+                "reward_description": "Credited after the first purchase",
+            }),
+        ),
+        ListingType::LocationDeal => {
+            let venue: String = CompanyName().fake_with_rng(rng);
+            let city: String = CityName().fake_with_rng(rng);
+            (
+                format!("{}% off at {} in {}", percent_off, venue, city),
+                json!({
+                    "venue_name": venue,
+                    "address": format!("{} {}", rng.gen_range(1..=200), StreetName().fake_with_rng::<String, _>(rng)),
+                    "city": city,
+                    "latitude": rng.gen_range(8.0..=32.0),
+                    "longitude": rng.gen_range(68.0..=90.0),
+                }),
+            )
+        }
+        ListingType::CashbackOffer => (
+            format!("{}% cashback on {}", percent_off / 2, brand),
+            json!({ "platform": brand, "cashback_percentage": (percent_off / 2).to_string(), "max_cashback": amount(rng, 100, 1000).to_string() }),
+        ),
+        ListingType::LoyaltyPoints => {
+            let points = rng.gen_range(1..=50) * 500;
+            (
+                format!("{} {} rewards points", points, brand),
+                json!({ "program": format!("{} Rewards", brand), "points": points, "transfer_method": "Account transfer" }),
+            )
+        }
+    };
+
+    let description = Sentences(2..5).fake_with_rng::<Vec<String>, _>(rng).join(" ");
+    let quantity: i32 = if listing_type == ListingType::DiscountCode { rng.gen_range(1..=3) } else { 1 };
+    let age_hours = rng.gen_range(24..=24 * 60);
+    let created_at = Utc::now() - Duration::hours(age_hours);
+    let expiration_date = match status {
+        ListingStatus::Expired => Utc::now() - Duration::hours(rng.gen_range(1..age_hours)),
+        _ => Utc::now() + Duration::days(rng.gen_range(7..=180)),
+    };
+
+    let listing = sqlx::query_as::<_, MarketplaceListing>(
+        r#"
+        INSERT INTO marketplace_listings (
+            id, seller_id, listing_type, title, description, description_html, category,
+            brand_name, original_value, selling_price, discount_percentage, expiration_date,
+            tags, details, quantity, remaining_quantity, status, is_verified, created_at, updated_at,
+            brand_id
+        ) VALUES (
+            $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $19,
+            (SELECT id FROM marketplace_brands WHERE LOWER(name) = LOWER($8))
+        )
+        RETURNING *
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(seller_id)
+    .bind(listing_type)
+    .bind(&title)
+    .bind(&description)
+    .bind(markdown::description_html(Some(&description)))
+    .bind(category)
+    .bind(brand)
+    .bind(&original_value)
+    .bind(&selling_price)
+    .bind(BigDecimal::from(percent_off))
+    .bind(expiration_date)
+    .bind(vec![brand.to_lowercase(), listing_type.as_str().replace('_', "-")])
+    .bind(details)
+    .bind(quantity)
+    .bind(if status == ListingStatus::Sold { 0 } else { quantity })
+    .bind(status)
+    .bind(rng.gen_bool(0.6))
+    .bind(created_at)
+    .fetch_one(&mut **tx)
+    .await?;
+
+    if listing_type == ListingType::DiscountCode {
+        for _ in 0..quantity {
+            let code = format!("{}{}", brand.to_uppercase(), rng.gen_range(1000..=9999));
+            sqlx::query("INSERT INTO marketplace_coupon_codes (listing_id, encrypted_code) VALUES ($1, $2)")
+                .bind(listing.id)
+                .bind(keyring.encrypt(&code)?)
+                .execute(&mut **tx)
+                .await?;
+        }
+    }
+
+    OutboxService::record(tx, "listing", listing.id, event_types::LISTING_CREATED, &listing).await?;
+    Ok(listing)
+}
+
+async fn insert_transaction(
+    tx: &mut Transaction<'_, Postgres>,
+    rng: &mut StdRng,
+    listing: &MarketplaceListing,
+    buyer_id: &str,
+    status: TransactionStatus,
+) -> Result<MarketplaceTransaction, AppError> {
+    // Bought after it was listed, and completed or reviewed no later than now
+    let created_at = (listing.created_at + Duration::minutes(rng.gen_range(10..=60 * 24))).min(Utc::now());
+    let funded = !matches!(status, TransactionStatus::Pending | TransactionStatus::Cancelled);
+
+    let transaction = sqlx::query_as::<_, MarketplaceTransaction>(
+        r#"
+        INSERT INTO marketplace_transactions (
+            id, listing_id, buyer_id, seller_id, amount, payment_method, payment_id, status,
+            escrow_release_date, created_at, completed_at, cancellation_reason, dispute_reason
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+        RETURNING *
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(listing.id)
+    .bind(buyer_id)
+    .bind(&listing.seller_id)
+    .bind(&listing.selling_price)
+    .bind(*["upi", "card", "wallet"].choose(rng).expect("payment methods are listed"))
+    .bind(funded.then(|| format!("seed_pay_{}", Uuid::new_v4().simple())))
+    .bind(status)
+    .bind((status == TransactionStatus::Escrow).then(|| Utc::now() + Duration::days(rng.gen_range(1..=7))))
+    .bind(created_at)
+    .bind((status == TransactionStatus::Completed).then(|| (created_at + Duration::days(rng.gen_range(1..=7))).min(Utc::now())))
+    .bind((status == TransactionStatus::Cancelled).then_some("Buyer changed their mind"))
+    .bind((status == TransactionStatus::Disputed).then_some("The code was rejected at checkout"))
+    .fetch_one(&mut **tx)
+    .await?;
+
+    if funded && listing.listing_type == ListingType::DiscountCode {
+        MarketplaceService::allocate_coupon_code(tx, &transaction).await?;
+    }
+
+    OutboxService::record(tx, "transaction", transaction.id, event_types::TRANSACTION_CREATED, &transaction).await?;
+    Ok(transaction)
+}
+
+// The buyer reviews most completed purchases and the seller some; returns how many were written
+async fn insert_reviews(
+    tx: &mut Transaction<'_, Postgres>,
+    rng: &mut StdRng,
+    transaction: &MarketplaceTransaction,
+) -> Result<usize, AppError> {
+    let mut reviews = Vec::new();
+    if rng.gen_bool(0.8) {
+        reviews.push((&transaction.buyer_id, &transaction.seller_id, true));
+    }
+    if rng.gen_bool(0.4) {
+        reviews.push((&transaction.seller_id, &transaction.buyer_id, false));
+    }
+
+    for (reviewer_id, reviewed_user_id, is_buyer_review) in &reviews {
+        let rating: i32 = weighted(rng, &[(5, 6), (4, 3), (3, 1), (2, 1), (1, 1)]);
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_reviews (
+                id, transaction_id, reviewer_id, reviewed_user_id,
+                rating, review_text, deal_verified, is_buyer_review, created_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(transaction.id)
+        .bind(reviewer_id)
+        .bind(reviewed_user_id)
+        .bind(rating)
+        .bind(Sentences(1..3).fake_with_rng::<Vec<String>, _>(rng).join(" "))
+        .bind(*is_buyer_review && rating >= 3)
+        .bind(is_buyer_review)
+        .bind((transaction.completed_at.unwrap_or(transaction.created_at) + Duration::hours(rng.gen_range(1..=48))).min(Utc::now()))
+        .execute(&mut **tx)
+        .await?;
+    }
+    Ok(reviews.len())
+}
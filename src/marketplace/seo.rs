@@ -0,0 +1,264 @@
+use crate::config::{Config, SeoSettings};
+use crate::error::AppError;
+use crate::marketplace::database;
+use crate::marketplace::jobs::Job;
+use crate::marketplace::replica;
+use crate::marketplace::away::SELLER_NOT_AWAY;
+use crate::marketplace::shadow_bans::NOT_SHADOW_BANNED;
+use crate::marketplace::versioning::CURRENT_PREFIX;
+use async_trait::async_trait;
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, SecondsFormat, Utc};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+pub const SITEMAP_INDEX: &str = "sitemap.xml";
+pub const MERCHANT_FEED_XML: &str = "merchant.xml";
+pub const MERCHANT_FEED_CSV: &str = "merchant.csv";
+
+const XML_CONTENT_TYPE: &str = "application/xml; charset=utf-8";
+const CSV_CONTENT_TYPE: &str = "text/csv; charset=utf-8";
+
+// URLs per sitemap file, the limit of the sitemap protocol
+const SITEMAP_PAGE_SIZE: usize = 50_000;
+
+// Merchant feeds reject longer titles and descriptions
+const MAX_FEED_TITLE_CHARS: usize = 150;
+const MAX_FEED_DESCRIPTION_CHARS: usize = 5000;
+
+// Columns of the merchant feed, in CSV order; XML items use them as `g:` elements
+const FEED_COLUMNS: [&str; 11] = [
+    "id", "title", "description", "link", "image_link", "availability", "price", "condition",
+    "brand", "identifier_exists", "product_type",
+];
+
+/// A stored sitemap or feed
+#[derive(Debug, Clone, FromRow)]
+pub struct SeoDocument {
+    pub name: String,
+    pub content_type: String,
+    pub body: String,
+    pub generated_at: DateTime<Utc>,
+}
+
+#[derive(FromRow)]
+struct FeedListing {
+    id: Uuid,
+    title: String,
+    description: Option<String>,
+    category: String,
+    brand_name: Option<String>,
+    selling_price: BigDecimal,
+    proof_image_url: Option<String>,
+    updated_at: DateTime<Utc>,
+}
+
+/// Sitemaps and the merchant product feed of listings anyone can buy. Building them scans
+/// every active listing, so they are generated on a schedule and served as stored.
+pub struct SeoService {
+    pool: PgPool,
+}
+
+impl SeoService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// A generated document: the sitemap index, a sitemap page or a merchant feed
+    pub async fn document(&self, name: &str) -> Result<SeoDocument, AppError> {
+        sqlx::query_as::<_, SeoDocument>("SELECT * FROM marketplace_seo_documents WHERE name = $1")
+            .bind(name)
+            .fetch_optional(&replica::read_pool(&self.pool))
+            .await?
+            .ok_or_else(|| AppError::NotFound("Document not found".to_string()))
+    }
+
+    /// Rebuild every document from active, verified listings, replacing the stored ones at once
+    pub async fn generate(&self) -> Result<usize, AppError> {
+        let settings = &Config::get().seo;
+        let listings = self.feed_listings().await?;
+
+        let mut documents = Vec::new();
+        let mut pages = Vec::new();
+        for (index, page) in listings.chunks(SITEMAP_PAGE_SIZE).enumerate() {
+            let name = format!("listings-{}.xml", index + 1);
+            let last_modified = page.iter().map(|listing| listing.updated_at).max();
+            documents.push((name.clone(), XML_CONTENT_TYPE, listings_sitemap(settings, page)));
+            pages.push((name, last_modified));
+        }
+        documents.push((SITEMAP_INDEX.to_string(), XML_CONTENT_TYPE, sitemap_index(settings, &pages)));
+        documents.push((MERCHANT_FEED_XML.to_string(), XML_CONTENT_TYPE, merchant_xml(settings, &listings)));
+        documents.push((MERCHANT_FEED_CSV.to_string(), CSV_CONTENT_TYPE, merchant_csv(settings, &listings)?));
+
+        let mut tx = self.pool.begin().await?;
+        // Sitemap pages past the current count go too, rather than listing stale URLs
+        sqlx::query("DELETE FROM marketplace_seo_documents").execute(&mut *tx).await?;
+        for (name, content_type, body) in &documents {
+            sqlx::query(
+                r#"
+                INSERT INTO marketplace_seo_documents (name, content_type, body, generated_at)
+                VALUES ($1, $2, $3, CURRENT_TIMESTAMP)
+                "#
+            )
+            .bind(name)
+            .bind(content_type)
+            .bind(body)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        tracing::info!(listings = listings.len(), sitemaps = pages.len(), "regenerated sitemaps and merchant feeds");
+        Ok(listings.len())
+    }
+
+    // Listings a visitor arriving from search could buy right now, oldest first so pages stay stable
+    async fn feed_listings(&self) -> Result<Vec<FeedListing>, AppError> {
+        let query = format!(
+            r#"
+            SELECT l.id, l.title, l.description, l.category, l.brand_name, l.selling_price,
+                   l.proof_image_url, l.updated_at
+            FROM marketplace_listings l
+            WHERE l.status = 'active' AND l.deleted_at IS NULL AND l.is_verified
+            AND l.remaining_quantity > 0
+            AND (l.expiration_date IS NULL OR l.expiration_date > CURRENT_TIMESTAMP)
+            AND {not_banned} AND {not_away}
+            ORDER BY l.created_at, l.id
+            "#,
+            not_banned = NOT_SHADOW_BANNED,
+            not_away = SELLER_NOT_AWAY
+        );
+        let listings = database::timed(
+            "seo.feed_listings",
+            sqlx::query_as::<_, FeedListing>(&query).fetch_all(&replica::read_pool(&self.pool)),
+        )
+        .await?;
+        Ok(listings)
+    }
+}
+
+fn listing_url(settings: &SeoSettings, id: Uuid) -> String {
+    format!("{}/listings/{}", settings.site_url, id)
+}
+
+fn listings_sitemap(settings: &SeoSettings, listings: &[FeedListing]) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for listing in listings {
+        xml.push_str(&format!(
+            "<url><loc>{}</loc><lastmod>{}</lastmod></url>\n",
+            escape_xml(&listing_url(settings, listing.id)),
+            listing.updated_at.to_rfc3339_opts(SecondsFormat::Secs, true)
+        ));
+    }
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+fn sitemap_index(settings: &SeoSettings, pages: &[(String, Option<DateTime<Utc>>)]) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<sitemapindex xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for (name, last_modified) in pages {
+        let loc = format!("{}{}/sitemaps/{}", settings.site_url, CURRENT_PREFIX, name);
+        xml.push_str(&format!("<sitemap><loc>{}</loc>", escape_xml(&loc)));
+        if let Some(last_modified) = last_modified {
+            xml.push_str(&format!("<lastmod>{}</lastmod>", last_modified.to_rfc3339_opts(SecondsFormat::Secs, true)));
+        }
+        xml.push_str("</sitemap>\n");
+    }
+    xml.push_str("</sitemapindex>\n");
+    xml
+}
+
+// One value per entry of FEED_COLUMNS; empty when the listing has none
+fn feed_values(settings: &SeoSettings, listing: &FeedListing) -> [String; 11] {
+    let description = listing.description.as_deref().unwrap_or(&listing.title);
+    [
+        listing.id.to_string(),
+        truncate_chars(&listing.title, MAX_FEED_TITLE_CHARS),
+        truncate_chars(description, MAX_FEED_DESCRIPTION_CHARS),
+        listing_url(settings, listing.id),
+        listing.proof_image_url.clone().unwrap_or_default(),
+        "in_stock".to_string(),
+        format!("{} {}", listing.selling_price.round(2), settings.currency),
+        "new".to_string(),
+        listing.brand_name.clone().unwrap_or_default(),
+        // Codes and gift cards have no GTIN or MPN
+        "no".to_string(),
+        listing.category.clone(),
+    ]
+}
+
+fn merchant_xml(settings: &SeoSettings, listings: &[FeedListing]) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<rss version=\"2.0\" xmlns:g=\"http://base.google.com/ns/1.0\">\n<channel>\n",
+    );
+    xml.push_str("<title>DealMate</title>\n");
+    xml.push_str(&format!("<link>{}</link>\n", escape_xml(&settings.site_url)));
+    xml.push_str("<description>Discount codes and gift cards for sale on DealMate</description>\n");
+
+    for listing in listings {
+        xml.push_str("<item>");
+        for (column, value) in FEED_COLUMNS.iter().zip(feed_values(settings, listing)) {
+            if !value.is_empty() {
+                xml.push_str(&format!("<g:{column}>{}</g:{column}>", escape_xml(&value)));
+            }
+        }
+        xml.push_str("</item>\n");
+    }
+    xml.push_str("</channel>\n</rss>\n");
+    xml
+}
+
+fn merchant_csv(settings: &SeoSettings, listings: &[FeedListing]) -> Result<String, AppError> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let csv_error = |e: csv::Error| AppError::InternalError(format!("CSV error: {}", e));
+    writer.write_record(FEED_COLUMNS).map_err(csv_error)?;
+    for listing in listings {
+        writer.write_record(feed_values(settings, listing)).map_err(csv_error)?;
+    }
+
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| AppError::InternalError(format!("CSV error: {}", e)))?;
+    String::from_utf8(bytes).map_err(|e| AppError::InternalError(format!("CSV error: {}", e)))
+}
+
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    text.chars().take(max_chars).collect()
+}
+
+// Text content or attribute value, without the control characters XML 1.0 does not allow
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c < ' ' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Regenerates the sitemaps and merchant feeds
+pub struct SeoFeedJob;
+
+#[async_trait]
+impl Job for SeoFeedJob {
+    fn name(&self) -> &'static str {
+        "seo_feeds"
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<(), AppError> {
+        SeoService::new(pool.clone()).generate().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,275 @@
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::marketplace::anomaly;
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::MarketplaceSession;
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{header, HeaderMap, Method},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+pub const DEVICE_ID_HEADER: &str = "x-device-id";
+
+// Sessions idle this long are no longer listed
+const SESSION_IDLE_DAYS: i32 = 30;
+
+// last_seen_at is refreshed at most this often per session
+const TOUCH_INTERVAL_MINS: i32 = 5;
+
+// Longer client-supplied values are cut to this many characters
+const MAX_DEVICE_FIELD_LENGTH: usize = 500;
+
+// Routes that spend money or move it off the platform; a new device using one is reported
+const TRANSACTING_ROUTES: &[(Method, &str)] = &[
+    (Method::POST, "/transactions"),
+    (Method::POST, "/cart/checkout"),
+    (Method::POST, "/bundles/:id/purchase"),
+    (Method::POST, "/wallet/withdrawals"),
+];
+
+/// The device a request comes from
+struct Device {
+    id: Option<String>,
+    user_agent: Option<String>,
+    ip_address: Option<String>,
+}
+
+impl Device {
+    fn from_headers(headers: &HeaderMap) -> Self {
+        let header_value = |name| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.trim().chars().take(MAX_DEVICE_FIELD_LENGTH).collect::<String>())
+                .filter(|value| !value.is_empty())
+        };
+        Self {
+            id: header_value(DEVICE_ID_HEADER),
+            user_agent: header_value(header::USER_AGENT.as_str()),
+            ip_address: anomaly::client_ip(headers),
+        }
+    }
+
+    // How the device is named to its user
+    fn label(&self) -> &str {
+        self.user_agent.as_deref().or(self.id.as_deref()).unwrap_or("an unknown device")
+    }
+}
+
+// What `touch` learned about the session of a request
+struct SessionState {
+    id: Uuid,
+    revoked: bool,
+    new_device: bool,
+}
+
+/// Signed-in devices. The auth provider issues the tokens, so sessions are kept here from
+/// the tokens seen on authenticated requests: each token is one session, grouped to a
+/// device by X-Device-Id or, without it, the user agent. Revoking a session makes this
+/// service refuse its token; the device has to sign in again. Users are notified when a
+/// device they had not used before buys something or withdraws money.
+pub struct SessionService {
+    pool: PgPool,
+}
+
+impl SessionService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// The caller's active sessions, most recently used first
+    pub async fn list(&self, auth_user: &AuthUser, headers: &HeaderMap) -> Result<Vec<MarketplaceSession>, AppError> {
+        let sessions = sqlx::query_as::<_, MarketplaceSession>(
+            r#"
+            SELECT id, device_id, user_agent, ip_address, created_at, last_seen_at,
+                   token_hash IS NOT DISTINCT FROM $2 as current
+            FROM marketplace_sessions
+            WHERE user_id = $1 AND revoked_at IS NULL
+            AND last_seen_at > CURRENT_TIMESTAMP - make_interval(days => $3)
+            ORDER BY last_seen_at DESC
+            LIMIT 100
+            "#
+        )
+        .bind(&auth_user.0.auth0_id)
+        .bind(token_hash(headers))
+        .bind(SESSION_IDLE_DAYS)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(sessions)
+    }
+
+    /// Sign one session out; revoking the current one signs the caller out
+    pub async fn revoke(&self, auth_user: &AuthUser, session_id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE marketplace_sessions SET revoked_at = CURRENT_TIMESTAMP
+            WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL
+            "#
+        )
+        .bind(session_id)
+        .bind(&auth_user.0.auth0_id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Session not found".to_string()));
+        }
+
+        tracing::info!(user_id = %auth_user.0.auth0_id, %session_id, "session revoked");
+        Ok(())
+    }
+
+    /// Sign out every session but the current one
+    pub async fn revoke_others(&self, auth_user: &AuthUser, headers: &HeaderMap) -> Result<u64, AppError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE marketplace_sessions SET revoked_at = CURRENT_TIMESTAMP
+            WHERE user_id = $1 AND revoked_at IS NULL AND token_hash IS DISTINCT FROM $2
+            "#
+        )
+        .bind(&auth_user.0.auth0_id)
+        .bind(token_hash(headers))
+        .execute(&self.pool)
+        .await?;
+
+        tracing::info!(user_id = %auth_user.0.auth0_id, revoked = result.rows_affected(), "other sessions revoked");
+        Ok(result.rows_affected())
+    }
+
+    // Record the request against its session, starting one for a token not seen before
+    async fn touch(&self, user_id: &str, token_hash: &str, device: &Device) -> Result<SessionState, AppError> {
+        // The first session of a user is not reported: there is no earlier device to compare with
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_sessions (
+                id, user_id, token_hash, device_id, user_agent, ip_address, new_device, created_at, last_seen_at
+            )
+            SELECT $1, $2, $3, $4, $5, $6,
+                EXISTS (SELECT 1 FROM marketplace_sessions WHERE user_id = $2)
+                AND NOT EXISTS (
+                    SELECT 1 FROM marketplace_sessions
+                    WHERE user_id = $2 AND COALESCE(device_id, user_agent) IS NOT DISTINCT FROM COALESCE($4, $5)
+                ),
+                CURRENT_TIMESTAMP, CURRENT_TIMESTAMP
+            WHERE NOT EXISTS (SELECT 1 FROM marketplace_sessions WHERE token_hash = $3)
+            ON CONFLICT (token_hash) DO NOTHING
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(token_hash)
+        .bind(&device.id)
+        .bind(&device.user_agent)
+        .bind(&device.ip_address)
+        .execute(&self.pool)
+        .await?;
+
+        let row = sqlx::query(
+            r#"
+            SELECT id, revoked_at IS NOT NULL as revoked,
+                   new_device AND new_device_notified_at IS NULL as new_device,
+                   last_seen_at < CURRENT_TIMESTAMP - make_interval(mins => $2) as stale
+            FROM marketplace_sessions
+            WHERE token_hash = $1
+            "#
+        )
+        .bind(token_hash)
+        .bind(TOUCH_INTERVAL_MINS)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let session = SessionState {
+            id: row.get("id"),
+            revoked: row.get("revoked"),
+            new_device: row.get("new_device"),
+        };
+
+        if row.get::<bool, _>("stale") && !session.revoked {
+            sqlx::query("UPDATE marketplace_sessions SET last_seen_at = CURRENT_TIMESTAMP, ip_address = $2 WHERE id = $1")
+                .bind(session.id)
+                .bind(&device.ip_address)
+                .execute(&self.pool)
+                .await?;
+        }
+
+        Ok(session)
+    }
+
+    // Tell the user, once per session, that a new device spent or withdrew money
+    async fn notify_new_device(&self, user_id: &str, session_id: Uuid, device: &Device) -> Result<(), AppError> {
+        let claimed = sqlx::query(
+            r#"
+            UPDATE marketplace_sessions SET new_device_notified_at = CURRENT_TIMESTAMP
+            WHERE id = $1 AND new_device_notified_at IS NULL
+            "#
+        )
+        .bind(session_id)
+        .execute(&self.pool)
+        .await?;
+        if claimed.rows_affected() == 0 {
+            return Ok(());
+        }
+
+        let message = format!(
+            "{} signed in to your account{} and just made a payment or withdrawal. If this wasn't you, sign it out under Sessions and change your password.",
+            device.label(),
+            device.ip_address.as_deref().map(|ip| format!(" from {}", ip)).unwrap_or_default()
+        );
+        MarketplaceService::new(self.pool.clone())
+            .create_notification(user_id, "new_device_transaction", "New device used your account", &message, None, None)
+            .await;
+        Ok(())
+    }
+}
+
+/// SHA-256 of the request's bearer token
+pub fn token_hash(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| format!("{:x}", Sha256::digest(token.trim().as_bytes())))
+}
+
+/// Records each authenticated request against its session and refuses revoked ones. Runs
+/// outside impersonation so an admin's token is recorded as the admin's own session.
+pub async fn track(State(pool): State<PgPool>, auth_user: AuthUser, request: Request, next: Next) -> Response {
+    let Some(token_hash) = token_hash(request.headers()) else {
+        return next.run(request).await;
+    };
+
+    let service = SessionService::new(pool);
+    let device = Device::from_headers(request.headers());
+    let session = match service.touch(&auth_user.0.auth0_id, &token_hash, &device).await {
+        Ok(session) => session,
+        Err(e) => return e.into_response(),
+    };
+    if session.revoked {
+        return AppError::Unauthorized("This session was signed out; sign in again".to_string()).into_response();
+    }
+
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| request.uri().path(), |matched| matched.as_str());
+    let transacting = TRANSACTING_ROUTES
+        .iter()
+        .any(|(method, path)| request.method() == method && route.ends_with(path));
+
+    let response = next.run(request).await;
+
+    if transacting && session.new_device && response.status().is_success() {
+        // The request already went through; a failed notification must not fail it
+        if let Err(e) = service.notify_new_device(&auth_user.0.auth0_id, session.id, &device).await {
+            tracing::warn!(error = %e, session_id = %session.id, "new device notification failed");
+        }
+    }
+
+    response
+}
@@ -0,0 +1,82 @@
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{CreateShadowBanRequest, ShadowBan};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// True when the seller of listing `l` is not shadow-banned. Everything that shows listings
+/// to anyone but their seller filters on it.
+pub const NOT_SHADOW_BANNED: &str = r#"NOT EXISTS (
+    SELECT 1 FROM marketplace_shadow_bans sb
+    WHERE sb.user_id = l.seller_id AND sb.lifted_at IS NULL
+)"#;
+
+/// Hides a suspected scammer's listings from other users without any sign to the seller:
+/// their own listings, dashboard and direct links keep working as before.
+pub struct ShadowBanService {
+    pool: PgPool,
+}
+
+impl ShadowBanService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn ban(&self, auth_user: &AuthUser, request: CreateShadowBanRequest) -> Result<ShadowBan, AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+
+        let ban = sqlx::query_as::<_, ShadowBan>(
+            r#"
+            INSERT INTO marketplace_shadow_bans (id, user_id, reason, banned_by, created_at)
+            VALUES ($1, $2, $3, $4, CURRENT_TIMESTAMP)
+            ON CONFLICT (user_id) WHERE lifted_at IS NULL DO NOTHING
+            RETURNING *
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(&request.user_id)
+        .bind(&request.reason)
+        .bind(&auth_user.0.auth0_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::Conflict("Seller is already shadow-banned".to_string()))?;
+
+        tracing::info!(user_id = %ban.user_id, banned_by = %ban.banned_by, "seller shadow-banned");
+        Ok(ban)
+    }
+
+    pub async fn lift(&self, auth_user: &AuthUser, user_id: &str) -> Result<ShadowBan, AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+
+        let ban = sqlx::query_as::<_, ShadowBan>(
+            r#"
+            UPDATE marketplace_shadow_bans
+            SET lifted_by = $2, lifted_at = CURRENT_TIMESTAMP
+            WHERE user_id = $1 AND lifted_at IS NULL
+            RETURNING *
+            "#
+        )
+        .bind(user_id)
+        .bind(&auth_user.0.auth0_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Seller is not shadow-banned".to_string()))?;
+
+        tracing::info!(user_id, lifted_by = %auth_user.0.auth0_id, "seller shadow ban lifted");
+        Ok(ban)
+    }
+
+    /// Bans in force, most recent first
+    pub async fn active_bans(&self, auth_user: &AuthUser) -> Result<Vec<ShadowBan>, AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+
+        let bans = sqlx::query_as::<_, ShadowBan>(
+            "SELECT * FROM marketplace_shadow_bans WHERE lifted_at IS NULL ORDER BY created_at DESC"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(bans)
+    }
+}
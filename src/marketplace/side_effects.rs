@@ -0,0 +1,318 @@
+use crate::auth::AuthUser;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::marketplace::cache::MarketplaceCache;
+use crate::marketplace::jobs::Job;
+use crate::marketplace::notification_queue;
+use crate::models::marketplace::DeadLetter;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgExecutor, PgPool, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+// Retries per job pass
+const RETRY_BATCH_SIZE: i64 = 100;
+
+/// Tries, the first included, before a side effect is given up on as a dead letter
+pub const MAX_SIDE_EFFECT_ATTEMPTS: i32 = 8;
+
+// Wait before the first retry, doubled after every further failure up to the maximum
+const BASE_RETRY_DELAY_SECS: i64 = 30;
+const MAX_RETRY_DELAY_SECS: i64 = 3600;
+
+// Dead letters listed at once
+const DEAD_LETTER_LIST_LIMIT: i64 = 200;
+
+/// Work that follows a committed change but that the change does not depend on. Stored as
+/// JSON while it waits for a retry, so variants must stay readable once released.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SideEffect {
+    Notification {
+        user_id: String,
+        notification_type: String,
+        title: String,
+        message: String,
+        listing_id: Option<Uuid>,
+        transaction_id: Option<Uuid>,
+    },
+    InvalidateListing {
+        listing_id: Uuid,
+    },
+    ClearUserCaches {
+        user_id: String,
+    },
+}
+
+impl SideEffect {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SideEffect::Notification { .. } => "notification",
+            SideEffect::InvalidateListing { .. } => "invalidate_listing",
+            SideEffect::ClearUserCaches { .. } => "clear_user_caches",
+        }
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<(), AppError> {
+        match self {
+            SideEffect::Notification {
+                user_id,
+                notification_type,
+                title,
+                message,
+                listing_id,
+                transaction_id,
+            } => {
+                notification_queue::enqueue(pool, user_id, notification_type, title, message, *listing_id, *transaction_id)
+                    .await
+            }
+            SideEffect::InvalidateListing { listing_id } => cache().invalidate_listing(listing_id).await,
+            SideEffect::ClearUserCaches { user_id } => cache().clear_user_caches(user_id).await,
+        }
+    }
+
+    pub(crate) fn to_payload(&self) -> Result<serde_json::Value, AppError> {
+        serde_json::to_value(self).map_err(|e| AppError::InternalError(format!("Serialization error: {}", e)))
+    }
+}
+
+#[derive(Debug, Clone, FromRow)]
+struct PendingRetry {
+    id: Uuid,
+    kind: String,
+    payload: serde_json::Value,
+    attempts: i32,
+    created_at: DateTime<Utc>,
+}
+
+fn cache() -> MarketplaceCache {
+    MarketplaceCache::new(Config::get().redis_url.clone())
+}
+
+// How long to wait after the given number of failed tries
+fn retry_delay(attempts: i32) -> Duration {
+    let doublings = (attempts - 1).clamp(0, 16) as u32;
+    Duration::seconds((BASE_RETRY_DELAY_SECS << doublings).min(MAX_RETRY_DELAY_SECS))
+}
+
+/// Run a side effect without failing the caller. One that fails is queued and retried with
+/// exponential backoff; only if it cannot even be queued is it logged and dropped.
+pub async fn perform(pool: &PgPool, effect: SideEffect) {
+    let Err(e) = effect.run(pool).await else {
+        return;
+    };
+    tracing::warn!(kind = effect.kind(), error = %e, "side effect failed; queued for retry");
+
+    let queued = async {
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_side_effect_retries (id, kind, payload, attempts, last_error, next_attempt_at, created_at)
+            VALUES ($1, $2, $3, 1, $4, $5, CURRENT_TIMESTAMP)
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(effect.kind())
+        .bind(effect.to_payload()?)
+        .bind(e.to_string())
+        .bind(Utc::now() + retry_delay(1))
+        .execute(pool)
+        .await?;
+        Ok::<_, AppError>(())
+    }
+    .await;
+    if let Err(queue_error) = queued {
+        tracing::error!(kind = effect.kind(), error = %queue_error, effect = ?effect, "side effect lost");
+    }
+}
+
+/// Give up on a side effect after `attempts` failed tries and keep it for an admin
+pub(crate) async fn dead_letter<'e>(
+    executor: impl PgExecutor<'e>,
+    id: Uuid,
+    kind: &str,
+    payload: &serde_json::Value,
+    attempts: i32,
+    error: &str,
+    first_failed_at: DateTime<Utc>,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        INSERT INTO marketplace_dead_letters (id, kind, payload, attempts, last_error, first_failed_at, dead_at)
+        VALUES ($1, $2, $3, $4, $5, $6, CURRENT_TIMESTAMP)
+        ON CONFLICT (id) DO NOTHING
+        "#
+    )
+    .bind(id)
+    .bind(kind)
+    .bind(payload)
+    .bind(attempts)
+    .bind(error)
+    .bind(first_failed_at)
+    .execute(executor)
+    .await?;
+
+    tracing::error!(%id, kind, attempts, error, "side effect moved to dead letters");
+    Ok(())
+}
+
+/// The retry queue and the dead letters side effects end up in once they run out of tries
+pub struct SideEffectService {
+    pool: PgPool,
+}
+
+impl SideEffectService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Retry the side effects that are due, returning how many went through. One that fails
+    /// again waits twice as long as before, until its last try makes it a dead letter.
+    pub async fn retry_due(&self) -> Result<usize, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let due = sqlx::query_as::<_, PendingRetry>(
+            r#"
+            SELECT id, kind, payload, attempts, created_at
+            FROM marketplace_side_effect_retries
+            WHERE next_attempt_at <= CURRENT_TIMESTAMP
+            ORDER BY next_attempt_at
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+            "#
+        )
+        .bind(RETRY_BATCH_SIZE)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut done = 0;
+        for retry in &due {
+            let result = match serde_json::from_value::<SideEffect>(retry.payload.clone()) {
+                Ok(effect) => effect.run(&self.pool).await,
+                Err(e) => Err(AppError::InternalError(format!("Unreadable side effect: {}", e))),
+            };
+
+            match result {
+                Ok(()) => {
+                    sqlx::query("DELETE FROM marketplace_side_effect_retries WHERE id = $1")
+                        .bind(retry.id)
+                        .execute(&mut *tx)
+                        .await?;
+                    done += 1;
+                }
+                Err(e) if retry.attempts + 1 >= MAX_SIDE_EFFECT_ATTEMPTS => {
+                    dead_letter(
+                        &mut *tx,
+                        retry.id,
+                        &retry.kind,
+                        &retry.payload,
+                        retry.attempts + 1,
+                        &e.to_string(),
+                        retry.created_at,
+                    ).await?;
+                    sqlx::query("DELETE FROM marketplace_side_effect_retries WHERE id = $1")
+                        .bind(retry.id)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+                Err(e) => {
+                    tracing::warn!(retry_id = %retry.id, kind = %retry.kind, error = %e, "side effect retry failed");
+                    sqlx::query(
+                        r#"
+                        UPDATE marketplace_side_effect_retries
+                        SET attempts = attempts + 1, last_error = $2, next_attempt_at = $3
+                        WHERE id = $1
+                        "#
+                    )
+                    .bind(retry.id)
+                    .bind(e.to_string())
+                    .bind(Utc::now() + retry_delay(retry.attempts + 1))
+                    .execute(&mut *tx)
+                    .await?;
+                }
+            }
+        }
+
+        tx.commit().await?;
+        if done > 0 {
+            tracing::info!(done, "side effects retried");
+        }
+        Ok(done)
+    }
+
+    /// Dead letters of `kind`, or of every kind, most recent first
+    pub async fn dead_letters(&self, kind: Option<&str>, unreplayed_only: bool) -> Result<Vec<DeadLetter>, AppError> {
+        let mut query = QueryBuilder::<Postgres>::new("SELECT * FROM marketplace_dead_letters WHERE 1=1");
+        if let Some(kind) = kind {
+            query.push(" AND kind = ").push_bind(kind);
+        }
+        if unreplayed_only {
+            query.push(" AND replayed_at IS NULL");
+        }
+        query.push(" ORDER BY dead_at DESC LIMIT ").push_bind(DEAD_LETTER_LIST_LIMIT);
+
+        let letters = query
+            .build_query_as::<DeadLetter>()
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(letters)
+    }
+
+    /// Run a dead letter's side effect again now. A letter is replayed once; if the replay
+    /// fails it stays unreplayed with the new error, to be tried again later.
+    pub async fn replay(&self, auth_user: &AuthUser, id: Uuid) -> Result<DeadLetter, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let letter = sqlx::query_as::<_, DeadLetter>("SELECT * FROM marketplace_dead_letters WHERE id = $1 FOR UPDATE")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Dead letter not found".to_string()))?;
+        if letter.replayed_at.is_some() {
+            return Err(AppError::Conflict("Dead letter was already replayed".to_string()));
+        }
+
+        let effect = serde_json::from_value::<SideEffect>(letter.payload.clone())
+            .map_err(|e| AppError::UnprocessableEntity(format!("Dead letter cannot be replayed: {}", e)))?;
+        if let Err(e) = effect.run(&self.pool).await {
+            sqlx::query("UPDATE marketplace_dead_letters SET last_error = $2 WHERE id = $1")
+                .bind(id)
+                .bind(e.to_string())
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            return Err(AppError::Conflict(format!("Replay failed: {}", e)));
+        }
+
+        let letter = sqlx::query_as::<_, DeadLetter>(
+            r#"
+            UPDATE marketplace_dead_letters SET replayed_at = CURRENT_TIMESTAMP, replayed_by = $2
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(&auth_user.0.auth0_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        tracing::info!(%id, kind = %letter.kind, replayed_by = %auth_user.0.auth0_id, "dead letter replayed");
+        Ok(letter)
+    }
+}
+
+/// Retries failed side effects as they come due
+pub struct SideEffectRetryJob;
+
+#[async_trait]
+impl Job for SideEffectRetryJob {
+    fn name(&self) -> &'static str {
+        "side_effect_retries"
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<(), AppError> {
+        SideEffectService::new(pool.clone()).retry_due().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,227 @@
+use crate::auth::AuthUser;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::marketplace::impersonation::ActingUser;
+use crate::marketplace::notifications::{load_settings, notification_sender_from_config, OutgoingMessage};
+use crate::marketplace::preferences::load_preferences;
+use crate::marketplace::rate_limiter::{ActionType, RateLimiter};
+use crate::models::marketplace::{StepUpChallenge, StepUpToken};
+use crate::validation::FieldError;
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::Method,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{Duration, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+pub const STEP_UP_HEADER: &str = "x-step-up-token";
+
+/// Step-up authentication for sensitive actions such as changing payout details. The user
+/// asks for a one-time code, which is emailed to them, and trades it for a short-lived
+/// token. Routes listed in STEP_UP_ROUTES refuse requests without a valid token for the caller.
+pub struct StepUpService {
+    pool: PgPool,
+}
+
+impl StepUpService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Email the caller a new code, replacing any earlier one. Wrong attempts at the earlier
+    /// code still count until it expires, so resending does not buy more guesses.
+    pub async fn send_code(&self, auth_user: &AuthUser) -> Result<StepUpChallenge, AppError> {
+        let settings = &Config::get().step_up;
+        let user_id = &auth_user.0.auth0_id;
+
+        let rate_limit = RateLimiter::new(self.pool.clone())
+            .check_and_increment(user_id, ActionType::SendStepUpCode)
+            .await?;
+        if !rate_limit.allowed {
+            return Err(AppError::RateLimited {
+                message: "Too many security codes requested, try again later".to_string(),
+                retry_after: rate_limit.retry_after,
+            });
+        }
+
+        let code = format!("{:06}", Uuid::new_v4().as_u128() % 1_000_000);
+        let expires_at = Utc::now() + Duration::minutes(i64::from(settings.code_ttl_mins));
+
+        // A live code with its attempts used up is not replaced until it expires
+        let stored = sqlx::query(
+            r#"
+            INSERT INTO marketplace_step_up_codes (user_id, code_hash, attempts, created_at, expires_at)
+            VALUES ($1, $2, 0, CURRENT_TIMESTAMP, $3)
+            ON CONFLICT (user_id) DO UPDATE SET
+                code_hash = EXCLUDED.code_hash,
+                attempts = CASE
+                    WHEN marketplace_step_up_codes.expires_at > CURRENT_TIMESTAMP THEN marketplace_step_up_codes.attempts
+                    ELSE 0
+                END,
+                created_at = EXCLUDED.created_at,
+                expires_at = EXCLUDED.expires_at
+            WHERE marketplace_step_up_codes.expires_at <= CURRENT_TIMESTAMP
+            OR marketplace_step_up_codes.attempts < $4
+            RETURNING user_id
+            "#
+        )
+        .bind(user_id)
+        .bind(hash_code(user_id, &code))
+        .bind(expires_at)
+        .bind(settings.max_code_attempts)
+        .fetch_optional(&self.pool)
+        .await?;
+        if stored.is_none() {
+            return Err(AppError::Conflict("Too many wrong codes; try again later".to_string()));
+        }
+
+        let preferences = load_preferences(&self.pool, user_id).await?;
+        let notification_settings = load_settings(&self.pool, user_id).await?;
+        let message = OutgoingMessage {
+            user_id: user_id.clone(),
+            channels: vec!["email"],
+            subject: "Your security code".to_string(),
+            body: format!(
+                "Enter {} to confirm it's you. The code expires in {} minutes. If you did not ask for it, do not share it and change your password.",
+                code, settings.code_ttl_mins
+            ),
+            locale: preferences.locale,
+            timezone: notification_settings.timezone,
+        };
+        notification_sender_from_config(Config::get())?.send(&message).await?;
+
+        Ok(StepUpChallenge { expires_at })
+    }
+
+    /// Trade the emailed code for a step-up token
+    pub async fn verify(&self, auth_user: &AuthUser, code: &str) -> Result<StepUpToken, AppError> {
+        let settings = &Config::get().step_up;
+        let user_id = &auth_user.0.auth0_id;
+        let mut tx = self.pool.begin().await?;
+
+        let row = sqlx::query(
+            r#"
+            SELECT code_hash, attempts, expires_at > CURRENT_TIMESTAMP as code_valid
+            FROM marketplace_step_up_codes
+            WHERE user_id = $1
+            FOR UPDATE
+            "#
+        )
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(row) = row.filter(|row| row.get::<bool, _>("code_valid")) else {
+            return Err(AppError::Conflict("No valid security code; request a new one".to_string()));
+        };
+        if row.get::<i32, _>("attempts") >= settings.max_code_attempts {
+            return Err(AppError::Conflict("Too many wrong codes; try again later".to_string()));
+        }
+
+        if hash_code(user_id, code.trim()) != row.get::<String, _>("code_hash") {
+            sqlx::query("UPDATE marketplace_step_up_codes SET attempts = attempts + 1 WHERE user_id = $1")
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            return Err(AppError::ValidationFailed(vec![FieldError {
+                field: "code".to_string(),
+                message: "is not the code we sent".to_string(),
+            }]));
+        }
+
+        sqlx::query("DELETE FROM marketplace_step_up_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM marketplace_step_up_tokens WHERE user_id = $1 AND expires_at < CURRENT_TIMESTAMP")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let expires_at = Utc::now() + Duration::minutes(i64::from(settings.token_ttl_mins));
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_step_up_tokens (token_hash, user_id, created_at, expires_at)
+            VALUES ($1, $2, CURRENT_TIMESTAMP, $3)
+            "#
+        )
+        .bind(hash_token(&token))
+        .bind(user_id)
+        .bind(expires_at)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        tracing::info!(user_id, %expires_at, "step-up token issued");
+        Ok(StepUpToken { token, expires_at })
+    }
+
+    // `token` was issued to `user_id` and has not expired
+    async fn is_valid(&self, user_id: &str, token: &str) -> Result<bool, AppError> {
+        let row = sqlx::query(
+            r#"
+            SELECT 1 FROM marketplace_step_up_tokens
+            WHERE token_hash = $1 AND user_id = $2 AND expires_at > CURRENT_TIMESTAMP
+            "#
+        )
+        .bind(hash_token(token))
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.is_some())
+    }
+}
+
+/// Whether `method` on `route`, as matched by the router, is listed in STEP_UP_ROUTES
+pub fn requires_step_up(method: &Method, route: &str) -> bool {
+    Config::get().step_up.routes.iter().any(|entry| {
+        entry
+            .split_once(' ')
+            .is_some_and(|(listed_method, path)| listed_method == method.as_str() && route.ends_with(path))
+    })
+}
+
+/// Refuses requests to STEP_UP_ROUTES unless they carry a valid step-up token of the
+/// caller in X-Step-Up-Token. Other requests pass through untouched.
+pub async fn require(State(pool): State<PgPool>, ActingUser(auth_user): ActingUser, request: Request, next: Next) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| request.uri().path(), |matched| matched.as_str());
+    if !requires_step_up(request.method(), route) {
+        return next.run(request).await;
+    }
+
+    let token = request.headers().get(STEP_UP_HEADER).and_then(|value| value.to_str().ok());
+    let verified = match token {
+        Some(token) => StepUpService::new(pool).is_valid(&auth_user.0.auth0_id, token).await,
+        None => Ok(false),
+    };
+
+    match verified {
+        Ok(true) => next.run(request).await,
+        Ok(false) => AppError::StepUpRequired(
+            "Confirm it's you first: request a code with POST /step-up/code, verify it with POST /step-up/verify and send the token in the X-Step-Up-Token header".to_string(),
+        )
+        .into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+fn hash_code(user_id: &str, code: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(user_id.as_bytes());
+    hasher.update(code.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
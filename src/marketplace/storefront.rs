@@ -0,0 +1,200 @@
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::marketplace::brands::BrandService;
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{
+    ListingFilters, ListingStatus, Storefront, StorefrontPage, UpdateStorefrontRequest,
+};
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+pub const MIN_SLUG_LENGTH: usize = 3;
+pub const MAX_SLUG_LENGTH: usize = 30;
+pub const MAX_BIO_LENGTH: usize = 1000;
+pub const MAX_FEATURED_LISTINGS: usize = 6;
+
+// Slugs that would be confused with our own pages
+const RESERVED_SLUGS: &[&str] = &[
+    "admin", "api", "dealmate", "help", "marketplace", "official", "settings", "store", "support",
+];
+
+/// Seller storefront pages at `/store/:slug`. A storefront is created with a slug derived
+/// from the username the first time the seller opens their storefront settings.
+pub struct StorefrontService {
+    pool: PgPool,
+}
+
+impl StorefrontService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub fn normalize_slug(slug: &str) -> String {
+        slug.trim().to_lowercase()
+    }
+
+    /// Lowercase ASCII letters, digits and inner hyphens, not reserved
+    pub fn is_valid_slug(slug: &str) -> bool {
+        (MIN_SLUG_LENGTH..=MAX_SLUG_LENGTH).contains(&slug.len())
+            && slug.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+            && !slug.starts_with('-')
+            && !slug.ends_with('-')
+            && !RESERVED_SLUGS.contains(&slug)
+    }
+
+    /// The seller's storefront, created on first access
+    pub async fn get_my_storefront(&self, auth_user: &AuthUser) -> Result<Storefront, AppError> {
+        self.ensure_storefront(&auth_user.0.auth0_id).await
+    }
+
+    pub async fn update_storefront(
+        &self,
+        auth_user: &AuthUser,
+        request: UpdateStorefrontRequest,
+    ) -> Result<Storefront, AppError> {
+        let seller_id = &auth_user.0.auth0_id;
+        let current = self.ensure_storefront(seller_id).await?;
+
+        let slug = match &request.slug {
+            Some(slug) => Self::normalize_slug(slug),
+            None => current.slug,
+        };
+
+        if let Some(ids) = &request.featured_listing_ids {
+            let mut unique = ids.clone();
+            unique.sort();
+            unique.dedup();
+            if unique.len() != ids.len() {
+                return Err(AppError::BadRequest("A listing can only be featured once".to_string()));
+            }
+
+            let owned: i64 = sqlx::query(
+                r#"
+                SELECT COUNT(*) as owned FROM marketplace_listings
+                WHERE id = ANY($1) AND seller_id = $2 AND status = 'active' AND deleted_at IS NULL
+                "#
+            )
+            .bind(ids)
+            .bind(seller_id)
+            .fetch_one(&self.pool)
+            .await?
+            .get("owned");
+
+            if owned != ids.len() as i64 {
+                return Err(AppError::BadRequest("Only your own active listings can be featured".to_string()));
+            }
+        }
+
+        // Empty strings clear the field
+        let bio = request.bio.as_deref().map(str::trim);
+        let banner_url = request.banner_url.as_deref().map(str::trim);
+
+        let storefront = sqlx::query_as::<_, Storefront>(
+            r#"
+            UPDATE marketplace_storefronts
+            SET slug = $2,
+                bio = CASE WHEN $3::text IS NULL THEN bio ELSE NULLIF($3, '') END,
+                banner_url = CASE WHEN $4::text IS NULL THEN banner_url ELSE NULLIF($4, '') END,
+                featured_listing_ids = COALESCE($5, featured_listing_ids),
+                updated_at = CURRENT_TIMESTAMP
+            WHERE user_id = $1
+            RETURNING *
+            "#
+        )
+        .bind(seller_id)
+        .bind(&slug)
+        .bind(bio)
+        .bind(banner_url)
+        .bind(&request.featured_listing_ids)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| match AppError::from(e) {
+            AppError::Conflict(_) => AppError::Conflict("That storefront address is already taken".to_string()),
+            other => other,
+        })?;
+
+        Ok(storefront)
+    }
+
+    /// Public storefront: seller profile, featured listings and a page of active listings
+    pub async fn get_page(&self, slug: &str, page: Option<i64>, limit: Option<i64>) -> Result<StorefrontPage, AppError> {
+        let storefront = sqlx::query_as::<_, Storefront>(
+            "SELECT * FROM marketplace_storefronts WHERE slug = $1"
+        )
+        .bind(Self::normalize_slug(slug))
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Storefront not found".to_string()))?;
+
+        let marketplace = MarketplaceService::new(self.pool.clone());
+        let filters = ListingFilters {
+            seller_id: Some(storefront.user_id.clone()),
+            status: Some(ListingStatus::Active),
+            page,
+            limit,
+            ..Default::default()
+        };
+
+        let (profile, featured, listings) = tokio::try_join!(
+            marketplace.get_user_profile(&storefront.user_id),
+            marketplace.get_active_listings_by_ids(&storefront.featured_listing_ids),
+            marketplace.get_listings(filters),
+        )?;
+
+        Ok(StorefrontPage { storefront, profile, featured, listings })
+    }
+
+    async fn ensure_storefront(&self, user_id: &str) -> Result<Storefront, AppError> {
+        let existing = sqlx::query_as::<_, Storefront>(
+            "SELECT * FROM marketplace_storefronts WHERE user_id = $1"
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if let Some(storefront) = existing {
+            return Ok(storefront);
+        }
+
+        let username: Option<String> = sqlx::query("SELECT username FROM users WHERE auth0_id = $1")
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .map(|row| row.get("username"));
+
+        let preferred = username
+            .map(|name| BrandService::slugify(&name))
+            .filter(|slug| Self::is_valid_slug(slug));
+        let fallback = format!("seller-{}", &Uuid::new_v4().simple().to_string()[..8]);
+
+        // The username slug may be taken, in which case the random one is used
+        for slug in preferred.into_iter().chain(std::iter::once(fallback)) {
+            let created = sqlx::query_as::<_, Storefront>(
+                r#"
+                INSERT INTO marketplace_storefronts (user_id, slug, created_at, updated_at)
+                VALUES ($1, $2, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+                ON CONFLICT DO NOTHING
+                RETURNING *
+                "#
+            )
+            .bind(user_id)
+            .bind(&slug)
+            .fetch_optional(&self.pool)
+            .await?;
+
+            if let Some(storefront) = created {
+                return Ok(storefront);
+            }
+        }
+
+        // A concurrent request created it first
+        let storefront = sqlx::query_as::<_, Storefront>(
+            "SELECT * FROM marketplace_storefronts WHERE user_id = $1"
+        )
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(storefront)
+    }
+}
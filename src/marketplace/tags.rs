@@ -0,0 +1,68 @@
+use crate::config::Config;
+use crate::error::AppError;
+use crate::marketplace::cache::{cache_ttl, MarketplaceCache};
+use crate::marketplace::categories::CategoryService;
+use crate::models::marketplace::TagCount;
+use sqlx::PgPool;
+use std::collections::HashSet;
+
+/// Listing tags. Tags are stored normalized so that "Gift Card", "gift  card" and
+/// "gift card" are the same tag for filtering and counting.
+pub struct TagService {
+    pool: PgPool,
+}
+
+impl TagService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Lowercase, trim and collapse whitespace; drop empty tags and duplicates, keeping the first occurrence
+    pub fn normalize(tags: &[String]) -> Vec<String> {
+        let mut seen = HashSet::new();
+        tags.iter()
+            .map(|tag| Self::normalize_tag(tag))
+            .filter(|tag| !tag.is_empty() && seen.insert(tag.clone()))
+            .collect()
+    }
+
+    pub fn normalize_tag(tag: &str) -> String {
+        tag.trim()
+            .to_lowercase()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Most used tags on listings currently for sale, optionally within a category
+    pub async fn popular_tags(&self, category: Option<&str>, limit: i64) -> Result<Vec<TagCount>, AppError> {
+        let category = category.map(CategoryService::normalize_slug);
+        let limit = limit.clamp(1, 100);
+
+        let cache = MarketplaceCache::new(Config::get().redis_url.clone());
+        let key = format!("tags:popular:{}:{}", category.as_deref().unwrap_or("all"), limit);
+        if let Ok(Some(tags)) = cache.get_report::<Vec<TagCount>>(&key).await {
+            return Ok(tags);
+        }
+
+        let tags = sqlx::query_as::<_, TagCount>(
+            r#"
+            SELECT tag, COUNT(*) as listing_count
+            FROM marketplace_listings l, unnest(l.tags) as tag
+            WHERE l.status = 'active' AND l.deleted_at IS NULL
+            AND ($1::text IS NULL OR l.category = $1)
+            GROUP BY tag
+            ORDER BY listing_count DESC, tag
+            LIMIT $2
+            "#
+        )
+        .bind(category)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let _ = cache.cache_report(&key, &tags, cache_ttl::CATEGORY_STATS).await;
+
+        Ok(tags)
+    }
+}
@@ -0,0 +1,296 @@
+use crate::config::{Config, Environment};
+use crate::error::AppError;
+use crate::marketplace::database;
+use crate::marketplace::duplicate_detector::DuplicateDetector;
+use crate::marketplace::jobs::Job;
+use crate::marketplace::keyring::CouponKeyring;
+use crate::marketplace::payments::{self, PaymentProvider, ProviderPaymentStatus};
+use crate::marketplace::seed::{SeedService, DEFAULT_SEED_SELLERS};
+use crate::marketplace::trust_scores::TrustScoreRecomputeJob;
+use crate::models::marketplace::TransactionStatus;
+use sqlx::{FromRow, PgPool};
+use std::str::FromStr;
+use uuid::Uuid;
+
+// Listings fingerprinted per page of the backfill
+const FINGERPRINT_BATCH_SIZE: i64 = 500;
+
+// Payments looked up per page of the reconciliation
+const RECONCILE_BATCH_SIZE: i64 = 200;
+
+// How far back payments are reconciled unless `--days` says otherwise
+const DEFAULT_RECONCILE_DAYS: i32 = 30;
+
+// Search indexes, rebuilt one at a time so searches keep using the others meanwhile
+const SEARCH_INDEXES: [&str; 4] = [
+    "idx_listings_title_trgm",
+    "idx_listings_tags",
+    "idx_brands_name_trgm",
+    "idx_categories_name_trgm",
+];
+
+/// One-off operational work, run as `<service binary> tasks <task> [options]` against the
+/// primary database rather than on the job schedule
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Task {
+    /// Recompute the trust score of every user who has one
+    RecomputeTrustScores,
+    /// Rebuild the search indexes and the trending ranking
+    RebuildSearchIndex,
+    /// Fingerprint listings created before duplicate detection stored fingerprints
+    BackfillFingerprints,
+    /// Compare funded transactions of the last `days` with the payment provider
+    ReconcilePayments { days: i32 },
+    /// Fill an empty development database with `sellers` fake sellers and their sales, run
+    /// as `tasks seed [--sellers N]` with `APP_ENV=development`
+    Seed { sellers: usize },
+}
+
+impl FromStr for Task {
+    type Err = AppError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "recompute-trust-scores" => Ok(Task::RecomputeTrustScores),
+            "rebuild-search-index" => Ok(Task::RebuildSearchIndex),
+            "backfill-fingerprints" => Ok(Task::BackfillFingerprints),
+            "reconcile-payments" => Ok(Task::ReconcilePayments { days: DEFAULT_RECONCILE_DAYS }),
+            "seed" => Ok(Task::Seed { sellers: DEFAULT_SEED_SELLERS }),
+            other => Err(AppError::BadRequest(format!(
+                "Unknown task: {} (expected recompute-trust-scores, rebuild-search-index, backfill-fingerprints, reconcile-payments or seed)",
+                other
+            ))),
+        }
+    }
+}
+
+impl Task {
+    /// The task named by the first argument, with its options from the rest
+    pub fn from_args(args: &[String]) -> Result<Self, AppError> {
+        let (name, options) = args
+            .split_first()
+            .ok_or_else(|| AppError::BadRequest("No task given".to_string()))?;
+        let mut task = name.parse::<Task>()?;
+
+        let mut options = options.iter();
+        while let Some(option) = options.next() {
+            match (option.as_str(), &mut task) {
+                ("--days", Task::ReconcilePayments { days }) => {
+                    *days = options
+                        .next()
+                        .and_then(|value| value.parse().ok())
+                        .filter(|value: &i32| *value > 0)
+                        .ok_or_else(|| AppError::BadRequest("--days needs a positive number of days".to_string()))?;
+                }
+                ("--sellers", Task::Seed { sellers }) => {
+                    *sellers = options
+                        .next()
+                        .and_then(|value| value.parse().ok())
+                        .filter(|value: &usize| *value > 0)
+                        .ok_or_else(|| AppError::BadRequest("--sellers needs a positive number of sellers".to_string()))?;
+                }
+                (other, _) => return Err(AppError::BadRequest(format!("Unknown option for {}: {}", name, other))),
+            }
+        }
+        Ok(task)
+    }
+}
+
+/// A funded transaction whose payment the provider does not report as captured
+#[derive(Debug, Clone)]
+pub struct PaymentMismatch {
+    pub transaction_id: Uuid,
+    pub payment_id: String,
+    pub local_status: TransactionStatus,
+    pub provider_status: ProviderPaymentStatus,
+}
+
+#[derive(FromRow)]
+struct FundedPayment {
+    id: Uuid,
+    payment_id: String,
+    status: TransactionStatus,
+}
+
+#[derive(FromRow)]
+struct UnfingerprintedListing {
+    id: Uuid,
+    category: String,
+    brand_name: Option<String>,
+    encrypted_code: String,
+}
+
+/// Connect to the primary at `database_url` and run the task the arguments name
+pub async fn run_command(database_url: &str, args: &[String]) -> Result<(), AppError> {
+    let task = Task::from_args(args)?;
+    let pool = database::connect_primary(database_url).await?;
+    run(&pool, &task).await
+}
+
+pub async fn run(pool: &PgPool, task: &Task) -> Result<(), AppError> {
+    tracing::info!(?task, "running task");
+    match task {
+        Task::RecomputeTrustScores => TrustScoreRecomputeJob.run(pool).await,
+        Task::RebuildSearchIndex => rebuild_search_index(pool).await,
+        Task::BackfillFingerprints => {
+            backfill_fingerprints(pool).await?;
+            Ok(())
+        }
+        Task::ReconcilePayments { days } => {
+            let provider = payments::payment_provider_from_config(Config::get())?;
+            let mismatches = reconcile_payments(pool, provider.as_ref(), *days).await?;
+            if !mismatches.is_empty() {
+                return Err(AppError::Conflict(format!(
+                    "{} transactions do not match the payment provider",
+                    mismatches.len()
+                )));
+            }
+            Ok(())
+        }
+        Task::Seed { sellers } => {
+            if Config::get().environment != Environment::Development {
+                return Err(AppError::Forbidden("Seeding is only allowed with APP_ENV=development".to_string()));
+            }
+            SeedService::new(pool.clone()).seed(*sellers).await?;
+            Ok(())
+        }
+    }
+}
+
+/// Rebuild the trigram and tag indexes behind search and suggestions, refresh the
+/// trending ranking and the planner statistics of the tables they cover
+pub async fn rebuild_search_index(pool: &PgPool) -> Result<(), AppError> {
+    // Concurrently, so listings can still be written while each index is rebuilt
+    for index in SEARCH_INDEXES {
+        sqlx::query(&format!("REINDEX INDEX CONCURRENTLY {}", index))
+            .execute(pool)
+            .await?;
+        tracing::info!(index, "rebuilt search index");
+    }
+
+    sqlx::query("REFRESH MATERIALIZED VIEW CONCURRENTLY marketplace_trending_listings")
+        .execute(pool)
+        .await?;
+    sqlx::query("ANALYZE marketplace_listings, marketplace_brands, marketplace_categories")
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Store a duplicate fingerprint for every listing with codes but none yet, returning how
+/// many were stored. Listings whose code cannot be decrypted are logged and left out.
+pub async fn backfill_fingerprints(pool: &PgPool) -> Result<u64, AppError> {
+    let keyring = CouponKeyring::from_config(Config::get())?;
+    let detector = DuplicateDetector::new(pool.clone());
+    let mut after = Uuid::nil();
+    let mut stored = 0;
+
+    loop {
+        let listings = sqlx::query_as::<_, UnfingerprintedListing>(
+            r#"
+            SELECT l.id, l.category, l.brand_name, c.encrypted_code
+            FROM marketplace_listings l
+            JOIN LATERAL (
+                SELECT encrypted_code FROM marketplace_coupon_codes
+                WHERE listing_id = l.id
+                ORDER BY id
+                LIMIT 1
+            ) c ON TRUE
+            WHERE l.id > $1 AND l.deleted_at IS NULL
+            AND NOT EXISTS (SELECT 1 FROM marketplace_fingerprints f WHERE f.listing_id = l.id)
+            ORDER BY l.id
+            LIMIT $2
+            "#
+        )
+        .bind(after)
+        .bind(FINGERPRINT_BATCH_SIZE)
+        .fetch_all(pool)
+        .await?;
+
+        for listing in &listings {
+            let code = match keyring.decrypt(&listing.encrypted_code) {
+                Ok(code) => code,
+                Err(e) => {
+                    tracing::warn!(listing_id = %listing.id, error = %e, "could not decrypt code to fingerprint");
+                    continue;
+                }
+            };
+            detector
+                .store_fingerprint(&listing.id.to_string(), &code, &listing.category, listing.brand_name.as_deref())
+                .await?;
+            stored += 1;
+        }
+
+        match listings.last() {
+            Some(last) if (listings.len() as i64) == FINGERPRINT_BATCH_SIZE => after = last.id,
+            _ => break,
+        }
+    }
+
+    tracing::info!(stored, "backfilled listing fingerprints");
+    Ok(stored)
+}
+
+/// Look up the payment of every transaction funded in the last `days` with the provider
+/// and report those it has not captured. Nothing is changed: escrow and refunds are left
+/// to an admin once the mismatch is understood.
+pub async fn reconcile_payments(
+    pool: &PgPool,
+    provider: &dyn PaymentProvider,
+    days: i32,
+) -> Result<Vec<PaymentMismatch>, AppError> {
+    let mut after = Uuid::nil();
+    let mut checked = 0;
+    let mut mismatches = Vec::new();
+
+    loop {
+        let funded = sqlx::query_as::<_, FundedPayment>(
+            r#"
+            SELECT id, payment_id, status FROM marketplace_transactions
+            WHERE id > $1 AND payment_id IS NOT NULL
+            AND status IN ('escrow', 'completed', 'disputed')
+            AND created_at >= CURRENT_TIMESTAMP - make_interval(days => $2)
+            ORDER BY id
+            LIMIT $3
+            "#
+        )
+        .bind(after)
+        .bind(days)
+        .bind(RECONCILE_BATCH_SIZE)
+        .fetch_all(pool)
+        .await?;
+
+        for payment in &funded {
+            let provider_status = match provider.payment_status(&payment.payment_id).await {
+                Ok(ProviderPaymentStatus::Captured) => continue,
+                Ok(status) => status,
+                Err(e) => {
+                    tracing::warn!(transaction_id = %payment.id, error = %e, "payment lookup failed");
+                    continue;
+                }
+            };
+            tracing::warn!(
+                transaction_id = %payment.id,
+                payment_id = %payment.payment_id,
+                local_status = payment.status.as_str(),
+                provider_status = ?provider_status,
+                "transaction funded without a captured payment"
+            );
+            mismatches.push(PaymentMismatch {
+                transaction_id: payment.id,
+                payment_id: payment.payment_id.clone(),
+                local_status: payment.status,
+                provider_status,
+            });
+        }
+        checked += funded.len();
+
+        match funded.last() {
+            Some(last) if (funded.len() as i64) == RECONCILE_BATCH_SIZE => after = last.id,
+            _ => break,
+        }
+    }
+
+    tracing::info!(checked, mismatched = mismatches.len(), days, "reconciled payments");
+    Ok(mismatches)
+}
@@ -0,0 +1,202 @@
+use crate::error::AppError;
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{TimelineEntry, TransactionTimeline};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// One arm per source; payments made through UPI or PayPal cover the whole checkout the
+// transaction was bought in. Reveals count from the purchase until the buyer buys the same
+// listing again.
+const TIMELINE_QUERY: &str = r#"
+    WITH t AS (SELECT * FROM marketplace_transactions WHERE id = $1)
+    SELECT t.created_at AS at, 'status_change' AS kind, t.buyer_id AS actor_id,
+           format('Purchase started for %s', t.amount) AS summary,
+           jsonb_strip_nulls(jsonb_build_object(
+               'payment_method', t.payment_method, 'checkout_id', t.checkout_id, 'listing_id', t.listing_id
+           )) AS details
+    FROM t
+
+    UNION ALL
+    SELECT h.created_at, 'status_change', h.actor_id,
+           format('%s: %s to %s', h.event, h.from_status, h.to_status),
+           jsonb_strip_nulls(jsonb_build_object(
+               'event', h.event, 'from_status', h.from_status, 'to_status', h.to_status, 'reason', h.reason
+           ))
+    FROM marketplace_transaction_status_history h JOIN t ON h.transaction_id = t.id
+
+    UNION ALL
+    SELECT p.created_at, 'payment', p.user_id,
+           format('UPI %s payment of %s requested for the checkout', p.flow, p.amount),
+           jsonb_strip_nulls(jsonb_build_object(
+               'upi_payment_id', p.id, 'provider_order_id', p.provider_order_id, 'expires_at', p.expires_at
+           ))
+    FROM marketplace_upi_payments p JOIN t ON p.purpose = 'checkout' AND p.reference_id = t.checkout_id
+
+    UNION ALL
+    SELECT p.completed_at, 'payment', NULL,
+           format('UPI payment %s', p.status),
+           jsonb_strip_nulls(jsonb_build_object(
+               'upi_payment_id', p.id, 'provider_payment_id', p.provider_payment_id, 'failure_reason', p.failure_reason
+           ))
+    FROM marketplace_upi_payments p JOIN t ON p.purpose = 'checkout' AND p.reference_id = t.checkout_id
+    WHERE p.completed_at IS NOT NULL
+
+    UNION ALL
+    SELECT o.created_at, 'payment', o.user_id,
+           format('PayPal order of %s %s created for the checkout', o.amount, o.currency),
+           jsonb_build_object('paypal_order_id', o.id, 'provider_order_id', o.provider_order_id)
+    FROM marketplace_paypal_orders o JOIN t ON o.checkout_id = t.checkout_id
+
+    UNION ALL
+    SELECT o.captured_at, 'payment', NULL,
+           format('PayPal order %s', o.status),
+           jsonb_strip_nulls(jsonb_build_object(
+               'paypal_order_id', o.id, 'payment_id', o.payment_id, 'failure_reason', o.failure_reason
+           ))
+    FROM marketplace_paypal_orders o JOIN t ON o.checkout_id = t.checkout_id
+    WHERE o.captured_at IS NOT NULL
+
+    UNION ALL
+    SELECT c.payment_received_at, 'payment', NULL,
+           'Payment received and held for high-value checks',
+           jsonb_strip_nulls(jsonb_build_object('payment_id', c.payment_id))
+    FROM marketplace_high_value_checks c JOIN t ON c.transaction_id = t.id
+    WHERE c.payment_received_at IS NOT NULL
+
+    UNION ALL
+    SELECT c.buyer_confirmed_at, 'payment', t.buyer_id,
+           'Buyer confirmed the high-value purchase',
+           '{}'::jsonb
+    FROM marketplace_high_value_checks c JOIN t ON c.transaction_id = t.id
+    WHERE c.buyer_confirmed_at IS NOT NULL
+
+    UNION ALL
+    SELECT c.seller_reviewed_at, 'payment', c.seller_reviewed_by,
+           CASE WHEN c.seller_approved THEN 'High-value review approved' ELSE 'High-value review rejected' END,
+           jsonb_strip_nulls(jsonb_build_object('notes', c.seller_review_notes))
+    FROM marketplace_high_value_checks c JOIN t ON c.transaction_id = t.id
+    WHERE c.seller_reviewed_at IS NOT NULL
+
+    UNION ALL
+    SELECT r.created_at, 'payment', NULL,
+           format('%s reversed the payment: %s', r.provider, r.reason),
+           jsonb_strip_nulls(jsonb_build_object(
+               'reversal_id', r.id, 'provider_event_id', r.provider_event_id, 'amount', r.amount
+           ))
+    FROM marketplace_payment_reversals r JOIN t ON r.payment_id = t.payment_id
+    WHERE r.kind = 'reversal'
+
+    UNION ALL
+    SELECT f.created_at, 'refund', f.initiated_by,
+           format('Refund of %s requested', f.amount),
+           jsonb_build_object('refund_id', f.id, 'reason', f.reason)
+    FROM marketplace_refunds f JOIN t ON f.transaction_id = t.id
+
+    UNION ALL
+    SELECT f.completed_at, 'refund', NULL,
+           format('Refund of %s %s', f.amount, f.status),
+           jsonb_strip_nulls(jsonb_build_object(
+               'refund_id', f.id, 'provider_refund_id', f.provider_refund_id, 'error', f.error
+           ))
+    FROM marketplace_refunds f JOIN t ON f.transaction_id = t.id
+    WHERE f.completed_at IS NOT NULL
+
+    UNION ALL
+    SELECT cb.created_at, 'chargeback', NULL,
+           format('%s charged back %s, %s of it from the seller', r.provider, cb.amount, cb.seller_clawback),
+           jsonb_build_object('reversal_id', r.id, 'provider_event_id', r.provider_event_id, 'reason', r.reason)
+    FROM marketplace_chargebacks cb
+    JOIN t ON cb.transaction_id = t.id
+    JOIN marketplace_payment_reversals r ON r.id = cb.reversal_id
+
+    UNION ALL
+    SELECT n.created_at, 'notification', NULL,
+           format(
+               '%s notified: %s',
+               CASE n.user_id WHEN t.buyer_id THEN 'Buyer' WHEN t.seller_id THEN 'Seller' ELSE n.user_id END,
+               n.title
+           ),
+           jsonb_strip_nulls(jsonb_build_object(
+               'notification_id', n.id, 'user_id', n.user_id, 'notification_type', n.notification_type,
+               'message', n.message, 'in_app', n.in_app, 'delivered_at', n.delivered_at
+           ))
+    FROM marketplace_notifications n JOIN t ON n.related_transaction_id = t.id
+
+    UNION ALL
+    SELECT e.created_at, 'dispute', e.uploaded_by,
+           format('Evidence uploaded: %s', COALESCE(e.file_name, 'unnamed file')),
+           jsonb_build_object('evidence_id', e.id, 'media_id', e.media_id)
+    FROM marketplace_dispute_evidence e JOIN t ON e.transaction_id = t.id
+
+    UNION ALL
+    SELECT c.created_at, 'dispute', c.buyer_id,
+           'Purchase protection claim opened',
+           jsonb_strip_nulls(jsonb_build_object(
+               'claim_id', c.id, 'validity_check', c.validity_check, 'validity_notes', c.validity_notes,
+               'response_due_at', c.response_due_at
+           ))
+    FROM marketplace_protection_claims c JOIN t ON c.transaction_id = t.id
+
+    UNION ALL
+    SELECT c.created_at, 'message', c.buyer_id, c.details,
+           jsonb_build_object('claim_id', c.id)
+    FROM marketplace_protection_claims c JOIN t ON c.transaction_id = t.id
+    WHERE c.details IS NOT NULL
+
+    UNION ALL
+    SELECT c.seller_responded_at, 'message', c.seller_id, c.seller_response,
+           jsonb_build_object('claim_id', c.id)
+    FROM marketplace_protection_claims c JOIN t ON c.transaction_id = t.id
+    WHERE c.seller_responded_at IS NOT NULL AND c.seller_response IS NOT NULL
+
+    UNION ALL
+    SELECT c.resolved_at, 'dispute', c.resolved_by,
+           format('Purchase protection claim %s', c.status),
+           jsonb_strip_nulls(jsonb_build_object('claim_id', c.id, 'resolution_notes', c.resolution_notes))
+    FROM marketplace_protection_claims c JOIN t ON c.transaction_id = t.id
+    WHERE c.resolved_at IS NOT NULL
+
+    UNION ALL
+    SELECT rv.revealed_at, 'reveal', rv.user_id,
+           format('Code revealed (reference %s)', rv.reference),
+           jsonb_strip_nulls(jsonb_build_object(
+               'reference', rv.reference, 'code_count', rv.code_count,
+               'ip_address', rv.ip_address, 'user_agent', rv.user_agent
+           ))
+    FROM marketplace_coupon_reveals rv
+    JOIN t ON rv.listing_id = t.listing_id AND rv.user_id = t.buyer_id AND rv.revealed_at >= t.created_at
+    WHERE NOT EXISTS (
+        SELECT 1 FROM marketplace_transactions later
+        WHERE later.listing_id = t.listing_id AND later.buyer_id = t.buyer_id
+        AND later.created_at > t.created_at AND later.created_at <= rv.revealed_at
+    )
+
+    ORDER BY at, kind
+"#;
+
+/// The whole story of one transaction in a single feed for support agents: its status
+/// changes, what the payment providers reported, refunds and chargebacks, the
+/// notifications either party got, disputes and claims, and code reveals. The marketplace
+/// has no direct messaging, so the messages are what the parties wrote in claims.
+pub struct TransactionTimelineService {
+    pool: PgPool,
+}
+
+impl TransactionTimelineService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn get(&self, transaction_id: Uuid) -> Result<TransactionTimeline, AppError> {
+        let transaction = MarketplaceService::new(self.pool.clone())
+            .get_transaction_by_id(transaction_id)
+            .await?;
+
+        let entries = sqlx::query_as::<_, TimelineEntry>(TIMELINE_QUERY)
+            .bind(transaction_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(TransactionTimeline { transaction, entries })
+    }
+}
@@ -0,0 +1,195 @@
+use crate::error::AppError;
+use crate::models::marketplace::{MarketplaceTransaction, TransactionStatus};
+use serde::{Deserialize, Serialize};
+use sqlx::{Postgres, QueryBuilder, Transaction};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+// Days the buyer's payment is held before the seller can expect release
+const ESCROW_HOLD_DAYS: i32 = 7;
+
+// Something that happened to a transaction and may move it to a new status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionEvent {
+    PaymentConfirmed,
+    EscrowReleased,
+    Disputed,
+    Cancelled,
+    ChargedBack,
+}
+
+impl TransactionEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransactionEvent::PaymentConfirmed => "payment_confirmed",
+            TransactionEvent::EscrowReleased => "escrow_released",
+            TransactionEvent::Disputed => "disputed",
+            TransactionEvent::Cancelled => "cancelled",
+            TransactionEvent::ChargedBack => "charged_back",
+        }
+    }
+}
+
+/// The only place transaction statuses change.
+///
+/// ```text
+/// pending --PaymentConfirmed--> escrow --EscrowReleased--> completed
+///    |                            |                           ^
+///    |                         Disputed --> disputed ---------+ (EscrowReleased)
+///    |                                         |
+///    +----------------Cancelled----------------+--> cancelled
+///
+/// escrow, disputed or completed --ChargedBack--> charged_back
+/// ```
+pub struct TransactionStateMachine;
+
+impl TransactionStateMachine {
+    /// Status reached by applying `event` in `from`, or `Conflict` if the move is not allowed
+    pub fn transition(from: TransactionStatus, event: TransactionEvent) -> Result<TransactionStatus, AppError> {
+        use TransactionEvent as E;
+        use TransactionStatus as S;
+
+        match (from, event) {
+            (S::Pending, E::PaymentConfirmed) => Ok(S::Escrow),
+            (S::Escrow | S::Disputed, E::EscrowReleased) => Ok(S::Completed),
+            (S::Pending | S::Escrow, E::Disputed) => Ok(S::Disputed),
+            (S::Pending | S::Escrow | S::Disputed, E::Cancelled) => Ok(S::Cancelled),
+            (S::Escrow | S::Disputed | S::Completed, E::ChargedBack) => Ok(S::ChargedBack),
+            _ => Err(AppError::Conflict(format!(
+                "Cannot apply {} to a {} transaction",
+                event.as_str(),
+                from.as_str()
+            ))),
+        }
+    }
+
+    /// Move the transaction to its next status and record the change in its history.
+    /// The update is guarded on the status we read, so a concurrent change makes this fail
+    /// with `Conflict` instead of silently overwriting it.
+    pub async fn apply(
+        tx: &mut Transaction<'_, Postgres>,
+        transaction: &MarketplaceTransaction,
+        event: TransactionEvent,
+        actor_id: &str,
+        reason: Option<&str>,
+    ) -> Result<MarketplaceTransaction, AppError> {
+        let from = transaction.status;
+        let to = Self::transition(from, event)?;
+
+        let mut query = QueryBuilder::<Postgres>::new("UPDATE marketplace_transactions SET status = ");
+        query.push_bind(to);
+
+        match to {
+            TransactionStatus::Escrow => {
+                query.push(", escrow_release_date = CURRENT_TIMESTAMP + make_interval(days => ")
+                    .push_bind(ESCROW_HOLD_DAYS)
+                    .push(")");
+            }
+            TransactionStatus::Completed => {
+                query.push(", completed_at = CURRENT_TIMESTAMP");
+            }
+            TransactionStatus::Cancelled => {
+                query.push(", cancellation_reason = ").push_bind(reason.map(str::to_string));
+            }
+            TransactionStatus::Disputed => {
+                query.push(", dispute_reason = ").push_bind(reason.map(str::to_string));
+            }
+            TransactionStatus::Pending | TransactionStatus::ChargedBack => {}
+        }
+
+        query.push(" WHERE id = ").push_bind(transaction.id);
+        query.push(" AND status = ").push_bind(from);
+        query.push(" RETURNING *");
+
+        let updated = query
+            .build_query_as::<MarketplaceTransaction>()
+            .fetch_optional(&mut **tx)
+            .await?
+            .ok_or_else(|| AppError::Conflict("Transaction status changed concurrently".to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_transaction_status_history (
+                id, transaction_id, from_status, to_status, event, actor_id, reason, created_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, CURRENT_TIMESTAMP)
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(transaction.id)
+        .bind(from)
+        .bind(to)
+        .bind(event.as_str())
+        .bind(actor_id)
+        .bind(reason)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(updated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use TransactionEvent as E;
+    use TransactionStatus as S;
+
+    // Every status against every event; `None` is a move that must be refused
+    const TRANSITIONS: [(S, E, Option<S>); 30] = [
+        (S::Pending, E::PaymentConfirmed, Some(S::Escrow)),
+        (S::Pending, E::EscrowReleased, None),
+        (S::Pending, E::Disputed, Some(S::Disputed)),
+        (S::Pending, E::Cancelled, Some(S::Cancelled)),
+        (S::Pending, E::ChargedBack, None),
+        (S::Escrow, E::PaymentConfirmed, None),
+        (S::Escrow, E::EscrowReleased, Some(S::Completed)),
+        (S::Escrow, E::Disputed, Some(S::Disputed)),
+        (S::Escrow, E::Cancelled, Some(S::Cancelled)),
+        (S::Escrow, E::ChargedBack, Some(S::ChargedBack)),
+        (S::Disputed, E::PaymentConfirmed, None),
+        (S::Disputed, E::EscrowReleased, Some(S::Completed)),
+        (S::Disputed, E::Disputed, None),
+        (S::Disputed, E::Cancelled, Some(S::Cancelled)),
+        (S::Disputed, E::ChargedBack, Some(S::ChargedBack)),
+        (S::Completed, E::PaymentConfirmed, None),
+        (S::Completed, E::EscrowReleased, None),
+        (S::Completed, E::Disputed, None),
+        (S::Completed, E::Cancelled, None),
+        (S::Completed, E::ChargedBack, Some(S::ChargedBack)),
+        (S::Cancelled, E::PaymentConfirmed, None),
+        (S::Cancelled, E::EscrowReleased, None),
+        (S::Cancelled, E::Disputed, None),
+        (S::Cancelled, E::Cancelled, None),
+        (S::Cancelled, E::ChargedBack, None),
+        (S::ChargedBack, E::PaymentConfirmed, None),
+        (S::ChargedBack, E::EscrowReleased, None),
+        (S::ChargedBack, E::Disputed, None),
+        (S::ChargedBack, E::Cancelled, None),
+        (S::ChargedBack, E::ChargedBack, None),
+    ];
+
+    #[test]
+    fn transition_follows_the_table() {
+        for (from, event, expected) in TRANSITIONS {
+            match (TransactionStateMachine::transition(from, event), expected) {
+                (Ok(to), Some(expected)) => assert_eq!(to, expected, "{:?} + {:?}", from, event),
+                (Err(AppError::Conflict(_)), None) => {}
+                (result, expected) => panic!(
+                    "{:?} + {:?}: expected {:?}, got {:?}",
+                    from, event, expected, result
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn refused_transition_names_the_event_and_status() {
+        match TransactionStateMachine::transition(S::Completed, E::Cancelled) {
+            Err(AppError::Conflict(message)) => {
+                assert_eq!(message, "Cannot apply cancelled to a completed transaction");
+            }
+            other => panic!("expected a conflict, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,443 @@
+use crate::auth::AuthUser;
+use crate::config::{Config, MachineTranslation};
+use crate::error::AppError;
+use crate::marketplace::authorization::AuthorizationPolicy;
+use crate::marketplace::jobs::Job;
+use crate::marketplace::markdown;
+use crate::marketplace::moderation::ModerationService;
+use crate::marketplace::replica;
+use crate::marketplace::scrubbing::ContactScrubber;
+use crate::models::marketplace::{
+    ListingTranslation, ListingWithSeller, TranslationSource, UpsertListingTranslationRequest,
+};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value;
+use sqlx::{FromRow, PgPool};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Locales listings are written and translated in
+pub const SUPPORTED_LOCALES: [&str; 3] = ["en", "hi", "es"];
+
+/// Language of listings created without one
+pub const DEFAULT_LOCALE: &str = "en";
+
+// Listing and locale pairs machine-translated per job run
+const MACHINE_TRANSLATION_BATCH_SIZE: i64 = 50;
+
+// Digest of the text a machine translation is made from, compared to spot edited listings
+const SOURCE_DIGEST: &str = "md5(l.title || E'\\n' || COALESCE(l.description, ''))";
+
+pub fn is_supported(locale: &str) -> bool {
+    SUPPORTED_LOCALES.contains(&locale)
+}
+
+/// The supported locale an `Accept-Language` header prefers, by quality and then order.
+/// Region subtags are ignored, so `hi-IN` asks for `hi`. None when the header is absent
+/// or names no supported locale; listings are then shown as written.
+pub fn negotiate(accept_language: Option<&str>) -> Option<&'static str> {
+    let mut best: Option<(&'static str, f32)> = None;
+
+    for entry in accept_language?.split(',') {
+        let mut parts = entry.split(';').map(str::trim);
+        let tag = parts.next().unwrap_or_default();
+        let quality = parts
+            .find_map(|param| param.strip_prefix("q="))
+            .map_or(Some(1.0), |q| q.parse::<f32>().ok());
+
+        let Some(quality) = quality.filter(|q| *q > 0.0) else {
+            continue;
+        };
+        let primary = tag.split('-').next().unwrap_or_default().to_lowercase();
+        let Some(locale) = SUPPORTED_LOCALES.iter().find(|locale| **locale == primary) else {
+            continue;
+        };
+        if best.is_none_or(|(_, best_quality)| quality > best_quality) {
+            best = Some((*locale, quality));
+        }
+    }
+    best.map(|(locale, _)| locale)
+}
+
+#[async_trait]
+pub trait TranslationProvider: Send + Sync {
+    /// `texts` translated from `source` to `target`, in the same order
+    async fn translate(&self, texts: &[String], source: &str, target: &str) -> Result<Vec<String>, AppError>;
+}
+
+#[derive(Deserialize)]
+struct TranslationResponse {
+    translations: Vec<String>,
+}
+
+/// Posts texts to the machine translation service as `{source, target, texts}`
+pub struct HttpTranslationProvider {
+    client: reqwest::Client,
+    url: String,
+    api_key: Option<String>,
+}
+
+impl HttpTranslationProvider {
+    pub fn new(config: &MachineTranslation) -> Result<Self, AppError> {
+        let url = config
+            .url
+            .clone()
+            .ok_or_else(|| AppError::InternalError("MACHINE_TRANSLATION_URL is not set".to_string()))?;
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .build()
+            .map_err(|e| AppError::InternalError(format!("Machine translation client error: {}", e)))?;
+
+        Ok(Self {
+            client,
+            url,
+            api_key: config.api_key.as_ref().map(|key| key.expose().to_string()),
+        })
+    }
+}
+
+#[async_trait]
+impl TranslationProvider for HttpTranslationProvider {
+    async fn translate(&self, texts: &[String], source: &str, target: &str) -> Result<Vec<String>, AppError> {
+        let mut request = self
+            .client
+            .post(&self.url)
+            .json(&serde_json::json!({ "source": source, "target": target, "texts": texts }));
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response = request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| AppError::InternalError(format!("Machine translation error: {}", e)))?
+            .json::<TranslationResponse>()
+            .await
+            .map_err(|e| AppError::InternalError(format!("Machine translation response error: {}", e)))?;
+
+        if response.translations.len() != texts.len() {
+            return Err(AppError::InternalError(format!(
+                "Machine translation returned {} texts for {}",
+                response.translations.len(),
+                texts.len()
+            )));
+        }
+        Ok(response.translations)
+    }
+}
+
+/// The configured machine translation provider, or None when listings are only shown in
+/// the languages their sellers wrote
+pub fn translation_provider_from_config(config: &Config) -> Result<Option<Arc<dyn TranslationProvider>>, AppError> {
+    match config.machine_translation.url {
+        Some(_) => Ok(Some(Arc::new(HttpTranslationProvider::new(&config.machine_translation)?))),
+        None => Ok(None),
+    }
+}
+
+#[derive(FromRow)]
+struct LocalizedText {
+    listing_id: Uuid,
+    title: String,
+    description: Option<String>,
+    description_html: Option<String>,
+    language: String,
+}
+
+#[derive(FromRow)]
+struct UntranslatedListing {
+    id: Uuid,
+    language: String,
+    locale: String,
+    title: String,
+    description: Option<String>,
+    source_digest: String,
+}
+
+/// Listing text in other locales. Sellers write their own translations; with a provider
+/// configured, machine translations fill in the rest and follow edits of the original.
+/// Reads overlay the translation for the negotiated locale onto the listing.
+pub struct ListingTranslationService {
+    pool: PgPool,
+}
+
+impl ListingTranslationService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list(&self, listing_id: Uuid) -> Result<Vec<ListingTranslation>, AppError> {
+        let translations = sqlx::query_as::<_, ListingTranslation>(
+            r#"
+            SELECT t.listing_id, t.locale, t.title, t.description, t.description_html, t.source, t.created_at, t.updated_at
+            FROM marketplace_listing_translations t
+            JOIN marketplace_listings l ON l.id = t.listing_id
+            WHERE t.listing_id = $1 AND l.deleted_at IS NULL
+            ORDER BY t.locale
+            "#
+        )
+        .bind(listing_id)
+        .fetch_all(&replica::listing_read_pool(&self.pool, listing_id))
+        .await?;
+        Ok(translations)
+    }
+
+    /// Write the seller's own translation, replacing a machine one. The text is screened
+    /// like the listing itself, except that findings reject it instead of holding the listing.
+    pub async fn upsert(
+        &self,
+        auth_user: &AuthUser,
+        listing_id: Uuid,
+        locale: &str,
+        mut request: UpsertListingTranslationRequest,
+    ) -> Result<ListingTranslation, AppError> {
+        let language = self.owned_listing_language(auth_user, listing_id).await?;
+        if !is_supported(locale) {
+            return Err(AppError::UnprocessableEntity(format!(
+                "Unsupported locale {}; must be one of {}",
+                locale,
+                SUPPORTED_LOCALES.join(", ")
+            )));
+        }
+        if locale == language {
+            return Err(AppError::UnprocessableEntity(format!(
+                "The listing is written in {}; edit the listing instead",
+                locale
+            )));
+        }
+
+        let scrubber = ContactScrubber::new(self.pool.clone());
+        scrubber.reject(&auth_user.0.auth0_id, "title", &request.title).await?;
+        request.description = scrubber
+            .mask_optional(&auth_user.0.auth0_id, "description", request.description.as_deref())
+            .await?;
+
+        let flags = ModerationService::new(self.pool.clone())
+            .screen_listing(&request.title, request.description.as_deref(), None)
+            .await?;
+        if !flags.is_empty() {
+            let details: Vec<String> = flags.iter().map(|flag| format!("{}: {}", flag.field, flag.detail)).collect();
+            return Err(AppError::UnprocessableEntity(format!(
+                "The translation did not pass moderation ({})",
+                details.join("; ")
+            )));
+        }
+
+        let translation = sqlx::query_as::<_, ListingTranslation>(
+            r#"
+            INSERT INTO marketplace_listing_translations (listing_id, locale, title, description, description_html, source)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (listing_id, locale) DO UPDATE
+            SET title = EXCLUDED.title, description = EXCLUDED.description, description_html = EXCLUDED.description_html,
+                source = EXCLUDED.source, source_digest = NULL, updated_at = CURRENT_TIMESTAMP
+            RETURNING listing_id, locale, title, description, description_html, source, created_at, updated_at
+            "#
+        )
+        .bind(listing_id)
+        .bind(locale)
+        .bind(&request.title)
+        .bind(&request.description)
+        .bind(markdown::description_html(request.description.as_deref()))
+        .bind(TranslationSource::Seller)
+        .fetch_one(&self.pool)
+        .await?;
+
+        replica::listing_written(listing_id);
+        Ok(translation)
+    }
+
+    /// Remove a translation. With a provider configured, a machine translation takes its
+    /// place on the next run.
+    pub async fn delete(&self, auth_user: &AuthUser, listing_id: Uuid, locale: &str) -> Result<(), AppError> {
+        self.owned_listing_language(auth_user, listing_id).await?;
+
+        let deleted = sqlx::query("DELETE FROM marketplace_listing_translations WHERE listing_id = $1 AND locale = $2")
+            .bind(listing_id)
+            .bind(locale)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        if deleted == 0 {
+            return Err(AppError::NotFound(format!("No {} translation for this listing", locale)));
+        }
+
+        replica::listing_written(listing_id);
+        Ok(())
+    }
+
+    async fn owned_listing_language(&self, auth_user: &AuthUser, listing_id: Uuid) -> Result<String, AppError> {
+        let listing: Option<(String, String)> = sqlx::query_as(
+            "SELECT seller_id, language FROM marketplace_listings WHERE id = $1 AND deleted_at IS NULL"
+        )
+        .bind(listing_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let (seller_id, language) = listing.ok_or_else(|| AppError::NotFound("Listing not found".to_string()))?;
+        AuthorizationPolicy::new(self.pool.clone()).can_edit_listing(&auth_user.0.auth0_id, &seller_id)?;
+        Ok(language)
+    }
+
+    /// Show listings in `locale` where they have a translation into it, recording the
+    /// language they were written in
+    pub async fn localize(&self, listings: &mut [ListingWithSeller], locale: &str) -> Result<(), AppError> {
+        let ids: Vec<Uuid> = listings.iter().map(|listing| listing.listing.id).collect();
+        let mut texts = self.texts(&ids, locale).await?;
+
+        for listing in listings {
+            if let Some(text) = texts.remove(&listing.listing.id) {
+                listing.listing.title = text.title;
+                listing.listing.description = text.description;
+                listing.listing.description_html = text.description_html;
+                listing.translated_from = Some(text.language);
+            }
+        }
+        Ok(())
+    }
+
+    /// `localize` for sparse listings: only the text fields that were requested are
+    /// replaced, and listings without their `id` are left as written
+    pub async fn localize_sparse(&self, listings: &mut [Value], locale: &str) -> Result<(), AppError> {
+        let ids: Vec<Uuid> = listings
+            .iter()
+            .filter_map(|listing| listing.get("id")?.as_str()?.parse().ok())
+            .collect();
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let mut texts = self.texts(&ids, locale).await?;
+
+        for listing in listings {
+            let Some(id) = listing.get("id").and_then(Value::as_str).and_then(|id| id.parse::<Uuid>().ok()) else {
+                continue;
+            };
+            let (Some(text), Some(fields)) = (texts.remove(&id), listing.as_object_mut()) else {
+                continue;
+            };
+            if let Some(title) = fields.get_mut("title") {
+                *title = Value::from(text.title);
+            }
+            if let Some(description) = fields.get_mut("description") {
+                *description = text.description.map_or(Value::Null, Value::from);
+            }
+            if let Some(description_html) = fields.get_mut("description_html") {
+                *description_html = text.description_html.map_or(Value::Null, Value::from);
+            }
+        }
+        Ok(())
+    }
+
+    // Translations into `locale` of those listings not written in it
+    async fn texts(&self, listing_ids: &[Uuid], locale: &str) -> Result<HashMap<Uuid, LocalizedText>, AppError> {
+        if listing_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let texts = sqlx::query_as::<_, LocalizedText>(
+            r#"
+            SELECT t.listing_id, t.title, t.description, t.description_html, l.language
+            FROM marketplace_listing_translations t
+            JOIN marketplace_listings l ON l.id = t.listing_id
+            WHERE t.listing_id = ANY($1) AND t.locale = $2 AND l.language <> $2
+            "#
+        )
+        .bind(listing_ids)
+        .bind(locale)
+        .fetch_all(&replica::read_pool(&self.pool))
+        .await?;
+        Ok(texts.into_iter().map(|text| (text.listing_id, text)).collect())
+    }
+
+    /// Machine-translate active listings into each supported locale they lack a
+    /// translation for, and redo machine translations of listings edited since. Returns
+    /// how many were written; stops early if the provider fails.
+    pub async fn machine_translate_batch(&self, provider: &dyn TranslationProvider) -> Result<usize, AppError> {
+        let pending = sqlx::query_as::<_, UntranslatedListing>(&format!(
+            r#"
+            SELECT l.id, l.language, loc.locale, l.title, l.description, {digest} AS source_digest
+            FROM marketplace_listings l
+            CROSS JOIN unnest($1::text[]) AS loc(locale)
+            LEFT JOIN marketplace_listing_translations t ON t.listing_id = l.id AND t.locale = loc.locale
+            WHERE l.status = 'active' AND l.deleted_at IS NULL AND l.language <> loc.locale
+            AND (t.listing_id IS NULL OR (t.source = 'machine' AND t.source_digest IS DISTINCT FROM {digest}))
+            ORDER BY l.updated_at
+            LIMIT $2
+            "#,
+            digest = SOURCE_DIGEST
+        ))
+        .bind(SUPPORTED_LOCALES.to_vec())
+        .bind(MACHINE_TRANSLATION_BATCH_SIZE)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut written = 0;
+        for listing in &pending {
+            let mut texts = vec![listing.title.clone()];
+            texts.extend(listing.description.clone());
+
+            let mut translated = match provider.translate(&texts, &listing.language, &listing.locale).await {
+                Ok(translated) => translated.into_iter(),
+                Err(e) => {
+                    tracing::warn!(listing_id = %listing.id, locale = %listing.locale, error = %e, "machine translation failed");
+                    break;
+                }
+            };
+            let title = translated.next().unwrap_or_default();
+            let description = translated.next();
+
+            // Only as translated, and never over a translation the seller wrote meanwhile
+            sqlx::query(
+                r#"
+                INSERT INTO marketplace_listing_translations
+                    (listing_id, locale, title, description, description_html, source, source_digest)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                ON CONFLICT (listing_id, locale) DO UPDATE
+                SET title = EXCLUDED.title, description = EXCLUDED.description, description_html = EXCLUDED.description_html,
+                    source_digest = EXCLUDED.source_digest, updated_at = CURRENT_TIMESTAMP
+                WHERE marketplace_listing_translations.source = 'machine'
+                "#
+            )
+            .bind(listing.id)
+            .bind(&listing.locale)
+            .bind(&title)
+            .bind(&description)
+            .bind(markdown::description_html(description.as_deref()))
+            .bind(TranslationSource::Machine)
+            .bind(&listing.source_digest)
+            .execute(&self.pool)
+            .await?;
+
+            replica::listing_written(listing.id);
+            written += 1;
+        }
+        Ok(written)
+    }
+}
+
+/// Machine-translates new and edited listings; does nothing without a provider configured
+pub struct MachineTranslationJob;
+
+#[async_trait]
+impl Job for MachineTranslationJob {
+    fn name(&self) -> &'static str {
+        "machine_translation"
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<(), AppError> {
+        let Some(provider) = translation_provider_from_config(Config::get())? else {
+            return Ok(());
+        };
+
+        let written = ListingTranslationService::new(pool.clone())
+            .machine_translate_batch(provider.as_ref())
+            .await?;
+        if written > 0 {
+            tracing::info!(written, "machine-translated listings");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use crate::error::AppError;
+use crate::marketplace::jobs::Job;
+use async_trait::async_trait;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// Days of listing views kept; marketplace_trending_listings looks back the same window
+const DAILY_VIEWS_RETENTION_DAYS: i32 = 7;
+
+/// Count a view of the listing, both in its lifetime total and in today's count used for trending
+pub async fn record_view(pool: &PgPool, listing_id: Uuid) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        WITH viewed AS (
+            UPDATE marketplace_listings SET view_count = view_count + 1 WHERE id = $1
+            RETURNING id
+        )
+        INSERT INTO marketplace_listing_daily_views (listing_id, day, views)
+        SELECT id, CURRENT_DATE, 1 FROM viewed
+        ON CONFLICT (listing_id, day)
+        DO UPDATE SET views = marketplace_listing_daily_views.views + 1
+        "#
+    )
+    .bind(listing_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Recomputes the trending ranking behind `sort_by=trending` and drops views that fell out of its window
+pub struct TrendingRefreshJob;
+
+#[async_trait]
+impl Job for TrendingRefreshJob {
+    fn name(&self) -> &'static str {
+        "trending_refresh"
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<(), AppError> {
+        // Concurrently, so browsing keeps reading the previous ranking meanwhile
+        sqlx::query("REFRESH MATERIALIZED VIEW CONCURRENTLY marketplace_trending_listings")
+            .execute(pool)
+            .await?;
+
+        sqlx::query("DELETE FROM marketplace_listing_daily_views WHERE day <= CURRENT_DATE - $1")
+            .bind(DAILY_VIEWS_RETENTION_DAYS)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+}
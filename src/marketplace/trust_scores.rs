@@ -0,0 +1,113 @@
+use crate::error::AppError;
+use crate::marketplace::jobs::Job;
+use crate::marketplace::MarketplaceService;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+// Events for a user within this long of the first one share a single recompute
+const RECOMPUTE_DEBOUNCE_SECS: f64 = 30.0;
+
+// Users recomputed per worker run and per page of the nightly pass
+const RECOMPUTE_BATCH_SIZE: i64 = 500;
+
+/// Schedule a recompute of the user's trust score, instead of recomputing while the
+/// review, sale or refund that changed it is being written
+pub async fn enqueue(pool: &PgPool, user_id: &str) -> Result<(), AppError> {
+    // A later event keeps the pending due time but moves `enqueued_at`, so a recompute
+    // that started before it does not remove the entry
+    sqlx::query(
+        r#"
+        INSERT INTO marketplace_trust_score_queue (user_id, enqueued_at, due_at)
+        VALUES ($1, clock_timestamp(), clock_timestamp() + make_interval(secs => $2))
+        ON CONFLICT (user_id) DO UPDATE SET enqueued_at = EXCLUDED.enqueued_at
+        "#
+    )
+    .bind(user_id)
+    .bind(RECOMPUTE_DEBOUNCE_SECS)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Recomputes the trust scores of queued users once their debounce period has passed
+pub struct TrustScoreQueueJob;
+
+#[async_trait]
+impl Job for TrustScoreQueueJob {
+    fn name(&self) -> &'static str {
+        "trust_score_queue"
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<(), AppError> {
+        let service = MarketplaceService::new(pool.clone());
+
+        loop {
+            let due = sqlx::query_as::<_, (String, DateTime<Utc>)>(
+                r#"
+                SELECT user_id, enqueued_at FROM marketplace_trust_score_queue
+                WHERE due_at <= CURRENT_TIMESTAMP
+                ORDER BY due_at
+                LIMIT $1
+                "#
+            )
+            .bind(RECOMPUTE_BATCH_SIZE)
+            .fetch_all(pool)
+            .await?;
+
+            for (user_id, enqueued_at) in &due {
+                service.recalculate_trust_score(user_id).await?;
+
+                sqlx::query("DELETE FROM marketplace_trust_score_queue WHERE user_id = $1 AND enqueued_at = $2")
+                    .bind(user_id)
+                    .bind(enqueued_at)
+                    .execute(pool)
+                    .await?;
+            }
+
+            if (due.len() as i64) < RECOMPUTE_BATCH_SIZE {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Recomputes every trust score nightly, healing any that drifted from a missed or failed recompute
+pub struct TrustScoreRecomputeJob;
+
+#[async_trait]
+impl Job for TrustScoreRecomputeJob {
+    fn name(&self) -> &'static str {
+        "trust_score_recompute"
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<(), AppError> {
+        let service = MarketplaceService::new(pool.clone());
+        let mut after = String::new();
+        let mut recomputed = 0;
+
+        loop {
+            let user_ids = sqlx::query_scalar::<_, String>(
+                "SELECT user_id FROM marketplace_trust_scores WHERE user_id > $1 ORDER BY user_id LIMIT $2"
+            )
+            .bind(&after)
+            .bind(RECOMPUTE_BATCH_SIZE)
+            .fetch_all(pool)
+            .await?;
+
+            for user_id in &user_ids {
+                service.recalculate_trust_score(user_id).await?;
+            }
+            recomputed += user_ids.len();
+
+            match user_ids.last() {
+                Some(last) if (user_ids.len() as i64) == RECOMPUTE_BATCH_SIZE => after = last.clone(),
+                _ => break,
+            }
+        }
+
+        tracing::info!(recomputed, "recomputed trust scores");
+        Ok(())
+    }
+}
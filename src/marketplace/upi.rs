@@ -0,0 +1,441 @@
+use crate::auth::AuthUser;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::marketplace::cart::{mark_paid, CartService};
+use crate::marketplace::jobs::Job;
+use crate::marketplace::payments::{
+    payment_provider_from_config, PaymentProvider, ProviderPaymentStatus, ProviderRefundRequest, ProviderWebhookEvent,
+    UpiPaymentRequest,
+};
+use crate::marketplace::reversals::PaymentReversalService;
+use crate::marketplace::wallet::{complete_topup, notify_topped_up};
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{
+    Checkout, CheckoutStatus, MarketplaceTransaction, StartUpiPaymentRequest, UpiFlow, UpiPayment,
+    UpiPaymentPurpose, UpiPaymentStatus, WalletFundingMethod, WalletOperationStatus, WalletTopup,
+};
+use async_trait::async_trait;
+use axum::http::HeaderMap;
+use bigdecimal::{BigDecimal, Zero};
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+const SYSTEM_ACTOR: &str = "system";
+
+// Pending payments younger than this are left to their webhook
+const RECONCILE_AFTER_MINS: i32 = 2;
+
+// Provider outcomes can trail the expiry we asked for
+const EXPIRY_GRACE_MINS: i32 = 10;
+
+// Payments looked up at the provider per reconciliation pass
+const RECONCILE_BATCH_SIZE: i64 = 100;
+
+/// `handle@bank`, as accepted by UPI apps
+pub fn is_valid_vpa(vpa: &str) -> bool {
+    let Some((handle, bank)) = vpa.split_once('@') else {
+        return false;
+    };
+    (2..=256).contains(&handle.len())
+        && handle.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+        && (2..=64).contains(&bank.len())
+        && bank.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// UPI payments for checkouts and UPI-funded wallet top-ups. The buyer either opens an
+/// intent link in their UPI app or approves a collect request sent to their VPA.
+///
+/// Payments are settled by the provider's signed webhooks. Pending payments whose webhook
+/// never arrived are looked up at the provider by `UpiReconciliationJob`, and expire when
+/// the provider still has no outcome. A payment captured after its checkout or top-up was
+/// already paid is refunded.
+pub struct UpiPaymentService {
+    pool: PgPool,
+    provider: Arc<dyn PaymentProvider>,
+}
+
+// What a captured payment paid for, for the notifications sent after commit
+enum Settled {
+    Checkout(Checkout, Vec<MarketplaceTransaction>),
+    Topup(WalletTopup),
+}
+
+impl UpiPaymentService {
+    pub fn new(pool: PgPool) -> Result<Self, AppError> {
+        Ok(Self {
+            pool,
+            provider: payment_provider_from_config(Config::get())?,
+        })
+    }
+
+    pub fn with_provider(pool: PgPool, provider: Arc<dyn PaymentProvider>) -> Self {
+        Self { pool, provider }
+    }
+
+    /// Pay what is due on a checkout by UPI
+    pub async fn pay_checkout(
+        &self,
+        auth_user: &AuthUser,
+        checkout_id: Uuid,
+        request: &StartUpiPaymentRequest,
+    ) -> Result<UpiPayment, AppError> {
+        let user_id = &auth_user.0.auth0_id;
+        let checkout = CartService::new(self.pool.clone()).fetch_checkout(checkout_id).await?;
+
+        if checkout.buyer_id != *user_id {
+            return Err(AppError::Forbidden("This checkout belongs to another user".to_string()));
+        }
+        if checkout.status == CheckoutStatus::Expired {
+            return Err(AppError::Conflict("Checkout expired before it was paid".to_string()));
+        }
+        if checkout.status != CheckoutStatus::AwaitingPayment {
+            return Err(AppError::Conflict("Checkout has already been paid".to_string()));
+        }
+
+        let due = &checkout.total_amount - &checkout.discount_amount;
+        self.start(user_id, UpiPaymentPurpose::Checkout, checkout.id, &due, "Marketplace order", request.vpa.as_deref())
+            .await
+    }
+
+    /// Pay a pending UPI wallet top-up
+    pub async fn pay_topup(
+        &self,
+        auth_user: &AuthUser,
+        topup_id: Uuid,
+        request: &StartUpiPaymentRequest,
+    ) -> Result<UpiPayment, AppError> {
+        let user_id = &auth_user.0.auth0_id;
+        let topup = sqlx::query_as::<_, WalletTopup>("SELECT * FROM marketplace_wallet_topups WHERE id = $1")
+            .bind(topup_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Top-up not found".to_string()))?;
+
+        if topup.user_id != *user_id {
+            return Err(AppError::Forbidden("This top-up belongs to another user".to_string()));
+        }
+        if topup.status != WalletOperationStatus::Pending {
+            return Err(AppError::Conflict("Top-up is no longer awaiting payment".to_string()));
+        }
+        if topup.funding_method != WalletFundingMethod::Upi {
+            return Err(AppError::UnprocessableEntity("This top-up is funded by card".to_string()));
+        }
+
+        self.start(user_id, UpiPaymentPurpose::WalletTopup, topup.id, &topup.amount, "Wallet top-up", request.vpa.as_deref())
+            .await
+    }
+
+    /// A payment for its payer or an admin, checked with the provider while still pending
+    pub async fn get_payment(&self, auth_user: &AuthUser, payment_id: Uuid) -> Result<UpiPayment, AppError> {
+        let user_id = &auth_user.0.auth0_id;
+        let payment = self.fetch_payment(payment_id).await?;
+
+        if payment.user_id != *user_id && !MarketplaceService::new(self.pool.clone()).is_admin(user_id).await? {
+            return Err(AppError::Forbidden("This payment belongs to another user".to_string()));
+        }
+
+        let Some(provider_payment_id) = payment.provider_payment_id.as_deref() else {
+            return Ok(payment);
+        };
+        if payment.status != UpiPaymentStatus::Pending {
+            return Ok(payment);
+        }
+
+        let status = self.provider.payment_status(provider_payment_id).await?;
+        self.apply(provider_payment_id, status).await?;
+        self.fetch_payment(payment_id).await
+    }
+
+    /// Verify and apply a provider webhook
+    pub async fn handle_webhook(&self, payload: &[u8], headers: &HeaderMap) -> Result<(), AppError> {
+        match self.provider.parse_webhook(payload, headers).await? {
+            Some(ProviderWebhookEvent::Payment { payment_id, status }) => self.apply(&payment_id, status).await,
+            Some(ProviderWebhookEvent::Reversal(reversal)) => {
+                PaymentReversalService::new(self.pool.clone())
+                    .record(&Config::get().payments.provider, &reversal)
+                    .await
+            }
+            Some(ProviderWebhookEvent::OrderCaptured { .. }) | None => Ok(()),
+        }
+    }
+
+    /// Look up pending payments whose webhook never arrived and expire the ones that lapsed.
+    /// Returns how many payments were settled or failed.
+    pub async fn reconcile_pending(&self) -> Result<u64, AppError> {
+        let stale = sqlx::query_as::<_, UpiPayment>(
+            r#"
+            SELECT * FROM marketplace_upi_payments
+            WHERE status = $1
+              AND provider_payment_id IS NOT NULL
+              AND created_at <= CURRENT_TIMESTAMP - make_interval(mins => $2)
+            ORDER BY created_at
+            LIMIT $3
+            "#
+        )
+        .bind(UpiPaymentStatus::Pending)
+        .bind(RECONCILE_AFTER_MINS)
+        .bind(RECONCILE_BATCH_SIZE)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut resolved = 0;
+        for payment in stale {
+            let Some(provider_payment_id) = payment.provider_payment_id.as_deref() else {
+                continue;
+            };
+            let result = match self.provider.payment_status(provider_payment_id).await {
+                Ok(ProviderPaymentStatus::Pending) => continue,
+                Ok(status) => self.apply(provider_payment_id, status).await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(()) => resolved += 1,
+                Err(e) => tracing::warn!(upi_payment_id = %payment.id, error = %e, "UPI reconciliation failed"),
+            }
+        }
+
+        // Still no outcome after the grace period, or never reached the provider
+        sqlx::query(
+            r#"
+            UPDATE marketplace_upi_payments
+            SET status = $2, completed_at = CURRENT_TIMESTAMP
+            WHERE status = $1 AND expires_at <= CURRENT_TIMESTAMP - make_interval(mins => $3)
+            "#
+        )
+        .bind(UpiPaymentStatus::Pending)
+        .bind(UpiPaymentStatus::Expired)
+        .bind(EXPIRY_GRACE_MINS)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(resolved)
+    }
+
+    async fn start(
+        &self,
+        user_id: &str,
+        purpose: UpiPaymentPurpose,
+        reference_id: Uuid,
+        amount: &BigDecimal,
+        description: &str,
+        vpa: Option<&str>,
+    ) -> Result<UpiPayment, AppError> {
+        if amount <= &BigDecimal::zero() {
+            return Err(AppError::UnprocessableEntity("Nothing is due".to_string()));
+        }
+        let expiry_mins = Config::get().payments.upi_expiry_mins;
+
+        // A lapsed request no longer blocks a new one; a late capture is still settled
+        sqlx::query(
+            r#"
+            UPDATE marketplace_upi_payments
+            SET status = $3, completed_at = CURRENT_TIMESTAMP
+            WHERE reference_id = $1 AND status = $2 AND expires_at <= CURRENT_TIMESTAMP
+            "#
+        )
+        .bind(reference_id)
+        .bind(UpiPaymentStatus::Pending)
+        .bind(UpiPaymentStatus::Expired)
+        .execute(&self.pool)
+        .await?;
+
+        // Claimed before calling the provider so the buyer cannot be asked to pay twice
+        let payment = sqlx::query_as::<_, UpiPayment>(
+            r#"
+            INSERT INTO marketplace_upi_payments (
+                id, user_id, purpose, reference_id, amount, flow, vpa, status, expires_at, created_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, CURRENT_TIMESTAMP + make_interval(mins => $9), CURRENT_TIMESTAMP)
+            ON CONFLICT (reference_id) WHERE status = 'pending' DO NOTHING
+            RETURNING *
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(purpose)
+        .bind(reference_id)
+        .bind(amount)
+        .bind(if vpa.is_some() { UpiFlow::Collect } else { UpiFlow::Intent })
+        .bind(vpa)
+        .bind(UpiPaymentStatus::Pending)
+        .bind(expiry_mins as i32)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| {
+            AppError::Conflict("A UPI payment is already in progress; approve it or wait until it expires".to_string())
+        })?;
+
+        let request = UpiPaymentRequest::new(payment.id.to_string(), amount, description, vpa, expiry_mins);
+        let session = match self.provider.create_upi_payment(&request).await {
+            Ok(session) => session,
+            Err(e) => {
+                tracing::warn!(upi_payment_id = %payment.id, error = %e, "UPI payment could not be started");
+                self.mark_failed(payment.id, &e.to_string()).await?;
+                return Err(e);
+            }
+        };
+
+        let payment = sqlx::query_as::<_, UpiPayment>(
+            r#"
+            UPDATE marketplace_upi_payments
+            SET provider_payment_id = $2, provider_order_id = $3, intent_url = $4
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(payment.id)
+        .bind(&session.payment_id)
+        .bind(&session.order_id)
+        .bind(&session.intent_url)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(payment)
+    }
+
+    async fn apply(&self, provider_payment_id: &str, status: ProviderPaymentStatus) -> Result<(), AppError> {
+        match status {
+            ProviderPaymentStatus::Pending => Ok(()),
+            ProviderPaymentStatus::Captured => self.settle(provider_payment_id).await,
+            ProviderPaymentStatus::Failed(reason) => {
+                sqlx::query(
+                    r#"
+                    UPDATE marketplace_upi_payments
+                    SET status = $3, failure_reason = $4, completed_at = CURRENT_TIMESTAMP
+                    WHERE provider_payment_id = $1 AND status = $2
+                    "#
+                )
+                .bind(provider_payment_id)
+                .bind(UpiPaymentStatus::Pending)
+                .bind(UpiPaymentStatus::Failed)
+                .bind(&reason)
+                .execute(&self.pool)
+                .await?;
+                Ok(())
+            }
+        }
+    }
+
+    // Pay the checkout or top-up with a captured payment; repeated calls do nothing
+    async fn settle(&self, provider_payment_id: &str) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let payment = sqlx::query_as::<_, UpiPayment>(
+            r#"
+            UPDATE marketplace_upi_payments
+            SET status = $2, completed_at = CURRENT_TIMESTAMP
+            WHERE provider_payment_id = $1 AND status IN ($3, $4)
+            RETURNING *
+            "#
+        )
+        .bind(provider_payment_id)
+        .bind(UpiPaymentStatus::Completed)
+        .bind(UpiPaymentStatus::Pending)
+        .bind(UpiPaymentStatus::Expired)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(payment) = payment else {
+            return Ok(());
+        };
+
+        let settled = match payment.purpose {
+            UpiPaymentPurpose::Checkout => mark_paid(&mut tx, payment.reference_id, Some(provider_payment_id), SYSTEM_ACTOR)
+                .await
+                .map(|(checkout, pending)| Settled::Checkout(checkout, pending)),
+            UpiPaymentPurpose::WalletTopup => complete_topup(&mut tx, payment.reference_id, provider_payment_id)
+                .await
+                .map(Settled::Topup),
+        };
+
+        match settled {
+            Ok(Settled::Checkout(_, pending)) if pending.is_empty() => {
+                tx.rollback().await?;
+                self.refund_unapplied(&payment, provider_payment_id, "Every item of the order was cancelled").await
+            }
+            Ok(Settled::Checkout(checkout, pending)) => {
+                tx.commit().await?;
+                CartService::new(self.pool.clone()).notify_paid(&checkout, &pending).await;
+                Ok(())
+            }
+            Ok(Settled::Topup(topup)) => {
+                tx.commit().await?;
+                notify_topped_up(&MarketplaceService::new(self.pool.clone()), &topup).await;
+                Ok(())
+            }
+            Err(AppError::Conflict(_)) => {
+                tx.rollback().await?;
+                self.refund_unapplied(&payment, provider_payment_id, "Already paid by another payment").await
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    // Give back a captured payment there is nothing left to apply to
+    async fn refund_unapplied(&self, payment: &UpiPayment, provider_payment_id: &str, reason: &str) -> Result<(), AppError> {
+        let request = ProviderRefundRequest::new(provider_payment_id, &payment.amount, reason, payment.id.to_string());
+        self.provider.refund(&request).await?;
+
+        sqlx::query(
+            r#"
+            UPDATE marketplace_upi_payments
+            SET status = $2, failure_reason = $3, completed_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            "#
+        )
+        .bind(payment.id)
+        .bind(UpiPaymentStatus::Refunded)
+        .bind(reason)
+        .execute(&self.pool)
+        .await?;
+
+        MarketplaceService::new(self.pool.clone()).create_notification(
+            &payment.user_id,
+            "upi_payment_refunded",
+            "Payment refunded",
+            &format!("Your UPI payment of {} was refunded: {}", payment.amount, reason.to_lowercase()),
+            None,
+            None,
+        ).await;
+        Ok(())
+    }
+
+    async fn mark_failed(&self, payment_id: Uuid, reason: &str) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            UPDATE marketplace_upi_payments
+            SET status = $2, failure_reason = $3, completed_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            "#
+        )
+        .bind(payment_id)
+        .bind(UpiPaymentStatus::Failed)
+        .bind(reason)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn fetch_payment(&self, payment_id: Uuid) -> Result<UpiPayment, AppError> {
+        sqlx::query_as::<_, UpiPayment>("SELECT * FROM marketplace_upi_payments WHERE id = $1")
+            .bind(payment_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Payment not found".to_string()))
+    }
+}
+
+pub struct UpiReconciliationJob;
+
+#[async_trait]
+impl Job for UpiReconciliationJob {
+    fn name(&self) -> &'static str {
+        "upi_reconciliation"
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<(), AppError> {
+        UpiPaymentService::new(pool.clone())?.reconcile_pending().await?;
+        Ok(())
+    }
+}
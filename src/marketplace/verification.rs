@@ -0,0 +1,466 @@
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::marketplace::commission::CommissionService;
+use crate::marketplace::jobs::Job;
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{
+    ClaimedVerification, ReviewSellerVerificationRequest, SellerVerification,
+    SubmitSellerVerificationRequest, VerificationClaim, VerificationClaimOutcome,
+    VerificationStatus, VerifierMetrics,
+};
+use async_trait::async_trait;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+const ALLOWED_DOCUMENT_TYPES: &[&str] = &["passport", "national_id", "drivers_license"];
+
+// A claimed submission returns to the queue if not reviewed within this time
+pub const CLAIM_SLA_MINUTES: i32 = 30;
+
+// Submissions waiting longer than this are escalated to admins and claimed first
+pub const ESCALATE_AFTER_HOURS: i32 = 48;
+
+pub struct SellerVerificationService {
+    pool: PgPool,
+}
+
+impl SellerVerificationService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Submit identity documents for seller verification
+    pub async fn submit(
+        &self,
+        auth_user: &AuthUser,
+        request: SubmitSellerVerificationRequest,
+    ) -> Result<SellerVerification, AppError> {
+        if !ALLOWED_DOCUMENT_TYPES.contains(&request.document_type.as_str()) {
+            return Err(AppError::BadRequest(format!(
+                "Unsupported document type, expected one of: {}",
+                ALLOWED_DOCUMENT_TYPES.join(", ")
+            )));
+        }
+
+        if request.full_name.trim().is_empty() || request.document_url.trim().is_empty() {
+            return Err(AppError::BadRequest(
+                "Full name and document are required".to_string()
+            ));
+        }
+
+        // Already verified sellers don't need to resubmit
+        let already_verified = sqlx::query(
+            "SELECT 1 FROM marketplace_trust_scores WHERE user_id = $1 AND verified_seller = TRUE"
+        )
+        .bind(&auth_user.0.auth0_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if already_verified.is_some() {
+            return Err(AppError::Conflict("You are already a verified seller".to_string()));
+        }
+
+        let open_submission = sqlx::query(
+            r#"
+            SELECT 1 FROM marketplace_seller_verifications
+            WHERE user_id = $1 AND status IN ('pending', 'in_progress')
+            "#
+        )
+        .bind(&auth_user.0.auth0_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if open_submission.is_some() {
+            return Err(AppError::Conflict(
+                "You already have a verification request under review".to_string()
+            ));
+        }
+
+        let verification = sqlx::query_as::<_, SellerVerification>(
+            r#"
+            INSERT INTO marketplace_seller_verifications (
+                id, user_id, full_name, document_type, document_url, selfie_url,
+                status, submitted_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, 'pending', CURRENT_TIMESTAMP)
+            RETURNING *
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(&auth_user.0.auth0_id)
+        .bind(request.full_name.trim())
+        .bind(&request.document_type)
+        .bind(&request.document_url)
+        .bind(&request.selfie_url)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(verification)
+    }
+
+    /// Get the latest verification submission of the current user
+    pub async fn get_my_verification(
+        &self,
+        auth_user: &AuthUser,
+    ) -> Result<Option<SellerVerification>, AppError> {
+        let verification = sqlx::query_as::<_, SellerVerification>(
+            r#"
+            SELECT * FROM marketplace_seller_verifications
+            WHERE user_id = $1
+            ORDER BY submitted_at DESC
+            LIMIT 1
+            "#
+        )
+        .bind(&auth_user.0.auth0_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(verification)
+    }
+
+    /// Review queue, escalated submissions first, then oldest first
+    pub async fn get_queue(
+        &self,
+        auth_user: &AuthUser,
+        status: Option<VerificationStatus>,
+    ) -> Result<Vec<SellerVerification>, AppError> {
+        let service = MarketplaceService::new(self.pool.clone());
+        service.require_verifier(auth_user).await?;
+
+        let queue = sqlx::query_as::<_, SellerVerification>(
+            r#"
+            SELECT * FROM marketplace_seller_verifications
+            WHERE status = $1
+            ORDER BY escalated_at ASC NULLS LAST, submitted_at ASC
+            LIMIT 100
+            "#
+        )
+        .bind(status.unwrap_or(VerificationStatus::Pending))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(queue)
+    }
+
+    /// Assign the next submission in the queue to the calling verifier. A verifier works on
+    /// one submission at a time, so an unexpired claim they already hold is returned instead.
+    /// `None` when the queue is empty.
+    pub async fn claim_next(&self, auth_user: &AuthUser) -> Result<Option<ClaimedVerification>, AppError> {
+        let service = MarketplaceService::new(self.pool.clone());
+        service.require_verifier(auth_user).await?;
+        let verifier_id = &auth_user.0.auth0_id;
+
+        let mut tx = self.pool.begin().await?;
+
+        let held = sqlx::query_as::<_, VerificationClaim>(
+            r#"
+            SELECT * FROM marketplace_verification_claims
+            WHERE verifier_id = $1 AND outcome IS NULL AND expires_at > CURRENT_TIMESTAMP
+            LIMIT 1
+            "#
+        )
+        .bind(verifier_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if let Some(claim) = held {
+            let verification = sqlx::query_as::<_, SellerVerification>(
+                "SELECT * FROM marketplace_seller_verifications WHERE id = $1"
+            )
+            .bind(claim.verification_id)
+            .fetch_one(&mut *tx)
+            .await?;
+            tx.commit().await?;
+            return Ok(Some(ClaimedVerification { verification, claim }));
+        }
+
+        // Claims past their SLA are up for grabs even before the sweep returns them to pending
+        let next = sqlx::query_as::<_, SellerVerification>(
+            r#"
+            SELECT * FROM marketplace_seller_verifications
+            WHERE (status = 'pending' OR (status = 'in_progress' AND claim_expires_at <= CURRENT_TIMESTAMP))
+            AND user_id <> $1
+            ORDER BY escalated_at ASC NULLS LAST, submitted_at ASC
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+            "#
+        )
+        .bind(verifier_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(next) = next else {
+            return Ok(None);
+        };
+
+        close_claim(&mut tx, next.id, None, VerificationClaimOutcome::Expired).await?;
+
+        let verification = sqlx::query_as::<_, SellerVerification>(
+            r#"
+            UPDATE marketplace_seller_verifications
+            SET status = 'in_progress',
+                reviewer_id = $2,
+                claim_expires_at = CURRENT_TIMESTAMP + make_interval(mins => $3)
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(next.id)
+        .bind(verifier_id)
+        .bind(CLAIM_SLA_MINUTES)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let claim = sqlx::query_as::<_, VerificationClaim>(
+            r#"
+            INSERT INTO marketplace_verification_claims (id, verification_id, verifier_id, claimed_at, expires_at)
+            VALUES ($1, $2, $3, CURRENT_TIMESTAMP, $4)
+            RETURNING *
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(verification.id)
+        .bind(verifier_id)
+        .bind(verification.claim_expires_at)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(Some(ClaimedVerification { verification, claim }))
+    }
+
+    /// Approve or reject a verification submission. Verifiers must hold an unexpired claim
+    /// on it; admins may review any open submission.
+    pub async fn review(
+        &self,
+        auth_user: &AuthUser,
+        verification_id: Uuid,
+        request: ReviewSellerVerificationRequest,
+    ) -> Result<SellerVerification, AppError> {
+        let service = MarketplaceService::new(self.pool.clone());
+        service.require_verifier(auth_user).await?;
+        let reviewer_id = &auth_user.0.auth0_id;
+
+        let (new_status, outcome) = if request.approved {
+            (VerificationStatus::Verified, VerificationClaimOutcome::Verified)
+        } else {
+            (VerificationStatus::Rejected, VerificationClaimOutcome::Rejected)
+        };
+
+        let mut tx = self.pool.begin().await?;
+
+        let open = sqlx::query_as::<_, SellerVerification>(
+            r#"
+            SELECT * FROM marketplace_seller_verifications
+            WHERE id = $1 AND status IN ('pending', 'in_progress')
+            FOR UPDATE
+            "#
+        )
+        .bind(verification_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Open verification request not found".to_string()))?;
+
+        if open.user_id == *reviewer_id {
+            return Err(AppError::Forbidden("You cannot review your own verification request".to_string()));
+        }
+
+        let holds_claim = open.status == VerificationStatus::InProgress
+            && open.reviewer_id.as_deref() == Some(reviewer_id.as_str())
+            && open.claim_expires_at.is_some_and(|expires_at| expires_at > chrono::Utc::now());
+        if !holds_claim && !service.is_admin(reviewer_id).await? {
+            return Err(AppError::Conflict(
+                "Claim this verification request from the queue before reviewing it".to_string()
+            ));
+        }
+
+        close_claim(&mut tx, verification_id, Some(reviewer_id), outcome).await?;
+
+        let verification = sqlx::query_as::<_, SellerVerification>(
+            r#"
+            UPDATE marketplace_seller_verifications
+            SET status = $1,
+                reviewer_id = $2,
+                review_notes = $3,
+                reviewed_at = CURRENT_TIMESTAMP,
+                claim_expires_at = NULL
+            WHERE id = $4
+            RETURNING *
+            "#
+        )
+        .bind(new_status)
+        .bind(reviewer_id)
+        .bind(&request.review_notes)
+        .bind(verification_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if request.approved {
+            sqlx::query(
+                r#"
+                INSERT INTO marketplace_trust_scores (user_id, trust_score, verified_seller, last_calculated)
+                VALUES ($1, 50.0, TRUE, CURRENT_TIMESTAMP)
+                ON CONFLICT (user_id) DO UPDATE SET verified_seller = TRUE
+                "#
+            )
+            .bind(&verification.user_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        if request.approved {
+            // Apply the verified seller bonus, which may also lower their commission
+            service.recalculate_trust_score(&verification.user_id).await?;
+            CommissionService::new(self.pool.clone()).refresh_seller(&verification.user_id).await?;
+
+            service.create_notification(
+                &verification.user_id,
+                "seller_verified",
+                "You're a Verified Seller!",
+                "Your identity has been verified and your listings now show the Verified Seller badge",
+                None,
+                None,
+            ).await;
+        } else {
+            service.create_notification(
+                &verification.user_id,
+                "seller_verification_rejected",
+                "Verification Unsuccessful",
+                &format!(
+                    "We couldn't verify your identity: {}",
+                    request.review_notes.as_deref().unwrap_or("please resubmit clearer documents")
+                ),
+                None,
+                None,
+            ).await;
+        }
+
+        Ok(verification)
+    }
+    /// Claim throughput per verifier over the last `days` days
+    pub async fn verifier_metrics(&self, auth_user: &AuthUser, days: i32) -> Result<Vec<VerifierMetrics>, AppError> {
+        MarketplaceService::new(self.pool.clone()).require_admin(auth_user).await?;
+
+        let metrics = sqlx::query_as::<_, VerifierMetrics>(
+            r#"
+            SELECT
+                verifier_id,
+                COUNT(*) as claims,
+                COUNT(*) FILTER (WHERE outcome = 'verified') as verified,
+                COUNT(*) FILTER (WHERE outcome = 'rejected') as rejected,
+                COUNT(*) FILTER (WHERE outcome = 'expired') as expired,
+                (AVG(EXTRACT(EPOCH FROM closed_at - claimed_at))
+                    FILTER (WHERE outcome IN ('verified', 'rejected')))::float8 as avg_review_seconds
+            FROM marketplace_verification_claims
+            WHERE claimed_at >= CURRENT_TIMESTAMP - make_interval(days => $1)
+            GROUP BY verifier_id
+            ORDER BY COUNT(*) FILTER (WHERE outcome IN ('verified', 'rejected')) DESC, verifier_id
+            "#
+        )
+        .bind(days.clamp(1, 365))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(metrics)
+    }
+
+    /// Return submissions whose claim ran past the SLA to the queue
+    pub async fn expire_claims(&self) -> Result<u64, AppError> {
+        let result = sqlx::query(
+            r#"
+            WITH expired AS (
+                UPDATE marketplace_verification_claims
+                SET outcome = 'expired', closed_at = CURRENT_TIMESTAMP
+                WHERE outcome IS NULL AND expires_at <= CURRENT_TIMESTAMP
+                RETURNING verification_id
+            )
+            UPDATE marketplace_seller_verifications
+            SET status = 'pending', reviewer_id = NULL, claim_expires_at = NULL
+            WHERE id IN (SELECT verification_id FROM expired) AND status = 'in_progress'
+            "#
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Flag submissions waiting past `ESCALATE_AFTER_HOURS` and tell the admins
+    pub async fn escalate_stale(&self) -> Result<usize, AppError> {
+        let escalated = sqlx::query(
+            r#"
+            UPDATE marketplace_seller_verifications
+            SET escalated_at = CURRENT_TIMESTAMP
+            WHERE status IN ('pending', 'in_progress')
+            AND escalated_at IS NULL
+            AND submitted_at <= CURRENT_TIMESTAMP - make_interval(hours => $1)
+            RETURNING id
+            "#
+        )
+        .bind(ESCALATE_AFTER_HOURS)
+        .fetch_all(&self.pool)
+        .await?
+        .len();
+
+        if escalated == 0 {
+            return Ok(0);
+        }
+
+        MarketplaceService::new(self.pool.clone())
+            .notify_admins(
+                "seller_verification_escalated",
+                "Seller Verifications Overdue",
+                &format!(
+                    "{} seller verification request(s) have waited more than {} hours for review",
+                    escalated, ESCALATE_AFTER_HOURS
+                ),
+            )
+            .await?;
+
+        Ok(escalated)
+    }
+}
+
+// Close the open claim on a submission. The claim's verifier gets `outcome` when they are
+// the reviewer; a claim held by someone else ends as released.
+async fn close_claim(
+    tx: &mut Transaction<'_, Postgres>,
+    verification_id: Uuid,
+    reviewer_id: Option<&str>,
+    outcome: VerificationClaimOutcome,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        UPDATE marketplace_verification_claims
+        SET outcome = CASE WHEN $2::text IS NULL OR verifier_id = $2 THEN $3 ELSE 'released' END,
+            closed_at = CURRENT_TIMESTAMP
+        WHERE verification_id = $1 AND outcome IS NULL
+        "#
+    )
+    .bind(verification_id)
+    .bind(reviewer_id)
+    .bind(outcome)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Expires verifier claims past their SLA and escalates long-waiting submissions
+pub struct VerificationSlaJob;
+
+#[async_trait]
+impl Job for VerificationSlaJob {
+    fn name(&self) -> &'static str {
+        "seller_verification_sla"
+    }
+
+    async fn run(&self, pool: &PgPool) -> Result<(), AppError> {
+        let service = SellerVerificationService::new(pool.clone());
+        service.expire_claims().await?;
+        service.escalate_stale().await?;
+        Ok(())
+    }
+}
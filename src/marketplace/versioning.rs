@@ -0,0 +1,75 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderName, HeaderValue},
+    middleware::{self, Next},
+    response::Response,
+    Router,
+};
+use chrono::NaiveDate;
+
+/// Prefix of the current API version
+pub const CURRENT_PREFIX: &str = "/api/v1/marketplace";
+
+/// Prefix of the partner API, authenticated by API key rather than user credentials
+pub const PARTNER_PREFIX: &str = "/api/partner/marketplace";
+
+/// An older prefix still served as an alias of the current routes while clients migrate
+#[derive(Debug, Clone, Copy)]
+pub struct LegacyAlias {
+    pub prefix: &'static str,
+    pub deprecated_on: &'static str, // YYYY-MM-DD
+    pub sunset_on: &'static str,     // YYYY-MM-DD, when the alias stops being served
+}
+
+// Unversioned paths predate /api/v1 and answer exactly like v1
+pub const LEGACY_ALIASES: &[LegacyAlias] = &[LegacyAlias {
+    prefix: "/api/marketplace",
+    deprecated_on: "2026-10-16",
+    sunset_on: "2027-04-30",
+}];
+
+const DEPRECATION: HeaderName = HeaderName::from_static("deprecation");
+const SUNSET: HeaderName = HeaderName::from_static("sunset");
+
+/// Mount version-relative routes under the current prefix and every legacy alias.
+/// Responses on an alias carry `Deprecation` (RFC 9745), `Sunset` (RFC 8594) and a
+/// `Link` to the same path under the current prefix.
+pub fn mount(routes: Router) -> Router {
+    LEGACY_ALIASES.iter().fold(
+        Router::new().nest(CURRENT_PREFIX, routes.clone()),
+        |router, alias| {
+            router.nest(
+                alias.prefix,
+                routes.clone().layer(middleware::from_fn_with_state(*alias, deprecation_headers)),
+            )
+        },
+    )
+}
+
+async fn deprecation_headers(State(alias): State<LegacyAlias>, request: Request, next: Next) -> Response {
+    // Inside the nested router the path no longer includes the alias prefix
+    let successor = format!("<{}{}>; rel=\"successor-version\"", CURRENT_PREFIX, request.uri().path());
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+
+    if let Some(deprecated_on) = parse_date(alias.deprecated_on) {
+        let value = format!("@{}", deprecated_on.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc().timestamp());
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.insert(DEPRECATION, value);
+        }
+    }
+    if let Some(sunset_on) = parse_date(alias.sunset_on) {
+        if let Ok(value) = HeaderValue::from_str(&sunset_on.format("%a, %d %b %Y 00:00:00 GMT").to_string()) {
+            headers.insert(SUNSET, value);
+        }
+    }
+    if let Ok(value) = HeaderValue::from_str(&successor) {
+        headers.append(header::LINK, value);
+    }
+
+    response
+}
+
+fn parse_date(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+}
@@ -0,0 +1,449 @@
+use crate::auth::AuthUser;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::marketplace::cart::fund_pending;
+use crate::marketplace::payments::{payment_provider_from_config, PaymentProvider, ProviderRefundRequest};
+use crate::marketplace::MarketplaceService;
+use crate::models::marketplace::{
+    Checkout, CheckoutStatus, MarketplaceTransaction, TransactionRefund, Wallet, WalletEntry,
+    WalletOperationStatus, WalletTopup, WalletTopupRequest, WalletWithdrawal,
+};
+use bigdecimal::{BigDecimal, Zero};
+use sqlx::{PgConnection, PgPool, Postgres, Row, Transaction};
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub const MIN_TOPUP: i64 = 1;
+pub const MAX_TOPUP: i64 = 1000;
+
+// Wallets are for paying on the marketplace, not for storing money
+const MAX_BALANCE: i64 = 5000;
+
+// Ledger history returned with the balance
+const HISTORY_LIMIT: i64 = 50;
+
+/// `payment_method` that pays a checkout from the buyer's wallet
+pub const WALLET_PAYMENT_METHOD: &str = "wallet";
+
+// Ledger accounts
+const WALLET_ACCOUNT: &str = "wallet";
+const FUNDING_ACCOUNT: &str = "funding";
+const ESCROW_ACCOUNT: &str = "escrow";
+
+pub fn is_wallet_payment(payment_method: &str) -> bool {
+    payment_method.trim().eq_ignore_ascii_case(WALLET_PAYMENT_METHOD)
+}
+
+/// In-app wallet. Users top up from a card or UPI, pay checkouts from the balance with the
+/// items going into escrow at once, get refunds of wallet purchases back into the wallet,
+/// and withdraw to the payments that funded it.
+///
+/// Every movement is a balanced journal in a double-entry ledger, so the wallet balances,
+/// the money held in escrow and the money that came in from outside always reconcile.
+pub struct WalletService {
+    pool: PgPool,
+    provider: Arc<dyn PaymentProvider>,
+}
+
+impl WalletService {
+    pub fn new(pool: PgPool) -> Result<Self, AppError> {
+        Ok(Self {
+            pool,
+            provider: payment_provider_from_config(Config::get())?,
+        })
+    }
+
+    pub fn with_provider(pool: PgPool, provider: Arc<dyn PaymentProvider>) -> Self {
+        Self { pool, provider }
+    }
+
+    pub async fn get_wallet(&self, user_id: &str) -> Result<Wallet, AppError> {
+        let mut conn = self.pool.acquire().await?;
+        let balance = wallet_balance(&mut *conn, user_id).await?;
+        let withdrawable = withdrawable_topups(&mut *conn, user_id).await?.min(balance.clone());
+
+        let entries = sqlx::query_as::<_, WalletEntry>(
+            r#"
+            SELECT id, journal_id, amount, entry_type, reference_id, created_at
+            FROM marketplace_wallet_entries
+            WHERE user_id = $1 AND account = 'wallet'
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#
+        )
+        .bind(user_id)
+        .bind(HISTORY_LIMIT)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        Ok(Wallet { balance, withdrawable, entries })
+    }
+
+    /// Start a top-up; the money is added once the payment is confirmed
+    pub async fn create_topup(&self, auth_user: &AuthUser, request: WalletTopupRequest) -> Result<WalletTopup, AppError> {
+        let user_id = &auth_user.0.auth0_id;
+        let amount = request.amount.round(2);
+
+        let mut conn = self.pool.acquire().await?;
+        let balance = wallet_balance(&mut *conn, user_id).await?;
+        if balance + &amount > BigDecimal::from(MAX_BALANCE) {
+            return Err(AppError::Conflict(format!("A wallet can hold at most {}", MAX_BALANCE)));
+        }
+
+        let topup = sqlx::query_as::<_, WalletTopup>(
+            r#"
+            INSERT INTO marketplace_wallet_topups (id, user_id, amount, funding_method, status, created_at)
+            VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP)
+            RETURNING *
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(&amount)
+        .bind(request.funding_method)
+        .bind(WalletOperationStatus::Pending)
+        .fetch_one(&mut *conn)
+        .await?;
+
+        Ok(topup)
+    }
+
+    /// Admin confirmation that a top-up payment was captured; credits the wallet
+    pub async fn confirm_topup(&self, auth_user: &AuthUser, topup_id: Uuid, payment_id: &str) -> Result<WalletTopup, AppError> {
+        let marketplace = MarketplaceService::new(self.pool.clone());
+        marketplace.require_admin(auth_user).await?;
+
+        let mut tx = self.pool.begin().await?;
+        let topup = complete_topup(&mut tx, topup_id, payment_id).await?;
+        tx.commit().await?;
+
+        notify_topped_up(&marketplace, &topup).await;
+        Ok(topup)
+    }
+
+    /// Send money back to the top-ups that funded the wallet, most recent first. Each part is
+    /// taken off the balance before the provider is called and put back if the call fails.
+    pub async fn withdraw(&self, auth_user: &AuthUser, amount: &BigDecimal) -> Result<Vec<WalletWithdrawal>, AppError> {
+        let user_id = &auth_user.0.auth0_id;
+        let amount = amount.round(2);
+
+        let mut tx = self.pool.begin().await?;
+        lock_wallet(&mut tx, user_id).await?;
+
+        let balance = wallet_balance(&mut *tx, user_id).await?;
+        if amount > balance {
+            return Err(AppError::Conflict(format!("Your wallet balance is {}", balance)));
+        }
+
+        let topups = sqlx::query_as::<_, WalletTopup>(
+            r#"
+            SELECT * FROM marketplace_wallet_topups
+            WHERE user_id = $1 AND status = 'completed' AND withdrawn_amount < amount
+            ORDER BY completed_at DESC
+            FOR UPDATE
+            "#
+        )
+        .bind(user_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let withdrawable: BigDecimal = topups.iter().map(|topup| &topup.amount - &topup.withdrawn_amount).sum();
+        if amount > withdrawable {
+            return Err(AppError::Conflict(format!(
+                "At most {} can be sent back to the payments that funded your wallet",
+                withdrawable
+            )));
+        }
+
+        let mut remaining = amount;
+        let mut withdrawals = Vec::new();
+        for topup in topups {
+            if remaining.is_zero() {
+                break;
+            }
+            let part = (&topup.amount - &topup.withdrawn_amount).min(remaining.clone());
+            remaining -= &part;
+
+            let withdrawal = sqlx::query_as::<_, WalletWithdrawal>(
+                r#"
+                INSERT INTO marketplace_wallet_withdrawals (id, user_id, topup_id, amount, status, created_at)
+                VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP)
+                RETURNING *
+                "#
+            )
+            .bind(Uuid::new_v4())
+            .bind(user_id)
+            .bind(topup.id)
+            .bind(&part)
+            .bind(WalletOperationStatus::Pending)
+            .fetch_one(&mut *tx)
+            .await?;
+
+            sqlx::query("UPDATE marketplace_wallet_topups SET withdrawn_amount = withdrawn_amount + $2 WHERE id = $1")
+                .bind(topup.id)
+                .bind(&part)
+                .execute(&mut *tx)
+                .await?;
+
+            post_journal(&mut tx, "withdrawal", withdrawal.id, &[
+                (WALLET_ACCOUNT, Some(user_id.as_str()), -part.clone()),
+                (FUNDING_ACCOUNT, None, part),
+            ]).await?;
+
+            withdrawals.push((withdrawal, topup.payment_id.unwrap_or_default()));
+        }
+
+        tx.commit().await?;
+
+        let mut sent = Vec::with_capacity(withdrawals.len());
+        for (withdrawal, payment_id) in withdrawals {
+            sent.push(self.send_withdrawal(withdrawal, &payment_id).await?);
+        }
+        Ok(sent)
+    }
+
+    /// Pay a checkout being created in `tx` from the buyer's wallet and put its items into
+    /// escrow, refreshing `transactions` to their funded state
+    pub(crate) async fn pay_checkout(
+        tx: &mut Transaction<'_, Postgres>,
+        checkout: &Checkout,
+        transactions: &mut Vec<MarketplaceTransaction>,
+    ) -> Result<Checkout, AppError> {
+        let buyer_id = &checkout.buyer_id;
+        let due = &checkout.total_amount - &checkout.discount_amount;
+
+        lock_wallet(tx, buyer_id).await?;
+        let balance = wallet_balance(&mut **tx, buyer_id).await?;
+        if due > balance {
+            return Err(AppError::Conflict(format!(
+                "Your wallet balance of {} does not cover {}",
+                balance, due
+            )));
+        }
+
+        let journal_id = post_journal(tx, "payment", checkout.id, &[
+            (WALLET_ACCOUNT, Some(buyer_id.as_str()), -due.clone()),
+            (ESCROW_ACCOUNT, None, due),
+        ]).await?;
+        let payment_id = format!("{}:{}", WALLET_PAYMENT_METHOD, journal_id);
+
+        let checkout = sqlx::query_as::<_, Checkout>(
+            r#"
+            UPDATE marketplace_checkouts
+            SET status = $2, payment_id = $3, paid_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(checkout.id)
+        .bind(CheckoutStatus::Paid)
+        .bind(&payment_id)
+        .fetch_one(&mut **tx)
+        .await?;
+
+        fund_pending(tx, checkout.id, Some(&payment_id), buyer_id).await?;
+
+        *transactions = sqlx::query_as::<_, MarketplaceTransaction>(
+            "SELECT * FROM marketplace_transactions WHERE checkout_id = $1 ORDER BY created_at"
+        )
+        .bind(checkout.id)
+        .fetch_all(&mut **tx)
+        .await?;
+
+        Ok(checkout)
+    }
+
+    /// Put a refund of a wallet purchase back into the buyer's wallet
+    pub(crate) async fn credit_refund(
+        tx: &mut Transaction<'_, Postgres>,
+        transaction: &MarketplaceTransaction,
+        refund: &TransactionRefund,
+    ) -> Result<(), AppError> {
+        post_journal(tx, "refund", refund.id, &[
+            (ESCROW_ACCOUNT, None, -refund.amount.clone()),
+            (WALLET_ACCOUNT, Some(transaction.buyer_id.as_str()), refund.amount.clone()),
+        ]).await?;
+        Ok(())
+    }
+
+    async fn send_withdrawal(&self, withdrawal: WalletWithdrawal, payment_id: &str) -> Result<WalletWithdrawal, AppError> {
+        let request = ProviderRefundRequest::new(payment_id, &withdrawal.amount, "Wallet withdrawal", withdrawal.id.to_string());
+
+        let provider_refund_id = match self.provider.refund(&request).await {
+            Ok(id) => id,
+            Err(e) => {
+                tracing::warn!(withdrawal_id = %withdrawal.id, error = %e, "wallet withdrawal failed");
+                return self.fail_withdrawal(&withdrawal, &e.to_string()).await;
+            }
+        };
+
+        let withdrawal = sqlx::query_as::<_, WalletWithdrawal>(
+            r#"
+            UPDATE marketplace_wallet_withdrawals
+            SET status = $2, provider_refund_id = $3, completed_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(withdrawal.id)
+        .bind(WalletOperationStatus::Completed)
+        .bind(&provider_refund_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(withdrawal)
+    }
+
+    // Give the money back to the wallet and the top-up
+    async fn fail_withdrawal(&self, withdrawal: &WalletWithdrawal, reason: &str) -> Result<WalletWithdrawal, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let failed = sqlx::query_as::<_, WalletWithdrawal>(
+            r#"
+            UPDATE marketplace_wallet_withdrawals
+            SET status = $2, failure_reason = $3, completed_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            RETURNING *
+            "#
+        )
+        .bind(withdrawal.id)
+        .bind(WalletOperationStatus::Failed)
+        .bind(reason)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE marketplace_wallet_topups SET withdrawn_amount = withdrawn_amount - $2 WHERE id = $1")
+            .bind(withdrawal.topup_id)
+            .bind(&withdrawal.amount)
+            .execute(&mut *tx)
+            .await?;
+
+        post_journal(&mut tx, "withdrawal_reversal", withdrawal.id, &[
+            (FUNDING_ACCOUNT, None, -withdrawal.amount.clone()),
+            (WALLET_ACCOUNT, Some(withdrawal.user_id.as_str()), withdrawal.amount.clone()),
+        ]).await?;
+
+        tx.commit().await?;
+        Ok(failed)
+    }
+}
+
+/// Mark a pending top-up as paid and credit the wallet
+pub(crate) async fn complete_topup(
+    tx: &mut Transaction<'_, Postgres>,
+    topup_id: Uuid,
+    payment_id: &str,
+) -> Result<WalletTopup, AppError> {
+    let topup = sqlx::query_as::<_, WalletTopup>(
+        r#"
+        UPDATE marketplace_wallet_topups
+        SET status = $2, payment_id = $3, completed_at = CURRENT_TIMESTAMP
+        WHERE id = $1 AND status = $4
+        RETURNING *
+        "#
+    )
+    .bind(topup_id)
+    .bind(WalletOperationStatus::Completed)
+    .bind(payment_id)
+    .bind(WalletOperationStatus::Pending)
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    let Some(topup) = topup else {
+        let exists = sqlx::query("SELECT 1 FROM marketplace_wallet_topups WHERE id = $1")
+            .bind(topup_id)
+            .fetch_optional(&mut **tx)
+            .await?;
+        return Err(match exists {
+            Some(_) => AppError::Conflict("Top-up is no longer awaiting payment".to_string()),
+            None => AppError::NotFound("Top-up not found".to_string()),
+        });
+    };
+
+    post_journal(tx, "topup", topup.id, &[
+        (FUNDING_ACCOUNT, None, -topup.amount.clone()),
+        (WALLET_ACCOUNT, Some(topup.user_id.as_str()), topup.amount.clone()),
+    ]).await?;
+
+    Ok(topup)
+}
+
+pub(crate) async fn notify_topped_up(marketplace: &MarketplaceService, topup: &WalletTopup) {
+    marketplace.create_notification(
+        &topup.user_id,
+        "wallet_topped_up",
+        "Wallet topped up",
+        &format!("{} was added to your wallet", topup.amount),
+        None,
+        None,
+    ).await
+}
+
+// Serialize balance-changing work per wallet until the transaction ends
+async fn lock_wallet(tx: &mut Transaction<'_, Postgres>, user_id: &str) -> Result<(), AppError> {
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+        .bind(format!("wallet:{}", user_id))
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+async fn wallet_balance(conn: &mut PgConnection, user_id: &str) -> Result<BigDecimal, AppError> {
+    let balance = sqlx::query(
+        "SELECT COALESCE(SUM(amount), 0)::numeric as balance FROM marketplace_wallet_entries WHERE user_id = $1 AND account = 'wallet'"
+    )
+    .bind(user_id)
+    .fetch_one(&mut *conn)
+    .await?
+    .get("balance");
+    Ok(balance)
+}
+
+async fn withdrawable_topups(conn: &mut PgConnection, user_id: &str) -> Result<BigDecimal, AppError> {
+    let withdrawable = sqlx::query(
+        r#"
+        SELECT COALESCE(SUM(amount - withdrawn_amount), 0)::numeric as withdrawable
+        FROM marketplace_wallet_topups
+        WHERE user_id = $1 AND status = 'completed'
+        "#
+    )
+    .bind(user_id)
+    .fetch_one(&mut *conn)
+    .await?
+    .get("withdrawable");
+    Ok(withdrawable)
+}
+
+// Record one journal; its legs of (account, wallet owner, amount) must sum to zero
+async fn post_journal(
+    tx: &mut Transaction<'_, Postgres>,
+    entry_type: &str,
+    reference_id: Uuid,
+    legs: &[(&str, Option<&str>, BigDecimal)],
+) -> Result<Uuid, AppError> {
+    let total: BigDecimal = legs.iter().map(|(_, _, amount)| amount.clone()).sum();
+    if !total.is_zero() {
+        return Err(AppError::InternalError(format!("Unbalanced {} journal", entry_type)));
+    }
+
+    let journal_id = Uuid::new_v4();
+    for (account, user_id, amount) in legs {
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_wallet_entries (id, journal_id, account, user_id, amount, entry_type, reference_id, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, CURRENT_TIMESTAMP)
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(journal_id)
+        .bind(account)
+        .bind(user_id)
+        .bind(amount)
+        .bind(entry_type)
+        .bind(reference_id)
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    Ok(journal_id)
+}
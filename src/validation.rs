@@ -0,0 +1,72 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+// A single failed field with a human-readable message
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// Request types that check their fields before reaching the services
+pub trait Validate {
+    fn validate(&self) -> Result<(), Vec<FieldError>>;
+}
+
+/// Collects field errors so a request reports every problem at once
+#[derive(Debug, Default)]
+pub struct Validator {
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn check(&mut self, valid: bool, field: &str, message: impl Into<String>) -> &mut Self {
+        if !valid {
+            self.errors.push(FieldError {
+                field: field.to_string(),
+                message: message.into(),
+            });
+        }
+        self
+    }
+
+    /// Trimmed length in characters must be within `min..=max`
+    pub fn length(&mut self, field: &str, value: &str, min: usize, max: usize) -> &mut Self {
+        let length = value.trim().chars().count();
+        if min > 0 && length < min {
+            self.check(false, field, format!("must be at least {} characters", min))
+        } else {
+            self.check(length <= max, field, format!("must be at most {} characters", max))
+        }
+    }
+
+    pub fn optional_length(&mut self, field: &str, value: Option<&str>, min: usize, max: usize) -> &mut Self {
+        match value {
+            Some(value) => self.length(field, value, min, max),
+            None => self,
+        }
+    }
+
+    pub fn url(&mut self, field: &str, value: Option<&str>) -> &mut Self {
+        match value {
+            Some(url) => self.check(
+                (url.starts_with("https://") || url.starts_with("http://")) && url.len() <= 2048,
+                field,
+                "must be an http(s) URL of at most 2048 characters",
+            ),
+            None => self,
+        }
+    }
+
+    pub fn finish(&mut self) -> Result<(), Vec<FieldError>> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+}